@@ -0,0 +1,346 @@
+use crate::state::{
+    read_config, read_epoch_kpis, read_state, remove_oldest_epoch_kpis, store_config,
+    store_epoch_kpis, store_state, Config, State,
+};
+
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    InitResponse, Querier, StdError, StdResult, Storage,
+};
+use moneymarket::analytics::{
+    ConfigResponse, EpochKpis, EpochKpisResponse, HandleMsg, InitMsg, QueryMsg,
+};
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    msg: InitMsg,
+) -> StdResult<InitResponse> {
+    assert_max_history(msg.max_history)?;
+
+    store_config(
+        &mut deps.storage,
+        &Config {
+            owner_addr: deps.api.canonical_address(&msg.owner_addr)?,
+            overseer_contract: deps.api.canonical_address(&msg.overseer_contract)?,
+            max_history: msg.max_history,
+        },
+    )?;
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            num_epochs: 0,
+            last_height: 0,
+        },
+    )?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> HandleResult {
+    match msg {
+        HandleMsg::UpdateConfig {
+            owner_addr,
+            overseer_contract,
+            max_history,
+        } => update_config(deps, env, owner_addr, overseer_contract, max_history),
+        HandleMsg::RecordEpoch { height, kpis } => record_epoch(deps, env, height, kpis),
+    }
+}
+
+pub fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner_addr: Option<HumanAddr>,
+    overseer_contract: Option<HumanAddr>,
+    max_history: Option<u64>,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if let Some(owner_addr) = owner_addr {
+        config.owner_addr = deps.api.canonical_address(&owner_addr)?;
+    }
+
+    if let Some(overseer_contract) = overseer_contract {
+        config.overseer_contract = deps.api.canonical_address(&overseer_contract)?;
+    }
+
+    if let Some(max_history) = max_history {
+        assert_max_history(max_history)?;
+        config.max_history = max_history;
+
+        // a shorter history drops the oldest epochs right away
+        let mut state: State = read_state(&deps.storage)?;
+        prune_history(&mut deps.storage, &config, &mut state)?;
+        store_state(&mut deps.storage, &state)?;
+    }
+
+    store_config(&mut deps.storage, &config)?;
+    Ok(HandleResponse::default())
+}
+
+/// Stores the indicators of the epoch executed at `height`,
+/// dropping the oldest epoch once the history is full
+pub fn record_epoch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    height: u64,
+    kpis: EpochKpis,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.overseer_contract {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+    if height <= state.last_height {
+        return Err(StdError::generic_err(format!(
+            "Epoch already recorded; last recorded height: {}",
+            state.last_height
+        )));
+    }
+
+    store_epoch_kpis(&mut deps.storage, height, &kpis)?;
+    state.num_epochs += 1;
+    state.last_height = height;
+    prune_history(&mut deps.storage, &config, &mut state)?;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "record_epoch"),
+            log("height", height),
+            log("deposit_rate", kpis.deposit_rate),
+            log("utilization_ratio", kpis.utilization_ratio),
+            log("liquidation_volume", kpis.liquidation_volume),
+            log("new_bad_debt", kpis.new_bad_debt),
+        ],
+        data: None,
+    })
+}
+
+fn prune_history<S: Storage>(storage: &mut S, config: &Config, state: &mut State) -> StdResult<()> {
+    if state.num_epochs > config.max_history {
+        remove_oldest_epoch_kpis(storage, state.num_epochs - config.max_history)?;
+        state.num_epochs = config.max_history;
+    }
+
+    Ok(())
+}
+
+fn assert_max_history(max_history: u64) -> StdResult<()> {
+    if max_history == 0 {
+        return Err(StdError::generic_err("max_history must be greater than 0"));
+    }
+
+    Ok(())
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::EpochKpis {
+            start_height,
+            end_height,
+            limit,
+        } => to_binary(&query_epoch_kpis(deps, start_height, end_height, limit)?),
+    }
+}
+
+fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    Ok(ConfigResponse {
+        owner_addr: deps.api.human_address(&config.owner_addr)?,
+        overseer_contract: deps.api.human_address(&config.overseer_contract)?,
+        max_history: config.max_history,
+    })
+}
+
+fn query_epoch_kpis<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_height: Option<u64>,
+    end_height: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EpochKpisResponse> {
+    Ok(EpochKpisResponse {
+        epochs: read_epoch_kpis(&deps.storage, start_height, end_height, limit)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_bignumber::{Decimal256, Uint256};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::StdError;
+    use moneymarket::analytics::EpochKpisResponseElem;
+
+    fn mock_kpis(liquidation_volume: u128) -> EpochKpis {
+        EpochKpis {
+            deposit_rate: Decimal256::permille(1),
+            utilization_ratio: Decimal256::percent(50),
+            liquidation_volume: Uint256::from(liquidation_volume),
+            new_bad_debt: Uint256::zero(),
+        }
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner_addr: HumanAddr::from("owner0000"),
+            overseer_contract: HumanAddr::from("overseer"),
+            max_history: 0,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let res = init(&mut deps, env.clone(), msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "max_history must be greater than 0")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        let msg = InitMsg {
+            owner_addr: HumanAddr::from("owner0000"),
+            overseer_contract: HumanAddr::from("overseer"),
+            max_history: 3,
+        };
+
+        // we can just call .unwrap() to assert this was a success
+        let res = init(&mut deps, env, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let value = query_config(&deps).unwrap();
+        assert_eq!(
+            value,
+            ConfigResponse {
+                owner_addr: HumanAddr::from("owner0000"),
+                overseer_contract: HumanAddr::from("overseer"),
+                max_history: 3,
+            }
+        );
+
+        let value = query_epoch_kpis(&deps, None, None, None).unwrap();
+        assert_eq!(value.epochs, vec![]);
+    }
+
+    #[test]
+    fn record_epoch() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner_addr: HumanAddr::from("owner0000"),
+            overseer_contract: HumanAddr::from("overseer"),
+            max_history: 3,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        // Unauthorized err
+        let env = mock_env("addr0000", &[]);
+        let msg = HandleMsg::RecordEpoch {
+            height: 100,
+            kpis: mock_kpis(0),
+        };
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let env = mock_env("overseer", &[]);
+        for height in &[100u64, 200, 300, 400] {
+            let msg = HandleMsg::RecordEpoch {
+                height: *height,
+                kpis: mock_kpis(*height as u128),
+            };
+            let res = handle(&mut deps, env.clone(), msg).unwrap();
+            assert_eq!(res.log[0], log("action", "record_epoch"));
+        }
+
+        // the same epoch cannot be recorded twice
+        let msg = HandleMsg::RecordEpoch {
+            height: 400,
+            kpis: mock_kpis(0),
+        };
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Epoch already recorded; last recorded height: 400")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        // the oldest epoch was dropped
+        let value = query_epoch_kpis(&deps, None, None, None).unwrap();
+        assert_eq!(
+            value.epochs,
+            vec![
+                EpochKpisResponseElem {
+                    height: 200,
+                    kpis: mock_kpis(200),
+                },
+                EpochKpisResponseElem {
+                    height: 300,
+                    kpis: mock_kpis(300),
+                },
+                EpochKpisResponseElem {
+                    height: 400,
+                    kpis: mock_kpis(400),
+                },
+            ]
+        );
+
+        // range queries include both ends
+        let value = query_epoch_kpis(&deps, Some(250), Some(400), None).unwrap();
+        assert_eq!(
+            value.epochs.iter().map(|v| v.height).collect::<Vec<u64>>(),
+            vec![300, 400]
+        );
+        let value = query_epoch_kpis(&deps, Some(200), Some(300), Some(1)).unwrap();
+        assert_eq!(
+            value.epochs.iter().map(|v| v.height).collect::<Vec<u64>>(),
+            vec![200]
+        );
+        let value = query_epoch_kpis(&deps, None, Some(u64::MAX), None).unwrap();
+        assert_eq!(value.epochs.len(), 3);
+
+        // shrinking the history drops the oldest epochs
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner_addr: None,
+            overseer_contract: None,
+            max_history: Some(1),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let value = query_epoch_kpis(&deps, None, None, None).unwrap();
+        assert_eq!(
+            value.epochs,
+            vec![EpochKpisResponseElem {
+                height: 400,
+                kpis: mock_kpis(400),
+            }]
+        );
+        assert_eq!(read_state(&deps.storage).unwrap().num_epochs, 1);
+    }
+}
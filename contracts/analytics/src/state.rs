@@ -0,0 +1,103 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, Order, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
+use moneymarket::analytics::{EpochKpis, EpochKpisResponseElem};
+
+static KEY_CONFIG: &[u8] = b"config";
+static KEY_STATE: &[u8] = b"state";
+
+static PREFIX_EPOCH_KPIS: &[u8] = b"epoch_kpis";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner_addr: CanonicalAddr,
+    pub overseer_contract: CanonicalAddr,
+    pub max_history: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    /// number of epochs stored
+    pub num_epochs: u64,
+    pub last_height: u64,
+}
+
+pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+pub fn store_state<S: Storage>(storage: &mut S, state: &State) -> StdResult<()> {
+    singleton(storage, KEY_STATE).save(state)
+}
+
+pub fn read_state<S: Storage>(storage: &S) -> StdResult<State> {
+    singleton_read(storage, KEY_STATE).load()
+}
+
+pub fn store_epoch_kpis<S: Storage>(
+    storage: &mut S,
+    height: u64,
+    kpis: &EpochKpis,
+) -> StdResult<()> {
+    let mut epoch_kpis_bucket: Bucket<S, EpochKpis> = Bucket::new(PREFIX_EPOCH_KPIS, storage);
+    epoch_kpis_bucket.save(&height.to_be_bytes(), kpis)
+}
+
+/// Removes the `count` oldest epochs
+pub fn remove_oldest_epoch_kpis<S: Storage>(storage: &mut S, count: u64) -> StdResult<()> {
+    let heights: Vec<Vec<u8>> = {
+        let epoch_kpis_bucket: ReadonlyBucket<S, EpochKpis> =
+            ReadonlyBucket::new(PREFIX_EPOCH_KPIS, storage);
+        epoch_kpis_bucket
+            .range(None, None, Order::Ascending)
+            .take(count as usize)
+            .map(|elem| Ok(elem?.0))
+            .collect::<StdResult<Vec<Vec<u8>>>>()?
+    };
+
+    let mut epoch_kpis_bucket: Bucket<S, EpochKpis> = Bucket::new(PREFIX_EPOCH_KPIS, storage);
+    for height in heights {
+        epoch_kpis_bucket.remove(&height);
+    }
+
+    Ok(())
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 100;
+const DEFAULT_LIMIT: u32 = 30;
+pub fn read_epoch_kpis<S: Storage>(
+    storage: &S,
+    start_height: Option<u64>,
+    end_height: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<EpochKpisResponseElem>> {
+    let epoch_kpis_bucket: ReadonlyBucket<S, EpochKpis> =
+        ReadonlyBucket::new(PREFIX_EPOCH_KPIS, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_height.map(|height| height.to_be_bytes().to_vec());
+    let end = end_height
+        .and_then(|height| height.checked_add(1))
+        .map(|height| height.to_be_bytes().to_vec());
+
+    epoch_kpis_bucket
+        .range(start.as_deref(), end.as_deref(), Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut height_bytes = [0u8; 8];
+            height_bytes.copy_from_slice(&k);
+            Ok(EpochKpisResponseElem {
+                height: u64::from_be_bytes(height_bytes),
+                kpis: v,
+            })
+        })
+        .collect()
+}
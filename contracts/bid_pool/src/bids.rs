@@ -0,0 +1,277 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+
+use crate::pool::query_pool_bids;
+use crate::state::{read_config, read_strategy, store_strategy, BidSlotConfig, Config};
+
+use moneymarket::bid_pool::{BidSlot, HandleMsg, StrategyResponse};
+use moneymarket::liquidation::HandleMsg as LiquidationHandleMsg;
+use moneymarket::overseer::HandleMsg as OverseerHandleMsg;
+use moneymarket::querier::{deduct_tax, query_balance, query_price, query_token_balance};
+use moneymarket::router::{AssetInfo, Cw20HookMsg as RouterCw20HookMsg, SwapOperation};
+
+const MAX_SLOTS: usize = 10;
+
+pub fn update_strategy<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    slots: Vec<BidSlot>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if slots.len() > MAX_SLOTS {
+        return Err(StdError::generic_err(format!(
+            "Strategy cannot have more than {} bid slots",
+            MAX_SLOTS
+        )));
+    }
+
+    let mut total_ratio = Decimal256::zero();
+    let mut slot_configs: Vec<BidSlotConfig> = vec![];
+    for slot in slots.iter() {
+        if slot.ratio.is_zero() {
+            return Err(StdError::generic_err(
+                "Bid slot ratio must be greater than 0",
+            ));
+        }
+
+        let collateral_token = deps.api.canonical_address(&slot.collateral_token)?;
+        if slot_configs
+            .iter()
+            .any(|v| v.collateral_token == collateral_token)
+        {
+            return Err(StdError::generic_err(format!(
+                "Duplicate bid slot for {}",
+                slot.collateral_token
+            )));
+        }
+
+        total_ratio += slot.ratio;
+        slot_configs.push(BidSlotConfig {
+            collateral_token,
+            premium_rate: slot.premium_rate,
+            ratio: slot.ratio,
+        });
+    }
+
+    if total_ratio > Decimal256::one() {
+        return Err(StdError::generic_err(format!(
+            "Bid slot ratios cannot exceed 1 in total: {}",
+            total_ratio
+        )));
+    }
+
+    // held collaterals are valued through the strategy slots
+    for slot in read_strategy(&deps.storage)? {
+        if slot_configs
+            .iter()
+            .any(|v| v.collateral_token == slot.collateral_token)
+        {
+            continue;
+        }
+
+        let collateral_token = deps.api.human_address(&slot.collateral_token)?;
+        if !query_token_balance(deps, &collateral_token, &env.contract.address)?.is_zero() {
+            return Err(StdError::generic_err(format!(
+                "Cannot drop {} from the strategy while the pool holds it",
+                collateral_token
+            )));
+        }
+    }
+
+    store_strategy(&mut deps.storage, &slot_configs)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_strategy"),
+            log("slots", slot_configs.len()),
+        ],
+        data: None,
+    })
+}
+
+/// Retracts every bid of the pool, the stable is placed
+/// again following the strategy in `PlaceBids`
+pub fn roll_bids<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for bid in query_pool_bids(deps, &config)?.bids {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: liquidation_contract.clone(),
+            send: vec![],
+            msg: to_binary(&LiquidationHandleMsg::RetractBid {
+                collateral_token: bid.collateral_token,
+                amount: None,
+            })?,
+        }));
+    }
+
+    let retracted_bids = messages.len();
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address,
+        send: vec![],
+        msg: to_binary(&HandleMsg::PlaceBids {})?,
+    }));
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "roll_bids"),
+            log("retracted_bids", retracted_bids),
+        ],
+        data: None,
+    })
+}
+
+pub fn place_bids<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    if env.message.sender != env.contract.address {
+        return Err(StdError::unauthorized());
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+    let idle_amount = query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut bid_amount = Uint256::zero();
+    for slot in read_strategy(&deps.storage)? {
+        let amount = idle_amount * slot.ratio;
+        if amount.is_zero() {
+            continue;
+        }
+
+        bid_amount += amount;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: liquidation_contract.clone(),
+            send: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.to_string(),
+                    amount: amount.into(),
+                },
+            )?],
+            msg: to_binary(&LiquidationHandleMsg::SubmitBid {
+                collateral_token: deps.api.human_address(&slot.collateral_token)?,
+                premium_rate: slot.premium_rate,
+            })?,
+        }));
+    }
+
+    Ok(HandleResponse {
+        log: vec![
+            log("action", "place_bids"),
+            log("bids", messages.len()),
+            log("bid_amount", bid_amount),
+        ],
+        messages,
+        data: None,
+    })
+}
+
+/// The collaterals bought by the pool bids are sent to the pool
+pub fn liquidate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&config.overseer_contract)?,
+            send: vec![],
+            msg: to_binary(&OverseerHandleMsg::LiquidateCollateral {
+                borrower: borrower.clone(),
+            })?,
+        })],
+        log: vec![log("action", "liquidate"), log("borrower", borrower)],
+        data: None,
+    })
+}
+
+pub fn sell_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let amount = query_token_balance(deps, &collateral_token, &env.contract.address)?;
+    if amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "No {} held by the pool",
+            collateral_token
+        )));
+    }
+
+    let price = query_price(
+        deps,
+        &deps.api.human_address(&config.oracle_contract)?,
+        collateral_token.to_string(),
+        config.stable_denom.to_string(),
+        None,
+    )?;
+    let minimum_receive = amount * price.rate * (Decimal256::one() - config.max_slippage);
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: collateral_token.clone(),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Send {
+                contract: deps.api.human_address(&config.swap_router)?,
+                amount: amount.into(),
+                msg: Some(to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                    operations: vec![SwapOperation::TerraSwap {
+                        offer_asset_info: AssetInfo::Token {
+                            contract_addr: collateral_token.clone(),
+                        },
+                        ask_asset_info: AssetInfo::NativeToken {
+                            denom: config.stable_denom,
+                        },
+                    }],
+                    minimum_receive: Some(minimum_receive.into()),
+                    to: None,
+                })?),
+            })?,
+        })],
+        log: vec![
+            log("action", "sell_collateral"),
+            log("collateral_token", collateral_token),
+            log("amount", amount),
+            log("minimum_receive", minimum_receive),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_strategy<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<StrategyResponse> {
+    let slots = read_strategy(&deps.storage)?
+        .into_iter()
+        .map(|slot| {
+            Ok(BidSlot {
+                collateral_token: deps.api.human_address(&slot.collateral_token)?,
+                premium_rate: slot.premium_rate,
+                ratio: slot.ratio,
+            })
+        })
+        .collect::<StdResult<Vec<BidSlot>>>()?;
+
+    Ok(StrategyResponse { slots })
+}
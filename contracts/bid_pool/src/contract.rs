@@ -0,0 +1,125 @@
+use crate::bids::{
+    liquidate, place_bids, query_strategy, roll_bids, sell_collateral, update_strategy,
+};
+use crate::pool::{deposit, query_pool, query_shares, withdraw};
+use crate::state::{read_config, store_config, Config};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    to_binary, Api, Binary, Env, Extern, HandleResponse, HandleResult, HumanAddr, InitResponse,
+    Querier, StdError, StdResult, Storage,
+};
+use moneymarket::bid_pool::{ConfigResponse, HandleMsg, InitMsg, QueryMsg};
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: InitMsg,
+) -> StdResult<InitResponse> {
+    assert_max_slippage(msg.max_slippage)?;
+
+    store_config(
+        &mut deps.storage,
+        &Config {
+            contract_addr: deps.api.canonical_address(&env.contract.address)?,
+            owner: deps.api.canonical_address(&msg.owner)?,
+            liquidation_contract: deps.api.canonical_address(&msg.liquidation_contract)?,
+            overseer_contract: deps.api.canonical_address(&msg.overseer_contract)?,
+            oracle_contract: deps.api.canonical_address(&msg.oracle_contract)?,
+            swap_router: deps.api.canonical_address(&msg.swap_router)?,
+            stable_denom: msg.stable_denom,
+            max_slippage: msg.max_slippage,
+        },
+    )?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> HandleResult {
+    match msg {
+        HandleMsg::UpdateConfig {
+            owner,
+            swap_router,
+            max_slippage,
+        } => update_config(deps, env, owner, swap_router, max_slippage),
+        HandleMsg::UpdateStrategy { slots } => update_strategy(deps, env, slots),
+        HandleMsg::Deposit {} => deposit(deps, env),
+        HandleMsg::Withdraw { shares } => withdraw(deps, env, shares),
+        HandleMsg::RollBids {} => roll_bids(deps, env),
+        HandleMsg::Liquidate { borrower } => liquidate(deps, env, borrower),
+        HandleMsg::SellCollateral { collateral_token } => {
+            sell_collateral(deps, env, collateral_token)
+        }
+        HandleMsg::PlaceBids {} => place_bids(deps, env),
+    }
+}
+
+pub fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: Option<HumanAddr>,
+    swap_router: Option<HumanAddr>,
+    max_slippage: Option<Decimal256>,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.canonical_address(&owner)?;
+    }
+
+    if let Some(swap_router) = swap_router {
+        config.swap_router = deps.api.canonical_address(&swap_router)?;
+    }
+
+    if let Some(max_slippage) = max_slippage {
+        assert_max_slippage(max_slippage)?;
+        config.max_slippage = max_slippage;
+    }
+
+    store_config(&mut deps.storage, &config)?;
+    Ok(HandleResponse::default())
+}
+
+fn assert_max_slippage(max_slippage: Decimal256) -> StdResult<()> {
+    if max_slippage > Decimal256::one() {
+        return Err(StdError::generic_err(
+            "max_slippage must be less than or equal to 1",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Strategy {} => to_binary(&query_strategy(deps)?),
+        QueryMsg::Pool {} => to_binary(&query_pool(deps)?),
+        QueryMsg::Shares { depositor } => to_binary(&query_shares(deps, depositor)?),
+    }
+}
+
+pub fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.human_address(&config.owner)?,
+        liquidation_contract: deps.api.human_address(&config.liquidation_contract)?,
+        overseer_contract: deps.api.human_address(&config.overseer_contract)?,
+        oracle_contract: deps.api.human_address(&config.oracle_contract)?,
+        swap_router: deps.api.human_address(&config.swap_router)?,
+        stable_denom: config.stable_denom,
+        max_slippage: config.max_slippage,
+    })
+}
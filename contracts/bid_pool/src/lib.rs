@@ -0,0 +1,10 @@
+mod bids;
+pub mod contract;
+mod pool;
+pub mod state;
+
+#[cfg(test)]
+mod testing;
+
+#[cfg(all(target_arch = "wasm32", not(feature = "library")))]
+cosmwasm_std::create_entry_points!(contract);
@@ -0,0 +1,215 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, QueryRequest, StdError, StdResult, Storage, WasmQuery,
+};
+
+use crate::state::{
+    read_config, read_shares, read_strategy, read_total_shares, store_shares, store_total_shares,
+    Config,
+};
+
+use moneymarket::bid_pool::{PoolResponse, SharesResponse};
+use moneymarket::liquidation::{BidsResponse, QueryMsg as LiquidationQueryMsg};
+use moneymarket::querier::{deduct_tax, query_balance, query_price, query_token_balance};
+
+/// Bids queried from the liquidation contract, covering
+/// every slot of the strategy and the bids left by older ones
+const MAX_BIDS: u32 = 30;
+
+pub fn deposit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let deposit_amount: Uint256 = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    if deposit_amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "Deposit amount must be greater than 0 {}",
+            config.stable_denom
+        )));
+    }
+
+    // the deposit is already part of the idle stable
+    let total_shares = read_total_shares(&deps.storage)?;
+    let pool_value = compute_pool_value(deps, &config)?.total_value - deposit_amount;
+    let shares = if total_shares.is_zero() {
+        deposit_amount
+    } else if pool_value.is_zero() {
+        return Err(StdError::generic_err(
+            "Cannot deposit into a pool without value",
+        ));
+    } else {
+        deposit_amount * Decimal256::from_uint256(total_shares)
+            / Decimal256::from_uint256(pool_value)
+    };
+
+    let depositor_raw = deps.api.canonical_address(&env.message.sender)?;
+    let depositor_shares = read_shares(&deps.storage, &depositor_raw)? + shares;
+    store_shares(&mut deps.storage, &depositor_raw, &depositor_shares)?;
+    store_total_shares(&mut deps.storage, &(total_shares + shares))?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "deposit"),
+            log("depositor", env.message.sender),
+            log("deposit_amount", deposit_amount),
+            log("shares", shares),
+        ],
+        data: None,
+    })
+}
+
+pub fn withdraw<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    shares: Uint256,
+) -> HandleResult {
+    if shares.is_zero() {
+        return Err(StdError::generic_err(
+            "Withdraw shares must be greater than 0",
+        ));
+    }
+
+    let depositor_raw = deps.api.canonical_address(&env.message.sender)?;
+    let depositor_shares = read_shares(&deps.storage, &depositor_raw)?;
+    if shares > depositor_shares {
+        return Err(StdError::generic_err(format!(
+            "Withdraw shares cannot exceed owned shares: {}",
+            depositor_shares
+        )));
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let total_shares = read_total_shares(&deps.storage)?;
+    let pool = compute_pool_value(deps, &config)?;
+    let withdraw_amount = shares * Decimal256::from_uint256(pool.total_value)
+        / Decimal256::from_uint256(total_shares);
+
+    // stable placed in bids comes back at the next roll
+    if withdraw_amount > pool.idle_amount {
+        return Err(StdError::generic_err(format!(
+            "Not enough idle stable in the pool: {}",
+            pool.idle_amount
+        )));
+    }
+
+    store_shares(
+        &mut deps.storage,
+        &depositor_raw,
+        &(depositor_shares - shares),
+    )?;
+    store_total_shares(&mut deps.storage, &(total_shares - shares))?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: env.message.sender.clone(),
+            amount: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom,
+                    amount: withdraw_amount.into(),
+                },
+            )?],
+        })],
+        log: vec![
+            log("action", "withdraw"),
+            log("depositor", env.message.sender),
+            log("shares", shares),
+            log("withdraw_amount", withdraw_amount),
+        ],
+        data: None,
+    })
+}
+
+/// Bids the pool placed on the liquidation contract
+pub(crate) fn query_pool_bids<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+) -> StdResult<BidsResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: deps.api.human_address(&config.liquidation_contract)?,
+        msg: to_binary(&LiquidationQueryMsg::BidsByUser {
+            bidder: deps.api.human_address(&config.contract_addr)?,
+            start_after: None,
+            limit: Some(MAX_BIDS),
+        })?,
+    }))
+}
+
+/// Idle stable, stable placed in bids and the oracle
+/// value of the collaterals bought by the bids
+pub(crate) fn compute_pool_value<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+) -> StdResult<PoolResponse> {
+    let contract_addr = deps.api.human_address(&config.contract_addr)?;
+    let idle_amount = query_balance(deps, &contract_addr, config.stable_denom.to_string())?;
+    let bid_amount = query_pool_bids(deps, config)?
+        .bids
+        .iter()
+        .fold(Uint256::zero(), |sum, bid| sum + bid.amount);
+
+    let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
+    let mut collateral_value = Uint256::zero();
+    for slot in read_strategy(&deps.storage)? {
+        let collateral_token = deps.api.human_address(&slot.collateral_token)?;
+        let balance = query_token_balance(deps, &collateral_token, &contract_addr)?;
+        if balance.is_zero() {
+            continue;
+        }
+
+        let price = query_price(
+            deps,
+            &oracle_contract,
+            collateral_token.to_string(),
+            config.stable_denom.to_string(),
+            None,
+        )?;
+        collateral_value += balance * price.rate;
+    }
+
+    Ok(PoolResponse {
+        total_shares: read_total_shares(&deps.storage)?,
+        idle_amount,
+        bid_amount,
+        collateral_value,
+        total_value: idle_amount + bid_amount + collateral_value,
+    })
+}
+
+pub fn query_pool<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<PoolResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    compute_pool_value(deps, &config)
+}
+
+pub fn query_shares<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    depositor: HumanAddr,
+) -> StdResult<SharesResponse> {
+    let shares = read_shares(&deps.storage, &deps.api.canonical_address(&depositor)?)?;
+    let value = if shares.is_zero() {
+        Uint256::zero()
+    } else {
+        let pool = query_pool(deps)?;
+        shares * Decimal256::from_uint256(pool.total_value)
+            / Decimal256::from_uint256(pool.total_shares)
+    };
+
+    Ok(SharesResponse {
+        depositor,
+        shares,
+        value,
+    })
+}
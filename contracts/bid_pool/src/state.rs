@@ -0,0 +1,80 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
+
+static KEY_CONFIG: &[u8] = b"config";
+static KEY_STRATEGY: &[u8] = b"strategy";
+static KEY_TOTAL_SHARES: &[u8] = b"total_shares";
+
+static PREFIX_SHARES: &[u8] = b"shares";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub contract_addr: CanonicalAddr,
+    pub owner: CanonicalAddr,
+    pub liquidation_contract: CanonicalAddr,
+    pub overseer_contract: CanonicalAddr,
+    pub oracle_contract: CanonicalAddr,
+    pub swap_router: CanonicalAddr,
+    pub stable_denom: String,
+    pub max_slippage: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidSlotConfig {
+    pub collateral_token: CanonicalAddr,
+    pub premium_rate: Decimal256,
+    pub ratio: Decimal256,
+}
+
+pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+pub fn store_strategy<S: Storage>(storage: &mut S, slots: &[BidSlotConfig]) -> StdResult<()> {
+    singleton(storage, KEY_STRATEGY).save(&slots.to_vec())
+}
+
+pub fn read_strategy<S: Storage>(storage: &S) -> StdResult<Vec<BidSlotConfig>> {
+    Ok(singleton_read(storage, KEY_STRATEGY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_total_shares<S: Storage>(storage: &mut S, total_shares: &Uint256) -> StdResult<()> {
+    singleton(storage, KEY_TOTAL_SHARES).save(total_shares)
+}
+
+pub fn read_total_shares<S: Storage>(storage: &S) -> StdResult<Uint256> {
+    Ok(singleton_read(storage, KEY_TOTAL_SHARES)
+        .may_load()?
+        .unwrap_or_else(Uint256::zero))
+}
+
+pub fn store_shares<S: Storage>(
+    storage: &mut S,
+    depositor: &CanonicalAddr,
+    shares: &Uint256,
+) -> StdResult<()> {
+    let mut shares_bucket: Bucket<S, Uint256> = Bucket::new(PREFIX_SHARES, storage);
+    if shares.is_zero() {
+        shares_bucket.remove(depositor.as_slice());
+        Ok(())
+    } else {
+        shares_bucket.save(depositor.as_slice(), shares)
+    }
+}
+
+pub fn read_shares<S: Storage>(storage: &S, depositor: &CanonicalAddr) -> StdResult<Uint256> {
+    let shares_bucket: ReadonlyBucket<S, Uint256> = ReadonlyBucket::new(PREFIX_SHARES, storage);
+    Ok(shares_bucket
+        .may_load(depositor.as_slice())?
+        .unwrap_or_else(Uint256::zero))
+}
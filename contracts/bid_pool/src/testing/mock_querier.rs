@@ -0,0 +1,226 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Api, CanonicalAddr, Coin, Decimal, Extern, HumanAddr,
+    Querier, QuerierResult, QueryRequest, SystemError, Uint128, WasmQuery,
+};
+use cosmwasm_storage::to_length_prefixed;
+use std::collections::HashMap;
+
+use moneymarket::liquidation::{BidResponse, BidsResponse};
+use moneymarket::oracle::PriceResponse;
+use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Query oracle price to oracle contract
+    Price { base: String, quote: String },
+    /// Query bids of the pool to liquidation contract
+    BidsByUser {
+        bidder: HumanAddr,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+}
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
+/// this uses our CustomQuerier.
+pub fn mock_dependencies(
+    canonical_length: usize,
+    contract_balance: &[Coin],
+) -> Extern<MockStorage, MockApi, WasmMockQuerier> {
+    let contract_addr = HumanAddr::from(MOCK_CONTRACT_ADDR);
+    let custom_querier: WasmMockQuerier = WasmMockQuerier::new(
+        MockQuerier::new(&[(&contract_addr, contract_balance)]),
+        canonical_length,
+    );
+
+    Extern {
+        storage: MockStorage::default(),
+        api: MockApi::new(canonical_length),
+        querier: custom_querier,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<TerraQueryWrapper>,
+    tax_querier: TaxQuerier,
+    oracle_prices: HashMap<(String, String), Decimal256>,
+    // (collateral_token, amount, premium_rate) of the pool bids
+    bids: Vec<(HumanAddr, Uint256, Decimal256)>,
+    // collateral token balances of the pool
+    token_balances: HashMap<HumanAddr, Uint128>,
+    canonical_length: usize,
+}
+
+#[derive(Clone, Default)]
+pub struct TaxQuerier {
+    rate: Decimal,
+    // this lets us iterate over all pairs that match the first string
+    caps: HashMap<String, Uint128>,
+}
+
+impl TaxQuerier {
+    pub fn new(rate: Decimal, caps: &[(&String, &Uint128)]) -> Self {
+        let mut caps_map: HashMap<String, Uint128> = HashMap::new();
+        for (denom, cap) in caps.iter() {
+            caps_map.insert(denom.to_string(), **cap);
+        }
+
+        TaxQuerier {
+            rate,
+            caps: caps_map,
+        }
+    }
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        // MockQuerier doesn't support Custom, so we ignore it completely here
+        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
+        match &request {
+            QueryRequest::Custom(TerraQueryWrapper { route, query_data }) => {
+                if &TerraRoute::Treasury == route {
+                    match query_data {
+                        TerraQuery::TaxRate {} => {
+                            let res = TaxRateResponse {
+                                rate: self.tax_querier.rate,
+                            };
+                            Ok(to_binary(&res))
+                        }
+                        TerraQuery::TaxCap { denom } => {
+                            let cap = self
+                                .tax_querier
+                                .caps
+                                .get(denom)
+                                .copied()
+                                .unwrap_or_default();
+                            let res = TaxCapResponse { cap };
+                            Ok(to_binary(&res))
+                        }
+                        _ => panic!("DO NOT ENTER HERE"),
+                    }
+                } else {
+                    panic!("DO NOT ENTER HERE")
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: _,
+                msg,
+            }) => match from_binary(msg).unwrap() {
+                QueryMsg::Price { base, quote } => match self.oracle_prices.get(&(base, quote)) {
+                    Some(v) => Ok(to_binary(&PriceResponse {
+                        rate: *v,
+                        last_updated_base: 0u64,
+                        last_updated_quote: 0u64,
+                    })),
+                    None => Err(SystemError::InvalidRequest {
+                        error: "No oracle price exists".to_string(),
+                        request: msg.as_slice().into(),
+                    }),
+                },
+                QueryMsg::BidsByUser {
+                    bidder,
+                    start_after: _,
+                    limit: _,
+                } => Ok(to_binary(&BidsResponse {
+                    bids: self
+                        .bids
+                        .iter()
+                        .map(|(collateral_token, amount, premium_rate)| BidResponse {
+                            collateral_token: collateral_token.clone(),
+                            bidder: bidder.clone(),
+                            amount: *amount,
+                            premium_rate: *premium_rate,
+                        })
+                        .collect(),
+                })),
+            },
+            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
+                let key: &[u8] = key.as_slice();
+                let prefix_balance = to_length_prefixed(b"balance").to_vec();
+                if key[..prefix_balance.len()].to_vec() != prefix_balance {
+                    panic!("DO NOT ENTER HERE")
+                }
+
+                let api: MockApi = MockApi::new(self.canonical_length);
+                let address_raw = CanonicalAddr::from(&key[prefix_balance.len()..]);
+                if api.human_address(&address_raw).unwrap() != HumanAddr::from(MOCK_CONTRACT_ADDR) {
+                    panic!("DO NOT ENTER HERE")
+                }
+
+                let balance = self
+                    .token_balances
+                    .get(contract_addr)
+                    .copied()
+                    .unwrap_or_else(Uint128::zero);
+                Ok(to_binary(&to_binary(&balance).unwrap()))
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<TerraQueryWrapper>, canonical_length: usize) -> Self {
+        WasmMockQuerier {
+            base,
+            tax_querier: TaxQuerier::default(),
+            oracle_prices: HashMap::new(),
+            bids: vec![],
+            token_balances: HashMap::new(),
+            canonical_length,
+        }
+    }
+
+    // configure the tax mock querier
+    pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
+        self.tax_querier = TaxQuerier::new(rate, caps);
+    }
+
+    pub fn with_balance(&mut self, balance: &[Coin]) {
+        self.base
+            .update_balance(HumanAddr::from(MOCK_CONTRACT_ADDR), balance.to_vec());
+    }
+
+    pub fn with_oracle_price(&mut self, oracle_prices: &[(&(String, String), &Decimal256)]) {
+        self.oracle_prices = oracle_prices
+            .iter()
+            .map(|(pair, price)| ((*pair).clone(), **price))
+            .collect();
+    }
+
+    pub fn with_bids(&mut self, bids: &[(&HumanAddr, &Uint256, &Decimal256)]) {
+        self.bids = bids
+            .iter()
+            .map(|(collateral_token, amount, premium_rate)| {
+                ((*collateral_token).clone(), **amount, **premium_rate)
+            })
+            .collect();
+    }
+
+    pub fn with_token_balances(&mut self, token_balances: &[(&HumanAddr, &Uint128)]) {
+        self.token_balances = token_balances
+            .iter()
+            .map(|(token, balance)| ((*token).clone(), **balance))
+            .collect();
+    }
+}
@@ -0,0 +1,488 @@
+use crate::contract::{handle, init, query};
+use crate::testing::mock_querier::mock_dependencies;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, log, to_binary, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError, Uint128,
+    WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+use moneymarket::bid_pool::{
+    BidSlot, ConfigResponse, HandleMsg, InitMsg, PoolResponse, QueryMsg, SharesResponse,
+    StrategyResponse,
+};
+use moneymarket::liquidation::HandleMsg as LiquidationHandleMsg;
+use moneymarket::overseer::HandleMsg as OverseerHandleMsg;
+use moneymarket::router::{AssetInfo, Cw20HookMsg as RouterCw20HookMsg, SwapOperation};
+
+fn init_msg() -> InitMsg {
+    InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        liquidation_contract: HumanAddr::from("liquidation0000"),
+        overseer_contract: HumanAddr::from("overseer0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        swap_router: HumanAddr::from("router0000"),
+        stable_denom: "uusd".to_string(),
+        max_slippage: Decimal256::percent(1),
+    }
+}
+
+fn strategy() -> Vec<BidSlot> {
+    vec![
+        BidSlot {
+            collateral_token: HumanAddr::from("bluna0000"),
+            premium_rate: Decimal256::percent(2),
+            ratio: Decimal256::percent(50),
+        },
+        BidSlot {
+            collateral_token: HumanAddr::from("beth0000"),
+            premium_rate: Decimal256::percent(3),
+            ratio: Decimal256::percent(30),
+        },
+    ]
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let mut msg = init_msg();
+    msg.max_slippage = Decimal256::percent(101);
+    match init(&mut deps, mock_env("addr0000", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "max_slippage must be less than or equal to 1")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = init(&mut deps, mock_env("addr0000", &[]), init_msg()).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let value: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        value,
+        ConfigResponse {
+            owner: HumanAddr::from("owner0000"),
+            liquidation_contract: HumanAddr::from("liquidation0000"),
+            overseer_contract: HumanAddr::from("overseer0000"),
+            oracle_contract: HumanAddr::from("oracle0000"),
+            swap_router: HumanAddr::from("router0000"),
+            stable_denom: "uusd".to_string(),
+            max_slippage: Decimal256::percent(1),
+        }
+    );
+
+    // only the owner can update the config
+    let msg = HandleMsg::UpdateConfig {
+        owner: Some(HumanAddr::from("owner0001")),
+        swap_router: None,
+        max_slippage: Some(Decimal256::percent(2)),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+    let value: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(value.owner, HumanAddr::from("owner0001"));
+    assert_eq!(value.max_slippage, Decimal256::percent(2));
+}
+
+#[test]
+fn update_strategy() {
+    let mut deps = mock_dependencies(20, &[]);
+    let _res = init(&mut deps, mock_env("addr0000", &[]), init_msg()).unwrap();
+
+    let msg = HandleMsg::UpdateStrategy { slots: strategy() };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut slots = strategy();
+    slots[1].ratio = Decimal256::percent(60);
+    match handle(
+        &mut deps,
+        mock_env("owner0000", &[]),
+        HandleMsg::UpdateStrategy { slots },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Bid slot ratios cannot exceed 1 in total: 1.1")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut slots = strategy();
+    slots[1].collateral_token = HumanAddr::from("bluna0000");
+    match handle(
+        &mut deps,
+        mock_env("owner0000", &[]),
+        HandleMsg::UpdateStrategy { slots },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Duplicate bid slot for bluna0000")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![log("action", "update_strategy"), log("slots", 2)]
+    );
+
+    let res: StrategyResponse = from_binary(&query(&deps, QueryMsg::Strategy {}).unwrap()).unwrap();
+    assert_eq!(res, StrategyResponse { slots: strategy() });
+
+    // held collaterals cannot be dropped from the strategy
+    deps.querier
+        .with_token_balances(&[(&HumanAddr::from("bluna0000"), &Uint128::from(100u128))]);
+    let msg = HandleMsg::UpdateStrategy {
+        slots: vec![strategy()[1].clone()],
+    };
+    match handle(&mut deps, mock_env("owner0000", &[]), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Cannot drop bluna0000 from the strategy while the pool holds it"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_token_balances(&[]);
+    let _res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+}
+
+#[test]
+fn deposit_and_withdraw() {
+    let mut deps = mock_dependencies(20, &[]);
+    let _res = init(&mut deps, mock_env("addr0000", &[]), init_msg()).unwrap();
+    let _res = handle(
+        &mut deps,
+        mock_env("owner0000", &[]),
+        HandleMsg::UpdateStrategy { slots: strategy() },
+    )
+    .unwrap();
+
+    match handle(&mut deps, mock_env("addr0000", &[]), HandleMsg::Deposit {}) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Deposit amount must be greater than 0 uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the first deposit mints shares one for one
+    deps.querier.with_balance(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1000000u128),
+    }]);
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = handle(&mut deps, env, HandleMsg::Deposit {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deposit"),
+            log("depositor", "addr0000"),
+            log("deposit_amount", 1000000),
+            log("shares", 1000000),
+        ]
+    );
+
+    // half the stable is in a bid, which bought 100 bluna
+    // pool value = 500000 + 500000 + 100 * 1000 = 1100000
+    deps.querier.with_bids(&[(
+        &HumanAddr::from("bluna0000"),
+        &Uint256::from(500000u64),
+        &Decimal256::percent(2),
+    )]);
+    deps.querier
+        .with_token_balances(&[(&HumanAddr::from("bluna0000"), &Uint128::from(100u128))]);
+    deps.querier.with_oracle_price(&[(
+        &("bluna0000".to_string(), "uusd".to_string()),
+        &Decimal256::from_uint256(1000u64),
+    )]);
+
+    // shares = 550000 * 1000000 / 1100000
+    deps.querier.with_balance(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1050000u128),
+    }]);
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(550000u128),
+        }],
+    );
+    let res = handle(&mut deps, env, HandleMsg::Deposit {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deposit"),
+            log("depositor", "addr0001"),
+            log("deposit_amount", 550000),
+            log("shares", 500000),
+        ]
+    );
+
+    let res: PoolResponse = from_binary(&query(&deps, QueryMsg::Pool {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        PoolResponse {
+            total_shares: Uint256::from(1500000u64),
+            idle_amount: Uint256::from(1050000u64),
+            bid_amount: Uint256::from(500000u64),
+            collateral_value: Uint256::from(100000u64),
+            total_value: Uint256::from(1650000u64),
+        }
+    );
+
+    let res: SharesResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Shares {
+                depositor: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SharesResponse {
+            depositor: HumanAddr::from("addr0000"),
+            shares: Uint256::from(1000000u64),
+            value: Uint256::from(1100000u64),
+        }
+    );
+
+    match handle(
+        &mut deps,
+        mock_env("addr0001", &[]),
+        HandleMsg::Withdraw {
+            shares: Uint256::from(500001u64),
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Withdraw shares cannot exceed owned shares: 500000")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // only the idle stable can be withdrawn
+    match handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::Withdraw {
+            shares: Uint256::from(1000000u64),
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Not enough idle stable in the pool: 1050000")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::Withdraw {
+            shares: Uint256::from(500000u64),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(544554u128), // 550000 / 1.01
+            }],
+        })]
+    );
+
+    let res: PoolResponse = from_binary(&query(&deps, QueryMsg::Pool {}).unwrap()).unwrap();
+    assert_eq!(res.total_shares, Uint256::from(1000000u64));
+}
+
+#[test]
+fn roll_bids() {
+    let mut deps = mock_dependencies(20, &[]);
+    let _res = init(&mut deps, mock_env("addr0000", &[]), init_msg()).unwrap();
+    let _res = handle(
+        &mut deps,
+        mock_env("owner0000", &[]),
+        HandleMsg::UpdateStrategy { slots: strategy() },
+    )
+    .unwrap();
+
+    deps.querier.with_bids(&[(
+        &HumanAddr::from("bluna0000"),
+        &Uint256::from(500000u64),
+        &Decimal256::percent(1),
+    )]);
+    let res = handle(&mut deps, mock_env("addr0000", &[]), HandleMsg::RollBids {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("liquidation0000"),
+                send: vec![],
+                msg: to_binary(&LiquidationHandleMsg::RetractBid {
+                    collateral_token: HumanAddr::from("bluna0000"),
+                    amount: None,
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&HandleMsg::PlaceBids {}).unwrap(),
+            }),
+        ]
+    );
+
+    match handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::PlaceBids {},
+    ) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the retracted stable is placed following the strategy
+    deps.querier.with_bids(&[]);
+    deps.querier.with_balance(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1000000u128),
+    }]);
+    let res = handle(
+        &mut deps,
+        mock_env(MOCK_CONTRACT_ADDR, &[]),
+        HandleMsg::PlaceBids {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("liquidation0000"),
+                send: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(500000u128),
+                }],
+                msg: to_binary(&LiquidationHandleMsg::SubmitBid {
+                    collateral_token: HumanAddr::from("bluna0000"),
+                    premium_rate: Decimal256::percent(2),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("liquidation0000"),
+                send: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(300000u128),
+                }],
+                msg: to_binary(&LiquidationHandleMsg::SubmitBid {
+                    collateral_token: HumanAddr::from("beth0000"),
+                    premium_rate: Decimal256::percent(3),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "place_bids"),
+            log("bids", 2),
+            log("bid_amount", 800000),
+        ]
+    );
+}
+
+#[test]
+fn liquidate_and_sell_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+    let _res = init(&mut deps, mock_env("addr0000", &[]), init_msg()).unwrap();
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::Liquidate {
+            borrower: HumanAddr::from("borrower0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("overseer0000"),
+            send: vec![],
+            msg: to_binary(&OverseerHandleMsg::LiquidateCollateral {
+                borrower: HumanAddr::from("borrower0000"),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let msg = HandleMsg::SellCollateral {
+        collateral_token: HumanAddr::from("bluna0000"),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "No bluna0000 held by the pool")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // minimum_receive = 100 * 1000 * 0.99
+    deps.querier
+        .with_token_balances(&[(&HumanAddr::from("bluna0000"), &Uint128::from(100u128))]);
+    deps.querier.with_oracle_price(&[(
+        &("bluna0000".to_string(), "uusd".to_string()),
+        &Decimal256::from_uint256(1000u64),
+    )]);
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("bluna0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Send {
+                contract: HumanAddr::from("router0000"),
+                amount: Uint128::from(100u128),
+                msg: Some(
+                    to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                        operations: vec![SwapOperation::TerraSwap {
+                            offer_asset_info: AssetInfo::Token {
+                                contract_addr: HumanAddr::from("bluna0000"),
+                            },
+                            ask_asset_info: AssetInfo::NativeToken {
+                                denom: "uusd".to_string(),
+                            },
+                        }],
+                        minimum_receive: Some(Uint128::from(99000u128)),
+                        to: None,
+                    })
+                    .unwrap()
+                ),
+            })
+            .unwrap(),
+        })]
+    );
+}
@@ -1,22 +1,27 @@
-use cosmwasm_bignumber::Uint256;
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
     log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
     HumanAddr, Querier, QueryRequest, StdError, StdResult, Storage, Uint128, WasmMsg, WasmQuery,
 };
 
 use crate::external::handle::{RewardContractHandleMsg, RewardContractQueryMsg};
-use crate::state::{read_config, BETHAccruedRewardsResponse, Config};
+use crate::state::{
+    read_config, read_reward_claimer, remove_reward_claimer, store_reward_claimer,
+    BETHAccruedRewardsResponse, Config, RewardClaimerConfig,
+};
 
-use moneymarket::custody::HandleMsg;
+use moneymarket::custody::{HandleMsg, RewardClaimer, RewardClaimerResponse};
 use moneymarket::querier::{deduct_tax, query_all_balances, query_balance};
 use terra_cosmwasm::{create_swap_msg, TerraMsgWrapper};
 
-/// Request withdraw reward operation to
-/// reward contract and execute `distribute_hook`
+/// Request withdraw reward operation to the reward
+/// contract, or the reward claimer when one is set,
+/// and execute `distribute_hook`
 /// Executor: overseer
 pub fn distribute_rewards<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    reward_recipients: Option<Vec<(HumanAddr, Decimal256)>>,
 ) -> HandleResult<TerraMsgWrapper> {
     let threshold = Uint128(1000000);
     let config: Config = read_config(&deps.storage)?;
@@ -27,22 +32,34 @@ pub fn distribute_rewards<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    let reward_contract = deps.api.human_address(&config.reward_contract)?;
-
-    let previous_reward_balance =
-        get_accrued_rewards(deps, reward_contract.clone(), contract_addr.clone())?;
-    if previous_reward_balance < threshold {
-        return Ok(HandleResponse::default());
-    }
+    // the accrued rewards are only known to the reward contract
+    let claim_msg = match read_reward_claimer(&deps.storage)? {
+        Some(reward_claimer) => WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&reward_claimer.contract_addr)?,
+            send: vec![],
+            msg: reward_claimer.claim_msg,
+        },
+        None => {
+            let reward_contract = deps.api.human_address(&config.reward_contract)?;
+
+            let previous_reward_balance =
+                get_accrued_rewards(deps, reward_contract.clone(), contract_addr.clone())?;
+            if previous_reward_balance < threshold {
+                return Ok(HandleResponse::default());
+            }
+
+            WasmMsg::Execute {
+                contract_addr: reward_contract,
+                send: vec![],
+                msg: to_binary(&RewardContractHandleMsg::ClaimRewards { recipient: None })?,
+            }
+        }
+    };
 
     // Do not emit the event logs here
     Ok(HandleResponse {
         messages: vec![
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: reward_contract,
-                send: vec![],
-                msg: to_binary(&RewardContractHandleMsg::ClaimRewards { recipient: None })?,
-            }),
+            CosmosMsg::Wasm(claim_msg),
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: contract_addr.clone(),
                 send: vec![],
@@ -51,7 +68,7 @@ pub fn distribute_rewards<S: Storage, A: Api, Q: Querier>(
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr,
                 send: vec![],
-                msg: to_binary(&HandleMsg::DistributeHook {})?,
+                msg: to_binary(&HandleMsg::DistributeHook { reward_recipients })?,
             }),
         ],
         log: vec![],
@@ -59,11 +76,13 @@ pub fn distribute_rewards<S: Storage, A: Api, Q: Querier>(
     })
 }
 
-/// Apply swapped reward to global index
+/// Send swapped reward to the reward recipients by ratio,
+/// the last one getting the rounding remainder
 /// Executor: itself
 pub fn distribute_hook<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    reward_recipients: Option<Vec<(HumanAddr, Decimal256)>>,
 ) -> HandleResult<TerraMsgWrapper> {
     let contract_addr = env.contract.address;
     let config: Config = read_config(&deps.storage)?;
@@ -77,16 +96,31 @@ pub fn distribute_hook<S: Storage, A: Api, Q: Querier>(
     // = (0 + reward_amount) - 0 = reward_amount = balance
     let reward_amount: Uint256 =
         query_balance(deps, &contract_addr, config.stable_denom.to_string())?;
+    let reward_recipients =
+        reward_recipients.unwrap_or_else(|| vec![(overseer_contract, Decimal256::one())]);
+
     let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
-    if !reward_amount.is_zero() {
+    let mut remaining_amount = reward_amount;
+    for (i, (recipient, ratio)) in reward_recipients.iter().enumerate() {
+        let amount = if i + 1 == reward_recipients.len() {
+            remaining_amount
+        } else {
+            std::cmp::min(reward_amount * *ratio, remaining_amount)
+        };
+        remaining_amount = remaining_amount - amount;
+
+        if amount.is_zero() {
+            continue;
+        }
+
         messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: contract_addr,
-            to_address: overseer_contract,
+            from_address: contract_addr.clone(),
+            to_address: recipient.clone(),
             amount: vec![deduct_tax(
                 deps,
                 Coin {
-                    denom: config.stable_denom,
-                    amount: reward_amount.into(),
+                    denom: config.stable_denom.to_string(),
+                    amount: amount.into(),
                 },
             )?],
         }));
@@ -135,6 +169,60 @@ pub fn swap_to_stable_denom<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/// Claim the rewards through the external `reward_claimer`
+/// instead of the reward contract of the hub
+/// Executor: owner
+pub fn update_reward_claimer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    reward_claimer: Option<RewardClaimer>,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let claimer_log = match reward_claimer {
+        Some(reward_claimer) => {
+            store_reward_claimer(
+                &mut deps.storage,
+                &RewardClaimerConfig {
+                    contract_addr: deps.api.canonical_address(&reward_claimer.contract_addr)?,
+                    claim_msg: reward_claimer.claim_msg,
+                },
+            )?;
+            reward_claimer.contract_addr.to_string()
+        }
+        None => {
+            remove_reward_claimer(&mut deps.storage);
+            deps.api.human_address(&config.reward_contract)?.to_string()
+        }
+    };
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_reward_claimer"),
+            log("reward_claimer", claimer_log),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_reward_claimer<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<RewardClaimerResponse> {
+    Ok(RewardClaimerResponse {
+        reward_claimer: match read_reward_claimer(&deps.storage)? {
+            Some(v) => Some(RewardClaimer {
+                contract_addr: deps.api.human_address(&v.contract_addr)?,
+                claim_msg: v.claim_msg,
+            }),
+            None => None,
+        },
+    })
+}
+
 pub(crate) fn get_accrued_rewards<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     reward_contract_addr: HumanAddr,
@@ -0,0 +1,22 @@
+use cosmwasm_std::{Api, Extern, Querier, StdResult, Storage};
+
+use moneymarket::custody::HandleMsg;
+use moneymarket::protocol_mode::load_protocol_mode;
+
+/// Rejects the user operations the protocol mode does not allow,
+/// the overseer operations are checked by the overseer
+pub(crate) fn assert_protocol_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+    msg: &HandleMsg,
+) -> StdResult<()> {
+    match msg {
+        HandleMsg::Receive(_) => {
+            load_protocol_mode(deps, block_height, false)?.assert_deposits_allowed()
+        }
+        HandleMsg::WithdrawCollateral { .. } => {
+            load_protocol_mode(deps, block_height, false)?.assert_withdrawals_allowed()
+        }
+        _ => Ok(()),
+    }
+}
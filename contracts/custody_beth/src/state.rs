@@ -2,7 +2,9 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::Uint256;
-use cosmwasm_std::{Api, CanonicalAddr, Extern, Order, Querier, StdResult, Storage, Uint128};
+use cosmwasm_std::{
+    Api, Binary, CanonicalAddr, Extern, Order, Querier, StdResult, Storage, Uint128,
+};
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 use moneymarket::custody::{BAssetInfo, BorrowerResponse};
 
@@ -14,6 +16,8 @@ pub struct BETHAccruedRewardsResponse {
 
 const KEY_CONFIG: &[u8] = b"config";
 const PREFIX_BORROWER: &[u8] = b"borrower";
+const KEY_REWARD_CLAIMER: &[u8] = b"reward_claimer";
+const KEY_SHARE_ACCOUNTING: &[u8] = b"share_accounting";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -41,6 +45,50 @@ pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardClaimerConfig {
+    pub contract_addr: CanonicalAddr,
+    pub claim_msg: Binary,
+}
+
+pub fn store_reward_claimer<S: Storage>(
+    storage: &mut S,
+    data: &RewardClaimerConfig,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_REWARD_CLAIMER).save(data)
+}
+
+pub fn read_reward_claimer<S: Storage>(storage: &S) -> StdResult<Option<RewardClaimerConfig>> {
+    ReadonlySingleton::new(storage, KEY_REWARD_CLAIMER).may_load()
+}
+
+pub fn remove_reward_claimer<S: Storage>(storage: &mut S) {
+    Singleton::<S, RewardClaimerConfig>::new(storage, KEY_REWARD_CLAIMER).remove()
+}
+
+/// Set while the borrower balances are kept in shares of the
+/// collateral token balance of the custody at `contract_addr`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShareAccounting {
+    pub contract_addr: CanonicalAddr,
+    pub total_shares: Uint256,
+}
+
+pub fn store_share_accounting<S: Storage>(
+    storage: &mut S,
+    data: &ShareAccounting,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_SHARE_ACCOUNTING).save(data)
+}
+
+pub fn read_share_accounting<S: Storage>(storage: &S) -> StdResult<Option<ShareAccounting>> {
+    ReadonlySingleton::new(storage, KEY_SHARE_ACCOUNTING).may_load()
+}
+
+pub fn remove_share_accounting<S: Storage>(storage: &mut S) {
+    Singleton::<S, ShareAccounting>::new(storage, KEY_SHARE_ACCOUNTING).remove()
+}
+
 pub fn store_borrower_info<S: Storage>(
     storage: &mut S,
     borrower: &CanonicalAddr,
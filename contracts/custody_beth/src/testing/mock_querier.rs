@@ -170,7 +170,7 @@ impl WasmMockQuerier {
                             name: "mAPPL".to_string(),
                             symbol: "mAPPL".to_string(),
                             decimals: 6,
-                            total_supply: total_supply,
+                            total_supply,
                         })
                         .unwrap(),
                     ))
@@ -204,7 +204,7 @@ impl WasmMockQuerier {
             QueryRequest::Wasm(WasmQuery::Smart {
                 contract_addr: _,
                 msg,
-            }) => match from_binary(&msg).unwrap() {
+            }) => match from_binary(msg).unwrap() {
                 RewardContractQueryMsg::AccruedRewards { address: _ } => {
                     Ok(to_binary(&BETHAccruedRewardsResponse {
                         rewards: self.accrued_rewards.rewards,
@@ -219,7 +219,7 @@ impl WasmMockQuerier {
                             denom: denom.to_string(),
                         },
                     };
-                    return Ok(to_binary(&bank_res));
+                    Ok(to_binary(&bank_res))
                 } else {
                     let bank_res = BalanceResponse {
                         amount: Coin {
@@ -227,7 +227,7 @@ impl WasmMockQuerier {
                             denom: denom.to_string(),
                         },
                     };
-                    return Ok(to_binary(&bank_res));
+                    Ok(to_binary(&bank_res))
                 }
             }
             _ => self.base.handle_query(request),
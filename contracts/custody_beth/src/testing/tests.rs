@@ -1,7 +1,7 @@
 use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{
-    from_binary, log, to_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, HandleResponse, HumanAddr,
-    StdError, Uint128, WasmMsg,
+    from_binary, log, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, HandleResponse,
+    HumanAddr, StdError, Uint128, WasmMsg,
 };
 
 use crate::contract::{handle, init, query};
@@ -13,6 +13,7 @@ use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
 use cw20::{Cw20HandleMsg, Cw20ReceiveMsg};
 use moneymarket::custody::{
     BAssetInfo, BorrowerResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, QueryMsg,
+    RewardClaimer, RewardClaimerResponse, ShareAccountingResponse, ShareConversionResponse,
 };
 use moneymarket::liquidation::Cw20HookMsg as LiquidationCw20HookMsg;
 use terra_cosmwasm::create_swap_msg;
@@ -80,12 +81,30 @@ fn update_config() {
     let _res = init(&mut deps, env, msg).unwrap();
 
     let msg = HandleMsg::UpdateConfig {
-        owner: Some(HumanAddr::from("owner2")),
         liquidation_contract: Some(HumanAddr::from("liquidation2")),
     };
     let env = mock_env("owner", &[]);
     handle(&mut deps, env, msg.clone()).unwrap();
 
+    // propose a new owner
+    let env = mock_env("owner", &[]);
+    let propose_msg = HandleMsg::ProposeNewOwner {
+        new_owner: HumanAddr::from("owner2"),
+        expiry_height: 12355u64,
+    };
+    handle(&mut deps, env, propose_msg).unwrap();
+
+    // only the proposed owner can accept
+    let env = mock_env("owner", &[]);
+    match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner2", &[]);
+    let res = handle(&mut deps, env, HandleMsg::AcceptOwnership {}).unwrap();
+    assert_eq!(0, res.messages.len());
+
     let query_res = query(&deps, QueryMsg::Config {}).unwrap();
     let config_res: ConfigResponse = from_binary(&query_res).unwrap();
     assert_eq!(HumanAddr::from("owner2"), config_res.owner);
@@ -153,7 +172,7 @@ fn deposit_collateral() {
     match res2 {
         Err(StdError::GenericErr { msg, .. }) => assert_eq!(
             msg,
-            "Invalid request: \"deposit collateral\" message not included in request"
+            "[E9005] Invalid request: \"deposit collateral\" message not included in request"
         ),
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -265,7 +284,7 @@ fn withdraw_collateral() {
         Err(StdError::GenericErr { msg, .. }) => {
             assert_eq!(
                 msg,
-                "Withdraw amount cannot exceed the user's spendable amount: 100"
+                "[E3001] Withdraw amount cannot exceed the user's spendable amount: 100"
             )
         }
         _ => panic!("DO NOT ENTER HERE"),
@@ -415,9 +434,9 @@ fn lock_collateral() {
 
     assert_eq!(
         res2,
-        StdError::generic_err(format!(
-            "Lock amount cannot excceed the user's spendable amount: 100"
-        ))
+        StdError::generic_err(
+            "[E3002] Lock amount cannot exceed the user's spendable amount: 100".to_string()
+        )
     );
 
     let env = mock_env("overseer", &[]);
@@ -451,7 +470,7 @@ fn lock_collateral() {
         Err(StdError::GenericErr { msg, .. }) => {
             assert_eq!(
                 msg,
-                "Withdraw amount cannot exceed the user's spendable amount: 50"
+                "[E3001] Withdraw amount cannot exceed the user's spendable amount: 50"
             )
         }
         _ => panic!("DO NOT ENTER HERE"),
@@ -511,7 +530,7 @@ fn lock_collateral() {
     let res3 = handle(&mut deps, env3, msg3);
     match res3 {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Unlock amount cannot exceed locked amount: 50")
+            assert_eq!(msg, "[E3003] Unlock amount cannot exceed locked amount: 50")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -598,14 +617,18 @@ fn distribute_rewards() {
     let env = mock_env("addr0000", &[]);
     let _res = init(&mut deps, env.clone(), msg).unwrap();
 
-    let msg = HandleMsg::DistributeRewards {};
+    let msg = HandleMsg::DistributeRewards {
+        reward_recipients: None,
+    };
     let res = handle(&mut deps, env, msg.clone());
     match res {
         Err(StdError::Unauthorized { .. }) => {}
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    let msg = HandleMsg::DistributeRewards {};
+    let msg = HandleMsg::DistributeRewards {
+        reward_recipients: None,
+    };
     let env = mock_env("overseer", &[]);
     deps.querier.set_reward_balance(Uint128(10000000));
     deps.querier
@@ -632,7 +655,10 @@ fn distribute_rewards() {
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
                 send: vec![],
-                msg: to_binary(&HandleMsg::DistributeHook {}).unwrap(),
+                msg: to_binary(&HandleMsg::DistributeHook {
+                    reward_recipients: None,
+                })
+                .unwrap(),
             }),
         ]
     );
@@ -680,7 +706,9 @@ fn distribute_hook() {
     let _res = init(&mut deps, env.clone(), msg).unwrap();
 
     // Claimed rewards is 1000000uusd
-    let msg = HandleMsg::DistributeHook {};
+    let msg = HandleMsg::DistributeHook {
+        reward_recipients: None,
+    };
     let res = handle(&mut deps, env, msg.clone());
     match res {
         Err(StdError::Unauthorized { .. }) => {}
@@ -734,7 +762,9 @@ fn distribution_hook_zero_rewards() {
     let _res = init(&mut deps, env, msg).unwrap();
 
     // Claimed rewards is 1000000uusd
-    let msg = HandleMsg::DistributeHook {};
+    let msg = HandleMsg::DistributeHook {
+        reward_recipients: None,
+    };
     let env = mock_env(MOCK_CONTRACT_ADDR, &[]);
     let res = handle(&mut deps, env, msg).unwrap();
     assert_eq!(
@@ -888,7 +918,10 @@ fn liquidate_collateral() {
     let res = handle(&mut deps, env.clone(), msg);
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Liquidation amount cannot exceed locked amount: 50")
+            assert_eq!(
+                msg,
+                "[E3005] Liquidation amount cannot exceed locked amount: 50"
+            )
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -921,6 +954,7 @@ fn liquidate_collateral() {
                         liquidator: HumanAddr::from("liquidator"),
                         fee_address: Some(HumanAddr::from("overseer")),
                         repay_address: Some(HumanAddr::from("market")),
+                        borrower: Some(HumanAddr::from("addr0000")),
                     })
                     .unwrap()
                 ),
@@ -958,20 +992,26 @@ fn proper_distribute_rewards_with_no_rewards() {
     let env = mock_env("addr0000", &[]);
     let _res = init(&mut deps, env.clone(), msg).unwrap();
 
-    let msg = HandleMsg::DistributeRewards {};
+    let msg = HandleMsg::DistributeRewards {
+        reward_recipients: None,
+    };
     let res = handle(&mut deps, env, msg.clone());
     match res {
         Err(StdError::Unauthorized { .. }) => {}
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    let msg = HandleMsg::DistributeRewards {};
+    let msg = HandleMsg::DistributeRewards {
+        reward_recipients: None,
+    };
     let env = mock_env("overseer", &[]);
     let res = handle(&mut deps, env, msg).unwrap();
     // must return
     assert_eq!(res, HandleResponse::default());
 
-    let msg = HandleMsg::DistributeRewards {};
+    let msg = HandleMsg::DistributeRewards {
+        reward_recipients: None,
+    };
     let env = mock_env("overseer", &[]);
     deps.querier
         .set_accrued_rewards(BETHAccruedRewardsResponse {
@@ -981,7 +1021,9 @@ fn proper_distribute_rewards_with_no_rewards() {
     // must return
     assert_eq!(res, HandleResponse::default());
 
-    let msg = HandleMsg::DistributeRewards {};
+    let msg = HandleMsg::DistributeRewards {
+        reward_recipients: None,
+    };
     let env = mock_env("overseer", &[]);
 
     deps.querier
@@ -1008,8 +1050,562 @@ fn proper_distribute_rewards_with_no_rewards() {
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
                 send: vec![],
-                msg: to_binary(&HandleMsg::DistributeHook {}).unwrap(),
+                msg: to_binary(&HandleMsg::DistributeHook {
+                    reward_recipients: None,
+                })
+                .unwrap(),
             }),
         ]
     );
 }
+
+#[test]
+fn lock_collateral_for() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner"),
+        collateral_token: HumanAddr::from("beth"),
+        overseer_contract: HumanAddr::from("overseer"),
+        market_contract: HumanAddr::from("market"),
+        reward_contract: HumanAddr::from("reward"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        stable_denom: "uusd".to_string(),
+        basset_info: BAssetInfo {
+            name: "beth".to_string(),
+            symbol: "beth".to_string(),
+            decimals: 6,
+        },
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("sponsor"),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap()),
+    });
+    let env = mock_env("beth", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateralFor {
+        locker: HumanAddr::from("sponsor"),
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(60u64),
+    };
+    let res = handle(&mut deps, mock_env("sponsor", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("overseer", &[]),
+        HandleMsg::LockCollateralFor {
+            locker: HumanAddr::from("sponsor"),
+            borrower: HumanAddr::from("addr0000"),
+            amount: Uint256::from(101u64),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("[E3002] Lock amount cannot exceed the user's spendable amount: 100")
+    );
+
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "lock_collateral_for"),
+            log("locker", "sponsor"),
+            log("borrower", "addr0000"),
+            log("amount", "60"),
+        ]
+    );
+
+    // the collaterals moved to the borrower as locked balance
+    let query_res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: HumanAddr::from("addr0000"),
+            balance: Uint256::from(60u64),
+            spendable: Uint256::zero(),
+        }
+    );
+
+    let query_res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("sponsor"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: HumanAddr::from("sponsor"),
+            balance: Uint256::from(40u64),
+            spendable: Uint256::from(40u64),
+        }
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("overseer", &[]),
+        HandleMsg::UnlockCollateralFor {
+            locker: HumanAddr::from("sponsor"),
+            borrower: HumanAddr::from("addr0000"),
+            amount: Uint256::from(61u64),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("[E3003] Unlock amount cannot exceed locked amount: 60")
+    );
+
+    let msg = HandleMsg::UnlockCollateralFor {
+        locker: HumanAddr::from("sponsor"),
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(60u64),
+    };
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "unlock_collateral_for"),
+            log("locker", "sponsor"),
+            log("borrower", "addr0000"),
+            log("amount", "60"),
+        ]
+    );
+
+    let query_res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("sponsor"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: HumanAddr::from("sponsor"),
+            balance: Uint256::from(100u64),
+            spendable: Uint256::from(100u64),
+        }
+    );
+}
+
+#[test]
+fn transfer_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner"),
+        collateral_token: HumanAddr::from("beth"),
+        overseer_contract: HumanAddr::from("overseer"),
+        market_contract: HumanAddr::from("market"),
+        reward_contract: HumanAddr::from("reward"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        stable_denom: "uusd".to_string(),
+        basset_info: BAssetInfo {
+            name: "beth".to_string(),
+            symbol: "beth".to_string(),
+            decimals: 6,
+        },
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap()),
+    });
+    let _res = handle(&mut deps, mock_env("beth", &[]), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(60u64),
+    };
+    let _res = handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+
+    let msg = HandleMsg::TransferCollateral {
+        from: HumanAddr::from("addr0000"),
+        to: HumanAddr::from("addr0001"),
+        amount: Uint256::from(60u64),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // spendable collaterals cannot be transferred
+    let res = handle(
+        &mut deps,
+        mock_env("overseer", &[]),
+        HandleMsg::TransferCollateral {
+            from: HumanAddr::from("addr0000"),
+            to: HumanAddr::from("addr0001"),
+            amount: Uint256::from(61u64),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("[E3004] Transfer amount cannot exceed locked amount: 60")
+    );
+
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "transfer_collateral"),
+            log("from", "addr0000"),
+            log("to", "addr0001"),
+            log("amount", "60"),
+        ]
+    );
+
+    let query_res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: HumanAddr::from("addr0000"),
+            balance: Uint256::from(40u64),
+            spendable: Uint256::from(40u64),
+        }
+    );
+
+    let query_res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("addr0001"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: HumanAddr::from("addr0001"),
+            balance: Uint256::from(60u64),
+            spendable: Uint256::zero(),
+        }
+    );
+}
+
+#[test]
+fn reward_claimer() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(1000000u128),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner"),
+        collateral_token: HumanAddr::from("bluna"),
+        overseer_contract: HumanAddr::from("overseer"),
+        market_contract: HumanAddr::from("market"),
+        reward_contract: HumanAddr::from("reward"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        stable_denom: "uusd".to_string(),
+        basset_info: BAssetInfo {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            decimals: 6,
+        },
+    };
+
+    let env = mock_env("owner", &[]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let reward_claimer = RewardClaimer {
+        contract_addr: HumanAddr::from("claimer"),
+        claim_msg: Binary::from(br#"{"claim":{}}"#.to_vec()),
+    };
+    let msg = HandleMsg::UpdateRewardClaimer {
+        reward_claimer: Some(reward_claimer.clone()),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_reward_claimer"),
+            log("reward_claimer", "claimer"),
+        ]
+    );
+
+    let res = query(&deps, QueryMsg::RewardClaimer {}).unwrap();
+    let claimer_res: RewardClaimerResponse = from_binary(&res).unwrap();
+    assert_eq!(claimer_res.reward_claimer, Some(reward_claimer));
+
+    // the rewards are claimed with the configured message
+    let msg = HandleMsg::DistributeRewards {
+        reward_recipients: None,
+    };
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("claimer"),
+            send: vec![],
+            msg: Binary::from(br#"{"claim":{}}"#.to_vec()),
+        })
+    );
+
+    // back to the reward contract of the hub
+    let res = handle(
+        &mut deps,
+        env,
+        HandleMsg::UpdateRewardClaimer {
+            reward_claimer: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_reward_claimer"),
+            log("reward_claimer", "reward"),
+        ]
+    );
+
+    let res = query(&deps, QueryMsg::RewardClaimer {}).unwrap();
+    let claimer_res: RewardClaimerResponse = from_binary(&res).unwrap();
+    assert_eq!(claimer_res.reward_claimer, None);
+
+    // the hub claim waits for enough accrued rewards
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+    assert_eq!(res, HandleResponse::default());
+}
+
+#[test]
+fn share_accounting() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner"),
+        collateral_token: HumanAddr::from("beth"),
+        overseer_contract: HumanAddr::from("overseer"),
+        market_contract: HumanAddr::from("market"),
+        reward_contract: HumanAddr::from("reward"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        stable_denom: "uusd".to_string(),
+        basset_info: BAssetInfo {
+            name: "beth".to_string(),
+            symbol: "beth".to_string(),
+            decimals: 6,
+        },
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("beth"),
+        &[(&HumanAddr::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+
+    let msg = HandleMsg::UpdateShareAccounting { enabled: true };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_share_accounting"),
+            log("enabled", true),
+        ]
+    );
+
+    // the first deposit gets shares one to one
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("beth"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(100u128),
+        )],
+    )]);
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap()),
+    });
+    handle(&mut deps, mock_env("beth", &[]), msg).unwrap();
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("beth"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(300u128),
+        )],
+    )]);
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(200u128),
+        msg: Some(to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap()),
+    });
+    handle(&mut deps, mock_env("beth", &[]), msg).unwrap();
+
+    // a positive rebase doubles every collateral
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("beth"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(600u128),
+        )],
+    )]);
+
+    let res = query(&deps, QueryMsg::ShareAccounting {}).unwrap();
+    let share_accounting_res: ShareAccountingResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        share_accounting_res,
+        ShareAccountingResponse {
+            enabled: true,
+            total_shares: Uint256::from(300u64),
+            collateral_balance: Uint256::from(600u64),
+        }
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::SharesToAmount {
+            shares: Uint256::from(100u64),
+        },
+    )
+    .unwrap();
+    let conversion_res: ShareConversionResponse = from_binary(&res).unwrap();
+    assert_eq!(conversion_res.amount, Uint256::from(200u64));
+
+    let res = query(
+        &deps,
+        QueryMsg::AmountToShares {
+            amount: Uint256::from(200u64),
+        },
+    )
+    .unwrap();
+    let conversion_res: ShareConversionResponse = from_binary(&res).unwrap();
+    assert_eq!(conversion_res.shares, Uint256::from(100u64));
+
+    let res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("addr0001"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_res.balance, Uint256::from(400u64));
+    assert_eq!(borrower_res.spendable, Uint256::from(400u64));
+
+    // lock and withdraw in amounts
+    let msg = HandleMsg::LockCollateral {
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(150u64),
+    };
+    handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+
+    let msg = HandleMsg::WithdrawCollateral {
+        amount: Some(Uint256::from(60u64)),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E3001] Withdraw amount cannot exceed the user's spendable amount: 50"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::WithdrawCollateral { amount: None };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("beth"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(50u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // a negative rebase halves every collateral
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("beth"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(275u128),
+        )],
+    )]);
+
+    let res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_res.balance, Uint256::from(75u64));
+    assert_eq!(borrower_res.spendable, Uint256::zero());
+
+    let res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("addr0001"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_res.balance, Uint256::from(200u64));
+
+    let msg = HandleMsg::UpdateShareAccounting { enabled: false };
+    let res = handle(&mut deps, mock_env("owner", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E3006] Cannot disable share accounting while shares are outstanding: 275"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
@@ -5,7 +5,9 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::custody::{
     BorrowerResponse, BorrowersResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, QueryMsg,
+    RewardClaimerResponse, ShareAccountingResponse, ShareConversionResponse,
 };
+use moneymarket::ownership::OwnershipProposalResponse;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -20,4 +22,8 @@ fn main() {
     export_schema(&schema_for!(BorrowerResponse), &out_dir);
     export_schema(&schema_for!(BorrowersResponse), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(OwnershipProposalResponse), &out_dir);
+    export_schema(&schema_for!(RewardClaimerResponse), &out_dir);
+    export_schema(&schema_for!(ShareAccountingResponse), &out_dir);
+    export_schema(&schema_for!(ShareConversionResponse), &out_dir);
 }
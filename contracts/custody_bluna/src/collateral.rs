@@ -1,3 +1,4 @@
+use crate::shares::CollateralUnits;
 use crate::state::{
     read_borrower_info, read_borrowers, read_config, remove_borrower_info, store_borrower_info,
     BorrowerInfo, Config,
@@ -10,6 +11,7 @@ use cosmwasm_std::{
 };
 use cw20::Cw20HandleMsg;
 use moneymarket::custody::{BorrowerResponse, BorrowersResponse};
+use moneymarket::error::ContractError;
 use moneymarket::liquidation::Cw20HookMsg as LiquidationCw20HookMsg;
 use terra_cosmwasm::TerraMsgWrapper;
 
@@ -22,12 +24,15 @@ pub fn deposit_collateral<S: Storage, A: Api, Q: Querier>(
 ) -> HandleResult<TerraMsgWrapper> {
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let mut units = CollateralUnits::load(deps)?;
+    let shares = units.credit(amount)?;
 
     // withdraw rewards to pending rewards
-    borrower_info.balance += amount;
-    borrower_info.spendable += amount;
+    borrower_info.balance += shares;
+    borrower_info.spendable += shares;
 
     store_borrower_info(&mut deps.storage, &borrower_raw, &borrower_info)?;
+    units.save(&mut deps.storage)?;
 
     Ok(HandleResponse {
         messages: vec![],
@@ -53,18 +58,23 @@ pub fn withdraw_collateral<S: Storage, A: Api, Q: Querier>(
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
 
+    let mut units = CollateralUnits::load(deps)?;
+    let spendable = units.to_amount(borrower_info.spendable);
+
     // Check spendable balance
-    let amount = amount.unwrap_or(borrower_info.spendable);
-    if borrower_info.spendable < amount {
-        return Err(StdError::generic_err(format!(
-            "Withdraw amount cannot exceed the user's spendable amount: {}",
-            borrower_info.spendable
-        )));
+    let (amount, debit) = match amount {
+        Some(amount) => (amount, units.debit_units(amount, borrower_info.spendable)),
+        None => (spendable, borrower_info.spendable),
+    };
+    if spendable < amount {
+        return Err(ContractError::WithdrawExceedsSpendable { spendable }.into());
     }
 
     // withdraw rewards to pending rewards
-    borrower_info.balance = borrower_info.balance - amount;
-    borrower_info.spendable = borrower_info.spendable - amount;
+    borrower_info.balance = borrower_info.balance - debit;
+    borrower_info.spendable = borrower_info.spendable - debit;
+    units.debit(debit, amount);
+    units.save(&mut deps.storage)?;
 
     if borrower_info.balance == Uint256::zero() {
         remove_borrower_info(&mut deps.storage, &borrower_raw);
@@ -106,14 +116,14 @@ pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
 
     let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
     let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
-    if amount > borrower_info.spendable {
-        return Err(StdError::generic_err(format!(
-            "Lock amount cannot excceed the user's spendable amount: {}",
-            borrower_info.spendable
-        )));
+    let units = CollateralUnits::load(deps)?;
+    let spendable = units.to_amount(borrower_info.spendable);
+    if amount > spendable {
+        return Err(ContractError::LockExceedsSpendable { spendable }.into());
     }
 
-    borrower_info.spendable = borrower_info.spendable - amount;
+    borrower_info.spendable =
+        borrower_info.spendable - units.debit_units(amount, borrower_info.spendable);
     store_borrower_info(&mut deps.storage, &borrower_raw, &borrower_info)?;
     Ok(HandleResponse {
         messages: vec![],
@@ -142,15 +152,17 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
 
     let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
     let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
-    let borrowed_amt = borrower_info.balance - borrower_info.spendable;
+    let units = CollateralUnits::load(deps)?;
+    let locked = borrower_info.balance - borrower_info.spendable;
+    let borrowed_amt = units.to_amount(locked);
     if amount > borrowed_amt {
-        return Err(StdError::generic_err(format!(
-            "Unlock amount cannot exceed locked amount: {}",
-            borrowed_amt
-        )));
+        return Err(ContractError::UnlockExceedsLocked {
+            locked: borrowed_amt,
+        }
+        .into());
     }
 
-    borrower_info.spendable += amount;
+    borrower_info.spendable += units.debit_units(amount, locked);
     store_borrower_info(&mut deps.storage, &borrower_raw, &borrower_info)?;
 
     Ok(HandleResponse {
@@ -164,6 +176,159 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/// Move spendable collateral of the locker to the
+/// borrower as locked collateral
+/// Executor: overseer
+pub fn lock_collateral_for<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locker: HumanAddr,
+    borrower: HumanAddr,
+    amount: Uint256,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.overseer_contract {
+        return Err(StdError::unauthorized());
+    }
+
+    let locker_raw: CanonicalAddr = deps.api.canonical_address(&locker)?;
+    let mut locker_info: BorrowerInfo = read_borrower_info(&deps.storage, &locker_raw);
+    let units = CollateralUnits::load(deps)?;
+    let spendable = units.to_amount(locker_info.spendable);
+    if amount > spendable {
+        return Err(ContractError::LockExceedsSpendable { spendable }.into());
+    }
+
+    let debit = units.debit_units(amount, locker_info.spendable);
+    locker_info.balance = locker_info.balance - debit;
+    locker_info.spendable = locker_info.spendable - debit;
+    if locker_info.balance == Uint256::zero() {
+        remove_borrower_info(&mut deps.storage, &locker_raw);
+    } else {
+        store_borrower_info(&mut deps.storage, &locker_raw, &locker_info)?;
+    }
+
+    let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    borrower_info.balance += debit;
+    store_borrower_info(&mut deps.storage, &borrower_raw, &borrower_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "lock_collateral_for"),
+            log("locker", locker),
+            log("borrower", borrower),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+/// Move locked collateral of the borrower back
+/// to the locker as spendable collateral
+/// Executor: overseer
+pub fn unlock_collateral_for<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    locker: HumanAddr,
+    borrower: HumanAddr,
+    amount: Uint256,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.overseer_contract {
+        return Err(StdError::unauthorized());
+    }
+
+    let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let units = CollateralUnits::load(deps)?;
+    let locked = borrower_info.balance - borrower_info.spendable;
+    let borrowed_amt = units.to_amount(locked);
+    if amount > borrowed_amt {
+        return Err(ContractError::UnlockExceedsLocked {
+            locked: borrowed_amt,
+        }
+        .into());
+    }
+
+    let debit = units.debit_units(amount, locked);
+    borrower_info.balance = borrower_info.balance - debit;
+    if borrower_info.balance == Uint256::zero() {
+        remove_borrower_info(&mut deps.storage, &borrower_raw);
+    } else {
+        store_borrower_info(&mut deps.storage, &borrower_raw, &borrower_info)?;
+    }
+
+    let locker_raw: CanonicalAddr = deps.api.canonical_address(&locker)?;
+    let mut locker_info: BorrowerInfo = read_borrower_info(&deps.storage, &locker_raw);
+    locker_info.balance += debit;
+    locker_info.spendable += debit;
+    store_borrower_info(&mut deps.storage, &locker_raw, &locker_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "unlock_collateral_for"),
+            log("locker", locker),
+            log("borrower", borrower),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+/// Move locked collateral between borrowers
+/// on a borrow position transfer
+pub fn transfer_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    to: HumanAddr,
+    amount: Uint256,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.overseer_contract {
+        return Err(StdError::unauthorized());
+    }
+
+    let from_raw: CanonicalAddr = deps.api.canonical_address(&from)?;
+    let mut from_info: BorrowerInfo = read_borrower_info(&deps.storage, &from_raw);
+    let units = CollateralUnits::load(deps)?;
+    let locked = from_info.balance - from_info.spendable;
+    let borrowed_amt = units.to_amount(locked);
+    if amount > borrowed_amt {
+        return Err(ContractError::TransferExceedsLocked {
+            locked: borrowed_amt,
+        }
+        .into());
+    }
+
+    let debit = units.debit_units(amount, locked);
+    from_info.balance = from_info.balance - debit;
+    if from_info.balance == Uint256::zero() {
+        remove_borrower_info(&mut deps.storage, &from_raw);
+    } else {
+        store_borrower_info(&mut deps.storage, &from_raw, &from_info)?;
+    }
+
+    let to_raw: CanonicalAddr = deps.api.canonical_address(&to)?;
+    let mut to_info: BorrowerInfo = read_borrower_info(&deps.storage, &to_raw);
+    to_info.balance += debit;
+    store_borrower_info(&mut deps.storage, &to_raw, &to_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "transfer_collateral"),
+            log("from", from),
+            log("to", to),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
 pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
@@ -178,16 +343,21 @@ pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
 
     let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
     let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
-    let borrowed_amt = borrower_info.balance - borrower_info.spendable;
+    let mut units = CollateralUnits::load(deps)?;
+    let locked = borrower_info.balance - borrower_info.spendable;
+    let borrowed_amt = units.to_amount(locked);
     if amount > borrowed_amt {
-        return Err(StdError::generic_err(format!(
-            "Liquidation amount cannot exceed locked amount: {}",
-            borrowed_amt
-        )));
+        return Err(ContractError::LiquidationExceedsLocked {
+            locked: borrowed_amt,
+        }
+        .into());
     }
 
-    borrower_info.balance = borrower_info.balance - amount;
+    let debit = units.debit_units(amount, locked);
+    borrower_info.balance = borrower_info.balance - debit;
     store_borrower_info(&mut deps.storage, &borrower_raw, &borrower_info)?;
+    units.debit(debit, amount);
+    units.save(&mut deps.storage)?;
 
     Ok(HandleResponse {
         messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
@@ -200,6 +370,7 @@ pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
                     liquidator: liquidator.clone(),
                     fee_address: Some(deps.api.human_address(&config.overseer_contract)?),
                     repay_address: Some(deps.api.human_address(&config.market_contract)?),
+                    borrower: Some(borrower.clone()),
                 })?),
             })?,
         })],
@@ -219,10 +390,11 @@ pub fn query_borrower<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<BorrowerResponse> {
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let units = CollateralUnits::load(deps)?;
     Ok(BorrowerResponse {
         borrower,
-        balance: borrower_info.balance,
-        spendable: borrower_info.spendable,
+        balance: units.to_amount(borrower_info.balance),
+        spendable: units.to_amount(borrower_info.spendable),
     })
 }
 
@@ -237,6 +409,14 @@ pub fn query_borrowers<S: Storage, A: Api, Q: Querier>(
         None
     };
 
-    let borrowers = read_borrowers(deps, start_after, limit)?;
+    let units = CollateralUnits::load(deps)?;
+    let borrowers = read_borrowers(deps, start_after, limit)?
+        .into_iter()
+        .map(|v| BorrowerResponse {
+            balance: units.to_amount(v.balance),
+            spendable: units.to_amount(v.spendable),
+            ..v
+        })
+        .collect();
     Ok(BorrowersResponse { borrowers })
 }
@@ -4,14 +4,25 @@ use cosmwasm_std::{
 };
 
 use crate::collateral::{
-    deposit_collateral, liquidate_collateral, lock_collateral, query_borrower, query_borrowers,
-    unlock_collateral, withdraw_collateral,
+    deposit_collateral, liquidate_collateral, lock_collateral, lock_collateral_for, query_borrower,
+    query_borrowers, transfer_collateral, unlock_collateral, unlock_collateral_for,
+    withdraw_collateral,
+};
+use crate::distribution::{
+    distribute_hook, distribute_rewards, query_reward_claimer, swap_to_stable_denom,
+    update_reward_claimer,
+};
+use crate::protocol_mode::assert_protocol_mode;
+use crate::shares::{
+    query_amount_to_shares, query_share_accounting, query_shares_to_amount, update_share_accounting,
 };
-use crate::distribution::{distribute_hook, distribute_rewards, swap_to_stable_denom};
 use crate::state::{read_config, store_config, Config};
 
 use cw20::Cw20ReceiveMsg;
 use moneymarket::custody::{ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, QueryMsg};
+use moneymarket::error::ContractError;
+use moneymarket::ownership::{accept_ownership, propose_new_owner, query_ownership_proposal};
+use moneymarket::protocol_mode::{refresh_protocol_mode, register_protocol_controller};
 use terra_cosmwasm::TerraMsgWrapper;
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
@@ -40,22 +51,62 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     env: Env,
     msg: HandleMsg,
 ) -> HandleResult<TerraMsgWrapper> {
+    assert_protocol_mode(deps, env.block.height, &msg)?;
     match msg {
         HandleMsg::Receive(msg) => receive_cw20(deps, env, msg),
         HandleMsg::UpdateConfig {
-            owner,
             liquidation_contract,
-        } => update_config(deps, env, owner, liquidation_contract),
+        } => update_config(deps, env, liquidation_contract),
+        HandleMsg::ProposeNewOwner {
+            new_owner,
+            expiry_height,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_new_owner(deps, env, &config.owner, new_owner, expiry_height)
+        }
+        HandleMsg::AcceptOwnership {} => accept_ownership(deps, env, |storage, new_owner| {
+            let mut config: Config = read_config(storage)?;
+            config.owner = new_owner;
+            store_config(storage, &config)
+        }),
+        HandleMsg::UpdateRewardClaimer { reward_claimer } => {
+            update_reward_claimer(deps, env, reward_claimer)
+        }
+        HandleMsg::RegisterProtocolController {
+            protocol_controller,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            register_protocol_controller(deps, env, &config.owner, protocol_controller)
+        }
+        HandleMsg::UpdateShareAccounting { enabled } => update_share_accounting(deps, env, enabled),
         HandleMsg::LockCollateral { borrower, amount } => {
             lock_collateral(deps, env, borrower, amount)
         }
         HandleMsg::UnlockCollateral { borrower, amount } => {
             unlock_collateral(deps, env, borrower, amount)
         }
-        HandleMsg::DistributeRewards {} => distribute_rewards(deps, env),
-        HandleMsg::DistributeHook {} => distribute_hook(deps, env),
+        HandleMsg::LockCollateralFor {
+            locker,
+            borrower,
+            amount,
+        } => lock_collateral_for(deps, env, locker, borrower, amount),
+        HandleMsg::UnlockCollateralFor {
+            locker,
+            borrower,
+            amount,
+        } => unlock_collateral_for(deps, env, locker, borrower, amount),
+        HandleMsg::TransferCollateral { from, to, amount } => {
+            transfer_collateral(deps, env, from, to, amount)
+        }
+        HandleMsg::DistributeRewards { reward_recipients } => {
+            distribute_rewards(deps, env, reward_recipients)
+        }
+        HandleMsg::DistributeHook { reward_recipients } => {
+            distribute_hook(deps, env, reward_recipients)
+        }
         HandleMsg::SwapToStableDenom {} => swap_to_stable_denom(deps, env),
         HandleMsg::WithdrawCollateral { amount } => withdraw_collateral(deps, env, amount),
+        HandleMsg::RefreshProtocolMode {} => refresh_protocol_mode(deps, env),
         HandleMsg::LiquidateCollateral {
             liquidator,
             borrower,
@@ -83,16 +134,16 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
             }
         }
     } else {
-        Err(StdError::generic_err(
-            "Invalid request: \"deposit collateral\" message not included in request",
-        ))
+        Err(ContractError::MissingHookMsg {
+            msg: "deposit collateral".to_string(),
+        }
+        .into())
     }
 }
 
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    owner: Option<HumanAddr>,
     liquidation_contract: Option<HumanAddr>,
 ) -> HandleResult<TerraMsgWrapper> {
     let mut config: Config = read_config(&deps.storage)?;
@@ -101,10 +152,6 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    if let Some(owner) = owner {
-        config.owner = deps.api.canonical_address(&owner)?;
-    }
-
     if let Some(liquidation_contract) = liquidation_contract {
         config.liquidation_contract = deps.api.canonical_address(&liquidation_contract)?;
     }
@@ -123,10 +170,15 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
         QueryMsg::Borrower { address } => to_binary(&query_borrower(deps, address)?),
         QueryMsg::Borrowers { start_after, limit } => {
             to_binary(&query_borrowers(deps, start_after, limit)?)
         }
+        QueryMsg::RewardClaimer {} => to_binary(&query_reward_claimer(deps)?),
+        QueryMsg::ShareAccounting {} => to_binary(&query_share_accounting(deps)?),
+        QueryMsg::SharesToAmount { shares } => to_binary(&query_shares_to_amount(deps, shares)?),
+        QueryMsg::AmountToShares { amount } => to_binary(&query_amount_to_shares(deps, amount)?),
     }
 }
 
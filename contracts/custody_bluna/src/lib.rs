@@ -1,6 +1,8 @@
 pub mod collateral;
 pub mod contract;
 pub mod distribution;
+pub mod protocol_mode;
+pub mod shares;
 pub mod state;
 
 mod external;
@@ -0,0 +1,206 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, Querier, StdError, StdResult, Storage,
+};
+
+use crate::state::{
+    read_config, read_share_accounting, remove_share_accounting, store_share_accounting, Config,
+    ShareAccounting,
+};
+
+use moneymarket::custody::{ShareAccountingResponse, ShareConversionResponse};
+use moneymarket::error::ContractError;
+use moneymarket::querier::query_token_balance;
+use terra_cosmwasm::TerraMsgWrapper;
+
+/// Keep the borrower balances in shares of the custody collateral
+/// balance, so the rebases of the collateral token flow to the
+/// borrowers. The amounts held when enabled become shares one to one,
+/// and it can only be disabled once no share is left
+pub fn update_share_accounting<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    enabled: bool,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    match (read_share_accounting(&deps.storage)?, enabled) {
+        (None, true) => {
+            let collateral_balance = query_token_balance(
+                deps,
+                &deps.api.human_address(&config.collateral_token)?,
+                &env.contract.address,
+            )?;
+
+            store_share_accounting(
+                &mut deps.storage,
+                &ShareAccounting {
+                    contract_addr: deps.api.canonical_address(&env.contract.address)?,
+                    total_shares: collateral_balance,
+                },
+            )?;
+        }
+        (Some(share_accounting), false) => {
+            if !share_accounting.total_shares.is_zero() {
+                return Err(ContractError::SharesOutstanding {
+                    total_shares: share_accounting.total_shares,
+                }
+                .into());
+            }
+
+            remove_share_accounting(&mut deps.storage);
+        }
+        _ => {}
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_share_accounting"),
+            log("enabled", enabled),
+        ],
+        data: None,
+    })
+}
+
+/// Units the borrower balances are kept in, collateral token
+/// amounts or shares of the custody collateral balance
+pub(crate) struct CollateralUnits {
+    share_accounting: Option<ShareAccounting>,
+    collateral_balance: Uint256,
+}
+
+impl CollateralUnits {
+    pub fn load<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<Self> {
+        let share_accounting = read_share_accounting(&deps.storage)?;
+        let collateral_balance = match &share_accounting {
+            Some(v) => {
+                let config: Config = read_config(&deps.storage)?;
+                query_token_balance(
+                    deps,
+                    &deps.api.human_address(&config.collateral_token)?,
+                    &deps.api.human_address(&v.contract_addr)?,
+                )?
+            }
+            None => Uint256::zero(),
+        };
+
+        Ok(CollateralUnits {
+            share_accounting,
+            collateral_balance,
+        })
+    }
+
+    /// Collateral amount of `units`, rounded down
+    pub fn to_amount(&self, units: Uint256) -> Uint256 {
+        match &self.share_accounting {
+            Some(v) if v.total_shares.is_zero() => Uint256::zero(),
+            Some(v) => units.multiply_ratio(self.collateral_balance.0, v.total_shares.0),
+            None => units,
+        }
+    }
+
+    /// Units worth `amount`, rounded down
+    pub fn to_units(&self, amount: Uint256) -> Uint256 {
+        match &self.share_accounting {
+            Some(v) if v.total_shares.is_zero() || self.collateral_balance.is_zero() => amount,
+            Some(v) => amount.multiply_ratio(v.total_shares.0, self.collateral_balance.0),
+            None => amount,
+        }
+    }
+
+    /// Units to take out of `available` units for `amount`, rounded up
+    /// so the amount is fully covered. The caller checks beforehand that
+    /// `available` units are worth the amount
+    pub fn debit_units(&self, amount: Uint256, available: Uint256) -> Uint256 {
+        let mut units = self.to_units(amount);
+        if self.to_amount(units) < amount {
+            units += Uint256::one();
+        }
+
+        if units > available {
+            available
+        } else {
+            units
+        }
+    }
+
+    /// Shares minted for the deposited `amount`, which is
+    /// already part of the custody collateral balance
+    pub fn credit(&mut self, amount: Uint256) -> StdResult<Uint256> {
+        let share_accounting = match &mut self.share_accounting {
+            Some(v) => v,
+            None => return Ok(amount),
+        };
+
+        let prev_balance = self.collateral_balance - amount;
+        let shares = if share_accounting.total_shares.is_zero() {
+            amount
+        } else if prev_balance.is_zero() {
+            return Err(ContractError::EmptyShares {}.into());
+        } else {
+            amount.multiply_ratio(share_accounting.total_shares.0, prev_balance.0)
+        };
+
+        share_accounting.total_shares += shares;
+        Ok(shares)
+    }
+
+    /// Burns the `units` of the `amount` sent out of the custody
+    pub fn debit(&mut self, units: Uint256, amount: Uint256) {
+        if let Some(v) = &mut self.share_accounting {
+            v.total_shares = v.total_shares - units;
+            self.collateral_balance = self.collateral_balance - amount;
+        }
+    }
+
+    pub fn save<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
+        match &self.share_accounting {
+            Some(v) => store_share_accounting(storage, v),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn query_share_accounting<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ShareAccountingResponse> {
+    let units = CollateralUnits::load(deps)?;
+    Ok(match &units.share_accounting {
+        Some(v) => ShareAccountingResponse {
+            enabled: true,
+            total_shares: v.total_shares,
+            collateral_balance: units.collateral_balance,
+        },
+        None => ShareAccountingResponse {
+            enabled: false,
+            total_shares: Uint256::zero(),
+            collateral_balance: Uint256::zero(),
+        },
+    })
+}
+
+pub fn query_shares_to_amount<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    shares: Uint256,
+) -> StdResult<ShareConversionResponse> {
+    let units = CollateralUnits::load(deps)?;
+    Ok(ShareConversionResponse {
+        shares,
+        amount: units.to_amount(shares),
+    })
+}
+
+pub fn query_amount_to_shares<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    amount: Uint256,
+) -> StdResult<ShareConversionResponse> {
+    let units = CollateralUnits::load(deps)?;
+    Ok(ShareConversionResponse {
+        shares: units.to_units(amount),
+        amount,
+    })
+}
@@ -4,7 +4,7 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::interest_model::{
-    BorrowRateResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg,
+    BorrowRateResponse, ConfigResponse, DepositRateResponse, HandleMsg, InitMsg, QueryMsg,
 };
 
 fn main() {
@@ -18,4 +18,5 @@ fn main() {
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(BorrowRateResponse), &out_dir);
+    export_schema(&schema_for!(DepositRateResponse), &out_dir);
 }
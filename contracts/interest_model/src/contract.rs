@@ -7,7 +7,7 @@ use cosmwasm_std::{
     Querier, StdError, StdResult, Storage,
 };
 use moneymarket::interest_model::{
-    BorrowRateResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg,
+    BorrowRateResponse, ConfigResponse, DepositRateResponse, HandleMsg, InitMsg, Kink, QueryMsg,
 };
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
@@ -15,12 +15,14 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
     _env: Env,
     msg: InitMsg,
 ) -> StdResult<InitResponse> {
+    let kink = validate_kink(msg.kink)?;
     store_config(
         &mut deps.storage,
         &Config {
             owner: deps.api.canonical_address(&msg.owner)?,
             base_rate: msg.base_rate,
             interest_multiplier: msg.interest_multiplier,
+            kink,
         },
     )?;
 
@@ -37,7 +39,8 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             owner,
             base_rate,
             interest_multiplier,
-        } => update_config(deps, env, owner, base_rate, interest_multiplier),
+            kink,
+        } => update_config(deps, env, owner, base_rate, interest_multiplier, kink),
     }
 }
 
@@ -47,6 +50,7 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
     owner: Option<HumanAddr>,
     base_rate: Option<Decimal256>,
     interest_multiplier: Option<Decimal256>,
+    kink: Option<Kink>,
 ) -> HandleResult {
     let mut config: Config = read_config(&deps.storage)?;
     if deps.api.canonical_address(&env.message.sender)? != config.owner {
@@ -65,6 +69,10 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         config.interest_multiplier = interest_multiplier;
     }
 
+    if kink.is_some() {
+        config.kink = validate_kink(kink)?;
+    }
+
     store_config(&mut deps.storage, &config)?;
     Ok(HandleResponse::default())
 }
@@ -85,6 +93,18 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             total_liabilities,
             total_reserves,
         )?),
+        QueryMsg::DepositRate {
+            market_balance,
+            total_liabilities,
+            total_reserves,
+            reserve_factor,
+        } => to_binary(&query_deposit_rate(
+            deps,
+            market_balance,
+            total_liabilities,
+            total_reserves,
+            reserve_factor,
+        )?),
     }
 }
 
@@ -96,6 +116,7 @@ fn query_config<S: Storage, A: Api, Q: Querier>(
         owner: deps.api.human_address(&state.owner)?,
         base_rate: state.base_rate,
         interest_multiplier: state.interest_multiplier,
+        kink: state.kink,
     };
 
     Ok(resp)
@@ -108,20 +129,75 @@ fn query_borrow_rate<S: Storage, A: Api, Q: Querier>(
     total_reserves: Decimal256,
 ) -> StdResult<BorrowRateResponse> {
     let config: Config = read_config(&deps.storage)?;
+    let utilization_ratio =
+        compute_utilization_ratio(market_balance, total_liabilities, total_reserves);
+
+    Ok(BorrowRateResponse {
+        rate: compute_borrow_rate(&config, utilization_ratio),
+    })
+}
+
+fn query_deposit_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    market_balance: Uint256,
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+    reserve_factor: Decimal256,
+) -> StdResult<DepositRateResponse> {
+    if reserve_factor > Decimal256::one() {
+        return Err(StdError::generic_err(
+            "reserve_factor must be less than or equal to 1",
+        ));
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let utilization_ratio =
+        compute_utilization_ratio(market_balance, total_liabilities, total_reserves);
+    let borrow_rate = compute_borrow_rate(&config, utilization_ratio);
+
+    // depositors earn the interest paid on the borrowed share
+    // of the market, minus the part kept as reserves
+    Ok(DepositRateResponse {
+        rate: borrow_rate * utilization_ratio * (Decimal256::one() - reserve_factor),
+    })
+}
 
+fn compute_utilization_ratio(
+    market_balance: Uint256,
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+) -> Decimal256 {
     // ignore decimal parts
     let total_value_in_market =
         Decimal256::from_uint256(market_balance) + total_liabilities - total_reserves;
 
-    let utilization_ratio = if total_value_in_market.is_zero() {
+    if total_value_in_market.is_zero() {
         Decimal256::zero()
     } else {
         total_liabilities / total_value_in_market
-    };
+    }
+}
 
-    Ok(BorrowRateResponse {
-        rate: utilization_ratio * config.interest_multiplier + config.base_rate,
-    })
+/// A kink at a utilization of 1 or above never applies, so it is dropped
+fn validate_kink(kink: Option<Kink>) -> StdResult<Option<Kink>> {
+    match kink {
+        Some(kink) if kink.utilization.is_zero() => Err(StdError::generic_err(
+            "kink utilization must be greater than 0",
+        )),
+        Some(kink) if kink.utilization >= Decimal256::one() => Ok(None),
+        kink => Ok(kink),
+    }
+}
+
+fn compute_borrow_rate(config: &Config, utilization_ratio: Decimal256) -> Decimal256 {
+    match &config.kink {
+        Some(kink) if utilization_ratio > kink.utilization => {
+            kink.utilization * config.interest_multiplier
+                + (utilization_ratio - kink.utilization) * kink.jump_multiplier
+                + config.base_rate
+        }
+        _ => utilization_ratio * config.interest_multiplier + config.base_rate,
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +214,7 @@ mod tests {
             owner: HumanAddr("owner0000".to_string()),
             base_rate: Decimal256::percent(10),
             interest_multiplier: Decimal256::percent(10),
+            kink: None,
         };
 
         let env = mock_env("addr0000", &[]);
@@ -173,6 +250,59 @@ mod tests {
         assert_eq!("0.1", &value.rate.to_string());
     }
 
+    #[test]
+    fn deposit_rate() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_rate: Decimal256::percent(10),
+            interest_multiplier: Decimal256::percent(10),
+            kink: None,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let res = query_deposit_rate(
+            &deps,
+            Uint256::from(1000000u128),
+            Decimal256::from_uint256(500000u128),
+            Decimal256::from_uint256(100000u128),
+            Decimal256::percent(101),
+        );
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "reserve_factor must be less than or equal to 1")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        let value = query_deposit_rate(
+            &deps,
+            Uint256::from(1000000u128),
+            Decimal256::from_uint256(500000u128),
+            Decimal256::from_uint256(100000u128),
+            Decimal256::percent(20),
+        )
+        .unwrap();
+        // utilization_ratio = 0.35714285714285714
+        // borrow_rate = 0.035714285 + 0.1
+        // deposit_rate = 0.135714285 * 0.357142857 * 0.8
+        assert_eq!("0.038775510204081632", &value.rate.to_string());
+
+        // nothing is borrowed, so depositors earn nothing
+        let value = query_deposit_rate(
+            &deps,
+            Uint256::from(1000000u128),
+            Decimal256::zero(),
+            Decimal256::zero(),
+            Decimal256::percent(20),
+        )
+        .unwrap();
+        assert_eq!(Decimal256::zero(), value.rate);
+    }
+
     #[test]
     fn update_config() {
         let mut deps = mock_dependencies(20, &[]);
@@ -181,6 +311,7 @@ mod tests {
             owner: HumanAddr("owner0000".to_string()),
             base_rate: Decimal256::percent(10),
             interest_multiplier: Decimal256::percent(10),
+            kink: None,
         };
 
         let env = mock_env("addr0000", &[]);
@@ -192,6 +323,7 @@ mod tests {
             owner: Some(HumanAddr("owner0001".to_string())),
             base_rate: None,
             interest_multiplier: None,
+            kink: None,
         };
 
         let res = handle(&mut deps, env, msg).unwrap();
@@ -209,6 +341,7 @@ mod tests {
             owner: None,
             base_rate: Some(Decimal256::percent(1)),
             interest_multiplier: Some(Decimal256::percent(1)),
+            kink: None,
         };
 
         let res = handle(&mut deps, env, msg);
@@ -217,4 +350,82 @@ mod tests {
             _ => panic!("Must return unauthorized error"),
         }
     }
+    #[test]
+    fn kinked_borrow_rate() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_rate: Decimal256::percent(10),
+            interest_multiplier: Decimal256::percent(10),
+            kink: Some(Kink {
+                utilization: Decimal256::percent(80),
+                jump_multiplier: Decimal256::one(),
+            }),
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        // below the kink, the model is linear
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(500000u128),
+            Decimal256::from_uint256(500000u128),
+            Decimal256::zero(),
+        )
+        .unwrap();
+        // borrow_rate = 0.5 * 0.1 + 0.1
+        assert_eq!("0.15", &value.rate.to_string());
+
+        // above the kink, the rate grows with the jump multiplier
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(100000u128),
+            Decimal256::from_uint256(900000u128),
+            Decimal256::zero(),
+        )
+        .unwrap();
+        // borrow_rate = 0.8 * 0.1 + 0.1 * 1 + 0.1
+        assert_eq!("0.28", &value.rate.to_string());
+
+        let msg = HandleMsg::UpdateConfig {
+            owner: None,
+            base_rate: None,
+            interest_multiplier: None,
+            kink: Some(Kink {
+                utilization: Decimal256::zero(),
+                jump_multiplier: Decimal256::one(),
+            }),
+        };
+        let res = handle(&mut deps, mock_env("owner0000", &[]), msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "kink utilization must be greater than 0")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        // a kink at a utilization of 1 removes it
+        let msg = HandleMsg::UpdateConfig {
+            owner: None,
+            base_rate: None,
+            interest_multiplier: None,
+            kink: Some(Kink {
+                utilization: Decimal256::one(),
+                jump_multiplier: Decimal256::one(),
+            }),
+        };
+        let _res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+        assert_eq!(query_config(&deps).unwrap().kink, None);
+
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(100000u128),
+            Decimal256::from_uint256(900000u128),
+            Decimal256::zero(),
+        )
+        .unwrap();
+        assert_eq!("0.19", &value.rate.to_string());
+    }
 }
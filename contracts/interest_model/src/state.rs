@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read};
+use moneymarket::interest_model::Kink;
 
 static KEY_CONFIG: &[u8] = b"config";
 
@@ -12,6 +13,8 @@ pub struct Config {
     pub owner: CanonicalAddr,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    #[serde(default)]
+    pub kink: Option<Kink>,
 }
 
 pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
@@ -4,9 +4,13 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::liquidation::{
-    BidResponse, BidsResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg,
-    LiquidationAmountResponse, QueryMsg,
+    AuctionResponse, AuctionsResponse, BidResponse, BidsResponse, CollateralMetricsResponse,
+    CollateralStreamResponse, ConfigResponse, ContinuationTicketResponse,
+    ContinuationTicketsResponse, Cw20HookMsg, HandleMsg, InitMsg, LiquidationAmountResponse,
+    LiquidationMechanismResponse, LiquidatorsResponse, MetricsResponse, QueryMsg,
+    StreamConfigResponse,
 };
+use moneymarket::ownership::OwnershipProposalResponse;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -19,7 +23,18 @@ fn main() {
     export_schema(&schema_for!(Cw20HookMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(OwnershipProposalResponse), &out_dir);
     export_schema(&schema_for!(BidResponse), &out_dir);
     export_schema(&schema_for!(BidsResponse), &out_dir);
     export_schema(&schema_for!(LiquidationAmountResponse), &out_dir);
+    export_schema(&schema_for!(CollateralMetricsResponse), &out_dir);
+    export_schema(&schema_for!(MetricsResponse), &out_dir);
+    export_schema(&schema_for!(LiquidatorsResponse), &out_dir);
+    export_schema(&schema_for!(StreamConfigResponse), &out_dir);
+    export_schema(&schema_for!(CollateralStreamResponse), &out_dir);
+    export_schema(&schema_for!(LiquidationMechanismResponse), &out_dir);
+    export_schema(&schema_for!(AuctionResponse), &out_dir);
+    export_schema(&schema_for!(AuctionsResponse), &out_dir);
+    export_schema(&schema_for!(ContinuationTicketResponse), &out_dir);
+    export_schema(&schema_for!(ContinuationTicketsResponse), &out_dir);
 }
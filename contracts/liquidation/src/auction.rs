@@ -0,0 +1,358 @@
+use crate::liquidators::assert_liquidator;
+use crate::state::{
+    push_auction, read_auction, read_auctions, read_collateral_metrics, read_config,
+    read_liquidation_mechanism, remove_auction, remove_liquidation_mechanism, store_auction,
+    store_collateral_metrics, store_liquidation_mechanism, Auction, CollateralMetrics, Config,
+};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+use moneymarket::liquidation::{
+    AuctionResponse, AuctionsResponse, LiquidationMechanism, LiquidationMechanismResponse,
+};
+use moneymarket::market::HandleMsg as MarketHandleMsg;
+use moneymarket::oracle::PriceResponse;
+use moneymarket::querier::{deduct_tax, query_price, TimeConstraints};
+
+pub fn update_liquidation_mechanism<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    mechanism: LiquidationMechanism,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    match &mechanism {
+        LiquidationMechanism::Bid {} => {
+            remove_liquidation_mechanism(&mut deps.storage, &collateral_token_raw);
+        }
+        LiquidationMechanism::DutchAuction {
+            start_premium,
+            decay_rate,
+            floor,
+        } => {
+            if decay_rate.is_zero() {
+                return Err(StdError::generic_err("decay_rate must be greater than 0"));
+            }
+
+            if floor.is_zero() || *floor > Decimal256::one() + *start_premium {
+                return Err(StdError::generic_err(
+                    "floor must be greater than 0 and cannot exceed the start price ratio",
+                ));
+            }
+
+            store_liquidation_mechanism(&mut deps.storage, &collateral_token_raw, &mechanism)?;
+        }
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_liquidation_mechanism"),
+            log("collateral_token", collateral_token),
+            log(
+                "mechanism",
+                match mechanism {
+                    LiquidationMechanism::Bid {} => "bid",
+                    LiquidationMechanism::DutchAuction { .. } => "dutch_auction",
+                },
+            ),
+        ],
+        data: None,
+    })
+}
+
+impl Auction {
+    /// Price over the oracle price at `block_height`
+    fn price_ratio(&self, block_height: u64) -> Decimal256 {
+        let start_ratio = Decimal256::one() + self.start_premium;
+        let decay = self.decay_rate
+            * Decimal256::from_uint256(block_height.saturating_sub(self.start_height));
+        if start_ratio > self.floor + decay {
+            start_ratio - decay
+        } else {
+            self.floor
+        }
+    }
+}
+
+/// Puts the liquidated collateral up for auction instead of executing
+/// a bid. The proceeds repay the loan of `borrower` as the auction sells
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn start_auction<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    liquidator: HumanAddr,
+    repay_address: HumanAddr,
+    fee_address: HumanAddr,
+    borrower: Option<HumanAddr>,
+    collateral_token: HumanAddr,
+    amount: Uint256,
+) -> HandleResult {
+    assert_liquidator(&deps.storage, &deps.api.canonical_address(&liquidator)?)?;
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let (start_premium, decay_rate, floor) =
+        match read_liquidation_mechanism(&deps.storage, &collateral_token_raw)? {
+            LiquidationMechanism::DutchAuction {
+                start_premium,
+                decay_rate,
+                floor,
+            } => (start_premium, decay_rate, floor),
+            LiquidationMechanism::Bid {} => {
+                return Err(StdError::generic_err(format!(
+                    "Collateral is not auctioned: {}",
+                    collateral_token
+                )))
+            }
+        };
+
+    let borrower = borrower.ok_or_else(|| {
+        StdError::generic_err("Auctioned collateral requires the borrower it was seized from")
+    })?;
+
+    let auction_id = push_auction(
+        &mut deps.storage,
+        &Auction {
+            collateral_token: collateral_token_raw,
+            borrower: deps.api.canonical_address(&borrower)?,
+            repay_address: deps.api.canonical_address(&repay_address)?,
+            fee_address: deps.api.canonical_address(&fee_address)?,
+            amount,
+            sold_amount: Uint256::zero(),
+            raised_amount: Uint256::zero(),
+            start_height: env.block.height,
+            start_premium,
+            decay_rate,
+            floor,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "start_auction"),
+            log("auction_id", auction_id),
+            log("collateral_token", collateral_token),
+            log("borrower", borrower),
+            log("collateral_amount", amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn buy_auction_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    auction_id: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    assert_liquidator(
+        &deps.storage,
+        &deps.api.canonical_address(&env.message.sender)?,
+    )?;
+
+    let mut auction = read_auction(&deps.storage, auction_id)?;
+    let stable_amount: Uint256 = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    if stable_amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "No {} assets have been provided",
+            config.stable_denom
+        )));
+    }
+
+    let collateral_token = deps.api.human_address(&auction.collateral_token)?;
+    let price: PriceResponse = query_price(
+        deps,
+        &deps.api.human_address(&config.oracle_contract)?,
+        collateral_token.to_string(),
+        config.stable_denom.clone(),
+        Some(TimeConstraints {
+            block_time: env.block.time,
+            valid_timeframe: config.price_timeframe,
+        }),
+    )?;
+
+    let auction_price = price.rate * auction.price_ratio(env.block.height);
+    let collateral_amount = std::cmp::min(
+        auction.amount,
+        Decimal256::from_uint256(stable_amount) / auction_price * Uint256::one(),
+    );
+    if collateral_amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "Provided amount cannot buy any collateral; Auction price: {}",
+            auction_price
+        )));
+    }
+
+    let paid_amount = collateral_amount * auction_price;
+    let bid_fee = paid_amount * config.bid_fee;
+    let repay_amount = paid_amount - bid_fee;
+
+    let collateral_value = collateral_amount * price.rate;
+    let mut metrics: CollateralMetrics =
+        read_collateral_metrics(&deps.storage, &auction.collateral_token);
+    metrics.executions += 1;
+    metrics.collateral_amount += collateral_amount;
+    metrics.collateral_value += collateral_value;
+    if collateral_value > paid_amount {
+        metrics.premium_value += collateral_value - paid_amount;
+    }
+    store_collateral_metrics(&mut deps.storage, &auction.collateral_token, &metrics)?;
+
+    auction.amount = auction.amount - collateral_amount;
+    auction.sold_amount += collateral_amount;
+    auction.raised_amount += paid_amount;
+    if auction.amount.is_zero() {
+        remove_auction(&mut deps.storage, auction_id);
+    } else {
+        store_auction(&mut deps.storage, auction_id, &auction)?;
+    }
+
+    let borrower = deps.api.human_address(&auction.borrower)?;
+    let mut messages: Vec<CosmosMsg> = vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: collateral_token.clone(),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: env.message.sender.clone(),
+                amount: collateral_amount.into(),
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&auction.repay_address)?,
+            send: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: repay_amount.into(),
+                },
+            )?],
+            msg: to_binary(&MarketHandleMsg::RepayStableFor {
+                borrower: borrower.clone(),
+            })?,
+        }),
+    ];
+
+    if !bid_fee.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: deps.api.human_address(&auction.fee_address)?,
+            amount: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: bid_fee.into(),
+                },
+            )?],
+        }));
+    }
+
+    // the stable the remaining collateral could not absorb
+    let refund_amount = stable_amount - paid_amount;
+    if !refund_amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: env.message.sender.clone(),
+            amount: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: refund_amount.into(),
+                },
+            )?],
+        }));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "buy_auction_collateral"),
+            log("auction_id", auction_id),
+            log("buyer", env.message.sender),
+            log("borrower", borrower),
+            log("collateral_token", collateral_token),
+            log("collateral_amount", collateral_amount),
+            log("repay_amount", repay_amount),
+            log("bid_fee", bid_fee),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_liquidation_mechanism<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<LiquidationMechanismResponse> {
+    let mechanism = read_liquidation_mechanism(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    Ok(LiquidationMechanismResponse {
+        collateral_token,
+        mechanism,
+    })
+}
+
+fn to_auction_response<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    auction_id: u64,
+    auction: Auction,
+    block_height: Option<u64>,
+) -> StdResult<AuctionResponse> {
+    Ok(AuctionResponse {
+        auction_id,
+        collateral_token: deps.api.human_address(&auction.collateral_token)?,
+        borrower: deps.api.human_address(&auction.borrower)?,
+        amount: auction.amount,
+        sold_amount: auction.sold_amount,
+        raised_amount: auction.raised_amount,
+        start_height: auction.start_height,
+        price_ratio: auction.price_ratio(block_height.unwrap_or(auction.start_height)),
+    })
+}
+
+pub fn query_auction<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    auction_id: u64,
+    block_height: Option<u64>,
+) -> StdResult<AuctionResponse> {
+    let auction = read_auction(&deps.storage, auction_id)?;
+    to_auction_response(deps, auction_id, auction, block_height)
+}
+
+pub fn query_auctions<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: Option<HumanAddr>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    block_height: Option<u64>,
+) -> StdResult<AuctionsResponse> {
+    let collateral_token = match collateral_token {
+        Some(v) => Some(deps.api.canonical_address(&v)?),
+        None => None,
+    };
+
+    let auctions = read_auctions(&deps.storage, collateral_token, start_after, limit)?
+        .into_iter()
+        .map(|(auction_id, auction)| to_auction_response(deps, auction_id, auction, block_height))
+        .collect::<StdResult<Vec<AuctionResponse>>>()?;
+
+    Ok(AuctionsResponse { auctions })
+}
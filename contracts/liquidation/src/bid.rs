@@ -1,15 +1,20 @@
+use crate::liquidators::assert_liquidator;
 use crate::state::{
-    read_bid, read_bids_by_collateral, read_bids_by_user, read_config, remove_bid, store_bid, Bid,
-    Config,
+    push_continuation_ticket, read_all_collateral_metrics, read_bid, read_bids_by_collateral,
+    read_bids_by_user, read_collateral_metrics, read_config, remove_bid, store_bid,
+    store_collateral_metrics, Bid, CollateralMetrics, Config, ContinuationTicket,
 };
+use crate::stream::stream_collateral;
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
-    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+    log, to_binary, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, Env, Extern, HandleResponse,
+    HandleResult, HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
 };
 use cw20::Cw20HandleMsg;
-use moneymarket::liquidation::{BidResponse, BidsResponse};
+use moneymarket::liquidation::{
+    BidResponse, BidsResponse, CollateralMetricsResponse, MetricsResponse,
+};
 use moneymarket::oracle::PriceResponse;
 use moneymarket::querier::{deduct_tax, query_price, TimeConstraints};
 
@@ -21,6 +26,7 @@ pub fn submit_bid<S: Storage, A: Api, Q: Querier>(
 ) -> HandleResult {
     let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
     let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    assert_liquidator(&deps.storage, &bidder_raw)?;
     if read_bid(&deps.storage, &bidder_raw, &collateral_token_raw).is_ok() {
         return Err(StdError::generic_err(format!(
             "User already has bid for specified collateral: {}",
@@ -109,7 +115,7 @@ pub fn retract_bid<S: Storage, A: Api, Q: Querier>(
             from_address: env.contract.address,
             to_address: env.message.sender.clone(),
             amount: vec![deduct_tax(
-                &deps,
+                deps,
                 Coin {
                     denom: config.stable_denom,
                     amount: amount.into(),
@@ -126,23 +132,26 @@ pub fn retract_bid<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_bid<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     liquidator: HumanAddr,
     repay_address: HumanAddr,
     fee_address: HumanAddr,
+    borrower: Option<HumanAddr>,
     collateral_token: HumanAddr,
     amount: Uint256,
 ) -> HandleResult {
     let config: Config = read_config(&deps.storage)?;
     let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
     let bidder_raw = deps.api.canonical_address(&liquidator)?;
+    assert_liquidator(&deps.storage, &bidder_raw)?;
     let bid: Bid = read_bid(&deps.storage, &bidder_raw, &collateral_token_raw)?;
 
     let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
     let price: PriceResponse = query_price(
-        &deps,
+        deps,
         &oracle_contract,
         collateral_token.to_string(),
         config.stable_denom.clone(),
@@ -152,9 +161,149 @@ pub fn execute_bid<S: Storage, A: Api, Q: Querier>(
         }),
     )?;
 
-    let collateral_value = amount * price.rate;
-    let required_stable = collateral_value
-        * (Decimal256::one() - std::cmp::min(bid.premium_rate, config.max_premium_rate));
+    // the bid fills what it can of the collateral of a borrower,
+    // the rest is left to a continuation ticket
+    let premium_rate = std::cmp::min(bid.premium_rate, config.max_premium_rate);
+    let required_stable = amount * price.rate * (Decimal256::one() - premium_rate);
+    let fill_amount = if required_stable <= bid.amount {
+        amount
+    } else if borrower.is_some() {
+        fillable_amount(&bid, price.rate, premium_rate, amount)
+    } else {
+        Uint256::zero()
+    };
+    if fill_amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "Insufficient bid balance; Required balance: {}",
+            required_stable
+        )));
+    }
+
+    let fill = fill_bid(
+        deps,
+        env.block.height,
+        &liquidator,
+        &bidder_raw,
+        bid,
+        &collateral_token,
+        &collateral_token_raw,
+        fill_amount,
+        price.rate,
+        premium_rate,
+    )?;
+
+    let mut messages: Vec<CosmosMsg> = fill.messages;
+    messages.push(CosmosMsg::Bank(BankMsg::Send {
+        from_address: env.contract.address.clone(),
+        to_address: repay_address.clone(),
+        amount: vec![deduct_tax(
+            deps,
+            Coin {
+                denom: config.stable_denom.clone(),
+                amount: fill.repay_amount.into(),
+            },
+        )?],
+    }));
+
+    if !fill.bid_fee.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: fee_address.clone(),
+            amount: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: fill.bid_fee.into(),
+                },
+            )?],
+        }));
+    }
+
+    let mut logs = vec![
+        log("action", "execute_bid"),
+        log("stable_denom", config.stable_denom),
+        log("repay_amount", fill.repay_amount),
+        log("bid_fee", fill.bid_fee),
+        log("collateral_token", collateral_token),
+        log("collateral_amount", fill_amount),
+    ];
+    if fill.streamed {
+        logs.push(log("streamed", true));
+    }
+
+    if let Some(borrower) = borrower.filter(|_| fill_amount < amount) {
+        let remaining_amount = amount - fill_amount;
+        let ticket_id = push_continuation_ticket(
+            &mut deps.storage,
+            &ContinuationTicket {
+                collateral_token: collateral_token_raw,
+                borrower: deps.api.canonical_address(&borrower)?,
+                repay_address: deps.api.canonical_address(&repay_address)?,
+                fee_address: deps.api.canonical_address(&fee_address)?,
+                amount: remaining_amount,
+                filled_amount: fill_amount,
+                price: price.rate,
+                max_premium_rate: config.max_premium_rate,
+                created_height: env.block.height,
+            },
+        )?;
+        logs.push(log("continuation_ticket_id", ticket_id));
+        logs.push(log("remaining_amount", remaining_amount));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Collateral of `amount` at most the bid can pay for at `price`
+pub(crate) fn fillable_amount(
+    bid: &Bid,
+    price: Decimal256,
+    premium_rate: Decimal256,
+    amount: Uint256,
+) -> Uint256 {
+    let bid_price = price * (Decimal256::one() - premium_rate);
+    if bid_price.is_zero() {
+        return amount;
+    }
+
+    std::cmp::min(
+        amount,
+        Decimal256::from_uint256(bid.amount) / bid_price * Uint256::one(),
+    )
+}
+
+/// Stable taken from a bid for its fill, split into the
+/// repay amount and the bid fee
+pub(crate) struct BidFill {
+    pub(crate) repay_amount: Uint256,
+    pub(crate) bid_fee: Uint256,
+    /// transfer of the collateral to the bidder, empty when it is streamed
+    pub(crate) messages: Vec<CosmosMsg>,
+    pub(crate) streamed: bool,
+}
+
+/// Pays `amount` of the collateral at `price` with the bid, records the
+/// metrics of the collateral and transfers or streams it to the bidder
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn fill_bid<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+    bidder: &HumanAddr,
+    bidder_raw: &CanonicalAddr,
+    bid: Bid,
+    collateral_token: &HumanAddr,
+    collateral_token_raw: &CanonicalAddr,
+    amount: Uint256,
+    price: Decimal256,
+    premium_rate: Decimal256,
+) -> StdResult<BidFill> {
+    let config: Config = read_config(&deps.storage)?;
+    let collateral_value = amount * price;
+    let required_stable = collateral_value * (Decimal256::one() - premium_rate);
     if required_stable > bid.amount {
         return Err(StdError::generic_err(format!(
             "Insufficient bid balance; Required balance: {}",
@@ -164,12 +313,12 @@ pub fn execute_bid<S: Storage, A: Api, Q: Querier>(
 
     // Update bid
     if bid.amount == required_stable {
-        remove_bid(&mut deps.storage, &bidder_raw, &collateral_token_raw);
+        remove_bid(&mut deps.storage, bidder_raw, collateral_token_raw);
     } else {
         store_bid(
             &mut deps.storage,
-            &bidder_raw,
-            &collateral_token_raw,
+            bidder_raw,
+            collateral_token_raw,
             Bid {
                 amount: bid.amount - required_stable,
                 ..bid
@@ -177,56 +326,44 @@ pub fn execute_bid<S: Storage, A: Api, Q: Querier>(
         )?;
     }
 
+    let mut metrics: CollateralMetrics =
+        read_collateral_metrics(&deps.storage, collateral_token_raw);
+    metrics.executions += 1;
+    metrics.collateral_amount += amount;
+    metrics.collateral_value += collateral_value;
+    metrics.premium_value += collateral_value - required_stable;
+    store_collateral_metrics(&mut deps.storage, collateral_token_raw, &metrics)?;
+
     let bid_fee = required_stable * config.bid_fee;
     let repay_amount = required_stable - bid_fee;
 
-    let mut messages: Vec<CosmosMsg> = vec![
-        CosmosMsg::Wasm(WasmMsg::Execute {
+    // Large claims of thin collaterals are streamed
+    // to the bidder instead of sent at once
+    let streamed = stream_collateral(
+        &mut deps.storage,
+        block_height,
+        bidder_raw,
+        collateral_token_raw,
+        amount,
+    )?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !streamed {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: collateral_token.clone(),
             send: vec![],
             msg: to_binary(&Cw20HandleMsg::Transfer {
-                recipient: liquidator,
+                recipient: bidder.clone(),
                 amount: amount.into(),
             })?,
-        }),
-        CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address.clone(),
-            to_address: repay_address,
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: config.stable_denom.clone(),
-                    amount: repay_amount.into(),
-                },
-            )?],
-        }),
-    ];
-
-    if !bid_fee.is_zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address,
-            to_address: fee_address,
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: config.stable_denom.clone(),
-                    amount: bid_fee.into(),
-                },
-            )?],
         }));
     }
 
-    Ok(HandleResponse {
+    Ok(BidFill {
+        repay_amount,
+        bid_fee,
         messages,
-        log: vec![
-            log("action", "execute_bid"),
-            log("stable_denom", config.stable_denom),
-            log("repay_amount", repay_amount),
-            log("bid_fee", bid_fee),
-            log("collateral_token", collateral_token),
-            log("collateral_amount", amount),
-        ],
-        data: None,
+        streamed,
     })
 }
 
@@ -262,7 +399,7 @@ pub fn query_bids_by_user<S: Storage, A: Api, Q: Querier>(
     };
 
     let bids: Vec<BidResponse> = read_bids_by_user(
-        &deps,
+        deps,
         &deps.api.canonical_address(&bidder)?,
         start_after,
         limit,
@@ -284,7 +421,7 @@ pub fn query_bids_by_collateral<S: Storage, A: Api, Q: Querier>(
     };
 
     let bids: Vec<BidResponse> = read_bids_by_collateral(
-        &deps,
+        deps,
         &deps.api.canonical_address(&collateral_token)?,
         start_after,
         limit,
@@ -292,3 +429,34 @@ pub fn query_bids_by_collateral<S: Storage, A: Api, Q: Querier>(
 
     Ok(BidsResponse { bids })
 }
+
+pub fn query_metrics<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<MetricsResponse> {
+    let mut total_executions: u64 = 0;
+    let collaterals = read_all_collateral_metrics(&deps.storage)?
+        .into_iter()
+        .map(|(collateral_token, metrics)| {
+            total_executions += metrics.executions;
+            let average_premium_rate = if metrics.collateral_value.is_zero() {
+                Decimal256::zero()
+            } else {
+                Decimal256::from_uint256(metrics.premium_value)
+                    / Decimal256::from_uint256(metrics.collateral_value)
+            };
+
+            Ok(CollateralMetricsResponse {
+                collateral_token: deps.api.human_address(&collateral_token)?,
+                executions: metrics.executions,
+                collateral_amount: metrics.collateral_amount,
+                collateral_value: metrics.collateral_value,
+                average_premium_rate,
+            })
+        })
+        .collect::<StdResult<Vec<CollateralMetricsResponse>>>()?;
+
+    Ok(MetricsResponse {
+        total_executions,
+        collaterals,
+    })
+}
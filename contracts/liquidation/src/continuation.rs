@@ -0,0 +1,158 @@
+use crate::bid::{fill_bid, fillable_amount};
+use crate::liquidators::assert_liquidator;
+use crate::state::{
+    read_bid, read_config, read_continuation_ticket, read_continuation_tickets,
+    remove_continuation_ticket, store_continuation_ticket, Bid, Config, ContinuationTicket,
+};
+
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use moneymarket::liquidation::{ContinuationTicketResponse, ContinuationTicketsResponse};
+use moneymarket::market::HandleMsg as MarketHandleMsg;
+use moneymarket::querier::deduct_tax;
+
+/// Fills the collateral left in a continuation ticket with the bid of
+/// the sender. The price and the max premium rate locked at the first
+/// execution apply, so the oracle is not queried again; the proceeds
+/// repay the loan of the borrower
+pub fn resume_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    assert_liquidator(&deps.storage, &bidder_raw)?;
+
+    let mut ticket: ContinuationTicket = read_continuation_ticket(&deps.storage, ticket_id)?;
+    let bid: Bid = read_bid(&deps.storage, &bidder_raw, &ticket.collateral_token)?;
+
+    let premium_rate = std::cmp::min(bid.premium_rate, ticket.max_premium_rate);
+    let fill_amount = fillable_amount(&bid, ticket.price, premium_rate, ticket.amount);
+    if fill_amount.is_zero() {
+        return Err(StdError::generic_err(
+            "Insufficient bid balance to resume the liquidation",
+        ));
+    }
+
+    let collateral_token = deps.api.human_address(&ticket.collateral_token)?;
+    let fill = fill_bid(
+        deps,
+        env.block.height,
+        &env.message.sender,
+        &bidder_raw,
+        bid,
+        &collateral_token,
+        &ticket.collateral_token,
+        fill_amount,
+        ticket.price,
+        premium_rate,
+    )?;
+
+    ticket.amount = ticket.amount - fill_amount;
+    ticket.filled_amount += fill_amount;
+    if ticket.amount.is_zero() {
+        remove_continuation_ticket(&mut deps.storage, ticket_id);
+    } else {
+        store_continuation_ticket(&mut deps.storage, ticket_id, &ticket)?;
+    }
+
+    let borrower = deps.api.human_address(&ticket.borrower)?;
+    let mut messages: Vec<CosmosMsg> = fill.messages;
+    if !fill.repay_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&ticket.repay_address)?,
+            send: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: fill.repay_amount.into(),
+                },
+            )?],
+            msg: to_binary(&MarketHandleMsg::RepayStableFor {
+                borrower: borrower.clone(),
+            })?,
+        }));
+    }
+
+    if !fill.bid_fee.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: deps.api.human_address(&ticket.fee_address)?,
+            amount: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: fill.bid_fee.into(),
+                },
+            )?],
+        }));
+    }
+
+    let mut logs = vec![
+        log("action", "resume_liquidation"),
+        log("continuation_ticket_id", ticket_id),
+        log("bidder", env.message.sender),
+        log("borrower", borrower),
+        log("collateral_token", collateral_token),
+        log("collateral_amount", fill_amount),
+        log("remaining_amount", ticket.amount),
+        log("repay_amount", fill.repay_amount),
+        log("bid_fee", fill.bid_fee),
+    ];
+    if fill.streamed {
+        logs.push(log("streamed", true));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+fn to_ticket_response<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    ticket_id: u64,
+    ticket: ContinuationTicket,
+) -> StdResult<ContinuationTicketResponse> {
+    Ok(ContinuationTicketResponse {
+        ticket_id,
+        collateral_token: deps.api.human_address(&ticket.collateral_token)?,
+        borrower: deps.api.human_address(&ticket.borrower)?,
+        amount: ticket.amount,
+        filled_amount: ticket.filled_amount,
+        price: ticket.price,
+        max_premium_rate: ticket.max_premium_rate,
+        created_height: ticket.created_height,
+    })
+}
+
+pub fn query_continuation_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    ticket_id: u64,
+) -> StdResult<ContinuationTicketResponse> {
+    let ticket = read_continuation_ticket(&deps.storage, ticket_id)?;
+    to_ticket_response(deps, ticket_id, ticket)
+}
+
+pub fn query_continuation_tickets<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: Option<HumanAddr>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ContinuationTicketsResponse> {
+    let collateral_token = match collateral_token {
+        Some(v) => Some(deps.api.canonical_address(&v)?),
+        None => None,
+    };
+
+    let tickets = read_continuation_tickets(&deps.storage, collateral_token, start_after, limit)?
+        .into_iter()
+        .map(|(ticket_id, ticket)| to_ticket_response(deps, ticket_id, ticket))
+        .collect::<StdResult<Vec<ContinuationTicketResponse>>>()?;
+
+    Ok(ContinuationTicketsResponse { tickets })
+}
@@ -1,7 +1,23 @@
+use crate::auction::{
+    buy_auction_collateral, query_auction, query_auctions, query_liquidation_mechanism,
+    start_auction, update_liquidation_mechanism,
+};
 use crate::bid::{
-    execute_bid, query_bid, query_bids_by_collateral, query_bids_by_user, retract_bid, submit_bid,
+    execute_bid, query_bid, query_bids_by_collateral, query_bids_by_user, query_metrics,
+    retract_bid, submit_bid,
+};
+use crate::continuation::{
+    query_continuation_ticket, query_continuation_tickets, resume_liquidation,
+};
+use crate::liquidators::{
+    deregister_liquidators, query_liquidators, register_liquidators, update_liquidator_whitelist,
+};
+use crate::protocol_mode::assert_protocol_mode;
+use crate::state::{read_config, read_liquidation_mechanism, store_config, Config};
+use crate::stream::{
+    claim_streamed_collateral, query_collateral_stream, query_stream_config,
+    update_collateral_stream,
 };
-use crate::state::{read_config, store_config, Config};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
@@ -10,9 +26,12 @@ use cosmwasm_std::{
 };
 use cw20::Cw20ReceiveMsg;
 use moneymarket::liquidation::{
-    ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, LiquidationAmountResponse, QueryMsg,
+    ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, LiquidationAmountResponse,
+    LiquidationMechanism, QueryMsg,
 };
-use moneymarket::querier::query_tax_rate;
+use moneymarket::ownership::{accept_ownership, propose_new_owner, query_ownership_proposal};
+use moneymarket::protocol_mode::{refresh_protocol_mode, register_protocol_controller};
+use moneymarket::querier::{is_taxed_denom, query_tax_rate};
 use moneymarket::tokens::TokensHuman;
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
@@ -42,10 +61,10 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     env: Env,
     msg: HandleMsg,
 ) -> HandleResult {
+    assert_protocol_mode(deps, env.block.height, &msg)?;
     match msg {
         HandleMsg::Receive(msg) => receive_cw20(deps, env, msg),
         HandleMsg::UpdateConfig {
-            owner,
             oracle_contract,
             stable_denom,
             safe_ratio,
@@ -56,7 +75,6 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
         } => update_config(
             deps,
             env,
-            owner,
             oracle_contract,
             stable_denom,
             safe_ratio,
@@ -65,6 +83,18 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             liquidation_threshold,
             price_timeframe,
         ),
+        HandleMsg::ProposeNewOwner {
+            new_owner,
+            expiry_height,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_new_owner(deps, env, &config.owner, new_owner, expiry_height)
+        }
+        HandleMsg::AcceptOwnership {} => accept_ownership(deps, env, |storage, new_owner| {
+            let mut config: Config = read_config(storage)?;
+            config.owner = new_owner;
+            store_config(storage, &config)
+        }),
         HandleMsg::SubmitBid {
             collateral_token,
             premium_rate,
@@ -73,6 +103,39 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             collateral_token,
             amount,
         } => retract_bid(deps, env, collateral_token, amount),
+        HandleMsg::UpdateLiquidatorWhitelist { enabled } => {
+            update_liquidator_whitelist(deps, env, enabled)
+        }
+        HandleMsg::RegisterLiquidators { liquidators } => {
+            register_liquidators(deps, env, liquidators)
+        }
+        HandleMsg::DeregisterLiquidators { liquidators } => {
+            deregister_liquidators(deps, env, liquidators)
+        }
+        HandleMsg::RegisterProtocolController {
+            protocol_controller,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            register_protocol_controller(deps, env, &config.owner, protocol_controller)
+        }
+        HandleMsg::RefreshProtocolMode {} => refresh_protocol_mode(deps, env),
+        HandleMsg::UpdateCollateralStream {
+            collateral_token,
+            threshold,
+            stream_blocks,
+        } => update_collateral_stream(deps, env, collateral_token, threshold, stream_blocks),
+        HandleMsg::ClaimStreamedCollateral {
+            collateral_token,
+            bidder,
+        } => claim_streamed_collateral(deps, env, collateral_token, bidder),
+        HandleMsg::UpdateLiquidationMechanism {
+            collateral_token,
+            mechanism,
+        } => update_liquidation_mechanism(deps, env, collateral_token, mechanism),
+        HandleMsg::BuyAuctionCollateral { auction_id } => {
+            buy_auction_collateral(deps, env, auction_id)
+        }
+        HandleMsg::ResumeLiquidation { ticket_id } => resume_liquidation(deps, env, ticket_id),
     }
 }
 
@@ -88,17 +151,35 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
                 liquidator,
                 repay_address,
                 fee_address,
+                borrower,
             } => {
                 let collateral_token = contract_addr;
                 let repay_address = repay_address.unwrap_or_else(|| cw20_msg.sender.clone());
                 let fee_address = fee_address.unwrap_or_else(|| cw20_msg.sender.clone());
 
+                if let LiquidationMechanism::DutchAuction { .. } = read_liquidation_mechanism(
+                    &deps.storage,
+                    &deps.api.canonical_address(&collateral_token)?,
+                )? {
+                    return start_auction(
+                        deps,
+                        env,
+                        liquidator,
+                        repay_address,
+                        fee_address,
+                        borrower,
+                        collateral_token,
+                        cw20_msg.amount.into(),
+                    );
+                }
+
                 execute_bid(
                     deps,
                     env,
                     liquidator,
                     repay_address,
                     fee_address,
+                    borrower,
                     collateral_token,
                     cw20_msg.amount.into(),
                 )
@@ -115,7 +196,6 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    owner: Option<HumanAddr>,
     oracle_contract: Option<HumanAddr>,
     stable_denom: Option<String>,
     safe_ratio: Option<Decimal256>,
@@ -129,10 +209,6 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    if let Some(owner) = owner {
-        config.owner = deps.api.canonical_address(&owner)?;
-    }
-
     if let Some(oracle_contract) = oracle_contract {
         config.oracle_contract = deps.api.canonical_address(&oracle_contract)?;
     }
@@ -171,6 +247,7 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
         QueryMsg::LiquidationAmount {
             borrow_amount,
             borrow_limit,
@@ -202,6 +279,55 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             start_after,
             limit,
         )?),
+        QueryMsg::Metrics {} => to_binary(&query_metrics(deps)?),
+        QueryMsg::Liquidators { start_after, limit } => {
+            to_binary(&query_liquidators(deps, start_after, limit)?)
+        }
+        QueryMsg::StreamConfig { collateral_token } => {
+            to_binary(&query_stream_config(deps, collateral_token)?)
+        }
+        QueryMsg::CollateralStream {
+            collateral_token,
+            bidder,
+            block_height,
+        } => to_binary(&query_collateral_stream(
+            deps,
+            collateral_token,
+            bidder,
+            block_height,
+        )?),
+        QueryMsg::LiquidationMechanism { collateral_token } => {
+            to_binary(&query_liquidation_mechanism(deps, collateral_token)?)
+        }
+        QueryMsg::Auction {
+            auction_id,
+            block_height,
+        } => to_binary(&query_auction(deps, auction_id, block_height)?),
+        QueryMsg::Auctions {
+            collateral_token,
+            start_after,
+            limit,
+            block_height,
+        } => to_binary(&query_auctions(
+            deps,
+            collateral_token,
+            start_after,
+            limit,
+            block_height,
+        )?),
+        QueryMsg::ContinuationTicket { ticket_id } => {
+            to_binary(&query_continuation_ticket(deps, ticket_id)?)
+        }
+        QueryMsg::ContinuationTickets {
+            collateral_token,
+            start_after,
+            limit,
+        } => to_binary(&query_continuation_tickets(
+            deps,
+            collateral_token,
+            start_after,
+            limit,
+        )?),
     }
 }
 
@@ -246,7 +372,11 @@ fn query_liquidation_amount<S: Storage, A: Api, Q: Querier>(
         collaterals_value += collateral_value;
     }
 
-    let tax_rate = query_tax_rate(&deps)?;
+    let tax_rate = if is_taxed_denom(&config.stable_denom) {
+        query_tax_rate(deps)?
+    } else {
+        Decimal256::zero()
+    };
 
     let fee_deductor = (Decimal256::one() - config.max_premium_rate)
         * (Decimal256::one() - config.bid_fee)
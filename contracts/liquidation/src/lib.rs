@@ -1,6 +1,11 @@
+mod auction;
 mod bid;
+mod continuation;
 pub mod contract;
+mod liquidators;
+mod protocol_mode;
 pub mod state;
+mod stream;
 
 #[cfg(test)]
 mod testing;
@@ -0,0 +1,109 @@
+use crate::state::{
+    is_liquidator, read_config, read_liquidator_whitelist_enabled, read_liquidators,
+    remove_liquidator, store_liquidator, store_liquidator_whitelist_enabled, Config,
+};
+
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+use moneymarket::liquidation::LiquidatorsResponse;
+
+pub fn update_liquidator_whitelist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    enabled: bool,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    store_liquidator_whitelist_enabled(&mut deps.storage, enabled)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_liquidator_whitelist"),
+            log("enabled", enabled),
+        ],
+        data: None,
+    })
+}
+
+pub fn register_liquidators<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    liquidators: Vec<HumanAddr>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut logs = vec![log("action", "register_liquidators")];
+    for liquidator in liquidators {
+        store_liquidator(&mut deps.storage, &deps.api.canonical_address(&liquidator)?)?;
+        logs.push(log("liquidator", liquidator));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
+}
+
+pub fn deregister_liquidators<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    liquidators: Vec<HumanAddr>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut logs = vec![log("action", "deregister_liquidators")];
+    for liquidator in liquidators {
+        remove_liquidator(&mut deps.storage, &deps.api.canonical_address(&liquidator)?);
+        logs.push(log("liquidator", liquidator));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
+}
+
+/// Anyone may bid and liquidate unless the whitelist is enabled
+pub(crate) fn assert_liquidator<S: Storage>(
+    storage: &S,
+    liquidator: &CanonicalAddr,
+) -> StdResult<()> {
+    if read_liquidator_whitelist_enabled(storage)? && !is_liquidator(storage, liquidator)? {
+        return Err(StdError::generic_err(
+            "Liquidations are restricted to registered liquidators",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn query_liquidators<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<LiquidatorsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    Ok(LiquidatorsResponse {
+        whitelist_enabled: read_liquidator_whitelist_enabled(&deps.storage)?,
+        liquidators: read_liquidators(deps, start_after, limit)?,
+    })
+}
@@ -0,0 +1,27 @@
+use cosmwasm_std::{Api, Extern, Querier, StdResult, Storage};
+
+use moneymarket::liquidation::HandleMsg;
+use moneymarket::protocol_mode::load_protocol_mode;
+
+/// Rejects the user operations the protocol mode does not allow,
+/// bids are deposits while retracting, executing them, resuming
+/// liquidations and buying auctioned collateral are not
+pub(crate) fn assert_protocol_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+    msg: &HandleMsg,
+) -> StdResult<()> {
+    match msg {
+        HandleMsg::SubmitBid { .. } => {
+            load_protocol_mode(deps, block_height, false)?.assert_deposits_allowed()
+        }
+        HandleMsg::Receive(_)
+        | HandleMsg::RetractBid { .. }
+        | HandleMsg::ClaimStreamedCollateral { .. }
+        | HandleMsg::BuyAuctionCollateral { .. }
+        | HandleMsg::ResumeLiquidation { .. } => {
+            load_protocol_mode(deps, block_height, false)?.assert_withdrawals_allowed()
+        }
+        _ => Ok(()),
+    }
+}
@@ -7,13 +7,23 @@ use cosmwasm_std::{
     Storage,
 };
 use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
-use moneymarket::liquidation::BidResponse;
+use moneymarket::liquidation::{BidResponse, LiquidationMechanism};
 
 static KEY_CONFIG: &[u8] = b"config";
 
 static PREFIX_BID: &[u8] = b"bid";
 static PREFIX_BID_BY_USER: &[u8] = b"bid_by_user";
 static PREFIX_BID_BY_COLLATERAL: &[u8] = b"bid_by_collateral";
+static PREFIX_COLLATERAL_METRICS: &[u8] = b"collateral_metrics";
+static KEY_LIQUIDATOR_WHITELIST_ENABLED: &[u8] = b"liquidator_whitelist_enabled";
+static PREFIX_LIQUIDATOR: &[u8] = b"liquidator";
+static PREFIX_STREAM_CONFIG: &[u8] = b"stream_config";
+static PREFIX_COLLATERAL_STREAM: &[u8] = b"collateral_stream";
+static PREFIX_LIQUIDATION_MECHANISM: &[u8] = b"liquidation_mechanism";
+static PREFIX_AUCTION: &[u8] = b"auction";
+static KEY_AUCTION_COUNT: &[u8] = b"auction_count";
+static PREFIX_CONTINUATION_TICKET: &[u8] = b"continuation_ticket";
+static KEY_CONTINUATION_TICKET_COUNT: &[u8] = b"continuation_ticket_count";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -97,6 +107,303 @@ pub fn read_bid<'a, S: Storage>(
         .map_err(|_| StdError::generic_err("No bids with the specified information exist"))
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralMetrics {
+    pub executions: u64,
+    pub collateral_amount: Uint256,
+    /// oracle value of the liquidated collateral
+    pub collateral_value: Uint256,
+    /// collateral value kept by the bidders as premium
+    pub premium_value: Uint256,
+}
+
+pub fn store_collateral_metrics<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    metrics: &CollateralMetrics,
+) -> StdResult<()> {
+    let mut metrics_bucket: Bucket<S, CollateralMetrics> =
+        Bucket::new(PREFIX_COLLATERAL_METRICS, storage);
+    metrics_bucket.save(collateral_token.as_slice(), metrics)
+}
+
+pub fn read_collateral_metrics<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> CollateralMetrics {
+    let metrics_bucket: ReadonlyBucket<S, CollateralMetrics> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL_METRICS, storage);
+    match metrics_bucket.load(collateral_token.as_slice()) {
+        Ok(v) => v,
+        _ => CollateralMetrics {
+            executions: 0,
+            collateral_amount: Uint256::zero(),
+            collateral_value: Uint256::zero(),
+            premium_value: Uint256::zero(),
+        },
+    }
+}
+
+pub fn read_all_collateral_metrics<S: Storage>(
+    storage: &S,
+) -> StdResult<Vec<(CanonicalAddr, CollateralMetrics)>> {
+    let metrics_bucket: ReadonlyBucket<S, CollateralMetrics> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL_METRICS, storage);
+    metrics_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_liquidator_whitelist_enabled<S: Storage>(
+    storage: &mut S,
+    enabled: bool,
+) -> StdResult<()> {
+    singleton(storage, KEY_LIQUIDATOR_WHITELIST_ENABLED).save(&enabled)
+}
+
+pub fn read_liquidator_whitelist_enabled<S: ReadonlyStorage>(storage: &S) -> StdResult<bool> {
+    Ok(singleton_read(storage, KEY_LIQUIDATOR_WHITELIST_ENABLED)
+        .may_load()?
+        .unwrap_or(false))
+}
+
+pub fn store_liquidator<S: Storage>(storage: &mut S, liquidator: &CanonicalAddr) -> StdResult<()> {
+    let mut liquidator_bucket: Bucket<S, bool> = Bucket::new(PREFIX_LIQUIDATOR, storage);
+    liquidator_bucket.save(liquidator.as_slice(), &true)
+}
+
+pub fn remove_liquidator<S: Storage>(storage: &mut S, liquidator: &CanonicalAddr) {
+    let mut liquidator_bucket: Bucket<S, bool> = Bucket::new(PREFIX_LIQUIDATOR, storage);
+    liquidator_bucket.remove(liquidator.as_slice());
+}
+
+pub fn is_liquidator<S: ReadonlyStorage>(
+    storage: &S,
+    liquidator: &CanonicalAddr,
+) -> StdResult<bool> {
+    let liquidator_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATOR, storage);
+    Ok(liquidator_bucket.may_load(liquidator.as_slice())?.is_some())
+}
+
+/// Collateral claims above `threshold` are streamed
+/// to the bidder over `stream_blocks` blocks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StreamConfig {
+    pub threshold: Uint256,
+    pub stream_blocks: u64,
+}
+
+pub fn store_stream_config<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    stream_config: &StreamConfig,
+) -> StdResult<()> {
+    let mut stream_config_bucket: Bucket<S, StreamConfig> =
+        Bucket::new(PREFIX_STREAM_CONFIG, storage);
+    stream_config_bucket.save(collateral_token.as_slice(), stream_config)
+}
+
+pub fn remove_stream_config<S: Storage>(storage: &mut S, collateral_token: &CanonicalAddr) {
+    let mut stream_config_bucket: Bucket<S, StreamConfig> =
+        Bucket::new(PREFIX_STREAM_CONFIG, storage);
+    stream_config_bucket.remove(collateral_token.as_slice());
+}
+
+pub fn read_stream_config<S: ReadonlyStorage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<StreamConfig>> {
+    let stream_config_bucket: ReadonlyBucket<S, StreamConfig> =
+        ReadonlyBucket::new(PREFIX_STREAM_CONFIG, storage);
+    stream_config_bucket.may_load(collateral_token.as_slice())
+}
+
+/// Collateral streamed to a bidder; `locked` is released
+/// linearly from `last_height` until `end_height`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralStream {
+    pub released: Uint256,
+    pub locked: Uint256,
+    pub last_height: u64,
+    pub end_height: u64,
+}
+
+pub fn store_collateral_stream<S: Storage>(
+    storage: &mut S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+    stream: &CollateralStream,
+) -> StdResult<()> {
+    let mut stream_bucket: Bucket<S, CollateralStream> =
+        Bucket::new(PREFIX_COLLATERAL_STREAM, storage);
+    stream_bucket.save(
+        &[bidder.as_slice(), collateral_token.as_slice()].concat(),
+        stream,
+    )
+}
+
+pub fn remove_collateral_stream<S: Storage>(
+    storage: &mut S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+) {
+    let mut stream_bucket: Bucket<S, CollateralStream> =
+        Bucket::new(PREFIX_COLLATERAL_STREAM, storage);
+    stream_bucket.remove(&[bidder.as_slice(), collateral_token.as_slice()].concat());
+}
+
+pub fn read_collateral_stream<S: ReadonlyStorage>(
+    storage: &S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<CollateralStream>> {
+    let stream_bucket: ReadonlyBucket<S, CollateralStream> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL_STREAM, storage);
+    stream_bucket.may_load(&[bidder.as_slice(), collateral_token.as_slice()].concat())
+}
+
+pub fn store_liquidation_mechanism<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    mechanism: &LiquidationMechanism,
+) -> StdResult<()> {
+    let mut mechanism_bucket: Bucket<S, LiquidationMechanism> =
+        Bucket::new(PREFIX_LIQUIDATION_MECHANISM, storage);
+    mechanism_bucket.save(collateral_token.as_slice(), mechanism)
+}
+
+pub fn remove_liquidation_mechanism<S: Storage>(storage: &mut S, collateral_token: &CanonicalAddr) {
+    let mut mechanism_bucket: Bucket<S, LiquidationMechanism> =
+        Bucket::new(PREFIX_LIQUIDATION_MECHANISM, storage);
+    mechanism_bucket.remove(collateral_token.as_slice());
+}
+
+/// Collaterals without a stored mechanism are executed against the bids
+pub fn read_liquidation_mechanism<S: ReadonlyStorage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<LiquidationMechanism> {
+    let mechanism_bucket: ReadonlyBucket<S, LiquidationMechanism> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATION_MECHANISM, storage);
+    Ok(mechanism_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or(LiquidationMechanism::Bid {}))
+}
+
+/// Dutch auction of the collateral seized from `borrower`,
+/// with the auction parameters at its start
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Auction {
+    pub collateral_token: CanonicalAddr,
+    pub borrower: CanonicalAddr,
+    pub repay_address: CanonicalAddr,
+    pub fee_address: CanonicalAddr,
+    /// collateral left to sell
+    pub amount: Uint256,
+    pub sold_amount: Uint256,
+    pub raised_amount: Uint256,
+    pub start_height: u64,
+    pub start_premium: Decimal256,
+    pub decay_rate: Decimal256,
+    pub floor: Decimal256,
+}
+
+/// Stores a new auction and returns its id
+pub fn push_auction<S: Storage>(storage: &mut S, auction: &Auction) -> StdResult<u64> {
+    let auction_id: u64 = singleton_read(storage, KEY_AUCTION_COUNT)
+        .may_load()?
+        .unwrap_or(0)
+        + 1;
+    singleton(storage, KEY_AUCTION_COUNT).save(&auction_id)?;
+    store_auction(storage, auction_id, auction)?;
+
+    Ok(auction_id)
+}
+
+pub fn store_auction<S: Storage>(
+    storage: &mut S,
+    auction_id: u64,
+    auction: &Auction,
+) -> StdResult<()> {
+    let mut auction_bucket: Bucket<S, Auction> = Bucket::new(PREFIX_AUCTION, storage);
+    auction_bucket.save(&auction_id.to_be_bytes(), auction)
+}
+
+pub fn remove_auction<S: Storage>(storage: &mut S, auction_id: u64) {
+    let mut auction_bucket: Bucket<S, Auction> = Bucket::new(PREFIX_AUCTION, storage);
+    auction_bucket.remove(&auction_id.to_be_bytes());
+}
+
+pub fn read_auction<S: ReadonlyStorage>(storage: &S, auction_id: u64) -> StdResult<Auction> {
+    let auction_bucket: ReadonlyBucket<S, Auction> = ReadonlyBucket::new(PREFIX_AUCTION, storage);
+    auction_bucket
+        .load(&auction_id.to_be_bytes())
+        .map_err(|_| StdError::generic_err(format!("Auction not found: {}", auction_id)))
+}
+
+/// Collateral of a liquidation its bid only partially filled, resumed
+/// at the oracle price and the max premium rate of the first execution
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContinuationTicket {
+    pub collateral_token: CanonicalAddr,
+    pub borrower: CanonicalAddr,
+    pub repay_address: CanonicalAddr,
+    pub fee_address: CanonicalAddr,
+    /// collateral left to fill
+    pub amount: Uint256,
+    pub filled_amount: Uint256,
+    pub price: Decimal256,
+    pub max_premium_rate: Decimal256,
+    pub created_height: u64,
+}
+
+/// Stores a new continuation ticket and returns its id
+pub fn push_continuation_ticket<S: Storage>(
+    storage: &mut S,
+    ticket: &ContinuationTicket,
+) -> StdResult<u64> {
+    let ticket_id: u64 = singleton_read(storage, KEY_CONTINUATION_TICKET_COUNT)
+        .may_load()?
+        .unwrap_or(0)
+        + 1;
+    singleton(storage, KEY_CONTINUATION_TICKET_COUNT).save(&ticket_id)?;
+    store_continuation_ticket(storage, ticket_id, ticket)?;
+
+    Ok(ticket_id)
+}
+
+pub fn store_continuation_ticket<S: Storage>(
+    storage: &mut S,
+    ticket_id: u64,
+    ticket: &ContinuationTicket,
+) -> StdResult<()> {
+    let mut ticket_bucket: Bucket<S, ContinuationTicket> =
+        Bucket::new(PREFIX_CONTINUATION_TICKET, storage);
+    ticket_bucket.save(&ticket_id.to_be_bytes(), ticket)
+}
+
+pub fn remove_continuation_ticket<S: Storage>(storage: &mut S, ticket_id: u64) {
+    let mut ticket_bucket: Bucket<S, ContinuationTicket> =
+        Bucket::new(PREFIX_CONTINUATION_TICKET, storage);
+    ticket_bucket.remove(&ticket_id.to_be_bytes());
+}
+
+pub fn read_continuation_ticket<S: ReadonlyStorage>(
+    storage: &S,
+    ticket_id: u64,
+) -> StdResult<ContinuationTicket> {
+    let ticket_bucket: ReadonlyBucket<S, ContinuationTicket> =
+        ReadonlyBucket::new(PREFIX_CONTINUATION_TICKET, storage);
+    ticket_bucket
+        .load(&ticket_id.to_be_bytes())
+        .map_err(|_| StdError::generic_err(format!("Continuation ticket not found: {}", ticket_id)))
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -120,10 +427,10 @@ pub fn read_bids_by_collateral<S: Storage, A: Api, Q: Querier>(
         .map(|elem| {
             let (k, _) = elem?;
             let bidder = CanonicalAddr::from(k);
-            let bid = read_bid(&deps.storage, &bidder, &collateral_token)?;
+            let bid = read_bid(&deps.storage, &bidder, collateral_token)?;
 
             let bidder: HumanAddr = deps.api.human_address(&bidder)?;
-            let collateral_token: HumanAddr = deps.api.human_address(&collateral_token)?;
+            let collateral_token: HumanAddr = deps.api.human_address(collateral_token)?;
             let amount = bid.amount;
             let premium_rate = bid.premium_rate;
 
@@ -155,10 +462,10 @@ pub fn read_bids_by_user<S: Storage, A: Api, Q: Querier>(
         .map(|elem| {
             let (k, _) = elem?;
             let collateral_token = CanonicalAddr::from(k);
-            let bid = read_bid(&deps.storage, &bidder, &collateral_token)?;
+            let bid = read_bid(&deps.storage, bidder, &collateral_token)?;
 
             let collateral_token: HumanAddr = deps.api.human_address(&collateral_token)?;
-            let bidder: HumanAddr = deps.api.human_address(&bidder)?;
+            let bidder: HumanAddr = deps.api.human_address(bidder)?;
             let amount = bid.amount;
             let premium_rate = bid.premium_rate;
 
@@ -172,6 +479,89 @@ pub fn read_bids_by_user<S: Storage, A: Api, Q: Querier>(
         .collect()
 }
 
+pub fn read_liquidators<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<HumanAddr>> {
+    let liquidator_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATOR, &deps.storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    liquidator_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, _) = elem?;
+            deps.api.human_address(&CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
+/// Running auctions after `start_after`, of `collateral_token` when given
+pub fn read_auctions<S: ReadonlyStorage>(
+    storage: &S,
+    collateral_token: Option<CanonicalAddr>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, Auction)>> {
+    let auction_bucket: ReadonlyBucket<S, Auction> = ReadonlyBucket::new(PREFIX_AUCTION, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|v| (v + 1).to_be_bytes().to_vec());
+
+    auction_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut auction_id = [0u8; 8];
+            auction_id.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(auction_id), v))
+        })
+        .filter(
+            |elem: &StdResult<(u64, Auction)>| match (elem, &collateral_token) {
+                (Ok((_, v)), Some(collateral_token)) => v.collateral_token == *collateral_token,
+                _ => true,
+            },
+        )
+        .take(limit)
+        .collect()
+}
+
+/// Open continuation tickets after `start_after`,
+/// of `collateral_token` when given
+pub fn read_continuation_tickets<S: ReadonlyStorage>(
+    storage: &S,
+    collateral_token: Option<CanonicalAddr>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, ContinuationTicket)>> {
+    let ticket_bucket: ReadonlyBucket<S, ContinuationTicket> =
+        ReadonlyBucket::new(PREFIX_CONTINUATION_TICKET, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|v| (v + 1).to_be_bytes().to_vec());
+
+    ticket_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut ticket_id = [0u8; 8];
+            ticket_id.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(ticket_id), v))
+        })
+        .filter(
+            |elem: &StdResult<(u64, ContinuationTicket)>| match (elem, &collateral_token) {
+                (Ok((_, v)), Some(collateral_token)) => v.collateral_token == *collateral_token,
+                _ => true,
+            },
+        )
+        .take(limit)
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
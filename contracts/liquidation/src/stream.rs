@@ -0,0 +1,227 @@
+use crate::state::{
+    read_collateral_stream, read_config, read_stream_config, remove_collateral_stream,
+    remove_stream_config, store_collateral_stream, store_stream_config, CollateralStream, Config,
+    StreamConfig,
+};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+use moneymarket::liquidation::{CollateralStreamResponse, StreamConfigResponse};
+
+pub fn update_collateral_stream<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    threshold: Uint256,
+    stream_blocks: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    // the streams already started keep their end height
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    if stream_blocks == 0 {
+        remove_stream_config(&mut deps.storage, &collateral_token_raw);
+    } else {
+        store_stream_config(
+            &mut deps.storage,
+            &collateral_token_raw,
+            &StreamConfig {
+                threshold,
+                stream_blocks,
+            },
+        )?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_collateral_stream"),
+            log("collateral_token", collateral_token),
+            log("threshold", threshold),
+            log("stream_blocks", stream_blocks),
+        ],
+        data: None,
+    })
+}
+
+impl CollateralStream {
+    /// Moves the collateral released until `block_height` out of `locked`
+    fn release(&mut self, block_height: u64) {
+        if block_height >= self.end_height {
+            self.released += self.locked;
+            self.locked = Uint256::zero();
+        } else if block_height > self.last_height {
+            let released = self.locked.multiply_ratio(
+                block_height - self.last_height,
+                self.end_height - self.last_height,
+            );
+            self.released += released;
+            self.locked = self.locked - released;
+        }
+
+        self.last_height = std::cmp::max(self.last_height, block_height);
+    }
+}
+
+/// Locks the `amount` claimed by `bidder` in its stream when the
+/// collateral streams claims that large. The still locked collateral
+/// is streamed again along with it, until `stream_blocks` from now.
+/// Returns false when the amount is to be sent at once
+pub(crate) fn stream_collateral<S: Storage>(
+    storage: &mut S,
+    block_height: u64,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+    amount: Uint256,
+) -> StdResult<bool> {
+    let stream_config = match read_stream_config(storage, collateral_token)? {
+        Some(v) if amount > v.threshold => v,
+        _ => return Ok(false),
+    };
+
+    let mut stream = match read_collateral_stream(storage, bidder, collateral_token)? {
+        Some(v) => v,
+        None => CollateralStream {
+            released: Uint256::zero(),
+            locked: Uint256::zero(),
+            last_height: block_height,
+            end_height: block_height,
+        },
+    };
+
+    stream.release(block_height);
+    stream.locked += amount;
+    stream.end_height = block_height + stream_config.stream_blocks;
+    store_collateral_stream(storage, bidder, collateral_token, &stream)?;
+
+    Ok(true)
+}
+
+/// Anyone can claim on behalf of a bidder,
+/// the collateral always goes to the bidder
+pub fn claim_streamed_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    bidder: Option<HumanAddr>,
+) -> HandleResult {
+    let bidder = bidder.unwrap_or_else(|| env.message.sender.clone());
+    let bidder_raw = deps.api.canonical_address(&bidder)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+
+    let mut stream =
+        match read_collateral_stream(&deps.storage, &bidder_raw, &collateral_token_raw)? {
+            Some(v) => v,
+            None => {
+                return Err(StdError::generic_err(
+                    "No streamed collateral with the specified information exists",
+                ))
+            }
+        };
+
+    stream.release(env.block.height);
+    let claim_amount = stream.released;
+    if claim_amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "No streamed collateral released yet; Stream ends at height: {}",
+            stream.end_height
+        )));
+    }
+
+    if stream.locked.is_zero() {
+        remove_collateral_stream(&mut deps.storage, &bidder_raw, &collateral_token_raw);
+    } else {
+        stream.released = Uint256::zero();
+        store_collateral_stream(
+            &mut deps.storage,
+            &bidder_raw,
+            &collateral_token_raw,
+            &stream,
+        )?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: collateral_token.clone(),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: bidder.clone(),
+                amount: claim_amount.into(),
+            })?,
+        })],
+        log: vec![
+            log("action", "claim_streamed_collateral"),
+            log("collateral_token", collateral_token),
+            log("bidder", bidder),
+            log("claim_amount", claim_amount),
+            log("locked_amount", stream.locked),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_stream_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<StreamConfigResponse> {
+    let stream_config = read_stream_config(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    Ok(match stream_config {
+        Some(v) => StreamConfigResponse {
+            collateral_token,
+            threshold: v.threshold,
+            stream_blocks: v.stream_blocks,
+        },
+        None => StreamConfigResponse {
+            collateral_token,
+            threshold: Uint256::zero(),
+            stream_blocks: 0,
+        },
+    })
+}
+
+pub fn query_collateral_stream<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    bidder: HumanAddr,
+    block_height: Option<u64>,
+) -> StdResult<CollateralStreamResponse> {
+    let stream = read_collateral_stream(
+        &deps.storage,
+        &deps.api.canonical_address(&bidder)?,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    Ok(match stream {
+        Some(mut v) => {
+            if let Some(block_height) = block_height {
+                v.release(block_height);
+            }
+
+            CollateralStreamResponse {
+                collateral_token,
+                bidder,
+                claimable: v.released,
+                locked: v.locked,
+                end_height: v.end_height,
+            }
+        }
+        None => CollateralStreamResponse {
+            collateral_token,
+            bidder,
+            claimable: Uint256::zero(),
+            locked: Uint256::zero(),
+            end_height: 0,
+        },
+    })
+}
@@ -75,6 +75,7 @@ pub struct OraclePriceQuerier {
 }
 
 impl OraclePriceQuerier {
+    #[allow(clippy::type_complexity)]
     pub fn new(oracle_price: &[(&(String, String), &(Decimal256, u64, u64))]) -> Self {
         OraclePriceQuerier {
             oracle_price: oracle_price_to_map(oracle_price),
@@ -82,6 +83,7 @@ impl OraclePriceQuerier {
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub(crate) fn oracle_price_to_map(
     oracle_price: &[(&(String, String), &(Decimal256, u64, u64))],
 ) -> HashMap<(String, String), (Decimal256, u64, u64)> {
@@ -140,7 +142,7 @@ impl WasmMockQuerier {
             QueryRequest::Wasm(WasmQuery::Smart {
                 contract_addr: _,
                 msg,
-            }) => match from_binary(&msg).unwrap() {
+            }) => match from_binary(msg).unwrap() {
                 QueryMsg::Price { base, quote } => {
                     match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
                         Some(v) => Ok(to_binary(&PriceResponse {
@@ -174,6 +176,7 @@ impl WasmMockQuerier {
         self.tax_querier = TaxQuerier::new(rate, caps);
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn with_oracle_price(
         &mut self,
         oracle_price: &[(&(String, String), &(Decimal256, u64, u64))],
@@ -9,9 +9,13 @@ use cosmwasm_std::{
 };
 use cw20::{Cw20HandleMsg, Cw20ReceiveMsg};
 use moneymarket::liquidation::{
-    BidResponse, BidsResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg,
-    LiquidationAmountResponse, QueryMsg,
+    AuctionResponse, AuctionsResponse, BidResponse, BidsResponse, CollateralMetricsResponse,
+    CollateralStreamResponse, ConfigResponse, ContinuationTicketResponse,
+    ContinuationTicketsResponse, Cw20HookMsg, HandleMsg, InitMsg, LiquidationAmountResponse,
+    LiquidationMechanism, LiquidationMechanismResponse, LiquidatorsResponse, MetricsResponse,
+    QueryMsg, StreamConfigResponse,
 };
+use moneymarket::market::HandleMsg as MarketHandleMsg;
 
 #[test]
 fn proper_initialization() {
@@ -69,20 +73,23 @@ fn update_config() {
     let env = mock_env("addr0000", &[]);
     let _res = init(&mut deps, env, msg).unwrap();
 
-    // update owner
+    // propose a new owner
     let env = mock_env("owner0000", &[]);
-    let msg = HandleMsg::UpdateConfig {
-        owner: Some(HumanAddr("owner0001".to_string())),
-        oracle_contract: None,
-        stable_denom: None,
-        safe_ratio: None,
-        bid_fee: None,
-        max_premium_rate: None,
-        liquidation_threshold: None,
-        price_timeframe: None,
+    let msg = HandleMsg::ProposeNewOwner {
+        new_owner: HumanAddr::from("owner0001"),
+        expiry_height: 12355u64,
     };
+    handle(&mut deps, env, msg).unwrap();
 
-    let res = handle(&mut deps, env, msg).unwrap();
+    // only the proposed owner can accept
+    let env = mock_env("owner0000", &[]);
+    match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner0001", &[]);
+    let res = handle(&mut deps, env, HandleMsg::AcceptOwnership {}).unwrap();
     assert_eq!(0, res.messages.len());
 
     // it worked, let's query the state
@@ -104,7 +111,6 @@ fn update_config() {
     // Update left items
     let env = mock_env("owner0001", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some(HumanAddr::from("oracle0001")),
         stable_denom: Some("ukrw".to_string()),
         safe_ratio: Some(Decimal256::percent(15)),
@@ -136,7 +142,6 @@ fn update_config() {
     // Unauthorized err
     let env = mock_env("owner0000", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some(HumanAddr::from("oracle0001")),
         stable_denom: Some("ukrw".to_string()),
         safe_ratio: Some(Decimal256::percent(1)),
@@ -352,6 +357,7 @@ fn execute_bid() {
                 liquidator: HumanAddr::from("addr0000"),
                 fee_address: Some(HumanAddr::from("fee0000")),
                 repay_address: Some(HumanAddr::from("repay0000")),
+                borrower: None,
             })
             .unwrap(),
         ),
@@ -375,6 +381,7 @@ fn execute_bid() {
                 liquidator: HumanAddr::from("addr0000"),
                 fee_address: Some(HumanAddr::from("fee0000")),
                 repay_address: Some(HumanAddr::from("repay0000")),
+                borrower: None,
             })
             .unwrap(),
         ),
@@ -419,6 +426,7 @@ fn execute_bid() {
                 liquidator: HumanAddr::from("addr0000"),
                 fee_address: None,
                 repay_address: None,
+                borrower: None,
             })
             .unwrap(),
         ),
@@ -454,6 +462,21 @@ fn execute_bid() {
             }),
         ]
     );
+
+    let res: MetricsResponse = from_binary(&query(&deps, QueryMsg::Metrics {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        MetricsResponse {
+            total_executions: 2,
+            collaterals: vec![CollateralMetricsResponse {
+                collateral_token: HumanAddr::from("asset0000"),
+                executions: 2,
+                collateral_amount: Uint256::from(2000000u64),
+                collateral_value: Uint256::from(1000000u64),
+                average_premium_rate: Decimal256::percent(1),
+            }],
+        }
+    );
 }
 
 #[test]
@@ -487,7 +510,7 @@ fn query_liquidation_amount() {
         collateral_prices: vec![Decimal256::percent(10)],
     };
 
-    let res = query(&mut deps, msg).unwrap();
+    let res = query(&deps, msg).unwrap();
     let res: LiquidationAmountResponse = from_binary(&res).unwrap();
     assert_eq!(
         res,
@@ -503,7 +526,7 @@ fn query_liquidation_amount() {
         collateral_prices: vec![Decimal256::one()],
     };
 
-    let res = query(&mut deps, msg).unwrap();
+    let res = query(&deps, msg).unwrap();
     let res: LiquidationAmountResponse = from_binary(&res).unwrap();
     assert_eq!(
         res,
@@ -529,7 +552,7 @@ fn query_liquidation_amount() {
 
     // fee_deductor = 0.931095
     // liquidation_ratio = 0.3580014213
-    let res = query(&mut deps, query_msg.clone()).unwrap();
+    let res = query(&deps, query_msg.clone()).unwrap();
     let res: LiquidationAmountResponse = from_binary(&res).unwrap();
     assert_eq!(
         res,
@@ -847,3 +870,962 @@ fn query_bids_by_collateral() {
         }
     );
 }
+
+#[test]
+fn liquidator_whitelist() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 60u64,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    // permissionless by default
+    let res: LiquidatorsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Liquidators {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LiquidatorsResponse {
+            whitelist_enabled: false,
+            liquidators: vec![],
+        }
+    );
+
+    let bid_msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+    };
+    let bid_env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, bid_env, bid_msg.clone()).unwrap();
+
+    let msg = HandleMsg::UpdateLiquidatorWhitelist { enabled: true };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterLiquidators {
+        liquidators: vec![HumanAddr::from("addr0001")],
+    };
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    // unregistered addresses can no longer bid
+    let bid_env = mock_env(
+        "addr0002",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = handle(&mut deps, bid_env, bid_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Liquidations are restricted to registered liquidators")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let bid_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, bid_env, bid_msg).unwrap();
+
+    // bids placed before the whitelist was enabled cannot be executed
+    let execute_msg = |liquidator: &str| {
+        HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("overseer0000"),
+            amount: Uint128::from(1000000u128),
+            msg: Some(
+                to_binary(&Cw20HookMsg::ExecuteBid {
+                    liquidator: HumanAddr::from(liquidator),
+                    fee_address: None,
+                    repay_address: None,
+                    borrower: None,
+                })
+                .unwrap(),
+            ),
+        })
+    };
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env.clone(), execute_msg("addr0000"));
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Liquidations are restricted to registered liquidators")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    handle(&mut deps, env.clone(), execute_msg("addr0001")).unwrap();
+
+    let res: LiquidatorsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Liquidators {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LiquidatorsResponse {
+            whitelist_enabled: true,
+            liquidators: vec![HumanAddr::from("addr0001")],
+        }
+    );
+
+    let msg = HandleMsg::DeregisterLiquidators {
+        liquidators: vec![HumanAddr::from("addr0001")],
+    };
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env, execute_msg("addr0001"));
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Liquidations are restricted to registered liquidators")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // disabling the whitelist restores permissionless liquidations
+    let msg = HandleMsg::UpdateLiquidatorWhitelist { enabled: false };
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("asset0000", &[]);
+    handle(&mut deps, env, execute_msg("addr0000")).unwrap();
+}
+
+#[test]
+fn streamed_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 60u64,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::UpdateCollateralStream {
+        collateral_token: HumanAddr::from("asset0000"),
+        threshold: Uint256::from(1000000u64),
+        stream_blocks: 100,
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let res: StreamConfigResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::StreamConfig {
+                collateral_token: HumanAddr::from("asset0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        StreamConfigResponse {
+            collateral_token: HumanAddr::from("asset0000"),
+            threshold: Uint256::from(1000000u64),
+            stream_blocks: 100,
+        }
+    );
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::zero(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let execute_msg = |amount: u128| {
+        HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("overseer0000"),
+            amount: Uint128::from(amount),
+            msg: Some(
+                to_binary(&Cw20HookMsg::ExecuteBid {
+                    liquidator: HumanAddr::from("addr0000"),
+                    fee_address: None,
+                    repay_address: None,
+                    borrower: None,
+                })
+                .unwrap(),
+            ),
+        })
+    };
+
+    // claims up to the threshold are sent at once
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env.clone(), execute_msg(1000000)).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("asset0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(1000000u128),
+            })
+            .unwrap(),
+        })
+    );
+
+    // larger claims only pay the repay amount
+    let res = handle(&mut deps, env.clone(), execute_msg(4000000)).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("overseer0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(2000000u128),
+            }]
+        })]
+    );
+
+    let stream_query = |block_height: Option<u64>| QueryMsg::CollateralStream {
+        collateral_token: HumanAddr::from("asset0000"),
+        bidder: HumanAddr::from("addr0000"),
+        block_height,
+    };
+    let res: CollateralStreamResponse =
+        from_binary(&query(&deps, stream_query(Some(env.block.height + 25))).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        CollateralStreamResponse {
+            collateral_token: HumanAddr::from("asset0000"),
+            bidder: HumanAddr::from("addr0000"),
+            claimable: Uint256::from(1000000u64),
+            locked: Uint256::from(3000000u64),
+            end_height: env.block.height + 100,
+        }
+    );
+
+    let msg = HandleMsg::ClaimStreamedCollateral {
+        collateral_token: HumanAddr::from("asset0000"),
+        bidder: None,
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "No streamed collateral released yet; Stream ends at height: {}",
+                env.block.height + 100
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a quarter of the stream is released after 25 blocks
+    let mut claim_env = env.clone();
+    claim_env.block.height += 25;
+    let res = handle(&mut deps, claim_env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("asset0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(1000000u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // a new claim streams the locked collateral again along with it
+    let mut execute_env = mock_env("asset0000", &[]);
+    execute_env.block.height = claim_env.block.height + 25;
+    handle(&mut deps, execute_env.clone(), execute_msg(2000000)).unwrap();
+
+    let res: CollateralStreamResponse =
+        from_binary(&query(&deps, stream_query(None)).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        CollateralStreamResponse {
+            collateral_token: HumanAddr::from("asset0000"),
+            bidder: HumanAddr::from("addr0000"),
+            claimable: Uint256::from(1000000u64),
+            locked: Uint256::from(4000000u64),
+            end_height: execute_env.block.height + 100,
+        }
+    );
+
+    // anyone can claim for the bidder once the stream ended
+    let msg = HandleMsg::ClaimStreamedCollateral {
+        collateral_token: HumanAddr::from("asset0000"),
+        bidder: Some(HumanAddr::from("addr0000")),
+    };
+    let mut claim_env = mock_env("addr0001", &[]);
+    claim_env.block.height = execute_env.block.height + 100;
+    let res = handle(&mut deps, claim_env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("asset0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(5000000u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let res: CollateralStreamResponse =
+        from_binary(&query(&deps, stream_query(None)).unwrap()).unwrap();
+    assert_eq!(res.claimable, Uint256::zero());
+    assert_eq!(res.locked, Uint256::zero());
+}
+
+#[test]
+fn dutch_auction() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 60u64,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[
+        (
+            &("asset0000".to_string(), "uusd".to_string()),
+            &(Decimal256::percent(50), env.block.time, env.block.time),
+        ),
+        (
+            &("asset0001".to_string(), "uusd".to_string()),
+            &(Decimal256::percent(50), env.block.time, env.block.time),
+        ),
+    ]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let mechanism = LiquidationMechanism::DutchAuction {
+        start_premium: Decimal256::percent(10),
+        decay_rate: Decimal256::percent(1),
+        floor: Decimal256::percent(95),
+    };
+    let msg = HandleMsg::UpdateLiquidationMechanism {
+        collateral_token: HumanAddr::from("asset0000"),
+        mechanism: mechanism.clone(),
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner0000", &[]);
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::UpdateLiquidationMechanism {
+            collateral_token: HumanAddr::from("asset0000"),
+            mechanism: LiquidationMechanism::DutchAuction {
+                start_premium: Decimal256::percent(10),
+                decay_rate: Decimal256::zero(),
+                floor: Decimal256::percent(95),
+            },
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "decay_rate must be greater than 0")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::UpdateLiquidationMechanism {
+            collateral_token: HumanAddr::from("asset0000"),
+            mechanism: LiquidationMechanism::DutchAuction {
+                start_premium: Decimal256::percent(10),
+                decay_rate: Decimal256::percent(1),
+                floor: Decimal256::percent(120),
+            },
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "floor must be greater than 0 and cannot exceed the start price ratio"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    handle(&mut deps, env.clone(), msg).unwrap();
+    handle(
+        &mut deps,
+        env,
+        HandleMsg::UpdateLiquidationMechanism {
+            collateral_token: HumanAddr::from("asset0001"),
+            mechanism: mechanism.clone(),
+        },
+    )
+    .unwrap();
+
+    let res: LiquidationMechanismResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationMechanism {
+                collateral_token: HumanAddr::from("asset0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LiquidationMechanismResponse {
+            collateral_token: HumanAddr::from("asset0000"),
+            mechanism,
+        }
+    );
+
+    let execute_msg = |amount: u128, borrower: Option<HumanAddr>| {
+        HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("overseer0000"),
+            amount: Uint128::from(amount),
+            msg: Some(
+                to_binary(&Cw20HookMsg::ExecuteBid {
+                    liquidator: HumanAddr::from("addr0000"),
+                    fee_address: None,
+                    repay_address: Some(HumanAddr::from("market0000")),
+                    borrower,
+                })
+                .unwrap(),
+            ),
+        })
+    };
+
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env.clone(), execute_msg(1000000, None));
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Auctioned collateral requires the borrower it was seized from"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the collateral is held for the auction, nothing is sent
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        execute_msg(1000000, Some(HumanAddr::from("borrower0000"))),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![]);
+
+    let env = mock_env("asset0001", &[]);
+    handle(
+        &mut deps,
+        env.clone(),
+        execute_msg(500000, Some(HumanAddr::from("borrower0001"))),
+    )
+    .unwrap();
+
+    // the price ratio decays every block down to the floor
+    let auction_query = |block_height: u64| QueryMsg::Auction {
+        auction_id: 1,
+        block_height: Some(block_height),
+    };
+    let res: AuctionResponse =
+        from_binary(&query(&deps, auction_query(env.block.height + 5)).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        AuctionResponse {
+            auction_id: 1,
+            collateral_token: HumanAddr::from("asset0000"),
+            borrower: HumanAddr::from("borrower0000"),
+            amount: Uint256::from(1000000u64),
+            sold_amount: Uint256::zero(),
+            raised_amount: Uint256::zero(),
+            start_height: env.block.height,
+            price_ratio: Decimal256::percent(105),
+        }
+    );
+
+    let res: AuctionResponse =
+        from_binary(&query(&deps, auction_query(env.block.height + 20)).unwrap()).unwrap();
+    assert_eq!(res.price_ratio, Decimal256::percent(95));
+
+    let res: AuctionsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Auctions {
+                collateral_token: Some(HumanAddr::from("asset0001")),
+                start_after: None,
+                limit: None,
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.auctions
+            .iter()
+            .map(|v| v.auction_id)
+            .collect::<Vec<u64>>(),
+        vec![2]
+    );
+
+    let res: AuctionsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Auctions {
+                collateral_token: None,
+                start_after: None,
+                limit: Some(1),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.auctions
+            .iter()
+            .map(|v| v.auction_id)
+            .collect::<Vec<u64>>(),
+        vec![1]
+    );
+
+    let res: AuctionsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Auctions {
+                collateral_token: None,
+                start_after: Some(1),
+                limit: None,
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.auctions
+            .iter()
+            .map(|v| v.auction_id)
+            .collect::<Vec<u64>>(),
+        vec![2]
+    );
+
+    let msg = HandleMsg::BuyAuctionCollateral { auction_id: 1 };
+    let env = mock_env("addr0001", &[]);
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "No uusd assets have been provided")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // buying the whole auction at 0.5 * 1.05 refunds the rest
+    let mut env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    env.block.height += 5;
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("asset0000"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Transfer {
+                    recipient: HumanAddr::from("addr0001"),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market0000"),
+                send: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(519750u128),
+                }],
+                msg: to_binary(&MarketHandleMsg::RepayStableFor {
+                    borrower: HumanAddr::from("borrower0000"),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("overseer0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(5250u128),
+                }]
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0001"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(475000u128),
+                }]
+            }),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Auction {
+            auction_id: 1,
+            block_height: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Auction not found: 1"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // partial buy at the floor price
+    let mut env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(95000u128),
+        }],
+    );
+    env.block.height += 20;
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::BuyAuctionCollateral { auction_id: 2 },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("asset0001"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0001"),
+                amount: Uint128::from(200000u128),
+            })
+            .unwrap(),
+        })
+    );
+    assert_eq!(res.messages.len(), 3);
+
+    let res: AuctionResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Auction {
+                auction_id: 2,
+                block_height: Some(env.block.height),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        AuctionResponse {
+            auction_id: 2,
+            collateral_token: HumanAddr::from("asset0001"),
+            borrower: HumanAddr::from("borrower0001"),
+            amount: Uint256::from(300000u64),
+            sold_amount: Uint256::from(200000u64),
+            raised_amount: Uint256::from(95000u64),
+            start_height: env.block.height - 20,
+            price_ratio: Decimal256::percent(95),
+        }
+    );
+}
+
+#[test]
+fn continuation_ticket() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 60u64,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    // the bid fills 2,020,202 of the 3,000,000 collateral
+    // required_stable 999,999
+    // bid_fee         9,999
+    // repay_amount    990,000
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("custody0000"),
+        amount: Uint128::from(3000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: Some(HumanAddr::from("overseer0000")),
+                repay_address: Some(HumanAddr::from("market0000")),
+                borrower: Some(HumanAddr::from("borrower0000")),
+            })
+            .unwrap(),
+        ),
+    });
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("asset0000"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Transfer {
+                    recipient: HumanAddr::from("addr0000"),
+                    amount: Uint128::from(2020202u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("market0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(980198u128), // 990000 / (1 + tax_rate)
+                }]
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("overseer0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(9900u128), // 9999 / (1 + tax_rate)
+                }]
+            }),
+        ]
+    );
+
+    let ticket: ContinuationTicketResponse =
+        from_binary(&query(&deps, QueryMsg::ContinuationTicket { ticket_id: 1u64 }).unwrap())
+            .unwrap();
+    let expected_ticket = ContinuationTicketResponse {
+        ticket_id: 1u64,
+        collateral_token: HumanAddr::from("asset0000"),
+        borrower: HumanAddr::from("borrower0000"),
+        amount: Uint256::from(979798u64),
+        filled_amount: Uint256::from(2020202u64),
+        price: Decimal256::percent(50),
+        max_premium_rate: Decimal256::percent(5),
+        created_height: env.block.height,
+    };
+    assert_eq!(ticket, expected_ticket);
+
+    let tickets: ContinuationTicketsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ContinuationTickets {
+                collateral_token: Some(HumanAddr::from("asset0000")),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(tickets.tickets, vec![expected_ticket]);
+
+    // the price moved, the resumed fill keeps the locked one
+    // required_stable 480,101
+    // bid_fee         4,801
+    // repay_amount    475,300
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::one(), env.block.time, env.block.time),
+    )]);
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(2),
+    };
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::ResumeLiquidation { ticket_id: 1u64 };
+    let res = handle(&mut deps, mock_env("addr0002", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "No bids with the specified information exist")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("asset0000"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Transfer {
+                    recipient: HumanAddr::from("addr0001"),
+                    amount: Uint128::from(979798u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market0000"),
+                send: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(470594u128), // 475300 / (1 + tax_rate)
+                }],
+                msg: to_binary(&MarketHandleMsg::RepayStableFor {
+                    borrower: HumanAddr::from("borrower0000"),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("overseer0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(4753u128), // 4801 / (1 + tax_rate)
+                }]
+            }),
+        ]
+    );
+
+    // the ticket is closed once filled
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Continuation ticket not found: 1")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let tickets: ContinuationTicketsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ContinuationTickets {
+                collateral_token: None,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(tickets.tickets, vec![]);
+}
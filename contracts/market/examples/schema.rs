@@ -4,9 +4,20 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::market::{
-    BorrowerInfoResponse, BorrowerInfosResponse, ConfigResponse, Cw20HookMsg, EpochStateResponse,
-    HandleMsg, InitMsg, QueryMsg,
+    ATerraExchangeRateResponse, AmortizingLoanResponse, AttestationContractResponse,
+    BootstrapModeResponse, BorrowerInfoResponse, BorrowerInfosResponse, ColdTrancheResponse,
+    ColdUnbondingResponse, ConfigResponse, CreditLineResponse, CreditLinesResponse, Cw20HookMsg,
+    Cw20StablesResponse, DebtAuctionParamsResponse, DebtAuctionResponse, DepositorInfoResponse,
+    DepositorsResponse, DonationsResponse, DustForgivenessResponse, EpochStateResponse,
+    FeeExemptRedeemersResponse, FlashLoanResponse, FlashMintResponse, FlowLimitResponse, HandleMsg,
+    IdleDeploymentResponse, InitMsg, InvariantChecksResponse, KeeperBountyResponse,
+    LoanBreakdownResponse, LoanParamsResponse, OperationModeResponse, QueryMsg,
+    QueuedRedemptionsResponse, RateModeResponse, RateSnapshotResponse, RedemptionFeeResponse,
+    RegistryResponse, ShadowInterestModelResponse, SimulateDepositResponse, SimulateRedeemResponse,
+    StableDenomsResponse, StableRateParamsResponse, StateHistoryResponse, StateSnapshotResponse,
+    TaxParamsResponse,
 };
+use moneymarket::ownership::OwnershipProposalResponse;
 use moneymarket_market::state::State;
 
 fn main() {
@@ -20,8 +31,47 @@ fn main() {
     export_schema(&schema_for!(Cw20HookMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(OwnershipProposalResponse), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
     export_schema(&schema_for!(EpochStateResponse), &out_dir);
     export_schema(&schema_for!(BorrowerInfoResponse), &out_dir);
     export_schema(&schema_for!(BorrowerInfosResponse), &out_dir);
+    export_schema(&schema_for!(ColdTrancheResponse), &out_dir);
+    export_schema(&schema_for!(ColdUnbondingResponse), &out_dir);
+    export_schema(&schema_for!(RedemptionFeeResponse), &out_dir);
+    export_schema(&schema_for!(FeeExemptRedeemersResponse), &out_dir);
+    export_schema(&schema_for!(QueuedRedemptionsResponse), &out_dir);
+    export_schema(&schema_for!(DepositorInfoResponse), &out_dir);
+    export_schema(&schema_for!(DepositorsResponse), &out_dir);
+    export_schema(&schema_for!(CreditLineResponse), &out_dir);
+    export_schema(&schema_for!(CreditLinesResponse), &out_dir);
+    export_schema(&schema_for!(FlashMintResponse), &out_dir);
+    export_schema(&schema_for!(FlashLoanResponse), &out_dir);
+    export_schema(&schema_for!(IdleDeploymentResponse), &out_dir);
+    export_schema(&schema_for!(TaxParamsResponse), &out_dir);
+    export_schema(&schema_for!(OperationModeResponse), &out_dir);
+    export_schema(&schema_for!(RegistryResponse), &out_dir);
+    export_schema(&schema_for!(LoanParamsResponse), &out_dir);
+    export_schema(&schema_for!(AmortizingLoanResponse), &out_dir);
+    export_schema(&schema_for!(DebtAuctionParamsResponse), &out_dir);
+    export_schema(&schema_for!(DebtAuctionResponse), &out_dir);
+    export_schema(&schema_for!(LoanBreakdownResponse), &out_dir);
+    export_schema(&schema_for!(RateSnapshotResponse), &out_dir);
+    export_schema(&schema_for!(StateSnapshotResponse), &out_dir);
+    export_schema(&schema_for!(StateHistoryResponse), &out_dir);
+    export_schema(&schema_for!(KeeperBountyResponse), &out_dir);
+    export_schema(&schema_for!(ATerraExchangeRateResponse), &out_dir);
+    export_schema(&schema_for!(StableDenomsResponse), &out_dir);
+    export_schema(&schema_for!(Cw20StablesResponse), &out_dir);
+    export_schema(&schema_for!(BootstrapModeResponse), &out_dir);
+    export_schema(&schema_for!(SimulateDepositResponse), &out_dir);
+    export_schema(&schema_for!(SimulateRedeemResponse), &out_dir);
+    export_schema(&schema_for!(InvariantChecksResponse), &out_dir);
+    export_schema(&schema_for!(FlowLimitResponse), &out_dir);
+    export_schema(&schema_for!(AttestationContractResponse), &out_dir);
+    export_schema(&schema_for!(StableRateParamsResponse), &out_dir);
+    export_schema(&schema_for!(RateModeResponse), &out_dir);
+    export_schema(&schema_for!(DustForgivenessResponse), &out_dir);
+    export_schema(&schema_for!(DonationsResponse), &out_dir);
+    export_schema(&schema_for!(ShadowInterestModelResponse), &out_dir);
 }
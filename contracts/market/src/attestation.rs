@@ -0,0 +1,79 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::state::{read_attestation_contract, read_config, store_attestation_contract, Config};
+
+use moneymarket::attestation::AttestedAction;
+use moneymarket::error::ContractError;
+use moneymarket::market::AttestationContractResponse;
+use moneymarket::querier::query_attestation;
+
+/// Permissioned pools ask an attestation contract, e.g. a KYC
+/// registry, before every deposit, borrow and redemption
+pub fn set_attestation_contract<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    attestation_contract: Option<HumanAddr>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let attestation_contract_raw = match &attestation_contract {
+        Some(v) => Some(deps.api.canonical_address(v)?),
+        None => None,
+    };
+    store_attestation_contract(&mut deps.storage, &attestation_contract_raw)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_attestation_contract"),
+            log(
+                "attestation_contract",
+                attestation_contract.unwrap_or_default(),
+            ),
+        ],
+        data: None,
+    })
+}
+
+/// Fails with the reason given by the attestation contract when it
+/// denies `action`; always passes without an attestation contract
+pub(crate) fn assert_attested<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    action: AttestedAction,
+    address: &HumanAddr,
+    amount: Uint256,
+) -> StdResult<()> {
+    let attestation_contract = match read_attestation_contract(&deps.storage)? {
+        Some(v) => deps.api.human_address(&v)?,
+        None => return Ok(()),
+    };
+
+    let attestation = query_attestation(deps, &attestation_contract, action, address, amount)?;
+    if !attestation.allowed {
+        return Err(ContractError::AttestationDenied {
+            action: action.to_string(),
+            reason: attestation.reason.unwrap_or_default(),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+pub fn query_attestation_contract<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<AttestationContractResponse> {
+    Ok(AttestationContractResponse {
+        attestation_contract: match read_attestation_contract(&deps.storage)? {
+            Some(v) => Some(deps.api.human_address(&v)?),
+            None => None,
+        },
+    })
+}
@@ -0,0 +1,167 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, LogAttribute, Querier, StdError,
+    StdResult, Storage,
+};
+
+use crate::idle_deployment::query_market_balance;
+use crate::state::{
+    read_bootstrap_mode, read_config, read_state, remove_bootstrap_mode, store_bootstrap_mode,
+    BootstrapMode, Config, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::BootstrapModeResponse;
+
+pub fn update_bootstrap_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    end_height: Option<u64>,
+    tvl_threshold: Option<Uint256>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if end_height.is_none() && tvl_threshold.is_none() {
+        return Err(ContractError::NoBootstrapThreshold {}.into());
+    }
+
+    if end_height.is_some_and(|v| v <= env.block.height) {
+        return Err(ContractError::BootstrapEndPassed {}.into());
+    }
+
+    let mode = BootstrapMode {
+        end_height,
+        tvl_threshold,
+    };
+    if bootstrap_end_reached(deps, &config, &mode, env.block.height)? {
+        return Err(ContractError::BootstrapThresholdsReached {}.into());
+    }
+
+    store_bootstrap_mode(&mut deps.storage, &mode)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_bootstrap_mode"),
+            log(
+                "end_height",
+                end_height.map_or("none".to_string(), |v| v.to_string()),
+            ),
+            log(
+                "tvl_threshold",
+                tvl_threshold.map_or("none".to_string(), |v| v.to_string()),
+            ),
+        ],
+        data: None,
+    })
+}
+
+pub fn end_bootstrap_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if read_bootstrap_mode(&deps.storage)?.is_none() {
+        return Err(ContractError::NotBootstrapping {}.into());
+    }
+
+    remove_bootstrap_mode(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "end_bootstrap_mode"),
+            log("bootstrap_ended", "owner"),
+        ],
+        data: None,
+    })
+}
+
+/// Ends the bootstrap once its height or TVL threshold is reached.
+/// Returns whether borrows are allowed with the logs of the transition
+pub(crate) fn refresh_bootstrap_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    config: &Config,
+    block_height: u64,
+) -> StdResult<(bool, Vec<LogAttribute>)> {
+    let mode = match read_bootstrap_mode(&deps.storage)? {
+        Some(v) => v,
+        None => return Ok((true, vec![])),
+    };
+
+    if !bootstrap_end_reached(deps, config, &mode, block_height)? {
+        return Ok((false, vec![]));
+    }
+
+    remove_bootstrap_mode(&mut deps.storage);
+    Ok((
+        true,
+        vec![
+            log("bootstrap_ended", "threshold"),
+            log("bootstrap_end_height", block_height),
+        ],
+    ))
+}
+
+pub(crate) fn assert_borrows_enabled<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    config: &Config,
+    block_height: u64,
+) -> StdResult<Vec<LogAttribute>> {
+    match refresh_bootstrap_mode(deps, config, block_height)? {
+        (true, logs) => Ok(logs),
+        (false, _) => Err(ContractError::BootstrapBorrowsDisabled {}.into()),
+    }
+}
+
+fn bootstrap_end_reached<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    mode: &BootstrapMode,
+    block_height: u64,
+) -> StdResult<bool> {
+    if mode.end_height.is_some_and(|v| block_height >= v) {
+        return Ok(true);
+    }
+
+    match mode.tvl_threshold {
+        Some(tvl_threshold) => Ok(compute_tvl(deps, config)? >= tvl_threshold),
+        None => Ok(false),
+    }
+}
+
+fn compute_tvl<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+) -> StdResult<Uint256> {
+    let state: State = read_state(&deps.storage)?;
+    Ok(query_market_balance(deps, config)? + state.total_liabilities * Uint256::one())
+}
+
+pub fn query_bootstrap_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<BootstrapModeResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let tvl = compute_tvl(deps, &config)?;
+    Ok(match read_bootstrap_mode(&deps.storage)? {
+        Some(mode) => BootstrapModeResponse {
+            active: true,
+            end_height: mode.end_height,
+            tvl_threshold: mode.tvl_threshold,
+            tvl,
+        },
+        None => BootstrapModeResponse {
+            active: false,
+            end_height: None,
+            tvl_threshold: None,
+            tvl,
+        },
+    })
+}
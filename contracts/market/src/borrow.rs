@@ -2,40 +2,70 @@ use anchor_token::distributor::HandleMsg as FaucetHandleMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
     log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
-    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+    HumanAddr, LogAttribute, Querier, StdError, StdResult, Storage, WasmMsg,
 };
+use cw20::Cw20HandleMsg;
+use moneymarket::attestation::AttestedAction;
+use moneymarket::error::ContractError;
 use moneymarket::interest_model::BorrowRateResponse;
 use moneymarket::market::{BorrowerInfoResponse, BorrowerInfosResponse};
-use moneymarket::overseer::BorrowLimitResponse;
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
-
-use crate::deposit::compute_exchange_rate_raw;
+use moneymarket::overseer::{BorrowLimitResponse, PositionHealthResponse};
+use moneymarket::querier::query_balance;
+use moneymarket::units::{to_micro_units, STABLE_DECIMALS};
+
+use crate::attestation::assert_attested;
+use crate::bootstrap::assert_borrows_enabled;
+use crate::credit_lines::saturating_sub;
+use crate::deposit::{compute_exchange_rate_raw, compute_mint_amount, query_aterra_supply};
+use crate::idle_deployment::{query_market_balance, recall_shortfall};
+use crate::loans::transfer_amortizing_loan;
+use crate::operation_mode::{
+    assert_borrowing_enabled, assert_deposits_enabled, assert_repayments_enabled,
+};
 use crate::querier::{query_borrow_limit, query_borrow_rate, query_target_deposit_rate};
+use crate::rate_snapshots::record_rate_snapshot;
+use crate::redemption_queue::service_redemption_queue;
+use crate::repayments::{loan_principal, record_borrow, record_repayment, transfer_loan_breakdown};
+use crate::shadow_interest_model::shadow_rate_logs;
+use crate::stable_rate::{add_stable_loan, blend_stable_rate, remove_stable_loan};
 use crate::state::{
-    read_borrower_info, read_borrower_infos, read_config, read_state, store_borrower_info,
-    store_state, BorrowerInfo, Config, State,
+    read_borrower_info, read_config, read_dust_forgiveness, read_filtered_borrower_infos,
+    read_state, store_borrower_info, store_state, AccrualClock, BorrowerInfo, Config, State,
 };
+use crate::tax::deduct_tax_cached;
 
 pub fn borrow_stable<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     borrow_amount: Uint256,
     to: Option<HumanAddr>,
+    deposit: Option<bool>,
+    decimals: Option<u8>,
 ) -> HandleResult {
+    assert_borrowing_enabled(deps, env.block.height)?;
+    let borrow_amount = to_micro_units(borrow_amount, decimals.unwrap_or(STABLE_DECIMALS))?;
     let config: Config = read_config(&deps.storage)?;
+    let bootstrap_logs = assert_borrows_enabled(deps, &config, env.block.height)?;
 
     let mut state: State = read_state(&deps.storage)?;
 
-    let borrower = env.message.sender;
+    let borrower = env.message.sender.clone();
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
 
     // Compute interest
-    compute_interest(&deps, &config, &mut state, env.block.height, None)?;
-    compute_borrower_interest(&state, &mut liability);
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_borrower_interest(&mut state, &mut liability);
 
     // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.height, env.block.time);
     compute_borrower_reward(&state, &mut liability);
 
     let overseer = deps.api.human_address(&config.overseer_contract)?;
@@ -43,44 +73,102 @@ pub fn borrow_stable<S: Storage, A: Api, Q: Querier>(
         query_borrow_limit(deps, &overseer, &borrower, Some(env.block.time))?;
 
     if borrow_limit_res.borrow_limit < borrow_amount + liability.loan_amount {
-        return Err(StdError::generic_err(format!(
-            "Borrow amount too high; Loan liability becomes greater than borrow limit: {}",
-            borrow_limit_res.borrow_limit,
-        )));
+        return Err(ContractError::BorrowExceedsLimit {
+            borrow_limit: borrow_limit_res.borrow_limit,
+        }
+        .into());
     }
 
-    let current_balance = query_balance(
-        &deps,
-        &env.contract.address,
-        config.stable_denom.to_string(),
-    )?;
+    let current_balance = query_market_balance(deps, &config)?;
 
     // Assert borrow amount
     assert_max_borrow_factor(&config, &state, current_balance, borrow_amount)?;
+    assert_borrow_caps(&config, &state, liability.loan_amount, borrow_amount)?;
+    assert_attested(deps, AttestedAction::Borrow, &borrower, borrow_amount)?;
+
+    record_borrow(
+        &mut deps.storage,
+        &borrower_raw,
+        liability.loan_amount,
+        borrow_amount,
+    )?;
+
+    remove_stable_loan(&mut state, &liability);
+    if let Some(stable_rate) = blend_stable_rate(deps, &config, &state, &liability, borrow_amount)?
+    {
+        liability.stable_rate = Some(stable_rate);
+    }
 
     liability.loan_amount += borrow_amount;
+    add_stable_loan(&mut state, &liability);
     state.total_liabilities += Decimal256::from_uint256(borrow_amount);
     store_state(&mut deps.storage, &state)?;
+    liability.last_updated = env.block.height;
     store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+    record_rate_snapshot(
+        deps,
+        &config,
+        &state,
+        &borrower_raw,
+        liability.loan_amount,
+        env.block.height,
+    )?;
 
-    Ok(HandleResponse {
-        messages: vec![CosmosMsg::Bank(BankMsg::Send {
+    let recipient = to.unwrap_or_else(|| borrower.clone());
+    let (messages, deposit_logs) = if deposit.unwrap_or(false) {
+        assert_deposits_enabled(deps, env.block.height)?;
+
+        // The borrowed stable never leaves the market, it is
+        // deposited back at the exchange rate before the borrow
+        let (mint_amount, _) = compute_mint_amount(deps, &env, &config, &recipient, borrow_amount)?;
+        (
+            vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.human_address(&config.aterra_contract)?,
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Mint {
+                    recipient: recipient.clone(),
+                    amount: mint_amount.into(),
+                })?,
+            })],
+            vec![log("recipient", recipient), log("mint_amount", mint_amount)],
+        )
+    } else {
+        let mut messages = recall_shortfall(deps, &config, borrow_amount)?;
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
             from_address: env.contract.address,
-            to_address: to.unwrap_or_else(|| borrower.clone()),
-            amount: vec![deduct_tax(
-                &deps,
+            to_address: recipient,
+            amount: vec![deduct_tax_cached(
+                deps,
+                env.block.height,
                 Coin {
                     denom: config.stable_denom,
                     amount: borrow_amount.into(),
                 },
             )?],
-        })],
-        log: vec![
-            log("action", "borrow_stable"),
-            log("borrower", borrower),
-            log("borrow_amount", borrow_amount),
-        ],
-        data: None,
+        }));
+        (messages, vec![])
+    };
+
+    let health = PositionHealthResponse::new(
+        borrower.clone(),
+        liability.loan_amount,
+        borrow_limit_res.borrow_limit,
+    );
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![
+                log("action", "borrow_stable"),
+                log("borrower", borrower),
+                log("borrow_amount", borrow_amount),
+            ],
+            bootstrap_logs,
+            deposit_logs,
+            accrual_logs,
+        ]
+        .concat(),
+        data: Some(to_binary(&health)?),
     })
 }
 
@@ -95,11 +183,9 @@ pub fn repay_stable_from_liquidation<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    let cur_balance: Uint256 = query_balance(
-        &deps,
-        &env.contract.address,
-        config.stable_denom.to_string(),
-    )?;
+    assert_repayments_enabled(deps)?;
+    let cur_balance: Uint256 =
+        query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
 
     // override env
     let mut env = env;
@@ -113,10 +199,34 @@ pub fn repay_stable_from_liquidation<S: Storage, A: Api, Q: Querier>(
     repay_stable(deps, env)
 }
 
+/// Repays the loan of `borrower` with the sent stable, used by third
+/// parties such as the insurance contracts of the overseer; any excess
+/// is refunded to the sender who paid it
+pub fn repay_stable_for<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    repay_loan(deps, env, borrower)
+}
+
 pub fn repay_stable<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
 ) -> HandleResult {
+    let borrower = env.message.sender.clone();
+    repay_loan(deps, env, borrower)
+}
+
+/// Repays the loan of `borrower` with the stable sent by the sender,
+/// the excess is refunded to the sender. Repayments stay open in every
+/// protocol mode, the interest keeps accruing on paused loans
+fn repay_loan<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    assert_repayments_enabled(deps)?;
     let config: Config = read_config(&deps.storage)?;
 
     // Check stable denom deposit
@@ -130,26 +240,34 @@ pub fn repay_stable<S: Storage, A: Api, Q: Querier>(
 
     // Cannot deposit zero amount
     if amount.is_zero() {
-        return Err(StdError::generic_err(format!(
-            "Repay amount must be greater than 0 {}",
-            config.stable_denom
-        )));
+        return Err(ContractError::ZeroRepay {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
     }
 
     let mut state: State = read_state(&deps.storage)?;
 
-    let borrower = env.message.sender;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
 
     // Compute interest
-    compute_interest(&deps, &config, &mut state, env.block.height, Some(amount))?;
-    compute_borrower_interest(&state, &mut liability);
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        Some(amount),
+    )?;
+    compute_borrower_interest(&mut state, &mut liability);
 
     // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.height, env.block.time);
     compute_borrower_reward(&state, &mut liability);
+    remove_stable_loan(&mut state, &liability);
 
+    let loan_amount = liability.loan_amount;
     let repay_amount: Uint256;
     let mut messages: Vec<CosmosMsg> = vec![];
     if liability.loan_amount < amount {
@@ -158,12 +276,13 @@ pub fn repay_stable<S: Storage, A: Api, Q: Querier>(
 
         // Payback left repay amount to sender
         messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address,
-            to_address: borrower.clone(),
-            amount: vec![deduct_tax(
-                &deps,
+            from_address: env.contract.address.clone(),
+            to_address: env.message.sender.clone(),
+            amount: vec![deduct_tax_cached(
+                deps,
+                env.block.height,
                 Coin {
-                    denom: config.stable_denom,
+                    denom: config.stable_denom.to_string(),
                     amount: (amount - repay_amount).into(),
                 },
             )?],
@@ -173,18 +292,234 @@ pub fn repay_stable<S: Storage, A: Api, Q: Querier>(
         liability.loan_amount = liability.loan_amount - repay_amount;
     }
 
-    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(repay_amount);
+    // a residual within the dust forgiveness amount is forgiven instead
+    // of being left as a loan too small to repay, only when the repayment
+    // covers the whole principal so that the residual is accrued interest
+    let mut forgiven_logs = vec![];
+    let forgiven_amount = if !liability.loan_amount.is_zero()
+        && liability.loan_amount <= read_dust_forgiveness(&deps.storage)?
+        && repay_amount >= loan_principal(&deps.storage, &borrower_raw, loan_amount)?
+    {
+        forgiven_logs.push(log("forgiven_amount", liability.loan_amount));
+        liability.loan_amount
+    } else {
+        Uint256::zero()
+    };
+    liability.loan_amount = liability.loan_amount - forgiven_amount;
+    state.forgiven_dust += forgiven_amount;
+    add_stable_loan(&mut state, &liability);
+
+    state.total_liabilities =
+        state.total_liabilities - Decimal256::from_uint256(repay_amount + forgiven_amount);
+
+    let (interest_repaid, principal_repaid) = record_repayment(
+        &mut deps.storage,
+        &borrower_raw,
+        loan_amount,
+        repay_amount,
+        forgiven_amount,
+    )?;
 
+    liability.last_updated = env.block.height;
     store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
     store_state(&mut deps.storage, &state)?;
+    record_rate_snapshot(
+        deps,
+        &config,
+        &state,
+        &borrower_raw,
+        liability.loan_amount,
+        env.block.height,
+    )?;
+
+    // The repaid liquidity services the queued redemptions
+    let (queue_messages, queue_logs) =
+        service_redemption_queue(deps, &env, amount - repay_amount, Uint256::zero())?;
+    messages.extend(queue_messages);
+
+    let overseer = deps.api.human_address(&config.overseer_contract)?;
+    let borrow_limit_res: BorrowLimitResponse =
+        query_borrow_limit(deps, &overseer, &borrower, Some(env.block.time))?;
+    let health = PositionHealthResponse::new(
+        borrower.clone(),
+        liability.loan_amount,
+        borrow_limit_res.borrow_limit,
+    );
 
     Ok(HandleResponse {
         messages,
-        log: vec![
-            log("action", "repay_stable"),
-            log("borrower", borrower),
-            log("repay_amount", repay_amount),
-        ],
+        log: [
+            vec![
+                log("action", "repay_stable"),
+                log("borrower", borrower),
+                log("repay_amount", repay_amount),
+                log("interest_repaid", interest_repaid),
+                log("principal_repaid", principal_repaid),
+            ],
+            forgiven_logs,
+            queue_logs,
+            accrual_logs,
+        ]
+        .concat(),
+        data: Some(to_binary(&health)?),
+    })
+}
+
+/// Reduces the loan of the borrower out of the reserves; the exchange
+/// rate is unchanged as liabilities and reserves decrease together
+pub fn rebate_interest<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+    amount: Uint256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if config.overseer_contract != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+
+    // Compute interest
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_borrower_interest(&mut state, &mut liability);
+
+    // Compute ANC reward
+    compute_reward(&mut state, env.block.height, env.block.time);
+    compute_borrower_reward(&state, &mut liability);
+
+    // Rebates are capped to the loan and the available reserves
+    let rebate_amount = std::cmp::min(
+        std::cmp::min(amount, liability.loan_amount),
+        state.total_reserves * Uint256::one(),
+    );
+
+    remove_stable_loan(&mut state, &liability);
+    liability.loan_amount = liability.loan_amount - rebate_amount;
+    add_stable_loan(&mut state, &liability);
+    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(rebate_amount);
+    state.total_reserves = state.total_reserves - Decimal256::from_uint256(rebate_amount);
+
+    liability.last_updated = env.block.height;
+    store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+    store_state(&mut deps.storage, &state)?;
+    record_rate_snapshot(
+        deps,
+        &config,
+        &state,
+        &borrower_raw,
+        liability.loan_amount,
+        env.block.height,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "rebate_interest"),
+                log("borrower", borrower),
+                log("rebate_amount", rebate_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn transfer_liability<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    to: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if config.overseer_contract != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+
+    let from_raw = deps.api.canonical_address(&from)?;
+    let to_raw = deps.api.canonical_address(&to)?;
+    let mut from_liability: BorrowerInfo = read_borrower_info(&deps.storage, &from_raw);
+    let mut to_liability: BorrowerInfo = read_borrower_info(&deps.storage, &to_raw);
+
+    // Compute interest
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_borrower_interest(&mut state, &mut from_liability);
+    compute_borrower_interest(&mut state, &mut to_liability);
+
+    // Compute ANC reward
+    compute_reward(&mut state, env.block.height, env.block.time);
+    compute_borrower_reward(&state, &mut from_liability);
+    compute_borrower_reward(&state, &mut to_liability);
+
+    if !to_liability.loan_amount.is_zero() {
+        return Err(ContractError::LoanTransferToBorrower {}.into());
+    }
+
+    // both liabilities are accrued to the global indexes and settled at
+    // the same periods. A stable loan moves with its locked rate, which
+    // leaves the stable liabilities unchanged
+    let loan_amount = from_liability.loan_amount;
+    to_liability.loan_amount = loan_amount;
+    to_liability.stable_rate = from_liability.stable_rate.take();
+    from_liability.loan_amount = Uint256::zero();
+
+    from_liability.last_updated = env.block.height;
+    to_liability.last_updated = env.block.height;
+    store_borrower_info(&mut deps.storage, &from_raw, &from_liability)?;
+    store_borrower_info(&mut deps.storage, &to_raw, &to_liability)?;
+    store_state(&mut deps.storage, &state)?;
+    transfer_amortizing_loan(&mut deps.storage, &from_raw, &to_raw)?;
+    transfer_loan_breakdown(&mut deps.storage, &from_raw, &to_raw, loan_amount)?;
+    record_rate_snapshot(
+        deps,
+        &config,
+        &state,
+        &from_raw,
+        Uint256::zero(),
+        env.block.height,
+    )?;
+    record_rate_snapshot(
+        deps,
+        &config,
+        &state,
+        &to_raw,
+        loan_amount,
+        env.block.height,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "transfer_liability"),
+                log("from", from),
+                log("to", to),
+                log("loan_amount", loan_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
         data: None,
     })
 }
@@ -202,17 +537,25 @@ pub fn claim_rewards<S: Storage, A: Api, Q: Querier>(
     let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
 
     // Compute interest
-    compute_interest(&deps, &config, &mut state, env.block.height, None)?;
-    compute_borrower_interest(&state, &mut liability);
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_borrower_interest(&mut state, &mut liability);
 
     // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.height, env.block.time);
     compute_borrower_reward(&state, &mut liability);
 
     let claim_amount = liability.pending_rewards * Uint256::one();
     liability.pending_rewards = liability.pending_rewards - Decimal256::from_uint256(claim_amount);
 
     store_state(&mut deps.storage, &state)?;
+    liability.last_updated = env.block.height;
     store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
 
     let messages: Vec<CosmosMsg> = if !claim_amount.is_zero() {
@@ -230,55 +573,137 @@ pub fn claim_rewards<S: Storage, A: Api, Q: Querier>(
 
     Ok(HandleResponse {
         messages,
-        log: vec![
-            log("action", "claim_rewards"),
-            log("claim_amount", claim_amount),
-        ],
+        log: [
+            vec![
+                log("action", "claim_rewards"),
+                log("claim_amount", claim_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
         data: None,
     })
 }
 
 /// Compute interest and update state
-/// total liabilities and total reserves
+/// total liabilities and total reserves.
+/// Once interest is accrued at a height, later calls at the same
+/// height, from the same or another handler, return before any query.
+/// `block_time` is only used once accrual is time based
+#[allow(clippy::too_many_arguments)]
 pub fn compute_interest<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     config: &Config,
     state: &mut State,
     block_height: u64,
+    block_time: u64,
     deposit_amount: Option<Uint256>,
-) -> StdResult<()> {
+) -> StdResult<Vec<LogAttribute>> {
     if state.last_interest_updated >= block_height {
-        return Ok(());
+        return Ok(vec![]);
     }
 
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
-    let balance: Uint256 = query_balance(
-        &deps,
-        &deps.api.human_address(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - deposit_amount.unwrap_or_else(Uint256::zero);
+    let aterra_supply = query_aterra_supply(deps, config)?;
+    let balance: Uint256 =
+        query_market_balance(deps, config)? - deposit_amount.unwrap_or_else(Uint256::zero);
 
+    let (model_reserves, excess_reserves) = clamp_model_reserves(state, balance);
     let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-        &deps,
+        deps,
         &deps.api.human_address(&config.interest_model)?,
         balance,
         state.total_liabilities,
-        state.total_reserves,
+        model_reserves,
     )?;
 
     let target_deposit_rate: Decimal256 =
-        query_target_deposit_rate(&deps, &deps.api.human_address(&config.overseer_contract)?)?;
+        query_target_deposit_rate(deps, &deps.api.human_address(&config.overseer_contract)?)?;
 
-    compute_interest_raw(
+    let shadow_logs = shadow_rate_logs(
+        deps,
+        block_height,
+        balance,
+        state.total_liabilities,
+        model_reserves,
+    )?;
+
+    let accrual = compute_interest_raw(
         state,
         block_height,
+        block_time,
         balance,
         aterra_supply,
         borrow_rate_res.rate,
         target_deposit_rate,
+        config.reserve_factor,
     );
 
-    Ok(())
+    Ok([
+        accrual.map(|v| v.to_logs()).unwrap_or_default(),
+        shadow_logs,
+        record_utilization_clamp(state, excess_reserves),
+    ]
+    .concat())
+}
+
+/// Reserves passed to the interest model. Rounding or the reserves
+/// accounting can leave the reserves above the market balance, which
+/// implies a utilization above 1; they are then capped to the balance,
+/// so the model sees a utilization of 1, and the excess is returned
+pub(crate) fn clamp_model_reserves(
+    state: &State,
+    balance: Uint256,
+) -> (Decimal256, Option<Decimal256>) {
+    let balance = Decimal256::from_uint256(balance);
+    if state.total_reserves > balance && !state.total_liabilities.is_zero() {
+        (balance, Some(state.total_reserves - balance))
+    } else {
+        (state.total_reserves, None)
+    }
+}
+
+/// Counts a clamped accrual and returns its diagnostic logs
+pub(crate) fn record_utilization_clamp(
+    state: &mut State,
+    excess_reserves: Option<Decimal256>,
+) -> Vec<LogAttribute> {
+    match excess_reserves {
+        Some(excess_reserves) => {
+            state.utilization_clamps += 1;
+            vec![
+                log("utilization_clamped", true),
+                log("excess_reserves", excess_reserves),
+                log("utilization_clamps", state.utilization_clamps),
+            ]
+        }
+        None => vec![],
+    }
+}
+
+/// Components of an interest accrual
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterestAccrual {
+    pub borrow_rate: Decimal256,
+    /// blocks the per block rates accrued over, derived
+    /// from the elapsed time when accrual is time based
+    pub passed_blocks: Decimal256,
+    pub interest_accrued: Decimal256,
+    pub reserves_accrued: Decimal256,
+    pub exchange_rate: Decimal256,
+}
+
+impl InterestAccrual {
+    /// Logged by every handler accruing interest, so
+    /// rates can be followed from the events alone
+    pub fn to_logs(&self) -> Vec<LogAttribute> {
+        vec![
+            log("accrual_borrow_rate", self.borrow_rate),
+            log("accrual_blocks", self.passed_blocks),
+            log("accrual_interest", self.interest_accrued),
+            log("accrual_reserves", self.reserves_accrued),
+            log("accrual_exchange_rate", self.exchange_rate),
+        ]
+    }
 }
 
 // CONTRACT: to use this function as state update purpose,
@@ -286,30 +711,59 @@ pub fn compute_interest<S: Storage, A: Api, Q: Querier>(
 // * state.prev_aterra_supply
 // * state.prev_exchange_rate
 // * state.last_interest_updated
+#[allow(clippy::too_many_arguments)]
 pub fn compute_interest_raw(
     state: &mut State,
     block_height: u64,
+    block_time: u64,
     balance: Uint256,
     aterra_supply: Uint256,
     borrow_rate: Decimal256,
     target_deposit_rate: Decimal256,
-) {
+    reserve_factor: Decimal256,
+) -> Option<InterestAccrual> {
     if state.last_interest_updated >= block_height {
-        return;
+        return None;
     }
 
-    let passed_blocks = Decimal256::from_uint256(block_height - state.last_interest_updated);
+    let passed_blocks = match state.accrual_clock.as_mut() {
+        Some(clock) => {
+            let periods = accrual_periods(clock, clock.last_interest_updated_time, block_time);
+            clock.last_interest_updated_time = block_time;
+            periods
+        }
+        None => Decimal256::from_uint256(block_height - state.last_interest_updated),
+    };
 
+    // stable loans accrue at their locked rates, the others at the variable rate
     let interest_factor = passed_blocks * borrow_rate;
-    let interest_accrued = state.total_liabilities * interest_factor;
+    let variable_liabilities =
+        saturating_sub(state.total_liabilities, state.total_stable_liabilities);
+    let stable_interest_accrued = state.stable_interest_per_block * passed_blocks;
+    let interest_accrued = variable_liabilities * interest_factor + stable_interest_accrued;
 
     state.global_interest_index =
         state.global_interest_index * (Decimal256::one() + interest_factor);
     state.total_liabilities += interest_accrued;
+    state.total_stable_liabilities += stable_interest_accrued;
 
-    let mut exchange_rate = compute_exchange_rate_raw(&state, aterra_supply, balance);
+    // Credit lines accrue at their own rates, settled per line on use
+    state.total_credit_liabilities += state.credit_interest_per_block * passed_blocks;
+    state.accrued_periods += passed_blocks;
+
+    // the reserve factor share of the interest never reaches the depositors
+    let mut reserves_accrued = interest_accrued * reserve_factor;
+    state.total_reserves += reserves_accrued;
+
+    let mut exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
     let effective_deposit_rate = exchange_rate / state.prev_exchange_rate;
-    let deposit_rate = (effective_deposit_rate - Decimal256::one()) / passed_blocks;
+
+    // the exchange rate decreases when a loss exceeds the interest
+    let deposit_rate = if effective_deposit_rate > Decimal256::one() && !passed_blocks.is_zero() {
+        (effective_deposit_rate - Decimal256::one()) / passed_blocks
+    } else {
+        Decimal256::zero()
+    };
 
     if deposit_rate > target_deposit_rate {
         // excess_deposit_rate(_per_block)
@@ -319,30 +773,83 @@ pub fn compute_interest_raw(
 
         // excess_yield = prev_deposits * excess_deposit_rate(_per_block) * blocks
         let excess_yield = prev_deposits * passed_blocks * excess_deposit_rate;
-        
+
         state.total_reserves += excess_yield;
-        exchange_rate = compute_exchange_rate_raw(&state, aterra_supply, balance);
+        reserves_accrued += excess_yield;
+        exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
     }
 
     state.prev_aterra_supply = aterra_supply;
     state.prev_exchange_rate = exchange_rate;
     state.last_interest_updated = block_height;
+    state.last_borrow_rate = borrow_rate;
+
+    Some(InterestAccrual {
+        borrow_rate,
+        passed_blocks,
+        interest_accrued,
+        reserves_accrued,
+        exchange_rate,
+    })
 }
 
-/// Compute new interest and apply to liability
-pub(crate) fn compute_borrower_interest(state: &State, liability: &mut BorrowerInfo) {
-    liability.loan_amount =
-        liability.loan_amount * state.global_interest_index / liability.interest_index;
+/// Compute new interest and apply to liability.
+/// A stable loan accrues at its locked rate over the periods accrued
+/// since it was last settled instead, which the stable liabilities of
+/// the state already accrued. Its interest accrues interest from then on
+pub(crate) fn compute_borrower_interest(state: &mut State, liability: &mut BorrowerInfo) {
+    liability.loan_amount = match liability.stable_rate {
+        Some(stable_rate) => {
+            let passed_periods =
+                saturating_sub(state.accrued_periods, liability.stable_accrued_periods);
+            let interest = liability.loan_amount * (stable_rate * passed_periods);
+            state.stable_interest_per_block += Decimal256::from_uint256(interest) * stable_rate;
+            liability.loan_amount + interest
+        }
+        None => liability.loan_amount * state.global_interest_index / liability.interest_index,
+    };
     liability.interest_index = state.global_interest_index;
+    liability.stable_accrued_periods = state.accrued_periods;
+}
+
+/// Blocks the per block rates accrue over between `last_time` and
+/// `block_time` when accrual is time based. Block time changes then
+/// leave the accrued amounts unchanged
+fn accrual_periods(clock: &AccrualClock, last_time: u64, block_time: u64) -> Decimal256 {
+    if block_time <= last_time {
+        return Decimal256::zero();
+    }
+
+    Decimal256::from_ratio(block_time - last_time, clock.seconds_per_block)
+}
+
+/// Block time of `block_height` for the queries, which have no block
+/// time. It is extrapolated from the last accrual with `seconds_per_block`
+pub(crate) fn estimate_block_time(state: &State, block_height: u64) -> u64 {
+    match &state.accrual_clock {
+        Some(clock) if block_height > state.last_interest_updated => {
+            clock.last_interest_updated_time
+                + (block_height - state.last_interest_updated) * clock.seconds_per_block
+        }
+        Some(clock) => clock.last_interest_updated_time,
+        None => 0,
+    }
 }
 
 /// Compute distributed reward and update global index
-pub fn compute_reward(state: &mut State, block_height: u64) {
+pub fn compute_reward(state: &mut State, block_height: u64, block_time: u64) {
     if state.last_reward_updated >= block_height {
         return;
     }
 
-    let passed_blocks = Decimal256::from_uint256(block_height - state.last_reward_updated);
+    let passed_blocks = match state.accrual_clock.as_mut() {
+        Some(clock) => {
+            let periods = accrual_periods(clock, clock.last_reward_updated_time, block_time);
+            clock.last_reward_updated_time = block_time;
+            periods
+        }
+        None => Decimal256::from_uint256(block_height - state.last_reward_updated),
+    };
     let reward_accrued = passed_blocks * state.anc_emission_rate;
     let borrow_amount = state.total_liabilities / state.global_interest_index;
 
@@ -373,10 +880,11 @@ pub fn query_borrower_info<S: Storage, A: Api, Q: Querier>(
         let config: Config = read_config(&deps.storage)?;
         let mut state: State = read_state(&deps.storage)?;
 
-        compute_interest(&deps, &config, &mut state, block_height, None)?;
-        compute_borrower_interest(&state, &mut borrower_info);
+        let block_time = estimate_block_time(&state, block_height);
+        compute_interest(deps, &config, &mut state, block_height, block_time, None)?;
+        compute_borrower_interest(&mut state, &mut borrower_info);
 
-        compute_reward(&mut state, block_height);
+        compute_reward(&mut state, block_height, block_time);
         compute_borrower_reward(&state, &mut borrower_info);
     }
 
@@ -389,10 +897,16 @@ pub fn query_borrower_info<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/// A page of the liabilities stored after `start_after`, keeping those
+/// whose loan at the last interest accrual is at least `min_liability`
+/// and, with `only_liquidatable`, exceeds the liquidation borrow limit
+/// of the overseer
 pub fn query_borrower_infos<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     start_after: Option<HumanAddr>,
     limit: Option<u32>,
+    min_liability: Option<Uint256>,
+    only_liquidatable: Option<bool>,
 ) -> StdResult<BorrowerInfosResponse> {
     let start_after = if let Some(start_after) = start_after {
         Some(deps.api.canonical_address(&start_after)?)
@@ -400,8 +914,75 @@ pub fn query_borrower_infos<S: Storage, A: Api, Q: Querier>(
         None
     };
 
-    let borrower_infos: Vec<BorrowerInfoResponse> = read_borrower_infos(&deps, start_after, limit)?;
-    Ok(BorrowerInfosResponse { borrower_infos })
+    let only_liquidatable = only_liquidatable.unwrap_or(false);
+    let (borrower_infos, last_scanned) = if min_liability.is_none() && !only_liquidatable {
+        read_filtered_borrower_infos(deps, start_after, limit, |_, _| Ok(true))?
+    } else {
+        let config: Config = read_config(&deps.storage)?;
+        let mut state: State = read_state(&deps.storage)?;
+        let overseer = deps.api.human_address(&config.overseer_contract)?;
+        let min_liability = min_liability.unwrap_or_else(Uint256::zero);
+
+        read_filtered_borrower_infos(deps, start_after, limit, |borrower, liability| {
+            let mut liability = liability.clone();
+            compute_borrower_interest(&mut state, &mut liability);
+            if liability.loan_amount.is_zero() || liability.loan_amount < min_liability {
+                return Ok(false);
+            }
+
+            if !only_liquidatable {
+                return Ok(true);
+            }
+
+            // the borrow limit is zeroed for the positions which cannot
+            // borrow any further, the liquidations compare the loan to
+            // the liquidation borrow limit instead
+            let borrow_limit_res: BorrowLimitResponse =
+                query_borrow_limit(deps, &overseer, &deps.api.human_address(borrower)?, None)?;
+            Ok(liability.loan_amount > borrow_limit_res.liquidation_borrow_limit)
+        })?
+    };
+
+    let last_scanned = match last_scanned {
+        Some(borrower_raw) => Some(deps.api.human_address(&borrower_raw)?),
+        None => None,
+    };
+
+    Ok(BorrowerInfosResponse {
+        borrower_infos,
+        last_scanned,
+    })
+}
+
+/// Hard limits of the total liabilities and of each loan,
+/// set while the markets of new collaterals are bootstrapped
+fn assert_borrow_caps(
+    config: &Config,
+    state: &State,
+    loan_amount: Uint256,
+    borrow_amount: Uint256,
+) -> StdResult<()> {
+    if let Some(global_borrow_cap) = config.global_borrow_cap {
+        if state.total_liabilities + Decimal256::from_uint256(borrow_amount)
+            > Decimal256::from_uint256(global_borrow_cap)
+        {
+            return Err(ContractError::GlobalBorrowCapExceeded {
+                cap: global_borrow_cap,
+            }
+            .into());
+        }
+    }
+
+    if let Some(per_account_borrow_cap) = config.per_account_borrow_cap {
+        if loan_amount + borrow_amount > per_account_borrow_cap {
+            return Err(ContractError::AccountBorrowCapExceeded {
+                cap: per_account_borrow_cap,
+            }
+            .into());
+        }
+    }
+
+    Ok(())
 }
 
 fn assert_max_borrow_factor(
@@ -418,19 +999,19 @@ fn assert_max_borrow_factor(
         > (current_balance + state.total_liabilities - state.total_reserves)
             * config.max_borrow_factor
     {
-        return Err(StdError::generic_err(format!(
-            "Exceeds {} max borrow factor; borrow demand too high",
-            config.stable_denom
-        )));
+        return Err(ContractError::MaxBorrowFactorExceeded {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
     }
 
     // Assert available balance
     if borrow_amount + state.total_reserves > current_balance {
-        return Err(StdError::generic_err(format!(
-            "Not enough {} available; borrow demand too high",
-            config.stable_denom
-        )));
+        return Err(ContractError::NoLiquidity {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
     }
 
-    return Ok(());
+    Ok(())
 }
@@ -1,11 +1,90 @@
+use crate::attestation::{query_attestation_contract, set_attestation_contract};
+use crate::bootstrap::{end_bootstrap_mode, query_bootstrap_mode, update_bootstrap_mode};
 use crate::borrow::{
-    borrow_stable, claim_rewards, compute_interest, compute_interest_raw, compute_reward,
-    query_borrower_info, query_borrower_infos, repay_stable, repay_stable_from_liquidation,
+    borrow_stable, claim_rewards, clamp_model_reserves, compute_interest, compute_interest_raw,
+    compute_reward, estimate_block_time, query_borrower_info, query_borrower_infos,
+    rebate_interest, record_utilization_clamp, repay_stable, repay_stable_for,
+    repay_stable_from_liquidation, transfer_liability,
+};
+use crate::credit_lines::{
+    default_credit_line, draw_credit, post_credit_collateral, query_credit_line,
+    query_credit_lines, repay_credit, set_credit_line, withdraw_credit_collateral,
+};
+use crate::cw20_stables::{
+    deposit_cw20_stable, query_cw20_stables, swap_cw20_stable, update_cw20_stable,
+};
+use crate::debt_auction::{
+    bid_debt_auction, query_debt_auction, query_debt_auction_params, update_debt_auction_params,
+    write_off_bad_debt,
+};
+use crate::deposit::{
+    compute_exchange_rate_raw, deposit_stable, query_aterra_supply, query_depositor_info,
+    query_depositors, query_simulate_deposit, query_simulate_redeem, redeem_stable,
+    redeem_stable_exact,
+};
+use crate::donations::{donate_to_depositors, query_donations};
+use crate::exchange_rate_feed::{
+    disable_exchange_rate_oracle, feed_exchange_rate, query_aterra_exchange_rate,
+    update_exchange_rate_oracle, update_exchange_rate_twap_window,
+};
+use crate::flash_loan::{
+    finish_flash_loan, flash_loan, query_flash_loan, repay_flash_loan, update_flash_loan_fee,
+};
+use crate::flash_mint::{
+    finish_flash_mint, flash_mint, query_flash_mint, repay_flash_mint, update_flash_mint_fee,
+};
+use crate::flow_limit::{override_flow_limit, query_flow_limit};
+use crate::idle_deployment::{
+    deploy_idle_stable, query_idle_deployment, query_market_balance, recall_idle_stable,
+    update_idle_deployment,
+};
+use crate::invariants::{assert_invariants, query_invariant_checks, update_invariant_checks};
+use crate::keeper::{
+    poke_position, query_keeper_bounty, reset_keeper_bounty, update_keeper_bounty,
+};
+use crate::loans::{
+    apply_loan_penalty, open_loan, payoff_loan, query_amortizing_loan, query_loan_params,
+    repay_installment, update_loan_params,
 };
-use crate::deposit::{compute_exchange_rate_raw, deposit_stable, redeem_stable};
 use crate::migration::{migrate_config, migrate_state};
+use crate::operation_mode::{query_operation_mode, set_pause, update_operation_mode};
+use crate::prune::prune_closed_positions;
 use crate::querier::{query_anc_emission_rate, query_borrow_rate, query_target_deposit_rate};
-use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use crate::rate_snapshots::query_rate_snapshot;
+use crate::redemption_fee::{
+    query_fee_exempt_redeemers, query_redemption_fee, update_fee_exempt_redeemer,
+    update_redemption_fee,
+};
+use crate::redemption_queue::{claim_redemption, query_queued_redemptions, redeem_stable_or_queue};
+use crate::registry::{
+    assert_registry_synced, disable_registry, query_registry, refresh_registry, register_registry,
+};
+use crate::repayments::{query_dust_forgiveness, query_loan_breakdown, update_dust_forgiveness};
+use crate::reserves::withdraw_reserves;
+use crate::reward_indexes::update_reward_indexes;
+use crate::shadow_interest_model::{
+    disable_shadow_interest_model, promote_shadow_interest_model, query_shadow_interest_model,
+    shadow_rate_logs, update_shadow_interest_model,
+};
+use crate::stable_denoms::{
+    add_stable_denom, find_stable_denom_by_aterra, query_stable_denoms, redeem_added_stable,
+    register_stable_denom_aterra,
+};
+use crate::stable_rate::{
+    query_rate_mode, query_stable_rate_params, rebalance_stable_rate, swap_rate_mode,
+    update_stable_rate_params,
+};
+use crate::state::{
+    read_cold_tranche, read_config, read_last_epoch_height, read_state, store_config,
+    store_last_epoch_height, store_state, Config, State,
+};
+use crate::state_history::{query_state_at, query_state_history, record_state_snapshot};
+use crate::tax::{deduct_tax_cached, query_tax_params, refresh_tax_params};
+use crate::time_accrual::enable_time_accrual;
+use crate::tranche::{
+    compute_cold_bonus, deposit_stable_cold, init_cold_tranche, query_cold_tranche,
+    query_cold_unbonding, register_cold_aterra, unbond_cold, withdraw_unbonded_cold,
+};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
@@ -15,12 +94,15 @@ use cosmwasm_std::{
 };
 use cw20::{Cw20CoinHuman, Cw20ReceiveMsg, MinterResponse};
 
+use moneymarket::error::ContractError;
 use moneymarket::interest_model::BorrowRateResponse;
 use moneymarket::market::{
     ConfigResponse, Cw20HookMsg, EpochStateResponse, HandleMsg, InitMsg, MigrateMsg, QueryMsg,
     StateResponse,
 };
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::ownership::{accept_ownership, propose_new_owner, query_ownership_proposal};
+use moneymarket::protocol_mode::{refresh_protocol_mode, register_protocol_controller};
+use moneymarket::querier::{query_balance, query_supply};
 use terraswap::hook::InitHook;
 use terraswap::token::InitMsg as TokenInitMsg;
 
@@ -36,14 +118,14 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
         .iter()
         .find(|c| c.denom == msg.stable_denom)
         .map(|c| c.amount)
-        .unwrap_or_else(|| Uint128::zero());
+        .unwrap_or_else(Uint128::zero);
 
     if initial_deposit != Uint128(INITIAL_DEPOSIT_AMOUNT) {
-        return Err(StdError::generic_err(format!(
-            "Must deposit initial funds {:?}{:?}",
-            INITIAL_DEPOSIT_AMOUNT,
-            msg.stable_denom.clone()
-        )));
+        return Err(ContractError::InitialDepositRequired {
+            amount: INITIAL_DEPOSIT_AMOUNT,
+            denom: msg.stable_denom.to_string(),
+        }
+        .into());
     }
 
     store_config(
@@ -59,6 +141,14 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
             distributor_contract: CanonicalAddr::default(),
             stable_denom: msg.stable_denom.clone(),
             max_borrow_factor: msg.max_borrow_factor,
+            global_borrow_cap: None,
+            per_account_borrow_cap: None,
+            min_deposit_amount: None,
+            min_redeem_amount: None,
+            flow_window: None,
+            max_net_flow_ratio: None,
+            guardian: None,
+            reserve_factor: Decimal256::zero(),
         },
     )?;
 
@@ -74,6 +164,15 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
             anc_emission_rate: msg.anc_emission_rate,
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )?;
 
@@ -111,6 +210,27 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     msg: HandleMsg,
+) -> HandleResult {
+    let block_height = env.block.height;
+    // the owner can still turn the checks off once they fail
+    let check_invariants = !matches!(msg, HandleMsg::UpdateInvariantChecks { .. });
+    assert_registry_synced(deps, block_height, env.block.time, &msg)?;
+    let mut res = dispatch(deps, env, msg)?;
+
+    if check_invariants {
+        assert_invariants(deps)?;
+    }
+
+    res.messages.extend(feed_exchange_rate(deps, block_height)?);
+    Ok(res)
+}
+
+/// Runs the handler of `msg`, without the registry sync and
+/// the invariant checks `handle` runs around it
+fn dispatch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
 ) -> HandleResult {
     match msg {
         HandleMsg::Receive(msg) => receive_cw20(deps, env, msg),
@@ -130,18 +250,170 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             distributor_contract,
         ),
         HandleMsg::UpdateConfig {
-            owner_addr,
             interest_model,
             distribution_model,
             max_borrow_factor,
+            global_borrow_cap,
+            per_account_borrow_cap,
+            min_deposit_amount,
+            min_redeem_amount,
+            flow_window,
+            max_net_flow_ratio,
+            guardian,
+            reserve_factor,
         } => update_config(
             deps,
             env,
-            owner_addr,
             interest_model,
             distribution_model,
             max_borrow_factor,
+            global_borrow_cap,
+            per_account_borrow_cap,
+            min_deposit_amount,
+            min_redeem_amount,
+            flow_window,
+            max_net_flow_ratio,
+            guardian,
+            reserve_factor,
+        ),
+        HandleMsg::ProposeNewOwner {
+            new_owner,
+            expiry_height,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_new_owner(deps, env, &config.owner_addr, new_owner, expiry_height)
+        }
+        HandleMsg::AcceptOwnership {} => accept_ownership(deps, env, |storage, new_owner| {
+            let mut config: Config = read_config(storage)?;
+            config.owner_addr = new_owner;
+            store_config(storage, &config)
+        }),
+        HandleMsg::InitColdTranche {
+            caterra_code_id,
+            redeem_delay,
+            bonus_rate,
+        } => init_cold_tranche(deps, env, caterra_code_id, redeem_delay, bonus_rate),
+        HandleMsg::SetCreditLine {
+            strategy,
+            cap,
+            interest_rate,
+            collateral_ratio,
+        } => set_credit_line(deps, env, strategy, cap, interest_rate, collateral_ratio),
+        HandleMsg::UpdateFlashMintFee { fee_rate } => update_flash_mint_fee(deps, env, fee_rate),
+        HandleMsg::UpdateFlashLoanFee { fee_rate } => update_flash_loan_fee(deps, env, fee_rate),
+        HandleMsg::DefaultCreditLine { strategy } => default_credit_line(deps, env, strategy),
+        HandleMsg::UpdateIdleDeployment {
+            venue,
+            utilization_threshold,
+            max_deploy_ratio,
+            cap,
+        } => update_idle_deployment(
+            deps,
+            env,
+            venue,
+            utilization_threshold,
+            max_deploy_ratio,
+            cap,
         ),
+        HandleMsg::UpdateLoanParams {
+            period_blocks,
+            max_installments,
+            penalty_rate,
+        } => update_loan_params(deps, env, period_blocks, max_installments, penalty_rate),
+        HandleMsg::UpdateDebtAuctionParams {
+            start_price,
+            floor_price,
+            price_decay_blocks,
+        } => update_debt_auction_params(deps, env, start_price, floor_price, price_decay_blocks),
+        HandleMsg::UpdateDustForgiveness { max_amount } => {
+            update_dust_forgiveness(deps, env, max_amount)
+        }
+        HandleMsg::WithdrawReserves { amount, recipient } => {
+            withdraw_reserves(deps, env, amount, recipient)
+        }
+        HandleMsg::UpdateKeeperBounty {
+            bounty_amount,
+            epoch_cap,
+            stale_blocks,
+        } => update_keeper_bounty(deps, env, bounty_amount, epoch_cap, stale_blocks),
+        HandleMsg::UpdateExchangeRateOracle { oracle } => {
+            update_exchange_rate_oracle(deps, env, oracle)
+        }
+        HandleMsg::DisableExchangeRateOracle {} => disable_exchange_rate_oracle(deps, env),
+        HandleMsg::UpdateExchangeRateTwapWindow { twap_window } => {
+            update_exchange_rate_twap_window(deps, env, twap_window)
+        }
+        HandleMsg::RegisterProtocolController {
+            protocol_controller,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            register_protocol_controller(deps, env, &config.owner_addr, protocol_controller)
+        }
+        HandleMsg::RegisterRegistry { registry_contract } => {
+            register_registry(deps, env, registry_contract)
+        }
+        HandleMsg::DisableRegistry {} => disable_registry(deps, env),
+        HandleMsg::AddStableDenom {
+            stable_denom,
+            aterra_code_id,
+        } => add_stable_denom(deps, env, stable_denom, aterra_code_id),
+        HandleMsg::UpdateCw20Stable {
+            token,
+            accept_deposits,
+            decimals,
+        } => update_cw20_stable(deps, env, token, accept_deposits, decimals),
+        HandleMsg::UpdateBootstrapMode {
+            end_height,
+            tvl_threshold,
+        } => update_bootstrap_mode(deps, env, end_height, tvl_threshold),
+        HandleMsg::EndBootstrapMode {} => end_bootstrap_mode(deps, env),
+        HandleMsg::UpdateInvariantChecks { enabled } => update_invariant_checks(deps, env, enabled),
+        HandleMsg::OverrideFlowLimit { enabled } => override_flow_limit(deps, env, enabled),
+        HandleMsg::SetAttestationContract {
+            attestation_contract,
+        } => set_attestation_contract(deps, env, attestation_contract),
+        HandleMsg::EnableTimeAccrual { seconds_per_block } => {
+            enable_time_accrual(deps, env, seconds_per_block)
+        }
+        HandleMsg::UpdateShadowInterestModel {
+            interest_model,
+            trial_blocks,
+        } => update_shadow_interest_model(deps, env, interest_model, trial_blocks),
+        HandleMsg::DisableShadowInterestModel {} => disable_shadow_interest_model(deps, env),
+        HandleMsg::PromoteShadowInterestModel {} => promote_shadow_interest_model(deps, env),
+        HandleMsg::AdminBatch { ops } => admin_batch(deps, env, ops),
+        HandleMsg::RegisterColdATerra {} => register_cold_aterra(deps, env),
+        HandleMsg::RegisterStableDenomATerra { stable_denom } => {
+            register_stable_denom_aterra(deps, env, stable_denom)
+        }
+        HandleMsg::UpdateRedemptionFee {
+            free_ratio,
+            max_fee_rate,
+        } => update_redemption_fee(deps, env, free_ratio, max_fee_rate),
+        HandleMsg::UpdateFeeExemptRedeemer { redeemer, exempt } => {
+            update_fee_exempt_redeemer(deps, env, redeemer, exempt)
+        }
+        HandleMsg::FinishFlashMint {} => finish_flash_mint(deps, env),
+        HandleMsg::FinishFlashLoan {} => finish_flash_loan(deps, env),
+        HandleMsg::UpdateOperationMode {
+            deposits_enabled,
+            redemptions_enabled,
+            borrows_enabled,
+            repayments_enabled,
+        } => update_operation_mode(
+            deps,
+            env,
+            deposits_enabled,
+            redemptions_enabled,
+            borrows_enabled,
+            repayments_enabled,
+        ),
+        HandleMsg::SetPause {
+            deposit,
+            redeem,
+            borrow,
+            repay,
+        } => set_pause(deps, env, deposit, redeem, borrow, repay),
         HandleMsg::ExecuteEpochOperations {
             deposit_rate,
             target_deposit_rate,
@@ -155,16 +427,70 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             threshold_deposit_rate,
             distributed_interest,
         ),
-        HandleMsg::DepositStable {} => deposit_stable(deps, env),
-        HandleMsg::BorrowStable { borrow_amount, to } => {
-            borrow_stable(deps, env, borrow_amount, to)
+        HandleMsg::DepositStable {
+            recipient,
+            amount,
+            decimals,
+        } => deposit_stable(deps, env, recipient, amount, decimals),
+        HandleMsg::RedeemStableExact { stable_amount } => {
+            redeem_stable_exact(deps, env, stable_amount)
         }
+        HandleMsg::ClaimRedemption { id } => claim_redemption(deps, env, id),
+        HandleMsg::DepositStableCold {} => deposit_stable_cold(deps, env),
+        HandleMsg::DonateToDepositors { amount } => donate_to_depositors(deps, env, amount),
+        HandleMsg::SwapCw20Stable { token } => swap_cw20_stable(deps, env, token),
+        HandleMsg::WithdrawUnbondedCold {} => withdraw_unbonded_cold(deps, env),
+        HandleMsg::BorrowStable {
+            borrow_amount,
+            to,
+            deposit,
+            decimals,
+        } => borrow_stable(deps, env, borrow_amount, to, deposit, decimals),
         HandleMsg::RepayStable {} => repay_stable(deps, env),
+        HandleMsg::RepayStableFor { borrower } => repay_stable_for(deps, env, borrower),
+        HandleMsg::OpenLoan {
+            amount,
+            installments,
+            to,
+        } => open_loan(deps, env, amount, installments, to),
+        HandleMsg::RepayInstallment {} => repay_installment(deps, env),
+        HandleMsg::PayoffLoan {} => payoff_loan(deps, env),
+        HandleMsg::ApplyLoanPenalty { borrower } => apply_loan_penalty(deps, env, borrower),
+        HandleMsg::BidDebtAuction {} => bid_debt_auction(deps, env),
+        HandleMsg::PokePosition { borrower } => poke_position(deps, env, borrower),
+        HandleMsg::RebalanceStableRate { borrower } => rebalance_stable_rate(deps, env, borrower),
+        HandleMsg::SwapRateMode {} => swap_rate_mode(deps, env),
+        HandleMsg::UpdateStableRateParams {
+            premium,
+            rebalance_threshold,
+        } => update_stable_rate_params(deps, env, premium, rebalance_threshold),
+        HandleMsg::RefreshProtocolMode {} => refresh_protocol_mode(deps, env),
+        HandleMsg::RefreshRegistry {} => refresh_registry(deps, env),
         HandleMsg::RepayStableFromLiquidation {
             borrower,
             prev_balance,
         } => repay_stable_from_liquidation(deps, env, borrower, prev_balance),
+        HandleMsg::RebateInterest { borrower, amount } => {
+            rebate_interest(deps, env, borrower, amount)
+        }
+        HandleMsg::TransferLiability { from, to } => transfer_liability(deps, env, from, to),
+        HandleMsg::WriteOffBadDebt { borrower } => write_off_bad_debt(deps, env, borrower),
         HandleMsg::ClaimRewards { to } => claim_rewards(deps, env, to),
+        HandleMsg::FlashMint { amount, msg } => flash_mint(deps, env, amount, msg),
+        HandleMsg::FlashLoan { amount, msg } => flash_loan(deps, env, amount, msg),
+        HandleMsg::RepayFlashLoan {} => repay_flash_loan(deps, env),
+        HandleMsg::DeployIdleStable {} => deploy_idle_stable(deps, env),
+        HandleMsg::RecallIdleStable { amount } => recall_idle_stable(deps, env, amount),
+        HandleMsg::RefreshTaxParams {} => refresh_tax_params(deps, env),
+        HandleMsg::PruneClosedPositions { limit } => prune_closed_positions(deps, env, limit),
+        HandleMsg::UpdateRewardIndexes { start_after, limit } => {
+            update_reward_indexes(deps, env, start_after, limit)
+        }
+        HandleMsg::DrawCredit { amount, to } => draw_credit(deps, env, amount, to),
+        HandleMsg::RepayCredit { strategy } => repay_credit(deps, env, strategy),
+        HandleMsg::WithdrawCreditCollateral { amount } => {
+            withdraw_credit_collateral(deps, env, amount)
+        }
     }
 }
 
@@ -177,19 +503,72 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
     if let Some(msg) = cw20_msg.msg {
         match from_binary(&msg)? {
             Cw20HookMsg::RedeemStable {} => {
+                // only asset contracts can execute this message
+                let config: Config = read_config(&deps.storage)?;
+                let aterra_contract = deps.api.canonical_address(&contract_addr)?;
+                if aterra_contract == config.aterra_contract {
+                    return redeem_stable(deps, env, cw20_msg.sender, cw20_msg.amount);
+                }
+
+                match find_stable_denom_by_aterra(&deps.storage, &aterra_contract)? {
+                    Some(stable_denom) => redeem_added_stable(
+                        deps,
+                        env,
+                        stable_denom,
+                        cw20_msg.sender,
+                        cw20_msg.amount,
+                    ),
+                    None => Err(StdError::unauthorized()),
+                }
+            }
+            Cw20HookMsg::RedeemStableOrQueue {} => {
+                // only asset contracts can execute this message
+                let config: Config = read_config(&deps.storage)?;
+                if deps.api.canonical_address(&contract_addr)? != config.aterra_contract {
+                    return Err(StdError::unauthorized());
+                }
+
+                redeem_stable_or_queue(deps, env, cw20_msg.sender, cw20_msg.amount)
+            }
+            Cw20HookMsg::UnbondCold {} => {
+                // only cold asset contract can execute this message
+                let tranche = read_cold_tranche(&deps.storage)?;
+                if tranche.map(|v| v.caterra_contract)
+                    != Some(deps.api.canonical_address(&contract_addr)?)
+                {
+                    return Err(StdError::unauthorized());
+                }
+
+                unbond_cold(deps, env, cw20_msg.sender, cw20_msg.amount.into())
+            }
+            Cw20HookMsg::PostCreditCollateral {} => {
+                // only asset contract can execute this message
+                let config: Config = read_config(&deps.storage)?;
+                if deps.api.canonical_address(&contract_addr)? != config.aterra_contract {
+                    return Err(StdError::unauthorized());
+                }
+
+                post_credit_collateral(deps, cw20_msg.sender, cw20_msg.amount.into())
+            }
+            Cw20HookMsg::RepayFlashMint {} => {
                 // only asset contract can execute this message
                 let config: Config = read_config(&deps.storage)?;
                 if deps.api.canonical_address(&contract_addr)? != config.aterra_contract {
                     return Err(StdError::unauthorized());
                 }
 
-                redeem_stable(deps, env, cw20_msg.sender, cw20_msg.amount)
+                repay_flash_mint(&mut deps.storage, cw20_msg.sender, cw20_msg.amount.into())
+            }
+            Cw20HookMsg::DepositStable {} => {
+                // only accepted cw20 stables can execute this message
+                deposit_cw20_stable(deps, env, cw20_msg.sender, cw20_msg.amount.into())
             }
         }
     } else {
-        Err(StdError::generic_err(
-            "Invalid request: \"redeem stable\" message not included in request",
-        ))
+        Err(ContractError::MissingHookMsg {
+            msg: "redeem stable".to_string(),
+        }
+        .into())
     }
 }
 
@@ -240,13 +619,89 @@ pub fn register_contracts<S: Storage, A: Api, Q: Querier>(
     Ok(HandleResponse::default())
 }
 
+/// Executes the owner operations in order within one handle call,
+/// so a failing operation reverts the operations applied before it
+/// and the invariants are only checked once the whole batch ran
+pub fn admin_batch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ops: Vec<HandleMsg>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if ops.is_empty() {
+        return Err(ContractError::EmptyAdminBatch {}.into());
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut logs = vec![log("action", "admin_batch"), log("ops", ops.len())];
+    for op in ops {
+        match op {
+            HandleMsg::UpdateConfig { .. }
+            | HandleMsg::ProposeNewOwner { .. }
+            | HandleMsg::InitColdTranche { .. }
+            | HandleMsg::UpdateRedemptionFee { .. }
+            | HandleMsg::UpdateFeeExemptRedeemer { .. }
+            | HandleMsg::UpdateFlashMintFee { .. }
+            | HandleMsg::UpdateFlashLoanFee { .. }
+            | HandleMsg::SetCreditLine { .. }
+            | HandleMsg::DefaultCreditLine { .. }
+            | HandleMsg::UpdateIdleDeployment { .. }
+            | HandleMsg::UpdateLoanParams { .. }
+            | HandleMsg::UpdateDebtAuctionParams { .. }
+            | HandleMsg::UpdateDustForgiveness { .. }
+            | HandleMsg::WithdrawReserves { .. }
+            | HandleMsg::UpdateKeeperBounty { .. }
+            | HandleMsg::UpdateStableRateParams { .. }
+            | HandleMsg::UpdateExchangeRateOracle { .. }
+            | HandleMsg::DisableExchangeRateOracle { .. }
+            | HandleMsg::UpdateExchangeRateTwapWindow { .. }
+            | HandleMsg::RegisterProtocolController { .. }
+            | HandleMsg::RegisterRegistry { .. }
+            | HandleMsg::DisableRegistry {}
+            | HandleMsg::UpdateCw20Stable { .. }
+            | HandleMsg::UpdateShadowInterestModel { .. }
+            | HandleMsg::DisableShadowInterestModel {}
+            | HandleMsg::PromoteShadowInterestModel {}
+            | HandleMsg::UpdateBootstrapMode { .. }
+            | HandleMsg::EndBootstrapMode {}
+            | HandleMsg::OverrideFlowLimit { .. }
+            | HandleMsg::EnableTimeAccrual { .. }
+            | HandleMsg::SetAttestationContract { .. }
+            | HandleMsg::SetPause { .. } => {}
+            _ => return Err(ContractError::NotOwnerOperation {}.into()),
+        }
+
+        let res = dispatch(deps, env.clone(), op)?;
+        messages.extend(res.messages);
+        logs.extend(res.log);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    owner_addr: Option<HumanAddr>,
     interest_model: Option<HumanAddr>,
     distribution_model: Option<HumanAddr>,
     max_borrow_factor: Option<Decimal256>,
+    global_borrow_cap: Option<Uint256>,
+    per_account_borrow_cap: Option<Uint256>,
+    min_deposit_amount: Option<Uint256>,
+    min_redeem_amount: Option<Uint256>,
+    flow_window: Option<u64>,
+    max_net_flow_ratio: Option<Decimal256>,
+    guardian: Option<HumanAddr>,
+    reserve_factor: Option<Decimal256>,
 ) -> HandleResult {
     let mut config: Config = read_config(&deps.storage)?;
 
@@ -255,18 +710,36 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    if let Some(owner_addr) = owner_addr {
-        config.owner_addr = deps.api.canonical_address(&owner_addr)?;
+    if let Some(reserve_factor) = reserve_factor {
+        if reserve_factor > Decimal256::one() {
+            return Err(ContractError::RatioAboveOne {
+                name: "reserve_factor".to_string(),
+            }
+            .into());
+        }
     }
 
-    if interest_model.is_some() {
+    // interest accrued so far is split with the previous parameters
+    let mut accrual_logs = vec![];
+    if interest_model.is_some() || reserve_factor.is_some() {
         let mut state: State = read_state(&deps.storage)?;
-        compute_interest(&deps, &config, &mut state, env.block.height, None)?;
+        accrual_logs = compute_interest(
+            deps,
+            &config,
+            &mut state,
+            env.block.height,
+            env.block.time,
+            None,
+        )?;
         store_state(&mut deps.storage, &state)?;
 
         if let Some(interest_model) = interest_model {
             config.interest_model = deps.api.canonical_address(&interest_model)?;
         }
+
+        if let Some(reserve_factor) = reserve_factor {
+            config.reserve_factor = reserve_factor;
+        }
     }
 
     if let Some(distribution_model) = distribution_model {
@@ -277,10 +750,38 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         config.max_borrow_factor = max_borrow_factor;
     }
 
+    if let Some(global_borrow_cap) = global_borrow_cap {
+        config.global_borrow_cap = Some(global_borrow_cap).filter(|v| !v.is_zero());
+    }
+
+    if let Some(per_account_borrow_cap) = per_account_borrow_cap {
+        config.per_account_borrow_cap = Some(per_account_borrow_cap).filter(|v| !v.is_zero());
+    }
+
+    if let Some(min_deposit_amount) = min_deposit_amount {
+        config.min_deposit_amount = Some(min_deposit_amount).filter(|v| !v.is_zero());
+    }
+
+    if let Some(min_redeem_amount) = min_redeem_amount {
+        config.min_redeem_amount = Some(min_redeem_amount).filter(|v| !v.is_zero());
+    }
+
+    if let Some(flow_window) = flow_window {
+        config.flow_window = Some(flow_window).filter(|v| *v != 0);
+    }
+
+    if let Some(max_net_flow_ratio) = max_net_flow_ratio {
+        config.max_net_flow_ratio = Some(max_net_flow_ratio).filter(|v| !v.is_zero());
+    }
+
+    if let Some(guardian) = guardian {
+        config.guardian = Some(deps.api.canonical_address(&guardian)?);
+    }
+
     store_config(&mut deps.storage, &config)?;
     Ok(HandleResponse {
         messages: vec![],
-        log: vec![log("action", "update_config")],
+        log: [vec![log("action", "update_config")], accrual_logs].concat(),
         data: None,
     })
 }
@@ -298,64 +799,116 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
+    // The emission rate compounds on every execution
+    let last_epoch_height = read_last_epoch_height(&deps.storage)?;
+    if env.block.height <= last_epoch_height {
+        return Err(ContractError::EpochAlreadyExecuted {
+            last_executed_height: last_epoch_height,
+        }
+        .into());
+    }
+
     let mut state: State = read_state(&deps.storage)?;
 
-    // Compute interest and reward before updating anc_emission_rate
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
-    let balance: Uint256 = query_balance(
-        &deps,
-        &deps.api.human_address(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - distributed_interest;
+    // Compute interest and reward before updating anc_emission_rate.
+    // Interest already accrued at this height by another handler left the
+    // exchange rate and the aterra supply of the state current, so the
+    // accrual queries are skipped
+    let (aterra_supply, borrow_rate, accrual, shadow_logs, clamp_logs) =
+        if state.last_interest_updated < env.block.height {
+            let aterra_supply = query_aterra_supply(deps, &config)?;
+            let balance: Uint256 = query_market_balance(deps, &config)? - distributed_interest;
 
-    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-        &deps,
-        &deps.api.human_address(&config.interest_model)?,
-        balance,
-        state.total_liabilities,
-        state.total_reserves,
-    )?;
+            let (model_reserves, excess_reserves) = clamp_model_reserves(&state, balance);
+            let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+                deps,
+                &deps.api.human_address(&config.interest_model)?,
+                balance,
+                state.total_liabilities,
+                model_reserves,
+            )?;
 
-    compute_interest_raw(
-        &mut state,
-        env.block.height,
-        balance,
-        aterra_supply,
-        borrow_rate_res.rate,
-        target_deposit_rate,
-    );
+            let shadow_logs = shadow_rate_logs(
+                deps,
+                env.block.height,
+                balance,
+                state.total_liabilities,
+                model_reserves,
+            )?;
+
+            let accrual = compute_interest_raw(
+                &mut state,
+                env.block.height,
+                env.block.time,
+                balance,
+                aterra_supply,
+                borrow_rate_res.rate,
+                target_deposit_rate,
+                config.reserve_factor,
+            );
+            let clamp_logs = record_utilization_clamp(&mut state, excess_reserves);
+
+            // recompute prev_exchange_rate with distributed_interest
+            state.prev_exchange_rate =
+                compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest);
+
+            (
+                aterra_supply,
+                borrow_rate_res.rate,
+                accrual,
+                shadow_logs,
+                clamp_logs,
+            )
+        } else {
+            // add distributed_interest to the current prev_exchange_rate
+            if !state.prev_aterra_supply.is_zero() {
+                state.prev_exchange_rate += Decimal256::from_uint256(distributed_interest)
+                    / Decimal256::from_uint256(state.prev_aterra_supply);
+            }
 
-    // recompute prev_exchange_rate with distributed_interest
-    state.prev_exchange_rate =
-        compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest);
+            (
+                state.prev_aterra_supply,
+                state.last_borrow_rate,
+                None,
+                vec![],
+                vec![],
+            )
+        };
 
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.height, env.block.time);
+
+    // Pay the cold tranche bonus before sweeping the reserves
+    let mut messages: Vec<CosmosMsg> = compute_cold_bonus(deps, &env, &config, &mut state)?;
 
     // Compute total_reserves to fund collector contract
     // Update total_reserves and send it to collector contract
-    // only when there is enough balance
+    // only when there is enough balance, excluding the deployed stable
     let total_reserves = state.total_reserves * Uint256::one();
-    let messages: Vec<CosmosMsg> = if !total_reserves.is_zero() && balance > total_reserves {
+    let contract_balance = query_balance(
+        deps,
+        &deps.api.human_address(&config.contract_addr)?,
+        config.stable_denom.to_string(),
+    )? - distributed_interest;
+    if !total_reserves.is_zero() && contract_balance > total_reserves {
         state.total_reserves = state.total_reserves - Decimal256::from_uint256(total_reserves);
 
-        vec![CosmosMsg::Bank(BankMsg::Send {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
             from_address: env.contract.address,
             to_address: deps.api.human_address(&config.collector_contract)?,
-            amount: vec![deduct_tax(
-                &deps,
+            amount: vec![deduct_tax_cached(
+                deps,
+                env.block.height,
                 Coin {
                     denom: config.stable_denom,
                     amount: total_reserves.into(),
                 },
             )?],
-        })]
-    } else {
-        vec![]
-    };
+        }));
+    }
 
     // Query updated anc_emission_rate
     state.anc_emission_rate = query_anc_emission_rate(
-        &deps,
+        deps,
         &deps.api.human_address(&config.distribution_model)?,
         deposit_rate,
         target_deposit_rate,
@@ -365,16 +918,32 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
     .emission_rate;
 
     store_state(&mut deps.storage, &state)?;
+    store_last_epoch_height(&mut deps.storage, &env.block.height)?;
+    record_state_snapshot(
+        &mut deps.storage,
+        env.block.height,
+        &state,
+        aterra_supply,
+        deposit_rate,
+        borrow_rate,
+    )?;
+    reset_keeper_bounty(&mut deps.storage)?;
 
-    return Ok(HandleResponse {
+    Ok(HandleResponse {
         messages,
-        log: vec![
-            log("action", "execute_epoch_operations"),
-            log("total_reserves", total_reserves),
-            log("anc_emission_rate", state.anc_emission_rate),
-        ],
+        log: [
+            vec![
+                log("action", "execute_epoch_operations"),
+                log("total_reserves", total_reserves),
+                log("anc_emission_rate", state.anc_emission_rate),
+            ],
+            accrual.map(|v| v.to_logs()).unwrap_or_default(),
+            shadow_logs,
+            clamp_logs,
+        ]
+        .concat(),
         data: None,
-    });
+    })
 }
 
 pub fn query<S: Storage, A: Api, Q: Querier>(
@@ -383,6 +952,7 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
         QueryMsg::State { block_height } => to_binary(&query_state(deps, block_height)?),
         QueryMsg::EpochState {
             block_height,
@@ -396,9 +966,84 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             borrower,
             block_height,
         } => to_binary(&query_borrower_info(deps, borrower, block_height)?),
-        QueryMsg::BorrowerInfos { start_after, limit } => {
-            to_binary(&query_borrower_infos(deps, start_after, limit)?)
+        QueryMsg::BorrowerInfos {
+            start_after,
+            limit,
+            min_liability,
+            only_liquidatable,
+        } => to_binary(&query_borrower_infos(
+            deps,
+            start_after,
+            limit,
+            min_liability,
+            only_liquidatable,
+        )?),
+        QueryMsg::ColdTranche {} => to_binary(&query_cold_tranche(deps)?),
+        QueryMsg::ColdUnbonding { address } => to_binary(&query_cold_unbonding(deps, address)?),
+        QueryMsg::RedemptionFee {} => to_binary(&query_redemption_fee(deps)?),
+        QueryMsg::FeeExemptRedeemers {} => to_binary(&query_fee_exempt_redeemers(deps)?),
+        QueryMsg::QueuedRedemptions { start_after, limit } => {
+            to_binary(&query_queued_redemptions(deps, start_after, limit)?)
+        }
+        QueryMsg::DepositorInfo { depositor } => to_binary(&query_depositor_info(deps, depositor)?),
+        QueryMsg::Depositor { address } => to_binary(&query_depositor_info(deps, address)?),
+        QueryMsg::Depositors { start_after, limit } => {
+            to_binary(&query_depositors(deps, start_after, limit)?)
+        }
+        QueryMsg::CreditLine {
+            strategy,
+            block_height,
+        } => to_binary(&query_credit_line(deps, strategy, block_height)?),
+        QueryMsg::CreditLines { start_after, limit } => {
+            to_binary(&query_credit_lines(deps, start_after, limit)?)
+        }
+        QueryMsg::FlashMint {} => to_binary(&query_flash_mint(deps)?),
+        QueryMsg::FlashLoan {} => to_binary(&query_flash_loan(deps)?),
+        QueryMsg::IdleDeployment {} => to_binary(&query_idle_deployment(deps)?),
+        QueryMsg::TaxParams {} => to_binary(&query_tax_params(deps)?),
+        QueryMsg::OperationMode {} => to_binary(&query_operation_mode(deps)?),
+        QueryMsg::Registry {} => to_binary(&query_registry(deps)?),
+        QueryMsg::LoanParams {} => to_binary(&query_loan_params(deps)?),
+        QueryMsg::AmortizingLoan {
+            borrower,
+            block_height,
+        } => to_binary(&query_amortizing_loan(deps, borrower, block_height)?),
+        QueryMsg::DebtAuctionParams {} => to_binary(&query_debt_auction_params(deps)?),
+        QueryMsg::DebtAuction { block_height } => {
+            to_binary(&query_debt_auction(deps, block_height)?)
+        }
+        QueryMsg::ATerraExchangeRate { block_height } => {
+            to_binary(&query_aterra_exchange_rate(deps, block_height)?)
+        }
+        QueryMsg::DustForgiveness {} => to_binary(&query_dust_forgiveness(deps)?),
+        QueryMsg::Donations {} => to_binary(&query_donations(deps)?),
+        QueryMsg::KeeperBounty {} => to_binary(&query_keeper_bounty(deps)?),
+        QueryMsg::StableRateParams {} => to_binary(&query_stable_rate_params(deps)?),
+        QueryMsg::RateMode { borrower } => to_binary(&query_rate_mode(deps, borrower)?),
+        QueryMsg::LoanBreakdown {
+            borrower,
+            block_height,
+        } => to_binary(&query_loan_breakdown(deps, borrower, block_height)?),
+        QueryMsg::RateSnapshot { borrower } => to_binary(&query_rate_snapshot(deps, borrower)?),
+        QueryMsg::StateAt { height } => to_binary(&query_state_at(deps, height)?),
+        QueryMsg::StateHistory { start_after, limit } => {
+            to_binary(&query_state_history(deps, start_after, limit)?)
         }
+        QueryMsg::StableDenoms {} => to_binary(&query_stable_denoms(deps)?),
+        QueryMsg::Cw20Stables {} => to_binary(&query_cw20_stables(deps)?),
+        QueryMsg::BootstrapMode {} => to_binary(&query_bootstrap_mode(deps)?),
+        QueryMsg::SimulateDeposit {
+            amount,
+            block_height,
+        } => to_binary(&query_simulate_deposit(deps, amount, block_height)?),
+        QueryMsg::SimulateRedeem {
+            burn_amount,
+            block_height,
+        } => to_binary(&query_simulate_redeem(deps, burn_amount, block_height)?),
+        QueryMsg::InvariantChecks {} => to_binary(&query_invariant_checks(deps)?),
+        QueryMsg::FlowLimit {} => to_binary(&query_flow_limit(deps)?),
+        QueryMsg::AttestationContract {} => to_binary(&query_attestation_contract(deps)?),
+        QueryMsg::ShadowInterestModel {} => to_binary(&query_shadow_interest_model(deps)?),
     }
 }
 
@@ -416,6 +1061,17 @@ pub fn query_config<S: Storage, A: Api, Q: Querier>(
         distributor_contract: deps.api.human_address(&config.distributor_contract)?,
         stable_denom: config.stable_denom,
         max_borrow_factor: config.max_borrow_factor,
+        global_borrow_cap: config.global_borrow_cap,
+        per_account_borrow_cap: config.per_account_borrow_cap,
+        min_deposit_amount: config.min_deposit_amount,
+        min_redeem_amount: config.min_redeem_amount,
+        flow_window: config.flow_window,
+        max_net_flow_ratio: config.max_net_flow_ratio,
+        guardian: match config.guardian {
+            Some(v) => Some(deps.api.human_address(&v)?),
+            None => None,
+        },
+        reserve_factor: config.reserve_factor,
     })
 }
 
@@ -427,24 +1083,21 @@ pub fn query_state<S: Storage, A: Api, Q: Querier>(
 
     if let Some(block_height) = block_height {
         if block_height < state.last_interest_updated {
-            return Err(StdError::generic_err(
-                "block_height must bigger than last_interest_updated",
-            ));
+            return Err(ContractError::BlockHeightBeforeInterestUpdate {}.into());
         }
 
         if block_height < state.last_reward_updated {
-            return Err(StdError::generic_err(
-                "block_height must bigger than last_reward_updated",
-            ));
+            return Err(ContractError::BlockHeightBeforeRewardUpdate {}.into());
         }
 
         let config: Config = read_config(&deps.storage)?;
 
         // Compute interest rate with given block height
-        compute_interest(&deps, &config, &mut state, block_height, None)?;
+        let block_time = estimate_block_time(&state, block_height);
+        compute_interest(deps, &config, &mut state, block_height, block_time, None)?;
 
         // Compute reward rate with given block height
-        compute_reward(&mut state, block_height);
+        compute_reward(&mut state, block_height, block_time);
     }
 
     Ok(StateResponse {
@@ -457,6 +1110,15 @@ pub fn query_state<S: Storage, A: Api, Q: Querier>(
         anc_emission_rate: state.anc_emission_rate,
         prev_aterra_supply: state.prev_aterra_supply,
         prev_exchange_rate: state.prev_exchange_rate,
+        total_credit_liabilities: state.total_credit_liabilities,
+        credit_interest_per_block: state.credit_interest_per_block,
+        utilization_clamps: state.utilization_clamps,
+        seconds_per_block: state.accrual_clock.as_ref().map(|v| v.seconds_per_block),
+        last_interest_updated_time: state
+            .accrual_clock
+            .as_ref()
+            .map(|v| v.last_interest_updated_time),
+        forgiven_dust: state.forgiven_dust,
     })
 }
 
@@ -469,39 +1131,37 @@ pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
     let mut state: State = read_state(&deps.storage)?;
 
     let distributed_interest = distributed_interest.unwrap_or(Uint256::zero());
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
-    let balance = query_balance(
-        &deps,
-        &deps.api.human_address(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - distributed_interest;
+    let aterra_supply = query_aterra_supply(deps, &config)?;
+    let balance = query_market_balance(deps, &config)? - distributed_interest;
 
     let exchange_rate = if let Some(block_height) = block_height {
         if block_height < state.last_interest_updated {
-            return Err(StdError::generic_err(
-                "block_height must bigger than last_interest_updated",
-            ));
+            return Err(ContractError::BlockHeightBeforeInterestUpdate {}.into());
         }
 
+        let (model_reserves, _) = clamp_model_reserves(&state, balance);
         let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-            &deps,
+            deps,
             &deps.api.human_address(&config.interest_model)?,
             balance,
             state.total_liabilities,
-            state.total_reserves,
+            model_reserves,
         )?;
 
         let target_deposit_rate: Decimal256 =
-            query_target_deposit_rate(&deps, &deps.api.human_address(&config.overseer_contract)?)?;
+            query_target_deposit_rate(deps, &deps.api.human_address(&config.overseer_contract)?)?;
 
         // Compute interest rate to return latest epoch state
+        let block_time = estimate_block_time(&state, block_height);
         compute_interest_raw(
             &mut state,
             block_height,
+            block_time,
             balance,
             aterra_supply,
             borrow_rate_res.rate,
             target_deposit_rate,
+            config.reserve_factor,
         );
 
         // compute_interest_raw store current exchange rate
@@ -514,6 +1174,10 @@ pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
     Ok(EpochStateResponse {
         exchange_rate,
         aterra_supply,
+        market_balance: balance + distributed_interest,
+        total_liabilities: state.total_liabilities,
+        total_credit_liabilities: state.total_credit_liabilities,
+        total_reserves: state.total_reserves,
     })
 }
 
@@ -530,8 +1194,8 @@ pub fn migrate<S: Storage, A: Api, Q: Querier>(
     )?;
 
     let config: Config = read_config(&deps.storage)?;
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
-    let balance = query_balance(&deps, &env.contract.address, config.stable_denom)?;
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    let balance = query_balance(deps, &env.contract.address, config.stable_denom)?;
 
     // migrate state to use new State
     migrate_state(&mut deps.storage, aterra_supply, balance)?;
@@ -0,0 +1,534 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, Env, Extern, HandleResponse,
+    HandleResult, HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+
+use crate::borrow::compute_interest;
+use crate::deposit::compute_exchange_rate;
+use crate::idle_deployment::{query_market_balance, recall_shortfall};
+use crate::operation_mode::assert_borrowing_enabled;
+use crate::state::{
+    read_config, read_credit_line, read_credit_lines, read_state, store_credit_line, store_state,
+    Config, CreditLine, State,
+};
+use crate::tax::deduct_tax_cached;
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{CreditLineResponse, CreditLinesResponse};
+
+pub fn set_credit_line<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    strategy: HumanAddr,
+    cap: Uint256,
+    interest_rate: Decimal256,
+    collateral_ratio: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let strategy_raw = deps.api.canonical_address(&strategy)?;
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+
+    let mut credit_line = match read_credit_line(&deps.storage, &strategy_raw)? {
+        Some(v) if v.defaulted => return Err(ContractError::CreditLineDefaulted {}.into()),
+        Some(v) => v,
+        None => CreditLine {
+            cap,
+            interest_rate,
+            collateral_ratio,
+            loan_amount: Decimal256::zero(),
+            collateral_amount: Uint256::zero(),
+            last_accrued: env.block.height,
+            defaulted: false,
+            accrued_periods: Some(state.accrued_periods),
+        },
+    };
+
+    // settle the interest at the previous rate before switching
+    accrue_credit_line(&mut credit_line, state.accrued_periods, env.block.height);
+    let loan_amount = credit_line.loan_amount;
+    update_loan_amount(&mut state, &mut credit_line, Decimal256::zero());
+    credit_line.interest_rate = interest_rate;
+    update_loan_amount(&mut state, &mut credit_line, loan_amount);
+
+    credit_line.cap = cap;
+    credit_line.collateral_ratio = collateral_ratio;
+
+    store_credit_line(&mut deps.storage, &strategy_raw, &credit_line)?;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "set_credit_line"),
+                log("strategy", strategy),
+                log("cap", cap),
+                log("interest_rate", interest_rate),
+                log("collateral_ratio", collateral_ratio),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn draw_credit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+    to: Option<HumanAddr>,
+) -> HandleResult {
+    assert_borrowing_enabled(deps, env.block.height)?;
+    let config: Config = read_config(&deps.storage)?;
+    let strategy = env.message.sender.clone();
+    let strategy_raw = deps.api.canonical_address(&strategy)?;
+    let mut credit_line = read_active_credit_line(&deps.storage, &strategy_raw)?;
+
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    accrue_credit_line(&mut credit_line, state.accrued_periods, env.block.height);
+
+    let loan_amount = credit_line.loan_amount + Decimal256::from_uint256(amount);
+    if loan_amount > Decimal256::from_uint256(credit_line.cap) {
+        return Err(ContractError::CreditLineCapExceeded {
+            cap: credit_line.cap,
+        }
+        .into());
+    }
+
+    let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
+    assert_credit_collateral(&credit_line, loan_amount, exchange_rate)?;
+
+    let current_balance = query_market_balance(deps, &config)?;
+    if Decimal256::from_uint256(amount) + state.total_reserves
+        > Decimal256::from_uint256(current_balance)
+    {
+        return Err(ContractError::NoLiquidity {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
+    }
+
+    update_loan_amount(&mut state, &mut credit_line, loan_amount);
+    store_credit_line(&mut deps.storage, &strategy_raw, &credit_line)?;
+    store_state(&mut deps.storage, &state)?;
+
+    let mut messages = recall_shortfall(deps, &config, amount)?;
+    messages.push(CosmosMsg::Bank(BankMsg::Send {
+        from_address: env.contract.address,
+        to_address: to.unwrap_or_else(|| strategy.clone()),
+        amount: vec![deduct_tax_cached(
+            deps,
+            env.block.height,
+            Coin {
+                denom: config.stable_denom,
+                amount: amount.into(),
+            },
+        )?],
+    }));
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![
+                log("action", "draw_credit"),
+                log("strategy", strategy),
+                log("draw_amount", amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn repay_credit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    strategy: Option<HumanAddr>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    let amount: Uint256 = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
+    }
+
+    let strategy = strategy.unwrap_or_else(|| env.message.sender.clone());
+    let strategy_raw = deps.api.canonical_address(&strategy)?;
+    let mut credit_line = read_active_credit_line(&deps.storage, &strategy_raw)?;
+
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        Some(amount),
+    )?;
+    accrue_credit_line(&mut credit_line, state.accrued_periods, env.block.height);
+
+    // round the loan up, so a full repay leaves no dust behind
+    let mut loan_amount = credit_line.loan_amount * Uint256::one();
+    if Decimal256::from_uint256(loan_amount) < credit_line.loan_amount {
+        loan_amount += Uint256::one();
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let repay_amount = if loan_amount < amount {
+        // Payback left repay amount to sender
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: env.message.sender,
+            amount: vec![deduct_tax_cached(
+                deps,
+                env.block.height,
+                Coin {
+                    denom: config.stable_denom,
+                    amount: (amount - loan_amount).into(),
+                },
+            )?],
+        }));
+
+        loan_amount
+    } else {
+        amount
+    };
+
+    let remaining_loan = saturating_sub(
+        credit_line.loan_amount,
+        Decimal256::from_uint256(repay_amount),
+    );
+    update_loan_amount(&mut state, &mut credit_line, remaining_loan);
+    store_credit_line(&mut deps.storage, &strategy_raw, &credit_line)?;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![
+                log("action", "repay_credit"),
+                log("strategy", strategy),
+                log("repay_amount", repay_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn post_credit_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    strategy: HumanAddr,
+    amount: Uint256,
+) -> HandleResult {
+    let strategy_raw = deps.api.canonical_address(&strategy)?;
+    let mut credit_line = read_active_credit_line(&deps.storage, &strategy_raw)?;
+
+    credit_line.collateral_amount += amount;
+    store_credit_line(&mut deps.storage, &strategy_raw, &credit_line)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "post_credit_collateral"),
+            log("strategy", strategy),
+            log("collateral_amount", amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn withdraw_credit_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let strategy = env.message.sender.clone();
+    let strategy_raw = deps.api.canonical_address(&strategy)?;
+    let mut credit_line = read_active_credit_line(&deps.storage, &strategy_raw)?;
+    if amount > credit_line.collateral_amount {
+        return Err(ContractError::WithdrawExceedsEscrow {}.into());
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    accrue_credit_line(&mut credit_line, state.accrued_periods, env.block.height);
+
+    credit_line.collateral_amount = credit_line.collateral_amount - amount;
+    let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
+    assert_credit_collateral(&credit_line, credit_line.loan_amount, exchange_rate)?;
+
+    store_credit_line(&mut deps.storage, &strategy_raw, &credit_line)?;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&config.aterra_contract)?,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: strategy.clone(),
+                amount: amount.into(),
+            })?,
+        })],
+        log: [
+            vec![
+                log("action", "withdraw_credit_collateral"),
+                log("strategy", strategy),
+                log("collateral_amount", amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Burns the escrowed aterra needed to cover the loan, which keeps the
+/// exchange rate unchanged, and writes off the uncovered liability
+/// against the depositors. The surplus collateral is returned.
+pub fn default_credit_line<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    strategy: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let strategy_raw = deps.api.canonical_address(&strategy)?;
+    let mut credit_line = read_active_credit_line(&deps.storage, &strategy_raw)?;
+
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    accrue_credit_line(&mut credit_line, state.accrued_periods, env.block.height);
+
+    let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
+    let covered_amount = (credit_line.loan_amount / exchange_rate) * Uint256::one();
+    let seized_amount = std::cmp::min(covered_amount, credit_line.collateral_amount);
+    let returned_amount = credit_line.collateral_amount - seized_amount;
+    let written_off = saturating_sub(
+        credit_line.loan_amount,
+        Decimal256::from_uint256(seized_amount) * exchange_rate,
+    );
+
+    update_loan_amount(&mut state, &mut credit_line, Decimal256::zero());
+    credit_line.collateral_amount = Uint256::zero();
+    credit_line.defaulted = true;
+
+    let aterra_contract = deps.api.human_address(&config.aterra_contract)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !seized_amount.is_zero() {
+        state.prev_aterra_supply = state.prev_aterra_supply - seized_amount;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: aterra_contract.clone(),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Burn {
+                amount: seized_amount.into(),
+            })?,
+        }));
+    }
+
+    if !returned_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: aterra_contract,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: strategy.clone(),
+                amount: returned_amount.into(),
+            })?,
+        }));
+    }
+
+    store_credit_line(&mut deps.storage, &strategy_raw, &credit_line)?;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![
+                log("action", "default_credit_line"),
+                log("strategy", strategy),
+                log("seized_amount", seized_amount),
+                log("written_off", written_off),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+fn read_active_credit_line<S: Storage>(
+    storage: &S,
+    strategy: &CanonicalAddr,
+) -> StdResult<CreditLine> {
+    match read_credit_line(storage, strategy)? {
+        Some(v) if v.defaulted => Err(ContractError::CreditLineDefaulted {}.into()),
+        Some(v) => Ok(v),
+        None => Err(ContractError::NoCreditLine {}.into()),
+    }
+}
+
+/// Settles the line interest up to `accrued_periods`; the aggregate
+/// was already accrued by compute_interest over the same periods,
+/// block or time based. Lines stored before the periods were
+/// tracked accrue per block once
+fn accrue_credit_line(
+    credit_line: &mut CreditLine,
+    accrued_periods: Decimal256,
+    block_height: u64,
+) {
+    let passed_periods = match credit_line.accrued_periods {
+        Some(v) => saturating_sub(accrued_periods, v),
+        None => Decimal256::from_uint256(block_height.saturating_sub(credit_line.last_accrued)),
+    };
+
+    credit_line.loan_amount += credit_line.loan_amount * credit_line.interest_rate * passed_periods;
+    credit_line.accrued_periods = Some(accrued_periods);
+    credit_line.last_accrued = block_height.max(credit_line.last_accrued);
+}
+
+/// Replaces the loan of an accrued credit line and
+/// keeps the aggregated credit state in sync
+fn update_loan_amount(state: &mut State, credit_line: &mut CreditLine, loan_amount: Decimal256) {
+    state.total_credit_liabilities =
+        saturating_sub(state.total_credit_liabilities, credit_line.loan_amount) + loan_amount;
+    state.credit_interest_per_block = saturating_sub(
+        state.credit_interest_per_block,
+        credit_line.loan_amount * credit_line.interest_rate,
+    ) + loan_amount * credit_line.interest_rate;
+    credit_line.loan_amount = loan_amount;
+}
+
+fn assert_credit_collateral(
+    credit_line: &CreditLine,
+    loan_amount: Decimal256,
+    exchange_rate: Decimal256,
+) -> StdResult<()> {
+    let required_value = loan_amount * credit_line.collateral_ratio;
+    let collateral_value = Decimal256::from_uint256(credit_line.collateral_amount) * exchange_rate;
+    if required_value > collateral_value {
+        return Err(ContractError::InsufficientCreditCollateral { required_value }.into());
+    }
+
+    Ok(())
+}
+
+// aggregated and per line interest are rounded separately
+pub(crate) fn saturating_sub(a: Decimal256, b: Decimal256) -> Decimal256 {
+    if a > b {
+        a - b
+    } else {
+        Decimal256::zero()
+    }
+}
+
+pub fn query_credit_line<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    strategy: HumanAddr,
+    block_height: Option<u64>,
+) -> StdResult<CreditLineResponse> {
+    let mut credit_line = read_credit_line(&deps.storage, &deps.api.canonical_address(&strategy)?)?
+        .ok_or_else(|| StdError::from(ContractError::NoCreditLine {}))?;
+
+    if let Some(block_height) = block_height {
+        // the periods after the last accrual are blocks, the queries have no block time
+        let state: State = read_state(&deps.storage)?;
+        let pending_periods =
+            Decimal256::from_uint256(block_height.saturating_sub(state.last_interest_updated));
+        accrue_credit_line(
+            &mut credit_line,
+            state.accrued_periods + pending_periods,
+            block_height,
+        );
+    }
+
+    Ok(credit_line_response(strategy, credit_line))
+}
+
+pub fn query_credit_lines<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<CreditLinesResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let credit_lines = read_credit_lines(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(strategy, credit_line)| {
+            Ok(credit_line_response(
+                deps.api.human_address(&strategy)?,
+                credit_line,
+            ))
+        })
+        .collect::<StdResult<Vec<CreditLineResponse>>>()?;
+
+    Ok(CreditLinesResponse { credit_lines })
+}
+
+fn credit_line_response(strategy: HumanAddr, credit_line: CreditLine) -> CreditLineResponse {
+    CreditLineResponse {
+        strategy,
+        cap: credit_line.cap,
+        interest_rate: credit_line.interest_rate,
+        collateral_ratio: credit_line.collateral_ratio,
+        loan_amount: credit_line.loan_amount * Uint256::one(),
+        collateral_amount: credit_line.collateral_amount,
+        defaulted: credit_line.defaulted,
+    }
+}
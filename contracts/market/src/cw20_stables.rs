@@ -0,0 +1,220 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+
+use crate::deposit::compute_mint_amount;
+use crate::operation_mode::assert_deposits_enabled;
+use crate::state::{
+    read_config, read_cw20_stable_state, read_cw20_stable_states, store_cw20_stable_state, Config,
+    Cw20StableState,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{Cw20StableResponse, Cw20StablesResponse};
+use moneymarket::units::{assert_decimals, from_micro_units, to_micro_units, STABLE_DECIMALS};
+
+/// Accept or stop deposits of the CW20 stablecoin `token`, which
+/// must be pegged to the config denom. The amount already held
+/// stays in the market balance until it is swapped out
+pub fn update_cw20_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    token: HumanAddr,
+    accept_deposits: bool,
+    decimals: Option<u8>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let token_raw = deps.api.canonical_address(&token)?;
+    let mut cw20_stable_state =
+        read_cw20_stable_state(&deps.storage, &token_raw)?.unwrap_or(Cw20StableState {
+            accept_deposits,
+            held_amount: Uint256::zero(),
+            decimals: STABLE_DECIMALS,
+        });
+
+    if let Some(decimals) = decimals {
+        assert_decimals(decimals)?;
+
+        // the held amount is swapped out at the current decimals
+        if decimals != cw20_stable_state.decimals && !cw20_stable_state.held_amount.is_zero() {
+            return Err(ContractError::Cw20StableHeld {
+                token: token.to_string(),
+            }
+            .into());
+        }
+
+        cw20_stable_state.decimals = decimals;
+    }
+
+    cw20_stable_state.accept_deposits = accept_deposits;
+    store_cw20_stable_state(&mut deps.storage, &token_raw, &cw20_stable_state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_cw20_stable"),
+            log("token", token),
+            log("accept_deposits", accept_deposits),
+            log("decimals", cw20_stable_state.decimals),
+        ],
+        data: None,
+    })
+}
+
+/// Mints aterra at par for the CW20 stables `depositor` sent
+/// to the market, like a deposit of the config denom. The sent
+/// token amount is converted to micro units from the token decimals
+pub fn deposit_cw20_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    depositor: HumanAddr,
+    deposit_amount: Uint256,
+) -> HandleResult {
+    assert_deposits_enabled(deps, env.block.height)?;
+
+    let token = env.message.sender.clone();
+    let token_raw = deps.api.canonical_address(&token)?;
+    let mut cw20_stable_state = match read_cw20_stable_state(&deps.storage, &token_raw)? {
+        Some(v) if v.accept_deposits => v,
+        _ => return Err(StdError::unauthorized()),
+    };
+
+    // Cannot deposit zero amount
+    if deposit_amount.is_zero() {
+        return Err(ContractError::ZeroDeposit {
+            denom: token.to_string(),
+        }
+        .into());
+    }
+
+    let deposit_amount = to_micro_units(deposit_amount, cw20_stable_state.decimals)?;
+
+    // The received tokens are part of the balance
+    // the exchange rate is computed from
+    cw20_stable_state.held_amount += deposit_amount;
+    store_cw20_stable_state(&mut deps.storage, &token_raw, &cw20_stable_state)?;
+
+    let config: Config = read_config(&deps.storage)?;
+    let (mint_amount, accrual_logs) =
+        compute_mint_amount(deps, &env, &config, &depositor, deposit_amount)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&config.aterra_contract)?,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: depositor.clone(),
+                amount: mint_amount.into(),
+            })?,
+        })],
+        log: [
+            vec![
+                log("action", "deposit_stable"),
+                log("depositor", depositor),
+                log("token", token),
+                log("mint_amount", mint_amount),
+                log("deposit_amount", deposit_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Sells held CW20 stables at par for the config denom, so the
+/// market keeps the liquidity borrowers and redeemers are paid in
+pub fn swap_cw20_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    token: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let token_raw = deps.api.canonical_address(&token)?;
+    let mut cw20_stable_state = match read_cw20_stable_state(&deps.storage, &token_raw)? {
+        Some(v) => v,
+        None => {
+            return Err(ContractError::Cw20StableNotRegistered {
+                token: token.to_string(),
+            }
+            .into())
+        }
+    };
+
+    let swap_amount: Uint256 = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    if swap_amount.is_zero() {
+        return Err(ContractError::ZeroSwap {
+            denom: config.stable_denom.to_string(),
+        }
+        .into());
+    }
+
+    if swap_amount > cw20_stable_state.held_amount {
+        return Err(ContractError::Cw20StableHeldExceeded {
+            token: token.to_string(),
+            held_amount: cw20_stable_state.held_amount,
+        }
+        .into());
+    }
+
+    let token_amount = from_micro_units(swap_amount, cw20_stable_state.decimals)?;
+    cw20_stable_state.held_amount = cw20_stable_state.held_amount - swap_amount;
+    store_cw20_stable_state(&mut deps.storage, &token_raw, &cw20_stable_state)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token.clone(),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: env.message.sender.clone(),
+                amount: token_amount.into(),
+            })?,
+        })],
+        log: vec![
+            log("action", "swap_cw20_stable"),
+            log("swapper", env.message.sender),
+            log("token", token),
+            log("swap_amount", swap_amount),
+        ],
+        data: None,
+    })
+}
+
+/// Total CW20 stables held, counted at par in the market balance
+pub(crate) fn query_cw20_stables_held<S: Storage>(storage: &S) -> StdResult<Uint256> {
+    Ok(read_cw20_stable_states(storage)?
+        .into_iter()
+        .fold(Uint256::zero(), |sum, (_, v)| sum + v.held_amount))
+}
+
+pub fn query_cw20_stables<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<Cw20StablesResponse> {
+    let cw20_stables = read_cw20_stable_states(&deps.storage)?
+        .into_iter()
+        .map(|(token, v)| {
+            Ok(Cw20StableResponse {
+                token: deps.api.human_address(&token)?,
+                accept_deposits: v.accept_deposits,
+                decimals: v.decimals,
+                held_amount: v.held_amount,
+            })
+        })
+        .collect::<StdResult<Vec<Cw20StableResponse>>>()?;
+
+    Ok(Cw20StablesResponse { cw20_stables })
+}
@@ -0,0 +1,326 @@
+use anchor_token::distributor::HandleMsg as FaucetHandleMsg;
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::borrow::{
+    compute_borrower_interest, compute_borrower_reward, compute_interest, compute_reward,
+};
+use crate::deposit::compute_exchange_rate;
+use crate::rate_snapshots::record_rate_snapshot;
+use crate::repayments::clear_principal;
+use crate::stable_rate::remove_stable_loan;
+use crate::state::{
+    read_borrower_info, read_config, read_debt_auction, read_debt_auction_params, read_state,
+    remove_amortizing_loan, remove_debt_auction, store_borrower_info, store_debt_auction,
+    store_debt_auction_params, store_state, BorrowerInfo, Config, DebtAuction, DebtAuctionParams,
+    State,
+};
+use crate::tax::deduct_tax_cached;
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{DebtAuctionParamsResponse, DebtAuctionResponse};
+
+pub fn update_debt_auction_params<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    start_price: Decimal256,
+    floor_price: Decimal256,
+    price_decay_blocks: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if floor_price.is_zero() {
+        return Err(ContractError::ZeroParameter {
+            name: "floor_price".to_string(),
+        }
+        .into());
+    }
+
+    if floor_price > start_price {
+        return Err(ContractError::FloorPriceAboveStartPrice {}.into());
+    }
+
+    if price_decay_blocks == 0 {
+        return Err(ContractError::ZeroParameter {
+            name: "price_decay_blocks".to_string(),
+        }
+        .into());
+    }
+
+    store_debt_auction_params(
+        &mut deps.storage,
+        &DebtAuctionParams {
+            start_price,
+            floor_price,
+            price_decay_blocks,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_debt_auction_params"),
+            log("start_price", start_price),
+            log("floor_price", floor_price),
+            log("price_decay_blocks", price_decay_blocks),
+        ],
+        data: None,
+    })
+}
+
+/// Removes the loan of `borrower` from the liabilities. The reserves
+/// absorb the loss first, the depositors bear the rest until the
+/// debt auction raises it back into the market balance
+pub fn write_off_bad_debt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if config.overseer_contract != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+
+    // Compute interest
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_borrower_interest(&mut state, &mut liability);
+
+    // Compute ANC reward
+    compute_reward(&mut state, env.block.height, env.block.time);
+    compute_borrower_reward(&state, &mut liability);
+
+    let bad_debt = liability.loan_amount;
+    let covered_amount = std::cmp::min(bad_debt, state.total_reserves * Uint256::one());
+    let auction_amount = bad_debt - covered_amount;
+
+    remove_stable_loan(&mut state, &liability);
+    liability.loan_amount = Uint256::zero();
+    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(bad_debt);
+    state.total_reserves = state.total_reserves - Decimal256::from_uint256(covered_amount);
+
+    liability.last_updated = env.block.height;
+    store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+    store_state(&mut deps.storage, &state)?;
+    remove_amortizing_loan(&mut deps.storage, &borrower_raw);
+    clear_principal(&mut deps.storage, &borrower_raw)?;
+    record_rate_snapshot(
+        deps,
+        &config,
+        &state,
+        &borrower_raw,
+        Uint256::zero(),
+        env.block.height,
+    )?;
+
+    if !auction_amount.is_zero() {
+        // the running auction keeps its price, a new one starts from now
+        let auction = match read_debt_auction(&deps.storage)? {
+            Some(mut v) => {
+                v.debt_amount += auction_amount;
+                v
+            }
+            None => DebtAuction {
+                debt_amount: auction_amount,
+                start_height: env.block.height,
+                raised_amount: Uint256::zero(),
+                sold_amount: Uint256::zero(),
+            },
+        };
+        store_debt_auction(&mut deps.storage, &auction)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "write_off_bad_debt"),
+                log("borrower", borrower),
+                log("bad_debt", bad_debt),
+                log("covered_amount", covered_amount),
+                log("auction_amount", auction_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn bid_debt_auction<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let params: DebtAuctionParams = match read_debt_auction_params(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::DebtAuctionsDisabled {}.into()),
+    };
+
+    let mut auction: DebtAuction = match read_debt_auction(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoDebtAuction {}.into()),
+    };
+
+    let amount: Uint256 = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroBid {
+            denom: config.stable_denom.to_string(),
+        }
+        .into());
+    }
+
+    let price = compute_auction_price(&params, &auction, env.block.height);
+    let bid_amount = std::cmp::min(amount, auction.debt_amount);
+    let anc_amount = Decimal256::from_uint256(bid_amount) / price * Uint256::one();
+    if anc_amount.is_zero() {
+        return Err(ContractError::BidTooSmall {}.into());
+    }
+
+    // Accrue interest without the bid, which is not borrower yield
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        Some(amount),
+    )?;
+    compute_reward(&mut state, env.block.height, env.block.time);
+
+    let bidder = env.message.sender;
+    let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&config.distributor_contract)?,
+        send: vec![],
+        msg: to_binary(&FaucetHandleMsg::Spend {
+            recipient: bidder.clone(),
+            amount: anc_amount.into(),
+        })?,
+    })];
+
+    // Payback the stable exceeding the debt
+    if amount > bid_amount {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: bidder.clone(),
+            amount: vec![deduct_tax_cached(
+                deps,
+                env.block.height,
+                Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: (amount - bid_amount).into(),
+                },
+            )?],
+        }));
+    }
+
+    auction.debt_amount = auction.debt_amount - bid_amount;
+    auction.raised_amount += bid_amount;
+    auction.sold_amount += anc_amount;
+    if auction.debt_amount.is_zero() {
+        remove_debt_auction(&mut deps.storage);
+    } else {
+        store_debt_auction(&mut deps.storage, &auction)?;
+    }
+
+    // The next accrual measures the yield from the raised exchange rate,
+    // the stable paid back to the bidder is not raised
+    state.prev_exchange_rate =
+        compute_exchange_rate(deps, &config, &state, Some(amount - bid_amount))?;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![
+                log("action", "bid_debt_auction"),
+                log("bidder", bidder),
+                log("bid_amount", bid_amount),
+                log("anc_amount", anc_amount),
+                log("price", price),
+                log("debt_amount", auction.debt_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Stable paid per ANC at `block_height`, decreasing linearly
+/// from the start price to the floor price
+fn compute_auction_price(
+    params: &DebtAuctionParams,
+    auction: &DebtAuction,
+    block_height: u64,
+) -> Decimal256 {
+    let elapsed = std::cmp::min(
+        block_height.saturating_sub(auction.start_height),
+        params.price_decay_blocks,
+    );
+
+    params.start_price
+        - (params.start_price - params.floor_price)
+            * Decimal256::from_ratio(elapsed, params.price_decay_blocks)
+}
+
+pub fn query_debt_auction_params<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<DebtAuctionParamsResponse> {
+    let params: DebtAuctionParams = match read_debt_auction_params(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::DebtAuctionsDisabled {}.into()),
+    };
+
+    Ok(DebtAuctionParamsResponse {
+        start_price: params.start_price,
+        floor_price: params.floor_price,
+        price_decay_blocks: params.price_decay_blocks,
+    })
+}
+
+pub fn query_debt_auction<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<DebtAuctionResponse> {
+    let auction: DebtAuction = match read_debt_auction(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoDebtAuction {}.into()),
+    };
+
+    let price = read_debt_auction_params(&deps.storage)?
+        .map(|params| compute_auction_price(&params, &auction, block_height));
+
+    Ok(DebtAuctionResponse {
+        debt_amount: auction.debt_amount,
+        start_height: auction.start_height,
+        raised_amount: auction.raised_amount,
+        sold_amount: auction.sold_amount,
+        price,
+    })
+}
@@ -1,20 +1,119 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
     log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
-    HumanAddr, Querier, StdError, StdResult, Storage, Uint128, WasmMsg,
+    HumanAddr, LogAttribute, Querier, StdResult, Storage, Uint128, WasmMsg,
 };
 
-use crate::borrow::{compute_interest, compute_reward};
-use crate::state::{read_config, read_state, store_state, Config, State};
+use crate::attestation::assert_attested;
+use crate::bootstrap::refresh_bootstrap_mode;
+use crate::borrow::{compute_interest, compute_reward, estimate_block_time};
+use crate::flow_limit::{record_net_flow, FlowDirection};
+use crate::idle_deployment::{query_market_balance, recall_shortfall};
+use crate::operation_mode::{assert_deposits_enabled, assert_redemptions_enabled};
+use crate::redemption_fee::compute_redemption_fee;
+use crate::redemption_queue::service_redemption_queue;
+use crate::stable_denoms::{deposit_added_stable, find_stable_denom_deposit};
+use crate::state::{
+    read_config, read_depositor_info, read_depositor_infos, read_flash_mint, read_state,
+    store_depositor_info, store_state, Config, DepositorInfo, State,
+};
+use crate::tax::deduct_tax_cached;
 
 use cw20::Cw20HandleMsg;
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::attestation::AttestedAction;
+use moneymarket::error::ContractError;
+use moneymarket::market::{
+    DepositorInfoResponse, DepositorsResponse, SimulateDepositResponse, SimulateRedeemResponse,
+};
+use moneymarket::querier::{is_taxed_denom, query_supply, query_tax_cap, query_tax_rate};
+use moneymarket::units::{to_micro_units, STABLE_DECIMALS};
 
 pub fn deposit_stable<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    recipient: Option<HumanAddr>,
+    amount: Option<Uint256>,
+    decimals: Option<u8>,
 ) -> HandleResult {
+    let expected_amount = match amount {
+        Some(v) => Some(to_micro_units(v, decimals.unwrap_or(STABLE_DECIMALS))?),
+        None if decimals.is_some() => return Err(ContractError::DecimalsWithoutAmount {}.into()),
+        None => None,
+    };
+
     let config: Config = read_config(&deps.storage)?;
+    let recipient = recipient.unwrap_or_else(|| env.message.sender.clone());
+    if let Some((stable_denom, deposit_amount)) =
+        find_stable_denom_deposit(&deps.storage, &config, &env)?
+    {
+        assert_deposit_amount(expected_amount, deposit_amount)?;
+        return deposit_added_stable(deps, env, stable_denom, deposit_amount, recipient);
+    }
+
+    let (deposit_amount, mint_amount, accrual_logs) =
+        compute_deposit(deps, &env, &config, &recipient)?;
+    let (_, bootstrap_logs) = refresh_bootstrap_mode(deps, &config, env.block.height)?;
+
+    // The deposited liquidity services the queued redemptions
+    let (queue_messages, queue_logs) =
+        service_redemption_queue(deps, &env, Uint256::zero(), deposit_amount)?;
+    assert_deposit_amount(expected_amount, deposit_amount)?;
+
+    Ok(HandleResponse {
+        messages: [
+            vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.human_address(&config.aterra_contract)?,
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Mint {
+                    recipient: recipient.clone(),
+                    amount: mint_amount.into(),
+                })?,
+            })],
+            queue_messages,
+        ]
+        .concat(),
+        log: [
+            vec![
+                log("action", "deposit_stable"),
+                log("depositor", env.message.sender),
+                log("recipient", recipient),
+                log("mint_amount", mint_amount),
+                log("deposit_amount", deposit_amount),
+            ],
+            bootstrap_logs,
+            queue_logs,
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// The sent amount must match the amount the depositor expected,
+/// so amounts sent in the wrong units are not deposited as dust
+fn assert_deposit_amount(
+    expected_amount: Option<Uint256>,
+    deposit_amount: Uint256,
+) -> StdResult<()> {
+    match expected_amount {
+        Some(v) if v != deposit_amount => Err(ContractError::DepositAmountMismatch {
+            sent_amount: deposit_amount,
+            amount: v,
+        }
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// Validates the sent stable coins, accrues interest and records the
+/// aterra to be minted for `recipient`. Returns (deposit_amount, mint_amount, accrual_logs).
+pub(crate) fn compute_deposit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    recipient: &HumanAddr,
+) -> StdResult<(Uint256, Uint256, Vec<LogAttribute>)> {
+    assert_deposits_enabled(deps, env.block.height)?;
 
     // Check base denom deposit
     let deposit_amount: Uint256 = env
@@ -27,46 +126,80 @@ pub fn deposit_stable<S: Storage, A: Api, Q: Querier>(
 
     // Cannot deposit zero amount
     if deposit_amount.is_zero() {
-        return Err(StdError::generic_err(format!(
-            "Deposit amount must be greater than 0 {}",
-            config.stable_denom,
-        )));
+        return Err(ContractError::ZeroDeposit {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
+    }
+
+    if let Some(min_deposit_amount) = config.min_deposit_amount {
+        if deposit_amount < min_deposit_amount {
+            return Err(ContractError::DepositTooSmall {
+                min_amount: min_deposit_amount,
+                denom: config.stable_denom.clone(),
+            }
+            .into());
+        }
     }
 
+    assert_attested(deps, AttestedAction::Deposit, recipient, deposit_amount)?;
+    record_net_flow(
+        deps,
+        config,
+        env.block.height,
+        FlowDirection::Deposit,
+        deposit_amount,
+    )?;
+
+    let (mint_amount, accrual_logs) =
+        compute_mint_amount(deps, env, config, recipient, deposit_amount)?;
+    Ok((deposit_amount, mint_amount, accrual_logs))
+}
+
+/// Accrues interest and records the aterra minted to `depositor` for
+/// `deposit_amount`, which must already be part of the market balance.
+/// Returns (mint_amount, accrual_logs).
+pub(crate) fn compute_mint_amount<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    depositor: &HumanAddr,
+    deposit_amount: Uint256,
+) -> StdResult<(Uint256, Vec<LogAttribute>)> {
     // Update interest related state
     let mut state: State = read_state(&deps.storage)?;
-    compute_interest(
-        &deps,
-        &config,
+    let accrual_logs = compute_interest(
+        deps,
+        config,
         &mut state,
         env.block.height,
+        env.block.time,
         Some(deposit_amount),
     )?;
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.height, env.block.time);
 
     // Load anchor token exchange rate with updated state
-    let exchange_rate = compute_exchange_rate(deps, &config, &state, Some(deposit_amount))?;
+    let exchange_rate = compute_exchange_rate(deps, config, &state, Some(deposit_amount))?;
     let mint_amount = deposit_amount / exchange_rate;
 
-    state.prev_aterra_supply = state.prev_aterra_supply + mint_amount;
+    state.prev_aterra_supply += mint_amount;
     store_state(&mut deps.storage, &state)?;
-    Ok(HandleResponse {
-        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.aterra_contract)?,
-            send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Mint {
-                recipient: env.message.sender.clone(),
-                amount: mint_amount.into(),
-            })?,
-        })],
-        log: vec![
-            log("action", "deposit_stable"),
-            log("depositor", env.message.sender),
-            log("mint_amount", mint_amount),
-            log("deposit_amount", deposit_amount),
-        ],
-        data: None,
-    })
+
+    let depositor_raw = deps.api.canonical_address(depositor)?;
+    let mut depositor_info: DepositorInfo = read_depositor_info(&deps.storage, &depositor_raw);
+    depositor_info.total_deposited += deposit_amount;
+    store_depositor_info(&mut deps.storage, &depositor_raw, &depositor_info)?;
+
+    Ok((mint_amount, accrual_logs))
+}
+
+/// Amount a redemption is given in
+enum RedeemTarget {
+    /// aterra sent to the market to be burned
+    Burn(Uint128),
+    /// stable to be redeemed, the aterra is burned from the
+    /// allowance the redeemer gave to the market
+    Stable(Uint256),
 }
 
 pub fn redeem_stable<S: Storage, A: Api, Q: Querier>(
@@ -75,54 +208,140 @@ pub fn redeem_stable<S: Storage, A: Api, Q: Querier>(
     sender: HumanAddr,
     burn_amount: Uint128,
 ) -> HandleResult {
+    execute_redeem(deps, env, sender, RedeemTarget::Burn(burn_amount))
+}
+
+/// Redeems exactly `stable_amount` before the redemption fee and the tax,
+/// burning the aterra worth it rounded up from the sender's allowance
+pub fn redeem_stable_exact<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    stable_amount: Uint256,
+) -> HandleResult {
+    if stable_amount.is_zero() {
+        return Err(ContractError::ZeroRedeem {}.into());
+    }
+
+    let sender = env.message.sender.clone();
+    execute_redeem(deps, env, sender, RedeemTarget::Stable(stable_amount))
+}
+
+fn execute_redeem<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    sender: HumanAddr,
+    target: RedeemTarget,
+) -> HandleResult {
+    assert_redemptions_enabled(deps, env.block.height)?;
     let config: Config = read_config(&deps.storage)?;
 
     // Update interest related state
     let mut state: State = read_state(&deps.storage)?;
-    compute_interest(&deps, &config, &mut state, env.block.height, None)?;
-    compute_reward(&mut state, env.block.height);
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_reward(&mut state, env.block.height, env.block.time);
 
     // Load anchor token exchange rate with updated state
     let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
-    let redeem_amount = Uint256::from(burn_amount) * exchange_rate;
+    let (action, burn_amount, redeem_amount, burn_msg) = match target {
+        RedeemTarget::Burn(burn_amount) => (
+            "redeem_stable",
+            burn_amount,
+            Uint256::from(burn_amount) * exchange_rate,
+            Cw20HandleMsg::Burn {
+                amount: burn_amount,
+            },
+        ),
+        RedeemTarget::Stable(stable_amount) => {
+            // Rounded up, the remainder is left to the other depositors
+            let mut burn_amount = stable_amount / exchange_rate;
+            if burn_amount * exchange_rate < stable_amount {
+                burn_amount += Uint256::one();
+            }
 
-    let current_balance = query_balance(
-        &deps,
-        &env.contract.address,
-        config.stable_denom.to_string(),
-    )?;
+            let burn_amount: Uint128 = burn_amount.into();
+            (
+                "redeem_stable_exact",
+                burn_amount,
+                stable_amount,
+                Cw20HandleMsg::BurnFrom {
+                    owner: sender.clone(),
+                    amount: burn_amount,
+                },
+            )
+        }
+    };
+
+    let current_balance = query_market_balance(deps, &config)?;
 
     // Assert redeem amount
     assert_redeem_amount(&config, &state, current_balance, redeem_amount)?;
+    assert_attested(deps, AttestedAction::Redeem, &sender, redeem_amount)?;
+    record_net_flow(
+        deps,
+        &config,
+        env.block.height,
+        FlowDirection::Redeem,
+        redeem_amount,
+    )?;
+
+    // Large redemptions relative to the liquidity pay a fee into the reserves
+    let available_liquidity =
+        (Decimal256::from_uint256(current_balance) - state.total_reserves) * Uint256::one();
+    let sender_raw = deps.api.canonical_address(&sender)?;
+    let fee_amount = compute_redemption_fee(
+        &deps.storage,
+        Some(&sender_raw),
+        redeem_amount,
+        available_liquidity,
+    )?;
+    let redeem_amount = redeem_amount - fee_amount;
+    state.total_reserves += Decimal256::from_uint256(fee_amount);
 
     state.prev_aterra_supply = state.prev_aterra_supply - Uint256::from(burn_amount);
     store_state(&mut deps.storage, &state)?;
+
+    let mut depositor_info: DepositorInfo = read_depositor_info(&deps.storage, &sender_raw);
+    depositor_info.total_redeemed += redeem_amount;
+    store_depositor_info(&mut deps.storage, &sender_raw, &depositor_info)?;
+
+    let mut messages = recall_shortfall(deps, &config, redeem_amount)?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&config.aterra_contract)?,
+        send: vec![],
+        msg: to_binary(&burn_msg)?,
+    }));
+    messages.push(CosmosMsg::Bank(BankMsg::Send {
+        from_address: env.contract.address,
+        to_address: sender,
+        amount: vec![deduct_tax_cached(
+            deps,
+            env.block.height,
+            Coin {
+                denom: config.stable_denom,
+                amount: redeem_amount.into(),
+            },
+        )?],
+    }));
+
     Ok(HandleResponse {
-        messages: vec![
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: deps.api.human_address(&config.aterra_contract)?,
-                send: vec![],
-                msg: to_binary(&Cw20HandleMsg::Burn {
-                    amount: burn_amount,
-                })?,
-            }),
-            CosmosMsg::Bank(BankMsg::Send {
-                from_address: env.contract.address,
-                to_address: sender,
-                amount: vec![deduct_tax(
-                    &deps,
-                    Coin {
-                        denom: config.stable_denom,
-                        amount: redeem_amount.into(),
-                    },
-                )?],
-            }),
-        ],
-        log: vec![
-            log("action", "redeem_stable"),
-            log("burn_amount", burn_amount),
-            log("redeem_amount", redeem_amount),
-        ],
+        messages,
+        log: [
+            vec![
+                log("action", action),
+                log("burn_amount", burn_amount),
+                log("redeem_amount", redeem_amount),
+                log("fee_amount", fee_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
         data: None,
     })
 }
@@ -133,16 +352,26 @@ fn assert_redeem_amount(
     current_balance: Uint256,
     redeem_amount: Uint256,
 ) -> StdResult<()> {
+    if let Some(min_redeem_amount) = config.min_redeem_amount {
+        if redeem_amount < min_redeem_amount {
+            return Err(ContractError::RedeemTooSmall {
+                min_amount: min_redeem_amount,
+                denom: config.stable_denom.clone(),
+            }
+            .into());
+        }
+    }
+
     let current_balance = Decimal256::from_uint256(current_balance);
     let redeem_amount = Decimal256::from_uint256(redeem_amount);
     if redeem_amount + state.total_reserves > current_balance {
-        return Err(StdError::generic_err(format!(
-            "Not enough {} available; borrow demand too high",
-            config.stable_denom
-        )));
+        return Err(ContractError::NoLiquidity {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
     }
 
-    return Ok(());
+    Ok(())
 }
 
 pub(crate) fn compute_exchange_rate<S: Storage, A: Api, Q: Querier>(
@@ -151,16 +380,26 @@ pub(crate) fn compute_exchange_rate<S: Storage, A: Api, Q: Querier>(
     state: &State,
     deposit_amount: Option<Uint256>,
 ) -> StdResult<Decimal256> {
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
-    let balance = query_balance(
-        &deps,
-        &deps.api.human_address(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - deposit_amount.unwrap_or_else(Uint256::zero);
+    let aterra_supply = query_aterra_supply(deps, config)?;
+    let balance =
+        query_market_balance(deps, config)? - deposit_amount.unwrap_or_else(Uint256::zero);
 
     Ok(compute_exchange_rate_raw(state, aterra_supply, balance))
 }
 
+/// Token supply excluding the aterra flash minted and not
+/// settled yet, which is burned once the flash mint finishes
+pub(crate) fn query_aterra_supply<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+) -> StdResult<Uint256> {
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    Ok(match read_flash_mint(&deps.storage)? {
+        Some(v) => aterra_supply - v.amount,
+        None => aterra_supply,
+    })
+}
+
 pub fn compute_exchange_rate_raw(
     state: &State,
     aterra_supply: Uint256,
@@ -171,7 +410,179 @@ pub fn compute_exchange_rate_raw(
     }
 
     // (aterra / stable_denom)
-    // exchange_rate = (balance + total_liabilities - total_reserves) / aterra_supply
-    (Decimal256::from_uint256(contract_balance) + state.total_liabilities - state.total_reserves)
+    // exchange_rate = (balance + total_liabilities + total_credit_liabilities - total_reserves)
+    //     / aterra_supply
+    (Decimal256::from_uint256(contract_balance)
+        + state.total_liabilities
+        + state.total_credit_liabilities
+        - state.total_reserves)
         / Decimal256::from_uint256(aterra_supply)
 }
+
+pub fn query_depositor_info<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    depositor: HumanAddr,
+) -> StdResult<DepositorInfoResponse> {
+    let depositor_info: DepositorInfo =
+        read_depositor_info(&deps.storage, &deps.api.canonical_address(&depositor)?);
+
+    Ok(to_depositor_info_response(depositor, depositor_info))
+}
+
+pub fn query_depositors<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<DepositorsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let depositors = read_depositor_infos(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(depositor, info)| {
+            Ok(to_depositor_info_response(
+                deps.api.human_address(&depositor)?,
+                info,
+            ))
+        })
+        .collect::<StdResult<Vec<DepositorInfoResponse>>>()?;
+
+    Ok(DepositorsResponse { depositors })
+}
+
+fn to_depositor_info_response(
+    depositor: HumanAddr,
+    depositor_info: DepositorInfo,
+) -> DepositorInfoResponse {
+    // aterra received by transfer can be redeemed beyond the deposits
+    let net_principal = if depositor_info.total_deposited > depositor_info.total_redeemed {
+        depositor_info.total_deposited - depositor_info.total_redeemed
+    } else {
+        Uint256::zero()
+    };
+
+    DepositorInfoResponse {
+        depositor,
+        total_deposited: depositor_info.total_deposited,
+        total_redeemed: depositor_info.total_redeemed,
+        net_principal,
+    }
+}
+
+/// Aterra minted for a deposit of `amount` at `block_height`, the
+/// current block when not given, without updating the state
+pub fn query_simulate_deposit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    amount: Uint256,
+    block_height: Option<u64>,
+) -> StdResult<SimulateDepositResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    if amount.is_zero() {
+        return Err(ContractError::ZeroDeposit {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
+    }
+
+    let (state, _) = simulate_accrual(deps, &config, block_height)?;
+    let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
+    let mint_amount = amount / exchange_rate;
+
+    // the depositor pays the tax on top of the sent coins
+    let tax_amount = if is_taxed_denom(&config.stable_denom) {
+        std::cmp::min(
+            amount * query_tax_rate(deps)?,
+            query_tax_cap(deps, config.stable_denom.to_string())?,
+        )
+    } else {
+        Uint256::zero()
+    };
+
+    let aterra_supply = query_aterra_supply(deps, &config)?;
+    let balance = query_market_balance(deps, &config)?;
+    Ok(SimulateDepositResponse {
+        mint_amount,
+        tax_amount,
+        exchange_rate: compute_exchange_rate_raw(
+            &state,
+            aterra_supply + mint_amount,
+            balance + amount,
+        ),
+    })
+}
+
+/// Stable paid out for redeeming `burn_amount` aterra at `block_height`,
+/// the current block when not given, without updating the state
+pub fn query_simulate_redeem<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    burn_amount: Uint256,
+    block_height: Option<u64>,
+) -> StdResult<SimulateRedeemResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let (mut state, block_height) = simulate_accrual(deps, &config, block_height)?;
+    let aterra_supply = query_aterra_supply(deps, &config)?;
+    if burn_amount > aterra_supply {
+        return Err(ContractError::BurnExceedsSupply {
+            supply: aterra_supply,
+        }
+        .into());
+    }
+
+    let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
+    let redeem_amount = burn_amount * exchange_rate;
+
+    let current_balance = query_market_balance(deps, &config)?;
+    assert_redeem_amount(&config, &state, current_balance, redeem_amount)?;
+
+    let available_liquidity =
+        (Decimal256::from_uint256(current_balance) - state.total_reserves) * Uint256::one();
+    let fee_amount =
+        compute_redemption_fee(&deps.storage, None, redeem_amount, available_liquidity)?;
+    let redeem_amount = redeem_amount - fee_amount;
+    state.total_reserves += Decimal256::from_uint256(fee_amount);
+
+    // the market pays the tax out of the redeemed coins
+    let received_amount = Uint256::from(
+        deduct_tax_cached(
+            deps,
+            block_height,
+            Coin {
+                denom: config.stable_denom.to_string(),
+                amount: redeem_amount.into(),
+            },
+        )?
+        .amount,
+    );
+
+    Ok(SimulateRedeemResponse {
+        redeem_amount: received_amount,
+        fee_amount,
+        tax_amount: redeem_amount - received_amount,
+        exchange_rate: compute_exchange_rate_raw(
+            &state,
+            aterra_supply - burn_amount,
+            current_balance - redeem_amount,
+        ),
+    })
+}
+
+/// State accrued up to `block_height` like the handlers do,
+/// along with the block height the simulation runs at
+fn simulate_accrual<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    block_height: Option<u64>,
+) -> StdResult<(State, u64)> {
+    let mut state: State = read_state(&deps.storage)?;
+    let block_height = block_height.unwrap_or(state.last_interest_updated);
+    if block_height < state.last_interest_updated {
+        return Err(ContractError::BlockHeightBeforeInterestUpdate {}.into());
+    }
+
+    let block_time = estimate_block_time(&state, block_height);
+    compute_interest(deps, config, &mut state, block_height, block_time, None)?;
+    Ok((state, block_height))
+}
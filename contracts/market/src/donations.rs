@@ -0,0 +1,97 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, Querier, StdResult, Storage,
+};
+
+use crate::borrow::{compute_interest, compute_reward};
+use crate::deposit::{compute_exchange_rate, query_aterra_supply};
+use crate::redemption_queue::service_redemption_queue;
+use crate::state::{
+    read_config, read_state, read_total_donated, store_state, store_total_donated, Config, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::DonationsResponse;
+
+/// Donated stable raises the aterra exchange rate for the current
+/// depositors, without minting aterra and without being swept to the
+/// reserves as excess yield
+pub fn donate_to_depositors<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let sent_amount: Uint256 = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroDonation {}.into());
+    }
+
+    if sent_amount != amount {
+        return Err(ContractError::DonationAmountMismatch {
+            sent_amount,
+            amount,
+        }
+        .into());
+    }
+
+    // Accrue interest without the donation, which is not borrower yield
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        Some(amount),
+    )?;
+    compute_reward(&mut state, env.block.height, env.block.time);
+
+    if query_aterra_supply(deps, &config)?.is_zero() {
+        return Err(ContractError::NoDepositsToDonate {}.into());
+    }
+
+    // The next accrual measures the yield from the donated exchange rate
+    let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
+    state.prev_exchange_rate = exchange_rate;
+    store_state(&mut deps.storage, &state)?;
+
+    let total_donated = read_total_donated(&deps.storage)? + amount;
+    store_total_donated(&mut deps.storage, &total_donated)?;
+
+    // The donated liquidity services the queued redemptions
+    let (queue_messages, queue_logs) =
+        service_redemption_queue(deps, &env, Uint256::zero(), Uint256::zero())?;
+
+    Ok(HandleResponse {
+        messages: queue_messages,
+        log: [
+            vec![
+                log("action", "donate_to_depositors"),
+                log("donor", env.message.sender),
+                log("donation_amount", amount),
+                log("exchange_rate", exchange_rate),
+                log("total_donated", total_donated),
+            ],
+            accrual_logs,
+            queue_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn query_donations<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<DonationsResponse> {
+    Ok(DonationsResponse {
+        total_donated: read_total_donated(&deps.storage)?,
+    })
+}
@@ -0,0 +1,255 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, to_binary, Api, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::contract::query_epoch_state;
+use crate::state::{
+    read_config, read_exchange_rate_feed, read_exchange_rate_observations, read_state,
+    store_exchange_rate_feed, store_exchange_rate_observations, Config, ExchangeRateFeed,
+    ExchangeRateObservation, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::exchange_rate_oracle::HandleMsg as OracleHandleMsg;
+use moneymarket::market::ATerraExchangeRateResponse;
+
+/// ~1 hour at 6 seconds per block
+pub const DEFAULT_TWAP_WINDOW: u64 = 600;
+
+/// Observations kept over the window, longer windows are
+/// sampled every `twap_window / MAX_TWAP_OBSERVATIONS` blocks
+pub const MAX_TWAP_OBSERVATIONS: u64 = 100;
+
+pub fn update_exchange_rate_oracle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    oracle: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut feed = load_exchange_rate_feed(&deps.storage)?;
+    feed.oracle = Some(deps.api.canonical_address(&oracle)?);
+    store_exchange_rate_feed(&mut deps.storage, &feed)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_exchange_rate_oracle"),
+            log("oracle", oracle),
+        ],
+        data: None,
+    })
+}
+
+pub fn disable_exchange_rate_oracle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut feed = load_exchange_rate_feed(&deps.storage)?;
+    if feed.oracle.is_none() {
+        return Err(ContractError::NoExchangeRateOracle {}.into());
+    }
+
+    feed.oracle = None;
+    store_exchange_rate_feed(&mut deps.storage, &feed)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "disable_exchange_rate_oracle")],
+        data: None,
+    })
+}
+
+pub fn update_exchange_rate_twap_window<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    twap_window: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if twap_window == 0 {
+        return Err(ContractError::ZeroParameter {
+            name: "twap_window".to_string(),
+        }
+        .into());
+    }
+
+    let mut feed = load_exchange_rate_feed(&deps.storage)?;
+    feed.twap_window = twap_window;
+    store_exchange_rate_feed(&mut deps.storage, &feed)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_exchange_rate_twap_window"),
+            log("twap_window", twap_window),
+        ],
+        data: None,
+    })
+}
+
+/// The feed starts from the exchange rate of the last accrual,
+/// which is recorded again by the next accrual
+fn load_exchange_rate_feed<S: Storage>(storage: &S) -> StdResult<ExchangeRateFeed> {
+    match read_exchange_rate_feed(storage)? {
+        Some(v) => Ok(v),
+        None => {
+            let state: State = read_state(storage)?;
+            Ok(ExchangeRateFeed {
+                oracle: None,
+                exchange_rate: state.prev_exchange_rate,
+                updated_height: 0,
+                monotonic: true,
+                twap_window: DEFAULT_TWAP_WINDOW,
+            })
+        }
+    }
+}
+
+/// Records the exchange rate of the accrual executed at `block_height`,
+/// if any, and pushes it to the registered oracle. Executed after every
+/// handler so no accrual is missed
+pub(crate) fn feed_exchange_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<Vec<CosmosMsg>> {
+    let state: State = read_state(&deps.storage)?;
+    let mut feed = load_exchange_rate_feed(&deps.storage)?;
+    if state.last_interest_updated != block_height || feed.updated_height >= block_height {
+        return Ok(vec![]);
+    }
+
+    // prev_exchange_rate is the exchange rate of the last accrual
+    let exchange_rate = state.prev_exchange_rate;
+    feed.monotonic = feed.monotonic && exchange_rate >= feed.exchange_rate;
+    feed.exchange_rate = exchange_rate;
+    feed.updated_height = block_height;
+    store_exchange_rate_feed(&mut deps.storage, &feed)?;
+    record_observation(&mut deps.storage, &feed, exchange_rate, block_height)?;
+
+    match feed.oracle {
+        Some(oracle) => Ok(vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&oracle)?,
+            send: vec![],
+            msg: to_binary(&OracleHandleMsg::FeedExchangeRate {
+                exchange_rate,
+                block_height,
+            })?,
+        })]),
+        None => Ok(vec![]),
+    }
+}
+
+/// Appends the observation and drops the ones no longer in
+/// effect within the window ending at `block_height`
+fn record_observation<S: Storage>(
+    storage: &mut S,
+    feed: &ExchangeRateFeed,
+    exchange_rate: Decimal256,
+    block_height: u64,
+) -> StdResult<()> {
+    let mut observations = read_exchange_rate_observations(storage)?;
+    let sample_blocks = std::cmp::max(1, feed.twap_window / MAX_TWAP_OBSERVATIONS);
+    if let Some(last) = observations.last() {
+        if block_height < last.height + sample_blocks {
+            return Ok(());
+        }
+    }
+
+    observations.push(ExchangeRateObservation {
+        height: block_height,
+        exchange_rate,
+    });
+
+    // the first observation is kept while it is in effect at the window start
+    let window_start = block_height.saturating_sub(feed.twap_window);
+    let expired = observations
+        .iter()
+        .skip(1)
+        .take_while(|v| v.height <= window_start)
+        .count();
+    let truncated = (observations.len() - expired).saturating_sub(MAX_TWAP_OBSERVATIONS as usize);
+    observations.drain(..expired + truncated);
+
+    store_exchange_rate_observations(storage, &observations)
+}
+
+/// Average of the observed exchange rates weighted by the blocks each
+/// was in effect between `block_height - twap_window` and `block_height`.
+/// The rate observed at `block_height` has no weight yet, so a rate
+/// moved within a block does not show until blocks pass
+fn compute_twap(
+    observations: &[ExchangeRateObservation],
+    twap_window: u64,
+    block_height: u64,
+) -> Option<Decimal256> {
+    let window_start = block_height.saturating_sub(twap_window);
+
+    let mut weighted_sum = Decimal256::zero();
+    let mut weighted_blocks = 0u64;
+    for (i, observation) in observations.iter().enumerate() {
+        let from = std::cmp::max(observation.height, window_start);
+        let to = observations
+            .get(i + 1)
+            .map(|v| std::cmp::min(v.height, block_height))
+            .unwrap_or(block_height);
+        if to > from {
+            weighted_sum += observation.exchange_rate * Decimal256::from_uint256(to - from);
+            weighted_blocks += to - from;
+        }
+    }
+
+    if weighted_blocks == 0 {
+        // no block passed since the first observation
+        return observations
+            .iter()
+            .rev()
+            .find(|v| v.height <= block_height)
+            .map(|v| v.exchange_rate);
+    }
+
+    Some(weighted_sum / Decimal256::from_uint256(weighted_blocks))
+}
+
+/// The exchange rate is accrued to `block_height` when given,
+/// it is only guaranteed monotonic while no accrual, including
+/// the projected one, observed a decrease
+pub fn query_aterra_exchange_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: Option<u64>,
+) -> StdResult<ATerraExchangeRateResponse> {
+    let state: State = read_state(&deps.storage)?;
+    let feed = load_exchange_rate_feed(&deps.storage)?;
+    let exchange_rate = query_epoch_state(deps, block_height, None)?.exchange_rate;
+    let twap = compute_twap(
+        &read_exchange_rate_observations(&deps.storage)?,
+        feed.twap_window,
+        block_height.unwrap_or(state.last_interest_updated),
+    )
+    .unwrap_or(exchange_rate);
+
+    Ok(ATerraExchangeRateResponse {
+        exchange_rate,
+        last_updated: state.last_interest_updated,
+        monotonic: feed.monotonic && exchange_rate >= feed.exchange_rate,
+        oracle: match feed.oracle {
+            Some(v) => Some(deps.api.human_address(&v)?),
+            None => None,
+        },
+        twap,
+        twap_window: feed.twap_window,
+    })
+}
@@ -0,0 +1,244 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Env, Extern, HandleResponse,
+    HandleResult, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::operation_mode::assert_borrowing_enabled;
+use crate::state::{
+    read_config, read_flash_loan, read_flash_loan_fee_rate, read_state, remove_flash_loan,
+    store_flash_loan, store_flash_loan_fee_rate, Config, FlashLoan, State,
+};
+use crate::tax::deduct_tax_cached;
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{FlashLoanResponse, HandleMsg};
+use moneymarket::querier::query_balance;
+
+pub fn update_flash_loan_fee<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    fee_rate: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if fee_rate > Decimal256::one() {
+        return Err(ContractError::RatioAboveOne {
+            name: "fee_rate".to_string(),
+        }
+        .into());
+    }
+
+    store_flash_loan_fee_rate(&mut deps.storage, &fee_rate)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_flash_loan_fee"),
+            log("fee_rate", fee_rate),
+        ],
+        data: None,
+    })
+}
+
+/// Sends idle stable to the sender, executes its callback and settles
+/// the repayment afterwards. The outstanding amount is counted in the
+/// market balance until it is repaid, so the exchange rate observed by
+/// any other operation inside the callback is left unchanged
+pub fn flash_loan<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+    msg: Binary,
+) -> HandleResult {
+    assert_borrowing_enabled(deps, env.block.height)?;
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroFlashLoan {}.into());
+    }
+
+    if read_flash_loan(&deps.storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {}.into());
+    }
+
+    // Only the stable above the reserves can be loaned
+    let config: Config = read_config(&deps.storage)?;
+    let state: State = read_state(&deps.storage)?;
+    let balance = query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
+    let available_liquidity = if Decimal256::from_uint256(balance) > state.total_reserves {
+        (Decimal256::from_uint256(balance) - state.total_reserves) * Uint256::one()
+    } else {
+        Uint256::zero()
+    };
+
+    if amount > available_liquidity {
+        return Err(ContractError::FlashLoanLiquidity {
+            available: available_liquidity,
+        }
+        .into());
+    }
+
+    let fee_rate = read_flash_loan_fee_rate(&deps.storage)?;
+    let fee = amount * fee_rate;
+
+    store_flash_loan(
+        &mut deps.storage,
+        &FlashLoan {
+            borrower: deps.api.canonical_address(&env.message.sender)?,
+            amount,
+            fee,
+            repaid: Uint256::zero(),
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: env.message.sender.clone(),
+                amount: vec![deduct_tax_cached(
+                    deps,
+                    env.block.height,
+                    Coin {
+                        denom: config.stable_denom,
+                        amount: amount.into(),
+                    },
+                )?],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.message.sender.clone(),
+                send: vec![],
+                msg,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address,
+                send: vec![],
+                msg: to_binary(&HandleMsg::FinishFlashLoan {})?,
+            }),
+        ],
+        log: vec![
+            log("action", "flash_loan"),
+            log("borrower", env.message.sender),
+            log("amount", amount),
+            log("fee", fee),
+        ],
+        data: None,
+    })
+}
+
+pub fn repay_flash_loan<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut flash_loan = match read_flash_loan(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoFlashLoan {}.into()),
+    };
+
+    let config: Config = read_config(&deps.storage)?;
+    let amount: Uint256 = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay {
+            denom: config.stable_denom.clone(),
+        }
+        .into());
+    }
+
+    flash_loan.repaid += amount;
+    if flash_loan.repaid > flash_loan.amount + flash_loan.fee {
+        return Err(ContractError::FlashLoanOverpaid {
+            max_repay: flash_loan.amount + flash_loan.fee,
+        }
+        .into());
+    }
+
+    store_flash_loan(&mut deps.storage, &flash_loan)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "repay_flash_loan"),
+            log("sender", env.message.sender),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn finish_flash_loan<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    if env.message.sender != env.contract.address {
+        return Err(StdError::unauthorized());
+    }
+
+    let flash_loan = match read_flash_loan(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoFlashLoan {}.into()),
+    };
+
+    let repay_amount = flash_loan.amount + flash_loan.fee;
+    if flash_loan.repaid < repay_amount {
+        return Err(ContractError::FlashLoanNotRepaid {
+            required: repay_amount,
+            repaid: flash_loan.repaid,
+        }
+        .into());
+    }
+
+    // The fee is left in the market balance
+    // and raises the exchange rate
+    remove_flash_loan(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "finish_flash_loan"),
+            log("borrower", deps.api.human_address(&flash_loan.borrower)?),
+            log("repay_amount", repay_amount),
+            log("fee", flash_loan.fee),
+        ],
+        data: None,
+    })
+}
+
+/// Loaned stable not repaid yet, still part of the market balance
+pub(crate) fn query_flash_loan_outstanding<S: Storage>(storage: &S) -> StdResult<Uint256> {
+    Ok(match read_flash_loan(storage)? {
+        Some(v) if v.repaid < v.amount => v.amount - v.repaid,
+        _ => Uint256::zero(),
+    })
+}
+
+pub fn query_flash_loan<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<FlashLoanResponse> {
+    let fee_rate = read_flash_loan_fee_rate(&deps.storage)?;
+    Ok(match read_flash_loan(&deps.storage)? {
+        Some(v) => FlashLoanResponse {
+            fee_rate,
+            borrower: Some(deps.api.human_address(&v.borrower)?),
+            amount: v.amount,
+            fee: v.fee,
+            repaid: v.repaid,
+        },
+        None => FlashLoanResponse {
+            fee_rate,
+            borrower: None,
+            amount: Uint256::zero(),
+            fee: Uint256::zero(),
+            repaid: Uint256::zero(),
+        },
+    })
+}
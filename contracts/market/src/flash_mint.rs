@@ -0,0 +1,224 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+
+use crate::borrow::compute_interest;
+use crate::operation_mode::assert_borrowing_enabled;
+use crate::state::{
+    read_config, read_flash_mint, read_flash_mint_fee_rate, read_state, remove_flash_mint,
+    store_flash_mint, store_flash_mint_fee_rate, store_state, Config, FlashMint, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{FlashMintResponse, HandleMsg};
+
+pub fn update_flash_mint_fee<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    fee_rate: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if fee_rate > Decimal256::one() {
+        return Err(ContractError::RatioAboveOne {
+            name: "fee_rate".to_string(),
+        }
+        .into());
+    }
+
+    store_flash_mint_fee_rate(&mut deps.storage, &fee_rate)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_flash_mint_fee"),
+            log("fee_rate", fee_rate),
+        ],
+        data: None,
+    })
+}
+
+/// Mints aterra to the sender, executes its callback and settles the
+/// repayment afterwards. The aterra supply excludes the minted amount
+/// until the settlement, so the exchange rate observed by any other
+/// operation inside the callback excludes the flash minted supply
+pub fn flash_mint<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+    msg: Binary,
+) -> HandleResult {
+    assert_borrowing_enabled(deps, env.block.height)?;
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroFlashMint {}.into());
+    }
+
+    if read_flash_mint(&deps.storage)?.is_some() {
+        return Err(ContractError::FlashMintInProgress {}.into());
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let fee_rate = read_flash_mint_fee_rate(&deps.storage)?;
+    let fee = amount * fee_rate;
+
+    store_flash_mint(
+        &mut deps.storage,
+        &FlashMint {
+            minter: deps.api.canonical_address(&env.message.sender)?,
+            amount,
+            fee,
+            repaid: Uint256::zero(),
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.human_address(&config.aterra_contract)?,
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Mint {
+                    recipient: env.message.sender.clone(),
+                    amount: amount.into(),
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.message.sender.clone(),
+                send: vec![],
+                msg,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address,
+                send: vec![],
+                msg: to_binary(&HandleMsg::FinishFlashMint {})?,
+            }),
+        ],
+        log: vec![
+            log("action", "flash_mint"),
+            log("minter", env.message.sender),
+            log("amount", amount),
+            log("fee", fee),
+        ],
+        data: None,
+    })
+}
+
+pub fn repay_flash_mint<S: Storage>(
+    storage: &mut S,
+    sender: HumanAddr,
+    amount: Uint256,
+) -> HandleResult {
+    let mut flash_mint = match read_flash_mint(storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoFlashMint {}.into()),
+    };
+
+    flash_mint.repaid += amount;
+    if flash_mint.repaid > flash_mint.amount + flash_mint.fee {
+        return Err(ContractError::FlashMintOverpaid {
+            max_repay: flash_mint.amount + flash_mint.fee,
+        }
+        .into());
+    }
+
+    store_flash_mint(storage, &flash_mint)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "repay_flash_mint"),
+            log("sender", sender),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn finish_flash_mint<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    if env.message.sender != env.contract.address {
+        return Err(StdError::unauthorized());
+    }
+
+    let flash_mint = match read_flash_mint(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoFlashMint {}.into()),
+    };
+
+    let burn_amount = flash_mint.amount + flash_mint.fee;
+    if flash_mint.repaid < burn_amount {
+        return Err(ContractError::FlashMintNotRepaid {
+            required: burn_amount,
+            repaid: flash_mint.repaid,
+        }
+        .into());
+    }
+
+    // Accrue interest before the fee raises the exchange rate
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+
+    // The burn below removes the flash minted amount from the supply
+    remove_flash_mint(&mut deps.storage);
+    state.prev_aterra_supply = state.prev_aterra_supply - flash_mint.fee;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&config.aterra_contract)?,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Burn {
+                amount: burn_amount.into(),
+            })?,
+        })],
+        log: [
+            vec![
+                log("action", "finish_flash_mint"),
+                log("minter", deps.api.human_address(&flash_mint.minter)?),
+                log("burn_amount", burn_amount),
+                log("fee", flash_mint.fee),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn query_flash_mint<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<FlashMintResponse> {
+    let fee_rate = read_flash_mint_fee_rate(&deps.storage)?;
+    Ok(match read_flash_mint(&deps.storage)? {
+        Some(v) => FlashMintResponse {
+            fee_rate,
+            minter: Some(deps.api.human_address(&v.minter)?),
+            amount: v.amount,
+            fee: v.fee,
+            repaid: v.repaid,
+        },
+        None => FlashMintResponse {
+            fee_rate,
+            minter: None,
+            amount: Uint256::zero(),
+            fee: Uint256::zero(),
+            repaid: Uint256::zero(),
+        },
+    })
+}
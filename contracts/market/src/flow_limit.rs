@@ -0,0 +1,147 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, Querier, StdError, StdResult, Storage,
+};
+
+use crate::idle_deployment::query_market_balance;
+use crate::state::{
+    read_config, read_flow_limit_override, read_flow_window, read_state, store_flow_limit_override,
+    store_flow_window, Config, FlowWindow, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::FlowLimitResponse;
+
+pub(crate) enum FlowDirection {
+    Deposit,
+    Redeem,
+}
+
+/// Lets the owner stop enforcing the net flow caps in an emergency,
+/// e.g. to let depositors exit during a run
+pub fn override_flow_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    enabled: bool,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_flow_limit_override(&mut deps.storage, enabled)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "override_flow_limit"),
+            log("enabled", enabled),
+        ],
+        data: None,
+    })
+}
+
+/// Records `amount` in the current window, failing when the net deposits
+/// or the net redemptions of the window would exceed the configured ratio
+/// of the deposits at its start. A deposit must already be part of the
+/// market balance
+pub(crate) fn record_net_flow<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    config: &Config,
+    block_height: u64,
+    direction: FlowDirection,
+    amount: Uint256,
+) -> StdResult<()> {
+    let (flow_window, max_net_flow_ratio) = match (config.flow_window, config.max_net_flow_ratio) {
+        (Some(flow_window), Some(max_net_flow_ratio)) => (flow_window, max_net_flow_ratio),
+        _ => return Ok(()),
+    };
+
+    if read_flow_limit_override(&deps.storage)? {
+        return Ok(());
+    }
+
+    let mut window = match read_flow_window(&deps.storage)? {
+        Some(v) if block_height < v.start_height + flow_window => v,
+        _ => {
+            let deposit_amount = match direction {
+                FlowDirection::Deposit => amount,
+                FlowDirection::Redeem => Uint256::zero(),
+            };
+
+            FlowWindow {
+                start_height: block_height,
+                base_deposits: compute_total_deposits(deps, config, deposit_amount)?,
+                deposited: Uint256::zero(),
+                redeemed: Uint256::zero(),
+            }
+        }
+    };
+
+    let cap = window.base_deposits * max_net_flow_ratio;
+    let window_end = window.start_height + flow_window;
+    match direction {
+        FlowDirection::Deposit => {
+            window.deposited += amount;
+            if window.deposited > window.redeemed + cap {
+                return Err(ContractError::NetDepositCapExceeded {
+                    cap,
+                    denom: config.stable_denom.to_string(),
+                    until_height: window_end,
+                }
+                .into());
+            }
+        }
+        FlowDirection::Redeem => {
+            window.redeemed += amount;
+            if window.redeemed > window.deposited + cap {
+                return Err(ContractError::NetRedemptionCapExceeded {
+                    cap,
+                    denom: config.stable_denom.to_string(),
+                    until_height: window_end,
+                }
+                .into());
+            }
+        }
+    }
+
+    store_flow_window(&mut deps.storage, &window)
+}
+
+/// Stable owed to the depositors, excluding `deposit_amount`
+fn compute_total_deposits<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    deposit_amount: Uint256,
+) -> StdResult<Uint256> {
+    let state: State = read_state(&deps.storage)?;
+    let balance = query_market_balance(deps, config)? - deposit_amount;
+    let total_deposits = Decimal256::from_uint256(balance) + state.total_liabilities;
+    if total_deposits <= state.total_reserves {
+        return Ok(Uint256::zero());
+    }
+
+    Ok((total_deposits - state.total_reserves) * Uint256::one())
+}
+
+pub fn query_flow_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<FlowLimitResponse> {
+    let override_enabled = read_flow_limit_override(&deps.storage)?;
+    Ok(match read_flow_window(&deps.storage)? {
+        Some(window) => FlowLimitResponse {
+            override_enabled,
+            window_start_height: Some(window.start_height),
+            base_deposits: window.base_deposits,
+            deposited: window.deposited,
+            redeemed: window.redeemed,
+        },
+        None => FlowLimitResponse {
+            override_enabled,
+            window_start_height: None,
+            base_deposits: Uint256::zero(),
+            deposited: Uint256::zero(),
+            redeemed: Uint256::zero(),
+        },
+    })
+}
@@ -0,0 +1,358 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    Querier, StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::borrow::compute_interest;
+use crate::cw20_stables::query_cw20_stables_held;
+use crate::flash_loan::query_flash_loan_outstanding;
+use crate::state::{
+    read_config, read_idle_deployment, read_state, store_idle_deployment, store_state, Config,
+    IdleDeployment, State,
+};
+use crate::tax::deduct_tax_cached;
+
+use moneymarket::error::ContractError;
+use moneymarket::market::IdleDeploymentResponse;
+use moneymarket::querier::query_balance;
+use moneymarket::venue::HandleMsg as VenueHandleMsg;
+
+pub fn update_idle_deployment<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    venue: HumanAddr,
+    utilization_threshold: Decimal256,
+    max_deploy_ratio: Decimal256,
+    cap: Uint256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if utilization_threshold > Decimal256::one() {
+        return Err(ContractError::RatioAboveOne {
+            name: "utilization_threshold".to_string(),
+        }
+        .into());
+    }
+
+    if max_deploy_ratio > Decimal256::one() {
+        return Err(ContractError::RatioAboveOne {
+            name: "max_deploy_ratio".to_string(),
+        }
+        .into());
+    }
+
+    let venue_raw = deps.api.canonical_address(&venue)?;
+    let deployed_amount = match read_idle_deployment(&deps.storage)? {
+        Some(v) if v.venue != venue_raw && !v.deployed_amount.is_zero() => {
+            return Err(ContractError::IdleStableDeployed {}.into())
+        }
+        Some(v) => v.deployed_amount,
+        None => Uint256::zero(),
+    };
+
+    store_idle_deployment(
+        &mut deps.storage,
+        &IdleDeployment {
+            venue: venue_raw,
+            utilization_threshold,
+            max_deploy_ratio,
+            cap,
+            deployed_amount,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_idle_deployment"),
+            log("venue", venue),
+            log("utilization_threshold", utilization_threshold),
+            log("max_deploy_ratio", max_deploy_ratio),
+            log("cap", cap),
+        ],
+        data: None,
+    })
+}
+
+/// Sends idle stable to the venue up to `max_deploy_ratio` of the
+/// available liquidity, including the already deployed amount. Only
+/// the native stable held by the contract above what must be kept
+/// liquid is sent
+pub fn deploy_idle_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut deployment = match read_idle_deployment(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoIdleDeployment {}.into()),
+    };
+
+    // The deposit tax lowers the exchange rate, so accrue interest first
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    store_state(&mut deps.storage, &state)?;
+
+    let balance = query_market_balance(deps, &config)?;
+    let utilization = compute_utilization(&state, balance);
+    if utilization >= deployment.utilization_threshold {
+        return Err(ContractError::UtilizationTooHigh { utilization }.into());
+    }
+
+    // the CW20 stables, the flash loaned stable and the deployed
+    // amount cannot be sent, the reserves stay in the contract
+    let native_balance = Decimal256::from_uint256(query_balance(
+        deps,
+        &env.contract.address,
+        config.stable_denom.to_string(),
+    )?);
+    let idle_liquidity = if native_balance > state.total_reserves {
+        (native_balance - state.total_reserves) * Uint256::one()
+    } else {
+        Uint256::zero()
+    };
+
+    // the share of the liquidity max_deploy_ratio does not deploy
+    let kept_liquidity = (idle_liquidity + deployment.deployed_amount)
+        * (Decimal256::one() - deployment.max_deploy_ratio);
+    let mut deploy_amount = if idle_liquidity > kept_liquidity {
+        idle_liquidity - kept_liquidity
+    } else {
+        Uint256::zero()
+    };
+
+    let cap_left = if deployment.cap > deployment.deployed_amount {
+        deployment.cap - deployment.deployed_amount
+    } else {
+        Uint256::zero()
+    };
+    if deploy_amount > cap_left {
+        deploy_amount = cap_left;
+    }
+
+    if deploy_amount.is_zero() {
+        return Err(ContractError::NoIdleStable {}.into());
+    }
+
+    let deploy_coin = deduct_tax_cached(
+        deps,
+        env.block.height,
+        Coin {
+            denom: config.stable_denom,
+            amount: deploy_amount.into(),
+        },
+    )?;
+    let deploy_amount = Uint256::from(deploy_coin.amount);
+    deployment.deployed_amount += deploy_amount;
+    store_idle_deployment(&mut deps.storage, &deployment)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&deployment.venue)?,
+            send: vec![deploy_coin],
+            msg: to_binary(&VenueHandleMsg::Deposit {})?,
+        })],
+        log: [
+            vec![
+                log("action", "deploy_idle_stable"),
+                log("deploy_amount", deploy_amount),
+                log("deployed_amount", deployment.deployed_amount),
+                log("utilization", utilization),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Recalls stable from the venue. Recalling the whole
+/// deployment also returns the yield earned by the venue
+pub fn recall_idle_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Option<Uint256>,
+) -> HandleResult {
+    let mut deployment = match read_idle_deployment(&deps.storage)? {
+        Some(v) if !v.deployed_amount.is_zero() => v,
+        _ => return Err(ContractError::NoIdleStableDeployed {}.into()),
+    };
+
+    // The recalled yield raises the exchange rate, so accrue interest first
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    store_state(&mut deps.storage, &state)?;
+
+    let balance = query_market_balance(deps, &config)?;
+    let utilization = compute_utilization(&state, balance);
+    if utilization < deployment.utilization_threshold
+        && deps.api.canonical_address(&env.message.sender)? != config.owner_addr
+    {
+        return Err(StdError::unauthorized());
+    }
+
+    let recall_amount = amount.unwrap_or(deployment.deployed_amount);
+    if recall_amount.is_zero() || recall_amount > deployment.deployed_amount {
+        return Err(ContractError::RecallExceedsDeployed {
+            deployed_amount: deployment.deployed_amount,
+        }
+        .into());
+    }
+
+    deployment.deployed_amount = deployment.deployed_amount - recall_amount;
+    store_idle_deployment(&mut deps.storage, &deployment)?;
+
+    Ok(HandleResponse {
+        messages: vec![recall_msg(deps, &deployment, amount)?],
+        log: [
+            vec![
+                log("action", "recall_idle_stable"),
+                log("recall_amount", recall_amount),
+                log("deployed_amount", deployment.deployed_amount),
+                log("utilization", utilization),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Returns the recall message for the stable missing from the contract
+/// balance to pay out `amount`, so deployed stable is instantly available
+/// to redemptions and loans. The message must precede the payment
+pub(crate) fn recall_shortfall<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    config: &Config,
+    amount: Uint256,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut deployment = match read_idle_deployment(&deps.storage)? {
+        Some(v) if !v.deployed_amount.is_zero() => v,
+        _ => return Ok(vec![]),
+    };
+
+    let contract_balance = query_balance(
+        deps,
+        &deps.api.human_address(&config.contract_addr)?,
+        config.stable_denom.to_string(),
+    )?;
+    if amount <= contract_balance {
+        return Ok(vec![]);
+    }
+
+    let mut shortfall = amount - contract_balance;
+    if shortfall > deployment.deployed_amount {
+        shortfall = deployment.deployed_amount;
+    }
+
+    deployment.deployed_amount = deployment.deployed_amount - shortfall;
+    store_idle_deployment(&mut deps.storage, &deployment)?;
+
+    Ok(vec![recall_msg(deps, &deployment, Some(shortfall))?])
+}
+
+fn recall_msg<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    deployment: &IdleDeployment,
+    amount: Option<Uint256>,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&deployment.venue)?,
+        send: vec![],
+        msg: to_binary(&VenueHandleMsg::Withdraw { amount })?,
+    }))
+}
+
+/// Native stable the contract can send once recall_shortfall
+/// recalled the deployed amount from the venue
+pub(crate) fn query_native_liquidity<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+) -> StdResult<Uint256> {
+    let balance = query_balance(
+        deps,
+        &deps.api.human_address(&config.contract_addr)?,
+        config.stable_denom.to_string(),
+    )?;
+
+    Ok(match read_idle_deployment(&deps.storage)? {
+        Some(v) => balance + v.deployed_amount,
+        None => balance,
+    })
+}
+
+/// Stable balance of the contract plus the amount deployed
+/// to the venue, the CW20 stables held at par and the
+/// flash loaned stable not repaid yet
+pub(crate) fn query_market_balance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+) -> StdResult<Uint256> {
+    let balance = query_balance(
+        deps,
+        &deps.api.human_address(&config.contract_addr)?,
+        config.stable_denom.to_string(),
+    )? + query_cw20_stables_held(&deps.storage)?
+        + query_flash_loan_outstanding(&deps.storage)?;
+
+    Ok(match read_idle_deployment(&deps.storage)? {
+        Some(v) => balance + v.deployed_amount,
+        None => balance,
+    })
+}
+
+fn compute_utilization(state: &State, market_balance: Uint256) -> Decimal256 {
+    let total_loans = state.total_liabilities + state.total_credit_liabilities;
+    let total_value_in_market = Decimal256::from_uint256(market_balance) + total_loans;
+    if total_value_in_market <= state.total_reserves {
+        return Decimal256::zero();
+    }
+
+    total_loans / (total_value_in_market - state.total_reserves)
+}
+
+pub fn query_idle_deployment<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<IdleDeploymentResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let state: State = read_state(&deps.storage)?;
+    let utilization = compute_utilization(&state, query_market_balance(deps, &config)?);
+
+    Ok(match read_idle_deployment(&deps.storage)? {
+        Some(v) => IdleDeploymentResponse {
+            venue: Some(deps.api.human_address(&v.venue)?),
+            utilization_threshold: v.utilization_threshold,
+            max_deploy_ratio: v.max_deploy_ratio,
+            cap: v.cap,
+            deployed_amount: v.deployed_amount,
+            utilization,
+        },
+        None => IdleDeploymentResponse {
+            venue: None,
+            utilization_threshold: Decimal256::zero(),
+            max_deploy_ratio: Decimal256::zero(),
+            cap: Uint256::zero(),
+            deployed_amount: Uint256::zero(),
+            utilization,
+        },
+    })
+}
@@ -0,0 +1,70 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, Querier, StdError, StdResult, Storage,
+};
+
+use crate::idle_deployment::query_market_balance;
+use crate::state::{
+    read_config, read_invariant_checks, read_state, store_invariant_checks, Config, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::InvariantChecksResponse;
+
+pub fn update_invariant_checks<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    enabled: bool,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_invariant_checks(&mut deps.storage, enabled)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_invariant_checks"),
+            log("enabled", enabled),
+        ],
+        data: None,
+    })
+}
+
+/// Verifies the core accounting invariants against the stored state
+/// once a handler is done, aborting the tx on a violation. The
+/// messages dispatched by the handler are not executed yet
+pub(crate) fn assert_invariants<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<()> {
+    if !read_invariant_checks(&deps.storage)? {
+        return Ok(());
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let state: State = read_state(&deps.storage)?;
+
+    // The reserves are part of the cash and of the lent out stable
+    let implied_cash = Decimal256::from_uint256(query_market_balance(deps, &config)?)
+        + state.total_liabilities
+        + state.total_credit_liabilities;
+    if state.total_reserves > implied_cash {
+        return Err(ContractError::ReservesExceedCash {
+            reserves: state.total_reserves,
+            cash: implied_cash,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+pub fn query_invariant_checks<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<InvariantChecksResponse> {
+    Ok(InvariantChecksResponse {
+        enabled: read_invariant_checks(&deps.storage)?,
+    })
+}
@@ -0,0 +1,185 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    Querier, StdError, StdResult, Storage,
+};
+
+use crate::borrow::{
+    compute_borrower_interest, compute_borrower_reward, compute_interest, compute_reward,
+};
+use crate::idle_deployment::recall_shortfall;
+use crate::state::{
+    read_borrower_info, read_config, read_keeper_bounty, read_state, store_borrower_info,
+    store_keeper_bounty, store_state, BorrowerInfo, Config, KeeperBounty, State,
+};
+use crate::tax::deduct_tax_cached;
+
+use moneymarket::error::ContractError;
+use moneymarket::market::KeeperBountyResponse;
+
+pub fn update_keeper_bounty<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    bounty_amount: Uint256,
+    epoch_cap: Uint256,
+    stale_blocks: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if stale_blocks == 0 {
+        return Err(ContractError::ZeroParameter {
+            name: "stale_blocks".to_string(),
+        }
+        .into());
+    }
+
+    // the amount paid in the current epoch is kept
+    let paid_amount = read_keeper_bounty(&deps.storage)?
+        .map(|v| v.paid_amount)
+        .unwrap_or_else(Uint256::zero);
+    store_keeper_bounty(
+        &mut deps.storage,
+        &KeeperBounty {
+            bounty_amount,
+            epoch_cap,
+            stale_blocks,
+            paid_amount,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_keeper_bounty"),
+            log("bounty_amount", bounty_amount),
+            log("epoch_cap", epoch_cap),
+            log("stale_blocks", stale_blocks),
+        ],
+        data: None,
+    })
+}
+
+/// Accrues the interest and the ANC rewards of a position not updated
+/// for `stale_blocks`, the caller is paid the bounty out of the reserves
+/// while the cap of the epoch is not reached
+pub fn poke_position<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let mut bounty: KeeperBounty = match read_keeper_bounty(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::KeeperBountiesDisabled {}.into()),
+    };
+
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    if liability.loan_amount.is_zero() {
+        return Err(ContractError::NoLoan {}.into());
+    }
+
+    if env.block.height < liability.last_updated + bounty.stale_blocks {
+        return Err(ContractError::PositionNotStale {
+            stale_height: liability.last_updated + bounty.stale_blocks,
+        }
+        .into());
+    }
+
+    // Compute interest
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_borrower_interest(&mut state, &mut liability);
+
+    // Compute ANC reward
+    compute_reward(&mut state, env.block.height, env.block.time);
+    compute_borrower_reward(&state, &mut liability);
+
+    let cap_left = if bounty.epoch_cap > bounty.paid_amount {
+        bounty.epoch_cap - bounty.paid_amount
+    } else {
+        Uint256::zero()
+    };
+    let bounty_amount = std::cmp::min(
+        std::cmp::min(bounty.bounty_amount, cap_left),
+        state.total_reserves * Uint256::one(),
+    );
+
+    let keeper = env.message.sender;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !bounty_amount.is_zero() {
+        state.total_reserves = state.total_reserves - Decimal256::from_uint256(bounty_amount);
+        bounty.paid_amount += bounty_amount;
+        store_keeper_bounty(&mut deps.storage, &bounty)?;
+
+        messages = recall_shortfall(deps, &config, bounty_amount)?;
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: keeper.clone(),
+            amount: vec![deduct_tax_cached(
+                deps,
+                env.block.height,
+                Coin {
+                    denom: config.stable_denom,
+                    amount: bounty_amount.into(),
+                },
+            )?],
+        }));
+    }
+
+    liability.last_updated = env.block.height;
+    store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![
+                log("action", "poke_position"),
+                log("borrower", borrower),
+                log("keeper", keeper),
+                log("bounty_amount", bounty_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Opens the bounty cap of the new epoch
+pub(crate) fn reset_keeper_bounty<S: Storage>(storage: &mut S) -> StdResult<()> {
+    if let Some(mut bounty) = read_keeper_bounty(storage)? {
+        bounty.paid_amount = Uint256::zero();
+        store_keeper_bounty(storage, &bounty)?;
+    }
+
+    Ok(())
+}
+
+pub fn query_keeper_bounty<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<KeeperBountyResponse> {
+    let bounty: KeeperBounty = match read_keeper_bounty(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::KeeperBountiesDisabled {}.into()),
+    };
+
+    Ok(KeeperBountyResponse {
+        bounty_amount: bounty.bounty_amount,
+        epoch_cap: bounty.epoch_cap,
+        stale_blocks: bounty.stale_blocks,
+        paid_amount: bounty.paid_amount,
+    })
+}
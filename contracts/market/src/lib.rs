@@ -1,8 +1,38 @@
+pub mod attestation;
+pub mod bootstrap;
 pub mod borrow;
 pub mod contract;
+pub mod credit_lines;
+pub mod cw20_stables;
+pub mod debt_auction;
 pub mod deposit;
+pub mod donations;
+pub mod exchange_rate_feed;
+pub mod flash_loan;
+pub mod flash_mint;
+pub mod flow_limit;
+pub mod idle_deployment;
+pub mod invariants;
+pub mod keeper;
+pub mod loans;
+pub mod operation_mode;
+pub mod prune;
 pub mod querier;
+pub mod rate_snapshots;
+pub mod redemption_fee;
+pub mod redemption_queue;
+pub mod registry;
+pub mod repayments;
+pub mod reserves;
+pub mod reward_indexes;
+pub mod shadow_interest_model;
+pub mod stable_denoms;
+pub mod stable_rate;
 pub mod state;
+pub mod state_history;
+pub mod tax;
+pub mod time_accrual;
+pub mod tranche;
 
 mod migration;
 
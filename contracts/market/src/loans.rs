@@ -0,0 +1,443 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, LogAttribute,
+    Querier, StdError, StdResult, Storage,
+};
+
+use crate::borrow::{
+    borrow_stable, compute_borrower_interest, compute_borrower_reward, compute_interest,
+    compute_reward, repay_stable,
+};
+use crate::operation_mode::{assert_borrowing_enabled, assert_repayments_enabled};
+use crate::rate_snapshots::record_rate_snapshot;
+use crate::stable_rate::{add_stable_loan, remove_stable_loan};
+use crate::state::{
+    read_amortizing_loan, read_borrower_info, read_config, read_loan_params, read_state,
+    remove_amortizing_loan, store_amortizing_loan, store_borrower_info, store_loan_params,
+    store_state, AmortizingLoan, BorrowerInfo, Config, LoanParams, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{AmortizingLoanResponse, LoanParamsResponse};
+
+pub fn update_loan_params<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    period_blocks: u64,
+    max_installments: u32,
+    penalty_rate: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if period_blocks == 0 {
+        return Err(ContractError::ZeroParameter {
+            name: "period_blocks".to_string(),
+        }
+        .into());
+    }
+
+    if max_installments == 0 {
+        return Err(ContractError::ZeroParameter {
+            name: "max_installments".to_string(),
+        }
+        .into());
+    }
+
+    if penalty_rate > Decimal256::one() {
+        return Err(ContractError::RatioAboveOne {
+            name: "penalty_rate".to_string(),
+        }
+        .into());
+    }
+
+    store_loan_params(
+        &mut deps.storage,
+        &LoanParams {
+            period_blocks,
+            max_installments,
+            penalty_rate,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_loan_params"),
+            log("period_blocks", period_blocks),
+            log("max_installments", max_installments),
+            log("penalty_rate", penalty_rate),
+        ],
+        data: None,
+    })
+}
+
+/// The loan is borrowed into the liability of the borrower, so it
+/// accrues interest and is covered by the collaterals and liquidated
+/// like any loan. The schedule only tracks the principal repaid
+/// through `RepayInstallment` and `PayoffLoan`
+pub fn open_loan<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+    installments: u32,
+    to: Option<HumanAddr>,
+) -> HandleResult {
+    assert_borrowing_enabled(deps, env.block.height)?;
+    let params: LoanParams = match read_loan_params(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::AmortizingLoansDisabled {}.into()),
+    };
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroLoan {}.into());
+    }
+
+    if installments == 0 || installments > params.max_installments {
+        return Err(ContractError::InvalidInstallments {
+            max_installments: params.max_installments,
+        }
+        .into());
+    }
+
+    let borrower = env.message.sender.clone();
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    if read_amortizing_loan(&deps.storage, &borrower_raw)?.is_some() {
+        return Err(ContractError::AmortizingLoanOpen {}.into());
+    }
+
+    let res = borrow_stable(deps, env.clone(), amount, to, None, None)?;
+
+    let loan = AmortizingLoan {
+        principal: amount,
+        installments,
+        start_height: env.block.height,
+        period_blocks: params.period_blocks,
+        penalty_rate: params.penalty_rate,
+        repaid_amount: Uint256::zero(),
+        checked_installments: 0,
+        penalty_amount: Uint256::zero(),
+    };
+    store_amortizing_loan(&mut deps.storage, &borrower_raw, &loan)?;
+
+    Ok(HandleResponse {
+        messages: res.messages,
+        log: [
+            vec![
+                log("action", "open_loan"),
+                log("borrower", borrower),
+                log("principal", amount),
+                log("installments", installments),
+                log("first_due_height", env.block.height + params.period_blocks),
+            ],
+            res.log,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn repay_installment<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    assert_repayments_enabled(deps)?;
+    let config: Config = read_config(&deps.storage)?;
+    let borrower = env.message.sender.clone();
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut loan = read_open_loan(&deps.storage, &borrower_raw)?;
+
+    let (penalty_amount, loan_amount, accrual_logs) = charge_penalties(
+        deps,
+        &config,
+        env.block.height,
+        env.block.time,
+        &borrower_raw,
+        &mut loan,
+    )?;
+
+    let amount: Uint256 = sent_stable(&config, &env);
+    let repaid_amount = std::cmp::min(
+        std::cmp::min(amount, loan.principal - loan.repaid_amount),
+        loan_amount,
+    );
+
+    let res = repay_stable(deps, env)?;
+
+    loan.repaid_amount += repaid_amount;
+    let remaining_principal = loan.principal - loan.repaid_amount;
+    if remaining_principal.is_zero() || loan_amount == repaid_amount {
+        remove_amortizing_loan(&mut deps.storage, &borrower_raw);
+    } else {
+        store_amortizing_loan(&mut deps.storage, &borrower_raw, &loan)?;
+    }
+
+    Ok(HandleResponse {
+        messages: res.messages,
+        log: [
+            vec![
+                log("action", "repay_installment"),
+                log("borrower", borrower),
+                log("repaid_amount", repaid_amount),
+                log("penalty_amount", penalty_amount),
+                log("remaining_principal", remaining_principal),
+            ],
+            accrual_logs,
+            res.log,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Installments already missed are charged before the payoff,
+/// the ones not yet due are waived
+pub fn payoff_loan<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    assert_repayments_enabled(deps)?;
+    let config: Config = read_config(&deps.storage)?;
+    let borrower = env.message.sender.clone();
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut loan = read_open_loan(&deps.storage, &borrower_raw)?;
+
+    let (penalty_amount, loan_amount, accrual_logs) = charge_penalties(
+        deps,
+        &config,
+        env.block.height,
+        env.block.time,
+        &borrower_raw,
+        &mut loan,
+    )?;
+
+    let remaining_principal = std::cmp::min(loan.principal - loan.repaid_amount, loan_amount);
+    if sent_stable(&config, &env) < remaining_principal {
+        return Err(ContractError::PayoffTooSmall {
+            remaining_principal,
+        }
+        .into());
+    }
+
+    let res = repay_stable(deps, env)?;
+    remove_amortizing_loan(&mut deps.storage, &borrower_raw);
+
+    Ok(HandleResponse {
+        messages: res.messages,
+        log: [
+            vec![
+                log("action", "payoff_loan"),
+                log("borrower", borrower),
+                log("repaid_amount", remaining_principal),
+                log("penalty_amount", penalty_amount),
+            ],
+            accrual_logs,
+            res.log,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn apply_loan_penalty<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut loan = read_open_loan(&deps.storage, &borrower_raw)?;
+
+    let (penalty_amount, loan_amount, accrual_logs) = charge_penalties(
+        deps,
+        &config,
+        env.block.height,
+        env.block.time,
+        &borrower_raw,
+        &mut loan,
+    )?;
+
+    // a loan repaid by other means, such as a liquidation, closes the schedule
+    let closed = loan_amount.is_zero();
+    if closed {
+        remove_amortizing_loan(&mut deps.storage, &borrower_raw);
+    } else {
+        store_amortizing_loan(&mut deps.storage, &borrower_raw, &loan)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "apply_loan_penalty"),
+                log("borrower", borrower),
+                log("penalty_amount", penalty_amount),
+                log("closed", closed),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Moves the schedule along with the liability; a stale
+/// schedule of the receiver, whose loan was closed, is dropped
+pub(crate) fn transfer_amortizing_loan<S: Storage>(
+    storage: &mut S,
+    from: &CanonicalAddr,
+    to: &CanonicalAddr,
+) -> StdResult<()> {
+    match read_amortizing_loan(storage, from)? {
+        Some(loan) => {
+            store_amortizing_loan(storage, to, &loan)?;
+            remove_amortizing_loan(storage, from);
+        }
+        None => remove_amortizing_loan(storage, to),
+    }
+
+    Ok(())
+}
+
+fn read_open_loan<S: Storage>(storage: &S, borrower: &CanonicalAddr) -> StdResult<AmortizingLoan> {
+    match read_amortizing_loan(storage, borrower)? {
+        Some(v) => Ok(v),
+        None => Err(ContractError::NoAmortizingLoan {}.into()),
+    }
+}
+
+fn sent_stable(config: &Config, env: &Env) -> Uint256 {
+    env.message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero)
+}
+
+/// Accrues the liability of the borrower and adds the penalties of
+/// the due dates passed since the last check to its loan.
+/// Returns (penalty_amount, loan_amount, accrual_logs)
+fn charge_penalties<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    config: &Config,
+    block_height: u64,
+    block_time: u64,
+    borrower: &CanonicalAddr,
+    loan: &mut AmortizingLoan,
+) -> StdResult<(Uint256, Uint256, Vec<LogAttribute>)> {
+    let mut state: State = read_state(&deps.storage)?;
+    let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, borrower);
+
+    let accrual_logs = compute_interest(deps, config, &mut state, block_height, block_time, None)?;
+    compute_borrower_interest(&mut state, &mut liability);
+    compute_reward(&mut state, block_height, block_time);
+    compute_borrower_reward(&state, &mut liability);
+
+    let mut penalty_amount = Uint256::zero();
+    let due_installments = compute_due_installments(loan, block_height);
+    for installment in (loan.checked_installments + 1)..=due_installments {
+        let overdue_amount = compute_scheduled_amount(loan, installment);
+        if overdue_amount > loan.repaid_amount {
+            penalty_amount += (overdue_amount - loan.repaid_amount) * loan.penalty_rate;
+        }
+    }
+    loan.checked_installments = std::cmp::max(loan.checked_installments, due_installments);
+
+    if liability.loan_amount.is_zero() {
+        penalty_amount = Uint256::zero();
+    }
+
+    remove_stable_loan(&mut state, &liability);
+    liability.loan_amount += penalty_amount;
+    add_stable_loan(&mut state, &liability);
+    liability.last_updated = block_height;
+    state.total_liabilities += Decimal256::from_uint256(penalty_amount);
+    loan.penalty_amount += penalty_amount;
+
+    store_borrower_info(&mut deps.storage, borrower, &liability)?;
+    store_state(&mut deps.storage, &state)?;
+    if !penalty_amount.is_zero() {
+        record_rate_snapshot(
+            deps,
+            config,
+            &state,
+            borrower,
+            liability.loan_amount,
+            block_height,
+        )?;
+    }
+
+    Ok((penalty_amount, liability.loan_amount, accrual_logs))
+}
+
+/// # of due dates passed at `block_height`
+fn compute_due_installments(loan: &AmortizingLoan, block_height: u64) -> u32 {
+    let periods = block_height.saturating_sub(loan.start_height) / loan.period_blocks;
+    std::cmp::min(periods, loan.installments as u64) as u32
+}
+
+/// Principal to be repaid by the `installment`th due date,
+/// the last installment absorbs the rounding
+fn compute_scheduled_amount(loan: &AmortizingLoan, installment: u32) -> Uint256 {
+    if installment >= loan.installments {
+        return loan.principal;
+    }
+
+    loan.principal * Decimal256::from_ratio(installment, loan.installments)
+}
+
+pub fn query_loan_params<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<LoanParamsResponse> {
+    let params: LoanParams = match read_loan_params(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::AmortizingLoansDisabled {}.into()),
+    };
+
+    Ok(LoanParamsResponse {
+        period_blocks: params.period_blocks,
+        max_installments: params.max_installments,
+        penalty_rate: params.penalty_rate,
+    })
+}
+
+pub fn query_amortizing_loan<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    block_height: u64,
+) -> StdResult<AmortizingLoanResponse> {
+    let loan = read_open_loan(&deps.storage, &deps.api.canonical_address(&borrower)?)?;
+
+    let due_installments = compute_due_installments(&loan, block_height);
+    let next_due_height = if due_installments < loan.installments {
+        Some(loan.start_height + (due_installments as u64 + 1) * loan.period_blocks)
+    } else {
+        None
+    };
+
+    let scheduled_amount = compute_scheduled_amount(
+        &loan,
+        std::cmp::min(due_installments + 1, loan.installments),
+    );
+    let amount_due = if scheduled_amount > loan.repaid_amount {
+        scheduled_amount - loan.repaid_amount
+    } else {
+        Uint256::zero()
+    };
+
+    Ok(AmortizingLoanResponse {
+        borrower,
+        principal: loan.principal,
+        installments: loan.installments,
+        start_height: loan.start_height,
+        period_blocks: loan.period_blocks,
+        penalty_rate: loan.penalty_rate,
+        repaid_amount: loan.repaid_amount,
+        penalty_amount: loan.penalty_amount,
+        next_due_height,
+        amount_due,
+    })
+}
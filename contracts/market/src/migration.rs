@@ -73,6 +73,14 @@ pub fn migrate_config<S: Storage>(
             distributor_contract: legacy_config.distributor_contract,
             stable_denom: legacy_config.stable_denom,
             max_borrow_factor: legacy_config.max_borrow_factor,
+            global_borrow_cap: None,
+            per_account_borrow_cap: None,
+            min_deposit_amount: None,
+            min_redeem_amount: None,
+            flow_window: None,
+            max_net_flow_ratio: None,
+            guardian: None,
+            reserve_factor: legacy_config.reserve_factor,
             collector_contract,
         },
     )
@@ -99,6 +107,15 @@ pub fn migrate_state<S: Storage>(
             anc_emission_rate: legacy_state.anc_emission_rate,
             prev_aterra_supply: aterra_supply,
             prev_exchange_rate: exchange_rate,
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
 }
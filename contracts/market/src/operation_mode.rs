@@ -0,0 +1,165 @@
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, Querier, StdError, StdResult, Storage,
+};
+
+use crate::querier::query_guardians;
+use crate::state::{read_config, read_operation_mode, store_operation_mode, Config};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::OperationModeResponse;
+use moneymarket::protocol_mode::load_protocol_mode;
+
+/// Only the guardians registered in the overseer can change the mode
+pub fn update_operation_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    deposits_enabled: Option<bool>,
+    redemptions_enabled: Option<bool>,
+    borrows_enabled: Option<bool>,
+    repayments_enabled: Option<bool>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let guardians = query_guardians(deps, &deps.api.human_address(&config.overseer_contract)?)?;
+    if !guardians.contains(&env.message.sender) {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut mode = read_operation_mode(&deps.storage)?;
+    if let Some(deposits_enabled) = deposits_enabled {
+        mode.deposits_enabled = deposits_enabled;
+    }
+
+    if let Some(redemptions_enabled) = redemptions_enabled {
+        mode.redemptions_enabled = redemptions_enabled;
+    }
+
+    if let Some(borrows_enabled) = borrows_enabled {
+        mode.borrows_enabled = borrows_enabled;
+    }
+
+    if let Some(repayments_enabled) = repayments_enabled {
+        mode.repayments_enabled = repayments_enabled;
+    }
+
+    store_operation_mode(&mut deps.storage, &mode)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_operation_mode"),
+            log("deposits_enabled", mode.deposits_enabled),
+            log("redemptions_enabled", mode.redemptions_enabled),
+            log("borrows_enabled", mode.borrows_enabled),
+            log("repayments_enabled", mode.repayments_enabled),
+        ],
+        data: None,
+    })
+}
+
+/// The guardian of the config freezes entry points during incidents,
+/// the owner can also change them, e.g. to unpause. Pausing closes the
+/// same entry points as the operation mode
+pub fn set_pause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    deposit: Option<bool>,
+    redeem: Option<bool>,
+    borrow: Option<bool>,
+    repay: Option<bool>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    if sender_raw != config.owner_addr && Some(sender_raw) != config.guardian {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut mode = read_operation_mode(&deps.storage)?;
+    if let Some(deposit) = deposit {
+        mode.deposits_enabled = !deposit;
+    }
+
+    if let Some(redeem) = redeem {
+        mode.redemptions_enabled = !redeem;
+    }
+
+    if let Some(borrow) = borrow {
+        mode.borrows_enabled = !borrow;
+    }
+
+    if let Some(repay) = repay {
+        mode.repayments_enabled = !repay;
+    }
+
+    store_operation_mode(&mut deps.storage, &mode)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_pause"),
+            log("deposit", !mode.deposits_enabled),
+            log("redeem", !mode.redemptions_enabled),
+            log("borrow", !mode.borrows_enabled),
+            log("repay", !mode.repayments_enabled),
+        ],
+        data: None,
+    })
+}
+
+// Each entry point group checks its flag, then the protocol mode
+
+pub(crate) fn assert_deposits_enabled<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<()> {
+    if !read_operation_mode(&deps.storage)?.deposits_enabled {
+        return Err(ContractError::DepositsDisabled {}.into());
+    }
+
+    load_protocol_mode(deps, block_height, false)?.assert_deposits_allowed()
+}
+
+pub(crate) fn assert_redemptions_enabled<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<()> {
+    if !read_operation_mode(&deps.storage)?.redemptions_enabled {
+        return Err(ContractError::RedemptionsDisabled {}.into());
+    }
+
+    load_protocol_mode(deps, block_height, false)?.assert_withdrawals_allowed()
+}
+
+pub(crate) fn assert_borrowing_enabled<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<()> {
+    if !read_operation_mode(&deps.storage)?.borrows_enabled {
+        return Err(ContractError::BorrowsDisabled {}.into());
+    }
+
+    load_protocol_mode(deps, block_height, false)?.assert_borrows_allowed()
+}
+
+/// No protocol mode closes the repayments, so the
+/// protocol controller is not queried for them
+pub(crate) fn assert_repayments_enabled<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<()> {
+    if !read_operation_mode(&deps.storage)?.repayments_enabled {
+        return Err(ContractError::RepaymentsDisabled {}.into());
+    }
+
+    Ok(())
+}
+
+pub fn query_operation_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<OperationModeResponse> {
+    let mode = read_operation_mode(&deps.storage)?;
+    Ok(OperationModeResponse {
+        deposits_enabled: mode.deposits_enabled,
+        redemptions_enabled: mode.redemptions_enabled,
+        borrows_enabled: mode.borrows_enabled,
+        repayments_enabled: mode.repayments_enabled,
+    })
+}
@@ -0,0 +1,54 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{log, Api, Env, Extern, HandleResponse, HandleResult, Querier, Storage};
+
+use crate::state::{read_liabilities, read_prune_cursor, remove_borrower_info, store_prune_cursor};
+
+/// Number of blocks a closed position must stay untouched before it can be pruned
+pub const CLOSED_POSITION_MIN_AGE: u64 = 100800;
+
+const DEFAULT_PRUNE_LIMIT: u32 = 10;
+const MAX_PRUNE_LIMIT: u32 = 30;
+
+/// Scans up to `limit` liabilities from where the previous call stopped,
+/// wrapping around at the end, and removes the closed ones: no loan and
+/// less than one unit of pending rewards. The totals in the state are
+/// not affected. Collaterals are kept by the overseer, so a borrower with
+/// locked collaterals but no loan loses nothing when its entry is removed;
+/// a pruned liability reads back as a fresh one
+pub fn prune_closed_positions<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    limit: Option<u32>,
+) -> HandleResult {
+    let limit = limit.unwrap_or(DEFAULT_PRUNE_LIMIT).min(MAX_PRUNE_LIMIT);
+    let cursor = read_prune_cursor(&deps.storage)?;
+    let liabilities = read_liabilities(&deps.storage, cursor, limit)?;
+
+    let next_cursor = if liabilities.len() < limit as usize {
+        None
+    } else {
+        liabilities.last().map(|(borrower, _)| borrower.clone())
+    };
+    store_prune_cursor(&mut deps.storage, &next_cursor)?;
+
+    let mut pruned: u32 = 0;
+    for (borrower, liability) in liabilities.iter() {
+        if liability.loan_amount.is_zero()
+            && liability.pending_rewards < Decimal256::one()
+            && liability.last_updated + CLOSED_POSITION_MIN_AGE <= env.block.height
+        {
+            remove_borrower_info(&mut deps.storage, borrower);
+            pruned += 1;
+        }
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "prune_closed_positions"),
+            log("scanned", liabilities.len()),
+            log("pruned", pruned),
+        ],
+        data: None,
+    })
+}
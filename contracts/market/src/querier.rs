@@ -79,3 +79,16 @@ pub fn query_target_deposit_rate<S: Storage, A: Api, Q: Querier>(
 
     Ok(overseer_config.target_deposit_rate)
 }
+
+pub fn query_guardians<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    overseer_contract: &HumanAddr,
+) -> StdResult<Vec<HumanAddr>> {
+    let overseer_config: ConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(overseer_contract),
+            msg: to_binary(&OverseerQueryMsg::Config {})?,
+        }))?;
+
+    Ok(overseer_config.guardians)
+}
@@ -0,0 +1,71 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Api, CanonicalAddr, Extern, HumanAddr, Querier, StdResult, Storage};
+
+use crate::borrow::clamp_model_reserves;
+use crate::idle_deployment::query_market_balance;
+use crate::querier::query_borrow_rate;
+use crate::state::{read_rate_snapshot, store_rate_snapshot, Config, RateSnapshot, State};
+
+use moneymarket::interest_model::BorrowRateResponse;
+use moneymarket::market::RateSnapshotResponse;
+
+/// Records the rates the loan of `borrower` accrues from after it is
+/// originated or modified at `block_height`, `state` being accrued to
+/// that height. The borrow rate is the one the interest model quotes
+/// for the market balance before the transfers of the message
+pub(crate) fn record_rate_snapshot<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    config: &Config,
+    state: &State,
+    borrower: &CanonicalAddr,
+    loan_amount: Uint256,
+    block_height: u64,
+) -> StdResult<()> {
+    let balance = query_market_balance(deps, config)?;
+    let (model_reserves, _) = clamp_model_reserves(state, balance);
+    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+        deps,
+        &deps.api.human_address(&config.interest_model)?,
+        balance,
+        state.total_liabilities,
+        model_reserves,
+    )?;
+
+    store_rate_snapshot(
+        &mut deps.storage,
+        borrower,
+        &RateSnapshot {
+            height: block_height,
+            loan_amount,
+            borrow_rate: borrow_rate_res.rate,
+            interest_index: state.global_interest_index,
+            exchange_rate: state.prev_exchange_rate,
+        },
+    )
+}
+
+pub fn query_rate_snapshot<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<RateSnapshotResponse> {
+    Ok(
+        match read_rate_snapshot(&deps.storage, &deps.api.canonical_address(&borrower)?)? {
+            Some(v) => RateSnapshotResponse {
+                borrower,
+                height: v.height,
+                loan_amount: v.loan_amount,
+                borrow_rate: v.borrow_rate,
+                interest_index: v.interest_index,
+                exchange_rate: v.exchange_rate,
+            },
+            None => RateSnapshotResponse {
+                borrower,
+                height: 0,
+                loan_amount: Uint256::zero(),
+                borrow_rate: Decimal256::zero(),
+                interest_index: Decimal256::zero(),
+                exchange_rate: Decimal256::zero(),
+            },
+        },
+    )
+}
@@ -0,0 +1,145 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+
+use crate::state::{
+    is_fee_exempt_redeemer, read_config, read_fee_exempt_redeemers, read_redemption_fee,
+    remove_fee_exempt_redeemer, store_fee_exempt_redeemer, store_redemption_fee, Config,
+    RedemptionFee,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{FeeExemptRedeemersResponse, RedemptionFeeResponse};
+
+pub fn update_redemption_fee<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    free_ratio: Decimal256,
+    max_fee_rate: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if free_ratio >= Decimal256::one() {
+        return Err(ContractError::FreeRatioTooHigh {}.into());
+    }
+
+    if max_fee_rate > Decimal256::one() {
+        return Err(ContractError::RatioAboveOne {
+            name: "max_fee_rate".to_string(),
+        }
+        .into());
+    }
+
+    store_redemption_fee(
+        &mut deps.storage,
+        &RedemptionFee {
+            free_ratio,
+            max_fee_rate,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_redemption_fee"),
+            log("free_ratio", free_ratio),
+            log("max_fee_rate", max_fee_rate),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_fee_exempt_redeemer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    redeemer: HumanAddr,
+    exempt: bool,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let redeemer_raw = deps.api.canonical_address(&redeemer)?;
+    if exempt {
+        store_fee_exempt_redeemer(&mut deps.storage, &redeemer_raw)?;
+    } else {
+        remove_fee_exempt_redeemer(&mut deps.storage, &redeemer_raw);
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_fee_exempt_redeemer"),
+            log("redeemer", redeemer),
+            log("exempt", exempt),
+        ],
+        data: None,
+    })
+}
+
+/// Returns the fee charged on `redeem_amount`, which is zero up to
+/// `free_ratio` of the available liquidity and grows linearly to
+/// `max_fee_rate` for a redemption draining the whole liquidity.
+/// Protocol contracts on the exempt list are never charged
+pub(crate) fn compute_redemption_fee<S: Storage>(
+    storage: &S,
+    redeemer: Option<&CanonicalAddr>,
+    redeem_amount: Uint256,
+    available_liquidity: Uint256,
+) -> StdResult<Uint256> {
+    if let Some(redeemer) = redeemer {
+        if is_fee_exempt_redeemer(storage, redeemer)? {
+            return Ok(Uint256::zero());
+        }
+    }
+
+    let fee = match read_redemption_fee(storage)? {
+        Some(v) if !v.max_fee_rate.is_zero() => v,
+        _ => return Ok(Uint256::zero()),
+    };
+
+    if available_liquidity.is_zero() {
+        return Ok(Uint256::zero());
+    }
+
+    let redeem_ratio =
+        Decimal256::from_uint256(redeem_amount) / Decimal256::from_uint256(available_liquidity);
+    if redeem_ratio <= fee.free_ratio {
+        return Ok(Uint256::zero());
+    }
+
+    let fee_rate =
+        fee.max_fee_rate * (redeem_ratio - fee.free_ratio) / (Decimal256::one() - fee.free_ratio);
+    Ok(redeem_amount * fee_rate)
+}
+
+pub fn query_redemption_fee<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<RedemptionFeeResponse> {
+    let fee = read_redemption_fee(&deps.storage)?.unwrap_or(RedemptionFee {
+        free_ratio: Decimal256::zero(),
+        max_fee_rate: Decimal256::zero(),
+    });
+
+    Ok(RedemptionFeeResponse {
+        free_ratio: fee.free_ratio,
+        max_fee_rate: fee.max_fee_rate,
+    })
+}
+
+pub fn query_fee_exempt_redeemers<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<FeeExemptRedeemersResponse> {
+    let redeemers = read_fee_exempt_redeemers(&deps.storage)?
+        .iter()
+        .map(|v| deps.api.human_address(v))
+        .collect::<StdResult<Vec<HumanAddr>>>()?;
+
+    Ok(FeeExemptRedeemersResponse { redeemers })
+}
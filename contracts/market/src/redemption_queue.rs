@@ -0,0 +1,275 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, LogAttribute, Querier, StdError, StdResult, Storage, Uint128, WasmMsg,
+};
+
+use crate::borrow::{compute_interest, compute_reward};
+use crate::deposit::{compute_exchange_rate, redeem_stable};
+use crate::idle_deployment::{query_market_balance, recall_shortfall};
+use crate::operation_mode::assert_redemptions_enabled;
+use crate::state::{
+    push_queued_redemption, read_config, read_depositor_info, read_queued_redemptions, read_state,
+    remove_queued_redemption, store_depositor_info, store_state, Config, DepositorInfo,
+    QueuedRedemption, State,
+};
+use crate::tax::deduct_tax_cached;
+
+use cw20::Cw20HandleMsg;
+use moneymarket::error::ContractError;
+use moneymarket::market::{QueuedRedemptionResponse, QueuedRedemptionsResponse};
+
+/// Max # of queued redemptions serviced by a deposit or a repayment
+const MAX_SERVICED_REDEMPTIONS: u32 = 5;
+
+/// Redeems the sent aterra when the market has the liquidity for it,
+/// otherwise escrows it at the end of the redemption queue
+pub fn redeem_stable_or_queue<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    sender: HumanAddr,
+    burn_amount: Uint128,
+) -> HandleResult {
+    assert_redemptions_enabled(deps, env.block.height)?;
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_reward(&mut state, env.block.height, env.block.time);
+    store_state(&mut deps.storage, &state)?;
+
+    // The redemptions queued before are serviced first
+    let queue_empty = read_queued_redemptions(&deps.storage, None, Some(1))?.is_empty();
+    if queue_empty {
+        let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
+        let redeem_amount = Uint256::from(burn_amount) * exchange_rate;
+        if redeem_amount <= available_liquidity(deps, &config, &state, Uint256::zero())? {
+            return redeem_stable(deps, env, sender, burn_amount);
+        }
+    }
+
+    let id = push_queued_redemption(
+        &mut deps.storage,
+        &QueuedRedemption {
+            redeemer: deps.api.canonical_address(&sender)?,
+            aterra_amount: Uint256::from(burn_amount),
+            queued_height: env.block.height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "queue_redemption"),
+                log("redemption_id", id),
+                log("redeemer", sender),
+                log("aterra_amount", burn_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Lets the redeemer of the redemption at the head of the queue redeem it
+/// as soon as the market has the liquidity, without waiting for a deposit
+pub fn claim_redemption<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    id: u64,
+) -> HandleResult {
+    let (head_id, head) = match read_queued_redemptions(&deps.storage, None, Some(1))?.pop() {
+        Some(v) => v,
+        None => return Err(ContractError::RedemptionQueueEmpty {}.into()),
+    };
+
+    if id != head_id {
+        return Err(ContractError::NotQueueHead {}.into());
+    }
+
+    if deps.api.canonical_address(&env.message.sender)? != head.redeemer {
+        return Err(StdError::unauthorized());
+    }
+
+    assert_redemptions_enabled(deps, env.block.height)?;
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_reward(&mut state, env.block.height, env.block.time);
+    store_state(&mut deps.storage, &state)?;
+
+    let (messages, service_logs) =
+        service_queue(deps, &env, &config, 1, Uint256::zero(), Uint256::zero())?;
+    if messages.is_empty() {
+        return Err(ContractError::RedemptionLiquidity {}.into());
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![log("action", "claim_redemption"), log("redemption_id", id)],
+            service_logs,
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Services the queued redemptions in order with the liquidity a deposit
+/// or a repayment brought, stopping at the first one it cannot cover.
+/// `pending_payout` is sent out by the calling handler and is not available.
+/// `pending_deposit` is deposited by the calling handler, whose aterra is
+/// not minted yet, so it is excluded from the exchange rate
+pub(crate) fn service_redemption_queue<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    pending_payout: Uint256,
+    pending_deposit: Uint256,
+) -> StdResult<(Vec<CosmosMsg>, Vec<LogAttribute>)> {
+    if read_queued_redemptions(&deps.storage, None, Some(1))?.is_empty()
+        || assert_redemptions_enabled(deps, env.block.height).is_err()
+    {
+        return Ok((vec![], vec![]));
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    service_queue(
+        deps,
+        env,
+        &config,
+        MAX_SERVICED_REDEMPTIONS,
+        pending_payout,
+        pending_deposit,
+    )
+}
+
+/// Interest must already be accrued at the current height
+fn service_queue<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    max_redemptions: u32,
+    pending_payout: Uint256,
+    pending_deposit: Uint256,
+) -> StdResult<(Vec<CosmosMsg>, Vec<LogAttribute>)> {
+    let mut state: State = read_state(&deps.storage)?;
+    let exchange_rate =
+        compute_exchange_rate(deps, config, &state, Some(pending_payout + pending_deposit))?;
+    let mut liquidity = available_liquidity(deps, config, &state, pending_payout)?;
+
+    let mut burn_amount = Uint256::zero();
+    let mut payouts: Vec<(HumanAddr, Uint256)> = vec![];
+    for (id, redemption) in read_queued_redemptions(&deps.storage, None, Some(max_redemptions))? {
+        let redeem_amount = redemption.aterra_amount * exchange_rate;
+        if redeem_amount > liquidity {
+            break;
+        }
+
+        liquidity = liquidity - redeem_amount;
+        burn_amount += redemption.aterra_amount;
+        remove_queued_redemption(&mut deps.storage, id);
+
+        let mut depositor_info: DepositorInfo =
+            read_depositor_info(&deps.storage, &redemption.redeemer);
+        depositor_info.total_redeemed += redeem_amount;
+        store_depositor_info(&mut deps.storage, &redemption.redeemer, &depositor_info)?;
+
+        payouts.push((deps.api.human_address(&redemption.redeemer)?, redeem_amount));
+    }
+
+    if payouts.is_empty() {
+        return Ok((vec![], vec![]));
+    }
+
+    state.prev_aterra_supply = state.prev_aterra_supply - burn_amount;
+    store_state(&mut deps.storage, &state)?;
+
+    let redeem_amount = payouts
+        .iter()
+        .fold(Uint256::zero(), |sum, (_, amount)| sum + *amount);
+    let mut messages = recall_shortfall(deps, config, redeem_amount + pending_payout)?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&config.aterra_contract)?,
+        send: vec![],
+        msg: to_binary(&Cw20HandleMsg::Burn {
+            amount: burn_amount.into(),
+        })?,
+    }));
+
+    let serviced_redemptions = payouts.len();
+    for (redeemer, amount) in payouts {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: redeemer,
+            amount: vec![deduct_tax_cached(
+                deps,
+                env.block.height,
+                Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: amount.into(),
+                },
+            )?],
+        }));
+    }
+
+    Ok((
+        messages,
+        vec![
+            log("serviced_redemptions", serviced_redemptions),
+            log("serviced_burn_amount", burn_amount),
+            log("serviced_redeem_amount", redeem_amount),
+        ],
+    ))
+}
+
+/// Market balance which is neither reserves nor about to be sent out
+fn available_liquidity<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    state: &State,
+    pending_payout: Uint256,
+) -> StdResult<Uint256> {
+    let balance = Decimal256::from_uint256(query_market_balance(deps, config)?);
+    let unavailable = state.total_reserves + Decimal256::from_uint256(pending_payout);
+    if balance <= unavailable {
+        return Ok(Uint256::zero());
+    }
+
+    Ok((balance - unavailable) * Uint256::one())
+}
+
+pub fn query_queued_redemptions<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<QueuedRedemptionsResponse> {
+    let redemptions = read_queued_redemptions(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(id, v)| {
+            Ok(QueuedRedemptionResponse {
+                id,
+                redeemer: deps.api.human_address(&v.redeemer)?,
+                aterra_amount: v.aterra_amount,
+                queued_height: v.queued_height,
+            })
+        })
+        .collect::<StdResult<Vec<QueuedRedemptionResponse>>>()?;
+
+    Ok(QueuedRedemptionsResponse { redemptions })
+}
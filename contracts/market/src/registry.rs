@@ -0,0 +1,209 @@
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+
+use crate::borrow::compute_interest;
+use crate::state::{
+    read_config, read_registry_cache, read_state, remove_registry_cache, store_config,
+    store_registry_cache, store_state, Config, RegistryCache, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{HandleMsg, RegistryResponse};
+use moneymarket::querier::{query_registry_resolve, query_registry_version};
+use moneymarket::registry::{
+    COLLECTOR, DISTRIBUTION_MODEL, DISTRIBUTOR, INTEREST_MODEL, OVERSEER, REGISTRY_MAX_AGE,
+};
+
+pub fn register_registry<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    registry_contract: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_registry_cache(
+        &mut deps.storage,
+        &RegistryCache {
+            registry_contract: deps.api.canonical_address(&registry_contract)?,
+            version: 0,
+            updated_height: env.block.height,
+        },
+    )?;
+
+    let version = load_registry(deps, env.block.height, env.block.time, true)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_registry"),
+            log("registry_contract", registry_contract),
+            log("version", version),
+        ],
+        data: None,
+    })
+}
+
+pub fn disable_registry<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    remove_registry_cache(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "disable_registry")],
+        data: None,
+    })
+}
+
+/// Anyone can resolve the peers again, the registry
+/// does so whenever an address changes
+pub fn refresh_registry<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    if read_registry_cache(&deps.storage)?.is_none() {
+        return Err(ContractError::NoRegistry {}.into());
+    }
+
+    let version = load_registry(deps, env.block.height, env.block.time, true)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "refresh_registry"), log("version", version)],
+        data: None,
+    })
+}
+
+/// Keeps the resolved peers up to date before any operation but the
+/// registry ones, so the owner can still replace an unreachable registry
+pub(crate) fn assert_registry_synced<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+    block_time: u64,
+    msg: &HandleMsg,
+) -> StdResult<()> {
+    match msg {
+        HandleMsg::RegisterRegistry { .. }
+        | HandleMsg::DisableRegistry {}
+        | HandleMsg::RefreshRegistry {} => Ok(()),
+        _ => load_registry(deps, block_height, block_time, false).map(|_| ()),
+    }
+}
+
+/// Checks the registry version once the resolved peers are older than
+/// REGISTRY_MAX_AGE blocks and resolves them again when it changed.
+/// Returns the registry version the peers are resolved at
+fn load_registry<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+    block_time: u64,
+    force_refresh: bool,
+) -> StdResult<u64> {
+    let mut cache = match read_registry_cache(&deps.storage)? {
+        Some(v) => v,
+        None => return Ok(0),
+    };
+
+    if !force_refresh && block_height <= cache.updated_height + REGISTRY_MAX_AGE {
+        return Ok(cache.version);
+    }
+
+    let registry_contract = deps.api.human_address(&cache.registry_contract)?;
+    let version = query_registry_version(deps, &registry_contract)?.version;
+    if force_refresh || version != cache.version {
+        cache.version = resolve_peers(deps, &registry_contract, block_height, block_time)?;
+    }
+
+    cache.updated_height = block_height;
+    store_registry_cache(&mut deps.storage, &cache)?;
+
+    Ok(cache.version)
+}
+
+/// Stores the registered addresses of the peers in the config,
+/// the unregistered ones keep their current address
+fn resolve_peers<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    registry_contract: &HumanAddr,
+    block_height: u64,
+    block_time: u64,
+) -> StdResult<u64> {
+    let names: Vec<String> = [
+        OVERSEER,
+        INTEREST_MODEL,
+        DISTRIBUTION_MODEL,
+        COLLECTOR,
+        DISTRIBUTOR,
+    ]
+    .iter()
+    .map(|v| v.to_string())
+    .collect();
+
+    let resolved = query_registry_resolve(deps, registry_contract, names)?;
+
+    let prev_config: Config = read_config(&deps.storage)?;
+    let mut config = prev_config.clone();
+    for (i, address) in resolved.addresses.iter().enumerate() {
+        let address = match address {
+            Some(v) => deps.api.canonical_address(v)?,
+            None => continue,
+        };
+
+        match i {
+            0 => config.overseer_contract = address,
+            1 => config.interest_model = address,
+            2 => config.distribution_model = address,
+            3 => config.collector_contract = address,
+            _ => config.distributor_contract = address,
+        }
+    }
+
+    // The blocks since the last accrual are charged at
+    // the rate of the model being replaced
+    if config.interest_model != prev_config.interest_model
+        && prev_config.interest_model != CanonicalAddr::default()
+    {
+        let mut state: State = read_state(&deps.storage)?;
+        compute_interest(
+            deps,
+            &prev_config,
+            &mut state,
+            block_height,
+            block_time,
+            None,
+        )?;
+        store_state(&mut deps.storage, &state)?;
+    }
+
+    store_config(&mut deps.storage, &config)?;
+
+    Ok(resolved.version)
+}
+
+pub fn query_registry<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<RegistryResponse> {
+    Ok(match read_registry_cache(&deps.storage)? {
+        Some(v) => RegistryResponse {
+            registry_contract: Some(deps.api.human_address(&v.registry_contract)?),
+            version: v.version,
+            updated_height: v.updated_height,
+        },
+        None => RegistryResponse {
+            registry_contract: None,
+            version: 0,
+            updated_height: 0,
+        },
+    })
+}
@@ -0,0 +1,175 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+
+use crate::borrow::query_borrower_info;
+use crate::state::{
+    read_config, read_dust_forgiveness, read_loan_breakdown, read_state, store_dust_forgiveness,
+    store_loan_breakdown, Config, LoanBreakdown,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{DustForgivenessResponse, LoanBreakdownResponse};
+
+/// Upper bound of the forgiven residual, one unit of the stable denom
+const MAX_DUST_FORGIVENESS: u64 = 1000000;
+
+pub fn update_dust_forgiveness<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    max_amount: Uint256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if max_amount > Uint256::from(MAX_DUST_FORGIVENESS) {
+        return Err(ContractError::DustForgivenessTooHigh {
+            max: Uint256::from(MAX_DUST_FORGIVENESS),
+        }
+        .into());
+    }
+
+    store_dust_forgiveness(&mut deps.storage, &max_amount)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_dust_forgiveness"),
+            log("max_amount", max_amount),
+        ],
+        data: None,
+    })
+}
+
+/// Loans opened before the breakdown was tracked are all principal
+fn load_loan_breakdown<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+    loan_amount: Uint256,
+) -> StdResult<LoanBreakdown> {
+    Ok(
+        read_loan_breakdown(storage, borrower)?.unwrap_or(LoanBreakdown {
+            principal: loan_amount,
+            principal_repaid: Uint256::zero(),
+            interest_repaid: Uint256::zero(),
+        }),
+    )
+}
+
+/// Adds the borrowed amount to the principal, `loan_amount`
+/// is the accrued loan before the borrow
+pub(crate) fn record_borrow<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    loan_amount: Uint256,
+    borrow_amount: Uint256,
+) -> StdResult<()> {
+    let mut breakdown = load_loan_breakdown(storage, borrower, loan_amount)?;
+    breakdown.principal += borrow_amount;
+
+    store_loan_breakdown(storage, borrower, &breakdown)
+}
+
+/// Principal left in an accrued loan of `loan_amount`
+pub(crate) fn loan_principal<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+    loan_amount: Uint256,
+) -> StdResult<Uint256> {
+    let breakdown = load_loan_breakdown(storage, borrower, loan_amount)?;
+
+    // rebates can bring the loan below the principal
+    Ok(std::cmp::min(breakdown.principal, loan_amount))
+}
+
+/// Splits the repaid amount of an accrued loan of `loan_amount`, the
+/// accrued interest and penalties are paid before the principal. The
+/// `forgiven_amount` of interest is written off without being repaid.
+/// Returns (interest_repaid, principal_repaid)
+pub(crate) fn record_repayment<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    loan_amount: Uint256,
+    repay_amount: Uint256,
+    forgiven_amount: Uint256,
+) -> StdResult<(Uint256, Uint256)> {
+    let mut breakdown = load_loan_breakdown(storage, borrower, loan_amount)?;
+
+    // rebates can bring the loan below the principal
+    let principal = std::cmp::min(breakdown.principal, loan_amount);
+    let interest_repaid = std::cmp::min(
+        repay_amount,
+        loan_amount - principal - std::cmp::min(forgiven_amount, loan_amount - principal),
+    );
+    let principal_repaid = repay_amount - interest_repaid;
+
+    breakdown.principal = principal - principal_repaid;
+    breakdown.principal_repaid += principal_repaid;
+    breakdown.interest_repaid += interest_repaid;
+    store_loan_breakdown(storage, borrower, &breakdown)?;
+
+    Ok((interest_repaid, principal_repaid))
+}
+
+/// Moves the principal along with the liability, the repaid
+/// amounts stay with the borrower who repaid them
+pub(crate) fn transfer_loan_breakdown<S: Storage>(
+    storage: &mut S,
+    from: &CanonicalAddr,
+    to: &CanonicalAddr,
+    loan_amount: Uint256,
+) -> StdResult<()> {
+    let mut from_breakdown = load_loan_breakdown(storage, from, loan_amount)?;
+    let mut to_breakdown = load_loan_breakdown(storage, to, Uint256::zero())?;
+
+    to_breakdown.principal = std::cmp::min(from_breakdown.principal, loan_amount);
+    from_breakdown.principal = Uint256::zero();
+    store_loan_breakdown(storage, from, &from_breakdown)?;
+    store_loan_breakdown(storage, to, &to_breakdown)
+}
+
+/// Clears the principal of a loan written off
+pub(crate) fn clear_principal<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut breakdown = load_loan_breakdown(storage, borrower, Uint256::zero())?;
+    breakdown.principal = Uint256::zero();
+
+    store_loan_breakdown(storage, borrower, &breakdown)
+}
+
+pub fn query_dust_forgiveness<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<DustForgivenessResponse> {
+    Ok(DustForgivenessResponse {
+        max_amount: read_dust_forgiveness(&deps.storage)?,
+        forgiven_dust: read_state(&deps.storage)?.forgiven_dust,
+    })
+}
+
+pub fn query_loan_breakdown<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    block_height: Option<u64>,
+) -> StdResult<LoanBreakdownResponse> {
+    let loan_amount = query_borrower_info(deps, borrower.clone(), block_height)?.loan_amount;
+    let breakdown = load_loan_breakdown(
+        &deps.storage,
+        &deps.api.canonical_address(&borrower)?,
+        loan_amount,
+    )?;
+
+    let principal = std::cmp::min(breakdown.principal, loan_amount);
+    Ok(LoanBreakdownResponse {
+        borrower,
+        principal,
+        accrued_interest: loan_amount - principal,
+        principal_repaid: breakdown.principal_repaid,
+        interest_repaid: breakdown.interest_repaid,
+    })
+}
@@ -0,0 +1,90 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    Querier, StdError, Storage,
+};
+
+use crate::borrow::{compute_interest, compute_reward};
+use crate::idle_deployment::{query_native_liquidity, recall_shortfall};
+use crate::state::{read_config, read_state, store_state, Config, State};
+use crate::tax::deduct_tax_cached;
+
+use moneymarket::error::ContractError;
+
+/// Sends `amount` of the reserves to `recipient`. Interest is accrued
+/// first, so the reserves include the share diverted up to this block.
+/// The exchange rate excludes the reserves and is left unchanged
+pub fn withdraw_reserves<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+    recipient: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroWithdraw {}.into());
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_reward(&mut state, env.block.height, env.block.time);
+
+    let withdraw_amount = Decimal256::from_uint256(amount);
+    if withdraw_amount > state.total_reserves {
+        return Err(ContractError::WithdrawExceedsReserves {
+            reserves: state.total_reserves * Uint256::one(),
+        }
+        .into());
+    }
+
+    // the CW20 stables and the flash loaned stable cannot be sent
+    if amount > query_native_liquidity(deps, &config)? {
+        return Err(ContractError::NoLiquidity {
+            denom: config.stable_denom,
+        }
+        .into());
+    }
+
+    state.total_reserves = state.total_reserves - withdraw_amount;
+    store_state(&mut deps.storage, &state)?;
+
+    let mut messages = recall_shortfall(deps, &config, amount)?;
+    messages.push(CosmosMsg::Bank(BankMsg::Send {
+        from_address: env.contract.address,
+        to_address: recipient.clone(),
+        amount: vec![deduct_tax_cached(
+            deps,
+            env.block.height,
+            Coin {
+                denom: config.stable_denom,
+                amount: amount.into(),
+            },
+        )?],
+    }));
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![
+                log("action", "withdraw_reserves"),
+                log("recipient", recipient),
+                log("amount", amount),
+                log("total_reserves", state.total_reserves),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
@@ -0,0 +1,81 @@
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, Storage,
+};
+
+use crate::borrow::{
+    compute_borrower_interest, compute_borrower_reward, compute_interest, compute_reward,
+};
+use crate::state::{read_config, read_liabilities, read_state, store_borrower_info, store_state};
+
+const DEFAULT_UPDATE_LIMIT: u32 = 10;
+const MAX_UPDATE_LIMIT: u32 = 30;
+
+/// Brings the interest and reward indexes of up to `limit` liabilities
+/// stored after `start_after` to the global ones, so the first operation
+/// of a long dormant borrower does not have to settle the whole delta.
+/// `last_updated` is left untouched, the liabilities stay prunable
+pub fn update_reward_indexes<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> HandleResult {
+    let config = read_config(&deps.storage)?;
+    let mut state = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_reward(&mut state, env.block.height, env.block.time);
+
+    let start_after = match start_after {
+        Some(v) => Some(deps.api.canonical_address(&v)?),
+        None => None,
+    };
+    let limit = limit.unwrap_or(DEFAULT_UPDATE_LIMIT).min(MAX_UPDATE_LIMIT);
+    let liabilities = read_liabilities(&deps.storage, start_after, limit)?;
+
+    let mut updated: u32 = 0;
+    for (borrower, mut liability) in liabilities.iter().cloned() {
+        if liability.interest_index == state.global_interest_index
+            && liability.reward_index == state.global_reward_index
+        {
+            continue;
+        }
+
+        compute_borrower_interest(&mut state, &mut liability);
+        compute_borrower_reward(&state, &mut liability);
+        store_borrower_info(&mut deps.storage, &borrower, &liability)?;
+        updated += 1;
+    }
+
+    // settling stable loans corrects the total liabilities
+    store_state(&mut deps.storage, &state)?;
+
+    // the next page starts after the last scanned borrower
+    let last_borrower = match liabilities.last() {
+        Some((borrower, _)) if liabilities.len() == limit as usize => {
+            deps.api.human_address(borrower)?.to_string()
+        }
+        _ => String::new(),
+    };
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "update_reward_indexes"),
+                log("scanned", liabilities.len()),
+                log("updated", updated),
+                log("last_borrower", last_borrower),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
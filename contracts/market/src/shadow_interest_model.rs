@@ -0,0 +1,205 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, LogAttribute, Querier,
+    StdError, StdResult, Storage,
+};
+
+use crate::borrow::{clamp_model_reserves, compute_interest};
+use crate::idle_deployment::query_market_balance;
+use crate::querier::query_borrow_rate;
+use crate::state::{
+    read_config, read_shadow_interest_model, read_state, remove_shadow_interest_model,
+    store_config, store_shadow_interest_model, store_state, Config, ShadowInterestModel, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::ShadowInterestModelResponse;
+
+pub fn update_shadow_interest_model<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    interest_model: HumanAddr,
+    trial_blocks: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if trial_blocks == 0 {
+        return Err(ContractError::ZeroParameter {
+            name: "trial_blocks".to_string(),
+        }
+        .into());
+    }
+
+    let interest_model_raw = deps.api.canonical_address(&interest_model)?;
+    if interest_model_raw == config.interest_model {
+        return Err(ContractError::ShadowModelIsLive {}.into());
+    }
+
+    let end_height = env.block.height + trial_blocks;
+    store_shadow_interest_model(
+        &mut deps.storage,
+        &ShadowInterestModel {
+            interest_model: interest_model_raw,
+            start_height: env.block.height,
+            end_height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_shadow_interest_model"),
+            log("interest_model", interest_model),
+            log("end_height", end_height),
+        ],
+        data: None,
+    })
+}
+
+pub fn disable_shadow_interest_model<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if read_shadow_interest_model(&deps.storage)?.is_none() {
+        return Err(ContractError::NoShadowModel {}.into());
+    }
+
+    remove_shadow_interest_model(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "disable_shadow_interest_model")],
+        data: None,
+    })
+}
+
+/// The trial does not need to be over, the owner may
+/// promote the shadow model whenever it is satisfied with it
+pub fn promote_shadow_interest_model<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let shadow_interest_model = match read_shadow_interest_model(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoShadowModel {}.into()),
+    };
+
+    // The blocks since the last accrual are
+    // charged at the rate of the live model
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    store_state(&mut deps.storage, &state)?;
+
+    config.interest_model = shadow_interest_model.interest_model;
+    store_config(&mut deps.storage, &config)?;
+    remove_shadow_interest_model(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "promote_shadow_interest_model"),
+                log(
+                    "interest_model",
+                    deps.api.human_address(&config.interest_model)?,
+                ),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Borrow rate of the shadow interest model for the accrual executed
+/// at `block_height`, logged next to the accrual logs. A failing shadow
+/// model is only logged, it never fails the accrual
+pub(crate) fn shadow_rate_logs<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+    balance: Uint256,
+    total_liabilities: Decimal256,
+    model_reserves: Decimal256,
+) -> StdResult<Vec<LogAttribute>> {
+    let shadow_interest_model = match read_shadow_interest_model(&deps.storage)? {
+        Some(v) if block_height <= v.end_height => v,
+        _ => return Ok(vec![]),
+    };
+
+    let shadow_borrow_rate = query_borrow_rate(
+        deps,
+        &deps
+            .api
+            .human_address(&shadow_interest_model.interest_model)?,
+        balance,
+        total_liabilities,
+        model_reserves,
+    );
+
+    Ok(match shadow_borrow_rate {
+        Ok(v) => vec![log("accrual_shadow_borrow_rate", v.rate)],
+        Err(_) => vec![log("accrual_shadow_borrow_rate", "unavailable")],
+    })
+}
+
+pub fn query_shadow_interest_model<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ShadowInterestModelResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let state: State = read_state(&deps.storage)?;
+    let balance = query_market_balance(deps, &config)?;
+    let (model_reserves, _) = clamp_model_reserves(&state, balance);
+
+    let live_borrow_rate = query_borrow_rate(
+        deps,
+        &deps.api.human_address(&config.interest_model)?,
+        balance,
+        state.total_liabilities,
+        model_reserves,
+    )?
+    .rate;
+
+    Ok(match read_shadow_interest_model(&deps.storage)? {
+        Some(v) => ShadowInterestModelResponse {
+            interest_model: Some(deps.api.human_address(&v.interest_model)?),
+            start_height: v.start_height,
+            end_height: v.end_height,
+            live_borrow_rate,
+            shadow_borrow_rate: query_borrow_rate(
+                deps,
+                &deps.api.human_address(&v.interest_model)?,
+                balance,
+                state.total_liabilities,
+                model_reserves,
+            )?
+            .rate,
+        },
+        None => ShadowInterestModelResponse {
+            interest_model: None,
+            start_height: 0,
+            end_height: 0,
+            live_borrow_rate,
+            shadow_borrow_rate: Decimal256::zero(),
+        },
+    })
+}
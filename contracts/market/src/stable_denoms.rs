@@ -0,0 +1,341 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, Env, Extern, HandleResponse,
+    HandleResult, HumanAddr, Querier, StdError, StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::{Cw20CoinHuman, Cw20HandleMsg, MinterResponse};
+
+use crate::contract::INITIAL_DEPOSIT_AMOUNT;
+use crate::operation_mode::{assert_deposits_enabled, assert_redemptions_enabled};
+use crate::state::{
+    read_config, read_stable_denom_state, read_stable_denom_states, store_stable_denom_state,
+    Config, StableDenomState,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{HandleMsg, StableDenomResponse, StableDenomsResponse};
+use moneymarket::querier::{deduct_tax, query_balance};
+use terraswap::hook::InitHook;
+use terraswap::token::InitMsg as TokenInitMsg;
+
+pub fn add_stable_denom<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    stable_denom: String,
+    aterra_code_id: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    // the token name and symbol are derived from the micro denom
+    if !stable_denom.starts_with('u') || stable_denom.len() < 3 {
+        return Err(ContractError::InvalidStableDenom {
+            denom: stable_denom.to_string(),
+        }
+        .into());
+    }
+
+    if stable_denom == config.stable_denom
+        || read_stable_denom_state(&deps.storage, &stable_denom)?.is_some()
+    {
+        return Err(ContractError::StableDenomAccepted {
+            denom: stable_denom.to_string(),
+        }
+        .into());
+    }
+
+    let initial_deposit = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == stable_denom)
+        .map(|c| c.amount)
+        .unwrap_or_else(Uint128::zero);
+
+    if initial_deposit != Uint128(INITIAL_DEPOSIT_AMOUNT) {
+        return Err(ContractError::InitialDepositRequired {
+            amount: INITIAL_DEPOSIT_AMOUNT,
+            denom: stable_denom.to_string(),
+        }
+        .into());
+    }
+
+    store_stable_denom_state(
+        &mut deps.storage,
+        &stable_denom,
+        &StableDenomState {
+            aterra_contract: CanonicalAddr::default(),
+            aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+            prev_exchange_rate: Decimal256::one(),
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: aterra_code_id,
+            send: vec![],
+            label: None,
+            msg: to_binary(&TokenInitMsg {
+                name: format!("Anchor Terra {}", stable_denom[1..].to_uppercase()),
+                symbol: format!(
+                    "a{}T",
+                    stable_denom[1..(stable_denom.len() - 1)].to_uppercase()
+                ),
+                decimals: 6u8,
+                initial_balances: vec![Cw20CoinHuman {
+                    address: env.contract.address.clone(),
+                    amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+                }],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.clone(),
+                    cap: None,
+                }),
+                init_hook: Some(InitHook {
+                    contract_addr: env.contract.address,
+                    msg: to_binary(&HandleMsg::RegisterStableDenomATerra {
+                        stable_denom: stable_denom.clone(),
+                    })?,
+                }),
+            })?,
+        })],
+        log: vec![
+            log("action", "add_stable_denom"),
+            log("stable_denom", stable_denom),
+        ],
+        data: None,
+    })
+}
+
+pub fn register_stable_denom_aterra<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    stable_denom: String,
+) -> HandleResult {
+    let mut stable_denom_state = match read_stable_denom_state(&deps.storage, &stable_denom)? {
+        Some(v) if v.aterra_contract == CanonicalAddr::default() => v,
+        _ => return Err(StdError::unauthorized()),
+    };
+
+    stable_denom_state.aterra_contract = deps.api.canonical_address(&env.message.sender)?;
+    store_stable_denom_state(&mut deps.storage, &stable_denom, &stable_denom_state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("stable_denom", stable_denom),
+            log("aterra", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+/// The added stable denom deposited with `DepositStable`, `None` when the
+/// config denom is sent or no sent denom has a registered aterra token
+pub(crate) fn find_stable_denom_deposit<S: Storage>(
+    storage: &S,
+    config: &Config,
+    env: &Env,
+) -> StdResult<Option<(String, Uint256)>> {
+    if env
+        .message
+        .sent_funds
+        .iter()
+        .any(|c| c.denom == config.stable_denom)
+    {
+        return Ok(None);
+    }
+
+    for coin in env.message.sent_funds.iter() {
+        if coin.amount.is_zero() {
+            continue;
+        }
+
+        if let Some(v) = read_stable_denom_state(storage, &coin.denom)? {
+            if v.aterra_contract != CanonicalAddr::default() {
+                return Ok(Some((coin.denom.to_string(), Uint256::from(coin.amount))));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// The added stable denom whose aterra token is `aterra_contract`
+pub(crate) fn find_stable_denom_by_aterra<S: Storage>(
+    storage: &S,
+    aterra_contract: &CanonicalAddr,
+) -> StdResult<Option<String>> {
+    Ok(read_stable_denom_states(storage)?
+        .into_iter()
+        .find(|(_, v)| v.aterra_contract == *aterra_contract)
+        .map(|(stable_denom, _)| stable_denom))
+}
+
+/// Mints the aterra of an added stable denom at its exchange rate.
+/// The denom is not borrowed, its deposits only earn what is sent
+/// to the market on top of them
+pub fn deposit_added_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    stable_denom: String,
+    deposit_amount: Uint256,
+    recipient: HumanAddr,
+) -> HandleResult {
+    assert_deposits_enabled(deps, env.block.height)?;
+
+    let mut stable_denom_state = read_registered_stable_denom_state(&deps.storage, &stable_denom)?;
+    let exchange_rate = compute_stable_denom_exchange_rate(
+        deps,
+        &env.contract.address,
+        &stable_denom,
+        &stable_denom_state,
+        Some(deposit_amount),
+    )?;
+    let mint_amount = deposit_amount / exchange_rate;
+
+    stable_denom_state.aterra_supply += mint_amount;
+    stable_denom_state.prev_exchange_rate = exchange_rate;
+    store_stable_denom_state(&mut deps.storage, &stable_denom, &stable_denom_state)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps
+                .api
+                .human_address(&stable_denom_state.aterra_contract)?,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: recipient.clone(),
+                amount: mint_amount.into(),
+            })?,
+        })],
+        log: vec![
+            log("action", "deposit_stable"),
+            log("depositor", env.message.sender),
+            log("recipient", recipient),
+            log("stable_denom", stable_denom),
+            log("mint_amount", mint_amount),
+            log("deposit_amount", deposit_amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn redeem_added_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    stable_denom: String,
+    sender: HumanAddr,
+    burn_amount: Uint128,
+) -> HandleResult {
+    assert_redemptions_enabled(deps, env.block.height)?;
+
+    let mut stable_denom_state = read_registered_stable_denom_state(&deps.storage, &stable_denom)?;
+    let exchange_rate = compute_stable_denom_exchange_rate(
+        deps,
+        &env.contract.address,
+        &stable_denom,
+        &stable_denom_state,
+        None,
+    )?;
+    let redeem_amount = Uint256::from(burn_amount) * exchange_rate;
+
+    stable_denom_state.aterra_supply =
+        stable_denom_state.aterra_supply - Uint256::from(burn_amount);
+    stable_denom_state.prev_exchange_rate = exchange_rate;
+    store_stable_denom_state(&mut deps.storage, &stable_denom, &stable_denom_state)?;
+
+    Ok(HandleResponse {
+        messages: vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps
+                    .api
+                    .human_address(&stable_denom_state.aterra_contract)?,
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Burn {
+                    amount: burn_amount,
+                })?,
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address,
+                to_address: sender,
+                amount: vec![deduct_tax(
+                    deps,
+                    Coin {
+                        denom: stable_denom.to_string(),
+                        amount: redeem_amount.into(),
+                    },
+                )?],
+            }),
+        ],
+        log: vec![
+            log("action", "redeem_stable"),
+            log("stable_denom", stable_denom),
+            log("burn_amount", burn_amount),
+            log("redeem_amount", redeem_amount),
+        ],
+        data: None,
+    })
+}
+
+fn read_registered_stable_denom_state<S: Storage>(
+    storage: &S,
+    stable_denom: &str,
+) -> StdResult<StableDenomState> {
+    match read_stable_denom_state(storage, stable_denom)? {
+        Some(v) if v.aterra_contract != CanonicalAddr::default() => Ok(v),
+        _ => Err(ContractError::StableDenomNotAccepted {
+            denom: stable_denom.to_string(),
+        }
+        .into()),
+    }
+}
+
+/// Balance of the denom per aterra, `deposit_amount`
+/// being already part of the balance
+fn compute_stable_denom_exchange_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    contract_addr: &HumanAddr,
+    stable_denom: &str,
+    stable_denom_state: &StableDenomState,
+    deposit_amount: Option<Uint256>,
+) -> StdResult<Decimal256> {
+    if stable_denom_state.aterra_supply.is_zero() {
+        return Ok(Decimal256::one());
+    }
+
+    let balance = query_balance(deps, contract_addr, stable_denom.to_string())?
+        - deposit_amount.unwrap_or_else(Uint256::zero);
+
+    Ok(Decimal256::from_uint256(balance)
+        / Decimal256::from_uint256(stable_denom_state.aterra_supply))
+}
+
+pub fn query_stable_denoms<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<StableDenomsResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let contract_addr = deps.api.human_address(&config.contract_addr)?;
+
+    let stable_denoms = read_stable_denom_states(&deps.storage)?
+        .into_iter()
+        .filter(|(_, v)| v.aterra_contract != CanonicalAddr::default())
+        .map(|(stable_denom, v)| {
+            Ok(StableDenomResponse {
+                exchange_rate: compute_stable_denom_exchange_rate(
+                    deps,
+                    &contract_addr,
+                    &stable_denom,
+                    &v,
+                    None,
+                )?,
+                stable_denom,
+                aterra_contract: deps.api.human_address(&v.aterra_contract)?,
+                aterra_supply: v.aterra_supply,
+            })
+        })
+        .collect::<StdResult<Vec<StableDenomResponse>>>()?;
+
+    Ok(StableDenomsResponse { stable_denoms })
+}
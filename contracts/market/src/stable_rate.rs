@@ -0,0 +1,274 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, LogAttribute, Querier,
+    StdError, StdResult, Storage,
+};
+
+use crate::borrow::{
+    clamp_model_reserves, compute_borrower_interest, compute_borrower_reward, compute_interest,
+    compute_reward,
+};
+use crate::credit_lines::saturating_sub;
+use crate::idle_deployment::query_market_balance;
+use crate::querier::query_borrow_rate;
+use crate::state::{
+    read_borrower_info, read_config, read_stable_rate_params, read_state, store_borrower_info,
+    store_stable_rate_params, store_state, BorrowerInfo, Config, StableRateParams, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{RateMode, RateModeResponse, StableRateParamsResponse};
+
+pub fn update_stable_rate_params<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    premium: Decimal256,
+    rebalance_threshold: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_stable_rate_params(
+        &mut deps.storage,
+        &StableRateParams {
+            premium,
+            rebalance_threshold,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_stable_rate_params"),
+            log("premium", premium),
+            log("rebalance_threshold", rebalance_threshold),
+        ],
+        data: None,
+    })
+}
+
+/// The rate of a stable loan stays locked until it is swapped back or
+/// rebalanced; swapping back resumes the variable rate from the
+/// accrued loan
+pub fn swap_rate_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let params = load_stable_rate_params(&deps.storage)?;
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+
+    let borrower_raw = deps.api.canonical_address(&env.message.sender)?;
+    let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let accrual_logs = settle_liability(deps, &config, &mut state, &mut liability, &env)?;
+
+    let mut logs = vec![
+        log("action", "swap_rate_mode"),
+        log("borrower", env.message.sender),
+    ];
+    match liability.stable_rate {
+        Some(_) => {
+            remove_stable_loan(&mut state, &liability);
+            liability.stable_rate = None;
+            logs.push(log("rate_mode", "variable"));
+        }
+        None => {
+            let stable_rate = current_stable_rate(deps, &config, &state, &params)?;
+            liability.stable_rate = Some(stable_rate);
+            add_stable_loan(&mut state, &liability);
+            logs.push(log("rate_mode", "stable"));
+            logs.push(log("stable_rate", stable_rate));
+        }
+    }
+
+    store_state(&mut deps.storage, &state)?;
+    store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [logs, accrual_logs].concat(),
+        data: None,
+    })
+}
+
+/// Locks the stable rate of `borrower` again at the current stable
+/// rate. Anyone can rebalance it up once the variable rate exceeds the
+/// locked rate by more than the rebalance threshold; only the borrower
+/// can rebalance it down, once the stable rate fell by more than it
+pub fn rebalance_stable_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let params = load_stable_rate_params(&deps.storage)?;
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let previous_rate = match liability.stable_rate {
+        Some(v) => v,
+        None => return Err(ContractError::NotStableRateLoan {}.into()),
+    };
+
+    let accrual_logs = settle_liability(deps, &config, &mut state, &mut liability, &env)?;
+
+    let borrow_rate = current_borrow_rate(deps, &config, &state)?;
+    let stable_rate = borrow_rate + params.premium;
+    let rebalance = if stable_rate > previous_rate {
+        borrow_rate > previous_rate + params.rebalance_threshold
+    } else {
+        if env.message.sender != borrower {
+            return Err(StdError::unauthorized());
+        }
+
+        previous_rate - stable_rate > params.rebalance_threshold
+    };
+    if !rebalance {
+        return Err(ContractError::StableRateWithinThreshold { stable_rate }.into());
+    }
+
+    remove_stable_loan(&mut state, &liability);
+    liability.stable_rate = Some(stable_rate);
+    add_stable_loan(&mut state, &liability);
+    store_state(&mut deps.storage, &state)?;
+    store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "rebalance_stable_rate"),
+                log("borrower", borrower),
+                log("previous_rate", previous_rate),
+                log("stable_rate", stable_rate),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Removes the stable loan of `liability` from the stable liabilities
+/// of `state`, before its amount or its rate change
+pub(crate) fn remove_stable_loan(state: &mut State, liability: &BorrowerInfo) {
+    if let Some(stable_rate) = liability.stable_rate {
+        let loan_amount = Decimal256::from_uint256(liability.loan_amount);
+        state.total_stable_liabilities =
+            saturating_sub(state.total_stable_liabilities, loan_amount);
+        state.stable_interest_per_block =
+            saturating_sub(state.stable_interest_per_block, loan_amount * stable_rate);
+    }
+}
+
+/// Adds the stable loan of `liability` to the stable liabilities of `state`
+pub(crate) fn add_stable_loan(state: &mut State, liability: &BorrowerInfo) {
+    if let Some(stable_rate) = liability.stable_rate {
+        let loan_amount = Decimal256::from_uint256(liability.loan_amount);
+        state.total_stable_liabilities += loan_amount;
+        state.stable_interest_per_block += loan_amount * stable_rate;
+    }
+}
+
+/// Rate locked by `borrow_amount` borrowed on top of a stable loan
+/// of `loan_amount`, the locked rates averaged by amount
+pub(crate) fn blend_stable_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    state: &State,
+    liability: &BorrowerInfo,
+    borrow_amount: Uint256,
+) -> StdResult<Option<Decimal256>> {
+    let locked_rate = match liability.stable_rate {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    let params = load_stable_rate_params(&deps.storage)?;
+    let stable_rate = current_stable_rate(deps, config, state, &params)?;
+    let loan_amount = Decimal256::from_uint256(liability.loan_amount);
+    let borrow_amount = Decimal256::from_uint256(borrow_amount);
+
+    Ok(Some(
+        (loan_amount * locked_rate + borrow_amount * stable_rate) / (loan_amount + borrow_amount),
+    ))
+}
+
+/// Variable borrow rate of the current market plus the premium
+fn current_stable_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    state: &State,
+    params: &StableRateParams,
+) -> StdResult<Decimal256> {
+    Ok(current_borrow_rate(deps, config, state)? + params.premium)
+}
+
+fn current_borrow_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    state: &State,
+) -> StdResult<Decimal256> {
+    let balance = query_market_balance(deps, config)?;
+    let (model_reserves, _) = clamp_model_reserves(state, balance);
+    Ok(query_borrow_rate(
+        deps,
+        &deps.api.human_address(&config.interest_model)?,
+        balance,
+        state.total_liabilities,
+        model_reserves,
+    )?
+    .rate)
+}
+
+fn settle_liability<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    state: &mut State,
+    liability: &mut BorrowerInfo,
+    env: &Env,
+) -> StdResult<Vec<LogAttribute>> {
+    let accrual_logs =
+        compute_interest(deps, config, state, env.block.height, env.block.time, None)?;
+    compute_borrower_interest(state, liability);
+
+    compute_reward(state, env.block.height, env.block.time);
+    compute_borrower_reward(state, liability);
+
+    Ok(accrual_logs)
+}
+
+fn load_stable_rate_params<S: Storage>(storage: &S) -> StdResult<StableRateParams> {
+    match read_stable_rate_params(storage)? {
+        Some(v) => Ok(v),
+        None => Err(ContractError::StableRateDisabled {}.into()),
+    }
+}
+
+pub fn query_stable_rate_params<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<StableRateParamsResponse> {
+    let params = load_stable_rate_params(&deps.storage)?;
+    Ok(StableRateParamsResponse {
+        premium: params.premium,
+        rebalance_threshold: params.rebalance_threshold,
+    })
+}
+
+pub fn query_rate_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<RateModeResponse> {
+    let liability = read_borrower_info(&deps.storage, &deps.api.canonical_address(&borrower)?);
+    Ok(RateModeResponse {
+        borrower,
+        rate_mode: match liability.stable_rate {
+            Some(_) => RateMode::Stable,
+            None => RateMode::Variable,
+        },
+        stable_rate: liability.stable_rate,
+    })
+}
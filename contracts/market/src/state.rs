@@ -2,15 +2,61 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{Api, CanonicalAddr, Extern, HumanAddr, Order, Querier, StdResult, Storage};
+use cosmwasm_std::{Api, CanonicalAddr, Extern, Order, Querier, StdResult, Storage};
 use cosmwasm_storage::{bucket, bucket_read, ReadonlyBucket, ReadonlySingleton, Singleton};
 
+use crate::exchange_rate_feed::DEFAULT_TWAP_WINDOW;
+
 use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::units::STABLE_DECIMALS;
 
 pub const KEY_CONFIG: &[u8] = b"config";
 pub const KEY_STATE: &[u8] = b"state";
+pub const KEY_COLD_TRANCHE: &[u8] = b"cold_tranche";
+pub const KEY_REDEMPTION_FEE: &[u8] = b"redemption_fee";
+pub const KEY_FLASH_MINT_FEE_RATE: &[u8] = b"flash_mint_fee_rate";
+pub const KEY_FLASH_MINT: &[u8] = b"flash_mint";
+pub const KEY_FLASH_LOAN_FEE_RATE: &[u8] = b"flash_loan_fee_rate";
+pub const KEY_FLASH_LOAN: &[u8] = b"flash_loan";
+pub const KEY_IDLE_DEPLOYMENT: &[u8] = b"idle_deployment";
+pub const KEY_TAX_PARAMS: &[u8] = b"tax_params";
+pub const KEY_OPERATION_MODE: &[u8] = b"operation_mode";
+pub const KEY_PRUNE_CURSOR: &[u8] = b"prune_cursor";
+pub const KEY_LOAN_PARAMS: &[u8] = b"loan_params";
+pub const KEY_DEBT_AUCTION_PARAMS: &[u8] = b"debt_auction_params";
+pub const KEY_DEBT_AUCTION: &[u8] = b"debt_auction";
+pub const KEY_DUST_FORGIVENESS: &[u8] = b"dust_forgiveness";
+pub const KEY_TOTAL_DONATED: &[u8] = b"total_donated";
+pub const KEY_KEEPER_BOUNTY: &[u8] = b"keeper_bounty";
+pub const KEY_EXCHANGE_RATE_FEED: &[u8] = b"exchange_rate_feed";
+pub const KEY_EXCHANGE_RATE_OBSERVATIONS: &[u8] = b"exchange_rate_observations";
+pub const KEY_LAST_EPOCH_HEIGHT: &[u8] = b"last_epoch_height";
+pub const KEY_BOOTSTRAP_MODE: &[u8] = b"bootstrap_mode";
+pub const KEY_INVARIANT_CHECKS: &[u8] = b"invariant_checks";
+pub const KEY_REDEMPTION_QUEUE_NEXT_ID: &[u8] = b"redemption_queue_next_id";
+pub const KEY_FLOW_WINDOW: &[u8] = b"flow_window";
+pub const KEY_FLOW_LIMIT_OVERRIDE: &[u8] = b"flow_limit_override";
+pub const KEY_REGISTRY: &[u8] = b"registry";
+pub const KEY_STATE_SNAPSHOT_COUNT: &[u8] = b"state_snapshot_count";
+pub const KEY_ATTESTATION_CONTRACT: &[u8] = b"attestation_contract";
+pub const KEY_STABLE_RATE_PARAMS: &[u8] = b"stable_rate_params";
+pub const KEY_SHADOW_INTEREST_MODEL: &[u8] = b"shadow_interest_model";
 
 const PREFIX_LIABILITY: &[u8] = b"liability";
+const PREFIX_COLD_UNBONDING: &[u8] = b"cold_unbonding";
+const PREFIX_DEPOSITOR: &[u8] = b"depositor";
+const PREFIX_CREDIT_LINE: &[u8] = b"credit_line";
+const PREFIX_AMORTIZING_LOAN: &[u8] = b"amortizing_loan";
+const PREFIX_LOAN_BREAKDOWN: &[u8] = b"loan_breakdown";
+const PREFIX_RATE_SNAPSHOT: &[u8] = b"rate_snapshot";
+const PREFIX_STATE_SNAPSHOT: &[u8] = b"state_snapshot";
+
+/// Epochs kept in the state history, the oldest is dropped first
+pub const MAX_STATE_SNAPSHOTS: u64 = 500;
+const PREFIX_STABLE_DENOM: &[u8] = b"stable_denom";
+const PREFIX_CW20_STABLE: &[u8] = b"cw20_stable";
+const PREFIX_FEE_EXEMPT_REDEEMER: &[u8] = b"fee_exempt_redeemer";
+const PREFIX_QUEUED_REDEMPTION: &[u8] = b"queued_redemption";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -24,6 +70,31 @@ pub struct Config {
     pub distributor_contract: CanonicalAddr,
     pub stable_denom: String,
     pub max_borrow_factor: Decimal256,
+    /// Limit of the total liabilities, uncapped when unset
+    #[serde(default)]
+    pub global_borrow_cap: Option<Uint256>,
+    /// Limit of the loan of each borrower, uncapped when unset
+    #[serde(default)]
+    pub per_account_borrow_cap: Option<Uint256>,
+    /// Smallest stable amount accepted by a deposit
+    #[serde(default)]
+    pub min_deposit_amount: Option<Uint256>,
+    /// Smallest stable amount a redemption can return
+    #[serde(default)]
+    pub min_redeem_amount: Option<Uint256>,
+    /// Length in blocks of the net flow cap windows
+    #[serde(default)]
+    pub flow_window: Option<u64>,
+    /// Net deposits or redemptions allowed per window, as a ratio of
+    /// the total deposits at the start of the window
+    #[serde(default)]
+    pub max_net_flow_ratio: Option<Decimal256>,
+    /// Allowed to pause the entry points next to the owner
+    #[serde(default)]
+    pub guardian: Option<CanonicalAddr>,
+    /// Share of the accrued interest moved to the reserves as it accrues
+    #[serde(default = "Decimal256::zero")]
+    pub reserve_factor: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -37,6 +108,46 @@ pub struct State {
     pub anc_emission_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
+    /// credit line loans including accrued interest,
+    /// kept apart from the retail liabilities
+    pub total_credit_liabilities: Decimal256,
+    /// interest accrued per block over all credit lines
+    pub credit_interest_per_block: Decimal256,
+    /// accruals whose reserves implied a utilization above 1,
+    /// the utilization fed to the interest model is clamped to 1
+    #[serde(default)]
+    pub utilization_clamps: u64,
+    /// set once interest and rewards accrue with the block time,
+    /// none while they accrue per block
+    #[serde(default)]
+    pub accrual_clock: Option<AccrualClock>,
+    /// loan residuals forgiven on repayments within the
+    /// dust forgiveness amount of the full loan
+    #[serde(default)]
+    pub forgiven_dust: Uint256,
+    /// borrow rate of the last accrual
+    #[serde(default)]
+    pub last_borrow_rate: Decimal256,
+    /// periods, blocks or time based, accrued by all the accruals;
+    /// the credit lines accrue over the same periods
+    #[serde(default)]
+    pub accrued_periods: Decimal256,
+    /// stable loans and their interest, included in total_liabilities
+    #[serde(default)]
+    pub total_stable_liabilities: Decimal256,
+    /// interest the stable loans accrue per period at their locked rates
+    #[serde(default)]
+    pub stable_interest_per_block: Decimal256,
+}
+
+/// Timestamps of the time based accrual. The per block rates of the
+/// interest model and the ANC emission are converted to per second
+/// rates with `seconds_per_block`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AccrualClock {
+    pub seconds_per_block: u64,
+    pub last_interest_updated_time: u64,
+    pub last_reward_updated_time: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -45,6 +156,313 @@ pub struct BorrowerInfo {
     pub reward_index: Decimal256,
     pub loan_amount: Uint256,
     pub pending_rewards: Decimal256,
+    /// height of the last update, zero for liabilities
+    /// stored before it was tracked
+    #[serde(default)]
+    pub last_updated: u64,
+    /// borrow rate per block locked by a stable loan,
+    /// none while the loan is at the variable rate
+    #[serde(default)]
+    pub stable_rate: Option<Decimal256>,
+    /// `state.accrued_periods` the loan was last settled at
+    #[serde(default)]
+    pub stable_accrued_periods: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositorInfo {
+    /// lifetime stable amount deposited, including cold deposits
+    pub total_deposited: Uint256,
+    /// lifetime stable amount paid out on redemptions
+    pub total_redeemed: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreditLine {
+    pub cap: Uint256,
+    /// interest rate per block
+    pub interest_rate: Decimal256,
+    /// required aterra value escrowed per loan amount
+    pub collateral_ratio: Decimal256,
+    pub loan_amount: Decimal256,
+    /// escrowed aterra amount
+    pub collateral_amount: Uint256,
+    pub last_accrued: u64,
+    pub defaulted: bool,
+    /// `state.accrued_periods` the line was last accrued at,
+    /// none for lines stored before it was tracked
+    #[serde(default)]
+    pub accrued_periods: Option<Decimal256>,
+}
+
+/// Stable denom accepted on top of `config.stable_denom`. The market
+/// is the only minter of its aterra, so the supply is tracked here
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StableDenomState {
+    pub aterra_contract: CanonicalAddr,
+    pub aterra_supply: Uint256,
+    /// exchange rate of the last deposit or redemption
+    pub prev_exchange_rate: Decimal256,
+}
+
+/// CW20 stablecoin pegged to `config.stable_denom`, deposited
+/// for aterra at par. The held amount, in micro units, is part
+/// of the market balance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20StableState {
+    pub accept_deposits: bool,
+    pub held_amount: Uint256,
+    #[serde(default = "default_cw20_stable_decimals")]
+    pub decimals: u8,
+}
+
+fn default_cw20_stable_decimals() -> u8 {
+    STABLE_DECIMALS
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ColdTranche {
+    pub caterra_contract: CanonicalAddr,
+    pub redeem_delay: u64,
+    pub bonus_rate: Decimal256,
+    /// aterra held by the market on behalf of cold A-token holders
+    pub aterra_balance: Uint256,
+    pub last_bonus_updated: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ColdUnbonding {
+    pub aterra_amount: Uint256,
+    pub release_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionFee {
+    /// share of the available liquidity that can be redeemed without fee
+    pub free_ratio: Decimal256,
+    /// fee rate charged when the whole available liquidity is redeemed
+    pub max_fee_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashMint {
+    pub minter: CanonicalAddr,
+    pub amount: Uint256,
+    pub fee: Uint256,
+    pub repaid: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoan {
+    pub borrower: CanonicalAddr,
+    pub amount: Uint256,
+    pub fee: Uint256,
+    pub repaid: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IdleDeployment {
+    pub venue: CanonicalAddr,
+    /// idle stable is deployed only below this utilization
+    pub utilization_threshold: Decimal256,
+    /// share of the available liquidity that can be deployed
+    pub max_deploy_ratio: Decimal256,
+    pub cap: Uint256,
+    /// stable sent to the venue and not yet recalled
+    pub deployed_amount: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TaxParams {
+    pub rate: Decimal256,
+    /// tax cap of the stable denom
+    pub cap: Uint256,
+    pub updated_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LoanParams {
+    /// # of blocks between two installments
+    pub period_blocks: u64,
+    pub max_installments: u32,
+    /// share of the overdue principal added to the loan per missed installment
+    pub penalty_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AmortizingLoan {
+    pub principal: Uint256,
+    pub installments: u32,
+    pub start_height: u64,
+    pub period_blocks: u64,
+    pub penalty_rate: Decimal256,
+    /// principal repaid through the schedule
+    pub repaid_amount: Uint256,
+    /// # of due dates already checked for missed installments
+    pub checked_installments: u32,
+    /// penalties added to the loan so far
+    pub penalty_amount: Uint256,
+}
+
+/// Principal part of the loan of a borrower, the rest is interest,
+/// and the lifetime amounts repaid towards each
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LoanBreakdown {
+    pub principal: Uint256,
+    pub principal_repaid: Uint256,
+    pub interest_repaid: Uint256,
+}
+
+/// Rates in effect when the loan of a borrower was last originated
+/// or modified, the interest accrued since is the loan times the
+/// growth of the global interest index from `interest_index`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateSnapshot {
+    pub height: u64,
+    /// loan after the modification
+    pub loan_amount: Uint256,
+    pub borrow_rate: Decimal256,
+    pub interest_index: Decimal256,
+    pub exchange_rate: Decimal256,
+}
+
+/// State at the end of the epoch operations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateSnapshot {
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub aterra_supply: Uint256,
+    pub exchange_rate: Decimal256,
+    /// deposit rate of the epoch reported by the overseer
+    pub deposit_rate: Decimal256,
+    pub borrow_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DebtAuctionParams {
+    /// stable paid per ANC when the auction starts
+    pub start_price: Decimal256,
+    /// lowest stable price per ANC, reached after `price_decay_blocks`
+    pub floor_price: Decimal256,
+    pub price_decay_blocks: u64,
+}
+
+/// Bad debt left uncovered by the reserves, raised
+/// back by selling ANC from the distributor
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DebtAuction {
+    /// stable left to raise
+    pub debt_amount: Uint256,
+    pub start_height: u64,
+    pub raised_amount: Uint256,
+    /// ANC sold so far
+    pub sold_amount: Uint256,
+}
+
+/// Bounty paid out of the reserves for poking stale positions
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct KeeperBounty {
+    pub bounty_amount: Uint256,
+    /// max paid between two epoch operations
+    pub epoch_cap: Uint256,
+    /// blocks since the last update for a position to be stale
+    pub stale_blocks: u64,
+    /// paid since the last epoch operations
+    pub paid_amount: Uint256,
+}
+
+/// Stable rate borrowing, disabled until set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StableRateParams {
+    /// added to the variable borrow rate when a rate is locked
+    pub premium: Decimal256,
+    /// drift of a locked rate from the current stable rate
+    /// past which the locked rate can be rebalanced
+    pub rebalance_threshold: Decimal256,
+}
+
+/// Borrows are blocked until `end_height` or until the market
+/// TVL reaches `tvl_threshold`, whichever comes first
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BootstrapMode {
+    pub end_height: Option<u64>,
+    pub tvl_threshold: Option<Uint256>,
+}
+
+/// aterra escrowed by `redeemer` until the market
+/// has the liquidity to redeem it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QueuedRedemption {
+    pub redeemer: CanonicalAddr,
+    pub aterra_amount: Uint256,
+    pub queued_height: u64,
+}
+
+/// Deposits and redemptions of the current net flow cap window
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlowWindow {
+    pub start_height: u64,
+    /// total deposits when the window started
+    pub base_deposits: Uint256,
+    pub deposited: Uint256,
+    pub redeemed: Uint256,
+}
+
+/// Interest model quoted next to the live one on every accrual
+/// until `end_height`, without affecting the accrual
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShadowInterestModel {
+    pub interest_model: CanonicalAddr,
+    pub start_height: u64,
+    pub end_height: u64,
+}
+
+/// Exchange rate observed at the last accrual
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateFeed {
+    /// contract receiving the exchange rate at every accrual
+    pub oracle: Option<CanonicalAddr>,
+    pub exchange_rate: Decimal256,
+    pub updated_height: u64,
+    /// unset once an accrual observed a lower exchange rate
+    pub monotonic: bool,
+    /// blocks the time-weighted average exchange rate is taken over
+    #[serde(default = "default_twap_window")]
+    pub twap_window: u64,
+}
+
+fn default_twap_window() -> u64 {
+    DEFAULT_TWAP_WINDOW
+}
+
+/// Exchange rate of the accrual executed at `height`,
+/// in effect until the next observation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateObservation {
+    pub height: u64,
+    pub exchange_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperationMode {
+    pub deposits_enabled: bool,
+    pub redemptions_enabled: bool,
+    #[serde(default = "default_enabled")]
+    pub borrows_enabled: bool,
+    #[serde(default = "default_enabled")]
+    pub repayments_enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Registry the peers are resolved through, at `version` and `updated_height`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegistryCache {
+    pub registry_contract: CanonicalAddr,
+    pub version: u64,
+    pub updated_height: u64,
 }
 
 pub fn store_config<S: Storage>(storage: &mut S, data: &Config) -> StdResult<()> {
@@ -63,6 +481,450 @@ pub fn read_state<S: Storage>(storage: &S) -> StdResult<State> {
     ReadonlySingleton::new(storage, KEY_STATE).load()
 }
 
+pub fn store_cold_tranche<S: Storage>(storage: &mut S, data: &ColdTranche) -> StdResult<()> {
+    Singleton::new(storage, KEY_COLD_TRANCHE).save(data)
+}
+
+pub fn read_cold_tranche<S: Storage>(storage: &S) -> StdResult<Option<ColdTranche>> {
+    ReadonlySingleton::new(storage, KEY_COLD_TRANCHE).may_load()
+}
+
+pub fn store_redemption_fee<S: Storage>(storage: &mut S, data: &RedemptionFee) -> StdResult<()> {
+    Singleton::new(storage, KEY_REDEMPTION_FEE).save(data)
+}
+
+pub fn read_redemption_fee<S: Storage>(storage: &S) -> StdResult<Option<RedemptionFee>> {
+    ReadonlySingleton::new(storage, KEY_REDEMPTION_FEE).may_load()
+}
+
+pub fn store_flash_mint_fee_rate<S: Storage>(storage: &mut S, data: &Decimal256) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_MINT_FEE_RATE).save(data)
+}
+
+pub fn read_flash_mint_fee_rate<S: Storage>(storage: &S) -> StdResult<Decimal256> {
+    Ok(ReadonlySingleton::new(storage, KEY_FLASH_MINT_FEE_RATE)
+        .may_load()?
+        .unwrap_or_else(Decimal256::zero))
+}
+
+pub fn store_flash_mint<S: Storage>(storage: &mut S, data: &FlashMint) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_MINT).save(data)
+}
+
+pub fn read_flash_mint<S: Storage>(storage: &S) -> StdResult<Option<FlashMint>> {
+    ReadonlySingleton::new(storage, KEY_FLASH_MINT).may_load()
+}
+
+pub fn remove_flash_mint<S: Storage>(storage: &mut S) {
+    Singleton::<S, FlashMint>::new(storage, KEY_FLASH_MINT).remove()
+}
+
+pub fn store_flash_loan_fee_rate<S: Storage>(storage: &mut S, data: &Decimal256) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_LOAN_FEE_RATE).save(data)
+}
+
+pub fn read_flash_loan_fee_rate<S: Storage>(storage: &S) -> StdResult<Decimal256> {
+    Ok(ReadonlySingleton::new(storage, KEY_FLASH_LOAN_FEE_RATE)
+        .may_load()?
+        .unwrap_or_else(Decimal256::zero))
+}
+
+pub fn store_flash_loan<S: Storage>(storage: &mut S, data: &FlashLoan) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_LOAN).save(data)
+}
+
+pub fn read_flash_loan<S: Storage>(storage: &S) -> StdResult<Option<FlashLoan>> {
+    ReadonlySingleton::new(storage, KEY_FLASH_LOAN).may_load()
+}
+
+pub fn remove_flash_loan<S: Storage>(storage: &mut S) {
+    Singleton::<S, FlashLoan>::new(storage, KEY_FLASH_LOAN).remove()
+}
+
+pub fn store_idle_deployment<S: Storage>(storage: &mut S, data: &IdleDeployment) -> StdResult<()> {
+    Singleton::new(storage, KEY_IDLE_DEPLOYMENT).save(data)
+}
+
+pub fn read_idle_deployment<S: Storage>(storage: &S) -> StdResult<Option<IdleDeployment>> {
+    ReadonlySingleton::new(storage, KEY_IDLE_DEPLOYMENT).may_load()
+}
+
+pub fn store_tax_params<S: Storage>(storage: &mut S, data: &TaxParams) -> StdResult<()> {
+    Singleton::new(storage, KEY_TAX_PARAMS).save(data)
+}
+
+pub fn read_tax_params<S: Storage>(storage: &S) -> StdResult<Option<TaxParams>> {
+    ReadonlySingleton::new(storage, KEY_TAX_PARAMS).may_load()
+}
+
+pub fn store_loan_params<S: Storage>(storage: &mut S, data: &LoanParams) -> StdResult<()> {
+    Singleton::new(storage, KEY_LOAN_PARAMS).save(data)
+}
+
+pub fn read_loan_params<S: Storage>(storage: &S) -> StdResult<Option<LoanParams>> {
+    ReadonlySingleton::new(storage, KEY_LOAN_PARAMS).may_load()
+}
+
+pub fn store_dust_forgiveness<S: Storage>(storage: &mut S, data: &Uint256) -> StdResult<()> {
+    Singleton::new(storage, KEY_DUST_FORGIVENESS).save(data)
+}
+
+/// Nothing is forgiven unless set otherwise
+pub fn read_dust_forgiveness<S: Storage>(storage: &S) -> StdResult<Uint256> {
+    Ok(ReadonlySingleton::new(storage, KEY_DUST_FORGIVENESS)
+        .may_load()?
+        .unwrap_or_else(Uint256::zero))
+}
+
+pub fn store_total_donated<S: Storage>(storage: &mut S, data: &Uint256) -> StdResult<()> {
+    Singleton::new(storage, KEY_TOTAL_DONATED).save(data)
+}
+
+pub fn read_total_donated<S: Storage>(storage: &S) -> StdResult<Uint256> {
+    Ok(ReadonlySingleton::new(storage, KEY_TOTAL_DONATED)
+        .may_load()?
+        .unwrap_or_else(Uint256::zero))
+}
+
+pub fn store_last_epoch_height<S: Storage>(storage: &mut S, data: &u64) -> StdResult<()> {
+    Singleton::new(storage, KEY_LAST_EPOCH_HEIGHT).save(data)
+}
+
+pub fn read_last_epoch_height<S: Storage>(storage: &S) -> StdResult<u64> {
+    Ok(ReadonlySingleton::new(storage, KEY_LAST_EPOCH_HEIGHT)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_loan_breakdown<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    data: &LoanBreakdown,
+) -> StdResult<()> {
+    bucket(PREFIX_LOAN_BREAKDOWN, storage).save(borrower.as_slice(), data)
+}
+
+pub fn read_loan_breakdown<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<LoanBreakdown>> {
+    bucket_read(PREFIX_LOAN_BREAKDOWN, storage).may_load(borrower.as_slice())
+}
+
+pub fn remove_loan_breakdown<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    bucket::<S, LoanBreakdown>(PREFIX_LOAN_BREAKDOWN, storage).remove(borrower.as_slice())
+}
+
+pub fn store_rate_snapshot<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    data: &RateSnapshot,
+) -> StdResult<()> {
+    bucket(PREFIX_RATE_SNAPSHOT, storage).save(borrower.as_slice(), data)
+}
+
+pub fn read_rate_snapshot<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<RateSnapshot>> {
+    bucket_read(PREFIX_RATE_SNAPSHOT, storage).may_load(borrower.as_slice())
+}
+
+/// Appends the snapshot of the epoch executed at `height`,
+/// dropping the oldest one beyond `MAX_STATE_SNAPSHOTS`
+pub fn push_state_snapshot<S: Storage>(
+    storage: &mut S,
+    height: u64,
+    snapshot: &StateSnapshot,
+) -> StdResult<()> {
+    bucket(PREFIX_STATE_SNAPSHOT, storage).save(&height.to_be_bytes(), snapshot)?;
+
+    let count: u64 = ReadonlySingleton::new(storage, KEY_STATE_SNAPSHOT_COUNT)
+        .may_load()?
+        .unwrap_or(0)
+        + 1;
+    if count > MAX_STATE_SNAPSHOTS {
+        let oldest = bucket_read::<S, StateSnapshot>(PREFIX_STATE_SNAPSHOT, storage)
+            .range(None, None, Order::Ascending)
+            .next()
+            .transpose()?
+            .map(|(k, _)| k);
+        if let Some(oldest) = oldest {
+            bucket::<S, StateSnapshot>(PREFIX_STATE_SNAPSHOT, storage).remove(&oldest);
+        }
+
+        return Singleton::new(storage, KEY_STATE_SNAPSHOT_COUNT).save(&MAX_STATE_SNAPSHOTS);
+    }
+
+    Singleton::new(storage, KEY_STATE_SNAPSHOT_COUNT).save(&count)
+}
+
+/// Latest snapshot taken at or before `height`
+pub fn read_state_snapshot_at<S: Storage>(
+    storage: &S,
+    height: u64,
+) -> StdResult<Option<(u64, StateSnapshot)>> {
+    let end = height.checked_add(1).map(|v| v.to_be_bytes().to_vec());
+    bucket_read::<S, StateSnapshot>(PREFIX_STATE_SNAPSHOT, storage)
+        .range(None, end.as_deref(), Order::Descending)
+        .next()
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((u64_from_key(&k), v))
+        })
+        .transpose()
+}
+
+pub fn read_state_snapshots<S: Storage>(
+    storage: &S,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, StateSnapshot)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|height| (height + 1).to_be_bytes().to_vec());
+
+    bucket_read::<S, StateSnapshot>(PREFIX_STATE_SNAPSHOT, storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((u64_from_key(&k), v))
+        })
+        .collect()
+}
+
+pub fn store_debt_auction_params<S: Storage>(
+    storage: &mut S,
+    data: &DebtAuctionParams,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_DEBT_AUCTION_PARAMS).save(data)
+}
+
+pub fn read_debt_auction_params<S: Storage>(storage: &S) -> StdResult<Option<DebtAuctionParams>> {
+    ReadonlySingleton::new(storage, KEY_DEBT_AUCTION_PARAMS).may_load()
+}
+
+pub fn store_debt_auction<S: Storage>(storage: &mut S, data: &DebtAuction) -> StdResult<()> {
+    Singleton::new(storage, KEY_DEBT_AUCTION).save(data)
+}
+
+pub fn read_debt_auction<S: Storage>(storage: &S) -> StdResult<Option<DebtAuction>> {
+    ReadonlySingleton::new(storage, KEY_DEBT_AUCTION).may_load()
+}
+
+pub fn remove_debt_auction<S: Storage>(storage: &mut S) {
+    Singleton::<S, DebtAuction>::new(storage, KEY_DEBT_AUCTION).remove()
+}
+
+pub fn store_keeper_bounty<S: Storage>(storage: &mut S, data: &KeeperBounty) -> StdResult<()> {
+    Singleton::new(storage, KEY_KEEPER_BOUNTY).save(data)
+}
+
+pub fn read_keeper_bounty<S: Storage>(storage: &S) -> StdResult<Option<KeeperBounty>> {
+    ReadonlySingleton::new(storage, KEY_KEEPER_BOUNTY).may_load()
+}
+
+pub fn store_stable_rate_params<S: Storage>(
+    storage: &mut S,
+    data: &StableRateParams,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_STABLE_RATE_PARAMS).save(data)
+}
+
+pub fn read_stable_rate_params<S: Storage>(storage: &S) -> StdResult<Option<StableRateParams>> {
+    ReadonlySingleton::new(storage, KEY_STABLE_RATE_PARAMS).may_load()
+}
+
+pub fn store_exchange_rate_feed<S: Storage>(
+    storage: &mut S,
+    data: &ExchangeRateFeed,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_EXCHANGE_RATE_FEED).save(data)
+}
+
+pub fn read_exchange_rate_feed<S: Storage>(storage: &S) -> StdResult<Option<ExchangeRateFeed>> {
+    ReadonlySingleton::new(storage, KEY_EXCHANGE_RATE_FEED).may_load()
+}
+
+pub fn store_exchange_rate_observations<S: Storage>(
+    storage: &mut S,
+    data: &[ExchangeRateObservation],
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_EXCHANGE_RATE_OBSERVATIONS).save(&data.to_vec())
+}
+
+/// Oldest first
+pub fn read_exchange_rate_observations<S: Storage>(
+    storage: &S,
+) -> StdResult<Vec<ExchangeRateObservation>> {
+    Ok(
+        ReadonlySingleton::new(storage, KEY_EXCHANGE_RATE_OBSERVATIONS)
+            .may_load()?
+            .unwrap_or_default(),
+    )
+}
+
+pub fn store_operation_mode<S: Storage>(storage: &mut S, data: &OperationMode) -> StdResult<()> {
+    Singleton::new(storage, KEY_OPERATION_MODE).save(data)
+}
+
+/// Every entry point group is enabled unless set otherwise
+pub fn read_operation_mode<S: Storage>(storage: &S) -> StdResult<OperationMode> {
+    Ok(ReadonlySingleton::new(storage, KEY_OPERATION_MODE)
+        .may_load()?
+        .unwrap_or(OperationMode {
+            deposits_enabled: true,
+            redemptions_enabled: true,
+            borrows_enabled: true,
+            repayments_enabled: true,
+        }))
+}
+
+pub fn store_attestation_contract<S: Storage>(
+    storage: &mut S,
+    data: &Option<CanonicalAddr>,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_ATTESTATION_CONTRACT).save(data)
+}
+
+/// No attestation is required unless set otherwise
+pub fn read_attestation_contract<S: Storage>(storage: &S) -> StdResult<Option<CanonicalAddr>> {
+    Ok(ReadonlySingleton::new(storage, KEY_ATTESTATION_CONTRACT)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_bootstrap_mode<S: Storage>(storage: &mut S, data: &BootstrapMode) -> StdResult<()> {
+    Singleton::new(storage, KEY_BOOTSTRAP_MODE).save(data)
+}
+
+pub fn remove_bootstrap_mode<S: Storage>(storage: &mut S) {
+    Singleton::<S, BootstrapMode>::new(storage, KEY_BOOTSTRAP_MODE).remove()
+}
+
+pub fn read_bootstrap_mode<S: Storage>(storage: &S) -> StdResult<Option<BootstrapMode>> {
+    ReadonlySingleton::new(storage, KEY_BOOTSTRAP_MODE).may_load()
+}
+
+pub fn store_invariant_checks<S: Storage>(storage: &mut S, enabled: bool) -> StdResult<()> {
+    Singleton::new(storage, KEY_INVARIANT_CHECKS).save(&enabled)
+}
+
+/// Invariant checks are disabled unless set otherwise
+pub fn read_invariant_checks<S: Storage>(storage: &S) -> StdResult<bool> {
+    Ok(ReadonlySingleton::new(storage, KEY_INVARIANT_CHECKS)
+        .may_load()?
+        .unwrap_or(false))
+}
+
+pub fn store_flow_window<S: Storage>(storage: &mut S, window: &FlowWindow) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLOW_WINDOW).save(window)
+}
+
+pub fn read_flow_window<S: Storage>(storage: &S) -> StdResult<Option<FlowWindow>> {
+    ReadonlySingleton::new(storage, KEY_FLOW_WINDOW).may_load()
+}
+
+pub fn store_flow_limit_override<S: Storage>(storage: &mut S, enabled: bool) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLOW_LIMIT_OVERRIDE).save(&enabled)
+}
+
+pub fn read_flow_limit_override<S: Storage>(storage: &S) -> StdResult<bool> {
+    Ok(ReadonlySingleton::new(storage, KEY_FLOW_LIMIT_OVERRIDE)
+        .may_load()?
+        .unwrap_or(false))
+}
+
+pub fn store_registry_cache<S: Storage>(storage: &mut S, data: &RegistryCache) -> StdResult<()> {
+    Singleton::new(storage, KEY_REGISTRY).save(data)
+}
+
+pub fn remove_registry_cache<S: Storage>(storage: &mut S) {
+    Singleton::<S, RegistryCache>::new(storage, KEY_REGISTRY).remove()
+}
+
+pub fn store_shadow_interest_model<S: Storage>(
+    storage: &mut S,
+    data: &ShadowInterestModel,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_SHADOW_INTEREST_MODEL).save(data)
+}
+
+pub fn remove_shadow_interest_model<S: Storage>(storage: &mut S) {
+    Singleton::<S, ShadowInterestModel>::new(storage, KEY_SHADOW_INTEREST_MODEL).remove()
+}
+
+pub fn read_shadow_interest_model<S: Storage>(
+    storage: &S,
+) -> StdResult<Option<ShadowInterestModel>> {
+    ReadonlySingleton::new(storage, KEY_SHADOW_INTEREST_MODEL).may_load()
+}
+
+pub fn read_registry_cache<S: Storage>(storage: &S) -> StdResult<Option<RegistryCache>> {
+    ReadonlySingleton::new(storage, KEY_REGISTRY).may_load()
+}
+
+pub fn store_cold_unbonding<S: Storage>(
+    storage: &mut S,
+    address: &CanonicalAddr,
+    unbonding: &ColdUnbonding,
+) -> StdResult<()> {
+    bucket(PREFIX_COLD_UNBONDING, storage).save(address.as_slice(), unbonding)
+}
+
+pub fn remove_cold_unbonding<S: Storage>(storage: &mut S, address: &CanonicalAddr) {
+    bucket::<S, ColdUnbonding>(PREFIX_COLD_UNBONDING, storage).remove(address.as_slice())
+}
+
+pub fn read_cold_unbonding<S: Storage>(storage: &S, address: &CanonicalAddr) -> ColdUnbonding {
+    match bucket_read(PREFIX_COLD_UNBONDING, storage).load(address.as_slice()) {
+        Ok(v) => v,
+        _ => ColdUnbonding {
+            aterra_amount: Uint256::zero(),
+            release_height: 0,
+        },
+    }
+}
+
+pub fn store_depositor_info<S: Storage>(
+    storage: &mut S,
+    depositor: &CanonicalAddr,
+    info: &DepositorInfo,
+) -> StdResult<()> {
+    bucket(PREFIX_DEPOSITOR, storage).save(depositor.as_slice(), info)
+}
+
+pub fn read_depositor_info<S: Storage>(storage: &S, depositor: &CanonicalAddr) -> DepositorInfo {
+    match bucket_read(PREFIX_DEPOSITOR, storage).load(depositor.as_slice()) {
+        Ok(v) => v,
+        _ => DepositorInfo {
+            total_deposited: Uint256::zero(),
+            total_redeemed: Uint256::zero(),
+        },
+    }
+}
+
+/// Depositors after `start_after`, in address order
+pub fn read_depositor_infos<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, DepositorInfo)>> {
+    let depositor_bucket: ReadonlyBucket<S, DepositorInfo> = bucket_read(PREFIX_DEPOSITOR, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    depositor_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
 pub fn store_borrower_info<S: Storage>(
     storage: &mut S,
     borrower: &CanonicalAddr,
@@ -79,10 +941,49 @@ pub fn read_borrower_info<S: Storage>(storage: &S, borrower: &CanonicalAddr) ->
             reward_index: Decimal256::zero(),
             loan_amount: Uint256::zero(),
             pending_rewards: Decimal256::zero(),
+            last_updated: 0,
+            stable_rate: None,
+            stable_accrued_periods: Decimal256::zero(),
         },
     }
 }
 
+pub fn remove_borrower_info<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    bucket::<S, BorrowerInfo>(PREFIX_LIABILITY, storage).remove(borrower.as_slice());
+}
+
+/// Returns up to `limit` liabilities stored after `start_after`
+pub fn read_liabilities<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: u32,
+) -> StdResult<Vec<(CanonicalAddr, BorrowerInfo)>> {
+    let liability_bucket: ReadonlyBucket<S, BorrowerInfo> = bucket_read(PREFIX_LIABILITY, storage);
+    let start = calc_range_start(start_after);
+
+    liability_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit as usize)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_prune_cursor<S: Storage>(
+    storage: &mut S,
+    cursor: &Option<CanonicalAddr>,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_PRUNE_CURSOR).save(cursor)
+}
+
+pub fn read_prune_cursor<S: Storage>(storage: &S) -> StdResult<Option<CanonicalAddr>> {
+    Ok(ReadonlySingleton::new(storage, KEY_PRUNE_CURSOR)
+        .may_load()?
+        .unwrap_or(None))
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -91,29 +992,241 @@ pub fn read_borrower_infos<S: Storage, A: Api, Q: Querier>(
     start_after: Option<CanonicalAddr>,
     limit: Option<u32>,
 ) -> StdResult<Vec<BorrowerInfoResponse>> {
+    let (borrower_infos, _) =
+        read_filtered_borrower_infos(deps, start_after, limit, |_, _| Ok(true))?;
+    Ok(borrower_infos)
+}
+
+/// Same as `read_borrower_infos`, keeping the liabilities `filter`
+/// returns true for. The limit caps the scanned liabilities, the
+/// last of which is returned for the next page to start after
+pub fn read_filtered_borrower_infos<S: Storage, A: Api, Q: Querier, F>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+    mut filter: F,
+) -> StdResult<(Vec<BorrowerInfoResponse>, Option<CanonicalAddr>)>
+where
+    F: FnMut(&CanonicalAddr, &BorrowerInfo) -> StdResult<bool>,
+{
     let liability_bucket: ReadonlyBucket<S, BorrowerInfo> =
         bucket_read(PREFIX_LIABILITY, &deps.storage);
 
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = calc_range_start(start_after);
 
-    liability_bucket
+    let mut borrower_infos: Vec<BorrowerInfoResponse> = vec![];
+    let mut last_scanned: Option<CanonicalAddr> = None;
+    for elem in liability_bucket
         .range(start.as_deref(), None, Order::Ascending)
         .take(limit)
-        .map(|elem| {
-            let (k, v) = elem?;
-            let borrower: HumanAddr = deps.api.human_address(&CanonicalAddr::from(k))?;
-            Ok(BorrowerInfoResponse {
-                borrower,
+    {
+        let (k, v) = elem?;
+        let borrower = CanonicalAddr::from(k);
+        if filter(&borrower, &v)? {
+            borrower_infos.push(BorrowerInfoResponse {
+                borrower: deps.api.human_address(&borrower)?,
                 interest_index: v.interest_index,
                 reward_index: v.reward_index,
                 loan_amount: v.loan_amount,
                 pending_rewards: v.pending_rewards,
-            })
+            });
+        }
+        last_scanned = Some(borrower);
+    }
+
+    Ok((borrower_infos, last_scanned))
+}
+
+pub fn store_credit_line<S: Storage>(
+    storage: &mut S,
+    strategy: &CanonicalAddr,
+    credit_line: &CreditLine,
+) -> StdResult<()> {
+    bucket(PREFIX_CREDIT_LINE, storage).save(strategy.as_slice(), credit_line)
+}
+
+pub fn read_credit_line<S: Storage>(
+    storage: &S,
+    strategy: &CanonicalAddr,
+) -> StdResult<Option<CreditLine>> {
+    bucket_read(PREFIX_CREDIT_LINE, storage).may_load(strategy.as_slice())
+}
+
+pub fn read_credit_lines<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, CreditLine)>> {
+    let credit_line_bucket: ReadonlyBucket<S, CreditLine> =
+        bucket_read(PREFIX_CREDIT_LINE, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    credit_line_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_stable_denom_state<S: Storage>(
+    storage: &mut S,
+    stable_denom: &str,
+    stable_denom_state: &StableDenomState,
+) -> StdResult<()> {
+    bucket(PREFIX_STABLE_DENOM, storage).save(stable_denom.as_bytes(), stable_denom_state)
+}
+
+pub fn read_stable_denom_state<S: Storage>(
+    storage: &S,
+    stable_denom: &str,
+) -> StdResult<Option<StableDenomState>> {
+    bucket_read(PREFIX_STABLE_DENOM, storage).may_load(stable_denom.as_bytes())
+}
+
+pub fn read_stable_denom_states<S: Storage>(
+    storage: &S,
+) -> StdResult<Vec<(String, StableDenomState)>> {
+    let stable_denom_bucket: ReadonlyBucket<S, StableDenomState> =
+        bucket_read(PREFIX_STABLE_DENOM, storage);
+
+    stable_denom_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((String::from_utf8_lossy(&k).to_string(), v))
         })
         .collect()
 }
 
+pub fn store_cw20_stable_state<S: Storage>(
+    storage: &mut S,
+    token: &CanonicalAddr,
+    cw20_stable_state: &Cw20StableState,
+) -> StdResult<()> {
+    bucket(PREFIX_CW20_STABLE, storage).save(token.as_slice(), cw20_stable_state)
+}
+
+pub fn read_cw20_stable_state<S: Storage>(
+    storage: &S,
+    token: &CanonicalAddr,
+) -> StdResult<Option<Cw20StableState>> {
+    bucket_read(PREFIX_CW20_STABLE, storage).may_load(token.as_slice())
+}
+
+pub fn read_cw20_stable_states<S: Storage>(
+    storage: &S,
+) -> StdResult<Vec<(CanonicalAddr, Cw20StableState)>> {
+    let cw20_stable_bucket: ReadonlyBucket<S, Cw20StableState> =
+        bucket_read(PREFIX_CW20_STABLE, storage);
+
+    cw20_stable_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_fee_exempt_redeemer<S: Storage>(
+    storage: &mut S,
+    redeemer: &CanonicalAddr,
+) -> StdResult<()> {
+    bucket(PREFIX_FEE_EXEMPT_REDEEMER, storage).save(redeemer.as_slice(), &true)
+}
+
+pub fn remove_fee_exempt_redeemer<S: Storage>(storage: &mut S, redeemer: &CanonicalAddr) {
+    bucket::<S, bool>(PREFIX_FEE_EXEMPT_REDEEMER, storage).remove(redeemer.as_slice())
+}
+
+pub fn is_fee_exempt_redeemer<S: Storage>(
+    storage: &S,
+    redeemer: &CanonicalAddr,
+) -> StdResult<bool> {
+    Ok(bucket_read::<S, bool>(PREFIX_FEE_EXEMPT_REDEEMER, storage)
+        .may_load(redeemer.as_slice())?
+        .is_some())
+}
+
+pub fn read_fee_exempt_redeemers<S: Storage>(storage: &S) -> StdResult<Vec<CanonicalAddr>> {
+    let redeemer_bucket: ReadonlyBucket<S, bool> = bucket_read(PREFIX_FEE_EXEMPT_REDEEMER, storage);
+
+    redeemer_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, _) = elem?;
+            Ok(CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
+/// Appends the redemption to the queue, returning its id
+pub fn push_queued_redemption<S: Storage>(
+    storage: &mut S,
+    redemption: &QueuedRedemption,
+) -> StdResult<u64> {
+    let id: u64 = ReadonlySingleton::new(storage, KEY_REDEMPTION_QUEUE_NEXT_ID)
+        .may_load()?
+        .unwrap_or(0);
+    Singleton::new(storage, KEY_REDEMPTION_QUEUE_NEXT_ID).save(&(id + 1))?;
+    bucket(PREFIX_QUEUED_REDEMPTION, storage).save(&id.to_be_bytes(), redemption)?;
+    Ok(id)
+}
+
+pub fn remove_queued_redemption<S: Storage>(storage: &mut S, id: u64) {
+    bucket::<S, QueuedRedemption>(PREFIX_QUEUED_REDEMPTION, storage).remove(&id.to_be_bytes())
+}
+
+/// Oldest first
+pub fn read_queued_redemptions<S: Storage>(
+    storage: &S,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, QueuedRedemption)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| (id + 1).to_be_bytes().to_vec());
+
+    bucket_read::<S, QueuedRedemption>(PREFIX_QUEUED_REDEMPTION, storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((u64_from_key(&k), v))
+        })
+        .collect()
+}
+
+fn u64_from_key(key: &[u8]) -> u64 {
+    let mut key_bytes = [0u8; 8];
+    key_bytes.copy_from_slice(key);
+    u64::from_be_bytes(key_bytes)
+}
+
+pub fn store_amortizing_loan<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    loan: &AmortizingLoan,
+) -> StdResult<()> {
+    bucket(PREFIX_AMORTIZING_LOAN, storage).save(borrower.as_slice(), loan)
+}
+
+pub fn remove_amortizing_loan<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    bucket::<S, AmortizingLoan>(PREFIX_AMORTIZING_LOAN, storage).remove(borrower.as_slice())
+}
+
+pub fn read_amortizing_loan<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<AmortizingLoan>> {
+    bucket_read(PREFIX_AMORTIZING_LOAN, storage).may_load(borrower.as_slice())
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
@@ -0,0 +1,67 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Api, Extern, Querier, StdResult, Storage};
+
+use crate::state::{
+    push_state_snapshot, read_state_snapshot_at, read_state_snapshots, State, StateSnapshot,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{StateHistoryResponse, StateSnapshotResponse};
+
+/// Records the state left by the epoch operations executed at `height`
+pub(crate) fn record_state_snapshot<S: Storage>(
+    storage: &mut S,
+    height: u64,
+    state: &State,
+    aterra_supply: Uint256,
+    deposit_rate: Decimal256,
+    borrow_rate: Decimal256,
+) -> StdResult<()> {
+    push_state_snapshot(
+        storage,
+        height,
+        &StateSnapshot {
+            total_liabilities: state.total_liabilities,
+            total_reserves: state.total_reserves,
+            aterra_supply,
+            exchange_rate: state.prev_exchange_rate,
+            deposit_rate,
+            borrow_rate,
+        },
+    )
+}
+
+fn to_response(height: u64, snapshot: StateSnapshot) -> StateSnapshotResponse {
+    StateSnapshotResponse {
+        height,
+        total_liabilities: snapshot.total_liabilities,
+        total_reserves: snapshot.total_reserves,
+        aterra_supply: snapshot.aterra_supply,
+        exchange_rate: snapshot.exchange_rate,
+        deposit_rate: snapshot.deposit_rate,
+        borrow_rate: snapshot.borrow_rate,
+    }
+}
+
+pub fn query_state_at<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    height: u64,
+) -> StdResult<StateSnapshotResponse> {
+    match read_state_snapshot_at(&deps.storage, height)? {
+        Some((snapshot_height, snapshot)) => Ok(to_response(snapshot_height, snapshot)),
+        None => Err(ContractError::NoStateSnapshot { height }.into()),
+    }
+}
+
+pub fn query_state_history<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<StateHistoryResponse> {
+    let snapshots = read_state_snapshots(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(height, snapshot)| to_response(height, snapshot))
+        .collect();
+
+    Ok(StateHistoryResponse { snapshots })
+}
@@ -0,0 +1,88 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, Coin, Env, Extern, HandleResponse, HandleResult, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::state::{read_config, read_tax_params, store_tax_params, Config, TaxParams};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::TaxParamsResponse;
+use moneymarket::querier::{
+    compute_tax_raw, deduct_tax, is_taxed_denom, query_tax_cap, query_tax_rate,
+};
+
+/// Number of blocks the cached tax parameters are used for,
+/// the treasury is queried again once they are older
+pub const TAX_PARAMS_MAX_AGE: u64 = 14400;
+
+pub fn refresh_tax_params<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    // the treasury has no tax cap for tax exempt denoms
+    let tax_params = if is_taxed_denom(&config.stable_denom) {
+        TaxParams {
+            rate: query_tax_rate(deps)?,
+            cap: query_tax_cap(deps, config.stable_denom)?,
+            updated_height: env.block.height,
+        }
+    } else {
+        TaxParams {
+            rate: Decimal256::zero(),
+            cap: Uint256::zero(),
+            updated_height: env.block.height,
+        }
+    };
+
+    store_tax_params(&mut deps.storage, &tax_params)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "refresh_tax_params"),
+            log("rate", tax_params.rate),
+            log("cap", tax_params.cap),
+        ],
+        data: None,
+    })
+}
+
+/// Deducts the tax of sending the stable `coin` with the cached
+/// tax parameters, querying the treasury only when they are stale
+pub(crate) fn deduct_tax_cached<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+    coin: Coin,
+) -> StdResult<Coin> {
+    if !is_taxed_denom(&coin.denom) {
+        return Ok(coin);
+    }
+
+    let tax_params = match read_tax_params(&deps.storage)? {
+        Some(v) if block_height <= v.updated_height + TAX_PARAMS_MAX_AGE => v,
+        _ => return deduct_tax(deps, coin),
+    };
+
+    let tax_amount = compute_tax_raw(&coin, tax_params.rate, tax_params.cap);
+    Ok(Coin {
+        denom: coin.denom,
+        amount: (Uint256::from(coin.amount) - tax_amount).into(),
+    })
+}
+
+pub fn query_tax_params<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<TaxParamsResponse> {
+    let tax_params: TaxParams = read_tax_params(&deps.storage)?
+        .ok_or_else(|| StdError::from(ContractError::NoTaxParams {}))?;
+
+    Ok(TaxParamsResponse {
+        rate: tax_params.rate,
+        cap: tax_params.cap,
+        updated_height: tax_params.updated_height,
+        expires_height: tax_params.updated_height + TAX_PARAMS_MAX_AGE,
+    })
+}
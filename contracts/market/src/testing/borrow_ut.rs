@@ -1,14 +1,17 @@
-use crate::borrow::{compute_borrower_interest, compute_interest};
-use crate::state::{store_state, BorrowerInfo, Config, State};
+use crate::borrow::{
+    clamp_model_reserves, compute_borrower_interest, compute_interest, compute_interest_raw,
+    compute_reward, estimate_block_time,
+};
+use crate::state::{store_state, AccrualClock, BorrowerInfo, Config, State};
 use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
-use cosmwasm_std::{Api, Coin, HumanAddr, Uint128};
+use cosmwasm_std::{log, Api, Coin, HumanAddr, Uint128};
 
 #[test]
 fn proper_compute_borrower_interest() {
     let env = mock_env("addr0000", &[]);
-    let mock_state = State {
+    let mut mock_state = State {
         total_liabilities: Decimal256::from_uint256(1000000u128),
         total_reserves: Decimal256::from_uint256(0u128),
         last_interest_updated: env.block.height,
@@ -18,23 +21,38 @@ fn proper_compute_borrower_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
     };
     let mut liability1 = BorrowerInfo {
         interest_index: Decimal256::one(),
         reward_index: Decimal256::zero(),
         loan_amount: Uint256::zero(),
         pending_rewards: Decimal256::zero(),
+        last_updated: 0,
+        stable_rate: None,
+        stable_accrued_periods: Decimal256::zero(),
     };
-    compute_borrower_interest(&mock_state, &mut liability1);
+    compute_borrower_interest(&mut mock_state, &mut liability1);
     let liability2 = BorrowerInfo {
         interest_index: Decimal256::one(),
         reward_index: Decimal256::zero(),
         loan_amount: Uint256::zero(),
         pending_rewards: Decimal256::zero(),
+        last_updated: 0,
+        stable_rate: None,
+        stable_accrued_periods: Decimal256::zero(),
     };
     assert_eq!(liability1, liability2);
 
-    let mock_state2 = State {
+    let mut mock_state2 = State {
         total_liabilities: Decimal256::from_uint256(300000u128),
         total_reserves: Decimal256::from_uint256(1000u128),
         last_interest_updated: env.block.height,
@@ -44,19 +62,34 @@ fn proper_compute_borrower_interest() {
         anc_emission_rate: Decimal256::zero(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
     };
     let mut liability3 = BorrowerInfo {
         interest_index: Decimal256::from_uint256(4u128),
         reward_index: Decimal256::zero(),
         loan_amount: Uint256::from(80u128),
         pending_rewards: Decimal256::zero(),
+        last_updated: 0,
+        stable_rate: None,
+        stable_accrued_periods: Decimal256::zero(),
     };
-    compute_borrower_interest(&mock_state2, &mut liability3);
+    compute_borrower_interest(&mut mock_state2, &mut liability3);
     let liability4 = BorrowerInfo {
         interest_index: Decimal256::from_uint256(2u128),
         reward_index: Decimal256::zero(),
         loan_amount: Uint256::from(40u128),
         pending_rewards: Decimal256::zero(),
+        last_updated: 0,
+        stable_rate: None,
+        stable_accrued_periods: Decimal256::zero(),
     };
     assert_eq!(liability3, liability4);
 }
@@ -113,6 +146,14 @@ fn proper_compute_interest() {
             .unwrap(),
         stable_denom: "uusd".to_string(),
         max_borrow_factor: Decimal256::one(),
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: Decimal256::zero(),
     };
 
     deps.querier
@@ -128,6 +169,15 @@ fn proper_compute_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
     };
     store_state(&mut deps.storage, &mock_state).unwrap();
 
@@ -138,6 +188,7 @@ fn proper_compute_interest() {
         &mock_config,
         &mut mock_state,
         env.block.height,
+        env.block.time,
         mock_deposit_amount,
     )
     .unwrap();
@@ -153,6 +204,15 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
 
@@ -163,6 +223,7 @@ fn proper_compute_interest() {
         &mock_config,
         &mut mock_state,
         env.block.height,
+        env.block.time,
         mock_deposit_amount,
     )
     .unwrap();
@@ -178,6 +239,15 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::from_ratio(19995, 10000),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::percent(1),
+            accrued_periods: Decimal256::from_uint256(100u64),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
 
@@ -192,6 +262,15 @@ fn proper_compute_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::from(2000000u128),
         prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
     };
     store_state(&mut deps.storage, &mock_state).unwrap();
 
@@ -207,7 +286,15 @@ fn proper_compute_interest() {
 
     // deposit_rate: 0.02
     // target_deposit_rate: 0.01
-    compute_interest(&deps, &mock_config, &mut mock_state, env.block.height, None).unwrap();
+    compute_interest(
+        &deps,
+        &mock_config,
+        &mut mock_state,
+        env.block.height,
+        env.block.time,
+        None,
+    )
+    .unwrap();
     assert_eq!(
         mock_state,
         State {
@@ -220,6 +307,424 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::from_uint256(2u64),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::percent(1),
+            accrued_periods: Decimal256::from_uint256(100u64),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
 }
+
+#[test]
+fn compute_interest_and_reward_idempotent() {
+    let env = mock_env("addr0000", &[]);
+    let mock_state = State {
+        total_liabilities: Decimal256::from_uint256(1000000u128),
+        total_reserves: Decimal256::zero(),
+        last_interest_updated: env.block.height,
+        last_reward_updated: env.block.height,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        anc_emission_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::from(2000000u64),
+        prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
+    };
+
+    let accrue = |state: &mut State, block_height: u64| {
+        compute_interest_raw(
+            state,
+            block_height,
+            env.block.time,
+            Uint256::from(1000000u64),
+            Uint256::from(2000000u64),
+            Decimal256::permille(1),
+            Decimal256::permille(1),
+            Decimal256::zero(),
+        )
+    };
+
+    // interest first, then reward
+    let mut state = mock_state.clone();
+    assert!(accrue(&mut state, env.block.height + 100).is_some());
+    compute_reward(&mut state, env.block.height + 100, env.block.time);
+
+    // reward first, then interest
+    let mut reordered_state = mock_state;
+    compute_reward(&mut reordered_state, env.block.height + 100, env.block.time);
+    assert!(accrue(&mut reordered_state, env.block.height + 100).is_some());
+    assert_eq!(state, reordered_state);
+
+    // repeated and out of order accruals are no-ops
+    let accrued_state = state.clone();
+    for block_height in &[
+        env.block.height + 100,
+        env.block.height + 50,
+        env.block.height,
+    ] {
+        assert_eq!(accrue(&mut state, *block_height), None);
+        compute_reward(&mut state, *block_height, env.block.time);
+        assert_eq!(state, accrued_state);
+    }
+}
+
+#[test]
+fn compute_interest_clamps_utilization() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(2000000u128),
+        }],
+    );
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(&HumanAddr::from("addr0000"), &Uint128::from(2000000u128))],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+
+    let mut env = mock_env("addr0000", &[]);
+    let mock_config = Config {
+        contract_addr: deps
+            .api
+            .canonical_address(&HumanAddr::from(MOCK_CONTRACT_ADDR))
+            .unwrap(),
+        owner_addr: deps
+            .api
+            .canonical_address(&HumanAddr::from("owner"))
+            .unwrap(),
+        aterra_contract: deps
+            .api
+            .canonical_address(&HumanAddr::from("AT-uusd"))
+            .unwrap(),
+        interest_model: deps
+            .api
+            .canonical_address(&HumanAddr::from("interest"))
+            .unwrap(),
+        distribution_model: deps
+            .api
+            .canonical_address(&HumanAddr::from("distribution"))
+            .unwrap(),
+        distributor_contract: deps
+            .api
+            .canonical_address(&HumanAddr::from("distributor"))
+            .unwrap(),
+        collector_contract: deps
+            .api
+            .canonical_address(&HumanAddr::from("collector"))
+            .unwrap(),
+        overseer_contract: deps
+            .api
+            .canonical_address(&HumanAddr::from("overseer"))
+            .unwrap(),
+        stable_denom: "uusd".to_string(),
+        max_borrow_factor: Decimal256::one(),
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: Decimal256::zero(),
+    };
+
+    let mut mock_state = State {
+        total_liabilities: Decimal256::from_uint256(1000000u128),
+        total_reserves: Decimal256::from_uint256(2500000u128),
+        last_interest_updated: env.block.height,
+        last_reward_updated: env.block.height,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        anc_emission_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::zero(),
+        prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
+    };
+    store_state(&mut deps.storage, &mock_state).unwrap();
+
+    // reserves above the balance are capped to it
+    assert_eq!(
+        clamp_model_reserves(&mock_state, Uint256::from(2000000u128)),
+        (
+            Decimal256::from_uint256(2000000u128),
+            Some(Decimal256::from_uint256(500000u128))
+        )
+    );
+    assert_eq!(
+        clamp_model_reserves(&mock_state, Uint256::from(3000000u128)),
+        (Decimal256::from_uint256(2500000u128), None)
+    );
+
+    // no loans, no utilization to clamp
+    let mut no_loan_state = mock_state.clone();
+    no_loan_state.total_liabilities = Decimal256::zero();
+    assert_eq!(
+        clamp_model_reserves(&no_loan_state, Uint256::from(2000000u128)),
+        (Decimal256::from_uint256(2500000u128), None)
+    );
+
+    env.block.height += 100;
+    let logs = compute_interest(
+        &deps,
+        &mock_config,
+        &mut mock_state,
+        env.block.height,
+        env.block.time,
+        None,
+    )
+    .unwrap();
+    assert_eq!(mock_state.utilization_clamps, 1);
+    assert_eq!(
+        logs[logs.len() - 3..].to_vec(),
+        vec![
+            log("utilization_clamped", true),
+            log("excess_reserves", 500000),
+            log("utilization_clamps", 1),
+        ]
+    );
+
+    // already accrued at this height, nothing is counted
+    let logs = compute_interest(
+        &deps,
+        &mock_config,
+        &mut mock_state,
+        env.block.height,
+        env.block.time,
+        None,
+    )
+    .unwrap();
+    assert!(logs.is_empty());
+    assert_eq!(mock_state.utilization_clamps, 1);
+
+    // healthy reserves are fed unchanged
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000000u128),
+        }],
+    );
+    env.block.height += 100;
+    let logs = compute_interest(
+        &deps,
+        &mock_config,
+        &mut mock_state,
+        env.block.height,
+        env.block.time,
+        None,
+    )
+    .unwrap();
+    assert!(!logs.iter().any(|v| v.key == "utilization_clamped"));
+    assert_eq!(mock_state.utilization_clamps, 1);
+}
+
+#[test]
+fn compute_interest_skips_queries_at_same_height() {
+    // no balances nor borrow rate, any query fails
+    let deps = mock_dependencies(20, &[]);
+    let env = mock_env("addr0000", &[]);
+
+    let mock_config = Config {
+        contract_addr: deps
+            .api
+            .canonical_address(&HumanAddr::from(MOCK_CONTRACT_ADDR))
+            .unwrap(),
+        owner_addr: deps
+            .api
+            .canonical_address(&HumanAddr::from("owner"))
+            .unwrap(),
+        aterra_contract: deps
+            .api
+            .canonical_address(&HumanAddr::from("AT-uusd"))
+            .unwrap(),
+        interest_model: deps
+            .api
+            .canonical_address(&HumanAddr::from("interest"))
+            .unwrap(),
+        distribution_model: deps
+            .api
+            .canonical_address(&HumanAddr::from("distribution"))
+            .unwrap(),
+        distributor_contract: deps
+            .api
+            .canonical_address(&HumanAddr::from("distributor"))
+            .unwrap(),
+        collector_contract: deps
+            .api
+            .canonical_address(&HumanAddr::from("collector"))
+            .unwrap(),
+        overseer_contract: deps
+            .api
+            .canonical_address(&HumanAddr::from("overseer"))
+            .unwrap(),
+        stable_denom: "uusd".to_string(),
+        max_borrow_factor: Decimal256::one(),
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: Decimal256::zero(),
+    };
+
+    // accrued by an earlier handler of the block
+    let mock_state = State {
+        total_liabilities: Decimal256::from_uint256(1000000u128),
+        total_reserves: Decimal256::zero(),
+        last_interest_updated: env.block.height,
+        last_reward_updated: env.block.height,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        anc_emission_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::from(2000000u64),
+        prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
+    };
+
+    let mut state = mock_state.clone();
+    let logs = compute_interest(
+        &deps,
+        &mock_config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )
+    .unwrap();
+    assert_eq!(logs, vec![]);
+    assert_eq!(state, mock_state);
+
+    // the next height queries the supply, the balance and the rates
+    assert!(compute_interest(
+        &deps,
+        &mock_config,
+        &mut state,
+        env.block.height + 1,
+        env.block.time,
+        None
+    )
+    .is_err());
+}
+
+#[test]
+fn compute_interest_and_reward_with_block_time() {
+    let env = mock_env("addr0000", &[]);
+    let block_state = State {
+        total_liabilities: Decimal256::from_uint256(1000000u128),
+        total_reserves: Decimal256::zero(),
+        last_interest_updated: env.block.height,
+        last_reward_updated: env.block.height,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        anc_emission_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::from(2000000u64),
+        prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
+    };
+    let time_state = State {
+        accrual_clock: Some(AccrualClock {
+            seconds_per_block: 6u64,
+            last_interest_updated_time: env.block.time,
+            last_reward_updated_time: env.block.time,
+        }),
+        ..block_state.clone()
+    };
+
+    let accrue = |state: &mut State, block_height: u64, block_time: u64| {
+        compute_interest_raw(
+            state,
+            block_height,
+            block_time,
+            Uint256::from(1000000u64),
+            Uint256::from(2000000u64),
+            Decimal256::permille(1),
+            Decimal256::permille(1),
+            Decimal256::zero(),
+        );
+        compute_reward(state, block_height, block_time);
+    };
+
+    // 100 blocks of 12 seconds accrue as much as 200 blocks of 6 seconds
+    let mut state = time_state.clone();
+    accrue(&mut state, env.block.height + 100, env.block.time + 1200);
+
+    let mut expected_state = block_state;
+    accrue(&mut expected_state, env.block.height + 200, env.block.time);
+    assert_eq!(state.total_liabilities, expected_state.total_liabilities);
+    assert_eq!(
+        state.global_interest_index,
+        expected_state.global_interest_index
+    );
+    assert_eq!(
+        state.global_reward_index,
+        expected_state.global_reward_index
+    );
+    assert_eq!(state.last_interest_updated, env.block.height + 100);
+    assert_eq!(
+        state.accrual_clock,
+        Some(AccrualClock {
+            seconds_per_block: 6u64,
+            last_interest_updated_time: env.block.time + 1200,
+            last_reward_updated_time: env.block.time + 1200,
+        })
+    );
+
+    // the queries extrapolate the block time
+    assert_eq!(
+        estimate_block_time(&state, env.block.height + 110),
+        env.block.time + 1260
+    );
+    assert_eq!(
+        estimate_block_time(&time_state, env.block.height),
+        env.block.time
+    );
+
+    // a new height without elapsed time accrues nothing
+    let mut state = time_state.clone();
+    accrue(&mut state, env.block.height + 1, env.block.time);
+    assert_eq!(state.total_liabilities, time_state.total_liabilities);
+    assert_eq!(state.global_reward_index, time_state.global_reward_index);
+    assert_eq!(state.last_interest_updated, env.block.height + 1);
+}
@@ -51,6 +51,14 @@ fn proper_compute_exchange_rate() {
             .unwrap(),
         stable_denom: "uusd".to_string(),
         max_borrow_factor: Decimal256::one(),
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: Decimal256::zero(),
     };
     deps.querier.with_token_balances(&[(
         &HumanAddr::from("AT-uusd"),
@@ -70,6 +78,15 @@ fn proper_compute_exchange_rate() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
     };
     let mock_deposit_amount = Some(Uint256::from(1000000u128));
 
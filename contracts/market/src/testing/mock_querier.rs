@@ -11,9 +11,12 @@ use cosmwasm_storage::to_length_prefixed;
 use std::collections::HashMap;
 
 use cw20::TokenInfoResponse;
+use moneymarket::attestation::{AttestResponse, AttestedAction};
 use moneymarket::distribution_model::AncEmissionRateResponse;
 use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse};
+use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse, LiquidationOrder};
+use moneymarket::protocol_controller::{ProtocolMode, ProtocolModeResponse};
+use moneymarket::registry::{ResolveResponse, VersionResponse};
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -39,6 +42,18 @@ pub enum QueryMsg {
     },
     /// Query overseer config to get target deposit rate
     Config {},
+    /// Query protocol mode to protocol controller contract
+    ProtocolMode {},
+    /// Query version to registry contract
+    Version {},
+    /// Query addresses to registry contract
+    Resolve { names: Vec<String> },
+    /// Query attestation contract to allow an action
+    Attest {
+        action: AttestedAction,
+        address: HumanAddr,
+        amount: Uint256,
+    },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -67,6 +82,9 @@ pub struct WasmMockQuerier {
     tax_querier: TaxQuerier,
     borrow_rate_querier: BorrowRateQuerier,
     borrow_limit_querier: BorrowLimitQuerier,
+    protocol_mode: ProtocolMode,
+    registry: (u64, HashMap<String, HumanAddr>),
+    attestation_denials: HashMap<HumanAddr, String>,
     canonical_length: usize,
 }
 
@@ -151,12 +169,15 @@ pub(crate) fn borrower_rate_to_map(
 pub struct BorrowLimitQuerier {
     // this lets us iterate over all pairs that match the first string
     borrow_limit: HashMap<HumanAddr, Uint256>,
+    // the borrow limit is used when unset
+    liquidation_borrow_limit: HashMap<HumanAddr, Uint256>,
 }
 
 impl BorrowLimitQuerier {
     pub fn new(borrow_limit: &[(&HumanAddr, &Uint256)]) -> Self {
         BorrowLimitQuerier {
             borrow_limit: borrow_limit_to_map(borrow_limit),
+            liquidation_borrow_limit: HashMap::new(),
         }
     }
 }
@@ -216,12 +237,12 @@ impl WasmMockQuerier {
                 }
             }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
-                match from_binary(&msg).unwrap() {
+                match from_binary(msg).unwrap() {
                     QueryMsg::BorrowRate {
                         market_balance: _,
                         total_liabilities: _,
                         total_reserves: _,
-                    } => match self.borrow_rate_querier.borrower_rate.get(&contract_addr) {
+                    } => match self.borrow_rate_querier.borrower_rate.get(contract_addr) {
                         Some(v) => Ok(to_binary(&BorrowRateResponse { rate: *v })),
                         None => Err(SystemError::InvalidRequest {
                             error: "No borrow rate exists".to_string(),
@@ -233,8 +254,15 @@ impl WasmMockQuerier {
                         block_time: _,
                     } => match self.borrow_limit_querier.borrow_limit.get(&borrower) {
                         Some(v) => Ok(to_binary(&BorrowLimitResponse {
+                            liquidation_borrow_limit: *self
+                                .borrow_limit_querier
+                                .liquidation_borrow_limit
+                                .get(&borrower)
+                                .unwrap_or(v),
                             borrower,
                             borrow_limit: *v,
+                            emergency_price_used: false,
+                            index_borrow_limit: None,
                         })),
                         None => Err(SystemError::InvalidRequest {
                             error: "No borrow limit exists".to_string(),
@@ -262,6 +290,39 @@ impl WasmMockQuerier {
                         stable_denom: "uusd".to_string(),
                         epoch_period: 100u64,
                         price_timeframe: 100u64,
+                        guardians: vec![HumanAddr::from("guardian")],
+                        guardian_threshold: 1,
+                        max_collateral_types: 10u64,
+                        swap_router: None,
+                        min_position_collateral_value: Uint256::zero(),
+                        liquidation_order: LiquidationOrder::ProRata,
+                        max_feed_gap: 0,
+                        max_catch_up_epochs: 0,
+                        close_factor: Decimal256::zero(),
+                        max_emergency_price_duration: 604800,
+                    })),
+                    QueryMsg::ProtocolMode {} => Ok(to_binary(&ProtocolModeResponse {
+                        mode: self.protocol_mode,
+                        updated_height: 0u64,
+                    })),
+                    QueryMsg::Version {} => Ok(to_binary(&VersionResponse {
+                        version: self.registry.0,
+                        updated_height: 0u64,
+                    })),
+                    QueryMsg::Resolve { names } => Ok(to_binary(&ResolveResponse {
+                        version: self.registry.0,
+                        addresses: names
+                            .iter()
+                            .map(|name| self.registry.1.get(name).cloned())
+                            .collect(),
+                    })),
+                    QueryMsg::Attest {
+                        action: _,
+                        address,
+                        amount: _,
+                    } => Ok(to_binary(&AttestResponse {
+                        allowed: !self.attestation_denials.contains_key(&address),
+                        reason: self.attestation_denials.get(&address).cloned(),
                     })),
                 }
             }
@@ -289,7 +350,7 @@ impl WasmMockQuerier {
                             name: "mAPPL".to_string(),
                             symbol: "mAPPL".to_string(),
                             decimals: 6,
-                            total_supply: total_supply,
+                            total_supply,
                         })
                         .unwrap(),
                     ))
@@ -337,6 +398,9 @@ impl WasmMockQuerier {
             tax_querier: TaxQuerier::default(),
             borrow_rate_querier: BorrowRateQuerier::default(),
             borrow_limit_querier: BorrowLimitQuerier::default(),
+            protocol_mode: ProtocolMode::Normal,
+            registry: (0, HashMap::new()),
+            attestation_denials: HashMap::new(),
             canonical_length,
         }
     }
@@ -367,4 +431,33 @@ impl WasmMockQuerier {
     pub fn with_borrow_limit(&mut self, borrow_limit: &[(&HumanAddr, &Uint256)]) {
         self.borrow_limit_querier = BorrowLimitQuerier::new(borrow_limit);
     }
+
+    pub fn with_liquidation_borrow_limit(
+        &mut self,
+        liquidation_borrow_limit: &[(&HumanAddr, &Uint256)],
+    ) {
+        self.borrow_limit_querier.liquidation_borrow_limit =
+            borrow_limit_to_map(liquidation_borrow_limit);
+    }
+
+    pub fn with_protocol_mode(&mut self, mode: ProtocolMode) {
+        self.protocol_mode = mode;
+    }
+
+    pub fn with_registry(&mut self, version: u64, addresses: &[(&str, &HumanAddr)]) {
+        self.registry = (
+            version,
+            addresses
+                .iter()
+                .map(|(name, address)| (name.to_string(), (*address).clone()))
+                .collect(),
+        );
+    }
+
+    pub fn with_attestation_denials(&mut self, denials: &[(&HumanAddr, &str)]) {
+        self.attestation_denials = denials
+            .iter()
+            .map(|(address, reason)| ((*address).clone(), reason.to_string()))
+            .collect();
+    }
 }
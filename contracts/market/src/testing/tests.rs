@@ -1,19 +1,42 @@
 use crate::contract::{handle, init, query, INITIAL_DEPOSIT_AMOUNT};
-use crate::state::{read_borrower_infos, read_state, store_state, State};
+use crate::prune::CLOSED_POSITION_MIN_AGE;
+use crate::state::{
+    push_state_snapshot, read_borrower_infos, read_state, store_cw20_stable_state,
+    store_idle_deployment, store_state, AccrualClock, Cw20StableState, IdleDeployment, State,
+    StateSnapshot, MAX_STATE_SNAPSHOTS,
+};
+use crate::tax::{deduct_tax_cached, TAX_PARAMS_MAX_AGE};
 use crate::testing::mock_querier::mock_dependencies;
 
 use anchor_token::distributor::HandleMsg as FaucetHandleMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, log, to_binary, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError, Uint128,
-    WasmMsg,
+    from_binary, log, to_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError,
+    Uint128, WasmMsg,
 };
 use cw20::{Cw20CoinHuman, Cw20HandleMsg, Cw20ReceiveMsg, MinterResponse};
+use moneymarket::exchange_rate_oracle::HandleMsg as ExchangeRateOracleHandleMsg;
 use moneymarket::market::{
-    BorrowerInfoResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, QueryMsg, StateResponse,
+    ATerraExchangeRateResponse, AmortizingLoanResponse, AttestationContractResponse,
+    BootstrapModeResponse, BorrowerInfoResponse, BorrowerInfosResponse, ColdTrancheResponse,
+    ColdUnbondingResponse, ConfigResponse, CreditLineResponse, Cw20HookMsg, Cw20StableResponse,
+    Cw20StablesResponse, DebtAuctionResponse, DepositorInfoResponse, DepositorsResponse,
+    DonationsResponse, DustForgivenessResponse, EpochStateResponse, FeeExemptRedeemersResponse,
+    FlashLoanResponse, FlashMintResponse, FlowLimitResponse, HandleMsg, IdleDeploymentResponse,
+    InitMsg, InvariantChecksResponse, KeeperBountyResponse, LoanBreakdownResponse,
+    OperationModeResponse, QueryMsg, QueuedRedemptionResponse, QueuedRedemptionsResponse, RateMode,
+    RateModeResponse, RateSnapshotResponse, RedemptionFeeResponse, RegistryResponse,
+    ShadowInterestModelResponse, SimulateDepositResponse, SimulateRedeemResponse,
+    StableDenomResponse, StableDenomsResponse, StableRateParamsResponse, StateHistoryResponse,
+    StateResponse, StateSnapshotResponse, TaxParamsResponse,
 };
+use moneymarket::overseer::PositionHealthResponse;
+use moneymarket::ownership::OwnershipProposalResponse;
+use moneymarket::protocol_controller::{ProtocolMode, PROTOCOL_MODE_MAX_AGE};
 use moneymarket::querier::deduct_tax;
+use moneymarket::registry::REGISTRY_MAX_AGE;
+use moneymarket::venue::HandleMsg as VenueHandleMsg;
 use std::str::FromStr;
 use terraswap::hook::InitHook;
 use terraswap::token::InitMsg as TokenInitMsg;
@@ -180,18 +203,89 @@ fn update_config() {
     let env = mock_env("addr0000", &[]);
     let _res = handle(&mut deps, env, msg).unwrap();
 
-    // update owner
+    // propose a new owner
     let env = mock_env("owner", &[]);
-    let msg = HandleMsg::UpdateConfig {
-        owner_addr: Some(HumanAddr("owner1".to_string())),
-        interest_model: None,
-        distribution_model: None,
-        max_borrow_factor: None,
+    let msg = HandleMsg::ProposeNewOwner {
+        new_owner: HumanAddr::from("owner1"),
+        expiry_height: 12355u64,
     };
 
-    let res = handle(&mut deps, env, msg).unwrap();
+    let res = handle(&mut deps, env, msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "propose_new_owner"),
+            log("new_owner", "owner1"),
+            log("expiry_height", 12355u64),
+        ]
+    );
+
+    // only the owner can propose
+    let env = mock_env("owner1", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // the expiry must be in the future
+    let env = mock_env("owner", &[]);
+    let msg = HandleMsg::ProposeNewOwner {
+        new_owner: HumanAddr::from("owner1"),
+        expiry_height: 12345u64,
+    };
+    match handle(&mut deps, env, msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E9011] expiry_height must be greater than the current height"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(&deps, QueryMsg::OwnershipProposal {}).unwrap();
+    let proposal_res: OwnershipProposalResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        proposal_res,
+        OwnershipProposalResponse {
+            new_owner: HumanAddr::from("owner1"),
+            expiry_height: 12355u64,
+        }
+    );
+
+    // only the proposed owner can accept
+    let env = mock_env("addr0000", &[]);
+    match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // the proposal cannot be accepted once expired
+    let mut env = mock_env("owner1", &[]);
+    env.block.height = 12355u64;
+    match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E9013] The ownership proposal expired at height 12355"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner1", &[]);
+    let res = handle(&mut deps, env, HandleMsg::AcceptOwnership {}).unwrap();
     assert_eq!(0, res.messages.len());
 
+    // the proposal is consumed
+    let env = mock_env("owner1", &[]);
+    match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9012] No ownership transfer is proposed")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
     // it worked, let's query the state
     let res = query(&deps, QueryMsg::Config {}).unwrap();
     let config_res: ConfigResponse = from_binary(&res).unwrap();
@@ -200,10 +294,17 @@ fn update_config() {
     // update left items
     let env = mock_env("owner1", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner_addr: None,
         interest_model: Some(HumanAddr::from("interest2")),
         distribution_model: Some(HumanAddr::from("distribution2")),
         max_borrow_factor: Some(Decimal256::percent(100)),
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: None,
     };
 
     let res = handle(&mut deps, env, msg).unwrap();
@@ -223,10 +324,17 @@ fn update_config() {
     // Unauthorized err
     let env = mock_env("owner", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner_addr: None,
         interest_model: None,
         distribution_model: None,
         max_borrow_factor: None,
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: None,
     };
 
     let res = handle(&mut deps, env, msg);
@@ -234,6 +342,82 @@ fn update_config() {
         Err(StdError::Unauthorized { .. }) => {}
         _ => panic!("Must return unauthorized error"),
     }
+
+    // batched owner operations
+    let msg = HandleMsg::AdminBatch {
+        ops: vec![
+            HandleMsg::UpdateConfig {
+                interest_model: None,
+                distribution_model: None,
+                max_borrow_factor: Some(Decimal256::percent(80)),
+                global_borrow_cap: None,
+                per_account_borrow_cap: None,
+                min_deposit_amount: None,
+                min_redeem_amount: None,
+                flow_window: None,
+                max_net_flow_ratio: None,
+                guardian: None,
+                reserve_factor: None,
+            },
+            HandleMsg::UpdateRedemptionFee {
+                free_ratio: Decimal256::percent(10),
+                max_fee_rate: Decimal256::percent(1),
+            },
+        ],
+    };
+    let res = handle(&mut deps, mock_env("owner", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner1", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "admin_batch"),
+            log("ops", 2),
+            log("action", "update_config"),
+            log("action", "update_redemption_fee"),
+            log("free_ratio", Decimal256::percent(10)),
+            log("max_fee_rate", Decimal256::percent(1)),
+        ]
+    );
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(Decimal256::percent(80), config_res.max_borrow_factor);
+    let fee_res: RedemptionFeeResponse =
+        from_binary(&query(&deps, QueryMsg::RedemptionFee {}).unwrap()).unwrap();
+    assert_eq!(Decimal256::percent(1), fee_res.max_fee_rate);
+
+    let msg = HandleMsg::AdminBatch {
+        ops: vec![HandleMsg::DepositStable {
+            recipient: None,
+            amount: None,
+            decimals: None,
+        }],
+    };
+    let res = handle(&mut deps, mock_env("owner1", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9002] Only owner operations can be batched")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the invariant checks are turned off outside of a batch,
+    // the batch itself is checked once all its operations ran
+    let msg = HandleMsg::AdminBatch {
+        ops: vec![HandleMsg::UpdateInvariantChecks { enabled: false }],
+    };
+    let res = handle(&mut deps, mock_env("owner1", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9002] Only owner operations can be batched")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 }
 
 #[test]
@@ -281,7 +465,11 @@ fn deposit_stable_huge_amount() {
     let _res = handle(&mut deps, env, msg).unwrap();
 
     // Must deposit stable_denom
-    let msg = HandleMsg::DepositStable {};
+    let msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
     let env = mock_env(
         "addr0000",
         &[Coin {
@@ -293,7 +481,7 @@ fn deposit_stable_huge_amount() {
     let res = handle(&mut deps, env, msg.clone());
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Deposit amount must be greater than 0 uusd")
+            assert_eq!(msg, "[E1001] Deposit amount must be greater than 0 uusd")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -329,6 +517,7 @@ fn deposit_stable_huge_amount() {
         vec![
             log("action", "deposit_stable"),
             log("depositor", "addr0000"),
+            log("recipient", "addr0000"),
             log("mint_amount", "55555555000000"),
             log("deposit_amount", "55555555000000"),
         ]
@@ -369,6 +558,7 @@ fn deposit_stable_huge_amount() {
         vec![
             log("action", "deposit_stable"),
             log("depositor", "addr0000"),
+            log("recipient", "addr0000"),
             log("mint_amount", "55555555000000"),
             log("deposit_amount", "55555555000000"),
         ]
@@ -433,7 +623,11 @@ fn deposit_stable() {
     let _res = handle(&mut deps, env, msg).unwrap();
 
     // Must deposit stable_denom
-    let msg = HandleMsg::DepositStable {};
+    let msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
     let env = mock_env(
         "addr0000",
         &[Coin {
@@ -445,7 +639,7 @@ fn deposit_stable() {
     let res = handle(&mut deps, env, msg.clone());
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Deposit amount must be greater than 0 uusd")
+            assert_eq!(msg, "[E1001] Deposit amount must be greater than 0 uusd")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -462,7 +656,7 @@ fn deposit_stable() {
     let res = handle(&mut deps, env, msg.clone());
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Deposit amount must be greater than 0 uusd")
+            assert_eq!(msg, "[E1001] Deposit amount must be greater than 0 uusd")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -509,6 +703,15 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(1000000u64),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
 
@@ -517,6 +720,7 @@ fn deposit_stable() {
         vec![
             log("action", "deposit_stable"),
             log("depositor", "addr0000"),
+            log("recipient", "addr0000"),
             log("mint_amount", "1000000"),
             log("deposit_amount", "1000000"),
         ]
@@ -548,16 +752,26 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
 
-    let res = handle(&mut deps, env, msg.clone()).unwrap();
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.log,
         vec![
             log("action", "deposit_stable"),
             log("depositor", "addr0000"),
+            log("recipient", "addr0000"),
             log("mint_amount", "2000000"),
             log("deposit_amount", "1000000"),
         ]
@@ -576,6 +790,103 @@ fn deposit_stable() {
         })]
     );
 
+    // Deposit on behalf of another address
+    let res = handle(
+        &mut deps,
+        env,
+        HandleMsg::DepositStable {
+            recipient: Some(HumanAddr::from("addr0001")),
+            amount: None,
+            decimals: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        &res.log[..3],
+        &[
+            log("action", "deposit_stable"),
+            log("depositor", "addr0000"),
+            log("recipient", "addr0001"),
+        ]
+    );
+    match &res.messages[0] {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+            Cw20HandleMsg::Mint { recipient, .. } => {
+                assert_eq!(recipient, HumanAddr::from("addr0001"))
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        },
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        &deps,
+        QueryMsg::DepositorInfo {
+            depositor: HumanAddr::from("addr0001"),
+        },
+    )
+    .unwrap();
+    let depositor_info: DepositorInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(depositor_info.total_deposited, Uint256::from(1000000u64));
+
+    let res = query(
+        &deps,
+        QueryMsg::Depositors {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let depositors: DepositorsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        depositors
+            .depositors
+            .iter()
+            .map(|v| v.depositor.clone())
+            .collect::<Vec<HumanAddr>>(),
+        vec![HumanAddr::from("addr0000"), HumanAddr::from("addr0001")]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Depositor {
+            address: HumanAddr::from("addr0001"),
+        },
+    )
+    .unwrap();
+    let depositor: DepositorInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(depositor, depositor_info);
+
+    // an address which never deposited has no totals
+    let res = query(
+        &deps,
+        QueryMsg::Depositor {
+            address: HumanAddr::from("addr0002"),
+        },
+    )
+    .unwrap();
+    let depositor: DepositorInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        depositor,
+        DepositorInfoResponse {
+            depositor: HumanAddr::from("addr0002"),
+            total_deposited: Uint256::zero(),
+            total_redeemed: Uint256::zero(),
+            net_principal: Uint256::zero(),
+        }
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Depositors {
+            start_after: Some(HumanAddr::from("addr0000")),
+            limit: Some(1u32),
+        },
+    )
+    .unwrap();
+    let depositors: DepositorsResponse = from_binary(&res).unwrap();
+    assert_eq!(depositors.depositors, vec![depositor_info]);
+
     // Case: compute_interest & compute_reward with block increment
     let mut env = mock_env(
         "addr0000",
@@ -597,6 +908,15 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -624,6 +944,15 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1818181),
             prev_exchange_rate: Decimal256::from_ratio(55u64, 100u64),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::percent(1),
+            accrued_periods: Decimal256::from_uint256(100u64),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
 }
@@ -674,7 +1003,11 @@ fn redeem_stable() {
     let _res = handle(&mut deps, env, msg).unwrap();
 
     // Deposit 1000000
-    let msg = HandleMsg::DepositStable {};
+    let msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
     let env = mock_env(
         "addr0000",
         &[Coin {
@@ -710,6 +1043,26 @@ fn redeem_stable() {
         )],
     )]);
 
+    let res: DepositorInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::DepositorInfo {
+                depositor: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        DepositorInfoResponse {
+            depositor: HumanAddr::from("addr0000"),
+            total_deposited: Uint256::from(1000000u64),
+            total_redeemed: Uint256::zero(),
+            net_principal: Uint256::from(1000000u64),
+        }
+    );
+
     // Redeem 1000000
     let msg = HandleMsg::Receive(Cw20ReceiveMsg {
         sender: HumanAddr::from("addr0000"),
@@ -764,6 +1117,15 @@ fn redeem_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -779,7 +1141,10 @@ fn redeem_stable() {
     let res = handle(&mut deps, env.clone(), msg.clone());
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Not enough uusd available; borrow demand too high")
+            assert_eq!(
+                msg,
+                "[E1005] Not enough uusd available; borrow demand too high"
+            )
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -818,6 +1183,27 @@ fn redeem_stable() {
             })
         ]
     );
+
+    // redeemed more than deposited, so no principal is left
+    let res: DepositorInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::DepositorInfo {
+                depositor: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        DepositorInfoResponse {
+            depositor: HumanAddr::from("addr0000"),
+            total_deposited: Uint256::from(1000000u64),
+            total_redeemed: Uint256::from(1500000u64),
+            net_principal: Uint256::zero(),
+        }
+    );
 }
 
 #[test]
@@ -885,6 +1271,15 @@ fn borrow_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -892,6 +1287,8 @@ fn borrow_stable() {
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        deposit: None,
+        decimals: None,
     };
 
     env.block.height += 100;
@@ -911,7 +1308,12 @@ fn borrow_stable() {
         vec![
             log("action", "borrow_stable"),
             log("borrower", "addr0000"),
-            log("borrow_amount", "500000")
+            log("borrow_amount", "500000"),
+            log("accrual_borrow_rate", "0.01"),
+            log("accrual_blocks", "100"),
+            log("accrual_interest", "1000000"),
+            log("accrual_reserves", "0"),
+            log("accrual_exchange_rate", "1"),
         ]
     );
 
@@ -928,7 +1330,7 @@ fn borrow_stable() {
                 }
             )
             .unwrap()],
-        }),]
+        })]
     );
 
     assert_eq!(
@@ -944,6 +1346,15 @@ fn borrow_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
 
@@ -969,6 +1380,15 @@ fn borrow_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
 
@@ -1041,12 +1461,14 @@ fn borrow_stable() {
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(500001u64),
         to: None,
+        deposit: None,
+        decimals: None,
     };
     let res = handle(&mut deps, env.clone(), msg);
     match res {
         Err(StdError::GenericErr { msg, .. }) => assert_eq!(
             msg,
-            "Borrow amount too high; Loan liability becomes greater than borrow limit: 1000000"
+            "[E1007] Borrow amount too high; Loan liability becomes greater than borrow limit: 1000000"
         ),
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -1117,6 +1539,15 @@ fn assert_max_borrow_factor() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -1124,6 +1555,8 @@ fn assert_max_borrow_factor() {
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(10000u64),
         to: None,
+        deposit: None,
+        decimals: None,
     };
 
     let res = handle(&mut deps, env.clone(), msg).unwrap();
@@ -1148,12 +1581,14 @@ fn assert_max_borrow_factor() {
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(1u64),
         to: None,
+        deposit: None,
+        decimals: None,
     };
     let res = handle(&mut deps, env.clone(), msg);
     match res {
         Err(StdError::GenericErr { msg, .. }) => assert_eq!(
             msg,
-            "Exceeds uusd max borrow factor; borrow demand too high"
+            "[E1010] Exceeds uusd max borrow factor; borrow demand too high"
         ),
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -1224,6 +1659,15 @@ fn repay_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -1231,10 +1675,22 @@ fn repay_stable() {
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        deposit: None,
+        decimals: None,
     };
 
     env.block.height += 100;
-    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    let health: PositionHealthResponse = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        health,
+        PositionHealthResponse {
+            borrower: HumanAddr::from("addr0000"),
+            loan_amount: Uint256::from(500000u64),
+            borrow_limit: Uint256::from(1000000u64),
+            health_factor: Some(Decimal256::from_uint256(2u64)),
+        }
+    );
 
     let msg = HandleMsg::RepayStable {};
     env.message.sent_funds = vec![Coin {
@@ -1245,7 +1701,7 @@ fn repay_stable() {
     let res = handle(&mut deps, env.clone(), msg.clone());
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Repay amount must be greater than 0 uusd")
+            assert_eq!(msg, "[E1006] Repay amount must be greater than 0 uusd")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -1258,7 +1714,7 @@ fn repay_stable() {
     let res2 = handle(&mut deps, env.clone(), msg.clone());
     match res2 {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Repay amount must be greater than 0 uusd")
+            assert_eq!(msg, "[E1006] Repay amount must be greater than 0 uusd")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -1282,13 +1738,25 @@ fn repay_stable() {
             log("action", "repay_stable"),
             log("borrower", "addr0000"),
             log("repay_amount", "100000"),
+            log("interest_repaid", "0"),
+            log("principal_repaid", "100000"),
         ]
     );
+    let health: PositionHealthResponse = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        health,
+        PositionHealthResponse {
+            borrower: HumanAddr::from("addr0000"),
+            loan_amount: Uint256::from(400000u64),
+            borrow_limit: Uint256::from(1000000u64),
+            health_factor: Some(Decimal256::from_str("2.5").unwrap()),
+        }
+    );
 
     //Loan amount and Total liability have decreased according to the repayment
     let res_loan = read_borrower_infos(&deps, None, None)
         .unwrap()
-        .get(0)
+        .first()
         .unwrap()
         .loan_amount;
     assert_eq!(res_loan, Uint256::from(400000u128));
@@ -1308,13 +1776,15 @@ fn repay_stable() {
             log("action", "repay_stable"),
             log("borrower", "addr0000"),
             log("repay_amount", "400000"),
+            log("interest_repaid", "0"),
+            log("principal_repaid", "400000"),
         ]
     );
 
     //Loan amount and Total liability have decreased according to the repayment
     let res_loan = read_borrower_infos(&deps, None, None)
         .unwrap()
-        .get(0)
+        .first()
         .unwrap()
         .loan_amount;
     assert_eq!(res_loan, Uint256::zero());
@@ -1405,6 +1875,15 @@ fn repay_stable_from_liquidation() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -1412,6 +1891,8 @@ fn repay_stable_from_liquidation() {
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        deposit: None,
+        decimals: None,
     };
 
     env.block.height += 100;
@@ -1450,7 +1931,7 @@ fn repay_stable_from_liquidation() {
     let res = handle(&mut deps, env.clone(), msg.clone());
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Repay amount must be greater than 0 uusd")
+            assert_eq!(msg, "[E1006] Repay amount must be greater than 0 uusd")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -1471,6 +1952,8 @@ fn repay_stable_from_liquidation() {
             log("action", "repay_stable"),
             log("borrower", "addr0000"),
             log("repay_amount", "100000"),
+            log("interest_repaid", "0"),
+            log("principal_repaid", "100000"),
         ]
     );
 
@@ -1489,6 +1972,8 @@ fn repay_stable_from_liquidation() {
             log("action", "repay_stable"),
             log("borrower", "addr0000"),
             log("repay_amount", "400000"),
+            log("interest_repaid", "0"),
+            log("principal_repaid", "400000"),
         ]
     );
 
@@ -1575,6 +2060,15 @@ fn claim_rewards() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -1587,6 +2081,8 @@ fn claim_rewards() {
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        deposit: None,
+        decimals: None,
     };
     let _res = handle(&mut deps, env.clone(), msg).unwrap();
 
@@ -1700,6 +2196,15 @@ fn execute_epoch_operations() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -1748,6 +2253,15 @@ fn execute_epoch_operations() {
             anc_emission_rate: Decimal256::from_uint256(5u64),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::percent(1),
+            accrued_periods: Decimal256::from_uint256(100u64),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
 
@@ -1761,7 +2275,9 @@ fn execute_epoch_operations() {
         }],
     );
 
+    // the next epoch starts from the last executed height
     let mut env = mock_env("overseer", &[]);
+    env.block.height += 100;
     store_state(
         &mut deps.storage,
         &State {
@@ -1774,6 +2290,15 @@ fn execute_epoch_operations() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         },
     )
     .unwrap();
@@ -1791,6 +2316,16 @@ fn execute_epoch_operations() {
     let res = handle(&mut deps, env.clone(), msg).unwrap();
     assert_eq!(res.messages.len(), 0);
 
+    // the reserves exceed the balance, the utilization is clamped
+    assert_eq!(
+        res.log[res.log.len() - 3..].to_vec(),
+        vec![
+            log("utilization_clamped", true),
+            log("excess_reserves", 1),
+            log("utilization_clamps", 1),
+        ]
+    );
+
     let state = read_state(&deps.storage).unwrap();
     assert_eq!(
         state,
@@ -1804,8 +2339,73 @@ fn execute_epoch_operations() {
             anc_emission_rate: Decimal256::from_uint256(5u64),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 1,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::percent(1),
+            accrued_periods: Decimal256::from_uint256(100u64),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
         }
     );
+
+    // interest already accrued at this height, the interest model is not
+    // queried and the distributed interest raises the exchange rate
+    env.block.height += 100;
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(1000000u64),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::percent(2),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+    deps.querier.with_borrow_rate(&[]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = HandleMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::one(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::from(100000u64),
+    };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "execute_epoch_operations"),
+            log("total_reserves", 0),
+            log("anc_emission_rate", "5"),
+        ]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.prev_exchange_rate, Decimal256::percent(110));
+    assert_eq!(state.last_borrow_rate, Decimal256::percent(2));
 }
 
 // #[test]
@@ -1898,3 +2498,9177 @@ fn execute_epoch_operations() {
 //     // only overseer can execute this
 //     let _ = handle(&mut deps, env.clone(), msg.clone()).unwrap();
 // }
+
+#[test]
+fn cold_tranche() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env, msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+        }],
+    );
+
+    let deposit_env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = handle(
+        &mut deps,
+        deposit_env.clone(),
+        HandleMsg::DepositStableCold {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1097] Cold tranche is not initialized")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::InitColdTranche {
+        caterra_code_id: 124u64,
+        redeem_delay: 100u64,
+        bonus_rate: Decimal256::from_str("0.000001").unwrap(),
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner", &[]);
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: 124u64,
+            send: vec![],
+            label: None,
+            msg: to_binary(&TokenInitMsg {
+                name: "Anchor Terra USD Cold".to_string(),
+                symbol: "caUST".to_string(),
+                decimals: 6u8,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    cap: None,
+                }),
+                init_hook: Some(InitHook {
+                    contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    msg: to_binary(&HandleMsg::RegisterColdATerra {}).unwrap(),
+                }),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // cannot initialize twice
+    let res = handle(&mut deps, env, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1094] Cold tranche already initialized")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("CAT-uusd", &[]);
+    let _res = handle(&mut deps, env.clone(), HandleMsg::RegisterColdATerra {}).unwrap();
+    let res = handle(&mut deps, env, HandleMsg::RegisterColdATerra {});
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // aterra is held by the market, cold token is minted to the depositor
+    let res = handle(&mut deps, deposit_env, HandleMsg::DepositStableCold {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("AT-uusd"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Mint {
+                    recipient: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("CAT-uusd"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Mint {
+                    recipient: HumanAddr::from("addr0000"),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &HumanAddr::from("AT-uusd"),
+            &[(
+                &HumanAddr::from(MOCK_CONTRACT_ADDR),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+            )],
+        ),
+        (
+            &HumanAddr::from("CAT-uusd"),
+            &[(
+                &HumanAddr::from(MOCK_CONTRACT_ADDR),
+                &Uint128::from(1000000u128),
+            )],
+        ),
+    ]);
+
+    // only the cold token can start unbonding
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(500000u128),
+        msg: Some(to_binary(&Cw20HookMsg::UnbondCold {}).unwrap()),
+    });
+    let env = mock_env("AT-uusd", &[]);
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("CAT-uusd", &[]);
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("CAT-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Burn {
+                amount: Uint128::from(500000u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let res: ColdUnbondingResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ColdUnbonding {
+                address: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ColdUnbondingResponse {
+            address: HumanAddr::from("addr0000"),
+            aterra_amount: Uint256::from(500000u64),
+            release_height: env.block.height + 100,
+        }
+    );
+
+    let res: ColdTrancheResponse =
+        from_binary(&query(&deps, QueryMsg::ColdTranche {}).unwrap()).unwrap();
+    assert_eq!(res.aterra_balance, Uint256::from(500000u64));
+
+    // cannot withdraw before the delay has passed
+    let mut env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env.clone(), HandleMsg::WithdrawUnbondedCold {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E1096] Cold deposits are unbonding until block {}",
+                env.block.height + 100
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.height += 100;
+    let res = handle(&mut deps, env.clone(), HandleMsg::WithdrawUnbondedCold {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("AT-uusd"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Burn {
+                    amount: Uint128::from(500000u128),
+                })
+                .unwrap()
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![deduct_tax(
+                    &deps,
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(500000u128),
+                    }
+                )
+                .unwrap(),]
+            })
+        ]
+    );
+
+    let res = handle(&mut deps, env, HandleMsg::WithdrawUnbondedCold {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1095] No unbonding cold deposits")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn redemption_fee() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    // disabled by default
+    let res: RedemptionFeeResponse =
+        from_binary(&query(&deps, QueryMsg::RedemptionFee {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        RedemptionFeeResponse {
+            free_ratio: Decimal256::zero(),
+            max_fee_rate: Decimal256::zero(),
+        }
+    );
+
+    let msg = HandleMsg::UpdateRedemptionFee {
+        free_ratio: Decimal256::percent(10),
+        max_fee_rate: Decimal256::percent(1),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdateRedemptionFee {
+            free_ratio: Decimal256::one(),
+            max_fee_rate: Decimal256::percent(1),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1078] free_ratio must be less than 1")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdateRedemptionFee {
+            free_ratio: Decimal256::percent(10),
+            max_fee_rate: Decimal256::percent(101),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9006] max_fee_rate must be less than or equal to 1")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    let res: RedemptionFeeResponse =
+        from_binary(&query(&deps, QueryMsg::RedemptionFee {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        RedemptionFeeResponse {
+            free_ratio: Decimal256::percent(10),
+            max_fee_rate: Decimal256::percent(1),
+        }
+    );
+
+    // redeeming up to 10% of the liquidity is free
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "redeem_stable"),
+            log("burn_amount", 100000u128),
+            log("redeem_amount", 100000u128),
+            log("fee_amount", 0u128),
+        ]
+    );
+
+    // the burn and the payout have been executed
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(900000u128),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(900000u128),
+        }],
+    );
+
+    // redeeming 55% of the liquidity pays
+    // 1% * (55% - 10%) / (100% - 10%) = 0.5%
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(495000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "redeem_stable"),
+            log("burn_amount", 495000u128),
+            log("redeem_amount", 492525u128),
+            log("fee_amount", 2475u128),
+        ]
+    );
+    assert_eq!(
+        res.messages[1],
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![deduct_tax(
+                &deps,
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(492525u128),
+                }
+            )
+            .unwrap(),]
+        })
+    );
+
+    let res: StateResponse =
+        from_binary(&query(&deps, QueryMsg::State { block_height: None }).unwrap()).unwrap();
+    assert_eq!(res.total_reserves, Decimal256::from_uint256(2475u64));
+}
+
+#[test]
+fn credit_lines() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let msg = HandleMsg::SetCreditLine {
+        strategy: HumanAddr::from("strategy0000"),
+        cap: Uint256::from(500000u64),
+        interest_rate: Decimal256::permille(1),
+        collateral_ratio: Decimal256::percent(20),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let msg = HandleMsg::DrawCredit {
+        amount: Uint256::from(100000u64),
+        to: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1029] No credit line for the strategy")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("strategy0000", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1030] Insufficient credit line collateral; Required collateral value: 20000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // escrow aterra as collateral
+    let post_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("strategy0000"),
+        amount: Uint128::from(30000u128),
+        msg: Some(to_binary(&Cw20HookMsg::PostCreditCollateral {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("addr0000", &[]), post_msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), post_msg).unwrap();
+
+    let res = handle(
+        &mut deps,
+        mock_env("strategy0000", &[]),
+        HandleMsg::DrawCredit {
+            amount: Uint256::from(500001u64),
+            to: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1027] Draw amount exceeds the credit line cap: 500000"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut env = mock_env("strategy0000", &[]);
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("strategy0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000u128),
+            }],
+        })]
+    );
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(900000u128),
+        }],
+    );
+
+    let res: StateResponse =
+        from_binary(&query(&deps, QueryMsg::State { block_height: None }).unwrap()).unwrap();
+    assert_eq!(
+        res.total_credit_liabilities,
+        Decimal256::from_uint256(100000u64)
+    );
+    assert_eq!(
+        res.credit_interest_per_block,
+        Decimal256::from_uint256(100u64)
+    );
+
+    // 10 blocks of 0.1% interest per block
+    env.block.height += 10;
+    let res: CreditLineResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::CreditLine {
+                strategy: HumanAddr::from("strategy0000"),
+                block_height: Some(env.block.height),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        CreditLineResponse {
+            strategy: HumanAddr::from("strategy0000"),
+            cap: Uint256::from(500000u64),
+            interest_rate: Decimal256::permille(1),
+            collateral_ratio: Decimal256::percent(20),
+            loan_amount: Uint256::from(101000u64),
+            collateral_amount: Uint256::from(30000u64),
+            defaulted: false,
+        }
+    );
+
+    // the credit interest is accrued to the depositors
+    let res: EpochStateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochState {
+                block_height: Some(env.block.height),
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.exchange_rate, Decimal256::from_str("1.001").unwrap());
+
+    // repaying more than the loan refunds the rest
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1100000u128),
+        }],
+    );
+    env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(200000u128),
+    }];
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::RepayCredit { strategy: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("strategy0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99000u128),
+            }],
+        })]
+    );
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1001000u128),
+        }],
+    );
+
+    let res: StateResponse =
+        from_binary(&query(&deps, QueryMsg::State { block_height: None }).unwrap()).unwrap();
+    assert_eq!(res.total_credit_liabilities, Decimal256::zero());
+    assert_eq!(res.credit_interest_per_block, Decimal256::zero());
+
+    // draw again and default with the collateral covering part of the loan
+    env.message.sent_funds = vec![];
+    let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(901000u128),
+        }],
+    );
+
+    let mut owner_env = env.clone();
+    owner_env.message.sender = HumanAddr::from("owner");
+    let res = handle(
+        &mut deps,
+        owner_env,
+        HandleMsg::DefaultCreditLine {
+            strategy: HumanAddr::from("strategy0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("AT-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Burn {
+                amount: Uint128::from(30000u128),
+            })
+            .unwrap()
+        })]
+    );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "default_credit_line"),
+            log("strategy", "strategy0000"),
+            log("seized_amount", 30000u128),
+            log("written_off", 69970u128),
+        ]
+    );
+
+    let res = handle(&mut deps, env, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1026] Credit line is defaulted")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn credit_line_time_accrual() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let msg = HandleMsg::SetCreditLine {
+        strategy: HumanAddr::from("strategy0000"),
+        cap: Uint256::from(500000u64),
+        interest_rate: Decimal256::permille(1),
+        collateral_ratio: Decimal256::percent(20),
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let post_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("strategy0000"),
+        amount: Uint128::from(30000u128),
+        msg: Some(to_binary(&Cw20HookMsg::PostCreditCollateral {}).unwrap()),
+    });
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), post_msg).unwrap();
+
+    let mut env = mock_env("strategy0000", &[]);
+    let msg = HandleMsg::DrawCredit {
+        amount: Uint256::from(100000u64),
+        to: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(900000u128),
+        }],
+    );
+
+    let msg = HandleMsg::EnableTimeAccrual {
+        seconds_per_block: 5u64,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    // 10 blocks of 10 seconds are 20 periods of 0.1% interest
+    env.block.height += 10;
+    env.block.time += 100;
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(902000u128),
+        }],
+    );
+    env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(2000u128),
+    }];
+    let _res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::RepayCredit { strategy: None },
+    )
+    .unwrap();
+
+    // the line and the aggregate accrued the same interest
+    let res: CreditLineResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::CreditLine {
+                strategy: HumanAddr::from("strategy0000"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::from(100000u64));
+
+    let res: StateResponse =
+        from_binary(&query(&deps, QueryMsg::State { block_height: None }).unwrap()).unwrap();
+    assert_eq!(
+        res.total_credit_liabilities,
+        Decimal256::from_uint256(100000u64)
+    );
+}
+
+#[test]
+fn flash_mint() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::UpdateFlashMintFee {
+        fee_rate: Decimal256::percent(1),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let msg = HandleMsg::FlashMint {
+        amount: Uint256::zero(),
+        msg: to_binary("callback").unwrap(),
+    };
+    let res = handle(&mut deps, mock_env("minter0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1054] Flash mint amount must be greater than 0")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::FlashMint {
+        amount: Uint256::from(1000000u64),
+        msg: to_binary("callback").unwrap(),
+    };
+    let res = handle(&mut deps, mock_env("minter0000", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("AT-uusd"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Mint {
+                    recipient: HumanAddr::from("minter0000"),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("minter0000"),
+                send: vec![],
+                msg: to_binary("callback").unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&HandleMsg::FinishFlashMint {}).unwrap(),
+            }),
+        ]
+    );
+
+    // flash mints cannot be nested
+    let res = handle(&mut deps, mock_env("minter0001", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1055] A flash mint is already in progress")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: FlashMintResponse =
+        from_binary(&query(&deps, QueryMsg::FlashMint {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FlashMintResponse {
+            fee_rate: Decimal256::percent(1),
+            minter: Some(HumanAddr::from("minter0000")),
+            amount: Uint256::from(1000000u64),
+            fee: Uint256::from(10000u64),
+            repaid: Uint256::zero(),
+        }
+    );
+
+    // the flash minted supply does not move the exchange rate
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[
+            (
+                &HumanAddr::from(MOCK_CONTRACT_ADDR),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&HumanAddr::from("minter0000"), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    let res: EpochStateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochState {
+                block_height: None,
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.exchange_rate, Decimal256::one());
+    assert_eq!(res.aterra_supply, Uint256::from(INITIAL_DEPOSIT_AMOUNT));
+
+    let res = handle(
+        &mut deps,
+        mock_env("minter0000", &[]),
+        HandleMsg::FinishFlashMint {},
+    );
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env(MOCK_CONTRACT_ADDR, &[]),
+        HandleMsg::FinishFlashMint {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1058] Flash minted aterra not repaid; required: 1010000, repaid: 0"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("minter0000"),
+        amount: Uint128::from(1010001u128),
+        msg: Some(to_binary(&Cw20HookMsg::RepayFlashMint {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("CT-uusd", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1057] Cannot repay more than the flash minted amount plus fee: 1010000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("minter0000"),
+        amount: Uint128::from(1010000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RepayFlashMint {}).unwrap()),
+    });
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+
+    let res = handle(
+        &mut deps,
+        mock_env(MOCK_CONTRACT_ADDR, &[]),
+        HandleMsg::FinishFlashMint {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("AT-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Burn {
+                amount: Uint128::from(1010000u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let res: FlashMintResponse =
+        from_binary(&query(&deps, QueryMsg::FlashMint {}).unwrap()).unwrap();
+    assert_eq!(res.minter, None);
+}
+
+#[test]
+fn idle_deployment() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::DeployIdleStable {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1062] Idle deployment is not registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateIdleDeployment {
+        venue: HumanAddr::from("venue0000"),
+        utilization_threshold: Decimal256::percent(50),
+        max_deploy_ratio: Decimal256::percent(50),
+        cap: Uint256::from(400000u64),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdateIdleDeployment {
+            venue: HumanAddr::from("venue0000"),
+            utilization_threshold: Decimal256::percent(101),
+            max_deploy_ratio: Decimal256::percent(50),
+            cap: Uint256::from(400000u64),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E9006] utilization_threshold must be less than or equal to 1"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    // half of the liquidity is 500000, capped at 400000
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::DeployIdleStable {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("venue0000"),
+            send: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(400000u128),
+            }],
+            msg: to_binary(&VenueHandleMsg::Deposit {}).unwrap(),
+        })]
+    );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deploy_idle_stable"),
+            log("deploy_amount", 400000u128),
+            log("deployed_amount", 400000u128),
+            log("utilization", "0"),
+        ]
+    );
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(600000u128),
+        }],
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::DeployIdleStable {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1064] No idle stable to deploy")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the deployed stable still backs the aterra
+    let res: EpochStateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochState {
+                block_height: None,
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.exchange_rate, Decimal256::one());
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdateIdleDeployment {
+            venue: HumanAddr::from("venue0001"),
+            utilization_threshold: Decimal256::percent(50),
+            max_deploy_ratio: Decimal256::percent(50),
+            cap: Uint256::from(400000u64),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1061] Recall the deployed stable before changing the venue"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // only the owner can recall below the threshold
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::RecallIdleStable { amount: None },
+    );
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the redemption recalls the missing 100000 before the payout
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(700000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("venue0000"),
+            send: vec![],
+            msg: to_binary(&VenueHandleMsg::Withdraw {
+                amount: Some(Uint256::from(100000u64)),
+            })
+            .unwrap(),
+        })
+    );
+    assert_eq!(
+        res.messages[2],
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(700000u128),
+            }],
+        })
+    );
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(300000u128),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::zero(),
+        }],
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::RecallIdleStable {
+            amount: Some(Uint256::from(100000u64)),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("venue0000"),
+            send: vec![],
+            msg: to_binary(&VenueHandleMsg::Withdraw {
+                amount: Some(Uint256::from(100000u64)),
+            })
+            .unwrap(),
+        })]
+    );
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+
+    // 300000 borrowed out of 600000 reaches the threshold
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.total_liabilities = Decimal256::from_uint256(300000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let res: IdleDeploymentResponse =
+        from_binary(&query(&deps, QueryMsg::IdleDeployment {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        IdleDeploymentResponse {
+            venue: Some(HumanAddr::from("venue0000")),
+            utilization_threshold: Decimal256::percent(50),
+            max_deploy_ratio: Decimal256::percent(50),
+            cap: Uint256::from(400000u64),
+            deployed_amount: Uint256::from(200000u64),
+            utilization: Decimal256::percent(50),
+        }
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::DeployIdleStable {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1063] Utilization too high to deploy idle stable: 0.5"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::RecallIdleStable { amount: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("venue0000"),
+            send: vec![],
+            msg: to_binary(&VenueHandleMsg::Withdraw { amount: None }).unwrap(),
+        })]
+    );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "recall_idle_stable"),
+            log("recall_amount", 200000u128),
+            log("deployed_amount", 0u128),
+            log("utilization", "0.5"),
+        ]
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::RecallIdleStable { amount: None },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1065] No idle stable deployed"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn idle_deployment_native_balance() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+
+    // the market balance counts 3000000 of CW20 stables
+    // the contract cannot send as native stable
+    store_cw20_stable_state(
+        &mut deps.storage,
+        &deps
+            .api
+            .canonical_address(&HumanAddr::from("cw20usd"))
+            .unwrap(),
+        &Cw20StableState {
+            accept_deposits: true,
+            held_amount: Uint256::from(3000000u64),
+            decimals: 6,
+        },
+    )
+    .unwrap();
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(200000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::UpdateIdleDeployment {
+        venue: HumanAddr::from("venue0000"),
+        utilization_threshold: Decimal256::percent(50),
+        max_deploy_ratio: Decimal256::percent(50),
+        cap: Uint256::from(10000000u64),
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    // half of the 800000 native stable above the reserves
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::DeployIdleStable {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("venue0000"),
+            send: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(400000u128),
+            }],
+            msg: to_binary(&VenueHandleMsg::Deposit {}).unwrap(),
+        })]
+    );
+
+    // the deployed stable is not idle anymore
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(600000u128),
+        }],
+    );
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::DeployIdleStable {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1064] No idle stable to deploy")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // nothing is deployed out of the reserves
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(150000u128),
+        }],
+    );
+    let res = handle(
+        &mut deps,
+        mock_env("keeper0000", &[]),
+        HandleMsg::DeployIdleStable {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1064] No idle stable to deploy")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn tax_params() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let res = query(&deps, QueryMsg::TaxParams {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1093] No tax parameters cached")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // anyone can refresh the cache
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env.clone(), HandleMsg::RefreshTaxParams {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "refresh_tax_params"),
+            log("rate", "0.01"),
+            log("cap", 1000000u128),
+        ]
+    );
+
+    let res: TaxParamsResponse =
+        from_binary(&query(&deps, QueryMsg::TaxParams {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        TaxParamsResponse {
+            rate: Decimal256::percent(1),
+            cap: Uint256::from(1000000u64),
+            updated_height: env.block.height,
+            expires_height: env.block.height + TAX_PARAMS_MAX_AGE,
+        }
+    );
+
+    // payouts use the cached rate until it expires
+    deps.querier.with_tax(
+        Decimal::percent(10),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[1],
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99009u128),
+            }],
+        })
+    );
+
+    let coin = Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(100000u128),
+    };
+    assert_eq!(
+        deduct_tax_cached(&deps, env.block.height + TAX_PARAMS_MAX_AGE, coin.clone()).unwrap(),
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(99009u128),
+        }
+    );
+    assert_eq!(
+        deduct_tax_cached(
+            &deps,
+            env.block.height + TAX_PARAMS_MAX_AGE + 1,
+            coin.clone()
+        )
+        .unwrap(),
+        deduct_tax(&deps, coin).unwrap()
+    );
+}
+
+#[test]
+fn operation_mode() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    // everything is open by default
+    let res: OperationModeResponse =
+        from_binary(&query(&deps, QueryMsg::OperationMode {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        OperationModeResponse {
+            deposits_enabled: true,
+            redemptions_enabled: true,
+            borrows_enabled: true,
+            repayments_enabled: true,
+        }
+    );
+
+    let msg = HandleMsg::UpdateOperationMode {
+        deposits_enabled: None,
+        redemptions_enabled: Some(false),
+        borrows_enabled: None,
+        repayments_enabled: None,
+    };
+    let res = handle(&mut deps, mock_env("owner", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("guardian", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_operation_mode"),
+            log("deposits_enabled", true),
+            log("redemptions_enabled", false),
+            log("borrows_enabled", true),
+            log("repayments_enabled", true),
+        ]
+    );
+
+    let redeem_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), redeem_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1013] Redemptions are disabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // deposits stay open while redemptions are closed
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+        }],
+    );
+    let deposit_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let _res = handle(
+        &mut deps,
+        deposit_env.clone(),
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: None,
+            decimals: None,
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::UpdateOperationMode {
+        deposits_enabled: Some(false),
+        redemptions_enabled: Some(true),
+        borrows_enabled: None,
+        repayments_enabled: None,
+    };
+    let _res = handle(&mut deps, mock_env("guardian", &[]), msg).unwrap();
+
+    let res = handle(
+        &mut deps,
+        deposit_env,
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: None,
+            decimals: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1012] Deposits are disabled"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), redeem_msg).unwrap();
+
+    let res: OperationModeResponse =
+        from_binary(&query(&deps, QueryMsg::OperationMode {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        OperationModeResponse {
+            deposits_enabled: false,
+            redemptions_enabled: true,
+            borrows_enabled: true,
+            repayments_enabled: true,
+        }
+    );
+}
+
+#[test]
+fn rebate_interest() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RebateInterest {
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(1000u64),
+    };
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("overseer", &[]);
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "rebate_interest"),
+            log("borrower", "addr0000"),
+            log("rebate_amount", "1000"),
+        ]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(1499000u128)
+    );
+    assert_eq!(state.total_reserves, Decimal256::from_uint256(2000u128));
+
+    // capped to the reserves
+    let msg = HandleMsg::RebateInterest {
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(5000u64),
+    };
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(res.log[2], log("rebate_amount", "2000"));
+
+    let liability: BorrowerInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BorrowerInfo {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(liability.loan_amount, Uint256::from(497000u64));
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(1497000u128)
+    );
+    assert_eq!(state.total_reserves, Decimal256::zero());
+}
+
+#[test]
+fn prune_closed_positions() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_limit(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(1000000u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(1000000u64)),
+    ]);
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone()).unwrap();
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+
+    // addr0000 closes its position
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(100000u128),
+        }],
+    );
+    let _res = handle(&mut deps, env.clone(), HandleMsg::RepayStable {}).unwrap();
+    let state_before = read_state(&deps.storage).unwrap();
+
+    let msg = HandleMsg::PruneClosedPositions { limit: None };
+    let mut prune_env = mock_env("addr0002", &[]);
+    prune_env.block.height = env.block.height + CLOSED_POSITION_MIN_AGE - 1;
+    let res = handle(&mut deps, prune_env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "prune_closed_positions"),
+            log("scanned", 2),
+            log("pruned", 0),
+        ]
+    );
+
+    prune_env.block.height += 1;
+    let res = handle(&mut deps, prune_env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "prune_closed_positions"),
+            log("scanned", 2),
+            log("pruned", 1),
+        ]
+    );
+
+    let liabilities = read_borrower_infos(&deps, None, None).unwrap();
+    assert_eq!(liabilities.len(), 1);
+    assert_eq!(liabilities[0].borrower, HumanAddr::from("addr0001"));
+    assert_eq!(liabilities[0].loan_amount, Uint256::from(100000u64));
+
+    // aggregate counters are untouched
+    assert_eq!(read_state(&deps.storage).unwrap(), state_before);
+}
+
+#[test]
+fn transfer_liability() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_limit(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(1000000u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(1000000u64)),
+    ]);
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+
+    let msg = HandleMsg::TransferLiability {
+        from: HumanAddr::from("addr0000"),
+        to: HumanAddr::from("addr0002"),
+    };
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("overseer", &[]);
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::TransferLiability {
+            from: HumanAddr::from("addr0000"),
+            to: HumanAddr::from("addr0001"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1022] Cannot transfer a loan to a borrower with a loan"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let state_before = read_state(&deps.storage).unwrap();
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "transfer_liability"),
+            log("from", "addr0000"),
+            log("to", "addr0002"),
+            log("loan_amount", "500000"),
+        ]
+    );
+
+    let liability: BorrowerInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BorrowerInfo {
+                borrower: HumanAddr::from("addr0002"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(liability.loan_amount, Uint256::from(500000u64));
+
+    let liability: BorrowerInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BorrowerInfo {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(liability.loan_amount, Uint256::zero());
+
+    // the total liabilities are unchanged
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, state_before.total_liabilities);
+}
+
+#[test]
+fn repay_stable_for() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+
+    env.block.height += 100;
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+        }],
+    );
+
+    // a third party repays the loan of addr0000
+    let env = mock_env(
+        "insurance",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(100000u128),
+        }],
+    );
+    let msg = HandleMsg::RepayStableFor {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "repay_stable"),
+            log("borrower", "addr0000"),
+            log("repay_amount", "100000"),
+            log("interest_repaid", "0"),
+            log("principal_repaid", "100000"),
+        ]
+    );
+
+    let res_loan = read_borrower_infos(&deps, None, None)
+        .unwrap()
+        .first()
+        .unwrap()
+        .loan_amount;
+    assert_eq!(res_loan, Uint256::from(400000u128));
+
+    // the excess is refunded to the third party that paid it
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 600000u128),
+        }],
+    );
+    let env = mock_env(
+        "insurance",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(500000u128),
+        }],
+    );
+    let msg = HandleMsg::RepayStableFor {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("insurance"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99009u128),
+            }],
+        })]
+    );
+}
+
+#[test]
+fn amortizing_loans() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::zero())]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::OpenLoan {
+        amount: Uint256::from(400000u64),
+        installments: 4,
+        to: None,
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1072] Amortizing loans are not enabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let params_msg = HandleMsg::UpdateLoanParams {
+        period_blocks: 100u64,
+        max_installments: 3,
+        penalty_rate: Decimal256::percent(10),
+    };
+    match handle(&mut deps, env.clone(), params_msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), params_msg).unwrap();
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1074] installments must be between 1 and 3")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let params_msg = HandleMsg::UpdateLoanParams {
+        period_blocks: 100u64,
+        max_installments: 12,
+        penalty_rate: Decimal256::percent(10),
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), params_msg).unwrap();
+
+    let start_height = env.block.height;
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log[0..5].to_vec(),
+        vec![
+            log("action", "open_loan"),
+            log("borrower", "addr0000"),
+            log("principal", "400000"),
+            log("installments", "4"),
+            log("first_due_height", start_height + 100),
+        ]
+    );
+
+    match handle(&mut deps, env.clone(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1075] An amortizing loan is already open")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the first installment is paid before its due date
+    env.block.height = start_height + 50;
+    env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128(100000u128),
+    }];
+    let res = handle(&mut deps, env.clone(), HandleMsg::RepayInstallment {}).unwrap();
+    assert_eq!(
+        res.log[0..5].to_vec(),
+        vec![
+            log("action", "repay_installment"),
+            log("borrower", "addr0000"),
+            log("repaid_amount", "100000"),
+            log("penalty_amount", "0"),
+            log("remaining_principal", "300000"),
+        ]
+    );
+
+    // the second installment is missed, the penalty is
+    // 10% of the overdue principal 200000 - 100000
+    env.block.height = start_height + 250;
+    let mut anyone_env = mock_env("anyone", &[]);
+    anyone_env.block.height = env.block.height;
+    let res = handle(
+        &mut deps,
+        anyone_env.clone(),
+        HandleMsg::ApplyLoanPenalty {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.log[0..4].to_vec(),
+        vec![
+            log("action", "apply_loan_penalty"),
+            log("borrower", "addr0000"),
+            log("penalty_amount", "10000"),
+            log("closed", false),
+        ]
+    );
+
+    // due dates are only charged once
+    let res = handle(
+        &mut deps,
+        anyone_env,
+        HandleMsg::ApplyLoanPenalty {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.log[2], log("penalty_amount", "0"));
+
+    let res: AmortizingLoanResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::AmortizingLoan {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: env.block.height,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        AmortizingLoanResponse {
+            borrower: HumanAddr::from("addr0000"),
+            principal: Uint256::from(400000u64),
+            installments: 4,
+            start_height,
+            period_blocks: 100u64,
+            penalty_rate: Decimal256::percent(10),
+            repaid_amount: Uint256::from(100000u64),
+            penalty_amount: Uint256::from(10000u64),
+            next_due_height: Some(start_height + 300),
+            amount_due: Uint256::from(200000u64),
+        }
+    );
+
+    let res = read_borrower_infos(&deps, None, None).unwrap();
+    assert_eq!(res[0].loan_amount, Uint256::from(310000u64));
+
+    // early payoff of the remaining principal
+    let res = handle(&mut deps, env.clone(), HandleMsg::PayoffLoan {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1076] Payoff amount must cover the remaining principal: 300000"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128(310000u128),
+    }];
+    let res = handle(&mut deps, env.clone(), HandleMsg::PayoffLoan {}).unwrap();
+    assert_eq!(
+        res.log[0..4].to_vec(),
+        vec![
+            log("action", "payoff_loan"),
+            log("borrower", "addr0000"),
+            log("repaid_amount", "300000"),
+            log("penalty_amount", "0"),
+        ]
+    );
+
+    let res = read_borrower_infos(&deps, None, None).unwrap();
+    assert_eq!(res[0].loan_amount, Uint256::zero());
+
+    match query(
+        &deps,
+        QueryMsg::AmortizingLoan {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: env.block.height,
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1077] No amortizing loan is open")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn debt_auction() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "owner",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("owner", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    // the loan left after liquidating all the collaterals
+    let msg = HandleMsg::WriteOffBadDebt {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "write_off_bad_debt"),
+            log("borrower", "addr0000"),
+            log("bad_debt", "500000"),
+            log("covered_amount", "3000"),
+            log("auction_amount", "497000"),
+        ]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(1000000u128)
+    );
+    assert_eq!(state.total_reserves, Decimal256::zero());
+
+    let liability: BorrowerInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BorrowerInfo {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(liability.loan_amount, Uint256::zero());
+
+    // bids wait for the auction to be enabled
+    let bid_msg = HandleMsg::BidDebtAuction {};
+    let mut bidder_env = mock_env(
+        "bidder",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(100000u128),
+        }],
+    );
+    match handle(&mut deps, bidder_env.clone(), bid_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1036] Debt auctions are not enabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateDebtAuctionParams {
+        start_price: Decimal256::percent(200),
+        floor_price: Decimal256::percent(250),
+        price_decay_blocks: 100u64,
+    };
+    match handle(&mut deps, env.clone(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1035] floor_price must be less than or equal to start_price"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateDebtAuctionParams {
+        start_price: Decimal256::percent(200),
+        floor_price: Decimal256::percent(50),
+        price_decay_blocks: 100u64,
+    };
+    match handle(&mut deps, bidder_env.clone(), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    // price = 2 - (2 - 0.5) * 50 / 100 = 1.25
+    bidder_env.block.height += 50;
+    let res: DebtAuctionResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::DebtAuction {
+                block_height: bidder_env.block.height,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        DebtAuctionResponse {
+            debt_amount: Uint256::from(497000u64),
+            start_height: env.block.height,
+            raised_amount: Uint256::zero(),
+            sold_amount: Uint256::zero(),
+            price: Some(Decimal256::percent(125)),
+        }
+    );
+
+    let res = handle(&mut deps, bidder_env.clone(), bid_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("distributor"),
+            send: vec![],
+            msg: to_binary(&FaucetHandleMsg::Spend {
+                recipient: HumanAddr::from("bidder"),
+                amount: Uint128::from(80000u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // the price stops at the floor price,
+    // the stable exceeding the debt is paid back
+    bidder_env.block.height += 100;
+    bidder_env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128(500000u128),
+    }];
+    let res = handle(&mut deps, bidder_env.clone(), bid_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("distributor"),
+                send: vec![],
+                msg: to_binary(&FaucetHandleMsg::Spend {
+                    recipient: HumanAddr::from("bidder"),
+                    amount: Uint128::from(794000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("bidder"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128(103000u128),
+                }],
+            }),
+        ]
+    );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "bid_debt_auction"),
+            log("bidder", "bidder"),
+            log("bid_amount", "397000"),
+            log("anc_amount", "794000"),
+            log("price", "0.5"),
+            log("debt_amount", "0"),
+            log("accrual_borrow_rate", "0.01"),
+            log("accrual_blocks", "100"),
+            log("accrual_interest", "1500000"),
+            log("accrual_reserves", "0"),
+            log("accrual_exchange_rate", "1"),
+        ]
+    );
+
+    // the debt is covered
+    match handle(&mut deps, bidder_env, bid_msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1037] No debt auction is running")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn loan_breakdown() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    // 1% interest per block over 10 blocks
+    env.block.height += 10;
+    let res: LoanBreakdownResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LoanBreakdown {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: Some(env.block.height),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LoanBreakdownResponse {
+            borrower: HumanAddr::from("addr0000"),
+            principal: Uint256::from(100000u64),
+            accrued_interest: Uint256::from(10000u64),
+            principal_repaid: Uint256::zero(),
+            interest_repaid: Uint256::zero(),
+        }
+    );
+
+    let res: StateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::State {
+                block_height: Some(env.block.height),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let total_reserves = res.total_reserves;
+
+    // the interest is repaid first, the reserves took their
+    // cut of it when it accrued
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT - 100000u128 + 30000u128),
+        }],
+    );
+    env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128(30000u128),
+    }];
+    let res = handle(&mut deps, env.clone(), HandleMsg::RepayStable {}).unwrap();
+    assert_eq!(
+        res.log[0..5].to_vec(),
+        vec![
+            log("action", "repay_stable"),
+            log("borrower", "addr0000"),
+            log("repay_amount", "30000"),
+            log("interest_repaid", "10000"),
+            log("principal_repaid", "20000"),
+        ]
+    );
+    assert_eq!(
+        read_state(&deps.storage).unwrap().total_reserves,
+        total_reserves
+    );
+
+    let res: LoanBreakdownResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LoanBreakdown {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LoanBreakdownResponse {
+            borrower: HumanAddr::from("addr0000"),
+            principal: Uint256::from(80000u64),
+            accrued_interest: Uint256::zero(),
+            principal_repaid: Uint256::from(20000u64),
+            interest_repaid: Uint256::from(10000u64),
+        }
+    );
+
+    // the principal moves along with the liability
+    let mut overseer_env = mock_env("overseer", &[]);
+    overseer_env.block.height = env.block.height;
+    let _res = handle(
+        &mut deps,
+        overseer_env,
+        HandleMsg::TransferLiability {
+            from: HumanAddr::from("addr0000"),
+            to: HumanAddr::from("addr0001"),
+        },
+    )
+    .unwrap();
+
+    let res: LoanBreakdownResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LoanBreakdown {
+                borrower: HumanAddr::from("addr0001"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.principal, Uint256::from(80000u64));
+    assert_eq!(res.interest_repaid, Uint256::zero());
+}
+
+#[test]
+fn keeper_bounty() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::zero())]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::from_uint256(1000u64),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let poke_msg = HandleMsg::PokePosition {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let mut keeper_env = mock_env("keeper", &[]);
+    match handle(&mut deps, keeper_env.clone(), poke_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1069] Keeper bounties are not enabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateKeeperBounty {
+        bounty_amount: Uint256::from(100u64),
+        epoch_cap: Uint256::from(150u64),
+        stale_blocks: 100u64,
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    match handle(
+        &mut deps,
+        keeper_env.clone(),
+        HandleMsg::PokePosition {
+            borrower: HumanAddr::from("addr0001"),
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1070] Position has no loan"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    keeper_env.block.height = env.block.height + 50;
+    match handle(&mut deps, keeper_env.clone(), poke_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E1071] Position is not stale until block {}",
+                env.block.height + 100
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    keeper_env.block.height = env.block.height + 100;
+    let res = handle(&mut deps, keeper_env.clone(), poke_msg.clone()).unwrap();
+    assert_eq!(
+        res.log[0..4].to_vec(),
+        vec![
+            log("action", "poke_position"),
+            log("borrower", "addr0000"),
+            log("keeper", "keeper"),
+            log("bounty_amount", "100"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("keeper"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100u128),
+            }],
+        })]
+    );
+    assert_eq!(
+        read_state(&deps.storage).unwrap().total_reserves,
+        Decimal256::from_uint256(900u64)
+    );
+
+    // the position was refreshed by the poke
+    match handle(&mut deps, keeper_env.clone(), poke_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E1071] Position is not stale until block {}",
+                env.block.height + 200
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the bounties are capped per epoch
+    keeper_env.block.height = env.block.height + 200;
+    let res = handle(&mut deps, keeper_env.clone(), poke_msg.clone()).unwrap();
+    assert_eq!(res.log[3], log("bounty_amount", "50"));
+
+    keeper_env.block.height = env.block.height + 300;
+    let res = handle(&mut deps, keeper_env.clone(), poke_msg.clone()).unwrap();
+    assert_eq!(res.log[3], log("bounty_amount", "0"));
+    assert_eq!(res.messages, vec![]);
+
+    let res: KeeperBountyResponse =
+        from_binary(&query(&deps, QueryMsg::KeeperBounty {}).unwrap()).unwrap();
+    assert_eq!(res.paid_amount, Uint256::from(150u64));
+
+    // the epoch operations open the cap again
+    let mut overseer_env = mock_env("overseer", &[]);
+    overseer_env.block.height = keeper_env.block.height;
+    let _res = handle(
+        &mut deps,
+        overseer_env,
+        HandleMsg::ExecuteEpochOperations {
+            deposit_rate: Decimal256::one(),
+            target_deposit_rate: Decimal256::one(),
+            threshold_deposit_rate: Decimal256::one(),
+            distributed_interest: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    let res: KeeperBountyResponse =
+        from_binary(&query(&deps, QueryMsg::KeeperBounty {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        KeeperBountyResponse {
+            bounty_amount: Uint256::from(100u64),
+            epoch_cap: Uint256::from(150u64),
+            stale_blocks: 100u64,
+            paid_amount: Uint256::zero(),
+        }
+    );
+}
+
+#[test]
+fn aterra_exchange_rate() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(1100000u128),
+        )],
+    )]);
+
+    let mut state = State {
+        total_liabilities: Decimal256::from_uint256(100000u64),
+        total_reserves: Decimal256::zero(),
+        last_interest_updated: env.block.height,
+        last_reward_updated: env.block.height,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        anc_emission_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::from(1100000u64),
+        prev_exchange_rate: Decimal256::one(),
+        total_credit_liabilities: Decimal256::zero(),
+        credit_interest_per_block: Decimal256::zero(),
+        utilization_clamps: 0,
+        accrual_clock: None,
+        forgiven_dust: Uint256::zero(),
+        last_borrow_rate: Decimal256::zero(),
+        accrued_periods: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        stable_interest_per_block: Decimal256::zero(),
+    };
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::UpdateExchangeRateOracle {
+        oracle: HumanAddr::from("rate_oracle"),
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res: ATerraExchangeRateResponse =
+        from_binary(&query(&deps, QueryMsg::ATerraExchangeRate { block_height: None }).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        ATerraExchangeRateResponse {
+            exchange_rate: Decimal256::one(),
+            last_updated: env.block.height,
+            monotonic: true,
+            oracle: Some(HumanAddr::from("rate_oracle")),
+            twap: Decimal256::one(),
+            twap_window: 600,
+        }
+    );
+
+    // every accrual pushes the exchange rate to the oracle
+    env.block.height += 10;
+    let res: ATerraExchangeRateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ATerraExchangeRate {
+                block_height: Some(env.block.height),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let exchange_rate = res.exchange_rate;
+    assert!(exchange_rate > Decimal256::one());
+    assert!(res.monotonic);
+
+    let borrow_msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(1000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let res = handle(&mut deps, env.clone(), borrow_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages.last(),
+        Some(&CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("rate_oracle"),
+            send: vec![],
+            msg: to_binary(&ExchangeRateOracleHandleMsg::FeedExchangeRate {
+                exchange_rate,
+                block_height: env.block.height,
+            })
+            .unwrap(),
+        }))
+    );
+
+    // accrued once per block
+    let res = handle(&mut deps, env.clone(), borrow_msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 1);
+
+    // a loss shows at the next accrual
+    state = read_state(&deps.storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(200000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    env.block.height += 10;
+    let res = handle(&mut deps, env.clone(), borrow_msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 2);
+
+    let res: ATerraExchangeRateResponse =
+        from_binary(&query(&deps, QueryMsg::ATerraExchangeRate { block_height: None }).unwrap())
+            .unwrap();
+    assert!(res.exchange_rate < exchange_rate);
+    assert_eq!(res.last_updated, env.block.height);
+    assert!(!res.monotonic);
+
+    let msg = HandleMsg::DisableExchangeRateOracle {};
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg.clone()).unwrap();
+    match handle(&mut deps, mock_env("owner", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1047] No exchange rate oracle is registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.height += 10;
+    let res = handle(&mut deps, env.clone(), borrow_msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
+
+#[test]
+fn epoch_operations_once_per_height() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::one(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+
+    let mut env = mock_env("overseer", &[]);
+    env.block.height += 100;
+    let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.last_interest_updated, env.block.height);
+
+    // executing twice in the same block is rejected
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E9003] Epoch operations already executed; last executed height: {}",
+                env.block.height
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // and so is an execution delivered out of order
+    env.block.height -= 50;
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E9003] Epoch operations already executed; last executed height: {}",
+                env.block.height + 50
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    assert_eq!(read_state(&deps.storage).unwrap(), state);
+
+    // accruals at the same or an older height leave the state as is
+    let borrow_msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(1000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+    env.message.sender = HumanAddr::from("addr0000");
+    let _res = handle(&mut deps, env.clone(), borrow_msg).unwrap();
+    let borrowed_state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        borrowed_state.total_liabilities,
+        state.total_liabilities + Decimal256::from_uint256(1000u64)
+    );
+    assert_eq!(
+        borrowed_state.global_interest_index,
+        state.global_interest_index
+    );
+    assert_eq!(
+        borrowed_state.global_reward_index,
+        state.global_reward_index
+    );
+    assert_eq!(
+        borrowed_state.last_interest_updated,
+        state.last_interest_updated
+    );
+
+    // the next block executes again
+    env.message.sender = HumanAddr::from("overseer");
+    env.block.height += 51;
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.last_interest_updated, env.block.height);
+    assert_eq!(state.last_reward_updated, env.block.height);
+}
+
+#[test]
+fn protocol_mode() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0002"), &Uint256::from(1000000u64))]);
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, mock_env("addr0002", &[]), msg).unwrap();
+
+    // nothing to refresh without a protocol controller
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::RefreshProtocolMode {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9010] No protocol controller is registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::RegisterProtocolController {
+        protocol_controller: HumanAddr::from("controller"),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "register_protocol_controller"),
+            log("protocol_controller", "controller"),
+            log("mode", "normal"),
+        ]
+    );
+
+    // the cached mode is used until it is refreshed
+    deps.querier
+        .with_protocol_mode(ProtocolMode::ConservativeWithdrawOnly);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+        }],
+    );
+    let deposit_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let _res = handle(
+        &mut deps,
+        deposit_env.clone(),
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: None,
+            decimals: None,
+        },
+    )
+    .unwrap();
+
+    let res = handle(
+        &mut deps,
+        mock_env("controller", &[]),
+        HandleMsg::RefreshProtocolMode {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "refresh_protocol_mode"),
+            log("mode", "conservative_withdraw_only"),
+        ]
+    );
+
+    // deposits and borrows are rejected, redemptions stay open
+    let res = handle(
+        &mut deps,
+        deposit_env,
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: None,
+            decimals: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9008] Deposits are not allowed in conservative_withdraw_only mode"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9018] Borrows are not allowed in conservative_withdraw_only mode"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::DrawCredit {
+        amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let res = handle(&mut deps, mock_env("strategy", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9018] Borrows are not allowed in conservative_withdraw_only mode"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::FlashMint {
+        amount: Uint256::from(500000u64),
+        msg: to_binary("callback").unwrap(),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9018] Borrows are not allowed in conservative_withdraw_only mode"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let msg = HandleMsg::FlashLoan {
+        amount: Uint256::from(500000u64),
+        msg: to_binary("callback").unwrap(),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9018] Borrows are not allowed in conservative_withdraw_only mode"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let redeem_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), redeem_msg.clone()).unwrap();
+
+    // the controller is queried again once the cached mode expires
+    deps.querier.with_protocol_mode(ProtocolMode::FullPause);
+    let mut env = mock_env("AT-uusd", &[]);
+    env.block.height += PROTOCOL_MODE_MAX_AGE;
+    let _res = handle(&mut deps, env.clone(), redeem_msg.clone()).unwrap();
+
+    env.block.height += 1;
+    let res = handle(&mut deps, env.clone(), redeem_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E9009] Withdrawals are not allowed in full_pause mode"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the loans can still be repaid
+    env.message.sender = HumanAddr::from("addr0002");
+    env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(50000u128),
+    }];
+    let res = handle(&mut deps, env, HandleMsg::RepayStable {}).unwrap();
+    assert!(res.log.contains(&log("repay_amount", 50000u128)));
+}
+
+#[test]
+fn tax_exempt_stable_denom() {
+    let ibc_denom = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: ibc_denom.to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(Decimal::percent(1), &[]);
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: ibc_denom.to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: ibc_denom.to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-ibc", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-ibc"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    // the treasury is not queried for a tax exempt denom
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::RefreshTaxParams {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "refresh_tax_params"),
+            log("rate", "0"),
+            log("cap", 0u128),
+        ]
+    );
+
+    // redemptions are paid in full
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-ibc", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[1],
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: ibc_denom.to_string(),
+                amount: Uint128::from(100000u128),
+            }],
+        })
+    );
+}
+
+#[test]
+fn stable_denoms() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let initial_deposit = [Coin {
+        denom: "ukrw".to_string(),
+        amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+    }];
+    let msg = HandleMsg::AddStableDenom {
+        stable_denom: "ukrw".to_string(),
+        aterra_code_id: 124u64,
+    };
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &initial_deposit),
+        msg.clone(),
+    );
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1023] Must deposit initial funds 1000000\"ukrw\"")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &initial_deposit),
+        HandleMsg::AddStableDenom {
+            stable_denom: "uusd".to_string(),
+            aterra_code_id: 124u64,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1087] Stable denom already accepted: uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &initial_deposit), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: 124u64,
+            send: vec![],
+            label: None,
+            msg: to_binary(&TokenInitMsg {
+                name: "Anchor Terra KRW".to_string(),
+                symbol: "aKRT".to_string(),
+                decimals: 6u8,
+                initial_balances: vec![Cw20CoinHuman {
+                    address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+                }],
+                mint: Some(MinterResponse {
+                    minter: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    cap: None,
+                }),
+                init_hook: Some(InitHook {
+                    contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    msg: to_binary(&HandleMsg::RegisterStableDenomATerra {
+                        stable_denom: "ukrw".to_string(),
+                    })
+                    .unwrap(),
+                })
+            })
+            .unwrap(),
+        })]
+    );
+
+    let msg = HandleMsg::RegisterStableDenomATerra {
+        stable_denom: "ukrw".to_string(),
+    };
+    let _res = handle(&mut deps, mock_env("AT-ukrw", &[]), msg.clone()).unwrap();
+
+    // Cannot register again
+    let _res = handle(&mut deps, mock_env("AT-ukrw", &[]), msg).unwrap_err();
+
+    // ukrw deposits mint aKRT at the ukrw exchange rate
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 2000000u128),
+            },
+        ],
+    );
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "ukrw".to_string(),
+            amount: Uint128::from(2000000u128),
+        }],
+    );
+    let res = handle(
+        &mut deps,
+        env,
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: None,
+            decimals: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("AT-ukrw"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(2000000u128),
+            })
+            .unwrap(),
+        })
+    );
+
+    // the ukrw balance grew by 10%
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(3300000u128),
+            },
+        ],
+    );
+
+    let res = query(&deps, QueryMsg::StableDenoms {}).unwrap();
+    let stable_denoms_res: StableDenomsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        stable_denoms_res.stable_denoms,
+        vec![StableDenomResponse {
+            stable_denom: "ukrw".to_string(),
+            aterra_contract: HumanAddr::from("AT-ukrw"),
+            aterra_supply: Uint256::from(3000000u64),
+            exchange_rate: Decimal256::percent(110),
+        }]
+    );
+
+    // only the registered aterra tokens can redeem
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uluna", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let res = handle(&mut deps, mock_env("AT-ukrw", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("AT-ukrw"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Burn {
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![deduct_tax(
+                    &deps,
+                    Coin {
+                        denom: "ukrw".to_string(),
+                        amount: Uint128::from(1100000u128),
+                    }
+                )
+                .unwrap()],
+            }),
+        ]
+    );
+}
+
+#[test]
+fn cw20_stables() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    // exchange rate of 2
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(2 * INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let deposit_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(to_binary(&Cw20HookMsg::DepositStable {}).unwrap()),
+    });
+
+    // Only accepted cw20 stables can deposit
+    let res = handle(&mut deps, mock_env("usdc", &[]), deposit_msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let msg = HandleMsg::UpdateCw20Stable {
+        token: HumanAddr::from("usdc"),
+        accept_deposits: true,
+        decimals: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_cw20_stable"),
+            log("token", "usdc"),
+            log("accept_deposits", true),
+            log("decimals", 6),
+        ]
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("usdc", &[]),
+        HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("addr0000"),
+            amount: Uint128::zero(),
+            msg: Some(to_binary(&Cw20HookMsg::DepositStable {}).unwrap()),
+        }),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1001] Deposit amount must be greater than 0 usdc")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // Minted at the aterra exchange rate like native deposits
+    let res = handle(&mut deps, mock_env("usdc", &[]), deposit_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("AT-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(500000u128),
+            })
+            .unwrap(),
+        })
+    );
+    assert_eq!(
+        &res.log[..5],
+        &[
+            log("action", "deposit_stable"),
+            log("depositor", "addr0000"),
+            log("token", "usdc"),
+            log("mint_amount", 500000),
+            log("deposit_amount", 1000000),
+        ]
+    );
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT + 500000),
+        )],
+    )]);
+
+    let res = query(
+        &deps,
+        QueryMsg::DepositorInfo {
+            depositor: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let depositor_info: DepositorInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(depositor_info.total_deposited, Uint256::from(1000000u64));
+
+    // The held cw20 stables keep the exchange rate
+    let res = query(&deps, QueryMsg::Cw20Stables {}).unwrap();
+    let cw20_stables_res: Cw20StablesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        cw20_stables_res.cw20_stables,
+        vec![Cw20StableResponse {
+            token: HumanAddr::from("usdc"),
+            accept_deposits: true,
+            decimals: 6,
+            held_amount: Uint256::from(1000000u64),
+        }]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::EpochState {
+            block_height: None,
+            distributed_interest: None,
+        },
+    )
+    .unwrap();
+    let epoch_state: EpochStateResponse = from_binary(&res).unwrap();
+    assert_eq!(epoch_state.exchange_rate, Decimal256::from_uint256(2u64));
+
+    // Held cw20 stables are sold at par for uusd
+    let res = handle(
+        &mut deps,
+        mock_env("addr0001", &[]),
+        HandleMsg::SwapCw20Stable {
+            token: HumanAddr::from("usdc"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1033] Swap amount must be greater than 0 uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env(
+            "addr0001",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000001u128),
+            }],
+        ),
+        HandleMsg::SwapCw20Stable {
+            token: HumanAddr::from("usdc"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1034] Not enough usdc held: 1000000")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env(
+            "addr0001",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(400000u128),
+            }],
+        ),
+        HandleMsg::SwapCw20Stable {
+            token: HumanAddr::from("usdc"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("usdc"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0001"),
+                amount: Uint128::from(400000u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // Stopping deposits keeps the held amount
+    let msg = HandleMsg::UpdateCw20Stable {
+        token: HumanAddr::from("usdc"),
+        accept_deposits: false,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res = query(&deps, QueryMsg::Cw20Stables {}).unwrap();
+    let cw20_stables_res: Cw20StablesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        cw20_stables_res.cw20_stables,
+        vec![Cw20StableResponse {
+            token: HumanAddr::from("usdc"),
+            accept_deposits: false,
+            decimals: 6,
+            held_amount: Uint256::from(600000u64),
+        }]
+    );
+
+    let res = handle(&mut deps, mock_env("usdc", &[]), deposit_msg);
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+}
+
+#[test]
+fn bootstrap_mode() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let msg = HandleMsg::UpdateBootstrapMode {
+        end_height: Some(env.block.height + 100),
+        tvl_threshold: Some(Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128)),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdateBootstrapMode {
+            end_height: None,
+            tvl_threshold: Some(Uint256::from(INITIAL_DEPOSIT_AMOUNT)),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1019] The bootstrap thresholds are already reached")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let borrow_msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), borrow_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1021] Borrows are disabled until the market bootstrap ends"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // deposits are accepted while bootstrapping
+    let deposit_msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
+    let deposit_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(500000u128),
+        }],
+    );
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 500000u128),
+        }],
+    );
+    let res = handle(&mut deps, deposit_env.clone(), deposit_msg.clone()).unwrap();
+    assert!(res.log.iter().all(|v| v.key != "bootstrap_ended"));
+
+    let res: BootstrapModeResponse =
+        from_binary(&query(&deps, QueryMsg::BootstrapMode {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        BootstrapModeResponse {
+            active: true,
+            end_height: Some(env.block.height + 100),
+            tvl_threshold: Some(Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128)),
+            tvl: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 500000u128),
+        }
+    );
+
+    // the deposit reaching the TVL threshold ends the bootstrap
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+        }],
+    );
+    let res = handle(&mut deps, deposit_env, deposit_msg).unwrap();
+    assert!(res.log.contains(&log("bootstrap_ended", "threshold")));
+
+    let res: BootstrapModeResponse =
+        from_binary(&query(&deps, QueryMsg::BootstrapMode {}).unwrap()).unwrap();
+    assert!(!res.active);
+
+    let res = handle(&mut deps, mock_env("addr0000", &[]), borrow_msg.clone()).unwrap();
+    assert!(res.log.iter().all(|v| v.key != "bootstrap_ended"));
+
+    // the first borrow at the end height ends the bootstrap
+    let msg = HandleMsg::UpdateBootstrapMode {
+        end_height: Some(env.block.height + 10),
+        tvl_threshold: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let mut borrow_env = mock_env("addr0000", &[]);
+    borrow_env.block.height += 9;
+    let res = handle(&mut deps, borrow_env.clone(), borrow_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1021] Borrows are disabled until the market bootstrap ends"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    borrow_env.block.height += 1;
+    let res = handle(&mut deps, borrow_env, borrow_msg).unwrap();
+    assert!(res.log.contains(&log("bootstrap_ended", "threshold")));
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::EndBootstrapMode {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1020] The market is not bootstrapping")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn fee_exempt_redeemer() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::UpdateRedemptionFee {
+        free_ratio: Decimal256::percent(10),
+        max_fee_rate: Decimal256::percent(1),
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let msg = HandleMsg::UpdateFeeExemptRedeemer {
+        redeemer: HumanAddr::from("liquidation"),
+        exempt: true,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_fee_exempt_redeemer"),
+            log("redeemer", "liquidation"),
+            log("exempt", true),
+        ]
+    );
+
+    let res: FeeExemptRedeemersResponse =
+        from_binary(&query(&deps, QueryMsg::FeeExemptRedeemers {}).unwrap()).unwrap();
+    assert_eq!(res.redeemers, vec![HumanAddr::from("liquidation")]);
+
+    // the exempt protocol contract redeems 55% of the liquidity for free
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("liquidation"),
+        amount: Uint128::from(550000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert!(res.log.contains(&log("redeem_amount", 550000u128)));
+    assert!(res.log.contains(&log("fee_amount", 0u128)));
+
+    // the burn and the payout have been executed
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(450000u128),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(450000u128),
+        }],
+    );
+
+    // others still pay 1% * (55% - 10%) / (100% - 10%) = 0.5%
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(247500u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert!(res.log.contains(&log("fee_amount", 1237u128)));
+
+    let msg = HandleMsg::UpdateFeeExemptRedeemer {
+        redeemer: HumanAddr::from("liquidation"),
+        exempt: false,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res: FeeExemptRedeemersResponse =
+        from_binary(&query(&deps, QueryMsg::FeeExemptRedeemers {}).unwrap()).unwrap();
+    assert_eq!(res.redeemers, Vec::<HumanAddr>::new());
+}
+
+#[test]
+fn redeem_stable_exact() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    // exchange_rate = (1000000 + 300000) / 1000000 = 1.3
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    state.total_liabilities = Decimal256::from_uint256(300000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::RedeemStableExact {
+        stable_amount: Uint256::zero(),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1003] Redeem amount must be greater than 0")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // 100000 / 1.3 = 76923.07 is rounded up
+    let msg = HandleMsg::RedeemStableExact {
+        stable_amount: Uint256::from(100000u64),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "redeem_stable_exact"),
+            log("burn_amount", 76924u128),
+            log("redeem_amount", 100000u128),
+            log("fee_amount", 0u128),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("AT-uusd"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::BurnFrom {
+                    owner: HumanAddr::from("addr0000"),
+                    amount: Uint128::from(76924u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }],
+            }),
+        ]
+    );
+
+    let res: DepositorInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::DepositorInfo {
+                depositor: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.total_redeemed, Uint256::from(100000u64));
+}
+
+#[test]
+fn simulate_deposit_and_redeem() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    // exchange rate of 2
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+            prev_exchange_rate: Decimal256::from_uint256(2u64),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::SimulateDeposit {
+            amount: Uint256::from(1000000u64),
+            block_height: None,
+        },
+    )
+    .unwrap();
+    let simulation: SimulateDepositResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        simulation,
+        SimulateDepositResponse {
+            mint_amount: Uint256::from(500000u64),
+            tax_amount: Uint256::from(10000u64),
+            exchange_rate: Decimal256::from_uint256(2u64),
+        }
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::SimulateRedeem {
+            burn_amount: Uint256::from(100000u64),
+            block_height: None,
+        },
+    )
+    .unwrap();
+    let simulation: SimulateRedeemResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        simulation,
+        SimulateRedeemResponse {
+            redeem_amount: Uint256::from(198019u64),
+            fee_amount: Uint256::zero(),
+            tax_amount: Uint256::from(1981u64),
+            exchange_rate: Decimal256::from_uint256(2u64),
+        }
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::SimulateRedeem {
+            burn_amount: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1),
+            block_height: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1043] Burn amount exceeds the aterra supply: 1000000"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        &deps,
+        QueryMsg::SimulateDeposit {
+            amount: Uint256::from(1000000u64),
+            block_height: Some(env.block.height - 1),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1024] block_height must bigger than last_interest_updated"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the simulations accrue interest like the handlers
+    env.block.height += 100;
+    let res = query(
+        &deps,
+        QueryMsg::SimulateDeposit {
+            amount: Uint256::from(1000000u64),
+            block_height: Some(env.block.height),
+        },
+    )
+    .unwrap();
+    let deposit_simulation: SimulateDepositResponse = from_binary(&res).unwrap();
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+        }],
+    );
+    env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1000000u128),
+    }];
+    let msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log[3],
+        log("mint_amount", deposit_simulation.mint_amount)
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::SimulateRedeem {
+            burn_amount: Uint256::from(100000u64),
+            block_height: Some(env.block.height),
+        },
+    )
+    .unwrap();
+    let redeem_simulation: SimulateRedeemResponse = from_binary(&res).unwrap();
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    env.message.sender = HumanAddr::from("AT-uusd");
+    env.message.sent_funds = vec![];
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log[2],
+        log(
+            "redeem_amount",
+            redeem_simulation.redeem_amount + redeem_simulation.tax_amount
+        )
+    );
+}
+
+#[test]
+fn invariant_checks() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let res: InvariantChecksResponse =
+        from_binary(&query(&deps, QueryMsg::InvariantChecks {}).unwrap()).unwrap();
+    assert!(!res.enabled);
+
+    let msg = HandleMsg::UpdateInvariantChecks { enabled: true };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    let res: InvariantChecksResponse =
+        from_binary(&query(&deps, QueryMsg::InvariantChecks {}).unwrap()).unwrap();
+    assert!(res.enabled);
+
+    let deposit_msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
+    let deposit_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+        }],
+    );
+    let _res = handle(&mut deps, deposit_env, deposit_msg).unwrap();
+
+    // reserves beyond the cash and the lent out stable abort the tx
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(INITIAL_DEPOSIT_AMOUNT + 1000001u128);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let fee_msg = HandleMsg::UpdateRedemptionFee {
+        free_ratio: Decimal256::percent(10),
+        max_fee_rate: Decimal256::percent(1),
+    };
+    let res = handle(&mut deps, mock_env("owner", &[]), fee_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1067] Invariant violated: reserves 2000001 exceed the implied cash 2000000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the owner can still turn the checks off
+    let msg = HandleMsg::UpdateInvariantChecks { enabled: false };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), fee_msg).unwrap();
+}
+
+#[test]
+fn borrow_stable_deposit() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    // exchange rate of 2
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+            prev_exchange_rate: Decimal256::from_uint256(2u64),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    // the borrowed stable stays in the market and
+    // is deposited at the exchange rate of 2
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: Some(HumanAddr::from("addr0001")),
+        deposit: Some(true),
+        decimals: None,
+    };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log[..5].to_vec(),
+        vec![
+            log("action", "borrow_stable"),
+            log("borrower", "addr0000"),
+            log("borrow_amount", "500000"),
+            log("recipient", "addr0001"),
+            log("mint_amount", "250000"),
+        ]
+    );
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("AT-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0001"),
+                amount: Uint128::from(250000u128),
+            })
+            .unwrap(),
+        })
+    );
+
+    let state: State =
+        from_binary(&query(&deps, QueryMsg::State { block_height: None }).unwrap()).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(1500000u128)
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::BorrowerInfo {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: None,
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::from(500000u64));
+}
+
+#[test]
+fn min_deposit_and_redeem_amounts() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: Some(Uint256::from(100000u64)),
+        min_redeem_amount: Some(Uint256::from(50000u64)),
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config_res.min_deposit_amount,
+        Some(Uint256::from(100000u64))
+    );
+    assert_eq!(config_res.min_redeem_amount, Some(Uint256::from(50000u64)));
+
+    let deposit_msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(99999u128),
+        }],
+    );
+    let res = handle(&mut deps, env, deposit_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1002] Deposit amount must be at least 100000uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let _res = handle(&mut deps, env, deposit_msg).unwrap();
+
+    let redeem_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(49999u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), redeem_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1004] Redeem amount must be at least 50000uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // zero removes the minimum
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: Some(Uint256::zero()),
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config_res.min_deposit_amount,
+        Some(Uint256::from(100000u64))
+    );
+    assert_eq!(config_res.min_redeem_amount, None);
+
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), redeem_msg).unwrap();
+    assert!(res.log.contains(&log("burn_amount", 49999u128)));
+}
+
+#[test]
+fn redemption_queue() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    // exchange_rate = (400000 + 600000) / 1000000 = 1
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(400000u128),
+        }],
+    );
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    state.total_liabilities = Decimal256::from_uint256(600000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    // enough liquidity, redeemed right away
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStableOrQueue {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert!(res.log.contains(&log("action", "redeem_stable")));
+    assert!(res.log.contains(&log("redeem_amount", 100000u128)));
+
+    // the burn and the payout have been executed
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(900000u128),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(300000u128),
+        }],
+    );
+
+    // not enough liquidity, the aterra is escrowed in the queue
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(500000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStableOrQueue {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "queue_redemption"),
+            log("redemption_id", 0),
+            log("redeemer", "addr0001"),
+            log("aterra_amount", 500000u128),
+        ]
+    );
+
+    // later redemptions wait behind the queued ones
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0002"),
+        amount: Uint128::from(10000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStableOrQueue {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert!(res.log.contains(&log("redemption_id", 1)));
+
+    let res: QueuedRedemptionsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::QueuedRedemptions {
+                start_after: Some(0),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.redemptions,
+        vec![QueuedRedemptionResponse {
+            id: 1,
+            redeemer: HumanAddr::from("addr0002"),
+            aterra_amount: Uint256::from(10000u64),
+            queued_height: 12345,
+        }]
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0002", &[]),
+        HandleMsg::ClaimRedemption { id: 1 },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1080] Only the redemption at the head of the queue can be claimed"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0002", &[]),
+        HandleMsg::ClaimRedemption { id: 0 },
+    );
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0001", &[]),
+        HandleMsg::ClaimRedemption { id: 0 },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1081] Not enough liquidity to service the redemption"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a deposit brings the liquidity to service both in order
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(600000u128),
+        }],
+    );
+    let msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
+    let env = mock_env(
+        "addr0003",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(300000u128),
+        }],
+    );
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert!(res.log.contains(&log("mint_amount", 300000u128)));
+    assert!(res.log.contains(&log("serviced_redemptions", 2)));
+    assert!(res.log.contains(&log("serviced_burn_amount", 510000u128)));
+    assert!(res.log.contains(&log("serviced_redeem_amount", 510000u128)));
+    assert!(res.messages.contains(&CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: HumanAddr::from("AT-uusd"),
+        send: vec![],
+        msg: to_binary(&Cw20HandleMsg::Burn {
+            amount: Uint128::from(510000u128),
+        })
+        .unwrap(),
+    })));
+
+    let res: QueuedRedemptionsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::QueuedRedemptions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.redemptions, vec![]);
+
+    let res: DepositorInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::DepositorInfo {
+                depositor: HumanAddr::from("addr0001"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.total_redeemed, Uint256::from(500000u64));
+}
+
+#[test]
+fn net_flow_limit() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    // 10% of the deposits can come in or go out per 100 blocks
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: Some(100u64),
+        max_net_flow_ratio: Some(Decimal256::percent(10)),
+        guardian: None,
+        reserve_factor: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_res.flow_window, Some(100u64));
+    assert_eq!(config_res.max_net_flow_ratio, Some(Decimal256::percent(10)));
+
+    let deposit_msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1100000u128),
+        }],
+    );
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let _res = handle(&mut deps, env, deposit_msg.clone()).unwrap();
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000),
+        )],
+    )]);
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1150000u128),
+        }],
+    );
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(50000u128),
+        }],
+    );
+    let res = handle(&mut deps, env, deposit_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1059] Net deposits would exceed the cap of 100000uusd until height 12445"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // redemptions net out the deposits of the window first
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1100000u128),
+        }],
+    );
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(150000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+    assert!(res.log.contains(&log("redeem_amount", 150000u128)));
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(950000u128),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(950000u128),
+        }],
+    );
+    let redeem_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(60000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), redeem_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1060] Net redemptions would exceed the cap of 100000uusd until height 12445"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: FlowLimitResponse =
+        from_binary(&query(&deps, QueryMsg::FlowLimit {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FlowLimitResponse {
+            override_enabled: false,
+            window_start_height: Some(12345u64),
+            base_deposits: Uint256::from(1000000u64),
+            deposited: Uint256::from(100000u64),
+            redeemed: Uint256::from(150000u64),
+        }
+    );
+
+    // the owner lifts the caps in an emergency
+    let msg = HandleMsg::OverrideFlowLimit { enabled: true };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![log("action", "override_flow_limit"), log("enabled", true)]
+    );
+
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), redeem_msg.clone()).unwrap();
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(890000u128),
+        }],
+    );
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(890000u128),
+        )],
+    )]);
+    let msg = HandleMsg::OverrideFlowLimit { enabled: false };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    // a new window starts from the current deposits
+    let mut env = mock_env("AT-uusd", &[]);
+    env.block.height += 100;
+    let _res = handle(&mut deps, env, redeem_msg).unwrap();
+
+    let res: FlowLimitResponse =
+        from_binary(&query(&deps, QueryMsg::FlowLimit {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FlowLimitResponse {
+            override_enabled: false,
+            window_start_height: Some(12445u64),
+            base_deposits: Uint256::from(890000u64),
+            deposited: Uint256::zero(),
+            redeemed: Uint256::from(60000u64),
+        }
+    );
+}
+
+#[test]
+fn flash_loan() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000u128),
+        }],
+    );
+
+    let msg = HandleMsg::UpdateFlashLoanFee {
+        fee_rate: Decimal256::permille(1),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let msg = HandleMsg::FlashLoan {
+        amount: Uint256::from(20000000u64),
+        msg: to_binary("callback").unwrap(),
+    };
+    let res = handle(&mut deps, mock_env("borrower0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1050] Not enough liquidity for the flash loan: 10000000"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::FlashLoan {
+        amount: Uint256::from(5000000u64),
+        msg: to_binary("callback").unwrap(),
+    };
+    let res = handle(&mut deps, mock_env("borrower0000", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("borrower0000"),
+                amount: vec![deduct_tax(
+                    &deps,
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(5000000u128),
+                    }
+                )
+                .unwrap()],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("borrower0000"),
+                send: vec![],
+                msg: to_binary("callback").unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&HandleMsg::FinishFlashLoan {}).unwrap(),
+            }),
+        ]
+    );
+
+    // flash loans cannot be nested
+    let res = handle(&mut deps, mock_env("borrower0001", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1049] A flash loan is already in progress")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the loaned stable is still counted in the market balance
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(5000000u128),
+        }],
+    );
+    let epoch_state: EpochStateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochState {
+                block_height: None,
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(epoch_state.market_balance, Uint256::from(10000000u64));
+
+    let res = handle(
+        &mut deps,
+        mock_env("borrower0000", &[]),
+        HandleMsg::FinishFlashLoan {},
+    );
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env(MOCK_CONTRACT_ADDR, &[]),
+        HandleMsg::FinishFlashLoan {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1053] Flash loaned stable not repaid; required: 5005000, repaid: 0"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env(
+            "borrower0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(5006000u128),
+            }],
+        ),
+        HandleMsg::RepayFlashLoan {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1052] Cannot repay more than the flash loaned amount plus fee: 5005000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10005000u128),
+        }],
+    );
+    let _res = handle(
+        &mut deps,
+        mock_env(
+            "borrower0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(5005000u128),
+            }],
+        ),
+        HandleMsg::RepayFlashLoan {},
+    )
+    .unwrap();
+
+    let res: FlashLoanResponse =
+        from_binary(&query(&deps, QueryMsg::FlashLoan {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FlashLoanResponse {
+            fee_rate: Decimal256::permille(1),
+            borrower: Some(HumanAddr::from("borrower0000")),
+            amount: Uint256::from(5000000u64),
+            fee: Uint256::from(5000u64),
+            repaid: Uint256::from(5005000u64),
+        }
+    );
+
+    // the fee is left to the depositors
+    let res = handle(
+        &mut deps,
+        mock_env(MOCK_CONTRACT_ADDR, &[]),
+        HandleMsg::FinishFlashLoan {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "finish_flash_loan"),
+            log("borrower", "borrower0000"),
+            log("repay_amount", "5005000"),
+            log("fee", "5000"),
+        ]
+    );
+
+    let epoch_state: EpochStateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochState {
+                block_height: None,
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(epoch_state.market_balance, Uint256::from(10005000u64));
+
+    let res: FlashLoanResponse =
+        from_binary(&query(&deps, QueryMsg::FlashLoan {}).unwrap()).unwrap();
+    assert_eq!(res.borrower, None);
+}
+
+#[test]
+fn disable_entry_points() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::UpdateOperationMode {
+        deposits_enabled: Some(false),
+        redemptions_enabled: Some(false),
+        borrows_enabled: Some(false),
+        repayments_enabled: Some(false),
+    };
+    let res = handle(&mut deps, mock_env("owner", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("guardian", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_operation_mode"),
+            log("deposits_enabled", false),
+            log("redemptions_enabled", false),
+            log("borrows_enabled", false),
+            log("repayments_enabled", false),
+        ]
+    );
+
+    let deposit_msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
+    let deposit_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let res = handle(&mut deps, deposit_env.clone(), deposit_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1012] Deposits are disabled"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1013] Redemptions are disabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1015] Borrows are disabled"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // credit line draws, flash loans and flash mints are borrows too
+    let msg = HandleMsg::DrawCredit {
+        amount: Uint256::from(100000u64),
+        to: None,
+    };
+    let res = handle(&mut deps, mock_env("strategy", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1015] Borrows are disabled"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::FlashLoan {
+        amount: Uint256::from(100000u64),
+        msg: to_binary("callback").unwrap(),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1015] Borrows are disabled"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::FlashMint {
+        amount: Uint256::from(100000u64),
+        msg: to_binary("callback").unwrap(),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1015] Borrows are disabled"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let res = handle(&mut deps, env, HandleMsg::RepayStable {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1016] Repayments are disabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // liquidations repay through the overseer
+    let msg = HandleMsg::RepayStableFromLiquidation {
+        borrower: HumanAddr::from("addr0001"),
+        prev_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+    };
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1016] Repayments are disabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the guardian enables the deposits only
+    let msg = HandleMsg::UpdateOperationMode {
+        deposits_enabled: Some(true),
+        redemptions_enabled: None,
+        borrows_enabled: None,
+        repayments_enabled: None,
+    };
+    let _res = handle(&mut deps, mock_env("guardian", &[]), msg).unwrap();
+
+    let res: OperationModeResponse =
+        from_binary(&query(&deps, QueryMsg::OperationMode {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        OperationModeResponse {
+            deposits_enabled: true,
+            redemptions_enabled: false,
+            borrows_enabled: false,
+            repayments_enabled: false,
+        }
+    );
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+        }],
+    );
+    let res = handle(&mut deps, deposit_env, deposit_msg).unwrap();
+    assert!(res.log.contains(&log("deposit_amount", 100000u128)));
+}
+
+#[test]
+fn set_pause() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0001"), &Uint256::from(1000000u64))]);
+
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: Some(HumanAddr::from("pauser")),
+        reserve_factor: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.guardian, Some(HumanAddr::from("pauser")));
+
+    let msg = HandleMsg::SetPause {
+        deposit: None,
+        redeem: None,
+        borrow: Some(true),
+        repay: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("pauser", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_pause"),
+            log("deposit", false),
+            log("redeem", false),
+            log("borrow", true),
+            log("repay", false),
+        ]
+    );
+
+    let borrow_msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), borrow_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E1015] Borrows are disabled"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: OperationModeResponse =
+        from_binary(&query(&deps, QueryMsg::OperationMode {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        OperationModeResponse {
+            deposits_enabled: true,
+            redemptions_enabled: true,
+            borrows_enabled: false,
+            repayments_enabled: true,
+        }
+    );
+
+    // the owner unpauses
+    let msg = HandleMsg::SetPause {
+        deposit: None,
+        redeem: None,
+        borrow: Some(false),
+        repay: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res = handle(&mut deps, mock_env("addr0001", &[]), borrow_msg).unwrap();
+    assert!(res.log.contains(&log("borrow_amount", 100000u64)));
+}
+
+#[test]
+fn registry() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    deps.querier.with_registry(
+        1,
+        &[
+            ("overseer", &HumanAddr::from("overseer0001")),
+            ("interest_model", &HumanAddr::from("interest0001")),
+        ],
+    );
+
+    let msg = HandleMsg::RegisterRegistry {
+        registry_contract: HumanAddr::from("registry"),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner", &[]);
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "register_registry"),
+            log("registry_contract", "registry"),
+            log("version", 1),
+        ]
+    );
+
+    let res: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.overseer_contract, HumanAddr::from("overseer0001"));
+    assert_eq!(res.interest_model, HumanAddr::from("interest0001"));
+    assert_eq!(res.distribution_model, HumanAddr::from("distribution"));
+
+    // a new version is resolved once the peers expire
+    deps.querier
+        .with_registry(2, &[("distributor", &HumanAddr::from("distributor0001"))]);
+    let mut env = mock_env("addr0000", &[]);
+    env.block.height += REGISTRY_MAX_AGE + 1;
+    let res = handle(&mut deps, env.clone(), HandleMsg::RefreshTaxParams {}).unwrap();
+    assert_eq!(res.log[0], log("action", "refresh_tax_params"));
+
+    let res: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.overseer_contract, HumanAddr::from("overseer0001"));
+    assert_eq!(res.distributor_contract, HumanAddr::from("distributor0001"));
+
+    let res: RegistryResponse = from_binary(&query(&deps, QueryMsg::Registry {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        RegistryResponse {
+            registry_contract: Some(HumanAddr::from("registry")),
+            version: 2,
+            updated_height: env.block.height,
+        }
+    );
+
+    let _res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::DisableRegistry {},
+    )
+    .unwrap();
+    let res = handle(&mut deps, env, HandleMsg::RefreshRegistry {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9004] No registry is registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn registry_interest_model_change() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier.with_borrow_rate(&[
+        (&HumanAddr::from("interest"), &Decimal256::percent(1)),
+        (&HumanAddr::from("interest0001"), &Decimal256::percent(10)),
+    ]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    state.total_liabilities = Decimal256::from_uint256(1000000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    deps.querier.with_registry(1, &[]);
+    let msg = HandleMsg::RegisterRegistry {
+        registry_contract: HumanAddr::from("registry"),
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    // the 10 blocks before the model changes are charged at its rate
+    deps.querier
+        .with_registry(2, &[("interest_model", &HumanAddr::from("interest0001"))]);
+    let mut env = mock_env("addr0000", &[]);
+    env.block.height += 10;
+    let _res = handle(&mut deps, env.clone(), HandleMsg::RefreshRegistry {}).unwrap();
+
+    let res: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.interest_model, HumanAddr::from("interest0001"));
+
+    let state_res: StateResponse =
+        from_binary(&query(&deps, QueryMsg::State { block_height: None }).unwrap()).unwrap();
+    assert_eq!(
+        state_res.total_liabilities,
+        Decimal256::from_uint256(1100000u64)
+    );
+    assert_eq!(state_res.last_interest_updated, env.block.height);
+
+    // the blocks after it at the rate of the new model
+    let state_res: StateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::State {
+                block_height: Some(env.block.height + 10),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        state_res.total_liabilities,
+        Decimal256::from_uint256(2200000u64)
+    );
+}
+
+#[test]
+fn time_based_accrual() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    state.total_liabilities = Decimal256::from_uint256(1000000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let mut env = mock_env("owner", &[]);
+    env.block.height += 10;
+    let block_time = env.block.time;
+    let msg = HandleMsg::EnableTimeAccrual {
+        seconds_per_block: 6u64,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::EnableTimeAccrual {
+            seconds_per_block: 0u64,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9007] seconds_per_block must be greater than 0")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the 10 blocks before the switch accrue per block
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log[0..3].to_vec(),
+        vec![
+            log("action", "enable_time_accrual"),
+            log("seconds_per_block", 6u64),
+            log("accrual_borrow_rate", "0.01"),
+        ]
+    );
+
+    let state_res: StateResponse =
+        from_binary(&query(&deps, QueryMsg::State { block_height: None }).unwrap()).unwrap();
+    assert_eq!(
+        state_res.total_liabilities,
+        Decimal256::from_uint256(1100000u64)
+    );
+    assert_eq!(state_res.last_interest_updated, env.block.height);
+    assert_eq!(state_res.seconds_per_block, Some(6u64));
+    assert_eq!(state_res.last_interest_updated_time, Some(block_time));
+
+    // the queries extrapolate the block time of the height
+    let state_res: StateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::State {
+                block_height: Some(env.block.height + 10),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        state_res.total_liabilities,
+        Decimal256::from_uint256(1210000u64)
+    );
+
+    // blocks without elapsed time accrue nothing, 60 seconds
+    // accrue 10 periods whatever the number of blocks
+    env.block.height += 30;
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::EnableTimeAccrual {
+            seconds_per_block: 12u64,
+        },
+    )
+    .unwrap();
+    assert!(res.log.contains(&log("accrual_blocks", "0")));
+    assert!(res.log.contains(&log("accrual_interest", "0")));
+
+    // the logged blocks are the periods the interest accrued over
+    env.block.height += 1;
+    env.block.time += 60;
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::EnableTimeAccrual {
+            seconds_per_block: 12u64,
+        },
+    )
+    .unwrap();
+    assert!(res.log.contains(&log("accrual_blocks", "5")));
+    assert!(res.log.contains(&log("accrual_interest", "55000")));
+
+    let state: State = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(1155000u64)
+    );
+    assert_eq!(
+        state.accrual_clock,
+        Some(AccrualClock {
+            seconds_per_block: 12u64,
+            last_interest_updated_time: block_time + 60,
+            last_reward_updated_time: block_time + 60,
+        })
+    );
+}
+
+#[test]
+fn amount_decimals() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+        }],
+    );
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: None,
+            decimals: Some(3),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1040] Deposit decimals cannot be given without an amount"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // 1 UST meant, 1 uusd expected
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: Some(Uint256::one()),
+            decimals: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1041] Sent amount 1000000 does not match the deposit amount 1"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: Some(Uint256::from(1000u64)),
+            decimals: Some(19),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9015] decimals must be less than or equal to 18")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // 1000 milli units
+    let res = handle(
+        &mut deps,
+        env,
+        HandleMsg::DepositStable {
+            recipient: None,
+            amount: Some(Uint256::from(1000u64)),
+            decimals: Some(3),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.log[4], log("deposit_amount", 1000000));
+
+    // 18 decimals borrow amounts cannot carry dust
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::BorrowStable {
+            borrow_amount: Uint256::from(500000000000000001u64),
+            to: None,
+            deposit: None,
+            decimals: Some(18),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9017] Amount 500000000000000001 has dust below the smallest unit"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::BorrowStable {
+            borrow_amount: Uint256::from(500000000000000000u64),
+            to: None,
+            deposit: None,
+            decimals: Some(18),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(500000u128),
+            }],
+        })]
+    );
+
+    // 18 decimals cw20 stable
+    let msg = HandleMsg::UpdateCw20Stable {
+        token: HumanAddr::from("wusdc"),
+        accept_deposits: true,
+        decimals: Some(18),
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res = handle(
+        &mut deps,
+        mock_env("wusdc", &[]),
+        HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("addr0000"),
+            amount: Uint128::from(1000000000000000000u128),
+            msg: Some(to_binary(&Cw20HookMsg::DepositStable {}).unwrap()),
+        }),
+    )
+    .unwrap();
+    assert_eq!(res.log[4], log("deposit_amount", 1000000));
+
+    let res = query(&deps, QueryMsg::Cw20Stables {}).unwrap();
+    let cw20_stables_res: Cw20StablesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        cw20_stables_res.cw20_stables,
+        vec![Cw20StableResponse {
+            token: HumanAddr::from("wusdc"),
+            accept_deposits: true,
+            decimals: 18,
+            held_amount: Uint256::from(1000000u64),
+        }]
+    );
+
+    let msg = HandleMsg::UpdateCw20Stable {
+        token: HumanAddr::from("wusdc"),
+        accept_deposits: true,
+        decimals: Some(6),
+    };
+    let res = handle(&mut deps, mock_env("owner", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1031] Cannot change the decimals of wusdc while the market holds it"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env(
+            "addr0001",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(400000u128),
+            }],
+        ),
+        HandleMsg::SwapCw20Stable {
+            token: HumanAddr::from("wusdc"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("wusdc"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0001"),
+                amount: Uint128::from(400000000000000000u128),
+            })
+            .unwrap(),
+        })]
+    );
+}
+
+#[test]
+fn rate_snapshot() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let rate_snapshot = |deps: &_| -> RateSnapshotResponse {
+        from_binary(
+            &query(
+                deps,
+                QueryMsg::RateSnapshot {
+                    borrower: HumanAddr::from("addr0000"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // no snapshot before the first borrow
+    assert_eq!(
+        rate_snapshot(&deps),
+        RateSnapshotResponse {
+            borrower: HumanAddr::from("addr0000"),
+            height: 0,
+            loan_amount: Uint256::zero(),
+            borrow_rate: Decimal256::zero(),
+            interest_index: Decimal256::zero(),
+            exchange_rate: Decimal256::zero(),
+        }
+    );
+
+    // global_interest_index = 2 after 100 blocks at 1%
+    env.block.height += 100;
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        rate_snapshot(&deps),
+        RateSnapshotResponse {
+            borrower: HumanAddr::from("addr0000"),
+            height: env.block.height,
+            loan_amount: Uint256::from(500000u64),
+            borrow_rate: Decimal256::percent(1),
+            interest_index: Decimal256::from_uint256(2u64),
+            exchange_rate: Decimal256::one(),
+        }
+    );
+
+    // the loan doubles as the index goes from 2 to 4,
+    // the repayment records the next snapshot
+    env.block.height += 100;
+    env.message.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(100000u128),
+    }];
+    let _res = handle(&mut deps, env.clone(), HandleMsg::RepayStable {}).unwrap();
+    assert_eq!(
+        rate_snapshot(&deps),
+        RateSnapshotResponse {
+            borrower: HumanAddr::from("addr0000"),
+            height: env.block.height,
+            loan_amount: Uint256::from(900000u64),
+            borrow_rate: Decimal256::percent(1),
+            interest_index: Decimal256::from_uint256(4u64),
+            exchange_rate: Decimal256::one(),
+        }
+    );
+}
+
+#[test]
+fn reserve_factor_and_withdraw_reserves() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    state.prev_exchange_rate = Decimal256::from_uint256(2u64);
+    state.total_liabilities = Decimal256::from_uint256(1000000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let update_msg = |reserve_factor: Decimal256| HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        global_borrow_cap: None,
+        per_account_borrow_cap: None,
+        min_deposit_amount: None,
+        min_redeem_amount: None,
+        flow_window: None,
+        max_net_flow_ratio: None,
+        guardian: None,
+        reserve_factor: Some(reserve_factor),
+    };
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        update_msg(Decimal256::percent(101)),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E9006] reserve_factor must be less than or equal to 1"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        update_msg(Decimal256::percent(10)),
+    )
+    .unwrap();
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_res.reserve_factor, Decimal256::percent(10));
+
+    // 10% of the 100000 interest accrued over 10 blocks
+    // goes to the reserves instead of the depositors
+    let mut env = mock_env("owner", &[]);
+    env.block.height += 10;
+    let msg = HandleMsg::WithdrawReserves {
+        amount: Uint256::from(10001u64),
+        recipient: HumanAddr::from("treasury"),
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1083] Withdraw amount cannot exceed the reserves: 10000"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::WithdrawReserves {
+        amount: Uint256::from(4000u64),
+        recipient: HumanAddr::from("treasury"),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let epoch_res: EpochStateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochState {
+                block_height: Some(env.block.height),
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        epoch_res.exchange_rate,
+        Decimal256::from_str("2.09").unwrap()
+    );
+
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("treasury"),
+            amount: vec![deduct_tax(
+                &deps,
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(4000u128),
+                }
+            )
+            .unwrap()],
+        })]
+    );
+    assert_eq!(
+        res.log[0..4].to_vec(),
+        vec![
+            log("action", "withdraw_reserves"),
+            log("recipient", "treasury"),
+            log("amount", 4000u64),
+            log("total_reserves", 6000u64),
+        ]
+    );
+    assert!(res.log.contains(&log("accrual_reserves", 10000u64)));
+
+    let state: State = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_reserves, Decimal256::from_uint256(6000u64));
+
+    // the CW20 stables count toward the market balance
+    // but cannot be sent to the recipient
+    store_cw20_stable_state(
+        &mut deps.storage,
+        &deps
+            .api
+            .canonical_address(&HumanAddr::from("cw20usd"))
+            .unwrap(),
+        &Cw20StableState {
+            accept_deposits: true,
+            held_amount: Uint256::from(3000000u64),
+            decimals: 6,
+        },
+    )
+    .unwrap();
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+
+    let msg = HandleMsg::WithdrawReserves {
+        amount: Uint256::from(6000u64),
+        recipient: HumanAddr::from("treasury"),
+    };
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1005] Not enough uusd available; borrow demand too high"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the deployed stable is recalled before the payment
+    store_idle_deployment(
+        &mut deps.storage,
+        &IdleDeployment {
+            venue: deps
+                .api
+                .canonical_address(&HumanAddr::from("venue0000"))
+                .unwrap(),
+            utilization_threshold: Decimal256::percent(50),
+            max_deploy_ratio: Decimal256::percent(50),
+            cap: Uint256::from(400000u64),
+            deployed_amount: Uint256::from(1000u64),
+        },
+    )
+    .unwrap();
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("venue0000"),
+            send: vec![],
+            msg: to_binary(&VenueHandleMsg::Withdraw {
+                amount: Some(Uint256::from(1000u64)),
+            })
+            .unwrap(),
+        })
+    );
+}
+
+#[test]
+fn aterra_exchange_rate_twap() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(1100000u128),
+        )],
+    )]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(100000u64),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(1100000u64),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::UpdateExchangeRateTwapWindow { twap_window: 100 };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    match handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdateExchangeRateTwapWindow { twap_window: 0 },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9007] twap_window must be greater than 0")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_exchange_rate_twap_window"),
+            log("twap_window", 100),
+        ]
+    );
+
+    let exchange_rate_at = |deps: &_, block_height: Option<u64>| -> ATerraExchangeRateResponse {
+        from_binary(&query(deps, QueryMsg::ATerraExchangeRate { block_height }).unwrap()).unwrap()
+    };
+
+    let borrow_msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(1000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+
+    env.block.height += 10;
+    let _res = handle(&mut deps, env.clone(), borrow_msg.clone()).unwrap();
+    let res = exchange_rate_at(&deps, None);
+
+    // observed at the accrual, before the borrow
+    let first_rate = read_state(&deps.storage).unwrap().prev_exchange_rate;
+    assert!(first_rate > Decimal256::one());
+
+    // the rate observed at the queried height has no weight yet,
+    // the rate of 1 observed by the owner message was in effect
+    assert_eq!(res.twap, Decimal256::one());
+    assert_eq!(res.twap_window, 100);
+
+    // a loss drops the spot rate, the average follows over the blocks
+    let mut state = read_state(&deps.storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(200000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    env.block.height += 10;
+    let _res = handle(&mut deps, env.clone(), borrow_msg).unwrap();
+    let res = exchange_rate_at(&deps, None);
+    let second_rate = read_state(&deps.storage).unwrap().prev_exchange_rate;
+    assert!(second_rate < first_rate);
+    let ten_blocks = Decimal256::from_uint256(10u64);
+    assert_eq!(
+        res.twap,
+        (Decimal256::one() * ten_blocks + first_rate * ten_blocks)
+            / Decimal256::from_uint256(20u64)
+    );
+
+    let res = exchange_rate_at(&deps, Some(env.block.height + 10));
+    assert_eq!(
+        res.twap,
+        (Decimal256::one() * ten_blocks + first_rate * ten_blocks + second_rate * ten_blocks)
+            / Decimal256::from_uint256(30u64)
+    );
+
+    // the first observation leaves the window
+    let res = exchange_rate_at(&deps, Some(env.block.height + 200));
+    assert_eq!(res.twap, second_rate);
+}
+
+#[test]
+fn state_history() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+            prev_exchange_rate: Decimal256::one(),
+            total_credit_liabilities: Decimal256::zero(),
+            credit_interest_per_block: Decimal256::zero(),
+            utilization_clamps: 0,
+            accrual_clock: None,
+            forgiven_dust: Uint256::zero(),
+            last_borrow_rate: Decimal256::zero(),
+            accrued_periods: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            stable_interest_per_block: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let start_height = env.block.height;
+    let mut env = mock_env("overseer", &[]);
+    for i in 1..=3u64 {
+        env.block.height = start_height + 100 * i;
+        let msg = HandleMsg::ExecuteEpochOperations {
+            deposit_rate: Decimal256::percent(i),
+            target_deposit_rate: Decimal256::one(),
+            threshold_deposit_rate: Decimal256::one(),
+            distributed_interest: Uint256::zero(),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let snapshot_at = |i: u64| StateSnapshotResponse {
+        height: start_height + 100 * i,
+        total_liabilities: Decimal256::zero(),
+        total_reserves: Decimal256::zero(),
+        aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+        exchange_rate: Decimal256::one(),
+        deposit_rate: Decimal256::percent(i),
+        borrow_rate: Decimal256::percent(1),
+    };
+
+    let res: StateSnapshotResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::StateAt {
+                height: start_height + 150,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, snapshot_at(1));
+
+    let res: StateSnapshotResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::StateAt {
+                height: start_height + 300,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, snapshot_at(3));
+
+    match query(
+        &deps,
+        QueryMsg::StateAt {
+            height: start_height + 50,
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E1092] No state snapshot at or before height {}",
+                start_height + 50
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: StateHistoryResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::StateHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.snapshots,
+        vec![snapshot_at(1), snapshot_at(2), snapshot_at(3)]
+    );
+
+    let res: StateHistoryResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::StateHistory {
+                start_after: Some(start_height + 100),
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.snapshots, vec![snapshot_at(2)]);
+
+    // the oldest snapshots are dropped once the history is full
+    let snapshot = StateSnapshot {
+        total_liabilities: Decimal256::zero(),
+        total_reserves: Decimal256::zero(),
+        aterra_supply: Uint256::zero(),
+        exchange_rate: Decimal256::one(),
+        deposit_rate: Decimal256::zero(),
+        borrow_rate: Decimal256::zero(),
+    };
+    for i in 4..=(MAX_STATE_SNAPSHOTS + 2) {
+        push_state_snapshot(&mut deps.storage, start_height + 100 * i, &snapshot).unwrap();
+    }
+
+    let res: StateHistoryResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::StateHistory {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.snapshots, vec![snapshot_at(3)]);
+}
+
+#[test]
+fn borrow_caps() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000u128),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_limit(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(10000000u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(10000000u64)),
+    ]);
+
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        global_borrow_cap: Some(Uint256::from(1500000u64)),
+        per_account_borrow_cap: Some(Uint256::from(1000000u64)),
+        max_net_flow_ratio: None,
+        flow_window: None,
+        guardian: None,
+        reserve_factor: None,
+        min_redeem_amount: None,
+        min_deposit_amount: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config_res.global_borrow_cap,
+        Some(Uint256::from(1500000u64))
+    );
+    assert_eq!(
+        config_res.per_account_borrow_cap,
+        Some(Uint256::from(1000000u64))
+    );
+
+    let borrow_msg = |borrow_amount: u64| HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(borrow_amount),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+
+    let res = handle(&mut deps, mock_env("addr0000", &[]), borrow_msg(1100000));
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1009] Borrow amount exceeds the per account borrow cap: 1000000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), borrow_msg(1000000)).unwrap();
+
+    let res = handle(&mut deps, mock_env("addr0001", &[]), borrow_msg(600000));
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1008] Borrow amount exceeds the global borrow cap: 1500000"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), borrow_msg(500000)).unwrap();
+
+    // a zero cap lifts the global limit
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        global_borrow_cap: Some(Uint256::zero()),
+        per_account_borrow_cap: None,
+        max_net_flow_ratio: None,
+        flow_window: None,
+        guardian: None,
+        reserve_factor: None,
+        min_redeem_amount: None,
+        min_deposit_amount: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_res.global_borrow_cap, None);
+    assert_eq!(
+        config_res.per_account_borrow_cap,
+        Some(Uint256::from(1000000u64))
+    );
+
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), borrow_msg(400000)).unwrap();
+}
+
+#[test]
+fn attestation_hook() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0001"), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier
+        .with_attestation_denials(&[(&HumanAddr::from("addr0001"), "not verified")]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::SetAttestationContract {
+        attestation_contract: Some(HumanAddr::from("attestation")),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_attestation_contract"),
+            log("attestation_contract", "attestation"),
+        ]
+    );
+
+    let res: AttestationContractResponse =
+        from_binary(&query(&deps, QueryMsg::AttestationContract {}).unwrap()).unwrap();
+    assert_eq!(
+        res.attestation_contract,
+        Some(HumanAddr::from("attestation"))
+    );
+
+    let deposit_msg = HandleMsg::DepositStable {
+        recipient: None,
+        amount: None,
+        decimals: None,
+    };
+    let deposit_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let res = handle(&mut deps, deposit_env.clone(), deposit_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1014] The deposit is not attested: not verified")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(100000u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+    });
+    let res = handle(&mut deps, mock_env("AT-uusd", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1014] The redeem is not attested: not verified")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1014] The borrow is not attested: not verified")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // an attested address deposits as usual
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+        }],
+    );
+    let env = mock_env(
+        "addr0002",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let res = handle(&mut deps, env, deposit_msg.clone()).unwrap();
+    assert!(res.log.contains(&log("deposit_amount", 100000u128)));
+
+    // without the attestation contract the market is permissionless again
+    let msg = HandleMsg::SetAttestationContract {
+        attestation_contract: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res: AttestationContractResponse =
+        from_binary(&query(&deps, QueryMsg::AttestationContract {}).unwrap()).unwrap();
+    assert_eq!(res.attestation_contract, None);
+
+    let res = handle(&mut deps, deposit_env, deposit_msg).unwrap();
+    assert!(res.log.contains(&log("deposit_amount", 100000u128)));
+}
+
+#[test]
+fn update_reward_indexes() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_limit(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(1000000u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(1000000u64)),
+    ]);
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone()).unwrap();
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+
+    // anyone can crank, one liability per page
+    let mut crank_env = mock_env("addr0002", &[]);
+    crank_env.block.height = env.block.height + 100;
+
+    let msg = HandleMsg::UpdateRewardIndexes {
+        start_after: None,
+        limit: Some(1),
+    };
+    let res = handle(&mut deps, crank_env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log[0..3].to_vec(),
+        vec![
+            log("action", "update_reward_indexes"),
+            log("scanned", 1),
+            log("updated", 1),
+        ]
+    );
+    let first_borrower = HumanAddr::from(res.log[3].value.as_str());
+
+    let msg = HandleMsg::UpdateRewardIndexes {
+        start_after: Some(first_borrower.clone()),
+        limit: Some(1),
+    };
+    let res = handle(&mut deps, crank_env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log[0..3].to_vec(),
+        vec![
+            log("action", "update_reward_indexes"),
+            log("scanned", 1),
+            log("updated", 1),
+        ]
+    );
+    let second_borrower = HumanAddr::from(res.log[3].value.as_str());
+    assert_ne!(first_borrower, second_borrower);
+
+    let msg = HandleMsg::UpdateRewardIndexes {
+        start_after: Some(second_borrower),
+        limit: Some(1),
+    };
+    let res = handle(&mut deps, crank_env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_reward_indexes"),
+            log("scanned", 0),
+            log("updated", 0),
+            log("last_borrower", ""),
+        ]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    for borrower_info in read_borrower_infos(&deps, None, None).unwrap() {
+        assert_eq!(borrower_info.interest_index, state.global_interest_index);
+        assert_eq!(borrower_info.reward_index, state.global_reward_index);
+        assert!(borrower_info.loan_amount > Uint256::from(100000u64));
+        assert!(borrower_info.pending_rewards > Decimal256::zero());
+    }
+
+    // current liabilities are skipped
+    let msg = HandleMsg::UpdateRewardIndexes {
+        start_after: None,
+        limit: None,
+    };
+    let res = handle(&mut deps, crank_env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_reward_indexes"),
+            log("scanned", 2),
+            log("updated", 0),
+            log("last_borrower", ""),
+        ]
+    );
+}
+
+#[test]
+fn stable_rate_borrowing() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0001"), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let mut env = mock_env("addr0001", &[]);
+    let res = handle(&mut deps, env.clone(), HandleMsg::SwapRateMode {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1091] Stable rate borrowing is not enabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateStableRateParams {
+        premium: Decimal256::permille(5),
+        rebalance_threshold: Decimal256::percent(1),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res: StableRateParamsResponse =
+        from_binary(&query(&deps, QueryMsg::StableRateParams {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        StableRateParamsResponse {
+            premium: Decimal256::permille(5),
+            rebalance_threshold: Decimal256::percent(1),
+        }
+    );
+
+    let borrow_msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, env.clone(), borrow_msg.clone()).unwrap();
+
+    // the variable rate plus the premium is locked
+    let res = handle(&mut deps, env.clone(), HandleMsg::SwapRateMode {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "swap_rate_mode"),
+            log("borrower", "addr0001"),
+            log("rate_mode", "stable"),
+            log("stable_rate", "0.015"),
+        ]
+    );
+
+    // later borrows lock the rate of their origination
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(2))]);
+    let _res = handle(&mut deps, env.clone(), borrow_msg).unwrap();
+
+    let res: RateModeResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::RateMode {
+                borrower: HumanAddr::from("addr0001"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        RateModeResponse {
+            borrower: HumanAddr::from("addr0001"),
+            rate_mode: RateMode::Stable,
+            stable_rate: Some(Decimal256::percent(2)),
+        }
+    );
+
+    let msg = HandleMsg::RebalanceStableRate {
+        borrower: HumanAddr::from("addr0001"),
+    };
+    let res = handle(&mut deps, mock_env("addr0002", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1090] Stable rate is within the rebalance threshold; current stable rate: 0.025"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the loan accrues at the locked rate whatever the variable rate,
+    // and so do the total liabilities before it is settled
+    env.block.height += 10;
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(4))]);
+    let res: StateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::State {
+                block_height: Some(env.block.height),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.total_liabilities, Decimal256::from_uint256(240000u64));
+
+    let mut keeper_env = mock_env("addr0002", &[]);
+    keeper_env.block.height = env.block.height;
+    let res = handle(&mut deps, keeper_env, msg).unwrap();
+    assert_eq!(
+        res.log[0..4].to_vec(),
+        vec![
+            log("action", "rebalance_stable_rate"),
+            log("borrower", "addr0001"),
+            log("previous_rate", "0.02"),
+            log("stable_rate", "0.045"),
+        ]
+    );
+
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BorrowerInfo {
+                borrower: HumanAddr::from("addr0001"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::from(240000u64));
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::from_uint256(240000u64));
+    assert_eq!(
+        state.total_stable_liabilities,
+        Decimal256::from_uint256(240000u64)
+    );
+
+    // the stable loan moves with its locked rate
+    let block_height = env.block.height;
+    let mut overseer_env = mock_env("overseer", &[]);
+    overseer_env.block.height = block_height;
+    let msg = HandleMsg::TransferLiability {
+        from: HumanAddr::from("addr0001"),
+        to: HumanAddr::from("addr0003"),
+    };
+    let _res = handle(&mut deps, overseer_env, msg).unwrap();
+
+    let res: RateModeResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::RateMode {
+                borrower: HumanAddr::from("addr0003"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.rate_mode, RateMode::Stable);
+    assert_eq!(res.stable_rate, Some(Decimal256::permille(45)));
+
+    // the stable rate only goes down on the call of the borrower
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    let msg = HandleMsg::RebalanceStableRate {
+        borrower: HumanAddr::from("addr0003"),
+    };
+    let mut keeper_env = mock_env("addr0002", &[]);
+    keeper_env.block.height = block_height;
+    let res = handle(&mut deps, keeper_env, msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut env = mock_env("addr0003", &[]);
+    env.block.height = block_height;
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(3))]);
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E1090] Stable rate is within the rebalance threshold; current stable rate: 0.035"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log[0..4].to_vec(),
+        vec![
+            log("action", "rebalance_stable_rate"),
+            log("borrower", "addr0003"),
+            log("previous_rate", "0.045"),
+            log("stable_rate", "0.015"),
+        ]
+    );
+
+    let res = handle(&mut deps, env, HandleMsg::SwapRateMode {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "swap_rate_mode"),
+            log("borrower", "addr0003"),
+            log("rate_mode", "variable"),
+        ]
+    );
+
+    let res: RateModeResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::RateMode {
+                borrower: HumanAddr::from("addr0003"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.rate_mode, RateMode::Variable);
+    assert_eq!(res.stable_rate, None);
+
+    // the variable loan leaves the stable liabilities
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_stable_liabilities, Decimal256::zero());
+    assert_eq!(state.stable_interest_per_block, Decimal256::zero());
+}
+
+#[test]
+fn dust_forgiveness() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0001"), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+
+    let repay_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(49995u128),
+        }],
+    );
+
+    // without forgiveness the residual is left as a loan
+    let res = handle(&mut deps, repay_env.clone(), HandleMsg::RepayStable {}).unwrap();
+    assert!(!res.log.iter().any(|v| v.key == "forgiven_amount"));
+
+    let msg = HandleMsg::UpdateDustForgiveness {
+        max_amount: Uint256::from(10u64),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdateDustForgiveness {
+            max_amount: Uint256::from(1000001u64),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1098] max_amount cannot exceed 1000000")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    // a principal residual is never forgiven
+    let res = handle(&mut deps, repay_env.clone(), HandleMsg::RepayStable {}).unwrap();
+    assert!(!res.log.iter().any(|v| v.key == "forgiven_amount"));
+
+    // 1% interest per block over 50 blocks, the repayment covers the
+    // principal and leaves interest within the forgiveness amount
+    let mut repay_env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(12u128),
+        }],
+    );
+    repay_env.block.height += 50;
+    let res = handle(&mut deps, repay_env, HandleMsg::RepayStable {}).unwrap();
+    assert_eq!(
+        res.log[0..6].to_vec(),
+        vec![
+            log("action", "repay_stable"),
+            log("borrower", "addr0001"),
+            log("repay_amount", 12u128),
+            log("interest_repaid", 2u128),
+            log("principal_repaid", 10u128),
+            log("forgiven_amount", 3u128),
+        ]
+    );
+
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BorrowerInfo {
+                borrower: HumanAddr::from("addr0001"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::zero());
+
+    let res: LoanBreakdownResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LoanBreakdown {
+                borrower: HumanAddr::from("addr0001"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.principal, Uint256::zero());
+
+    let state: State = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+    assert_eq!(state.forgiven_dust, Uint256::from(3u64));
+
+    let res: DustForgivenessResponse =
+        from_binary(&query(&deps, QueryMsg::DustForgiveness {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        DustForgivenessResponse {
+            max_amount: Uint256::from(10u64),
+            forgiven_dust: Uint256::from(3u64),
+        }
+    );
+}
+
+#[test]
+fn borrower_infos_filters() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000u128),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_limit(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(1000000u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(1000000u64)),
+        (&HumanAddr::from("addr0002"), &Uint256::from(1000000u64)),
+    ]);
+
+    for (borrower, borrow_amount) in [
+        ("addr0000", 100000u64),
+        ("addr0001", 500000u64),
+        ("addr0002", 800000u64),
+    ]
+    .iter()
+    {
+        let msg = HandleMsg::BorrowStable {
+            borrow_amount: Uint256::from(*borrow_amount),
+            to: None,
+            deposit: None,
+            decimals: None,
+        };
+        let _res = handle(&mut deps, mock_env(*borrower, &[]), msg).unwrap();
+    }
+
+    // collateral prices dropped for addr0000 and addr0002, addr0001 is
+    // rejected by the risk module and has no borrow limit left but its
+    // loan is below the liquidation borrow limit
+    deps.querier.with_borrow_limit(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(50000u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::zero()),
+        (&HumanAddr::from("addr0002"), &Uint256::from(700000u64)),
+    ]);
+    deps.querier.with_liquidation_borrow_limit(&[(
+        &HumanAddr::from("addr0001"),
+        &Uint256::from(1000000u64),
+    )]);
+
+    let query_borrowers = |deps: &_, min_liability: Option<u64>, only_liquidatable, limit| {
+        let res: BorrowerInfosResponse = from_binary(
+            &query(
+                deps,
+                QueryMsg::BorrowerInfos {
+                    start_after: None,
+                    limit,
+                    min_liability: min_liability.map(Uint256::from),
+                    only_liquidatable,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let mut borrowers: Vec<HumanAddr> =
+            res.borrower_infos.into_iter().map(|v| v.borrower).collect();
+        borrowers.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        borrowers
+    };
+
+    assert_eq!(query_borrowers(&deps, None, None, None).len(), 3);
+    assert_eq!(
+        query_borrowers(&deps, Some(400000), None, None),
+        vec![HumanAddr::from("addr0001"), HumanAddr::from("addr0002")]
+    );
+    assert_eq!(
+        query_borrowers(&deps, None, Some(true), None),
+        vec![HumanAddr::from("addr0000"), HumanAddr::from("addr0002")]
+    );
+    assert_eq!(
+        query_borrowers(&deps, Some(400000), Some(true), None),
+        vec![HumanAddr::from("addr0002")]
+    );
+
+    assert_eq!(query_borrowers(&deps, Some(900000), None, None).len(), 0);
+
+    // the limit counts the scanned liabilities, the
+    // pages continue from the last scanned one
+    let mut start_after: Option<HumanAddr> = None;
+    let mut borrowers: Vec<HumanAddr> = vec![];
+    let mut pages = 0;
+    loop {
+        let res: BorrowerInfosResponse = from_binary(
+            &query(
+                &deps,
+                QueryMsg::BorrowerInfos {
+                    start_after: start_after.clone(),
+                    limit: Some(1),
+                    min_liability: None,
+                    only_liquidatable: Some(true),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.borrower_infos.len() <= 1);
+        borrowers.extend(res.borrower_infos.into_iter().map(|v| v.borrower));
+
+        match res.last_scanned {
+            Some(v) => start_after = Some(v),
+            None => break,
+        }
+        pages += 1;
+    }
+    borrowers.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    assert_eq!(pages, 3);
+    assert_eq!(
+        borrowers,
+        vec![HumanAddr::from("addr0000"), HumanAddr::from("addr0002")]
+    );
+}
+
+#[test]
+fn donate_to_depositors() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, HandleMsg::RegisterATerra {}).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+
+    let donation = |amount: u128| {
+        mock_env(
+            "grant",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(amount),
+            }],
+        )
+    };
+
+    let msg = HandleMsg::DonateToDepositors {
+        amount: Uint256::zero(),
+    };
+    let res = handle(&mut deps, donation(0), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1044] Donation amount must be greater than 0")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::DonateToDepositors {
+        amount: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+    };
+    let res = handle(&mut deps, donation(100), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E1045] Sent amount 100 does not match the donation amount {}",
+                INITIAL_DEPOSIT_AMOUNT
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // no aterra to donate to
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(2 * INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let res = handle(&mut deps, donation(INITIAL_DEPOSIT_AMOUNT), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1046] No deposits to donate to")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let res = handle(&mut deps, donation(INITIAL_DEPOSIT_AMOUNT), msg).unwrap();
+    assert_eq!(
+        res.log[0..5].to_vec(),
+        vec![
+            log("action", "donate_to_depositors"),
+            log("donor", "grant"),
+            log("donation_amount", INITIAL_DEPOSIT_AMOUNT),
+            log("exchange_rate", 2u128),
+            log("total_donated", INITIAL_DEPOSIT_AMOUNT),
+        ]
+    );
+
+    // the donation is not swept to the reserves as excess yield
+    let mut env = mock_env("addr0000", &[]);
+    env.block.height += 100;
+    let res: EpochStateResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochState {
+                block_height: Some(env.block.height),
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.exchange_rate, Decimal256::from_uint256(2u64));
+
+    let res: DonationsResponse =
+        from_binary(&query(&deps, QueryMsg::Donations {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        DonationsResponse {
+            total_donated: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+        }
+    );
+}
+
+#[test]
+fn shadow_interest_model() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier.with_borrow_rate(&[
+        (&HumanAddr::from("interest"), &Decimal256::percent(1)),
+        (&HumanAddr::from("interest0001"), &Decimal256::percent(2)),
+    ]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    let msg = HandleMsg::UpdateShadowInterestModel {
+        interest_model: HumanAddr::from("interest0001"),
+        trial_blocks: 100,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdateShadowInterestModel {
+            interest_model: HumanAddr::from("interest"),
+            trial_blocks: 100,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E1084] The shadow interest model cannot be the live one"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let res: ShadowInterestModelResponse =
+        from_binary(&query(&deps, QueryMsg::ShadowInterestModel {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        ShadowInterestModelResponse {
+            interest_model: Some(HumanAddr::from("interest0001")),
+            start_height: env.block.height,
+            end_height: env.block.height + 100,
+            live_borrow_rate: Decimal256::percent(1),
+            shadow_borrow_rate: Decimal256::percent(2),
+        }
+    );
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        deposit: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone()).unwrap();
+
+    // the shadow rate is logged, the accrual follows the live model
+    let mut borrow_env = mock_env("addr0000", &[]);
+    borrow_env.block.height += 10;
+    let res = handle(&mut deps, borrow_env.clone(), msg.clone()).unwrap();
+    assert!(res.log.contains(&log("accrual_borrow_rate", "0.01")));
+    assert!(res.log.contains(&log("accrual_shadow_borrow_rate", "0.02")));
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(Uint256::from(210000u64))
+    );
+
+    // a failing shadow model does not fail the accrual
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    borrow_env.block.height += 10;
+    let res = handle(&mut deps, borrow_env.clone(), msg.clone()).unwrap();
+    assert!(res
+        .log
+        .contains(&log("accrual_shadow_borrow_rate", "unavailable")));
+    deps.querier.with_borrow_rate(&[
+        (&HumanAddr::from("interest"), &Decimal256::percent(1)),
+        (&HumanAddr::from("interest0001"), &Decimal256::percent(2)),
+    ]);
+
+    // nothing is logged once the trial is over
+    borrow_env.block.height = env.block.height + 101;
+    let res = handle(&mut deps, borrow_env.clone(), msg).unwrap();
+    assert!(res.log.contains(&log("accrual_borrow_rate", "0.01")));
+    assert!(res
+        .log
+        .iter()
+        .all(|v| v.key != "accrual_shadow_borrow_rate"));
+
+    let msg = HandleMsg::PromoteShadowInterestModel {};
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut promote_env = mock_env("owner", &[]);
+    promote_env.block.height = env.block.height + 110;
+    let res = handle(&mut deps, promote_env.clone(), msg.clone()).unwrap();
+    assert!(res.log.contains(&log("accrual_borrow_rate", "0.01")));
+
+    let res: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.interest_model, HumanAddr::from("interest0001"));
+
+    let res: ShadowInterestModelResponse =
+        from_binary(&query(&deps, QueryMsg::ShadowInterestModel {}).unwrap()).unwrap();
+    assert_eq!(res.interest_model, None);
+    assert_eq!(res.live_borrow_rate, Decimal256::percent(2));
+
+    let res = handle(&mut deps, promote_env, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E1085] No shadow interest model is registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
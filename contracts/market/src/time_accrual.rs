@@ -0,0 +1,67 @@
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, Querier, StdError, Storage,
+};
+
+use crate::borrow::{compute_interest, compute_reward};
+use crate::state::{read_config, read_state, store_state, AccrualClock, Config, State};
+
+use moneymarket::error::ContractError;
+
+/// Switches the interest and reward accrual from block heights to block
+/// times. Both are first accrued per block up to the current height,
+/// whose block time becomes the start of the time based accrual.
+/// Once enabled, calling it again only updates `seconds_per_block`
+pub fn enable_time_accrual<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    seconds_per_block: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if seconds_per_block == 0 {
+        return Err(ContractError::ZeroParameter {
+            name: "seconds_per_block".to_string(),
+        }
+        .into());
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+    let accrual_logs = compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        env.block.time,
+        None,
+    )?;
+    compute_reward(&mut state, env.block.height, env.block.time);
+
+    state.accrual_clock = Some(match state.accrual_clock {
+        Some(clock) => AccrualClock {
+            seconds_per_block,
+            ..clock
+        },
+        None => AccrualClock {
+            seconds_per_block,
+            last_interest_updated_time: env.block.time,
+            last_reward_updated_time: env.block.time,
+        },
+    });
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "enable_time_accrual"),
+                log("seconds_per_block", seconds_per_block),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
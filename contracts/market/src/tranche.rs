@@ -0,0 +1,307 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use cw20::{Cw20HandleMsg, MinterResponse};
+
+use crate::deposit::{compute_deposit, redeem_stable};
+use crate::state::{
+    read_cold_tranche, read_cold_unbonding, read_config, remove_cold_unbonding, store_cold_tranche,
+    store_cold_unbonding, ColdTranche, ColdUnbonding, Config, State,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{ColdTrancheResponse, ColdUnbondingResponse, HandleMsg};
+use moneymarket::querier::query_supply;
+use terraswap::hook::InitHook;
+use terraswap::token::InitMsg as TokenInitMsg;
+
+pub fn init_cold_tranche<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    caterra_code_id: u64,
+    redeem_delay: u64,
+    bonus_rate: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if read_cold_tranche(&deps.storage)?.is_some() {
+        return Err(ContractError::ColdTrancheInitialized {}.into());
+    }
+
+    store_cold_tranche(
+        &mut deps.storage,
+        &ColdTranche {
+            caterra_contract: CanonicalAddr::default(),
+            redeem_delay,
+            bonus_rate,
+            aterra_balance: Uint256::zero(),
+            last_bonus_updated: env.block.height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: caterra_code_id,
+            send: vec![],
+            label: None,
+            msg: to_binary(&TokenInitMsg {
+                name: format!(
+                    "Anchor Terra {} Cold",
+                    config.stable_denom[1..].to_uppercase()
+                ),
+                symbol: format!(
+                    "ca{}T",
+                    config.stable_denom[1..(config.stable_denom.len() - 1)].to_uppercase()
+                ),
+                decimals: 6u8,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.clone(),
+                    cap: None,
+                }),
+                init_hook: Some(InitHook {
+                    contract_addr: env.contract.address,
+                    msg: to_binary(&HandleMsg::RegisterColdATerra {})?,
+                }),
+            })?,
+        })],
+        log: vec![
+            log("action", "init_cold_tranche"),
+            log("redeem_delay", redeem_delay),
+            log("bonus_rate", bonus_rate),
+        ],
+        data: None,
+    })
+}
+
+pub fn register_cold_aterra<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut tranche = match read_cold_tranche(&deps.storage)? {
+        Some(v) if v.caterra_contract == CanonicalAddr::default() => v,
+        _ => return Err(StdError::unauthorized()),
+    };
+
+    tranche.caterra_contract = deps.api.canonical_address(&env.message.sender)?;
+    store_cold_tranche(&mut deps.storage, &tranche)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("caterra", env.message.sender)],
+        data: None,
+    })
+}
+
+pub fn deposit_stable_cold<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let mut tranche = read_registered_cold_tranche(&deps.storage)?;
+
+    // aterra is minted to the market and held for the cold tranche
+    let (deposit_amount, mint_amount, accrual_logs) =
+        compute_deposit(deps, &env, &config, &env.message.sender)?;
+
+    let caterra_contract = deps.api.human_address(&tranche.caterra_contract)?;
+    let caterra_supply = query_supply(deps, &caterra_contract)?;
+    let cold_mint_amount = if caterra_supply.is_zero() || tranche.aterra_balance.is_zero() {
+        mint_amount
+    } else {
+        mint_amount
+            * (Decimal256::from_uint256(caterra_supply)
+                / Decimal256::from_uint256(tranche.aterra_balance))
+    };
+
+    tranche.aterra_balance += mint_amount;
+    store_cold_tranche(&mut deps.storage, &tranche)?;
+
+    Ok(HandleResponse {
+        messages: vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.human_address(&config.aterra_contract)?,
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Mint {
+                    recipient: env.contract.address.clone(),
+                    amount: mint_amount.into(),
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: caterra_contract,
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Mint {
+                    recipient: env.message.sender.clone(),
+                    amount: cold_mint_amount.into(),
+                })?,
+            }),
+        ],
+        log: [
+            vec![
+                log("action", "deposit_stable_cold"),
+                log("depositor", env.message.sender),
+                log("mint_amount", cold_mint_amount),
+                log("deposit_amount", deposit_amount),
+            ],
+            accrual_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn unbond_cold<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    sender: HumanAddr,
+    burn_amount: Uint256,
+) -> HandleResult {
+    let mut tranche = read_registered_cold_tranche(&deps.storage)?;
+
+    // the burn amount is still included in the supply at this point
+    let caterra_contract = deps.api.human_address(&tranche.caterra_contract)?;
+    let caterra_supply = query_supply(deps, &caterra_contract)?;
+    let aterra_amount = burn_amount
+        * (Decimal256::from_uint256(tranche.aterra_balance)
+            / Decimal256::from_uint256(caterra_supply));
+
+    tranche.aterra_balance = tranche.aterra_balance - aterra_amount;
+    store_cold_tranche(&mut deps.storage, &tranche)?;
+
+    // merging with a pending unbonding restarts its delay
+    let sender_raw = deps.api.canonical_address(&sender)?;
+    let mut unbonding: ColdUnbonding = read_cold_unbonding(&deps.storage, &sender_raw);
+    unbonding.aterra_amount += aterra_amount;
+    unbonding.release_height = env.block.height + tranche.redeem_delay;
+    store_cold_unbonding(&mut deps.storage, &sender_raw, &unbonding)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: caterra_contract,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Burn {
+                amount: burn_amount.into(),
+            })?,
+        })],
+        log: vec![
+            log("action", "unbond_cold"),
+            log("burn_amount", burn_amount),
+            log("aterra_amount", aterra_amount),
+            log("release_height", unbonding.release_height),
+        ],
+        data: None,
+    })
+}
+
+pub fn withdraw_unbonded_cold<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let sender = env.message.sender.clone();
+    let sender_raw = deps.api.canonical_address(&sender)?;
+    let unbonding: ColdUnbonding = read_cold_unbonding(&deps.storage, &sender_raw);
+    if unbonding.aterra_amount.is_zero() {
+        return Err(ContractError::NoUnbondingColdDeposits {}.into());
+    }
+
+    if unbonding.release_height > env.block.height {
+        return Err(ContractError::ColdDepositsUnbonding {
+            release_height: unbonding.release_height,
+        }
+        .into());
+    }
+
+    remove_cold_unbonding(&mut deps.storage, &sender_raw);
+    redeem_stable(deps, env, sender, unbonding.aterra_amount.into())
+}
+
+/// Pays the cold tranche bonus out of the reserves by minting
+/// aterra at the current exchange rate to the market, which keeps
+/// the exchange rate of the hot tranche unchanged.
+pub(crate) fn compute_cold_bonus<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    state: &mut State,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut tranche = match read_cold_tranche(&deps.storage)? {
+        Some(v) if v.caterra_contract != CanonicalAddr::default() => v,
+        _ => return Ok(vec![]),
+    };
+
+    if tranche.last_bonus_updated >= env.block.height {
+        return Ok(vec![]);
+    }
+
+    let passed_blocks = env.block.height - tranche.last_bonus_updated;
+    tranche.last_bonus_updated = env.block.height;
+
+    let bonus_value = Decimal256::from_uint256(tranche.aterra_balance)
+        * state.prev_exchange_rate
+        * tranche.bonus_rate
+        * Decimal256::from_uint256(passed_blocks);
+    let bonus_value = if bonus_value > state.total_reserves {
+        state.total_reserves
+    } else {
+        bonus_value
+    };
+
+    let bonus_amount = (bonus_value / state.prev_exchange_rate) * Uint256::one();
+    if bonus_amount.is_zero() {
+        store_cold_tranche(&mut deps.storage, &tranche)?;
+        return Ok(vec![]);
+    }
+
+    state.total_reserves =
+        state.total_reserves - Decimal256::from_uint256(bonus_amount) * state.prev_exchange_rate;
+    state.prev_aterra_supply += bonus_amount;
+    tranche.aterra_balance += bonus_amount;
+    store_cold_tranche(&mut deps.storage, &tranche)?;
+
+    Ok(vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&config.aterra_contract)?,
+        send: vec![],
+        msg: to_binary(&Cw20HandleMsg::Mint {
+            recipient: env.contract.address.clone(),
+            amount: bonus_amount.into(),
+        })?,
+    })])
+}
+
+fn read_registered_cold_tranche<S: Storage>(storage: &S) -> StdResult<ColdTranche> {
+    match read_cold_tranche(storage)? {
+        Some(v) if v.caterra_contract != CanonicalAddr::default() => Ok(v),
+        _ => Err(ContractError::ColdTrancheNotInitialized {}.into()),
+    }
+}
+
+pub fn query_cold_tranche<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ColdTrancheResponse> {
+    let tranche = read_registered_cold_tranche(&deps.storage)?;
+    Ok(ColdTrancheResponse {
+        caterra_contract: deps.api.human_address(&tranche.caterra_contract)?,
+        redeem_delay: tranche.redeem_delay,
+        bonus_rate: tranche.bonus_rate,
+        aterra_balance: tranche.aterra_balance,
+        last_bonus_updated: tranche.last_bonus_updated,
+    })
+}
+
+pub fn query_cold_unbonding<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+) -> StdResult<ColdUnbondingResponse> {
+    let unbonding: ColdUnbonding =
+        read_cold_unbonding(&deps.storage, &deps.api.canonical_address(&address)?);
+    Ok(ColdUnbondingResponse {
+        address,
+        aterra_amount: unbonding.aterra_amount,
+        release_height: unbonding.release_height,
+    })
+}
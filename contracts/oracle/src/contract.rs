@@ -77,7 +77,7 @@ pub fn register_feeder<S: Storage, A: Api, Q: Querier>(
         &deps.api.canonical_address(&feeder)?,
     )?;
 
-    return Ok(HandleResponse {
+    Ok(HandleResponse {
         messages: vec![],
         log: vec![
             log("action", "register_feeder"),
@@ -85,7 +85,7 @@ pub fn register_feeder<S: Storage, A: Api, Q: Querier>(
             log("feeder", feeder),
         ],
         data: None,
-    });
+    })
 }
 
 pub fn feed_prices<S: Storage, A: Api, Q: Querier>(
@@ -204,6 +204,14 @@ fn query_prices<S: Storage, A: Api, Q: Querier>(
     Ok(PricesResponse { prices })
 }
 
+pub fn migrate<S: Storage, A: Api, Q: Querier>(
+    _deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> MigrateResult {
+    Ok(MigrateResponse::default())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -404,11 +412,3 @@ mod tests {
         }
     }
 }
-
-pub fn migrate<S: Storage, A: Api, Q: Querier>(
-    _deps: &mut Extern<S, A, Q>,
-    _env: Env,
-    _msg: MigrateMsg,
-) -> MigrateResult {
-    Ok(MigrateResponse::default())
-}
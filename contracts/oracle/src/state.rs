@@ -34,7 +34,7 @@ pub struct PriceInfo {
 
 pub fn store_price<S: Storage>(storage: &mut S, asset: &str, price: &PriceInfo) -> StdResult<()> {
     let mut price_bucket: Bucket<S, PriceInfo> = Bucket::new(PREFIX_PRICE, storage);
-    price_bucket.save(asset.as_bytes(), &price)
+    price_bucket.save(asset.as_bytes(), price)
 }
 
 pub fn read_price<S: Storage>(storage: &S, asset: &str) -> StdResult<PriceInfo> {
@@ -83,11 +83,12 @@ pub fn store_feeder<S: Storage>(
     feeder: &CanonicalAddr,
 ) -> StdResult<()> {
     let mut price_bucket: Bucket<S, CanonicalAddr> = Bucket::new(PREFIX_FEEDER, storage);
-    price_bucket.save(asset.as_bytes(), &feeder)
+    price_bucket.save(asset.as_bytes(), feeder)
 }
 
 pub fn read_feeder<S: Storage>(storage: &S, asset: &str) -> StdResult<CanonicalAddr> {
-    let price_bucket: ReadonlyBucket<S, CanonicalAddr> = ReadonlyBucket::new(PREFIX_FEEDER, storage);
+    let price_bucket: ReadonlyBucket<S, CanonicalAddr> =
+        ReadonlyBucket::new(PREFIX_FEEDER, storage);
     let res = price_bucket.load(asset.as_bytes());
     match res {
         Ok(data) => Ok(data),
@@ -4,9 +4,18 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::overseer::{
-    AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse, HandleMsg,
-    InitMsg, QueryMsg, WhitelistResponse,
+    AllCollateralsResponse, AnalyticsContractResponse, AssetMetadataResponse, BorrowLimitResponse,
+    CollateralProtectionResponse, CollateralsResponse, ConfigResponse,
+    EmergencyPriceProposalResponse, EmergencyPriceResponse, EpochPhaseResponse,
+    EpochRecordsResponse, FeedLivenessResponse, HandleMsg, InboxResponse, IndexUnitResponse,
+    InitMsg, InsuranceContractsResponse, InterestRebateResponse, InvariantChecksResponse,
+    LiquidationCandidatesResponse, LtvCheckpointResponse, MaxLtvOverrideResponse,
+    PositionHealthResponse, PositionInsuranceResponse, PositionTransferResponse,
+    PriceCheckResponse, QueryMsg, RegistryResponse, RewardRouteResponse, RiskModuleResponse,
+    SimulateLiquidationResponse, SnapshotHealthResponse, SolvencyResponse, SponsorshipsResponse,
+    WhitelistProposalsResponse, WhitelistResponse,
 };
+use moneymarket::ownership::OwnershipProposalResponse;
 use moneymarket_overseer::state::EpochState;
 
 fn main() {
@@ -22,6 +31,35 @@ fn main() {
     export_schema(&schema_for!(BorrowLimitResponse), &out_dir);
     export_schema(&schema_for!(CollateralsResponse), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(OwnershipProposalResponse), &out_dir);
+    export_schema(&schema_for!(EmergencyPriceResponse), &out_dir);
+    export_schema(&schema_for!(EmergencyPriceProposalResponse), &out_dir);
+    export_schema(&schema_for!(InboxResponse), &out_dir);
+    export_schema(&schema_for!(IndexUnitResponse), &out_dir);
+    export_schema(&schema_for!(SponsorshipsResponse), &out_dir);
     export_schema(&schema_for!(EpochState), &out_dir);
+    export_schema(&schema_for!(EpochRecordsResponse), &out_dir);
+    export_schema(&schema_for!(EpochPhaseResponse), &out_dir);
+    export_schema(&schema_for!(InvariantChecksResponse), &out_dir);
     export_schema(&schema_for!(WhitelistResponse), &out_dir);
+    export_schema(&schema_for!(InterestRebateResponse), &out_dir);
+    export_schema(&schema_for!(LtvCheckpointResponse), &out_dir);
+    export_schema(&schema_for!(PositionTransferResponse), &out_dir);
+    export_schema(&schema_for!(InsuranceContractsResponse), &out_dir);
+    export_schema(&schema_for!(PositionInsuranceResponse), &out_dir);
+    export_schema(&schema_for!(CollateralProtectionResponse), &out_dir);
+    export_schema(&schema_for!(MaxLtvOverrideResponse), &out_dir);
+    export_schema(&schema_for!(RewardRouteResponse), &out_dir);
+    export_schema(&schema_for!(PriceCheckResponse), &out_dir);
+    export_schema(&schema_for!(FeedLivenessResponse), &out_dir);
+    export_schema(&schema_for!(RiskModuleResponse), &out_dir);
+    export_schema(&schema_for!(AnalyticsContractResponse), &out_dir);
+    export_schema(&schema_for!(RegistryResponse), &out_dir);
+    export_schema(&schema_for!(SimulateLiquidationResponse), &out_dir);
+    export_schema(&schema_for!(LiquidationCandidatesResponse), &out_dir);
+    export_schema(&schema_for!(SolvencyResponse), &out_dir);
+    export_schema(&schema_for!(SnapshotHealthResponse), &out_dir);
+    export_schema(&schema_for!(PositionHealthResponse), &out_dir);
+    export_schema(&schema_for!(WhitelistProposalsResponse), &out_dir);
+    export_schema(&schema_for!(AssetMetadataResponse), &out_dir);
 }
@@ -0,0 +1,128 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::state::{
+    read_analytics_contract, read_config, read_pending_kpis, remove_analytics_contract,
+    store_analytics_contract, store_pending_kpis, Config, PendingKpis,
+};
+
+use moneymarket::analytics::{EpochKpis, HandleMsg as AnalyticsHandleMsg};
+use moneymarket::market::EpochStateResponse;
+use moneymarket::overseer::AnalyticsContractResponse;
+
+/// Push the indicators of every epoch to `analytics_contract`
+pub fn update_analytics_contract<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    analytics_contract: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_analytics_contract(
+        &mut deps.storage,
+        &deps.api.canonical_address(&analytics_contract)?,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_analytics_contract"),
+            log("analytics_contract", analytics_contract),
+        ],
+        data: None,
+    })
+}
+
+pub fn disable_analytics_contract<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    remove_analytics_contract(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "disable_analytics_contract")],
+        data: None,
+    })
+}
+
+/// Adds a liquidation to the volumes of the current epoch
+pub(crate) fn record_liquidation<S: Storage>(
+    storage: &mut S,
+    liquidation_value: Uint256,
+    bad_debt: Uint256,
+) -> StdResult<()> {
+    let mut pending_kpis: PendingKpis = read_pending_kpis(storage)?;
+    pending_kpis.liquidation_volume += liquidation_value;
+    pending_kpis.new_bad_debt += bad_debt;
+    store_pending_kpis(storage, &pending_kpis)
+}
+
+/// Closes the volumes of the epoch executed at `height` and
+/// pushes the epoch indicators to the analytics contract, if any
+pub(crate) fn push_epoch_kpis<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    height: u64,
+    deposit_rate: Decimal256,
+    epoch_state: &EpochStateResponse,
+) -> StdResult<Vec<CosmosMsg>> {
+    let pending_kpis: PendingKpis = read_pending_kpis(&deps.storage)?;
+    store_pending_kpis(&mut deps.storage, &PendingKpis::default())?;
+
+    let analytics_contract = match read_analytics_contract(&deps.storage)? {
+        Some(v) => deps.api.human_address(&v)?,
+        None => return Ok(vec![]),
+    };
+
+    Ok(vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: analytics_contract,
+        send: vec![],
+        msg: to_binary(&AnalyticsHandleMsg::RecordEpoch {
+            height,
+            kpis: EpochKpis {
+                deposit_rate,
+                utilization_ratio: compute_utilization_ratio(epoch_state),
+                liquidation_volume: pending_kpis.liquidation_volume,
+                new_bad_debt: pending_kpis.new_bad_debt,
+            },
+        })?,
+    })])
+}
+
+/// Same ratio as the interest model, capped to 1 when
+/// the reserves exceed the market balance
+fn compute_utilization_ratio(epoch_state: &EpochStateResponse) -> Decimal256 {
+    if epoch_state.total_liabilities.is_zero() {
+        return Decimal256::zero();
+    }
+
+    let market_balance = Decimal256::from_uint256(epoch_state.market_balance);
+    if epoch_state.total_reserves >= market_balance {
+        return Decimal256::one();
+    }
+
+    epoch_state.total_liabilities
+        / (market_balance + epoch_state.total_liabilities - epoch_state.total_reserves)
+}
+
+pub fn query_analytics_contract<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<AnalyticsContractResponse> {
+    Ok(AnalyticsContractResponse {
+        analytics_contract: match read_analytics_contract(&deps.storage)? {
+            Some(v) => Some(deps.api.human_address(&v)?),
+            None => None,
+        },
+    })
+}
@@ -0,0 +1,59 @@
+use cosmwasm_std::{Api, Env, Extern, HandleResult, Querier, StdResult, Storage};
+
+use crate::collateral::{lock_collateral, unlock_collateral};
+
+use moneymarket::error::ContractError;
+use moneymarket::tokens::TokensHuman;
+
+/// Max # of (token, amount) pairs in a collateral batch
+const MAX_BATCH_SIZE: usize = 30;
+
+/// Locks the collaterals of a batch, repeated tokens are summed
+pub fn lock_collateral_batch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collaterals: TokensHuman,
+) -> HandleResult {
+    let collaterals = merge_collaterals(collaterals)?;
+    lock_collateral(deps, env, collaterals)
+}
+
+/// Unlocks the collaterals of a batch, repeated tokens are summed.
+/// The borrow limit is checked once with all the unlocks applied
+pub fn unlock_collateral_batch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collaterals: TokensHuman,
+) -> HandleResult {
+    let collaterals = merge_collaterals(collaterals)?;
+    unlock_collateral(deps, env, collaterals)
+}
+
+/// Vaults aggregate the positions of their users, so a batch can
+/// repeat a token. The single lock and unlock abort on repeated tokens
+fn merge_collaterals(collaterals: TokensHuman) -> StdResult<TokensHuman> {
+    if collaterals.len() > MAX_BATCH_SIZE {
+        return Err(ContractError::BatchTooLarge {
+            max: MAX_BATCH_SIZE as u64,
+        }
+        .into());
+    }
+
+    let mut merged: TokensHuman = vec![];
+    for (token, amount) in collaterals {
+        if amount.is_zero() {
+            continue;
+        }
+
+        match merged.iter_mut().find(|c| c.0 == token) {
+            Some(c) => c.1 += amount,
+            None => merged.push((token, amount)),
+        }
+    }
+
+    if merged.is_empty() {
+        return Err(ContractError::EmptyBatch {}.into());
+    }
+
+    Ok(merged)
+}
@@ -1,22 +1,47 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
-    StdError, StdResult, Storage, WasmMsg,
+    log, to_binary, Api, CanonicalAddr, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdResult, Storage, WasmMsg,
 };
 
-use crate::querier::{query_borrower_info, query_liquidation_amount};
+use crate::analytics::record_liquidation;
+use crate::feed_liveness::{is_live_feed, query_dead_feed_price, query_last_price};
+use crate::health::{
+    is_dust_snapshot, is_liquidatable_snapshot, query_snapshot_health, snapshot_borrow_limit,
+    snapshot_liquidation_borrow_limit,
+};
+use crate::index::{query_index_price, to_index_units};
+use crate::insurance::{call_insurance, read_active_insurance};
+use crate::max_ltv_override::read_max_ltv_override;
+use crate::price_check::{assert_prices_converged, has_diverged_price};
+use crate::querier::{
+    query_borrower_info, query_liquidation_amount, query_liquidation_config,
+    query_liquidation_mechanism, query_risk_liquidation_amount,
+};
+use crate::risk::{can_borrow, can_withdraw, read_active_risk_module};
+use crate::sponsorship::{cap_sponsorships, compute_unlockable_collaterals};
 use crate::state::{
-    read_all_collaterals, read_collaterals, read_config, read_whitelist_elem, store_collaterals,
-    Config, WhitelistElem,
+    push_inbox_record, read_all_collaterals, read_asset_metadata, read_collateral_positions,
+    read_collateral_protection, read_collaterals, read_config, read_emergency_price,
+    read_liquidation_price_time, read_whitelist_elem, store_collaterals,
+    store_liquidation_price_time, Config, WhitelistElem,
 };
 
 use moneymarket::custody::HandleMsg as CustodyHandleMsg;
-use moneymarket::liquidation::LiquidationAmountResponse;
+use moneymarket::error::ContractError;
+use moneymarket::liquidation::{
+    ConfigResponse as LiquidationConfigResponse, LiquidationAmountResponse, LiquidationMechanism,
+};
 use moneymarket::market::{BorrowerInfoResponse, HandleMsg as MarketHandleMsg};
 use moneymarket::oracle::PriceResponse;
-use moneymarket::overseer::{AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse};
-use moneymarket::querier::{query_balance, query_price, TimeConstraints};
-use moneymarket::tokens::{Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
+use moneymarket::overseer::{
+    AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, HealthSnapshot, InboxEvent,
+    LiquidatedCollateralResponse, LiquidationCandidateResponse, LiquidationCandidatesResponse,
+    LiquidationOrder, PositionHealthResponse, SimulateLiquidationResponse, SnapshotCollateral,
+    WhitelistResponseElem,
+};
+use moneymarket::querier::{deduct_tax, query_balance, query_price, TimeConstraints};
+use moneymarket::tokens::{Token, Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
 
 pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
@@ -26,9 +51,11 @@ pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
     let borrower_raw = deps.api.canonical_address(&env.message.sender)?;
     let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
 
-    let collaterals: Tokens = collaterals_human.to_raw(&deps)?;
+    let collaterals: Tokens = collaterals_human.to_raw(deps)?;
 
+    let prev_collateral_types = cur_collaterals.len();
     cur_collaterals.add(collaterals.clone());
+    assert_max_collateral_types(&deps.storage, prev_collateral_types, &cur_collaterals)?;
     store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
@@ -47,7 +74,7 @@ pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
     // Logging stuff, so can be removed
     let collateral_logs: Vec<String> = collaterals_human
         .iter()
-        .map(|c| format!("{}{}", c.1, c.0.to_string()))
+        .map(|c| format!("{}{}", c.1, c.0))
         .collect();
 
     Ok(HandleResponse {
@@ -61,6 +88,26 @@ pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/// Rejects locks adding a new collateral type beyond the configured max;
+/// borrowers already above a lowered max can still top up existing types
+pub(crate) fn assert_max_collateral_types<S: Storage>(
+    storage: &S,
+    prev_collateral_types: usize,
+    collaterals: &[(CanonicalAddr, Uint256)],
+) -> StdResult<()> {
+    let config: Config = read_config(storage)?;
+    if collaterals.len() > prev_collateral_types
+        && collaterals.len() as u64 > config.max_collateral_types
+    {
+        return Err(ContractError::TooManyCollateralTypes {
+            max: config.max_collateral_types,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
 pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
@@ -72,27 +119,47 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
     let borrower = env.message.sender;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
-    let collaterals: Tokens = collaterals_human.to_raw(&deps)?;
+    let collaterals: Tokens = collaterals_human.to_raw(deps)?;
 
     // Underflow check is done in sub_collateral
+    let mut unlockable: Tokens =
+        compute_unlockable_collaterals(&deps.storage, &borrower_raw, &cur_collaterals)?;
     if cur_collaterals.sub(collaterals.clone()).is_err() {
-        return Err(StdError::generic_err(
-            "Unlock amount cannot exceed locked amount",
-        ));
+        return Err(ContractError::UnlockExceedsLockedCollateral {}.into());
+    }
+
+    if unlockable.sub(collaterals.clone()).is_err() {
+        return Err(ContractError::NotSponsor {}.into());
     }
 
+    assert_prices_converged(deps, &config, &collaterals, env.block.time)?;
+
     // Compute borrow limit with collaterals except unlock target collaterals
-    let (borrow_limit, _) = compute_borrow_limit(deps, &cur_collaterals, Some(env.block.time))?;
+    let (borrow_limit, _, _) = compute_borrow_limit(
+        deps,
+        &cur_collaterals,
+        Some(env.block.time),
+        read_max_ltv_override(&deps.storage, &borrower_raw)?,
+    )?;
     let borrow_amount_res: BorrowerInfoResponse =
         query_borrower_info(deps, &market, &borrower, env.block.height)?;
     if borrow_limit < borrow_amount_res.loan_amount {
-        return Err(StdError::generic_err(format!(
-            "Unlock amount too high; Loan liability becomes greater than borrow limit: {}",
-            borrow_limit
-        )));
+        return Err(ContractError::UnlockExceedsBorrowLimit { borrow_limit }.into());
+    }
+
+    if !can_withdraw(
+        deps,
+        &borrower,
+        borrow_amount_res.loan_amount,
+        borrow_limit,
+        &cur_collaterals,
+        &collaterals,
+    )? {
+        return Err(ContractError::UnlockRejected {}.into());
     }
 
     store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+    cap_sponsorships(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
     for collateral in collaterals.clone() {
@@ -110,9 +177,15 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
     // Logging stuff, so can be removed
     let collateral_logs: Vec<String> = collaterals_human
         .iter()
-        .map(|c| format!("{}{}", c.1, c.0.to_string()))
+        .map(|c| format!("{}{}", c.1, c.0))
         .collect();
 
+    let health = PositionHealthResponse::new(
+        borrower.clone(),
+        borrow_amount_res.loan_amount,
+        borrow_limit,
+    );
+
     Ok(HandleResponse {
         messages,
         log: vec![
@@ -120,53 +193,297 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
             log("borrower", borrower),
             log("collaterals", collateral_logs.join(",")),
         ],
-        data: None,
+        data: Some(to_binary(&health)?),
     })
 }
 
+/// Outcome of liquidating the borrower at the given block
+pub(crate) struct Liquidation {
+    pub(crate) borrow_amount: Uint256,
+    pub(crate) borrow_limit: Uint256,
+    pub(crate) collateral_prices: Vec<Decimal256>,
+    pub(crate) emergency_price_used: bool,
+    /// collaterals seized from the borrower
+    pub(crate) liquidation_amount: Tokens,
+    /// value of the seized collaterals at the collateral prices
+    pub(crate) liquidation_value: Uint256,
+    /// collaterals left to the borrower
+    pub(crate) remaining_collaterals: Tokens,
+    /// update time of the prices of a liquidation capped by
+    /// the close factor, none without a close factor
+    pub(crate) prices_updated_at: Option<u64>,
+}
+
+/// Shared by the liquidation and its simulation,
+/// `None` when the loan is safely collateralized
+pub(crate) fn compute_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    borrower: &HumanAddr,
+    block_height: u64,
+    block_time: u64,
+) -> StdResult<Option<Liquidation>> {
+    let market = deps.api.human_address(&config.market_contract)?;
+    let borrower_raw = deps.api.canonical_address(borrower)?;
+    let cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    let borrow_amount_res: BorrowerInfoResponse =
+        query_borrower_info(deps, &market, borrower, block_height)?;
+    let borrow_amount = borrow_amount_res.loan_amount;
+
+    // liquidations ignore the max ltv override
+    let (snapshot, emergency_price_used) = load_health_snapshot(
+        deps,
+        config,
+        &cur_collaterals,
+        Some(block_time),
+        None,
+        borrow_amount,
+    )?;
+
+    // borrow limit is equal or bigger than loan amount
+    // cannot liquidation collaterals
+    if !is_liquidatable_snapshot(&snapshot) {
+        return Ok(None);
+    }
+    let borrow_limit = snapshot_liquidation_borrow_limit(&snapshot);
+    let collateral_prices = snapshot_prices(&snapshot);
+
+    // a position is liquidated again under the close factor
+    // only once the prices updated since its last liquidation
+    let prices_updated_at = if config.close_factor.is_zero() {
+        None
+    } else {
+        let prices_updated_at = query_prices_updated_at(deps, config, &cur_collaterals)?;
+        if let Some(last_price_time) = read_liquidation_price_time(&deps.storage, &borrower_raw)? {
+            if prices_updated_at <= last_price_time {
+                return Err(ContractError::LiquidationAwaitingPriceUpdate {
+                    prices_updated_at: last_price_time,
+                }
+                .into());
+            }
+        }
+        Some(prices_updated_at)
+    };
+
+    // liquidation amounts only depend on the value ratios, but
+    // the liquidation threshold applies in the index unit
+    let (liquidation_borrow_amount, liquidation_borrow_limit, liquidation_prices) =
+        match query_index_price(deps, config, Some(block_time))? {
+            Some(index_price) => (
+                to_index_units(borrow_amount, index_price),
+                to_index_units(borrow_limit, index_price),
+                collateral_prices
+                    .iter()
+                    .map(|price| *price / index_price)
+                    .collect(),
+            ),
+            None => (borrow_amount, borrow_limit, collateral_prices.clone()),
+        };
+
+    let liquidation_amount_res: LiquidationAmountResponse = match read_active_risk_module(deps)? {
+        Some(risk_module) => query_risk_liquidation_amount(
+            deps,
+            &risk_module,
+            borrower,
+            liquidation_borrow_amount,
+            liquidation_borrow_limit,
+            &cur_collaterals.to_human(deps)?,
+            liquidation_prices,
+        )?,
+        None => query_liquidation_amount(
+            deps,
+            &deps.api.human_address(&config.liquidation_contract)?,
+            liquidation_borrow_amount,
+            liquidation_borrow_limit,
+            &cur_collaterals.to_human(deps)?,
+            liquidation_prices,
+        )?,
+    };
+
+    // dust positions are liquidated in full regardless of the close factor
+    let liquidation_amount = if is_dust_snapshot(&snapshot) {
+        cur_collaterals.clone()
+    } else {
+        let liquidation_amount = liquidation_amount_res.collaterals.to_raw(deps)?;
+        let protected_collaterals = read_collateral_protection(&deps.storage, &borrower_raw)?;
+        let liquidation_amount = if config.liquidation_order == LiquidationOrder::ProRata
+            && protected_collaterals.is_empty()
+        {
+            liquidation_amount
+        } else {
+            order_liquidation_amount(
+                &deps.storage,
+                config,
+                &protected_collaterals,
+                &cur_collaterals,
+                &collateral_prices,
+                &liquidation_amount,
+            )?
+        };
+        apply_close_factor(config, &cur_collaterals, liquidation_amount)
+    };
+    let mut liquidation_value = Uint256::zero();
+    for (token, amount) in liquidation_amount.iter() {
+        if let Some(i) = cur_collaterals.iter().position(|v| v.0 == *token) {
+            liquidation_value += *amount * collateral_prices[i];
+        }
+    }
+
+    let mut remaining_collaterals = cur_collaterals;
+    remaining_collaterals.sub(liquidation_amount.clone())?;
+
+    Ok(Some(Liquidation {
+        borrow_amount,
+        borrow_limit,
+        collateral_prices,
+        emergency_price_used,
+        liquidation_amount,
+        liquidation_value,
+        remaining_collaterals,
+        prices_updated_at,
+    }))
+}
+
+/// Latest oracle update time among the prices of `collaterals`
+fn query_prices_updated_at<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collaterals: &[Token],
+) -> StdResult<u64> {
+    let mut prices_updated_at = 0u64;
+    for collateral in collaterals.iter() {
+        if let Some((_, last_updated)) =
+            query_last_price(deps, config, &deps.api.human_address(&collateral.0)?)?
+        {
+            prices_updated_at = std::cmp::max(prices_updated_at, last_updated);
+        }
+    }
+
+    Ok(prices_updated_at)
+}
+
+/// Caps the seized amount of each collateral to the close factor of
+/// the borrower holding, a shortfall left is liquidated by a later call
+fn apply_close_factor(
+    config: &Config,
+    cur_collaterals: &[Token],
+    liquidation_amount: Tokens,
+) -> Tokens {
+    if config.close_factor.is_zero() {
+        return liquidation_amount;
+    }
+
+    liquidation_amount
+        .into_iter()
+        .map(|(token, amount)| {
+            let max_amount = match cur_collaterals.iter().find(|c| c.0 == token) {
+                Some(collateral) => collateral.1 * config.close_factor,
+                None => amount,
+            };
+            (token, std::cmp::min(amount, max_amount))
+        })
+        .collect()
+}
+
+fn assert_liquidatable(liquidation: Option<Liquidation>) -> StdResult<Liquidation> {
+    match liquidation {
+        Some(v) => Ok(v),
+        None => Err(ContractError::SafelyCollateralized {}.into()),
+    }
+}
+
 pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     borrower: HumanAddr,
 ) -> HandleResult {
     let config: Config = read_config(&deps.storage)?;
-    let market = deps.api.human_address(&config.market_contract)?;
+    let liquidation = assert_liquidatable(compute_liquidation(
+        deps,
+        &config,
+        &borrower,
+        env.block.height,
+        env.block.time,
+    )?)?;
 
     let borrower_raw = deps.api.canonical_address(&borrower)?;
-    let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
-
-    // Compute borrow limit with collaterals except unlock target collaterals
-    let (borrow_limit, collateral_prices) =
-        compute_borrow_limit(deps, &cur_collaterals, Some(env.block.time))?;
-    let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps, &market, &borrower, env.block.height)?;
-    let borrow_amount = borrow_amount_res.loan_amount;
-
-    // borrow limit is equal or bigger than loan amount
-    // cannot liquidation collaterals
-    if borrow_limit >= borrow_amount {
-        return Err(StdError::generic_err(
-            "Cannot liquidate safely collateralized loan",
-        ));
+    if let Some(insurance_contract) = read_active_insurance(&deps.storage, &borrower_raw)? {
+        return call_insurance(deps, env, borrower, insurance_contract, &liquidation);
     }
 
-    let liquidation_amount_res: LiquidationAmountResponse = query_liquidation_amount(
-        &deps,
-        &deps.api.human_address(&config.liquidation_contract)?,
+    execute_liquidation(deps, env, &config, borrower, liquidation)
+}
+
+/// Seizes the collaterals of the liquidation on behalf
+/// of the sender and repays the loan with the proceeds
+pub(crate) fn execute_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    config: &Config,
+    borrower: HumanAddr,
+    liquidation: Liquidation,
+) -> HandleResult {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let Liquidation {
         borrow_amount,
         borrow_limit,
-        &cur_collaterals.to_human(&deps)?,
-        collateral_prices,
-    )?;
+        emergency_price_used,
+        liquidation_amount,
+        liquidation_value,
+        remaining_collaterals: cur_collaterals,
+        prices_updated_at,
+        ..
+    } = liquidation;
 
-    let liquidation_amount = liquidation_amount_res.collaterals.to_raw(&deps)?;
+    // Auctioned collaterals repay the loan as they are sold, after the
+    // liquidation. The loan they leave is not written off as bad debt
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+    let mut auctioned_collaterals: usize = 0;
+    for collateral in liquidation_amount.iter() {
+        if let LiquidationMechanism::DutchAuction { .. } = query_liquidation_mechanism(
+            deps,
+            &liquidation_contract,
+            &deps.api.human_address(&collateral.0)?,
+        )?
+        .mechanism
+        {
+            auctioned_collaterals += 1;
+        }
+    }
+
+    // the loan the seized collaterals cannot cover is
+    // bad debt once the borrower has no collateral left
+    let bad_debt = if cur_collaterals.is_empty()
+        && auctioned_collaterals == 0
+        && borrow_amount > liquidation_value
+    {
+        borrow_amount - liquidation_value
+    } else {
+        Uint256::zero()
+    };
+    record_liquidation(&mut deps.storage, liquidation_value, bad_debt)?;
+    if let Some(prices_updated_at) = prices_updated_at {
+        store_liquidation_price_time(&mut deps.storage, &borrower_raw, prices_updated_at)?;
+    }
 
     // Store left collaterals
-    cur_collaterals.sub(liquidation_amount.clone())?;
     store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+    cap_sponsorships(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+    push_inbox_record(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        InboxEvent::Liquidated {
+            liquidator: env.message.sender.clone(),
+            borrow_amount,
+            borrow_limit,
+        },
+    )?;
 
+    let borrower_log = borrower.to_string();
     let market_contract = deps.api.human_address(&config.market_contract)?;
-    let prev_balance: Uint256 = query_balance(&deps, &market_contract, config.stable_denom)?;
+    let prev_balance: Uint256 =
+        query_balance(deps, &market_contract, config.stable_denom.to_string())?;
 
     let liquidation_messages: Vec<CosmosMsg> = liquidation_amount
         .iter()
@@ -186,24 +503,189 @@ pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
         .filter(|msg| msg.is_ok())
         .collect::<StdResult<Vec<CosmosMsg>>>()?;
 
+    let mut messages: Vec<CosmosMsg> = liquidation_messages;
+
+    // nothing is repaid yet when every collateral is auctioned
+    if auctioned_collaterals == 0 || auctioned_collaterals < liquidation_amount.len() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: market_contract.clone(),
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
+                borrower: borrower.clone(),
+                prev_balance,
+            })?,
+        }));
+    }
+
+    // the loan left once all collaterals are sold is bad debt
+    if cur_collaterals.is_empty() && auctioned_collaterals == 0 {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: market_contract,
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::WriteOffBadDebt { borrower })?,
+        }));
+    }
+
     Ok(HandleResponse {
-        messages: vec![
-            liquidation_messages,
-            vec![CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: market_contract,
-                send: vec![],
-                msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
-                    borrower,
-                    prev_balance,
-                })?,
-            })],
+        messages,
+        log: [
+            vec![
+                log("action", "liquidate_collateral"),
+                log("borrower", borrower_log),
+                log("emergency_price_used", emergency_price_used),
+            ],
+            if auctioned_collaterals > 0 {
+                vec![log("auctioned_collaterals", auctioned_collaterals)]
+            } else {
+                vec![]
+            },
         ]
         .concat(),
-        log: vec![],
         data: None,
     })
 }
 
+/// Runs the liquidation of the borrower at the given block without
+/// executing it. Bids are assumed to apply the max premium rate, so
+/// the simulated repay amount is the lowest the market can receive
+pub fn query_simulate_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    block_height: u64,
+    block_time: u64,
+) -> StdResult<SimulateLiquidationResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let liquidation = assert_liquidatable(compute_liquidation(
+        deps,
+        &config,
+        &borrower,
+        block_height,
+        block_time,
+    )?)?;
+    let liquidation_config: LiquidationConfigResponse =
+        query_liquidation_config(deps, &deps.api.human_address(&config.liquidation_contract)?)?;
+
+    let cur_collaterals: Tokens =
+        read_collaterals(&deps.storage, &deps.api.canonical_address(&borrower)?);
+    let premium_rate = liquidation_config.max_premium_rate;
+
+    let mut repay_amount = Uint256::zero();
+    let mut collaterals: Vec<LiquidatedCollateralResponse> = vec![];
+    for collateral in liquidation.liquidation_amount.iter() {
+        // prices are listed in the order of the borrower collaterals
+        let price = match cur_collaterals
+            .iter()
+            .position(|c| c.0 == collateral.0)
+            .and_then(|i| liquidation.collateral_prices.get(i))
+        {
+            Some(v) => *v,
+            None => return Err(ContractError::LiquidatedCollateralNotFound {}.into()),
+        };
+
+        let required_stable = collateral.1 * price * (Decimal256::one() - premium_rate);
+        let collateral_repay_amount: Uint256 = deduct_tax(
+            deps,
+            Coin {
+                denom: config.stable_denom.clone(),
+                amount: (required_stable - required_stable * liquidation_config.bid_fee).into(),
+            },
+        )?
+        .amount
+        .into();
+
+        repay_amount += collateral_repay_amount;
+        collaterals.push(LiquidatedCollateralResponse {
+            collateral_token: deps.api.human_address(&collateral.0)?,
+            amount: collateral.1,
+            price,
+            premium_rate,
+            repay_amount: collateral_repay_amount,
+        });
+    }
+
+    let loan_amount_after = if liquidation.borrow_amount > repay_amount {
+        liquidation.borrow_amount - repay_amount
+    } else {
+        Uint256::zero()
+    };
+    let (borrow_limit_after, _, _) = compute_borrow_limit(
+        deps,
+        &liquidation.remaining_collaterals,
+        Some(block_time),
+        None,
+    )?;
+
+    Ok(SimulateLiquidationResponse {
+        borrower,
+        borrow_amount: liquidation.borrow_amount,
+        borrow_limit: liquidation.borrow_limit,
+        collaterals,
+        repay_amount,
+        loan_amount_after,
+        borrow_limit_after,
+        emergency_price_used: liquidation.emergency_price_used,
+    })
+}
+
+/// Scans a page of collateral positions for loans exceeding their
+/// borrow limit, the same check the liquidation starts from
+pub fn query_liquidation_candidates<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+    block_height: u64,
+    block_time: u64,
+) -> StdResult<LiquidationCandidatesResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let positions = read_collateral_positions(&deps.storage, start_after, limit)?;
+    let last_scanned = match positions.last() {
+        Some((borrower_raw, _)) => Some(deps.api.human_address(borrower_raw)?),
+        None => None,
+    };
+
+    let mut candidates: Vec<LiquidationCandidateResponse> = vec![];
+    for (borrower_raw, collaterals) in positions.iter() {
+        // nothing to seize from emptied positions
+        if collaterals.is_empty() {
+            continue;
+        }
+
+        let borrower = deps.api.human_address(borrower_raw)?;
+        let loan_amount = query_borrower_info(deps, &market, &borrower, block_height)?.loan_amount;
+        let (snapshot, _) = load_health_snapshot(
+            deps,
+            &config,
+            collaterals,
+            Some(block_time),
+            None,
+            loan_amount,
+        )?;
+        if !is_liquidatable_snapshot(&snapshot) {
+            continue;
+        }
+        let borrow_limit = snapshot_liquidation_borrow_limit(&snapshot);
+
+        candidates.push(LiquidationCandidateResponse {
+            borrower,
+            loan_amount,
+            borrow_limit,
+            shortfall: loan_amount - borrow_limit,
+        });
+    }
+
+    Ok(LiquidationCandidatesResponse {
+        candidates,
+        last_scanned,
+    })
+}
+
 pub fn query_collaterals<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     borrower: HumanAddr,
@@ -231,46 +713,313 @@ pub fn query_all_collaterals<S: Storage, A: Api, Q: Querier>(
         None
     };
 
-    let all_collaterals: Vec<CollateralsResponse> =
-        read_all_collaterals(&deps, start_after, limit)?;
+    let all_collaterals: Vec<CollateralsResponse> = read_all_collaterals(deps, start_after, limit)?;
 
     Ok(AllCollateralsResponse { all_collaterals })
 }
 
-#[allow(clippy::ptr_arg)]
-pub(crate) fn compute_borrow_limit<S: Storage, A: Api, Q: Querier>(
+/// Returns the price of the collateral in stable denom and whether
+/// an unexpired emergency price was used instead of the oracle price.
+/// Emergency prices are only applied when the block time is known.
+pub(crate) fn query_collateral_price<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
-    collaterals: &Tokens,
+    config: &Config,
+    collateral_token: &CanonicalAddr,
     block_time: Option<u64>,
-) -> StdResult<(Uint256, Vec<Decimal256>)> {
-    let config: Config = read_config(&deps.storage)?;
+) -> StdResult<(Decimal256, bool)> {
+    if let Some(block_time) = block_time {
+        if let Some(emergency_price) = read_emergency_price(&deps.storage, collateral_token) {
+            if block_time < emergency_price.expires_at {
+                return Ok((emergency_price.price, true));
+            }
+        }
+    }
+
+    let price: PriceResponse = query_price(
+        deps,
+        &deps.api.human_address(&config.oracle_contract)?,
+        (deps.api.human_address(collateral_token)?).to_string(),
+        config.stable_denom.to_string(),
+        block_time.map(|block_time| TimeConstraints {
+            block_time,
+            valid_timeframe: config.price_timeframe,
+        }),
+    )?;
+
+    Ok((price.rate, false))
+}
+
+/// Returns an error listing every whitelisted collateral whose oracle
+/// price is unavailable or older than the price timeframe at `block_time`.
+/// Collaterals with an unexpired emergency price are considered fresh,
+/// those with a dead feed are skipped and returned.
+pub(crate) fn assert_fresh_prices<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    whitelist: &[WhitelistResponseElem],
+    block_time: u64,
+) -> StdResult<Vec<HumanAddr>> {
     let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
+    let valid_update_time = block_time.saturating_sub(config.price_timeframe);
 
-    let mut borrow_limit: Uint256 = Uint256::zero();
-    let mut collateral_prices: Vec<Decimal256> = vec![];
-    for collateral in collaterals.iter() {
-        let collateral_token = collateral.0.clone();
-        let collateral_amount = collateral.1;
+    let mut stale_prices: Vec<String> = vec![];
+    let mut dead_feeds: Vec<HumanAddr> = vec![];
+    for elem in whitelist.iter() {
+        let collateral_token = deps.api.canonical_address(&elem.collateral_token)?;
+        if let Some(emergency_price) = read_emergency_price(&deps.storage, &collateral_token) {
+            if block_time < emergency_price.expires_at {
+                continue;
+            }
+        }
 
-        let price: PriceResponse = query_price(
-            &deps,
+        match query_price(
+            deps,
             &oracle_contract,
-            (deps.api.human_address(&collateral_token)?).to_string(),
+            elem.collateral_token.to_string(),
             config.stable_denom.to_string(),
-            block_time.map(|block_time| TimeConstraints {
-                block_time,
-                valid_timeframe: config.price_timeframe,
-            }),
-        )?;
+            None,
+        ) {
+            Ok(price) => {
+                let last_updated = std::cmp::min(price.last_updated_base, price.last_updated_quote);
+                if !is_live_feed(config, last_updated, block_time) {
+                    dead_feeds.push(elem.collateral_token.clone());
+                } else if last_updated < valid_update_time {
+                    stale_prices.push(format!(
+                        "{} last updated at {}",
+                        elem.collateral_token, last_updated
+                    ));
+                }
+            }
+            Err(_) => stale_prices.push(format!("{} price unavailable", elem.collateral_token)),
+        }
+    }
+
+    if !stale_prices.is_empty() {
+        return Err(ContractError::StalePrices {
+            valid_from: valid_update_time,
+            prices: stale_prices.join(", "),
+        }
+        .into());
+    }
+
+    Ok(dead_feeds)
+}
+
+/// Reallocates the value seized by `liquidation_amount`. The collaterals
+/// not protected by the borrower are seized first, pro rata or the most
+/// liquid first depending on the liquidation order; the protected ones only
+/// cover what is left, the first listed being seized last
+#[allow(clippy::ptr_arg)]
+fn order_liquidation_amount<S: Storage>(
+    storage: &S,
+    config: &Config,
+    protected_collaterals: &[CanonicalAddr],
+    collaterals: &Tokens,
+    collateral_prices: &[Decimal256],
+    liquidation_amount: &Tokens,
+) -> StdResult<Tokens> {
+    let mut remaining_value = Uint256::zero();
+    let mut unprotected_collaterals: Vec<(Option<u8>, &Token, Decimal256)> = vec![];
+    for (collateral, price) in collaterals.iter().zip(collateral_prices.iter()) {
+        if let Some(seized) = liquidation_amount.iter().find(|v| v.0 == collateral.0) {
+            remaining_value += seized.1 * *price;
+        }
+
+        if !protected_collaterals.contains(&collateral.0) {
+            // the collaterals without risk metadata come last
+            let liquidity_score = match config.liquidation_order {
+                LiquidationOrder::ProRata => None,
+                LiquidationOrder::MostLiquidFirst => {
+                    read_asset_metadata(storage, &collateral.0)?.map(|v| v.metadata.liquidity_score)
+                }
+            };
+            unprotected_collaterals.push((liquidity_score, collateral, *price));
+        }
+    }
+
+    let mut seized_collaterals = match config.liquidation_order {
+        LiquidationOrder::ProRata => seize_pro_rata(
+            unprotected_collaterals.iter().map(|v| (v.1, v.2)).collect(),
+            &mut remaining_value,
+        ),
+        LiquidationOrder::MostLiquidFirst => {
+            // ties keep the order of the collaterals
+            unprotected_collaterals.sort_by_key(|v| std::cmp::Reverse(v.0));
+            seize_in_order(
+                unprotected_collaterals.iter().map(|v| (v.1, v.2)).collect(),
+                &mut remaining_value,
+            )
+        }
+    };
+
+    let protected_collaterals: Vec<(&Token, Decimal256)> = protected_collaterals
+        .iter()
+        .rev()
+        .filter_map(|token| {
+            collaterals
+                .iter()
+                .position(|v| v.0 == *token)
+                .map(|i| (&collaterals[i], collateral_prices[i]))
+        })
+        .collect();
+    seized_collaterals.extend(seize_in_order(protected_collaterals, &mut remaining_value));
+
+    Ok(seized_collaterals)
+}
+
+/// Seizes the collaterals one after the other until `remaining_value`
+/// is covered, the last one is rounded up
+fn seize_in_order(collaterals: Vec<(&Token, Decimal256)>, remaining_value: &mut Uint256) -> Tokens {
+    let mut seized_collaterals: Tokens = vec![];
+    for (collateral, price) in collaterals {
+        if remaining_value.is_zero() {
+            break;
+        }
+
+        if price.is_zero() {
+            continue;
+        }
+
+        let collateral_value = collateral.1 * price;
+        let amount = if collateral_value <= *remaining_value {
+            *remaining_value = *remaining_value - collateral_value;
+            collateral.1
+        } else {
+            let amount = Decimal256::from_uint256(*remaining_value) / price * Uint256::one();
+            let amount = if amount * price < *remaining_value {
+                amount + Uint256::one()
+            } else {
+                amount
+            };
+
+            *remaining_value = Uint256::zero();
+            std::cmp::min(amount, collateral.1)
+        };
+
+        if !amount.is_zero() {
+            seized_collaterals.push((collateral.0.clone(), amount));
+        }
+    }
+
+    seized_collaterals
+}
+
+/// Seizes the same share of every collateral to cover
+/// `remaining_value`, the amounts are rounded up
+fn seize_pro_rata(collaterals: Vec<(&Token, Decimal256)>, remaining_value: &mut Uint256) -> Tokens {
+    if remaining_value.is_zero() {
+        return vec![];
+    }
+
+    let collaterals_value = collaterals
+        .iter()
+        .fold(Uint256::zero(), |acc, (collateral, price)| {
+            acc + collateral.1 * *price
+        });
+
+    let ratio = if collaterals_value <= *remaining_value {
+        *remaining_value = *remaining_value - collaterals_value;
+        Decimal256::one()
+    } else {
+        let ratio = Decimal256::from_uint256(*remaining_value)
+            / Decimal256::from_uint256(collaterals_value);
+        *remaining_value = Uint256::zero();
+        ratio
+    };
+
+    collaterals
+        .iter()
+        .map(|(collateral, _)| {
+            let amount = collateral.1 * ratio;
+            let amount = if Decimal256::from_uint256(amount)
+                < Decimal256::from_uint256(collateral.1) * ratio
+            {
+                amount + Uint256::one()
+            } else {
+                amount
+            };
+
+            (collateral.0.clone(), std::cmp::min(amount, collateral.1))
+        })
+        .filter(|v| !v.1.is_zero())
+        .collect()
+}
+
+/// Snapshot of the position holding `collaterals`, with the prices and
+/// the max ltvs read at `block_time`, and whether an emergency price
+/// was used. The health checks of the overseer are computed over it
+pub(crate) fn load_health_snapshot<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+    max_ltv_override: Option<Decimal256>,
+    loan_amount: Uint256,
+) -> StdResult<(HealthSnapshot, bool)> {
+    let mut snapshot_collaterals: Vec<SnapshotCollateral> = vec![];
+    let mut emergency_price_used = false;
+    for collateral in collaterals.iter() {
+        let dead_feed_price = match block_time {
+            Some(block_time) => query_dead_feed_price(deps, config, &collateral.0, block_time)?,
+            None => None,
+        };
+
+        let (price, is_emergency_price) = match dead_feed_price {
+            Some(price) => (price, false),
+            None => query_collateral_price(deps, config, &collateral.0, block_time)?,
+        };
+        emergency_price_used |= is_emergency_price;
 
         let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
-        let collateral_value = collateral_amount * price.rate;
-        borrow_limit += collateral_value * elem.max_ltv;
-        collateral_prices.push(price.rate);
+        snapshot_collaterals.push(SnapshotCollateral {
+            collateral_token: deps.api.human_address(&collateral.0)?,
+            amount: collateral.1,
+            price,
+            max_ltv: elem.max_ltv,
+            dead_feed: dead_feed_price.is_some(),
+        });
     }
 
+    Ok((
+        HealthSnapshot {
+            loan_amount,
+            collaterals: snapshot_collaterals,
+            max_ltv_override,
+            min_position_collateral_value: config.min_position_collateral_value,
+        },
+        emergency_price_used,
+    ))
+}
+
+/// Prices of the snapshot collaterals, in the order of the position
+fn snapshot_prices(snapshot: &HealthSnapshot) -> Vec<Decimal256> {
+    snapshot.collaterals.iter().map(|v| v.price).collect()
+}
+
+/// Borrow limit of `collaterals`, each collateral capped to
+/// the borrower `max_ltv_override` when it is lower
+pub(crate) fn compute_borrow_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+    max_ltv_override: Option<Decimal256>,
+) -> StdResult<(Uint256, Vec<Decimal256>, bool)> {
+    let config: Config = read_config(&deps.storage)?;
+    let (snapshot, emergency_price_used) = load_health_snapshot(
+        deps,
+        &config,
+        collaterals,
+        block_time,
+        max_ltv_override,
+        Uint256::zero(),
+    )?;
+
     // returns borrow_limit with collaterals value in stable denom
-    Ok((borrow_limit, collateral_prices))
+    Ok((
+        snapshot_borrow_limit(&snapshot),
+        snapshot_prices(&snapshot),
+        emergency_price_used,
+    ))
 }
 
 pub fn query_borrow_limit<S: Storage, A: Api, Q: Querier>(
@@ -278,13 +1027,49 @@ pub fn query_borrow_limit<S: Storage, A: Api, Q: Querier>(
     borrower: HumanAddr,
     block_time: Option<u64>,
 ) -> StdResult<BorrowLimitResponse> {
-    let collaterals = read_collaterals(&deps.storage, &deps.api.canonical_address(&borrower)?);
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals = read_collaterals(&deps.storage, &borrower_raw);
 
     // Compute borrow limit with collaterals
-    let (borrow_limit, _) = compute_borrow_limit(deps, &collaterals, block_time)?;
+    let config: Config = read_config(&deps.storage)?;
+    let (snapshot, emergency_price_used) = load_health_snapshot(
+        deps,
+        &config,
+        &collaterals,
+        block_time,
+        read_max_ltv_override(&deps.storage, &borrower_raw)?,
+        Uint256::zero(),
+    )?;
+    let collateral_prices = snapshot_prices(&snapshot);
+    let liquidation_borrow_limit = snapshot_liquidation_borrow_limit(&snapshot);
+
+    // dust positions, which SnapshotHealth gives no borrow limit,
+    // positions holding a collateral whose oracle price diverges from
+    // the DEX price and borrowers rejected by the risk module cannot
+    // borrow any further
+    let borrow_limit = query_snapshot_health(snapshot).borrow_limit;
+    let borrow_limit = if !borrow_limit.is_zero()
+        && !has_diverged_price(deps, &config, &collaterals, &collateral_prices)?
+        && can_borrow(
+            deps,
+            &borrower,
+            borrow_limit,
+            &collaterals,
+            collateral_prices,
+        )? {
+        borrow_limit
+    } else {
+        Uint256::zero()
+    };
+
+    let index_borrow_limit = query_index_price(deps, &config, block_time)?
+        .map(|index_price| to_index_units(borrow_limit, index_price));
 
     Ok(BorrowLimitResponse {
         borrower,
         borrow_limit,
+        liquidation_borrow_limit,
+        emergency_price_used,
+        index_borrow_limit,
     })
 }
@@ -1,28 +1,72 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Env, Extern, HandleResponse,
+    log, to_binary, Api, Binary, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse,
     HandleResult, HumanAddr, InitResponse, InitResult, MigrateResponse, MigrateResult, Querier,
-    StdError, StdResult, Storage, WasmMsg,
+    StdError, StdResult, Storage,
 };
 
+use crate::analytics::{
+    disable_analytics_contract, query_analytics_contract, update_analytics_contract,
+};
+use crate::batch::{lock_collateral_batch, unlock_collateral_batch};
 use crate::collateral::{
     liquidate_collateral, lock_collateral, query_all_collaterals, query_borrow_limit,
-    query_collaterals, unlock_collateral,
+    query_collaterals, query_liquidation_candidates, query_simulate_liquidation, unlock_collateral,
+};
+use crate::epoch::{
+    execute_epoch_operations, execute_epoch_step, query_epoch_phase, update_epoch_state,
+};
+use crate::feed_liveness::query_feed_liveness;
+use crate::health::query_snapshot_health;
+use crate::inbox::{prune_inbox, query_inbox, register_inbox, unregister_inbox};
+use crate::index::{disable_index_unit, query_index_unit, update_index_unit};
+use crate::insurance::{
+    attach_insurance, deregister_insurance, detach_insurance, finish_insured_liquidation,
+    query_insurance_contracts, query_position_insurance, register_insurance,
+};
+use crate::invariants::{assert_invariants, query_invariant_checks, update_invariant_checks};
+use crate::max_ltv_override::{query_max_ltv_override, update_max_ltv_override};
+use crate::position::{
+    accept_position, cancel_position_transfer, query_position_transfer, transfer_position,
+};
+use crate::price_check::{query_price_check, update_price_check};
+use crate::protection::{query_collateral_protection, update_collateral_protection};
+use crate::protocol_mode::assert_protocol_mode;
+use crate::rebate::{
+    checkpoint_ltv, query_interest_rebate, query_ltv_checkpoint, update_interest_rebate,
+    update_rebate_ltv,
+};
+use crate::registry::{
+    assert_registry_synced, disable_registry, query_registry, refresh_registry, register_registry,
 };
-use crate::querier::query_epoch_state;
+use crate::reward_route::{query_reward_route, update_reward_route};
+use crate::risk::{disable_risk_module, query_risk_module, update_risk_module};
+use crate::solvency::query_solvency;
+use crate::sponsorship::{lock_collateral_for, query_sponsorships, unlock_collateral_for};
 use crate::state::{
-    read_config, read_epoch_state, read_whitelist, read_whitelist_elem, store_config,
-    store_epoch_state, store_whitelist_elem, Config, EpochState, WhitelistElem,
+    push_inbox_record, read_collaterals, read_config, read_emergency_price,
+    read_emergency_price_proposal, read_epoch_records, read_epoch_state, read_inbox_owners,
+    read_whitelist, read_whitelist_elem, remove_emergency_price_proposal, store_config,
+    store_emergency_price, store_emergency_price_proposal, store_epoch_state, store_whitelist_elem,
+    Config, EmergencyPrice, EmergencyPriceProposal, EpochState, WhitelistElem,
+};
+use crate::swap::{finish_swap_collateral, swap_collateral};
+use crate::whitelist_proposal::{
+    confirm_whitelist, propose_whitelist, query_asset_metadata, query_whitelist_proposal,
+    query_whitelist_proposals, reject_whitelist,
 };
 
-use moneymarket::custody::HandleMsg as CustodyHandleMsg;
-use moneymarket::market::EpochStateResponse;
-use moneymarket::market::HandleMsg as MarketHandleMsg;
+use moneymarket::error::ContractError;
 use moneymarket::overseer::{
-    ConfigResponse, HandleMsg, InitMsg, MigrateMsg, QueryMsg, WhitelistResponse,
+    ConfigResponse, EmergencyPriceProposalResponse, EmergencyPriceResponse, EpochRecordsResponse,
+    HandleMsg, InboxEvent, InitMsg, LiquidationOrder, MigrateMsg, QueryMsg, WhitelistResponse,
     WhitelistResponseElem,
 };
-use moneymarket::querier::{deduct_tax, query_balance};
+use moneymarket::ownership::{accept_ownership, propose_new_owner, query_ownership_proposal};
+use moneymarket::protocol_mode::{refresh_protocol_mode, register_protocol_controller};
+
+pub const DEFAULT_MAX_COLLATERAL_TYPES: u64 = 10;
+pub const DEFAULT_MAX_EMERGENCY_PRICE_DURATION: u64 = 604800;
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
@@ -44,6 +88,16 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
             buffer_distribution_factor: msg.buffer_distribution_factor,
             anc_purchase_factor: msg.anc_purchase_factor,
             price_timeframe: msg.price_timeframe,
+            guardians: vec![deps.api.canonical_address(&msg.owner_addr)?],
+            guardian_threshold: 1,
+            max_collateral_types: DEFAULT_MAX_COLLATERAL_TYPES,
+            swap_router: CanonicalAddr::default(),
+            min_position_collateral_value: Uint256::zero(),
+            liquidation_order: LiquidationOrder::ProRata,
+            max_feed_gap: 0,
+            max_catch_up_epochs: 0,
+            close_factor: Decimal256::zero(),
+            max_emergency_price_duration: DEFAULT_MAX_EMERGENCY_PRICE_DURATION,
         },
     )?;
 
@@ -65,10 +119,30 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     msg: HandleMsg,
+) -> HandleResult {
+    assert_registry_synced(deps, env.block.height, &msg)?;
+    assert_protocol_mode(deps, env.block.height, &msg)?;
+    let block_height = env.block.height;
+    // the owner can still turn the checks off once they fail
+    let check_invariants = !matches!(msg, HandleMsg::UpdateInvariantChecks { .. });
+    let res = dispatch(deps, env, msg)?;
+
+    if check_invariants {
+        assert_invariants(deps, block_height)?;
+    }
+
+    Ok(res)
+}
+
+/// Runs the handler of `msg`, without the registry sync, the protocol
+/// mode and the invariant checks `handle` runs around it
+fn dispatch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
 ) -> HandleResult {
     match msg {
         HandleMsg::UpdateConfig {
-            owner_addr,
             oracle_contract,
             liquidation_contract,
             threshold_deposit_rate,
@@ -77,10 +151,19 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             anc_purchase_factor,
             epoch_period,
             price_timeframe,
+            guardians,
+            guardian_threshold,
+            max_collateral_types,
+            swap_router,
+            min_position_collateral_value,
+            liquidation_order,
+            max_feed_gap,
+            max_catch_up_epochs,
+            close_factor,
+            max_emergency_price_duration,
         } => update_config(
             deps,
             env,
-            owner_addr,
             oracle_contract,
             liquidation_contract,
             threshold_deposit_rate,
@@ -89,7 +172,29 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             anc_purchase_factor,
             epoch_period,
             price_timeframe,
+            guardians,
+            guardian_threshold,
+            max_collateral_types,
+            swap_router,
+            min_position_collateral_value,
+            liquidation_order,
+            max_feed_gap,
+            max_catch_up_epochs,
+            close_factor,
+            max_emergency_price_duration,
         ),
+        HandleMsg::ProposeNewOwner {
+            new_owner,
+            expiry_height,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_new_owner(deps, env, &config.owner_addr, new_owner, expiry_height)
+        }
+        HandleMsg::AcceptOwnership {} => accept_ownership(deps, env, |storage, new_owner| {
+            let mut config: Config = read_config(storage)?;
+            config.owner_addr = new_owner;
+            store_config(storage, &config)
+        }),
         HandleMsg::Whitelist {
             name,
             symbol,
@@ -110,22 +215,195 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             custody_contract,
             max_ltv,
         } => update_whitelist(deps, env, collateral_token, custody_contract, max_ltv),
+        HandleMsg::ConfirmWhitelist { collateral_token } => {
+            confirm_whitelist(deps, env, collateral_token)
+        }
+        HandleMsg::RejectWhitelist { collateral_token } => {
+            reject_whitelist(deps, env, collateral_token)
+        }
+        HandleMsg::UpdateRewardRoute {
+            collateral_token,
+            route,
+        } => update_reward_route(deps, env, collateral_token, route),
+        HandleMsg::UpdatePriceCheck {
+            collateral_token,
+            price_check,
+        } => update_price_check(deps, env, collateral_token, price_check),
+        HandleMsg::UpdateInterestRebate { rebate_rate } => {
+            update_interest_rebate(deps, env, rebate_rate)
+        }
+        HandleMsg::UpdateRebateLtv {
+            collateral_token,
+            rebate_ltv,
+        } => update_rebate_ltv(deps, env, collateral_token, rebate_ltv),
+        HandleMsg::UpdateIndexUnit {
+            index_oracle,
+            index_asset,
+        } => update_index_unit(deps, env, index_oracle, index_asset),
+        HandleMsg::DisableIndexUnit {} => disable_index_unit(deps, env),
+        HandleMsg::UpdateRiskModule { risk_module } => update_risk_module(deps, env, risk_module),
+        HandleMsg::DisableRiskModule {} => disable_risk_module(deps, env),
+        HandleMsg::RegisterProtocolController {
+            protocol_controller,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            register_protocol_controller(deps, env, &config.owner_addr, protocol_controller)
+        }
+        HandleMsg::UpdateAnalyticsContract { analytics_contract } => {
+            update_analytics_contract(deps, env, analytics_contract)
+        }
+        HandleMsg::DisableAnalyticsContract {} => disable_analytics_contract(deps, env),
+        HandleMsg::RegisterRegistry { registry_contract } => {
+            register_registry(deps, env, registry_contract)
+        }
+        HandleMsg::DisableRegistry {} => disable_registry(deps, env),
+        HandleMsg::UpdateInvariantChecks { enabled } => update_invariant_checks(deps, env, enabled),
+        HandleMsg::RegisterInsurance { insurance_contract } => {
+            register_insurance(deps, env, insurance_contract)
+        }
+        HandleMsg::DeregisterInsurance { insurance_contract } => {
+            deregister_insurance(deps, env, insurance_contract)
+        }
+        HandleMsg::AdminBatch { ops } => admin_batch(deps, env, ops),
         HandleMsg::ExecuteEpochOperations {} => execute_epoch_operations(deps, env),
+        HandleMsg::ExecuteEpochStep {} => execute_epoch_step(deps, env),
         HandleMsg::UpdateEpochState {
             interest_buffer,
             distributed_interest,
         } => update_epoch_state(deps, env, interest_buffer, distributed_interest),
+        HandleMsg::FinishSwapCollateral {} => finish_swap_collateral(deps, env),
+        HandleMsg::FinishInsuredLiquidation {
+            borrower,
+            liquidator,
+        } => finish_insured_liquidation(deps, env, borrower, liquidator),
+        HandleMsg::SetEmergencyPrice {
+            asset,
+            price,
+            expires_at,
+        } => set_emergency_price(deps, env, asset, price, expires_at),
         HandleMsg::LockCollateral { collaterals } => lock_collateral(deps, env, collaterals),
         HandleMsg::UnlockCollateral { collaterals } => unlock_collateral(deps, env, collaterals),
+        HandleMsg::LockCollateralBatch { collaterals } => {
+            lock_collateral_batch(deps, env, collaterals)
+        }
+        HandleMsg::UnlockCollateralBatch { collaterals } => {
+            unlock_collateral_batch(deps, env, collaterals)
+        }
+        HandleMsg::LockCollateralFor {
+            borrower,
+            collaterals,
+            withdraw_allowed,
+        } => lock_collateral_for(deps, env, borrower, collaterals, withdraw_allowed),
+        HandleMsg::UnlockCollateralFor {
+            borrower,
+            collaterals,
+        } => unlock_collateral_for(deps, env, borrower, collaterals),
+        HandleMsg::SwapCollateral {
+            from_token,
+            to_token,
+            min_receive,
+        } => swap_collateral(deps, env, from_token, to_token, min_receive),
+        HandleMsg::RegisterInbox {} => register_inbox(deps, env),
+        HandleMsg::UnregisterInbox {} => unregister_inbox(deps, env),
+        HandleMsg::PruneInbox { up_to } => prune_inbox(deps, env, up_to),
+        HandleMsg::TransferPosition { to } => transfer_position(deps, env, to),
+        HandleMsg::CancelPositionTransfer {} => cancel_position_transfer(deps, env),
+        HandleMsg::AcceptPosition { from } => accept_position(deps, env, from),
+        HandleMsg::AttachInsurance { insurance_contract } => {
+            attach_insurance(deps, env, insurance_contract)
+        }
+        HandleMsg::DetachInsurance {} => detach_insurance(deps, env),
+        HandleMsg::UpdateCollateralProtection {
+            protected_collaterals,
+        } => update_collateral_protection(deps, env, protected_collaterals),
+        HandleMsg::UpdateMaxLtvOverride { max_ltv_override } => {
+            update_max_ltv_override(deps, env, max_ltv_override)
+        }
+        HandleMsg::ProposeWhitelist {
+            name,
+            symbol,
+            collateral_token,
+            custody_contract,
+            max_ltv,
+            metadata,
+        } => propose_whitelist(
+            deps,
+            env,
+            name,
+            symbol,
+            collateral_token,
+            custody_contract,
+            max_ltv,
+            metadata,
+        ),
         HandleMsg::LiquidateCollateral { borrower } => liquidate_collateral(deps, env, borrower),
+        HandleMsg::CheckpointLtv { borrower } => checkpoint_ltv(deps, env, borrower),
+        HandleMsg::RefreshProtocolMode {} => refresh_protocol_mode(deps, env),
+        HandleMsg::RefreshRegistry {} => refresh_registry(deps, env),
     }
 }
 
+/// Executes the owner operations in order within one handle call,
+/// so a failing operation reverts the operations applied before it
+/// and the invariants are only checked once the whole batch ran
+pub fn admin_batch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ops: Vec<HandleMsg>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if ops.is_empty() {
+        return Err(ContractError::EmptyAdminBatch {}.into());
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut logs = vec![log("action", "admin_batch"), log("ops", ops.len())];
+    for op in ops {
+        match op {
+            HandleMsg::UpdateConfig { .. }
+            | HandleMsg::ProposeNewOwner { .. }
+            | HandleMsg::Whitelist { .. }
+            | HandleMsg::UpdateWhitelist { .. }
+            | HandleMsg::ConfirmWhitelist { .. }
+            | HandleMsg::RejectWhitelist { .. }
+            | HandleMsg::UpdateRewardRoute { .. }
+            | HandleMsg::UpdatePriceCheck { .. }
+            | HandleMsg::UpdateInterestRebate { .. }
+            | HandleMsg::UpdateRebateLtv { .. }
+            | HandleMsg::UpdateIndexUnit { .. }
+            | HandleMsg::DisableIndexUnit {}
+            | HandleMsg::RegisterInsurance { .. }
+            | HandleMsg::DeregisterInsurance { .. }
+            | HandleMsg::UpdateRiskModule { .. }
+            | HandleMsg::DisableRiskModule {}
+            | HandleMsg::RegisterProtocolController { .. }
+            | HandleMsg::UpdateAnalyticsContract { .. }
+            | HandleMsg::DisableAnalyticsContract {}
+            | HandleMsg::RegisterRegistry { .. }
+            | HandleMsg::DisableRegistry {} => {}
+            _ => return Err(ContractError::NotOwnerOperation {}.into()),
+        }
+
+        let res = dispatch(deps, env.clone(), op)?;
+        messages.extend(res.messages);
+        logs.extend(res.log);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    owner_addr: Option<HumanAddr>,
     oracle_contract: Option<HumanAddr>,
     liquidation_contract: Option<HumanAddr>,
     threshold_deposit_rate: Option<Decimal256>,
@@ -134,6 +412,16 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
     anc_purchase_factor: Option<Decimal256>,
     epoch_period: Option<u64>,
     price_timeframe: Option<u64>,
+    guardians: Option<Vec<HumanAddr>>,
+    guardian_threshold: Option<u64>,
+    max_collateral_types: Option<u64>,
+    swap_router: Option<HumanAddr>,
+    min_position_collateral_value: Option<Uint256>,
+    liquidation_order: Option<LiquidationOrder>,
+    max_feed_gap: Option<u64>,
+    max_catch_up_epochs: Option<u64>,
+    close_factor: Option<Decimal256>,
+    max_emergency_price_duration: Option<u64>,
 ) -> HandleResult {
     let mut config: Config = read_config(&deps.storage)?;
 
@@ -141,10 +429,6 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    if let Some(owner_addr) = owner_addr {
-        config.owner_addr = deps.api.canonical_address(&owner_addr)?;
-    }
-
     if let Some(oracle_contract) = oracle_contract {
         config.oracle_contract = deps.api.canonical_address(&oracle_contract)?;
     }
@@ -177,6 +461,61 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         config.price_timeframe = price_timeframe;
     }
 
+    if guardians.is_some() || guardian_threshold.is_some() {
+        if let Some(guardians) = guardians {
+            config.guardians = guardians
+                .iter()
+                .map(|guardian| deps.api.canonical_address(guardian))
+                .collect::<StdResult<Vec<CanonicalAddr>>>()?;
+        }
+
+        if let Some(guardian_threshold) = guardian_threshold {
+            config.guardian_threshold = guardian_threshold;
+        }
+
+        if config.guardian_threshold == 0
+            || config.guardian_threshold > config.guardians.len() as u64
+        {
+            return Err(ContractError::InvalidGuardianThreshold {}.into());
+        }
+    }
+
+    if let Some(max_collateral_types) = max_collateral_types {
+        config.max_collateral_types = max_collateral_types;
+    }
+
+    if let Some(swap_router) = swap_router {
+        config.swap_router = deps.api.canonical_address(&swap_router)?;
+    }
+
+    if let Some(min_position_collateral_value) = min_position_collateral_value {
+        config.min_position_collateral_value = min_position_collateral_value;
+    }
+
+    if let Some(liquidation_order) = liquidation_order {
+        config.liquidation_order = liquidation_order;
+    }
+
+    if let Some(max_feed_gap) = max_feed_gap {
+        config.max_feed_gap = max_feed_gap;
+    }
+
+    if let Some(max_catch_up_epochs) = max_catch_up_epochs {
+        config.max_catch_up_epochs = max_catch_up_epochs;
+    }
+
+    if let Some(close_factor) = close_factor {
+        if close_factor > Decimal256::one() {
+            return Err(ContractError::CloseFactorAboveOne {}.into());
+        }
+
+        config.close_factor = close_factor;
+    }
+
+    if let Some(max_emergency_price_duration) = max_emergency_price_duration {
+        config.max_emergency_price_duration = max_emergency_price_duration;
+    }
+
     store_config(&mut deps.storage, &config)?;
 
     Ok(HandleResponse {
@@ -202,9 +541,7 @@ pub fn register_whitelist<S: Storage, A: Api, Q: Querier>(
 
     let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
     if read_whitelist_elem(&deps.storage, &collateral_token_raw).is_ok() {
-        return Err(StdError::generic_err(
-            "Token is already registered as collateral",
-        ));
+        return Err(ContractError::CollateralRegistered {}.into());
     }
 
     store_whitelist_elem(
@@ -254,6 +591,24 @@ pub fn update_whitelist<S: Storage, A: Api, Q: Querier>(
 
     if let Some(max_ltv) = max_ltv {
         whitelist_elem.max_ltv = max_ltv;
+
+        // Notify the registered inboxes holding this collateral
+        for address in read_inbox_owners(&deps.storage)? {
+            if read_collaterals(&deps.storage, &address)
+                .iter()
+                .any(|c| c.0 == collateral_token_raw)
+            {
+                push_inbox_record(
+                    &mut deps.storage,
+                    &address,
+                    env.block.height,
+                    InboxEvent::WhitelistUpdated {
+                        collateral_token: collateral_token.clone(),
+                        max_ltv,
+                    },
+                )?;
+            }
+        }
     }
 
     store_whitelist_elem(&mut deps.storage, &collateral_token_raw, &whitelist_elem)?;
@@ -273,198 +628,76 @@ pub fn update_whitelist<S: Storage, A: Api, Q: Querier>(
     })
 }
 
-pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
+/// Records the approval of a guardian; the price applies once
+/// `guardian_threshold` guardians approved the same price and expiry
+pub fn set_emergency_price<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    asset: HumanAddr,
+    price: Decimal256,
+    expires_at: u64,
 ) -> HandleResult {
     let config: Config = read_config(&deps.storage)?;
-    let state: EpochState = read_epoch_state(&deps.storage)?;
-    if env.block.height < state.last_executed_height + config.epoch_period {
-        return Err(StdError::generic_err(format!(
-            "An epoch has not passed yet; last executed height: {}",
-            state.last_executed_height
-        )));
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    if !config.guardians.contains(&sender_raw) {
+        return Err(StdError::unauthorized());
     }
 
-    // # of blocks from the last executed height
-    let blocks = Uint256::from(env.block.height - state.last_executed_height);
-
-    // Compute next epoch state
-    let market_contract: HumanAddr = deps.api.human_address(&config.market_contract)?;
-    let epoch_state: EpochStateResponse =
-        query_epoch_state(&deps, &market_contract, env.block.height, None)?;
-
-    // effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
-    // deposit_rate = (effective_deposit_rate - 1) / blocks
-    let effective_deposit_rate = epoch_state.exchange_rate / state.prev_exchange_rate;
-    let deposit_rate =
-        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
-
-    let mut messages: Vec<CosmosMsg> = vec![];
-    let mut interest_buffer = query_balance(
-        &deps,
-        &env.contract.address,
-        config.stable_denom.to_string(),
-    )?;
-
-    // Send accrued_buffer * config.anc_purchase_factor amount stable token to collector
-    let accrued_buffer = interest_buffer - state.prev_interest_buffer;
-    let anc_purchase_amount = accrued_buffer * config.anc_purchase_factor;
-    if !anc_purchase_amount.is_zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address.clone(),
-            to_address: deps.api.human_address(&config.collector_contract)?,
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: config.stable_denom.to_string(),
-                    amount: anc_purchase_amount.into(),
-                },
-            )?],
-        }));
-    }
-
-    // Deduct anc_purchase_amount from the interest_buffer
-    interest_buffer = interest_buffer - anc_purchase_amount;
-
-    // Distribute Interest Buffer to depositor
-    // Only executed when deposit rate < threshold_deposit_rate
-    let mut distributed_interest: Uint256 = Uint256::zero();
-    if deposit_rate < config.threshold_deposit_rate {
-        // missing_deposit_rate(_per_block)
-        let missing_deposit_rate = config.threshold_deposit_rate - deposit_rate;
-        let prev_deposits = state.prev_aterra_supply * state.prev_exchange_rate;
-
-        // missing_deposits = prev_deposits * missing_deposit_rate(_per_block) * blocks
-        let missing_deposits = prev_deposits * blocks * missing_deposit_rate;
-        let distribution_buffer = interest_buffer * config.buffer_distribution_factor;
-
-        // When there was not enough deposits happens,
-        // distribute interest to market contract
-        distributed_interest = std::cmp::min(missing_deposits, distribution_buffer);
-        interest_buffer = interest_buffer - distributed_interest;
-
-        if !distributed_interest.is_zero() {
-            // deduct tax
-            distributed_interest = Uint256::from(
-                deduct_tax(
-                    &deps,
-                    Coin {
-                        denom: config.stable_denom.to_string(),
-                        amount: distributed_interest.into(),
-                    },
-                )?
-                .amount,
-            );
-
-            // Send some portion of interest buffer to Market contract
-            messages.push(CosmosMsg::Bank(BankMsg::Send {
-                from_address: env.contract.address.clone(),
-                to_address: market_contract,
-                amount: vec![Coin {
-                    denom: config.stable_denom,
-                    amount: distributed_interest.into(),
-                }],
-            }));
-        }
+    if expires_at <= env.block.time {
+        return Err(ContractError::EmergencyPriceExpired {}.into());
     }
 
-    // Execute DistributeRewards
-    let whitelist: Vec<WhitelistResponseElem> = read_whitelist(&deps, None, None)?;
-    for elem in whitelist.iter() {
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: elem.custody_contract.clone(),
-            send: vec![],
-            msg: to_binary(&CustodyHandleMsg::DistributeRewards {})?,
-        }));
+    let max_expires_at = env.block.time + config.max_emergency_price_duration;
+    if expires_at > max_expires_at {
+        return Err(ContractError::EmergencyPriceTooLong { max_expires_at }.into());
     }
 
-    // Execute store epoch state operation
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        send: vec![],
-        msg: to_binary(&HandleMsg::UpdateEpochState {
-            interest_buffer,
-            distributed_interest,
-        })?,
-    }));
-
-    Ok(HandleResponse {
-        messages,
-        log: vec![
-            log("action", "epoch_operations"),
-            log("deposit_rate", deposit_rate),
-            log("exchange_rate", epoch_state.exchange_rate),
-            log("aterra_supply", epoch_state.aterra_supply),
-            log("distributed_interest", distributed_interest),
-            log("anc_purchase_amount", anc_purchase_amount),
-        ],
-        data: None,
-    })
-}
-
-pub fn update_epoch_state<S: Storage, A: Api, Q: Querier>(
-    deps: &mut Extern<S, A, Q>,
-    env: Env,
-    // To store interest buffer before receiving epoch staking rewards,
-    // pass interest_buffer from execute_epoch_operations
-    interest_buffer: Uint256,
-    distributed_interest: Uint256,
-) -> HandleResult {
-    let config: Config = read_config(&deps.storage)?;
-    let overseer_epoch_state: EpochState = read_epoch_state(&deps.storage)?;
-    if env.message.sender != env.contract.address {
-        return Err(StdError::unauthorized());
+    if price.is_zero() {
+        return Err(ContractError::ZeroEmergencyPrice {}.into());
     }
 
-    // # of blocks from the last executed height
-    let blocks = Uint256::from(env.block.height - overseer_epoch_state.last_executed_height);
-
-    // Compute next epoch state
-    let market_contract: HumanAddr = deps.api.human_address(&config.market_contract)?;
-    let market_epoch_state: EpochStateResponse = query_epoch_state(
-        &deps,
-        &market_contract,
-        env.block.height,
-        Some(distributed_interest),
-    )?;
-
-    // effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
-    // deposit_rate = (effective_deposit_rate - 1) / blocks
-    let effective_deposit_rate =
-        market_epoch_state.exchange_rate / overseer_epoch_state.prev_exchange_rate;
-    let deposit_rate =
-        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
-
-    // store updated epoch state
-    store_epoch_state(
-        &mut deps.storage,
-        &EpochState {
-            last_executed_height: env.block.height,
-            prev_aterra_supply: market_epoch_state.aterra_supply,
-            prev_exchange_rate: market_epoch_state.exchange_rate,
-            prev_interest_buffer: interest_buffer,
-            deposit_rate,
+    // only whitelisted collaterals can be overridden
+    let asset_raw = deps.api.canonical_address(&asset)?;
+    read_whitelist_elem(&deps.storage, &asset_raw)?;
+
+    // approvals of another price or expiry are dropped
+    let mut proposal = match read_emergency_price_proposal(&deps.storage, &asset_raw) {
+        Some(proposal) if proposal.price == price && proposal.expires_at == expires_at => proposal,
+        _ => EmergencyPriceProposal {
+            price,
+            expires_at,
+            approvals: vec![],
         },
-    )?;
+    };
+    // approvals of the accounts removed from the guardians since do not count
+    proposal
+        .approvals
+        .retain(|approval| config.guardians.contains(approval));
+    if !proposal.approvals.contains(&sender_raw) {
+        proposal.approvals.push(sender_raw);
+    }
+
+    let approvals = proposal.approvals.len() as u64;
+    if approvals >= config.guardian_threshold {
+        remove_emergency_price_proposal(&mut deps.storage, &asset_raw);
+        store_emergency_price(
+            &mut deps.storage,
+            &asset_raw,
+            &EmergencyPrice { price, expires_at },
+        )?;
+    } else {
+        store_emergency_price_proposal(&mut deps.storage, &asset_raw, &proposal)?;
+    }
 
     Ok(HandleResponse {
-        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: market_contract,
-            send: vec![],
-            msg: to_binary(&MarketHandleMsg::ExecuteEpochOperations {
-                deposit_rate,
-                target_deposit_rate: config.target_deposit_rate,
-                threshold_deposit_rate: config.threshold_deposit_rate,
-                distributed_interest,
-            })?,
-        })],
+        messages: vec![],
         log: vec![
-            log("action", "update_epoch_state"),
-            log("deposit_rate", deposit_rate),
-            log("aterra_supply", market_epoch_state.aterra_supply),
-            log("exchange_rate", market_epoch_state.exchange_rate),
-            log("interest_buffer", interest_buffer),
+            log("action", "set_emergency_price"),
+            log("asset", asset),
+            log("price", price),
+            log("expires_at", expires_at),
+            log("approvals", approvals),
+            log("guardian_threshold", config.guardian_threshold),
         ],
         data: None,
     })
@@ -476,7 +709,10 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
         QueryMsg::EpochState {} => to_binary(&query_state(deps)?),
+        QueryMsg::EpochPhase {} => to_binary(&query_epoch_phase(deps)?),
+        QueryMsg::InvariantChecks {} => to_binary(&query_invariant_checks(deps)?),
         QueryMsg::Whitelist {
             collateral_token,
             start_after,
@@ -495,6 +731,82 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             borrower,
             block_time,
         } => to_binary(&query_borrow_limit(deps, borrower, block_time)?),
+        QueryMsg::EmergencyPrice { asset } => to_binary(&query_emergency_price(deps, asset)?),
+        QueryMsg::EmergencyPriceProposal { asset } => {
+            to_binary(&query_emergency_price_proposal(deps, asset)?)
+        }
+        QueryMsg::Inbox {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&query_inbox(deps, address, start_after, limit)?),
+        QueryMsg::Sponsorships { borrower } => to_binary(&query_sponsorships(deps, borrower)?),
+        QueryMsg::EpochRecords { start_after, limit } => {
+            to_binary(&query_epoch_records(deps, start_after, limit)?)
+        }
+        QueryMsg::InterestRebate {} => to_binary(&query_interest_rebate(deps)?),
+        QueryMsg::IndexUnit {} => to_binary(&query_index_unit(deps)?),
+        QueryMsg::PositionTransfer { borrower } => {
+            to_binary(&query_position_transfer(deps, borrower)?)
+        }
+        QueryMsg::InsuranceContracts {} => to_binary(&query_insurance_contracts(deps)?),
+        QueryMsg::PositionInsurance { borrower } => {
+            to_binary(&query_position_insurance(deps, borrower)?)
+        }
+        QueryMsg::RiskModule {} => to_binary(&query_risk_module(deps)?),
+        QueryMsg::WhitelistProposal { collateral_token } => {
+            to_binary(&query_whitelist_proposal(deps, collateral_token)?)
+        }
+        QueryMsg::WhitelistProposals { start_after, limit } => {
+            to_binary(&query_whitelist_proposals(deps, start_after, limit)?)
+        }
+        QueryMsg::AssetMetadata { collateral_token } => {
+            to_binary(&query_asset_metadata(deps, collateral_token)?)
+        }
+        QueryMsg::AnalyticsContract {} => to_binary(&query_analytics_contract(deps)?),
+        QueryMsg::Registry {} => to_binary(&query_registry(deps)?),
+        QueryMsg::FeedLiveness {
+            block_time,
+            start_after,
+            limit,
+        } => to_binary(&query_feed_liveness(deps, block_time, start_after, limit)?),
+        QueryMsg::CollateralProtection { borrower } => {
+            to_binary(&query_collateral_protection(deps, borrower)?)
+        }
+        QueryMsg::MaxLtvOverride { borrower } => {
+            to_binary(&query_max_ltv_override(deps, borrower)?)
+        }
+        QueryMsg::RewardRoute { collateral_token } => {
+            to_binary(&query_reward_route(deps, collateral_token)?)
+        }
+        QueryMsg::PriceCheck { collateral_token } => {
+            to_binary(&query_price_check(deps, collateral_token)?)
+        }
+        QueryMsg::Solvency { block_height } => to_binary(&query_solvency(deps, block_height)?),
+        QueryMsg::SnapshotHealth { snapshot } => to_binary(&query_snapshot_health(snapshot)),
+        QueryMsg::LtvCheckpoint { borrower } => to_binary(&query_ltv_checkpoint(deps, borrower)?),
+        QueryMsg::SimulateLiquidation {
+            borrower,
+            block_height,
+            block_time,
+        } => to_binary(&query_simulate_liquidation(
+            deps,
+            borrower,
+            block_height,
+            block_time,
+        )?),
+        QueryMsg::LiquidationCandidates {
+            start_after,
+            limit,
+            block_height,
+            block_time,
+        } => to_binary(&query_liquidation_candidates(
+            deps,
+            start_after,
+            limit,
+            block_height,
+            block_time,
+        )?),
     }
 }
 
@@ -515,6 +827,24 @@ pub fn query_config<S: Storage, A: Api, Q: Querier>(
         buffer_distribution_factor: config.buffer_distribution_factor,
         anc_purchase_factor: config.anc_purchase_factor,
         price_timeframe: config.price_timeframe,
+        guardians: config
+            .guardians
+            .iter()
+            .map(|guardian| deps.api.human_address(guardian))
+            .collect::<StdResult<Vec<HumanAddr>>>()?,
+        guardian_threshold: config.guardian_threshold,
+        max_collateral_types: config.max_collateral_types,
+        swap_router: if config.swap_router == CanonicalAddr::default() {
+            None
+        } else {
+            Some(deps.api.human_address(&config.swap_router)?)
+        },
+        min_position_collateral_value: config.min_position_collateral_value,
+        liquidation_order: config.liquidation_order,
+        max_feed_gap: config.max_feed_gap,
+        max_catch_up_epochs: config.max_catch_up_epochs,
+        close_factor: config.close_factor,
+        max_emergency_price_duration: config.max_emergency_price_duration,
     })
 }
 
@@ -524,6 +854,51 @@ pub fn query_state<S: Storage, A: Api, Q: Querier>(
     read_epoch_state(&deps.storage)
 }
 
+pub fn query_epoch_records<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EpochRecordsResponse> {
+    Ok(EpochRecordsResponse {
+        records: read_epoch_records(&deps.storage, start_after, limit)?,
+    })
+}
+
+pub fn query_emergency_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    asset: HumanAddr,
+) -> StdResult<EmergencyPriceResponse> {
+    let emergency_price: EmergencyPrice =
+        read_emergency_price(&deps.storage, &deps.api.canonical_address(&asset)?)
+            .ok_or_else(|| StdError::from(ContractError::NoEmergencyPrice {}))?;
+
+    Ok(EmergencyPriceResponse {
+        asset,
+        price: emergency_price.price,
+        expires_at: emergency_price.expires_at,
+    })
+}
+
+pub fn query_emergency_price_proposal<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    asset: HumanAddr,
+) -> StdResult<EmergencyPriceProposalResponse> {
+    let proposal: EmergencyPriceProposal =
+        read_emergency_price_proposal(&deps.storage, &deps.api.canonical_address(&asset)?)
+            .ok_or_else(|| StdError::from(ContractError::NoEmergencyPriceProposal {}))?;
+
+    Ok(EmergencyPriceProposalResponse {
+        asset,
+        price: proposal.price,
+        expires_at: proposal.expires_at,
+        approvals: proposal
+            .approvals
+            .iter()
+            .map(|guardian| deps.api.human_address(guardian))
+            .collect::<StdResult<Vec<HumanAddr>>>()?,
+    })
+}
+
 pub fn query_whitelist<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     collateral_token: Option<HumanAddr>,
@@ -551,7 +926,7 @@ pub fn query_whitelist<S: Storage, A: Api, Q: Querier>(
             None
         };
 
-        let whitelist: Vec<WhitelistResponseElem> = read_whitelist(&deps, start_after, limit)?;
+        let whitelist: Vec<WhitelistResponseElem> = read_whitelist(deps, start_after, limit)?;
         Ok(WhitelistResponse { elems: whitelist })
     }
 }
@@ -561,7 +936,14 @@ pub fn migrate<S: Storage, A: Api, Q: Querier>(
     _env: Env,
     msg: MigrateMsg,
 ) -> MigrateResult {
-    let config: Config = read_config(&deps.storage)?;
+    let mut config: Config = read_config(&deps.storage)?;
+
+    // configs stored before the guardians existed are guarded by the owner
+    if config.guardians.is_empty() {
+        config.guardians = vec![config.owner_addr.clone()];
+        config.guardian_threshold = 1;
+    }
+
     store_config(
         &mut deps.storage,
         &Config {
@@ -0,0 +1,477 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, LogAttribute, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::analytics::push_epoch_kpis;
+use crate::collateral::assert_fresh_prices;
+use crate::querier::query_epoch_state;
+use crate::reward_route::distribute_rewards_msg;
+use crate::state::{
+    read_config, read_epoch_progress, read_epoch_state, read_whitelist, store_epoch_progress,
+    store_epoch_record, store_epoch_state, Config, EpochProgress, EpochRecord, EpochState,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::EpochStateResponse;
+use moneymarket::market::HandleMsg as MarketHandleMsg;
+use moneymarket::overseer::{EpochPhase, EpochPhaseResponse, HandleMsg, WhitelistResponseElem};
+use moneymarket::querier::{deduct_tax, query_balance};
+
+/// Steps of the epoch operations, each one
+/// moves the epoch to the next phase:
+/// Idle → PricesValidated → RewardsDistributed → RateUpdated
+const EPOCH_STEPS: [&str; 3] = ["validate_prices", "distribute_rewards", "update_rate"];
+
+/// Index of the first step left after `phase`
+fn first_pending_step(phase: EpochPhase) -> usize {
+    match phase {
+        EpochPhase::Idle | EpochPhase::RateUpdated => 0,
+        EpochPhase::PricesValidated => 1,
+        EpochPhase::RewardsDistributed => 2,
+    }
+}
+
+/// Index of the step to execute at the block of `env`. Prices validated
+/// over price_timeframe ago are validated again, so the rewards are only
+/// distributed on prices validated within price_timeframe, whichever
+/// block the distribution lands in. The rate update only carries the
+/// buffer amounts of the distribution and computes the epoch state at
+/// its own height
+fn next_step(config: &Config, progress: &EpochProgress, env: &Env) -> usize {
+    match progress.phase {
+        EpochPhase::PricesValidated if env.block.time > progress.time + config.price_timeframe => 0,
+        phase => first_pending_step(phase),
+    }
+}
+
+/// Executes every pending step at once. The epoch state update it
+/// dispatches completes the epoch within the same transaction, so
+/// the phases reached on the way are not stored. An epoch left in
+/// progress by ExecuteEpochStep is resumed from its phase
+pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let mut progress: EpochProgress = read_epoch_progress(&deps.storage)?;
+
+    let mut logs = vec![log("action", "epoch_operations")];
+    if first_pending_step(progress.phase) > 0 {
+        logs.push(log("resumed_phase", progress.phase));
+    }
+
+    let mut dead_feeds: Vec<HumanAddr> = vec![];
+    if next_step(&config, &progress, &env) == 0 {
+        dead_feeds = validate_prices(deps, &env, &config, &mut progress)?;
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if progress.phase == EpochPhase::PricesValidated {
+        let (distribution_messages, distribution_logs) =
+            distribute_rewards(deps, &env, &config, &mut progress)?;
+        messages.extend(distribution_messages);
+        logs.extend(distribution_logs);
+    }
+
+    messages.push(update_epoch_state_msg(&env, &progress)?);
+
+    // the collaterals without borrow power
+    if !dead_feeds.is_empty() {
+        logs.push(dead_feeds_log(&dead_feeds));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Executes the next pending step alone and stores the phase it
+/// reaches, so a failing step leaves the epoch at the last phase
+/// reached, to be resumed by either execution
+pub fn execute_epoch_step<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let mut progress: EpochProgress = read_epoch_progress(&deps.storage)?;
+
+    let step = next_step(&config, &progress, &env);
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut logs = vec![log("action", "epoch_step"), log("step", EPOCH_STEPS[step])];
+    match step {
+        0 => {
+            let dead_feeds = validate_prices(deps, &env, &config, &mut progress)?;
+            if !dead_feeds.is_empty() {
+                logs.push(dead_feeds_log(&dead_feeds));
+            }
+        }
+        1 => {
+            let (distribution_messages, distribution_logs) =
+                distribute_rewards(deps, &env, &config, &mut progress)?;
+            messages.extend(distribution_messages);
+            logs.extend(distribution_logs);
+        }
+        // the epoch state update moves the epoch to RateUpdated
+        _ => messages.push(update_epoch_state_msg(&env, &progress)?),
+    }
+
+    store_epoch_progress(&mut deps.storage, &progress)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Checks an epoch has passed and aborts before distributing
+/// rewards on stale collateral prices. Returns the collaterals
+/// whose feed is dead, they have no borrow power
+fn validate_prices<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    progress: &mut EpochProgress,
+) -> StdResult<Vec<HumanAddr>> {
+    let state: EpochState = read_epoch_state(&deps.storage)?;
+    if env.block.height < state.last_executed_height + config.epoch_period {
+        return Err(ContractError::EpochNotPassed {
+            last_executed_height: state.last_executed_height,
+        }
+        .into());
+    }
+
+    // A zero epoch period must not execute twice in the same block
+    if env.block.height <= state.last_executed_height {
+        return Err(ContractError::EpochAlreadyExecuted {
+            last_executed_height: state.last_executed_height,
+        }
+        .into());
+    }
+
+    let whitelist: Vec<WhitelistResponseElem> = read_whitelist(deps, None, None)?;
+    let dead_feeds = assert_fresh_prices(deps, config, &whitelist, env.block.time)?;
+
+    progress.phase = EpochPhase::PricesValidated;
+    progress.height = env.block.height;
+    progress.time = env.block.time;
+    Ok(dead_feeds)
+}
+
+/// Sends the collector its share of the accrued interest buffer,
+/// distributes the buffer to the depositors when the deposit rate
+/// is under the threshold and invokes [Custody] DistributeRewards.
+/// The buffer left is carried to the epoch state update
+fn distribute_rewards<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    progress: &mut EpochProgress,
+) -> StdResult<(Vec<CosmosMsg>, Vec<LogAttribute>)> {
+    let state: EpochState = read_epoch_state(&deps.storage)?;
+
+    // # of blocks from the last executed height
+    let blocks = Uint256::from(env.block.height - state.last_executed_height);
+
+    // Missed epochs are combined into this one: the deposit rate is
+    // averaged over the whole gap, but the interest buffer only makes
+    // up for the missing deposits of max_catch_up_epochs epochs
+    let (missed_epochs, distribution_blocks) =
+        compute_catch_up(config, env.block.height - state.last_executed_height);
+
+    // Compute next epoch state
+    let market_contract: HumanAddr = deps.api.human_address(&config.market_contract)?;
+    let epoch_state: EpochStateResponse =
+        query_epoch_state(deps, &market_contract, env.block.height, None)?;
+
+    let deposit_rate =
+        compute_deposit_rate(epoch_state.exchange_rate, state.prev_exchange_rate, blocks);
+
+    // Keep the inputs so the deposit rate can be re-derived off-chain
+    store_epoch_record(
+        &mut deps.storage,
+        env.block.height,
+        &EpochRecord {
+            blocks: env.block.height - state.last_executed_height,
+            prev_exchange_rate: state.prev_exchange_rate,
+            exchange_rate: epoch_state.exchange_rate,
+            aterra_supply: epoch_state.aterra_supply,
+            market_balance: epoch_state.market_balance,
+            total_liabilities: epoch_state.total_liabilities,
+            total_credit_liabilities: epoch_state.total_credit_liabilities,
+            total_reserves: epoch_state.total_reserves,
+            deposit_rate,
+        },
+    )?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut interest_buffer =
+        query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
+
+    // Send accrued_buffer * config.anc_purchase_factor amount stable token to collector
+    let accrued_buffer = interest_buffer - state.prev_interest_buffer;
+    let anc_purchase_amount = accrued_buffer * config.anc_purchase_factor;
+    if !anc_purchase_amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: deps.api.human_address(&config.collector_contract)?,
+            amount: vec![deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.to_string(),
+                    amount: anc_purchase_amount.into(),
+                },
+            )?],
+        }));
+    }
+
+    // Deduct anc_purchase_amount from the interest_buffer
+    interest_buffer = interest_buffer - anc_purchase_amount;
+
+    // Distribute Interest Buffer to depositor
+    // Only executed when deposit rate < threshold_deposit_rate
+    let mut distributed_interest: Uint256 = Uint256::zero();
+    if deposit_rate < config.threshold_deposit_rate {
+        // missing_deposit_rate(_per_block)
+        let missing_deposit_rate = config.threshold_deposit_rate - deposit_rate;
+        let prev_deposits = state.prev_aterra_supply * state.prev_exchange_rate;
+
+        // missing_deposits = prev_deposits * missing_deposit_rate(_per_block) * blocks
+        let missing_deposits =
+            prev_deposits * Uint256::from(distribution_blocks) * missing_deposit_rate;
+        let distribution_buffer = interest_buffer * config.buffer_distribution_factor;
+
+        // When there was not enough deposits happens,
+        // distribute interest to market contract
+        distributed_interest = std::cmp::min(missing_deposits, distribution_buffer);
+        interest_buffer = interest_buffer - distributed_interest;
+
+        if !distributed_interest.is_zero() {
+            // deduct tax
+            distributed_interest = Uint256::from(
+                deduct_tax(
+                    deps,
+                    Coin {
+                        denom: config.stable_denom.to_string(),
+                        amount: distributed_interest.into(),
+                    },
+                )?
+                .amount,
+            );
+
+            // Send some portion of interest buffer to Market contract
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: market_contract,
+                amount: vec![Coin {
+                    denom: config.stable_denom.to_string(),
+                    amount: distributed_interest.into(),
+                }],
+            }));
+        }
+    }
+
+    messages.extend(push_epoch_kpis(
+        deps,
+        env.block.height,
+        deposit_rate,
+        &epoch_state,
+    )?);
+
+    // Execute DistributeRewards
+    let whitelist: Vec<WhitelistResponseElem> = read_whitelist(deps, None, None)?;
+    for elem in whitelist.iter() {
+        messages.push(distribute_rewards_msg(
+            deps,
+            config,
+            &env.contract.address,
+            &elem.collateral_token,
+            &elem.custody_contract,
+        )?);
+    }
+
+    progress.phase = EpochPhase::RewardsDistributed;
+    progress.height = env.block.height;
+    progress.time = env.block.time;
+    progress.interest_buffer = interest_buffer;
+    progress.distributed_interest = distributed_interest;
+
+    let mut logs = vec![
+        log("deposit_rate", deposit_rate),
+        log("exchange_rate", epoch_state.exchange_rate),
+        log("aterra_supply", epoch_state.aterra_supply),
+        log("distributed_interest", distributed_interest),
+        log("anc_purchase_amount", anc_purchase_amount),
+    ];
+
+    if missed_epochs > 0 {
+        logs.push(log("missed_epochs", missed_epochs));
+        logs.push(log("distribution_blocks", distribution_blocks));
+    }
+
+    Ok((messages, logs))
+}
+
+/// Execute store epoch state operation
+fn update_epoch_state_msg(env: &Env, progress: &EpochProgress) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.clone(),
+        send: vec![],
+        msg: to_binary(&HandleMsg::UpdateEpochState {
+            interest_buffer: progress.interest_buffer,
+            distributed_interest: progress.distributed_interest,
+        })?,
+    }))
+}
+
+fn dead_feeds_log(dead_feeds: &[HumanAddr]) -> LogAttribute {
+    log(
+        "dead_feeds",
+        dead_feeds
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(","),
+    )
+}
+
+/// # of epochs missed since the last execution `blocks` ago, and
+/// the blocks the interest buffer is distributed for, capped to
+/// max_catch_up_epochs epochs when set
+fn compute_catch_up(config: &Config, blocks: u64) -> (u64, u64) {
+    if config.epoch_period == 0 {
+        return (0, blocks);
+    }
+
+    let missed_epochs = (blocks / config.epoch_period).saturating_sub(1);
+    let distribution_blocks = if config.max_catch_up_epochs == 0 {
+        blocks
+    } else {
+        blocks.min(
+            config
+                .epoch_period
+                .saturating_mul(config.max_catch_up_epochs),
+        )
+    };
+
+    (missed_epochs, distribution_blocks)
+}
+
+pub fn update_epoch_state<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    // To store interest buffer before receiving epoch staking rewards,
+    // pass interest_buffer from execute_epoch_operations
+    interest_buffer: Uint256,
+    distributed_interest: Uint256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let overseer_epoch_state: EpochState = read_epoch_state(&deps.storage)?;
+    if env.message.sender != env.contract.address {
+        return Err(StdError::unauthorized());
+    }
+
+    if env.block.height <= overseer_epoch_state.last_executed_height {
+        return Err(ContractError::EpochStateUpdated {
+            last_executed_height: overseer_epoch_state.last_executed_height,
+        }
+        .into());
+    }
+
+    // # of blocks from the last executed height
+    let blocks = Uint256::from(env.block.height - overseer_epoch_state.last_executed_height);
+
+    // Compute next epoch state
+    let market_contract: HumanAddr = deps.api.human_address(&config.market_contract)?;
+    let market_epoch_state: EpochStateResponse = query_epoch_state(
+        deps,
+        &market_contract,
+        env.block.height,
+        Some(distributed_interest),
+    )?;
+
+    let deposit_rate = compute_deposit_rate(
+        market_epoch_state.exchange_rate,
+        overseer_epoch_state.prev_exchange_rate,
+        blocks,
+    );
+
+    // store updated epoch state
+    store_epoch_state(
+        &mut deps.storage,
+        &EpochState {
+            last_executed_height: env.block.height,
+            prev_aterra_supply: market_epoch_state.aterra_supply,
+            prev_exchange_rate: market_epoch_state.exchange_rate,
+            prev_interest_buffer: interest_buffer,
+            deposit_rate,
+        },
+    )?;
+
+    // the epoch is complete
+    store_epoch_progress(
+        &mut deps.storage,
+        &EpochProgress {
+            phase: EpochPhase::RateUpdated,
+            height: env.block.height,
+            time: env.block.time,
+            interest_buffer: Uint256::zero(),
+            distributed_interest: Uint256::zero(),
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: market_contract,
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::ExecuteEpochOperations {
+                deposit_rate,
+                target_deposit_rate: config.target_deposit_rate,
+                threshold_deposit_rate: config.threshold_deposit_rate,
+                distributed_interest,
+            })?,
+        })],
+        log: vec![
+            log("action", "update_epoch_state"),
+            log("deposit_rate", deposit_rate),
+            log("aterra_supply", market_epoch_state.aterra_supply),
+            log("exchange_rate", market_epoch_state.exchange_rate),
+            log("interest_buffer", interest_buffer),
+        ],
+        data: None,
+    })
+}
+
+/// effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
+/// deposit_rate = (effective_deposit_rate - 1) / blocks
+/// The deposit rate is zero when the exchange rate decreased
+fn compute_deposit_rate(
+    exchange_rate: Decimal256,
+    prev_exchange_rate: Decimal256,
+    blocks: Uint256,
+) -> Decimal256 {
+    let effective_deposit_rate = exchange_rate / prev_exchange_rate;
+    if effective_deposit_rate > Decimal256::one() {
+        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks)
+    } else {
+        Decimal256::zero()
+    }
+}
+
+pub fn query_epoch_phase<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<EpochPhaseResponse> {
+    let progress: EpochProgress = read_epoch_progress(&deps.storage)?;
+    Ok(EpochPhaseResponse {
+        phase: progress.phase,
+        height: progress.height,
+        pending_steps: EPOCH_STEPS[first_pending_step(progress.phase)..]
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+    })
+}
@@ -0,0 +1,98 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{Api, CanonicalAddr, Extern, HumanAddr, Querier, StdResult, Storage};
+
+use crate::state::{read_config, read_emergency_price, read_whitelist, Config};
+
+use moneymarket::oracle::PriceResponse;
+use moneymarket::overseer::{FeedLivenessResponse, FeedLivenessResponseElem};
+use moneymarket::querier::query_price;
+
+/// Last oracle price of the collateral and its update time,
+/// `None` when the oracle has no price for it
+pub(crate) fn query_last_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collateral_token: &HumanAddr,
+) -> StdResult<Option<(Decimal256, u64)>> {
+    let price: PriceResponse = match query_price(
+        deps,
+        &deps.api.human_address(&config.oracle_contract)?,
+        collateral_token.to_string(),
+        config.stable_denom.to_string(),
+        None,
+    ) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
+
+    Ok(Some((
+        price.rate,
+        std::cmp::min(price.last_updated_base, price.last_updated_quote),
+    )))
+}
+
+pub(crate) fn is_live_feed(config: &Config, last_updated: u64, block_time: u64) -> bool {
+    config.max_feed_gap == 0 || last_updated >= block_time.saturating_sub(config.max_feed_gap)
+}
+
+/// Last price of a collateral whose feed was not updated within the max
+/// feed gap; `None` while the feed is live or an unexpired emergency price
+/// overrides it. Dead feeds give no borrow power, their last price is only
+/// read to value the collaterals seized by liquidations
+pub(crate) fn query_dead_feed_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collateral_token: &CanonicalAddr,
+    block_time: u64,
+) -> StdResult<Option<Decimal256>> {
+    if config.max_feed_gap == 0 {
+        return Ok(None);
+    }
+
+    if let Some(emergency_price) = read_emergency_price(&deps.storage, collateral_token) {
+        if block_time < emergency_price.expires_at {
+            return Ok(None);
+        }
+    }
+
+    Ok(
+        match query_last_price(deps, config, &deps.api.human_address(collateral_token)?)? {
+            Some((price, last_updated)) if !is_live_feed(config, last_updated, block_time) => {
+                Some(price)
+            }
+            _ => None,
+        },
+    )
+}
+
+pub fn query_feed_liveness<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_time: u64,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<FeedLivenessResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let feeds = read_whitelist(deps, start_after, limit)?
+        .into_iter()
+        .map(|elem| {
+            let last_updated = query_last_price(deps, &config, &elem.collateral_token)?
+                .map(|(_, last_updated)| last_updated);
+
+            Ok(FeedLivenessResponseElem {
+                collateral_token: elem.collateral_token,
+                last_updated,
+                live: last_updated
+                    .map(|last_updated| is_live_feed(&config, last_updated, block_time))
+                    .unwrap_or(false),
+            })
+        })
+        .collect::<StdResult<Vec<FeedLivenessResponseElem>>>()?;
+
+    Ok(FeedLivenessResponse { feeds })
+}
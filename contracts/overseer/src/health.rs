@@ -0,0 +1,92 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+
+use moneymarket::overseer::{HealthSnapshot, SnapshotHealthResponse};
+
+/// Max ltv of a collateral, capped to the borrower
+/// `max_ltv_override` when it is lower
+fn capped_max_ltv(max_ltv: Decimal256, max_ltv_override: Option<Decimal256>) -> Decimal256 {
+    match max_ltv_override {
+        Some(v) if v < max_ltv => v,
+        _ => max_ltv,
+    }
+}
+
+/// Borrow power of `amount` of a collateral at `price`
+fn collateral_borrow_limit(amount: Uint256, price: Decimal256, max_ltv: Decimal256) -> Uint256 {
+    let collateral_value = amount * price;
+    collateral_value * max_ltv
+}
+
+/// Value of the collaterals of `snapshot`
+pub(crate) fn snapshot_collaterals_value(snapshot: &HealthSnapshot) -> Uint256 {
+    snapshot
+        .collaterals
+        .iter()
+        .fold(Uint256::zero(), |acc, v| acc + v.amount * v.price)
+}
+
+/// Borrow limit of `snapshot`, each collateral capped to the max ltv
+/// override. Dead feeds keep their last price for liquidations but
+/// give no borrow power
+pub(crate) fn snapshot_borrow_limit(snapshot: &HealthSnapshot) -> Uint256 {
+    snapshot
+        .collaterals
+        .iter()
+        .filter(|v| !v.dead_feed)
+        .fold(Uint256::zero(), |acc, v| {
+            acc + collateral_borrow_limit(
+                v.amount,
+                v.price,
+                capped_max_ltv(v.max_ltv, snapshot.max_ltv_override),
+            )
+        })
+}
+
+/// Borrow limit the liquidations compare the loan to, which
+/// ignores the max ltv override
+pub(crate) fn snapshot_liquidation_borrow_limit(snapshot: &HealthSnapshot) -> Uint256 {
+    snapshot
+        .collaterals
+        .iter()
+        .filter(|v| !v.dead_feed)
+        .fold(Uint256::zero(), |acc, v| {
+            acc + collateral_borrow_limit(v.amount, v.price, v.max_ltv)
+        })
+}
+
+/// Whether the collaterals of `snapshot` are worth less
+/// than the minimum collateral value of a position
+pub(crate) fn is_dust_snapshot(snapshot: &HealthSnapshot) -> bool {
+    snapshot_collaterals_value(snapshot) < snapshot.min_position_collateral_value
+}
+
+/// Whether the loan of `snapshot` exceeds its liquidation borrow limit
+pub(crate) fn is_liquidatable_snapshot(snapshot: &HealthSnapshot) -> bool {
+    snapshot_liquidation_borrow_limit(snapshot) < snapshot.loan_amount
+}
+
+/// The borrow limit and liquidation checks of the overseer, computed
+/// over `snapshot` only
+pub fn query_snapshot_health(snapshot: HealthSnapshot) -> SnapshotHealthResponse {
+    let borrow_limit = snapshot_borrow_limit(&snapshot);
+    let liquidation_borrow_limit = snapshot_liquidation_borrow_limit(&snapshot);
+    let dust = is_dust_snapshot(&snapshot);
+
+    let health_factor = if snapshot.loan_amount.is_zero() {
+        None
+    } else {
+        Some(
+            Decimal256::from_uint256(liquidation_borrow_limit)
+                / Decimal256::from_uint256(snapshot.loan_amount),
+        )
+    };
+
+    SnapshotHealthResponse {
+        collaterals_value: snapshot_collaterals_value(&snapshot),
+        borrow_limit: if dust { Uint256::zero() } else { borrow_limit },
+        liquidation_borrow_limit,
+        health_factor,
+        liquidatable: is_liquidatable_snapshot(&snapshot),
+        dust,
+    }
+}
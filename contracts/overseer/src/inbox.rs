@@ -0,0 +1,102 @@
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+
+use crate::state::{
+    read_inbox_meta, read_inbox_records, remove_inbox_meta, remove_inbox_records, store_inbox_meta,
+    InboxMeta,
+};
+use moneymarket::error::ContractError;
+use moneymarket::overseer::InboxResponse;
+
+pub fn register_inbox<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let address_raw = deps.api.canonical_address(&env.message.sender)?;
+    if read_inbox_meta(&deps.storage, &address_raw).is_some() {
+        return Err(ContractError::InboxRegistered {}.into());
+    }
+
+    store_inbox_meta(
+        &mut deps.storage,
+        &address_raw,
+        &InboxMeta {
+            first_seq: 0,
+            next_seq: 0,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_inbox"),
+            log("address", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+pub fn unregister_inbox<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let address_raw = deps.api.canonical_address(&env.message.sender)?;
+    let mut inbox_meta = read_registered_inbox_meta(&deps.storage, &address_raw)?;
+
+    remove_inbox_records(&mut deps.storage, &address_raw, &mut inbox_meta, u64::MAX);
+    remove_inbox_meta(&mut deps.storage, &address_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "unregister_inbox"),
+            log("address", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+pub fn prune_inbox<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    up_to: u64,
+) -> HandleResult {
+    let address_raw = deps.api.canonical_address(&env.message.sender)?;
+    let mut inbox_meta = read_registered_inbox_meta(&deps.storage, &address_raw)?;
+
+    remove_inbox_records(&mut deps.storage, &address_raw, &mut inbox_meta, up_to);
+    store_inbox_meta(&mut deps.storage, &address_raw, &inbox_meta)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "prune_inbox"),
+            log("address", env.message.sender),
+            log("first_seq", inbox_meta.first_seq),
+        ],
+        data: None,
+    })
+}
+
+fn read_registered_inbox_meta<S: Storage>(
+    storage: &S,
+    address: &CanonicalAddr,
+) -> StdResult<InboxMeta> {
+    read_inbox_meta(storage, address)
+        .ok_or_else(|| StdError::from(ContractError::InboxNotRegistered {}))
+}
+
+pub fn query_inbox<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<InboxResponse> {
+    let address_raw = deps.api.canonical_address(&address)?;
+    read_registered_inbox_meta(&deps.storage, &address_raw)?;
+
+    let records = read_inbox_records(&deps.storage, &address_raw, start_after, limit)?;
+    Ok(InboxResponse { address, records })
+}
@@ -0,0 +1,126 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::state::{
+    read_config, read_index_unit, remove_index_unit, store_index_unit, Config, IndexUnit,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::oracle::PriceResponse;
+use moneymarket::overseer::IndexUnitResponse;
+use moneymarket::querier::{query_price, TimeConstraints};
+
+pub fn update_index_unit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    index_oracle: HumanAddr,
+    index_asset: String,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let index_unit = IndexUnit {
+        index_oracle: deps.api.canonical_address(&index_oracle)?,
+        index_asset,
+    };
+
+    // borrow limits cannot be computed without a fresh index price
+    let index_price = query_index_unit_price(deps, &config, &index_unit, Some(env.block.time))?;
+    store_index_unit(&mut deps.storage, &index_unit)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_index_unit"),
+            log("index_oracle", index_oracle),
+            log("index_asset", index_unit.index_asset),
+            log("index_price", index_price),
+        ],
+        data: None,
+    })
+}
+
+pub fn disable_index_unit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    remove_index_unit(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "disable_index_unit")],
+        data: None,
+    })
+}
+
+/// Returns the stable denom price of one index unit,
+/// or `None` when borrow limits are denominated in stable denom
+pub(crate) fn query_index_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    block_time: Option<u64>,
+) -> StdResult<Option<Decimal256>> {
+    match read_index_unit(&deps.storage)? {
+        Some(index_unit) => Ok(Some(query_index_unit_price(
+            deps,
+            config,
+            &index_unit,
+            block_time,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+fn query_index_unit_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    index_unit: &IndexUnit,
+    block_time: Option<u64>,
+) -> StdResult<Decimal256> {
+    let price: PriceResponse = query_price(
+        deps,
+        &deps.api.human_address(&index_unit.index_oracle)?,
+        index_unit.index_asset.to_string(),
+        config.stable_denom.to_string(),
+        block_time.map(|block_time| TimeConstraints {
+            block_time,
+            valid_timeframe: config.price_timeframe,
+        }),
+    )?;
+
+    if price.rate.is_zero() {
+        return Err(ContractError::ZeroIndexPrice {}.into());
+    }
+
+    Ok(price.rate)
+}
+
+/// Converts a stable denom amount to index units
+pub(crate) fn to_index_units(amount: Uint256, index_price: Decimal256) -> Uint256 {
+    Decimal256::from_uint256(amount) / index_price * Uint256::one()
+}
+
+pub fn query_index_unit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<IndexUnitResponse> {
+    Ok(match read_index_unit(&deps.storage)? {
+        Some(v) => IndexUnitResponse {
+            index_oracle: Some(deps.api.human_address(&v.index_oracle)?),
+            index_asset: Some(v.index_asset),
+        },
+        None => IndexUnitResponse {
+            index_oracle: None,
+            index_asset: None,
+        },
+    })
+}
@@ -0,0 +1,230 @@
+use cosmwasm_std::{
+    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::collateral::{compute_liquidation, execute_liquidation, Liquidation};
+use crate::state::{
+    is_insurance_contract, read_config, read_insurance_contracts, read_position_insurance,
+    remove_insurance_contract, remove_position_insurance, store_insurance_contract,
+    store_position_insurance, Config,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::insurance::HandleMsg as InsuranceHandleMsg;
+use moneymarket::overseer::{HandleMsg, InsuranceContractsResponse, PositionInsuranceResponse};
+
+pub fn register_insurance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    insurance_contract: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_insurance_contract(
+        &mut deps.storage,
+        &deps.api.canonical_address(&insurance_contract)?,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_insurance"),
+            log("insurance_contract", insurance_contract),
+        ],
+        data: None,
+    })
+}
+
+/// A failing insurance contract reverts the liquidations calling it,
+/// deregistering it lets the attached positions be liquidated again
+pub fn deregister_insurance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    insurance_contract: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let insurance_contract_raw = deps.api.canonical_address(&insurance_contract)?;
+    if !is_insurance_contract(&deps.storage, &insurance_contract_raw)? {
+        return Err(ContractError::InsuranceNotRegistered {}.into());
+    }
+
+    remove_insurance_contract(&mut deps.storage, &insurance_contract_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "deregister_insurance"),
+            log("insurance_contract", insurance_contract),
+        ],
+        data: None,
+    })
+}
+
+pub fn attach_insurance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    insurance_contract: HumanAddr,
+) -> HandleResult {
+    let insurance_contract_raw = deps.api.canonical_address(&insurance_contract)?;
+    if !is_insurance_contract(&deps.storage, &insurance_contract_raw)? {
+        return Err(ContractError::InsuranceNotRegistered {}.into());
+    }
+
+    let borrower = env.message.sender;
+    store_position_insurance(
+        &mut deps.storage,
+        &deps.api.canonical_address(&borrower)?,
+        &insurance_contract_raw,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "attach_insurance"),
+            log("borrower", borrower),
+            log("insurance_contract", insurance_contract),
+        ],
+        data: None,
+    })
+}
+
+pub fn detach_insurance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let borrower = env.message.sender;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    if read_position_insurance(&deps.storage, &borrower_raw)?.is_none() {
+        return Err(ContractError::NoInsurance {}.into());
+    }
+
+    remove_position_insurance(&mut deps.storage, &borrower_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "detach_insurance"), log("borrower", borrower)],
+        data: None,
+    })
+}
+
+/// Returns the insurance contract attached to the
+/// position of `borrower` while it is registered
+pub(crate) fn read_active_insurance<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<CanonicalAddr>> {
+    match read_position_insurance(storage, borrower)? {
+        Some(v) if is_insurance_contract(storage, &v)? => Ok(Some(v)),
+        _ => Ok(None),
+    }
+}
+
+/// Calls the insurance contract of the borrower before the liquidation,
+/// which runs in `FinishInsuredLiquidation` afterwards in the same
+/// transaction, once the insurance contract had a chance to cure it
+pub(crate) fn call_insurance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+    insurance_contract: CanonicalAddr,
+    liquidation: &Liquidation,
+) -> HandleResult {
+    let insurance_contract = deps.api.human_address(&insurance_contract)?;
+
+    Ok(HandleResponse {
+        messages: vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: insurance_contract.clone(),
+                send: vec![],
+                msg: to_binary(&InsuranceHandleMsg::CurePosition {
+                    borrower: borrower.clone(),
+                    loan_amount: liquidation.borrow_amount,
+                    borrow_limit: liquidation.borrow_limit,
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address,
+                send: vec![],
+                msg: to_binary(&HandleMsg::FinishInsuredLiquidation {
+                    borrower: borrower.clone(),
+                    liquidator: env.message.sender,
+                })?,
+            }),
+        ],
+        log: vec![
+            log("action", "liquidate_collateral"),
+            log("borrower", borrower),
+            log("insurance_contract", insurance_contract),
+        ],
+        data: None,
+    })
+}
+
+pub fn finish_insured_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+    liquidator: HumanAddr,
+) -> HandleResult {
+    if env.message.sender != env.contract.address {
+        return Err(StdError::unauthorized());
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    match compute_liquidation(deps, &config, &borrower, env.block.height, env.block.time)? {
+        Some(liquidation) => {
+            // the liquidator receives the collaterals
+            let mut env = env;
+            env.message.sender = liquidator;
+
+            execute_liquidation(deps, env, &config, borrower, liquidation)
+        }
+        None => Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "finish_insured_liquidation"),
+                log("borrower", borrower),
+                log("cured", true),
+            ],
+            data: None,
+        }),
+    }
+}
+
+pub fn query_insurance_contracts<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<InsuranceContractsResponse> {
+    let insurance_contracts = read_insurance_contracts(&deps.storage)?
+        .iter()
+        .map(|v| deps.api.human_address(v))
+        .collect::<StdResult<Vec<HumanAddr>>>()?;
+
+    Ok(InsuranceContractsResponse {
+        insurance_contracts,
+    })
+}
+
+pub fn query_position_insurance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<PositionInsuranceResponse> {
+    let insurance_contract =
+        match read_position_insurance(&deps.storage, &deps.api.canonical_address(&borrower)?)? {
+            Some(v) => v,
+            None => return Err(ContractError::NoInsurance {}.into()),
+        };
+
+    Ok(PositionInsuranceResponse {
+        borrower,
+        registered: is_insurance_contract(&deps.storage, &insurance_contract)?,
+        insurance_contract: deps.api.human_address(&insurance_contract)?,
+    })
+}
@@ -0,0 +1,71 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, Querier, StdError, StdResult, Storage,
+};
+
+use crate::state::{
+    read_config, read_epoch_state, read_invariant_checks, store_invariant_checks, Config,
+    EpochState,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::overseer::InvariantChecksResponse;
+
+pub fn update_invariant_checks<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    enabled: bool,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_invariant_checks(&mut deps.storage, enabled)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_invariant_checks"),
+            log("enabled", enabled),
+        ],
+        data: None,
+    })
+}
+
+/// Verifies the config and epoch invariants once a
+/// handler is done, aborting the tx on a violation
+pub(crate) fn assert_invariants<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<()> {
+    if !read_invariant_checks(&deps.storage)? {
+        return Ok(());
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    if config.threshold_deposit_rate > config.target_deposit_rate {
+        return Err(ContractError::DepositRateInvariant {}.into());
+    }
+
+    if config.buffer_distribution_factor > Decimal256::one()
+        || config.anc_purchase_factor > Decimal256::one()
+    {
+        return Err(ContractError::BufferFactorInvariant {}.into());
+    }
+
+    let epoch_state: EpochState = read_epoch_state(&deps.storage)?;
+    if epoch_state.last_executed_height > block_height {
+        return Err(ContractError::EpochHeightInvariant {}.into());
+    }
+
+    Ok(())
+}
+
+pub fn query_invariant_checks<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<InvariantChecksResponse> {
+    Ok(InvariantChecksResponse {
+        enabled: read_invariant_checks(&deps.storage)?,
+    })
+}
@@ -1,7 +1,29 @@
+pub mod analytics;
+pub mod batch;
 pub mod collateral;
 pub mod contract;
+pub mod epoch;
+pub mod feed_liveness;
+pub mod health;
+pub mod inbox;
+pub mod index;
+pub mod insurance;
+pub mod invariants;
+pub mod max_ltv_override;
+pub mod position;
+pub mod price_check;
+pub mod protection;
+pub mod protocol_mode;
 pub mod querier;
+pub mod rebate;
+pub mod registry;
+pub mod reward_route;
+pub mod risk;
+pub mod solvency;
+pub mod sponsorship;
 pub mod state;
+pub mod swap;
+pub mod whitelist_proposal;
 
 #[cfg(test)]
 mod testing;
@@ -0,0 +1,87 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdResult, Storage,
+};
+
+use crate::state::{
+    read_max_ltv_override_elem, remove_max_ltv_override, store_max_ltv_override, MaxLtvOverride,
+    MAX_LTV_OVERRIDE_COOLDOWN,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::overseer::MaxLtvOverrideResponse;
+
+pub fn update_max_ltv_override<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    max_ltv_override: Option<Decimal256>,
+) -> HandleResult {
+    let borrower_raw = deps.api.canonical_address(&env.message.sender)?;
+
+    if let Some(max_ltv) = max_ltv_override {
+        if max_ltv.is_zero() || max_ltv >= Decimal256::one() {
+            return Err(ContractError::InvalidMaxLtvOverride {}.into());
+        }
+    }
+
+    // lowering the override takes effect at once,
+    // loosening it must wait for the cooldown
+    if let Some(cur_override) = read_max_ltv_override_elem(&deps.storage, &borrower_raw)? {
+        let raising = match max_ltv_override {
+            Some(max_ltv) => max_ltv > cur_override.max_ltv,
+            None => true,
+        };
+
+        let raisable_at = cur_override.updated_at + MAX_LTV_OVERRIDE_COOLDOWN;
+        if raising && env.block.time < raisable_at {
+            return Err(ContractError::MaxLtvOverrideLocked { raisable_at }.into());
+        }
+    }
+
+    match max_ltv_override {
+        Some(max_ltv) => store_max_ltv_override(
+            &mut deps.storage,
+            &borrower_raw,
+            &MaxLtvOverride {
+                max_ltv,
+                updated_at: env.block.time,
+            },
+        )?,
+        None => remove_max_ltv_override(&mut deps.storage, &borrower_raw),
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_max_ltv_override"),
+            log("borrower", env.message.sender),
+            log(
+                "max_ltv_override",
+                max_ltv_override.map_or_else(|| "none".to_string(), |v| v.to_string()),
+            ),
+        ],
+        data: None,
+    })
+}
+
+/// The max LTV `borrower` imposed on its own borrows and unlocks
+pub(crate) fn read_max_ltv_override<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<Decimal256>> {
+    Ok(read_max_ltv_override_elem(storage, borrower)?.map(|v| v.max_ltv))
+}
+
+pub fn query_max_ltv_override<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<MaxLtvOverrideResponse> {
+    let max_ltv_override =
+        read_max_ltv_override_elem(&deps.storage, &deps.api.canonical_address(&borrower)?)?;
+    Ok(MaxLtvOverrideResponse {
+        borrower,
+        max_ltv_override: max_ltv_override.as_ref().map(|v| v.max_ltv),
+        raisable_at: max_ltv_override.map(|v| v.updated_at + MAX_LTV_OVERRIDE_COOLDOWN),
+    })
+}
@@ -0,0 +1,169 @@
+use cosmwasm_std::{
+    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdResult, Storage, WasmMsg,
+};
+
+use crate::state::{
+    read_collaterals, read_config, read_position_transfer, read_sponsorships, read_whitelist_elem,
+    remove_ltv_checkpoint, remove_position_transfer, store_collaterals, store_position_transfer,
+    Config, WhitelistElem,
+};
+
+use moneymarket::custody::HandleMsg as CustodyHandleMsg;
+use moneymarket::error::ContractError;
+use moneymarket::market::HandleMsg as MarketHandleMsg;
+use moneymarket::overseer::PositionTransferResponse;
+use moneymarket::tokens::Tokens;
+
+/// Offers the position of the sender to `to`, replacing a previous
+/// offer. Sponsored collaterals belong to their lockers, so positions
+/// holding them cannot be transferred
+pub fn transfer_position<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    to: HumanAddr,
+) -> HandleResult {
+    let borrower = env.message.sender;
+    if borrower == to {
+        return Err(ContractError::PositionTransferToSelf {}.into());
+    }
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    assert_not_sponsored(&deps.storage, &borrower_raw)?;
+    store_position_transfer(
+        &mut deps.storage,
+        &borrower_raw,
+        &deps.api.canonical_address(&to)?,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "transfer_position"),
+            log("borrower", borrower),
+            log("to", to),
+        ],
+        data: None,
+    })
+}
+
+pub fn cancel_position_transfer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let borrower = env.message.sender;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    if read_position_transfer(&deps.storage, &borrower_raw)?.is_none() {
+        return Err(ContractError::NoPositionTransfer {}.into());
+    }
+
+    remove_position_transfer(&mut deps.storage, &borrower_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "cancel_position_transfer"),
+            log("borrower", borrower),
+        ],
+        data: None,
+    })
+}
+
+/// Moves the collaterals in the overseer and the custody contracts
+/// and the loan in the market within the same transaction, so the
+/// position is moved as a whole or not at all. The market rejects
+/// the transfer when the receiver has a loan
+pub fn accept_position<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let to = env.message.sender;
+    let from_raw = deps.api.canonical_address(&from)?;
+    let to_raw = deps.api.canonical_address(&to)?;
+
+    match read_position_transfer(&deps.storage, &from_raw)? {
+        Some(v) if v == to_raw => {}
+        _ => return Err(ContractError::NoPositionTransferToSender {}.into()),
+    }
+
+    if !read_collaterals(&deps.storage, &to_raw).is_empty() {
+        return Err(ContractError::AcceptWithCollaterals {}.into());
+    }
+
+    // sponsorships could have been made after the offer
+    assert_not_sponsored(&deps.storage, &from_raw)?;
+    assert_not_sponsored(&deps.storage, &to_raw)?;
+
+    let collaterals: Tokens = read_collaterals(&deps.storage, &from_raw);
+    store_collaterals(&mut deps.storage, &to_raw, &collaterals)?;
+    store_collaterals(&mut deps.storage, &from_raw, &vec![])?;
+    remove_position_transfer(&mut deps.storage, &from_raw);
+
+    // the rebate checkpoints sample the LTV of the previous owner
+    remove_ltv_checkpoint(&mut deps.storage, &from_raw);
+    remove_ltv_checkpoint(&mut deps.storage, &to_raw);
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for collateral in collaterals.iter() {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&whitelist_elem.custody_contract)?,
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::TransferCollateral {
+                from: from.clone(),
+                to: to.clone(),
+                amount: collateral.1,
+            })?,
+        }));
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&config.market_contract)?,
+        send: vec![],
+        msg: to_binary(&MarketHandleMsg::TransferLiability {
+            from: from.clone(),
+            to: to.clone(),
+        })?,
+    }));
+
+    let collateral_logs: Vec<String> = collaterals
+        .iter()
+        .map(|c| Ok(format!("{}{}", c.1, deps.api.human_address(&c.0)?)))
+        .collect::<StdResult<Vec<String>>>()?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "accept_position"),
+            log("from", from),
+            log("to", to),
+            log("collaterals", collateral_logs.join(",")),
+        ],
+        data: None,
+    })
+}
+
+fn assert_not_sponsored<S: Storage>(storage: &S, borrower: &CanonicalAddr) -> StdResult<()> {
+    if !read_sponsorships(storage, borrower)?.is_empty() {
+        return Err(ContractError::TransferSponsoredPosition {}.into());
+    }
+
+    Ok(())
+}
+
+pub fn query_position_transfer<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<PositionTransferResponse> {
+    let to = match read_position_transfer(&deps.storage, &deps.api.canonical_address(&borrower)?)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoPositionTransfer {}.into()),
+    };
+
+    Ok(PositionTransferResponse {
+        borrower,
+        to: deps.api.human_address(&to)?,
+    })
+}
@@ -0,0 +1,198 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+
+use crate::collateral::query_collateral_price;
+use crate::querier::query_dex_twap;
+use crate::state::{
+    read_config, read_price_check, read_whitelist_elem, remove_price_check, store_price_check,
+    Config, PriceCheckConfig,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::overseer::{PriceCheck, PriceCheckResponse};
+use moneymarket::tokens::Tokens;
+
+pub fn update_price_check<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    price_check: Option<PriceCheck>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+
+    let enabled = price_check.is_some();
+    match price_check {
+        Some(v) => {
+            if v.max_deviation.is_zero() {
+                return Err(ContractError::ZeroParameter {
+                    name: "max_deviation".to_string(),
+                }
+                .into());
+            }
+
+            store_price_check(
+                &mut deps.storage,
+                &collateral_token_raw,
+                &PriceCheckConfig {
+                    dex_twap: deps.api.canonical_address(&v.dex_twap)?,
+                    max_deviation: v.max_deviation,
+                },
+            )?;
+        }
+        None => remove_price_check(&mut deps.storage, &collateral_token_raw),
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_price_check"),
+            log("collateral_token", collateral_token),
+            log("enabled", enabled),
+        ],
+        data: None,
+    })
+}
+
+/// Whether the oracle price of `collateral_token` diverges from its
+/// DEX TWAP beyond the max deviation. An unavailable TWAP counts as
+/// diverged, so a broken source blocks the collateral instead of
+/// failing every operation of its borrowers, repayments included
+pub(crate) fn is_price_diverged<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collateral_token: &CanonicalAddr,
+    oracle_price: Decimal256,
+) -> StdResult<bool> {
+    let price_check = match read_price_check(&deps.storage, collateral_token)? {
+        Some(v) => v,
+        None => return Ok(false),
+    };
+
+    Ok(
+        match query_dex_twap(
+            deps,
+            &deps.api.human_address(&price_check.dex_twap)?,
+            (deps.api.human_address(collateral_token)?).to_string(),
+            config.stable_denom.to_string(),
+        ) {
+            Ok(v) => exceeds_max_deviation(oracle_price, v.twap, price_check.max_deviation),
+            Err(_) => true,
+        },
+    )
+}
+
+/// Whether any of `collaterals`, priced at `collateral_prices`,
+/// has an oracle price diverging from its DEX TWAP
+pub(crate) fn has_diverged_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collaterals: &Tokens,
+    collateral_prices: &[Decimal256],
+) -> StdResult<bool> {
+    for (collateral, price) in collaterals.iter().zip(collateral_prices.iter()) {
+        if is_price_diverged(deps, config, &collateral.0, *price)? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Returns an error when any of the `collaterals` to unlock
+/// has an oracle price diverging from its DEX TWAP
+pub(crate) fn assert_prices_converged<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collaterals: &Tokens,
+    block_time: u64,
+) -> StdResult<()> {
+    for collateral in collaterals.iter() {
+        if read_price_check(&deps.storage, &collateral.0)?.is_none() {
+            continue;
+        }
+
+        let (oracle_price, _) =
+            query_collateral_price(deps, config, &collateral.0, Some(block_time))?;
+        if is_price_diverged(deps, config, &collateral.0, oracle_price)? {
+            return Err(ContractError::PriceDivergence {
+                token: deps.api.human_address(&collateral.0)?.to_string(),
+            }
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// |oracle_price - twap| / twap > max_deviation
+fn exceeds_max_deviation(
+    oracle_price: Decimal256,
+    twap: Decimal256,
+    max_deviation: Decimal256,
+) -> bool {
+    if twap.is_zero() {
+        return true;
+    }
+
+    let difference = if oracle_price > twap {
+        oracle_price - twap
+    } else {
+        twap - oracle_price
+    };
+
+    difference / twap > max_deviation
+}
+
+pub fn query_price_check<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<PriceCheckResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let price_check = match read_price_check(&deps.storage, &collateral_token_raw)? {
+        Some(v) => v,
+        None => {
+            return Ok(PriceCheckResponse {
+                collateral_token,
+                price_check: None,
+                oracle_price: None,
+                dex_twap: None,
+                diverged: false,
+            })
+        }
+    };
+
+    let dex_twap = deps.api.human_address(&price_check.dex_twap)?;
+    let (oracle_price, _) = query_collateral_price(deps, &config, &collateral_token_raw, None)?;
+    let twap = query_dex_twap(
+        deps,
+        &dex_twap,
+        collateral_token.to_string(),
+        config.stable_denom.to_string(),
+    )
+    .ok()
+    .map(|v| v.twap);
+
+    Ok(PriceCheckResponse {
+        price_check: Some(PriceCheck {
+            dex_twap,
+            max_deviation: price_check.max_deviation,
+        }),
+        oracle_price: Some(oracle_price),
+        dex_twap: twap,
+        diverged: match twap {
+            Some(v) => exceeds_max_deviation(oracle_price, v, price_check.max_deviation),
+            None => true,
+        },
+        collateral_token,
+    })
+}
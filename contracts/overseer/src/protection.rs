@@ -0,0 +1,84 @@
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdResult, Storage,
+};
+
+use crate::state::{
+    read_collateral_protection, read_config, read_whitelist_elem, remove_collateral_protection,
+    store_collateral_protection, Config,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::overseer::CollateralProtectionResponse;
+
+/// Collaterals of the sender to liquidate last, the first listed being
+/// seized last. The liquidations keep seizing the same value, only the
+/// collaterals it is taken from change
+pub fn update_collateral_protection<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    protected_collaterals: Vec<HumanAddr>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if protected_collaterals.len() as u64 > config.max_collateral_types {
+        return Err(ContractError::TooManyProtectedCollaterals {
+            max: config.max_collateral_types,
+        }
+        .into());
+    }
+
+    let mut protected_collaterals_raw: Vec<CanonicalAddr> = vec![];
+    for collateral_token in protected_collaterals.iter() {
+        let collateral_token_raw = deps.api.canonical_address(collateral_token)?;
+        read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+        if protected_collaterals_raw.contains(&collateral_token_raw) {
+            return Err(ContractError::CollateralProtectedTwice {
+                token: collateral_token.to_string(),
+            }
+            .into());
+        }
+
+        protected_collaterals_raw.push(collateral_token_raw);
+    }
+
+    let borrower = env.message.sender;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    if protected_collaterals_raw.is_empty() {
+        remove_collateral_protection(&mut deps.storage, &borrower_raw);
+    } else {
+        store_collateral_protection(&mut deps.storage, &borrower_raw, &protected_collaterals_raw)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_collateral_protection"),
+            log("borrower", borrower),
+            log(
+                "protected_collaterals",
+                protected_collaterals
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_collateral_protection<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<CollateralProtectionResponse> {
+    let protected_collaterals =
+        read_collateral_protection(&deps.storage, &deps.api.canonical_address(&borrower)?)?;
+
+    Ok(CollateralProtectionResponse {
+        borrower,
+        protected_collaterals: protected_collaterals
+            .iter()
+            .map(|v| deps.api.human_address(v))
+            .collect::<StdResult<Vec<HumanAddr>>>()?,
+    })
+}
@@ -0,0 +1,29 @@
+use cosmwasm_std::{Api, Extern, Querier, StdResult, Storage};
+
+use moneymarket::overseer::HandleMsg;
+use moneymarket::protocol_mode::load_protocol_mode;
+
+/// Rejects the user operations the protocol mode does not allow,
+/// collateral locks open positions while unlocks and liquidations
+/// unwind them
+pub(crate) fn assert_protocol_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+    msg: &HandleMsg,
+) -> StdResult<()> {
+    match msg {
+        HandleMsg::LockCollateral { .. }
+        | HandleMsg::LockCollateralBatch { .. }
+        | HandleMsg::LockCollateralFor { .. }
+        | HandleMsg::SwapCollateral { .. } => {
+            load_protocol_mode(deps, block_height, false)?.assert_deposits_allowed()
+        }
+        HandleMsg::UnlockCollateral { .. }
+        | HandleMsg::UnlockCollateralBatch { .. }
+        | HandleMsg::UnlockCollateralFor { .. }
+        | HandleMsg::LiquidateCollateral { .. } => {
+            load_protocol_mode(deps, block_height, false)?.assert_withdrawals_allowed()
+        }
+        _ => Ok(()),
+    }
+}
@@ -3,8 +3,15 @@ use cosmwasm_std::{
     to_binary, Api, Extern, HumanAddr, Querier, QueryRequest, StdResult, Storage, WasmQuery,
 };
 
-use moneymarket::liquidation::{LiquidationAmountResponse, QueryMsg as LiquidationQueryMsg};
+use moneymarket::dex_twap::{QueryMsg as DexTwapQueryMsg, TwapResponse};
+use moneymarket::liquidation::{
+    ConfigResponse as LiquidationConfigResponse, LiquidationAmountResponse,
+    LiquidationMechanismResponse, QueryMsg as LiquidationQueryMsg,
+};
 use moneymarket::market::{BorrowerInfoResponse, EpochStateResponse, QueryMsg as MarketQueryMsg};
+use moneymarket::risk_module::{
+    CanBorrowResponse, CanWithdrawResponse, QueryMsg as RiskModuleQueryMsg,
+};
 use moneymarket::tokens::TokensHuman;
 
 pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
@@ -18,7 +25,7 @@ pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
             contract_addr: HumanAddr::from(market_addr),
             msg: to_binary(&MarketQueryMsg::EpochState {
                 block_height: Some(block_height),
-                distributed_interest: distributed_interest,
+                distributed_interest,
             })?,
         }))?;
 
@@ -66,3 +73,119 @@ pub fn query_liquidation_amount<S: Storage, A: Api, Q: Querier>(
 
     Ok(liquidation_amount_res)
 }
+
+pub fn query_liquidation_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    liquidation_contract: &HumanAddr,
+) -> StdResult<LiquidationConfigResponse> {
+    let config: LiquidationConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(liquidation_contract),
+            msg: to_binary(&LiquidationQueryMsg::Config {})?,
+        }))?;
+
+    Ok(config)
+}
+
+pub fn query_liquidation_mechanism<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    liquidation_contract: &HumanAddr,
+    collateral_token: &HumanAddr,
+) -> StdResult<LiquidationMechanismResponse> {
+    let mechanism: LiquidationMechanismResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(liquidation_contract),
+            msg: to_binary(&LiquidationQueryMsg::LiquidationMechanism {
+                collateral_token: HumanAddr::from(collateral_token),
+            })?,
+        }))?;
+
+    Ok(mechanism)
+}
+
+pub fn query_dex_twap<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    dex_twap: &HumanAddr,
+    base: String,
+    quote: String,
+) -> StdResult<TwapResponse> {
+    let twap_res: TwapResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(dex_twap),
+        msg: to_binary(&DexTwapQueryMsg::Twap { base, quote })?,
+    }))?;
+
+    Ok(twap_res)
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn query_can_borrow<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    risk_module: &HumanAddr,
+    borrower: &HumanAddr,
+    borrow_limit: Uint256,
+    collaterals: &TokensHuman,
+    collateral_prices: Vec<Decimal256>,
+) -> StdResult<CanBorrowResponse> {
+    let can_borrow_res: CanBorrowResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(risk_module),
+            msg: to_binary(&RiskModuleQueryMsg::CanBorrow {
+                borrower: HumanAddr::from(borrower),
+                borrow_limit,
+                collaterals: collaterals.clone(),
+                collateral_prices,
+            })?,
+        }))?;
+
+    Ok(can_borrow_res)
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn query_can_withdraw<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    risk_module: &HumanAddr,
+    borrower: &HumanAddr,
+    loan_amount: Uint256,
+    borrow_limit: Uint256,
+    collaterals: &TokensHuman,
+    unlock_collaterals: &TokensHuman,
+) -> StdResult<CanWithdrawResponse> {
+    let can_withdraw_res: CanWithdrawResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(risk_module),
+            msg: to_binary(&RiskModuleQueryMsg::CanWithdraw {
+                borrower: HumanAddr::from(borrower),
+                loan_amount,
+                borrow_limit,
+                collaterals: collaterals.clone(),
+                unlock_collaterals: unlock_collaterals.clone(),
+            })?,
+        }))?;
+
+    Ok(can_withdraw_res)
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn query_risk_liquidation_amount<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    risk_module: &HumanAddr,
+    borrower: &HumanAddr,
+    borrow_amount: Uint256,
+    borrow_limit: Uint256,
+    collaterals: &TokensHuman,
+    collateral_prices: Vec<Decimal256>,
+) -> StdResult<LiquidationAmountResponse> {
+    let liquidation_amount_res: LiquidationAmountResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(risk_module),
+            msg: to_binary(&RiskModuleQueryMsg::LiquidationAmount {
+                borrower: HumanAddr::from(borrower),
+                borrow_amount,
+                borrow_limit,
+                collaterals: collaterals.clone(),
+                collateral_prices,
+            })?,
+        }))?;
+
+    Ok(liquidation_amount_res)
+}
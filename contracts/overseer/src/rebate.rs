@@ -0,0 +1,230 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::collateral::query_collateral_price;
+use crate::querier::query_borrower_info;
+use crate::state::{
+    read_collaterals, read_config, read_epoch_records, read_epoch_state, read_ltv_checkpoint,
+    read_rebate_ltv, read_rebate_ltvs, read_rebate_rate, read_whitelist_elem,
+    remove_ltv_checkpoint, store_ltv_checkpoint, store_rebate_ltv, store_rebate_rate, Config,
+    EpochState, LtvCheckpoint, WhitelistElem,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::market::{BorrowerInfoResponse, HandleMsg as MarketHandleMsg};
+use moneymarket::overseer::{InterestRebateResponse, LtvCheckpointResponse};
+use moneymarket::tokens::Tokens;
+
+pub fn update_interest_rebate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    rebate_rate: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if rebate_rate > Decimal256::one() {
+        return Err(ContractError::RatioAboveOne {
+            name: "rebate_rate".to_string(),
+        }
+        .into());
+    }
+
+    store_rebate_rate(&mut deps.storage, &rebate_rate)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_interest_rebate"),
+            log("rebate_rate", rebate_rate),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_rebate_ltv<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    rebate_ltv: Decimal256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let whitelist_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+    if rebate_ltv > whitelist_elem.max_ltv {
+        return Err(ContractError::RebateLtvTooHigh {
+            max_ltv: whitelist_elem.max_ltv,
+        }
+        .into());
+    }
+
+    store_rebate_ltv(&mut deps.storage, &collateral_token_raw, &rebate_ltv)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_rebate_ltv"),
+            log("collateral_token", collateral_token),
+            log("rebate_ltv", rebate_ltv),
+        ],
+        data: None,
+    })
+}
+
+/// A checkpoint earns a rebate once a full epoch, started after the
+/// checkpoint was taken, has been executed. The borrower LTV is only
+/// sampled at the checkpoints, so anyone can checkpoint a borrower
+/// to drop the checkpoint of a loan above the rebate limit
+pub fn checkpoint_ltv<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let rebate_rate = read_rebate_rate(&deps.storage)?;
+    if rebate_rate.is_zero() {
+        return Err(ContractError::RebateDisabled {}.into());
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let epoch_state: EpochState = read_epoch_state(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    let rebate_limit = compute_rebate_limit(deps, &collaterals, env.block.time)?;
+    let borrower_info: BorrowerInfoResponse =
+        query_borrower_info(deps, &market, &borrower, env.block.height)?;
+
+    let loan_amount = borrower_info.loan_amount;
+    if loan_amount.is_zero() || loan_amount > rebate_limit {
+        remove_ltv_checkpoint(&mut deps.storage, &borrower_raw);
+
+        return Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "checkpoint_ltv"),
+                log("borrower", borrower),
+                log("loan_amount", loan_amount),
+                log("rebate_limit", rebate_limit),
+                log("eligible", false),
+            ],
+            data: None,
+        });
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut rebate_amount = Uint256::zero();
+    let mut checkpoint = LtvCheckpoint {
+        epoch_height: epoch_state.last_executed_height,
+        loan_amount,
+        interest_index: borrower_info.interest_index,
+    };
+
+    if let Some(prev) = read_ltv_checkpoint(&deps.storage, &borrower_raw)? {
+        // the epoch of the checkpoint and the following one have ended
+        if read_epoch_records(&deps.storage, Some(prev.epoch_height), Some(2))?.len() < 2 {
+            checkpoint = prev;
+        } else {
+            // new borrows since the checkpoint do not earn a rebate
+            let principal = std::cmp::min(prev.loan_amount, loan_amount);
+            let accrued_interest = Decimal256::from_uint256(principal)
+                * (borrower_info.interest_index / prev.interest_index - Decimal256::one());
+            rebate_amount =
+                std::cmp::min(accrued_interest * rebate_rate * Uint256::one(), loan_amount);
+
+            if !rebate_amount.is_zero() {
+                checkpoint.loan_amount = loan_amount - rebate_amount;
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: market,
+                    send: vec![],
+                    msg: to_binary(&MarketHandleMsg::RebateInterest {
+                        borrower: borrower.clone(),
+                        amount: rebate_amount,
+                    })?,
+                }));
+            }
+        }
+    }
+
+    store_ltv_checkpoint(&mut deps.storage, &borrower_raw, &checkpoint)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "checkpoint_ltv"),
+            log("borrower", borrower),
+            log("loan_amount", loan_amount),
+            log("rebate_limit", rebate_limit),
+            log("eligible", true),
+            log("rebate_amount", rebate_amount),
+        ],
+        data: None,
+    })
+}
+
+/// Same as the borrow limit with the rebate LTVs, capped
+/// to the max LTV in case it was lowered afterwards
+fn compute_rebate_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collaterals: &[(CanonicalAddr, Uint256)],
+    block_time: u64,
+) -> StdResult<Uint256> {
+    let config: Config = read_config(&deps.storage)?;
+
+    let mut rebate_limit = Uint256::zero();
+    for collateral in collaterals.iter() {
+        let rebate_ltv = read_rebate_ltv(&deps.storage, &collateral.0)?;
+        if rebate_ltv.is_zero() {
+            continue;
+        }
+
+        let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
+        let (price, _) = query_collateral_price(deps, &config, &collateral.0, Some(block_time))?;
+        rebate_limit += collateral.1 * price * std::cmp::min(rebate_ltv, elem.max_ltv);
+    }
+
+    Ok(rebate_limit)
+}
+
+pub fn query_interest_rebate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<InterestRebateResponse> {
+    let rebate_ltvs = read_rebate_ltvs(&deps.storage)?
+        .into_iter()
+        .map(|(collateral_token, rebate_ltv)| {
+            Ok((deps.api.human_address(&collateral_token)?, rebate_ltv))
+        })
+        .collect::<StdResult<Vec<(HumanAddr, Decimal256)>>>()?;
+
+    Ok(InterestRebateResponse {
+        rebate_rate: read_rebate_rate(&deps.storage)?,
+        rebate_ltvs,
+    })
+}
+
+pub fn query_ltv_checkpoint<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<LtvCheckpointResponse> {
+    let checkpoint =
+        match read_ltv_checkpoint(&deps.storage, &deps.api.canonical_address(&borrower)?)? {
+            Some(v) => v,
+            None => return Err(ContractError::NoLtvCheckpoint {}.into()),
+        };
+
+    Ok(LtvCheckpointResponse {
+        borrower,
+        epoch_height: checkpoint.epoch_height,
+        loan_amount: checkpoint.loan_amount,
+        interest_index: checkpoint.interest_index,
+    })
+}
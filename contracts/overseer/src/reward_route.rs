@@ -0,0 +1,118 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, to_binary, Api, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::state::{
+    read_config, read_reward_route, read_whitelist_elem, remove_reward_route, store_reward_route,
+    Config,
+};
+
+use moneymarket::custody::HandleMsg as CustodyHandleMsg;
+use moneymarket::error::ContractError;
+use moneymarket::overseer::{RewardDestination, RewardRouteResponse, RewardShare};
+
+pub fn update_reward_route<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    route: Vec<RewardShare>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+
+    if route.is_empty() {
+        remove_reward_route(&mut deps.storage, &collateral_token_raw);
+    } else {
+        let mut total_ratio = Decimal256::zero();
+        for share in route.iter() {
+            if share.ratio.is_zero() {
+                return Err(ContractError::ZeroRewardShare {}.into());
+            }
+
+            if let RewardDestination::Pool { address } = &share.destination {
+                deps.api.canonical_address(address)?;
+            }
+
+            total_ratio += share.ratio;
+        }
+
+        if total_ratio != Decimal256::one() {
+            return Err(ContractError::InvalidRewardShares { total_ratio }.into());
+        }
+
+        store_reward_route(&mut deps.storage, &collateral_token_raw, &route)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_reward_route"),
+            log("collateral_token", collateral_token),
+            log("shares", route.len()),
+        ],
+        data: None,
+    })
+}
+
+/// `DistributeRewards` of the custody of `collateral_token`, sending
+/// the rewards along its reward route. Unrouted collaterals keep the
+/// custody default, the overseer interest buffer
+pub(crate) fn distribute_rewards_msg<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    overseer_contract: &HumanAddr,
+    collateral_token: &HumanAddr,
+    custody_contract: &HumanAddr,
+) -> StdResult<CosmosMsg> {
+    let route = read_reward_route(
+        &deps.storage,
+        &deps.api.canonical_address(collateral_token)?,
+    )?;
+    let reward_recipients = if route.is_empty() {
+        None
+    } else {
+        Some(
+            route
+                .into_iter()
+                .map(|share| {
+                    let recipient = match share.destination {
+                        RewardDestination::InterestBuffer {} => overseer_contract.clone(),
+                        RewardDestination::Market {} => {
+                            deps.api.human_address(&config.market_contract)?
+                        }
+                        RewardDestination::Pool { address } => address,
+                    };
+
+                    Ok((recipient, share.ratio))
+                })
+                .collect::<StdResult<Vec<(HumanAddr, Decimal256)>>>()?,
+        )
+    };
+
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: custody_contract.clone(),
+        send: vec![],
+        msg: to_binary(&CustodyHandleMsg::DistributeRewards { reward_recipients })?,
+    }))
+}
+
+pub fn query_reward_route<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<RewardRouteResponse> {
+    let route = read_reward_route(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+    Ok(RewardRouteResponse {
+        collateral_token,
+        route,
+    })
+}
@@ -0,0 +1,123 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::querier::{query_can_borrow, query_can_withdraw};
+use crate::state::{read_config, read_risk_module, remove_risk_module, store_risk_module, Config};
+
+use moneymarket::overseer::RiskModuleResponse;
+use moneymarket::tokens::{Tokens, TokensToHuman};
+
+/// Delegate the borrow, withdrawal and liquidation
+/// decisions to the `risk_module` contract
+pub fn update_risk_module<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    risk_module: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_risk_module(
+        &mut deps.storage,
+        &deps.api.canonical_address(&risk_module)?,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_risk_module"),
+            log("risk_module", risk_module),
+        ],
+        data: None,
+    })
+}
+
+pub fn disable_risk_module<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    remove_risk_module(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "disable_risk_module")],
+        data: None,
+    })
+}
+
+/// Returns the risk module the decisions are delegated to,
+/// or `None` when the overseer applies its own rules
+pub(crate) fn read_active_risk_module<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<Option<HumanAddr>> {
+    match read_risk_module(&deps.storage)? {
+        Some(v) => Ok(Some(deps.api.human_address(&v)?)),
+        None => Ok(None),
+    }
+}
+
+/// Whether `borrower` can borrow against `collaterals`,
+/// always true without risk module
+pub(crate) fn can_borrow<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: &HumanAddr,
+    borrow_limit: Uint256,
+    collaterals: &Tokens,
+    collateral_prices: Vec<Decimal256>,
+) -> StdResult<bool> {
+    match read_active_risk_module(deps)? {
+        Some(risk_module) => Ok(query_can_borrow(
+            deps,
+            &risk_module,
+            borrower,
+            borrow_limit,
+            &collaterals.to_human(deps)?,
+            collateral_prices,
+        )?
+        .allowed),
+        None => Ok(true),
+    }
+}
+
+/// Whether `borrower` can withdraw `unlock_collaterals`,
+/// always true without risk module
+pub(crate) fn can_withdraw<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: &HumanAddr,
+    loan_amount: Uint256,
+    borrow_limit: Uint256,
+    collaterals: &Tokens,
+    unlock_collaterals: &Tokens,
+) -> StdResult<bool> {
+    match read_active_risk_module(deps)? {
+        Some(risk_module) => Ok(query_can_withdraw(
+            deps,
+            &risk_module,
+            borrower,
+            loan_amount,
+            borrow_limit,
+            &collaterals.to_human(deps)?,
+            &unlock_collaterals.to_human(deps)?,
+        )?
+        .allowed),
+        None => Ok(true),
+    }
+}
+
+pub fn query_risk_module<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<RiskModuleResponse> {
+    Ok(RiskModuleResponse {
+        risk_module: read_active_risk_module(deps)?,
+    })
+}
@@ -0,0 +1,66 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Api, Extern, Querier, StdResult, Storage};
+
+use crate::collateral::query_collateral_price;
+use crate::querier::query_epoch_state;
+use crate::state::{read_config, read_whitelist, Config};
+
+use moneymarket::market::EpochStateResponse;
+use moneymarket::overseer::{SolvencyResponse, WhitelistResponseElem};
+use moneymarket::querier::query_token_balance;
+
+/// Assets of the protocol against what it owes the depositors at
+/// `block_height`. Collateralized loans are counted up to the oracle
+/// value of the collaterals held by the custody contracts
+pub fn query_solvency<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<SolvencyResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let market_contract = deps.api.human_address(&config.market_contract)?;
+    let epoch_state: EpochStateResponse =
+        query_epoch_state(deps, &market_contract, block_height, None)?;
+
+    let whitelist: Vec<WhitelistResponseElem> = read_whitelist(deps, None, None)?;
+    let mut collateral_value = Uint256::zero();
+    for elem in whitelist.iter() {
+        let balance = query_token_balance(deps, &elem.collateral_token, &elem.custody_contract)?;
+        let (price, _) = query_collateral_price(
+            deps,
+            &config,
+            &deps.api.canonical_address(&elem.collateral_token)?,
+            None,
+        )?;
+        collateral_value += balance * price;
+    }
+
+    // credit lines are not backed by collaterals
+    let collateral_value_dec = Decimal256::from_uint256(collateral_value);
+    let receivable = if epoch_state.total_liabilities < collateral_value_dec {
+        epoch_state.total_liabilities
+    } else {
+        collateral_value_dec
+    } + epoch_state.total_credit_liabilities;
+
+    let total_assets = Decimal256::from_uint256(epoch_state.market_balance) + receivable;
+    let aterra_value =
+        Decimal256::from_uint256(epoch_state.aterra_supply * epoch_state.exchange_rate);
+    let total_obligations = aterra_value + epoch_state.total_reserves;
+    let solvency_ratio = if total_obligations.is_zero() {
+        Decimal256::one()
+    } else {
+        total_assets / total_obligations
+    };
+
+    Ok(SolvencyResponse {
+        market_balance: epoch_state.market_balance,
+        total_liabilities: epoch_state.total_liabilities,
+        total_credit_liabilities: epoch_state.total_credit_liabilities,
+        collateral_value,
+        total_assets,
+        aterra_value,
+        total_reserves: epoch_state.total_reserves,
+        total_obligations,
+        solvency_ratio,
+    })
+}
@@ -0,0 +1,246 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdResult, Storage, WasmMsg,
+};
+
+use crate::collateral::{assert_max_collateral_types, compute_borrow_limit};
+use crate::querier::query_borrower_info;
+use crate::state::{
+    read_collaterals, read_config, read_sponsorship, read_sponsorships, read_whitelist_elem,
+    store_collaterals, store_sponsorship, Config, Sponsorship, WhitelistElem,
+};
+
+use moneymarket::custody::HandleMsg as CustodyHandleMsg;
+use moneymarket::error::ContractError;
+use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::overseer::{SponsorshipResponse, SponsorshipsResponse};
+use moneymarket::tokens::{Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
+
+pub fn lock_collateral_for<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+    collaterals_human: TokensHuman,
+    withdraw_allowed: bool,
+) -> HandleResult {
+    let locker = env.message.sender;
+    if locker == borrower {
+        return Err(ContractError::LockForSelf {}.into());
+    }
+
+    let locker_raw = deps.api.canonical_address(&locker)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals: Tokens = collaterals_human.to_raw(deps)?;
+
+    let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    let prev_collateral_types = cur_collaterals.len();
+    cur_collaterals.add(collaterals.clone());
+    assert_max_collateral_types(&deps.storage, prev_collateral_types, &cur_collaterals)?;
+    store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+
+    // the consent flag is overwritten on every lock
+    let mut sponsorship =
+        read_sponsorship(&deps.storage, &borrower_raw, &locker_raw).unwrap_or(Sponsorship {
+            collaterals: vec![],
+            withdraw_allowed,
+        });
+    sponsorship.collaterals.add(collaterals.clone());
+    sponsorship.withdraw_allowed = withdraw_allowed;
+    store_sponsorship(&mut deps.storage, &borrower_raw, &locker_raw, &sponsorship)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for collateral in collaterals {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&whitelist_elem.custody_contract)?,
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LockCollateralFor {
+                locker: locker.clone(),
+                borrower: borrower.clone(),
+                amount: collateral.1,
+            })?,
+        }));
+    }
+
+    // Logging stuff, so can be removed
+    let collateral_logs: Vec<String> = collaterals_human
+        .iter()
+        .map(|c| format!("{}{}", c.1, c.0))
+        .collect();
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "lock_collateral_for"),
+            log("locker", locker),
+            log("borrower", borrower),
+            log("collaterals", collateral_logs.join(",")),
+            log("withdraw_allowed", withdraw_allowed),
+        ],
+        data: None,
+    })
+}
+
+pub fn unlock_collateral_for<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+    collaterals_human: TokensHuman,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+
+    let locker = env.message.sender;
+    let locker_raw = deps.api.canonical_address(&locker)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals: Tokens = collaterals_human.to_raw(deps)?;
+
+    let mut sponsorship = match read_sponsorship(&deps.storage, &borrower_raw, &locker_raw) {
+        Some(v) => v,
+        None => return Err(ContractError::NoSponsoredCollaterals {}.into()),
+    };
+
+    if sponsorship.collaterals.sub(collaterals.clone()).is_err() {
+        return Err(ContractError::UnlockExceedsSponsored {}.into());
+    }
+
+    let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    if cur_collaterals.sub(collaterals.clone()).is_err() {
+        return Err(ContractError::UnlockExceedsLockedCollateral {}.into());
+    }
+
+    // The borrower's loan must stay covered without the returned collaterals
+    let (borrow_limit, _, _) =
+        compute_borrow_limit(deps, &cur_collaterals, Some(env.block.time), None)?;
+    let borrow_amount_res: BorrowerInfoResponse =
+        query_borrower_info(deps, &market, &borrower, env.block.height)?;
+    if borrow_limit < borrow_amount_res.loan_amount {
+        return Err(ContractError::UnlockExceedsBorrowLimit { borrow_limit }.into());
+    }
+
+    store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+    store_sponsorship(&mut deps.storage, &borrower_raw, &locker_raw, &sponsorship)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for collateral in collaterals {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&whitelist_elem.custody_contract)?,
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::UnlockCollateralFor {
+                locker: locker.clone(),
+                borrower: borrower.clone(),
+                amount: collateral.1,
+            })?,
+        }));
+    }
+
+    // Logging stuff, so can be removed
+    let collateral_logs: Vec<String> = collaterals_human
+        .iter()
+        .map(|c| format!("{}{}", c.1, c.0))
+        .collect();
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "unlock_collateral_for"),
+            log("locker", locker),
+            log("borrower", borrower),
+            log("collaterals", collateral_logs.join(",")),
+        ],
+        data: None,
+    })
+}
+
+/// Returns the collaterals the borrower can unlock by itself,
+/// which excludes sponsored collaterals without withdraw consent
+pub(crate) fn compute_unlockable_collaterals<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+    collaterals: &[(CanonicalAddr, Uint256)],
+) -> StdResult<Tokens> {
+    let mut unlockable: Tokens = collaterals.to_vec();
+    for (_, sponsorship) in read_sponsorships(storage, borrower)? {
+        if sponsorship.withdraw_allowed {
+            continue;
+        }
+
+        for (token, amount) in sponsorship.collaterals {
+            if let Some(collateral) = unlockable.iter_mut().find(|c| c.0 == token) {
+                collateral.1 = if collateral.1 > amount {
+                    collateral.1 - amount
+                } else {
+                    Uint256::zero()
+                };
+            }
+        }
+    }
+
+    unlockable.retain(|c| c.1 > Uint256::zero());
+    Ok(unlockable)
+}
+
+/// Shrinks sponsorships to the remaining collaterals of the borrower, so
+/// liquidations and unlocks consume the borrower's own collaterals first.
+/// Sponsorships without withdraw consent are preserved before the others
+pub(crate) fn cap_sponsorships<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    collaterals: &[(CanonicalAddr, Uint256)],
+) -> StdResult<()> {
+    let mut sponsorships = read_sponsorships(storage, borrower)?;
+    sponsorships.sort_by_key(|(_, sponsorship)| sponsorship.withdraw_allowed);
+
+    let mut remaining: Tokens = collaterals.to_vec();
+    for (locker, mut sponsorship) in sponsorships {
+        let mut capped = false;
+        for sponsored in sponsorship.collaterals.iter_mut() {
+            let available = match remaining.iter_mut().find(|c| c.0 == sponsored.0) {
+                Some(v) => v,
+                None => {
+                    sponsored.1 = Uint256::zero();
+                    capped = true;
+                    continue;
+                }
+            };
+
+            if sponsored.1 > available.1 {
+                sponsored.1 = available.1;
+                capped = true;
+            }
+
+            available.1 = available.1 - sponsored.1;
+        }
+
+        if capped {
+            sponsorship.collaterals.retain(|c| c.1 > Uint256::zero());
+            store_sponsorship(storage, borrower, &locker, &sponsorship)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn query_sponsorships<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<SponsorshipsResponse> {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let sponsorships = read_sponsorships(&deps.storage, &borrower_raw)?
+        .into_iter()
+        .map(|(locker, sponsorship)| {
+            Ok(SponsorshipResponse {
+                locker: deps.api.human_address(&locker)?,
+                collaterals: sponsorship.collaterals.to_human(deps)?,
+                withdraw_allowed: sponsorship.withdraw_allowed,
+            })
+        })
+        .collect::<StdResult<Vec<SponsorshipResponse>>>()?;
+
+    Ok(SponsorshipsResponse {
+        borrower,
+        sponsorships,
+    })
+}
@@ -2,19 +2,58 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{
-    Api, CanonicalAddr, Extern, HumanAddr, Order, Querier, StdError, StdResult, Storage,
-};
+use cosmwasm_std::{Api, CanonicalAddr, Extern, HumanAddr, Order, Querier, StdResult, Storage};
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 
-use moneymarket::overseer::{CollateralsResponse, WhitelistResponseElem};
+use crate::contract::{DEFAULT_MAX_COLLATERAL_TYPES, DEFAULT_MAX_EMERGENCY_PRICE_DURATION};
+
+use moneymarket::error::ContractError;
+use moneymarket::overseer::{
+    AssetMetadata, CollateralsResponse, EpochPhase, EpochRecordResponse, InboxEvent,
+    InboxRecordResponse, LiquidationOrder, RewardShare, WhitelistResponseElem,
+};
 use moneymarket::tokens::Tokens;
 
 const KEY_CONFIG: &[u8] = b"config";
 const KEY_EPOCH_STATE: &[u8] = b"epoch_state";
+const KEY_EPOCH_PROGRESS: &[u8] = b"epoch_progress";
+const KEY_COLLATERAL_SWAP: &[u8] = b"collateral_swap";
+const KEY_REBATE_RATE: &[u8] = b"rebate_rate";
+const KEY_INDEX_UNIT: &[u8] = b"index_unit";
+const KEY_RISK_MODULE: &[u8] = b"risk_module";
+const KEY_ANALYTICS_CONTRACT: &[u8] = b"analytics_contract";
+const KEY_PENDING_KPIS: &[u8] = b"pending_kpis";
+const KEY_REGISTRY: &[u8] = b"registry";
+const KEY_INVARIANT_CHECKS: &[u8] = b"invariant_checks";
 
 const PREFIX_WHITELIST: &[u8] = b"whitelist";
 const PREFIX_COLLATERALS: &[u8] = b"collateral";
+const PREFIX_EMERGENCY_PRICE: &[u8] = b"emergency_price";
+const PREFIX_EMERGENCY_PRICE_PROPOSAL: &[u8] = b"emergency_price_proposal";
+const PREFIX_INBOX_META: &[u8] = b"inbox_meta";
+const PREFIX_INBOX: &[u8] = b"inbox";
+const PREFIX_SPONSORSHIP: &[u8] = b"sponsorship";
+const PREFIX_EPOCH_RECORD: &[u8] = b"epoch_record";
+const PREFIX_REBATE_LTV: &[u8] = b"rebate_ltv";
+const PREFIX_LTV_CHECKPOINT: &[u8] = b"ltv_checkpoint";
+const PREFIX_POSITION_TRANSFER: &[u8] = b"position_transfer";
+const PREFIX_INSURANCE_CONTRACT: &[u8] = b"insurance_contract";
+const PREFIX_POSITION_INSURANCE: &[u8] = b"position_insurance";
+const PREFIX_WHITELIST_PROPOSAL: &[u8] = b"whitelist_proposal";
+const PREFIX_ASSET_METADATA: &[u8] = b"asset_metadata";
+const PREFIX_COLLATERAL_PROTECTION: &[u8] = b"collateral_protection";
+const PREFIX_REWARD_ROUTE: &[u8] = b"reward_route";
+const PREFIX_PRICE_CHECK: &[u8] = b"price_check";
+const PREFIX_MAX_LTV_OVERRIDE: &[u8] = b"max_ltv_override";
+const PREFIX_LIQUIDATION_PRICE_TIME: &[u8] = b"liquidation_price_time";
+
+/// Maximum number of records kept per inbox;
+/// the oldest record is pruned when exceeded
+pub const MAX_INBOX_RECORDS: u64 = 30;
+
+/// Seconds after its last update before a max LTV
+/// override can be raised or removed
+pub const MAX_LTV_OVERRIDE_COOLDOWN: u64 = 86400;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -30,6 +69,57 @@ pub struct Config {
     pub buffer_distribution_factor: Decimal256,
     pub anc_purchase_factor: Decimal256,
     pub price_timeframe: u64,
+    /// guardians approving emergency prices, any of them
+    /// can change the operation mode of the market
+    #[serde(default)]
+    pub guardians: Vec<CanonicalAddr>,
+    /// # of guardians an emergency price needs
+    #[serde(default)]
+    pub guardian_threshold: u64,
+    /// max # of distinct collateral tokens a borrower can lock
+    #[serde(default = "default_max_collateral_types")]
+    pub max_collateral_types: u64,
+    /// router used for collateral swaps, unset when default
+    #[serde(default)]
+    pub swap_router: CanonicalAddr,
+    /// positions whose collaterals are worth less, in stable
+    /// denom, cannot borrow and are liquidated in full
+    #[serde(default)]
+    pub min_position_collateral_value: Uint256,
+    #[serde(default)]
+    pub liquidation_order: LiquidationOrder,
+    /// collaterals whose oracle price is older lose their borrow
+    /// power and keep their last price for liquidations, 0 disables
+    #[serde(default)]
+    pub max_feed_gap: u64,
+    /// epochs of interest buffer a late execution distributes for,
+    /// missed epochs are combined into one; 0 leaves it uncapped
+    #[serde(default)]
+    pub max_catch_up_epochs: u64,
+    /// fraction of each collateral a liquidation can seize
+    /// per call, 0 leaves it uncapped
+    #[serde(default)]
+    pub close_factor: Decimal256,
+    /// max # of seconds an emergency price can stay in force
+    #[serde(default = "default_max_emergency_price_duration")]
+    pub max_emergency_price_duration: u64,
+}
+
+fn default_max_collateral_types() -> u64 {
+    DEFAULT_MAX_COLLATERAL_TYPES
+}
+
+fn default_max_emergency_price_duration() -> u64 {
+    DEFAULT_MAX_EMERGENCY_PRICE_DURATION
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralSwap {
+    pub borrower: CanonicalAddr,
+    pub to_token: CanonicalAddr,
+    pub min_receive: Uint256,
+    /// to_token balance of the overseer before the swap
+    pub prev_balance: Uint256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -41,6 +131,32 @@ pub struct EpochState {
     pub last_executed_height: u64,
 }
 
+/// Phase reached by the epoch operations at `height`, and the
+/// buffer amounts carried from the reward distribution to the
+/// epoch state update
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochProgress {
+    pub phase: EpochPhase,
+    pub height: u64,
+    pub time: u64,
+    pub interest_buffer: Uint256,
+    pub distributed_interest: Uint256,
+}
+
+/// Inputs of the deposit rate computed on an epoch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochRecord {
+    pub blocks: u64,
+    pub prev_exchange_rate: Decimal256,
+    pub exchange_rate: Decimal256,
+    pub aterra_supply: Uint256,
+    pub market_balance: Uint256,
+    pub total_liabilities: Decimal256,
+    pub total_credit_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub deposit_rate: Decimal256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct WhitelistElem {
     pub name: String,
@@ -49,6 +165,64 @@ pub struct WhitelistElem {
     pub custody_contract: CanonicalAddr,
 }
 
+/// Collateral proposed for the whitelist, pending the owner
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistProposal {
+    pub name: String,
+    pub symbol: String,
+    pub custody_contract: CanonicalAddr,
+    pub max_ltv: Decimal256,
+    pub metadata: AssetMetadata,
+    pub proposer: CanonicalAddr,
+    pub proposed_height: u64,
+}
+
+/// Metadata of a collateral kept once its proposal is confirmed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetRiskMetadata {
+    pub metadata: AssetMetadata,
+    pub proposer: CanonicalAddr,
+    pub proposed_height: u64,
+    pub confirmed_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InboxMeta {
+    /// Sequence of the oldest stored record
+    pub first_seq: u64,
+    /// Sequence assigned to the next record
+    pub next_seq: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InboxRecord {
+    pub height: u64,
+    pub event: InboxEvent,
+}
+
+/// Collaterals locked by a sponsor for a borrower
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Sponsorship {
+    pub collaterals: Tokens,
+    pub withdraw_allowed: bool,
+}
+
+/// Loan of a borrower under its rebate limit at a checkpoint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LtvCheckpoint {
+    pub epoch_height: u64,
+    pub loan_amount: Uint256,
+    pub interest_index: Decimal256,
+}
+
+/// Index the borrow limits and liquidations are denominated in,
+/// priced in stable denom by the index oracle
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IndexUnit {
+    pub index_oracle: CanonicalAddr,
+    pub index_asset: String,
+}
+
 pub fn store_config<S: Storage>(storage: &mut S, data: &Config) -> StdResult<()> {
     Singleton::new(storage, KEY_CONFIG).save(data)
 }
@@ -65,13 +239,156 @@ pub fn read_epoch_state<S: Storage>(storage: &S) -> StdResult<EpochState> {
     ReadonlySingleton::new(storage, KEY_EPOCH_STATE).load()
 }
 
+pub fn store_epoch_progress<S: Storage>(storage: &mut S, data: &EpochProgress) -> StdResult<()> {
+    Singleton::new(storage, KEY_EPOCH_PROGRESS).save(data)
+}
+
+pub fn read_epoch_progress<S: Storage>(storage: &S) -> StdResult<EpochProgress> {
+    Ok(ReadonlySingleton::new(storage, KEY_EPOCH_PROGRESS)
+        .may_load()?
+        .unwrap_or(EpochProgress {
+            phase: EpochPhase::Idle,
+            height: 0,
+            time: 0,
+            interest_buffer: Uint256::zero(),
+            distributed_interest: Uint256::zero(),
+        }))
+}
+
+pub fn store_collateral_swap<S: Storage>(storage: &mut S, data: &CollateralSwap) -> StdResult<()> {
+    Singleton::new(storage, KEY_COLLATERAL_SWAP).save(data)
+}
+
+pub fn read_collateral_swap<S: Storage>(storage: &S) -> StdResult<Option<CollateralSwap>> {
+    ReadonlySingleton::new(storage, KEY_COLLATERAL_SWAP).may_load()
+}
+
+pub fn remove_collateral_swap<S: Storage>(storage: &mut S) {
+    Singleton::<S, CollateralSwap>::new(storage, KEY_COLLATERAL_SWAP).remove()
+}
+
+pub fn store_index_unit<S: Storage>(storage: &mut S, data: &IndexUnit) -> StdResult<()> {
+    Singleton::new(storage, KEY_INDEX_UNIT).save(data)
+}
+
+pub fn read_index_unit<S: Storage>(storage: &S) -> StdResult<Option<IndexUnit>> {
+    ReadonlySingleton::new(storage, KEY_INDEX_UNIT).may_load()
+}
+
+pub fn remove_index_unit<S: Storage>(storage: &mut S) {
+    Singleton::<S, IndexUnit>::new(storage, KEY_INDEX_UNIT).remove()
+}
+
+pub fn store_risk_module<S: Storage>(storage: &mut S, data: &CanonicalAddr) -> StdResult<()> {
+    Singleton::new(storage, KEY_RISK_MODULE).save(data)
+}
+
+pub fn read_risk_module<S: Storage>(storage: &S) -> StdResult<Option<CanonicalAddr>> {
+    ReadonlySingleton::new(storage, KEY_RISK_MODULE).may_load()
+}
+
+pub fn remove_risk_module<S: Storage>(storage: &mut S) {
+    Singleton::<S, CanonicalAddr>::new(storage, KEY_RISK_MODULE).remove()
+}
+
+pub fn store_analytics_contract<S: Storage>(
+    storage: &mut S,
+    data: &CanonicalAddr,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_ANALYTICS_CONTRACT).save(data)
+}
+
+pub fn read_analytics_contract<S: Storage>(storage: &S) -> StdResult<Option<CanonicalAddr>> {
+    ReadonlySingleton::new(storage, KEY_ANALYTICS_CONTRACT).may_load()
+}
+
+pub fn remove_analytics_contract<S: Storage>(storage: &mut S) {
+    Singleton::<S, CanonicalAddr>::new(storage, KEY_ANALYTICS_CONTRACT).remove()
+}
+
+/// Volumes accumulated since the last epoch operations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PendingKpis {
+    pub liquidation_volume: Uint256,
+    pub new_bad_debt: Uint256,
+}
+
+pub fn store_pending_kpis<S: Storage>(storage: &mut S, data: &PendingKpis) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_KPIS).save(data)
+}
+
+pub fn read_pending_kpis<S: Storage>(storage: &S) -> StdResult<PendingKpis> {
+    Ok(ReadonlySingleton::new(storage, KEY_PENDING_KPIS)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// Registry the peers are resolved through, at `version` and `updated_height`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegistryCache {
+    pub registry_contract: CanonicalAddr,
+    pub version: u64,
+    pub updated_height: u64,
+}
+
+pub fn store_registry_cache<S: Storage>(storage: &mut S, data: &RegistryCache) -> StdResult<()> {
+    Singleton::new(storage, KEY_REGISTRY).save(data)
+}
+
+pub fn remove_registry_cache<S: Storage>(storage: &mut S) {
+    Singleton::<S, RegistryCache>::new(storage, KEY_REGISTRY).remove()
+}
+
+pub fn store_invariant_checks<S: Storage>(storage: &mut S, enabled: bool) -> StdResult<()> {
+    Singleton::new(storage, KEY_INVARIANT_CHECKS).save(&enabled)
+}
+
+/// Invariant checks are disabled unless set otherwise
+pub fn read_invariant_checks<S: Storage>(storage: &S) -> StdResult<bool> {
+    Ok(ReadonlySingleton::new(storage, KEY_INVARIANT_CHECKS)
+        .may_load()?
+        .unwrap_or(false))
+}
+
+pub fn read_registry_cache<S: Storage>(storage: &S) -> StdResult<Option<RegistryCache>> {
+    ReadonlySingleton::new(storage, KEY_REGISTRY).may_load()
+}
+
+/// Max LTV a borrower imposed on its own position, updated at `updated_at`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MaxLtvOverride {
+    pub max_ltv: Decimal256,
+    pub updated_at: u64,
+}
+
+/// DEX TWAP the oracle price of a collateral is checked against
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceCheckConfig {
+    pub dex_twap: CanonicalAddr,
+    pub max_deviation: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmergencyPrice {
+    pub price: Decimal256,
+    pub expires_at: u64,
+}
+
+/// Emergency price waiting for `guardian_threshold` approvals
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmergencyPriceProposal {
+    pub price: Decimal256,
+    pub expires_at: u64,
+    pub approvals: Vec<CanonicalAddr>,
+}
+
 pub fn store_whitelist_elem<S: Storage>(
     storage: &mut S,
     collateral_token: &CanonicalAddr,
     whitelist_elem: &WhitelistElem,
 ) -> StdResult<()> {
     let mut whitelist_bucket: Bucket<S, WhitelistElem> = Bucket::new(PREFIX_WHITELIST, storage);
-    whitelist_bucket.save(collateral_token.as_slice(), &whitelist_elem)?;
+    whitelist_bucket.save(collateral_token.as_slice(), whitelist_elem)?;
 
     Ok(())
 }
@@ -82,11 +399,9 @@ pub fn read_whitelist_elem<S: Storage>(
 ) -> StdResult<WhitelistElem> {
     let whitelist_bucket: ReadonlyBucket<S, WhitelistElem> =
         ReadonlyBucket::new(PREFIX_WHITELIST, storage);
-    match whitelist_bucket.load(&collateral_token.as_slice()) {
+    match whitelist_bucket.load(collateral_token.as_slice()) {
         Ok(v) => Ok(v),
-        _ => Err(StdError::generic_err(
-            "Token is not registered as collateral",
-        )),
+        _ => Err(ContractError::CollateralNotRegistered {}.into()),
     }
 }
 
@@ -126,10 +441,10 @@ pub fn store_collaterals<S: Storage>(
     collaterals: &Tokens,
 ) -> StdResult<()> {
     let mut collaterals_bucket: Bucket<S, Tokens> = Bucket::new(PREFIX_COLLATERALS, storage);
-    if collaterals.len() == 0 {
-        collaterals_bucket.remove(&borrower.as_slice());
+    if collaterals.is_empty() {
+        collaterals_bucket.remove(borrower.as_slice());
     } else {
-        collaterals_bucket.save(&borrower.as_slice(), &collaterals)?;
+        collaterals_bucket.save(borrower.as_slice(), collaterals)?;
     }
 
     Ok(())
@@ -138,9 +453,129 @@ pub fn store_collaterals<S: Storage>(
 pub fn read_collaterals<S: Storage>(storage: &S, borrower: &CanonicalAddr) -> Tokens {
     let collaterals_bucket: ReadonlyBucket<S, Tokens> =
         ReadonlyBucket::new(PREFIX_COLLATERALS, storage);
-    match collaterals_bucket.load(&borrower.as_slice()) {
-        Ok(v) => v,
-        _ => vec![],
+    collaterals_bucket
+        .load(borrower.as_slice())
+        .unwrap_or_default()
+}
+
+pub fn store_emergency_price<S: Storage>(
+    storage: &mut S,
+    asset: &CanonicalAddr,
+    emergency_price: &EmergencyPrice,
+) -> StdResult<()> {
+    let mut emergency_price_bucket: Bucket<S, EmergencyPrice> =
+        Bucket::new(PREFIX_EMERGENCY_PRICE, storage);
+    emergency_price_bucket.save(asset.as_slice(), emergency_price)
+}
+
+pub fn read_emergency_price<S: Storage>(
+    storage: &S,
+    asset: &CanonicalAddr,
+) -> Option<EmergencyPrice> {
+    let emergency_price_bucket: ReadonlyBucket<S, EmergencyPrice> =
+        ReadonlyBucket::new(PREFIX_EMERGENCY_PRICE, storage);
+    emergency_price_bucket.load(asset.as_slice()).ok()
+}
+
+pub fn store_emergency_price_proposal<S: Storage>(
+    storage: &mut S,
+    asset: &CanonicalAddr,
+    proposal: &EmergencyPriceProposal,
+) -> StdResult<()> {
+    let mut proposal_bucket: Bucket<S, EmergencyPriceProposal> =
+        Bucket::new(PREFIX_EMERGENCY_PRICE_PROPOSAL, storage);
+    proposal_bucket.save(asset.as_slice(), proposal)
+}
+
+pub fn remove_emergency_price_proposal<S: Storage>(storage: &mut S, asset: &CanonicalAddr) {
+    let mut proposal_bucket: Bucket<S, EmergencyPriceProposal> =
+        Bucket::new(PREFIX_EMERGENCY_PRICE_PROPOSAL, storage);
+    proposal_bucket.remove(asset.as_slice())
+}
+
+pub fn read_emergency_price_proposal<S: Storage>(
+    storage: &S,
+    asset: &CanonicalAddr,
+) -> Option<EmergencyPriceProposal> {
+    let proposal_bucket: ReadonlyBucket<S, EmergencyPriceProposal> =
+        ReadonlyBucket::new(PREFIX_EMERGENCY_PRICE_PROPOSAL, storage);
+    proposal_bucket.load(asset.as_slice()).ok()
+}
+
+pub fn store_inbox_meta<S: Storage>(
+    storage: &mut S,
+    address: &CanonicalAddr,
+    inbox_meta: &InboxMeta,
+) -> StdResult<()> {
+    let mut inbox_meta_bucket: Bucket<S, InboxMeta> = Bucket::new(PREFIX_INBOX_META, storage);
+    inbox_meta_bucket.save(address.as_slice(), inbox_meta)
+}
+
+pub fn remove_inbox_meta<S: Storage>(storage: &mut S, address: &CanonicalAddr) {
+    let mut inbox_meta_bucket: Bucket<S, InboxMeta> = Bucket::new(PREFIX_INBOX_META, storage);
+    inbox_meta_bucket.remove(address.as_slice())
+}
+
+pub fn read_inbox_meta<S: Storage>(storage: &S, address: &CanonicalAddr) -> Option<InboxMeta> {
+    let inbox_meta_bucket: ReadonlyBucket<S, InboxMeta> =
+        ReadonlyBucket::new(PREFIX_INBOX_META, storage);
+    inbox_meta_bucket.load(address.as_slice()).ok()
+}
+
+/// Returns all inbox owners; only used on owner operations
+pub fn read_inbox_owners<S: Storage>(storage: &S) -> StdResult<Vec<CanonicalAddr>> {
+    let inbox_meta_bucket: ReadonlyBucket<S, InboxMeta> =
+        ReadonlyBucket::new(PREFIX_INBOX_META, storage);
+    inbox_meta_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, _) = elem?;
+            Ok(CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
+/// Appends a record to the inbox of the address when it is registered
+pub fn push_inbox_record<S: Storage>(
+    storage: &mut S,
+    address: &CanonicalAddr,
+    height: u64,
+    event: InboxEvent,
+) -> StdResult<()> {
+    let mut inbox_meta = match read_inbox_meta(storage, address) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let mut inbox_bucket: Bucket<S, InboxRecord> =
+        Bucket::multilevel(&[PREFIX_INBOX, address.as_slice()], storage);
+    inbox_bucket.save(
+        &inbox_meta.next_seq.to_be_bytes(),
+        &InboxRecord { height, event },
+    )?;
+    inbox_meta.next_seq += 1;
+
+    if inbox_meta.next_seq - inbox_meta.first_seq > MAX_INBOX_RECORDS {
+        inbox_bucket.remove(&inbox_meta.first_seq.to_be_bytes());
+        inbox_meta.first_seq += 1;
+    }
+
+    store_inbox_meta(storage, address, &inbox_meta)
+}
+
+/// Removes the inbox records up to and including `up_to` sequence
+pub fn remove_inbox_records<S: Storage>(
+    storage: &mut S,
+    address: &CanonicalAddr,
+    inbox_meta: &mut InboxMeta,
+    up_to: u64,
+) {
+    let up_to = up_to.min(inbox_meta.next_seq.saturating_sub(1));
+    let mut inbox_bucket: Bucket<S, InboxRecord> =
+        Bucket::multilevel(&[PREFIX_INBOX, address.as_slice()], storage);
+    while inbox_meta.first_seq <= up_to {
+        inbox_bucket.remove(&inbox_meta.first_seq.to_be_bytes());
+        inbox_meta.first_seq += 1;
     }
 }
 
@@ -177,6 +612,29 @@ pub fn read_all_collaterals<S: Storage, A: Api, Q: Querier>(
         .collect()
 }
 
+/// Collateral positions in borrower order, the raw
+/// counterpart of `read_all_collaterals`
+pub fn read_collateral_positions<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, Tokens)>> {
+    let collaterals_bucket: ReadonlyBucket<S, Tokens> =
+        ReadonlyBucket::new(PREFIX_COLLATERALS, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    collaterals_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
@@ -185,3 +643,471 @@ fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
         v
     })
 }
+
+pub fn read_inbox_records<S: Storage>(
+    storage: &S,
+    address: &CanonicalAddr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<InboxRecordResponse>> {
+    let inbox_bucket: ReadonlyBucket<S, InboxRecord> =
+        ReadonlyBucket::multilevel(&[PREFIX_INBOX, address.as_slice()], storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|seq| (seq + 1).to_be_bytes().to_vec());
+
+    inbox_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut seq_bytes = [0u8; 8];
+            seq_bytes.copy_from_slice(&k);
+            Ok(InboxRecordResponse {
+                seq: u64::from_be_bytes(seq_bytes),
+                height: v.height,
+                event: v.event,
+            })
+        })
+        .collect()
+}
+
+pub fn store_epoch_record<S: Storage>(
+    storage: &mut S,
+    height: u64,
+    epoch_record: &EpochRecord,
+) -> StdResult<()> {
+    let mut epoch_record_bucket: Bucket<S, EpochRecord> = Bucket::new(PREFIX_EPOCH_RECORD, storage);
+    epoch_record_bucket.save(&height.to_be_bytes(), epoch_record)
+}
+
+pub fn read_epoch_records<S: Storage>(
+    storage: &S,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<EpochRecordResponse>> {
+    let epoch_record_bucket: ReadonlyBucket<S, EpochRecord> =
+        ReadonlyBucket::new(PREFIX_EPOCH_RECORD, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|height| (height + 1).to_be_bytes().to_vec());
+
+    epoch_record_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut height_bytes = [0u8; 8];
+            height_bytes.copy_from_slice(&k);
+            Ok(EpochRecordResponse {
+                height: u64::from_be_bytes(height_bytes),
+                blocks: v.blocks,
+                prev_exchange_rate: v.prev_exchange_rate,
+                exchange_rate: v.exchange_rate,
+                aterra_supply: v.aterra_supply,
+                market_balance: v.market_balance,
+                total_liabilities: v.total_liabilities,
+                total_credit_liabilities: v.total_credit_liabilities,
+                total_reserves: v.total_reserves,
+                deposit_rate: v.deposit_rate,
+            })
+        })
+        .collect()
+}
+
+pub fn store_sponsorship<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    locker: &CanonicalAddr,
+    sponsorship: &Sponsorship,
+) -> StdResult<()> {
+    let mut sponsorship_bucket: Bucket<S, Sponsorship> =
+        Bucket::multilevel(&[PREFIX_SPONSORSHIP, borrower.as_slice()], storage);
+    if sponsorship.collaterals.is_empty() {
+        sponsorship_bucket.remove(locker.as_slice());
+    } else {
+        sponsorship_bucket.save(locker.as_slice(), sponsorship)?;
+    }
+
+    Ok(())
+}
+
+pub fn read_sponsorship<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+    locker: &CanonicalAddr,
+) -> Option<Sponsorship> {
+    let sponsorship_bucket: ReadonlyBucket<S, Sponsorship> =
+        ReadonlyBucket::multilevel(&[PREFIX_SPONSORSHIP, borrower.as_slice()], storage);
+    sponsorship_bucket.load(locker.as_slice()).ok()
+}
+
+/// Returns all sponsorships of the borrower as (locker, sponsorship)
+pub fn read_sponsorships<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Vec<(CanonicalAddr, Sponsorship)>> {
+    let sponsorship_bucket: ReadonlyBucket<S, Sponsorship> =
+        ReadonlyBucket::multilevel(&[PREFIX_SPONSORSHIP, borrower.as_slice()], storage);
+    sponsorship_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_rebate_rate<S: Storage>(storage: &mut S, rebate_rate: &Decimal256) -> StdResult<()> {
+    Singleton::new(storage, KEY_REBATE_RATE).save(rebate_rate)
+}
+
+pub fn read_rebate_rate<S: Storage>(storage: &S) -> StdResult<Decimal256> {
+    Ok(ReadonlySingleton::new(storage, KEY_REBATE_RATE)
+        .may_load()?
+        .unwrap_or_else(Decimal256::zero))
+}
+
+pub fn store_rebate_ltv<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    rebate_ltv: &Decimal256,
+) -> StdResult<()> {
+    let mut rebate_ltv_bucket: Bucket<S, Decimal256> = Bucket::new(PREFIX_REBATE_LTV, storage);
+    if rebate_ltv.is_zero() {
+        rebate_ltv_bucket.remove(collateral_token.as_slice());
+        Ok(())
+    } else {
+        rebate_ltv_bucket.save(collateral_token.as_slice(), rebate_ltv)
+    }
+}
+
+pub fn read_rebate_ltv<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Decimal256> {
+    let rebate_ltv_bucket: ReadonlyBucket<S, Decimal256> =
+        ReadonlyBucket::new(PREFIX_REBATE_LTV, storage);
+    Ok(rebate_ltv_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or_else(Decimal256::zero))
+}
+
+/// Returns the collaterals with a rebate LTV as (collateral_token, rebate_ltv)
+pub fn read_rebate_ltvs<S: Storage>(storage: &S) -> StdResult<Vec<(CanonicalAddr, Decimal256)>> {
+    let rebate_ltv_bucket: ReadonlyBucket<S, Decimal256> =
+        ReadonlyBucket::new(PREFIX_REBATE_LTV, storage);
+    rebate_ltv_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_ltv_checkpoint<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    checkpoint: &LtvCheckpoint,
+) -> StdResult<()> {
+    let mut checkpoint_bucket: Bucket<S, LtvCheckpoint> =
+        Bucket::new(PREFIX_LTV_CHECKPOINT, storage);
+    checkpoint_bucket.save(borrower.as_slice(), checkpoint)
+}
+
+pub fn remove_ltv_checkpoint<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut checkpoint_bucket: Bucket<S, LtvCheckpoint> =
+        Bucket::new(PREFIX_LTV_CHECKPOINT, storage);
+    checkpoint_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_ltv_checkpoint<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<LtvCheckpoint>> {
+    let checkpoint_bucket: ReadonlyBucket<S, LtvCheckpoint> =
+        ReadonlyBucket::new(PREFIX_LTV_CHECKPOINT, storage);
+    checkpoint_bucket.may_load(borrower.as_slice())
+}
+
+/// Stores the receiver of the position offered by `borrower`
+pub fn store_position_transfer<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    to: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut transfer_bucket: Bucket<S, CanonicalAddr> =
+        Bucket::new(PREFIX_POSITION_TRANSFER, storage);
+    transfer_bucket.save(borrower.as_slice(), to)
+}
+
+pub fn remove_position_transfer<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut transfer_bucket: Bucket<S, CanonicalAddr> =
+        Bucket::new(PREFIX_POSITION_TRANSFER, storage);
+    transfer_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_position_transfer<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<CanonicalAddr>> {
+    let transfer_bucket: ReadonlyBucket<S, CanonicalAddr> =
+        ReadonlyBucket::new(PREFIX_POSITION_TRANSFER, storage);
+    transfer_bucket.may_load(borrower.as_slice())
+}
+
+pub fn store_insurance_contract<S: Storage>(
+    storage: &mut S,
+    insurance_contract: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut insurance_bucket: Bucket<S, bool> = Bucket::new(PREFIX_INSURANCE_CONTRACT, storage);
+    insurance_bucket.save(insurance_contract.as_slice(), &true)
+}
+
+pub fn remove_insurance_contract<S: Storage>(storage: &mut S, insurance_contract: &CanonicalAddr) {
+    let mut insurance_bucket: Bucket<S, bool> = Bucket::new(PREFIX_INSURANCE_CONTRACT, storage);
+    insurance_bucket.remove(insurance_contract.as_slice());
+}
+
+pub fn is_insurance_contract<S: Storage>(
+    storage: &S,
+    insurance_contract: &CanonicalAddr,
+) -> StdResult<bool> {
+    let insurance_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_INSURANCE_CONTRACT, storage);
+    Ok(insurance_bucket
+        .may_load(insurance_contract.as_slice())?
+        .is_some())
+}
+
+/// Returns the registered insurance contracts
+pub fn read_insurance_contracts<S: Storage>(storage: &S) -> StdResult<Vec<CanonicalAddr>> {
+    let insurance_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_INSURANCE_CONTRACT, storage);
+    insurance_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, _) = elem?;
+            Ok(CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
+/// Stores the insurance contract attached to the position of `borrower`
+pub fn store_position_insurance<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    insurance_contract: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut insurance_bucket: Bucket<S, CanonicalAddr> =
+        Bucket::new(PREFIX_POSITION_INSURANCE, storage);
+    insurance_bucket.save(borrower.as_slice(), insurance_contract)
+}
+
+pub fn remove_position_insurance<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut insurance_bucket: Bucket<S, CanonicalAddr> =
+        Bucket::new(PREFIX_POSITION_INSURANCE, storage);
+    insurance_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_position_insurance<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<CanonicalAddr>> {
+    let insurance_bucket: ReadonlyBucket<S, CanonicalAddr> =
+        ReadonlyBucket::new(PREFIX_POSITION_INSURANCE, storage);
+    insurance_bucket.may_load(borrower.as_slice())
+}
+
+/// Collaterals the borrower wants liquidated last,
+/// the first one being seized last
+pub fn store_collateral_protection<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    protected_collaterals: &[CanonicalAddr],
+) -> StdResult<()> {
+    let mut protection_bucket: Bucket<S, Vec<CanonicalAddr>> =
+        Bucket::new(PREFIX_COLLATERAL_PROTECTION, storage);
+    protection_bucket.save(borrower.as_slice(), &protected_collaterals.to_vec())
+}
+
+pub fn remove_collateral_protection<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut protection_bucket: Bucket<S, Vec<CanonicalAddr>> =
+        Bucket::new(PREFIX_COLLATERAL_PROTECTION, storage);
+    protection_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_collateral_protection<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Vec<CanonicalAddr>> {
+    let protection_bucket: ReadonlyBucket<S, Vec<CanonicalAddr>> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL_PROTECTION, storage);
+    Ok(protection_bucket
+        .may_load(borrower.as_slice())?
+        .unwrap_or_default())
+}
+
+pub fn store_reward_route<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    route: &[RewardShare],
+) -> StdResult<()> {
+    let mut reward_route_bucket: Bucket<S, Vec<RewardShare>> =
+        Bucket::new(PREFIX_REWARD_ROUTE, storage);
+    reward_route_bucket.save(collateral_token.as_slice(), &route.to_vec())
+}
+
+pub fn remove_reward_route<S: Storage>(storage: &mut S, collateral_token: &CanonicalAddr) {
+    let mut reward_route_bucket: Bucket<S, Vec<RewardShare>> =
+        Bucket::new(PREFIX_REWARD_ROUTE, storage);
+    reward_route_bucket.remove(collateral_token.as_slice());
+}
+
+/// Empty when the rewards go to the interest buffer
+pub fn read_reward_route<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Vec<RewardShare>> {
+    let reward_route_bucket: ReadonlyBucket<S, Vec<RewardShare>> =
+        ReadonlyBucket::new(PREFIX_REWARD_ROUTE, storage);
+    Ok(reward_route_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or_default())
+}
+
+pub fn store_price_check<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    price_check: &PriceCheckConfig,
+) -> StdResult<()> {
+    let mut price_check_bucket: Bucket<S, PriceCheckConfig> =
+        Bucket::new(PREFIX_PRICE_CHECK, storage);
+    price_check_bucket.save(collateral_token.as_slice(), price_check)
+}
+
+pub fn remove_price_check<S: Storage>(storage: &mut S, collateral_token: &CanonicalAddr) {
+    let mut price_check_bucket: Bucket<S, PriceCheckConfig> =
+        Bucket::new(PREFIX_PRICE_CHECK, storage);
+    price_check_bucket.remove(collateral_token.as_slice());
+}
+
+pub fn read_price_check<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<PriceCheckConfig>> {
+    let price_check_bucket: ReadonlyBucket<S, PriceCheckConfig> =
+        ReadonlyBucket::new(PREFIX_PRICE_CHECK, storage);
+    price_check_bucket.may_load(collateral_token.as_slice())
+}
+
+pub fn store_max_ltv_override<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    max_ltv_override: &MaxLtvOverride,
+) -> StdResult<()> {
+    let mut max_ltv_override_bucket: Bucket<S, MaxLtvOverride> =
+        Bucket::new(PREFIX_MAX_LTV_OVERRIDE, storage);
+    max_ltv_override_bucket.save(borrower.as_slice(), max_ltv_override)
+}
+
+pub fn remove_max_ltv_override<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut max_ltv_override_bucket: Bucket<S, MaxLtvOverride> =
+        Bucket::new(PREFIX_MAX_LTV_OVERRIDE, storage);
+    max_ltv_override_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_max_ltv_override_elem<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<MaxLtvOverride>> {
+    let max_ltv_override_bucket: ReadonlyBucket<S, MaxLtvOverride> =
+        ReadonlyBucket::new(PREFIX_MAX_LTV_OVERRIDE, storage);
+    max_ltv_override_bucket.may_load(borrower.as_slice())
+}
+
+/// Update time of the prices the position of `borrower` was last
+/// partially liquidated at
+pub fn store_liquidation_price_time<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    prices_updated_at: u64,
+) -> StdResult<()> {
+    let mut price_time_bucket: Bucket<S, u64> = Bucket::new(PREFIX_LIQUIDATION_PRICE_TIME, storage);
+    price_time_bucket.save(borrower.as_slice(), &prices_updated_at)
+}
+
+pub fn read_liquidation_price_time<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<u64>> {
+    let price_time_bucket: ReadonlyBucket<S, u64> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATION_PRICE_TIME, storage);
+    price_time_bucket.may_load(borrower.as_slice())
+}
+
+pub fn store_whitelist_proposal<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    proposal: &WhitelistProposal,
+) -> StdResult<()> {
+    let mut proposal_bucket: Bucket<S, WhitelistProposal> =
+        Bucket::new(PREFIX_WHITELIST_PROPOSAL, storage);
+    proposal_bucket.save(collateral_token.as_slice(), proposal)
+}
+
+pub fn remove_whitelist_proposal<S: Storage>(storage: &mut S, collateral_token: &CanonicalAddr) {
+    let mut proposal_bucket: Bucket<S, WhitelistProposal> =
+        Bucket::new(PREFIX_WHITELIST_PROPOSAL, storage);
+    proposal_bucket.remove(collateral_token.as_slice());
+}
+
+pub fn read_whitelist_proposal<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<WhitelistProposal>> {
+    let proposal_bucket: ReadonlyBucket<S, WhitelistProposal> =
+        ReadonlyBucket::new(PREFIX_WHITELIST_PROPOSAL, storage);
+    proposal_bucket.may_load(collateral_token.as_slice())
+}
+
+pub fn read_whitelist_proposals<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, WhitelistProposal)>> {
+    let proposal_bucket: ReadonlyBucket<S, WhitelistProposal> =
+        ReadonlyBucket::new(PREFIX_WHITELIST_PROPOSAL, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    proposal_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_asset_metadata<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    metadata: &AssetRiskMetadata,
+) -> StdResult<()> {
+    let mut metadata_bucket: Bucket<S, AssetRiskMetadata> =
+        Bucket::new(PREFIX_ASSET_METADATA, storage);
+    metadata_bucket.save(collateral_token.as_slice(), metadata)
+}
+
+pub fn read_asset_metadata<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<AssetRiskMetadata>> {
+    let metadata_bucket: ReadonlyBucket<S, AssetRiskMetadata> =
+        ReadonlyBucket::new(PREFIX_ASSET_METADATA, storage);
+    metadata_bucket.may_load(collateral_token.as_slice())
+}
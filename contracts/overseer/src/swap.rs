@@ -0,0 +1,225 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, Storage, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+
+use crate::collateral::{assert_max_collateral_types, compute_borrow_limit};
+use crate::querier::query_borrower_info;
+use crate::sponsorship::{cap_sponsorships, compute_unlockable_collaterals};
+use crate::state::{
+    read_collateral_swap, read_collaterals, read_config, read_whitelist_elem,
+    remove_collateral_swap, store_collateral_swap, store_collaterals, CollateralSwap, Config,
+    WhitelistElem,
+};
+
+use moneymarket::custody::{Cw20HookMsg as CustodyCw20HookMsg, HandleMsg as CustodyHandleMsg};
+use moneymarket::error::ContractError;
+use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::overseer::HandleMsg;
+use moneymarket::querier::query_token_balance;
+use moneymarket::router::{AssetInfo, Cw20HookMsg as RouterCw20HookMsg, SwapOperation};
+use moneymarket::tokens::{Tokens, TokensMath};
+
+/// Takes the unlockable `from_token` collateral of the sender out of
+/// its custody, sends it to the swap router and finishes the swap in
+/// `FinishSwapCollateral`, which runs after the router has returned
+/// the `to_token` to the overseer within the same transaction
+pub fn swap_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from_token: HumanAddr,
+    to_token: HumanAddr,
+    min_receive: Uint256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if config.swap_router == CanonicalAddr::default() {
+        return Err(ContractError::NoSwapRouter {}.into());
+    }
+
+    if from_token == to_token {
+        return Err(ContractError::SwapToSameToken {}.into());
+    }
+
+    if read_collateral_swap(&deps.storage)?.is_some() {
+        return Err(ContractError::SwapInProgress {}.into());
+    }
+
+    let from_token_raw = deps.api.canonical_address(&from_token)?;
+    let to_token_raw = deps.api.canonical_address(&to_token)?;
+    let from_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &from_token_raw)?;
+    read_whitelist_elem(&deps.storage, &to_token_raw)?;
+
+    // Sponsored collaterals without withdraw consent stay locked
+    let borrower = env.message.sender;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    let swap_amount =
+        compute_unlockable_collaterals(&deps.storage, &borrower_raw, &cur_collaterals)?
+            .into_iter()
+            .find(|c| c.0 == from_token_raw)
+            .map(|c| c.1)
+            .unwrap_or_else(Uint256::zero);
+    if swap_amount.is_zero() {
+        return Err(ContractError::NoCollateralToSwap {}.into());
+    }
+
+    cur_collaterals.sub(vec![(from_token_raw, swap_amount)])?;
+    store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+    cap_sponsorships(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+
+    let prev_balance = query_token_balance(deps, &to_token, &env.contract.address)?;
+    store_collateral_swap(
+        &mut deps.storage,
+        &CollateralSwap {
+            borrower: borrower_raw,
+            to_token: to_token_raw,
+            min_receive,
+            prev_balance,
+        },
+    )?;
+
+    let from_custody = deps.api.human_address(&from_elem.custody_contract)?;
+    Ok(HandleResponse {
+        messages: vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: from_custody.clone(),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::UnlockCollateralFor {
+                    locker: env.contract.address.clone(),
+                    borrower: borrower.clone(),
+                    amount: swap_amount,
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: from_custody,
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::WithdrawCollateral {
+                    amount: Some(swap_amount),
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: from_token.clone(),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Send {
+                    contract: deps.api.human_address(&config.swap_router)?,
+                    amount: swap_amount.into(),
+                    msg: Some(to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                        operations: vec![
+                            SwapOperation::TerraSwap {
+                                offer_asset_info: AssetInfo::Token {
+                                    contract_addr: from_token.clone(),
+                                },
+                                ask_asset_info: AssetInfo::NativeToken {
+                                    denom: config.stable_denom.clone(),
+                                },
+                            },
+                            SwapOperation::TerraSwap {
+                                offer_asset_info: AssetInfo::NativeToken {
+                                    denom: config.stable_denom,
+                                },
+                                ask_asset_info: AssetInfo::Token {
+                                    contract_addr: to_token.clone(),
+                                },
+                            },
+                        ],
+                        minimum_receive: Some(min_receive.into()),
+                        to: None,
+                    })?),
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address,
+                send: vec![],
+                msg: to_binary(&HandleMsg::FinishSwapCollateral {})?,
+            }),
+        ],
+        log: vec![
+            log("action", "swap_collateral"),
+            log("borrower", borrower),
+            log("from_token", from_token),
+            log("to_token", to_token),
+            log("swap_amount", swap_amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn finish_swap_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    if env.message.sender != env.contract.address {
+        return Err(StdError::unauthorized());
+    }
+
+    let swap = match read_collateral_swap(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoSwapInProgress {}.into()),
+    };
+
+    let to_token = deps.api.human_address(&swap.to_token)?;
+    let balance = query_token_balance(deps, &to_token, &env.contract.address)?;
+    let received_amount = if balance > swap.prev_balance {
+        balance - swap.prev_balance
+    } else {
+        Uint256::zero()
+    };
+
+    if received_amount.is_zero() || received_amount < swap.min_receive {
+        return Err(ContractError::SwapBelowMinimum { received_amount }.into());
+    }
+
+    let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &swap.borrower);
+    let prev_collateral_types = cur_collaterals.len();
+    cur_collaterals.add(vec![(swap.to_token.clone(), received_amount)]);
+    assert_max_collateral_types(&deps.storage, prev_collateral_types, &cur_collaterals)?;
+
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+    let borrower = deps.api.human_address(&swap.borrower)?;
+    let (borrow_limit, _, _) =
+        compute_borrow_limit(deps, &cur_collaterals, Some(env.block.time), None)?;
+    let borrow_amount_res: BorrowerInfoResponse =
+        query_borrower_info(deps, &market, &borrower, env.block.height)?;
+    if borrow_limit < borrow_amount_res.loan_amount {
+        return Err(ContractError::SwapExceedsBorrowLimit { borrow_limit }.into());
+    }
+
+    store_collaterals(&mut deps.storage, &swap.borrower, &cur_collaterals)?;
+    remove_collateral_swap(&mut deps.storage);
+
+    // the received tokens are deposited by the overseer and moved to the borrower
+    let whitelist_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &swap.to_token)?;
+    let to_custody = deps.api.human_address(&whitelist_elem.custody_contract)?;
+    Ok(HandleResponse {
+        messages: vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: to_token.clone(),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Send {
+                    contract: to_custody.clone(),
+                    amount: received_amount.into(),
+                    msg: Some(to_binary(&CustodyCw20HookMsg::DepositCollateral {})?),
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: to_custody,
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LockCollateralFor {
+                    locker: env.contract.address,
+                    borrower: borrower.clone(),
+                    amount: received_amount,
+                })?,
+            }),
+        ],
+        log: vec![
+            log("action", "finish_swap_collateral"),
+            log("borrower", borrower),
+            log("to_token", to_token),
+            log("received_amount", received_amount),
+        ],
+        data: None,
+    })
+}
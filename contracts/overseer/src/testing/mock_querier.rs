@@ -4,14 +4,22 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Api, Coin, Decimal, Extern, HumanAddr, Querier,
-    QuerierResult, QueryRequest, SystemError, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Api, CanonicalAddr, Coin, Decimal, Extern, HumanAddr,
+    Querier, QuerierResult, QueryRequest, SystemError, Uint128, WasmQuery,
 };
+use cosmwasm_storage::to_length_prefixed;
 use std::collections::HashMap;
 
-use moneymarket::liquidation::LiquidationAmountResponse;
+use moneymarket::dex_twap::TwapResponse;
+use moneymarket::liquidation::{
+    ConfigResponse as LiquidationConfigResponse, LiquidationAmountResponse, LiquidationMechanism,
+    LiquidationMechanismResponse,
+};
 use moneymarket::market::{BorrowerInfoResponse, EpochStateResponse};
 use moneymarket::oracle::PriceResponse;
+use moneymarket::protocol_controller::{ProtocolMode, ProtocolModeResponse};
+use moneymarket::registry::{ResolveResponse, VersionResponse};
+use moneymarket::risk_module::{CanBorrowResponse, CanWithdrawResponse};
 use moneymarket::tokens::TokensHuman;
 
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
@@ -38,6 +46,22 @@ pub enum QueryMsg {
         collaterals: TokensHuman,
         collateral_prices: Vec<Decimal256>,
     },
+    /// Query config to liquidation model contract
+    Config {},
+    /// Query collateral liquidation mechanism to liquidation contract
+    LiquidationMechanism { collateral_token: HumanAddr },
+    /// Query borrow decision to risk module contract
+    CanBorrow { borrower: HumanAddr },
+    /// Query withdrawal decision to risk module contract
+    CanWithdraw { borrower: HumanAddr },
+    /// Query protocol mode to protocol controller contract
+    ProtocolMode {},
+    /// Query version to registry contract
+    Version {},
+    /// Query addresses to registry contract
+    Resolve { names: Vec<String> },
+    /// Query time weighted average price to DEX twap contract
+    Twap { base: String, quote: String },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -49,7 +73,7 @@ pub fn mock_dependencies(
     let contract_addr = HumanAddr::from(MOCK_CONTRACT_ADDR);
     let custom_querier: WasmMockQuerier = WasmMockQuerier::new(
         MockQuerier::new(&[(&contract_addr, contract_balance)]),
-        MockApi::new(canonical_length),
+        canonical_length,
     );
 
     Extern {
@@ -61,11 +85,41 @@ pub fn mock_dependencies(
 
 pub struct WasmMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
+    token_querier: TokenQuerier,
     tax_querier: TaxQuerier,
     epoch_state_querier: EpochStateQuerier,
     oracle_price_querier: OraclePriceQuerier,
     loan_amount_querier: LoanAmountQuerier,
     liquidation_percent_querier: LiquidationPercentQuerier,
+    risk_module_querier: RiskModuleQuerier,
+    dex_twap: HashMap<(String, String), Decimal256>,
+    auctions: HashMap<HumanAddr, LiquidationMechanism>,
+    protocol_mode: ProtocolMode,
+    registry: (u64, HashMap<String, HumanAddr>),
+    canonical_length: usize,
+}
+
+#[derive(Clone, Default)]
+pub struct TokenQuerier {
+    balances: HashMap<HumanAddr, HashMap<HumanAddr, Uint128>>,
+}
+
+impl TokenQuerier {
+    pub fn new(balances: &[(&HumanAddr, &[(&HumanAddr, &Uint128)])]) -> Self {
+        let mut balances_map: HashMap<HumanAddr, HashMap<HumanAddr, Uint128>> = HashMap::new();
+        for (contract_addr, balances) in balances.iter() {
+            let mut contract_balances_map: HashMap<HumanAddr, Uint128> = HashMap::new();
+            for (addr, balance) in balances.iter() {
+                contract_balances_map.insert(HumanAddr::from(addr), **balance);
+            }
+
+            balances_map.insert(HumanAddr::from(contract_addr), contract_balances_map);
+        }
+
+        TokenQuerier {
+            balances: balances_map,
+        }
+    }
 }
 
 #[derive(Clone, Default)]
@@ -99,6 +153,7 @@ pub struct OraclePriceQuerier {
 }
 
 impl OraclePriceQuerier {
+    #[allow(clippy::type_complexity)]
     pub fn new(oracle_price: &[(&(String, String), &(Decimal256, u64, u64))]) -> Self {
         OraclePriceQuerier {
             oracle_price: oracle_price_to_map(oracle_price),
@@ -106,6 +161,7 @@ impl OraclePriceQuerier {
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub(crate) fn oracle_price_to_map(
     oracle_price: &[(&(String, String), &(Decimal256, u64, u64))],
 ) -> HashMap<(String, String), (Decimal256, u64, u64)> {
@@ -121,12 +177,14 @@ pub(crate) fn oracle_price_to_map(
 pub struct EpochStateQuerier {
     // this lets us iterate over all pairs that match the first string
     epoch_state: HashMap<HumanAddr, (Uint256, Decimal256)>,
+    total_liabilities: Option<Decimal256>,
 }
 
 impl EpochStateQuerier {
     pub fn new(epoch_state: &[(&HumanAddr, &(Uint256, Decimal256))]) -> Self {
         EpochStateQuerier {
             epoch_state: epoch_state_to_map(epoch_state),
+            total_liabilities: None,
         }
     }
 }
@@ -145,12 +203,14 @@ pub(crate) fn epoch_state_to_map(
 pub struct LoanAmountQuerier {
     // this lets us iterate over all pairs that match the first string
     borrower_amount: HashMap<HumanAddr, Uint256>,
+    interest_index: Option<Decimal256>,
 }
 
 impl LoanAmountQuerier {
     pub fn new(borrower_amount: &[(&HumanAddr, &Uint256)]) -> Self {
         LoanAmountQuerier {
             borrower_amount: borrower_amount_to_map(borrower_amount),
+            interest_index: None,
         }
     }
 }
@@ -205,6 +265,25 @@ pub(crate) fn liquidation_percent_to_map(
     liquidation_percent_map
 }
 
+#[derive(Clone, Default)]
+pub struct RiskModuleQuerier {
+    // (can_borrow, can_withdraw) per risk module contract
+    decisions: HashMap<HumanAddr, (bool, bool)>,
+}
+
+impl RiskModuleQuerier {
+    pub fn new(decisions: &[(&HumanAddr, &(bool, bool))]) -> Self {
+        let mut decisions_map: HashMap<HumanAddr, (bool, bool)> = HashMap::new();
+        for (risk_module, decision) in decisions.iter() {
+            decisions_map.insert((*risk_module).clone(), **decision);
+        }
+
+        RiskModuleQuerier {
+            decisions: decisions_map,
+        }
+    }
+}
+
 impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
         match &request {
@@ -234,14 +313,21 @@ impl WasmMockQuerier {
                 }
             }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
-                match from_binary(&msg).unwrap() {
+                match from_binary(msg).unwrap() {
                     QueryMsg::EpochState {
                         block_height: _,
                         distributed_interest: _,
-                    } => match self.epoch_state_querier.epoch_state.get(&contract_addr) {
+                    } => match self.epoch_state_querier.epoch_state.get(contract_addr) {
                         Some(v) => Ok(to_binary(&EpochStateResponse {
                             aterra_supply: v.0,
                             exchange_rate: v.1,
+                            market_balance: v.0 * v.1,
+                            total_liabilities: self
+                                .epoch_state_querier
+                                .total_liabilities
+                                .unwrap_or_else(Decimal256::zero),
+                            total_credit_liabilities: Decimal256::zero(),
+                            total_reserves: Decimal256::zero(),
                         })),
                         None => Err(SystemError::InvalidRequest {
                             error: "No epoch state exists".to_string(),
@@ -254,7 +340,10 @@ impl WasmMockQuerier {
                     } => match self.loan_amount_querier.borrower_amount.get(&borrower) {
                         Some(v) => Ok(to_binary(&BorrowerInfoResponse {
                             borrower,
-                            interest_index: Decimal256::one(),
+                            interest_index: self
+                                .loan_amount_querier
+                                .interest_index
+                                .unwrap_or_else(Decimal256::one),
                             reward_index: Decimal256::zero(),
                             loan_amount: *v,
                             pending_rewards: Decimal256::zero(),
@@ -286,7 +375,7 @@ impl WasmMockQuerier {
                         match self
                             .liquidation_percent_querier
                             .liquidation_percent
-                            .get(&contract_addr)
+                            .get(contract_addr)
                         {
                             Some(v) => {
                                 if borrow_amount > borrow_limit {
@@ -309,6 +398,98 @@ impl WasmMockQuerier {
                             }),
                         }
                     }
+                    QueryMsg::Config {} => Ok(to_binary(&LiquidationConfigResponse {
+                        owner: HumanAddr::from("owner"),
+                        oracle_contract: HumanAddr::from("oracle"),
+                        stable_denom: "uusd".to_string(),
+                        safe_ratio: Decimal256::percent(80),
+                        bid_fee: Decimal256::percent(1),
+                        max_premium_rate: Decimal256::percent(5),
+                        liquidation_threshold: Uint256::from(100000000u64),
+                        price_timeframe: 60u64,
+                    })),
+                    QueryMsg::LiquidationMechanism { collateral_token } => {
+                        Ok(to_binary(&LiquidationMechanismResponse {
+                            mechanism: self
+                                .auctions
+                                .get(&collateral_token)
+                                .cloned()
+                                .unwrap_or(LiquidationMechanism::Bid {}),
+                            collateral_token,
+                        }))
+                    }
+                    QueryMsg::CanBorrow { borrower: _ } => {
+                        match self.risk_module_querier.decisions.get(contract_addr) {
+                            Some(v) => Ok(to_binary(&CanBorrowResponse { allowed: v.0 })),
+                            None => Err(SystemError::InvalidRequest {
+                                error: "No risk module exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
+                    QueryMsg::CanWithdraw { borrower: _ } => {
+                        match self.risk_module_querier.decisions.get(contract_addr) {
+                            Some(v) => Ok(to_binary(&CanWithdrawResponse { allowed: v.1 })),
+                            None => Err(SystemError::InvalidRequest {
+                                error: "No risk module exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
+                    QueryMsg::ProtocolMode {} => Ok(to_binary(&ProtocolModeResponse {
+                        mode: self.protocol_mode,
+                        updated_height: 0u64,
+                    })),
+                    QueryMsg::Version {} => Ok(to_binary(&VersionResponse {
+                        version: self.registry.0,
+                        updated_height: 0u64,
+                    })),
+                    QueryMsg::Resolve { names } => Ok(to_binary(&ResolveResponse {
+                        version: self.registry.0,
+                        addresses: names
+                            .iter()
+                            .map(|name| self.registry.1.get(name).cloned())
+                            .collect(),
+                    })),
+                    QueryMsg::Twap { base, quote } => match self.dex_twap.get(&(base, quote)) {
+                        Some(v) => Ok(to_binary(&TwapResponse { twap: *v })),
+                        None => Err(SystemError::InvalidRequest {
+                            error: "No twap exists".to_string(),
+                            request: msg.as_slice().into(),
+                        }),
+                    },
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
+                let key: &[u8] = key.as_slice();
+                let prefix_balance = to_length_prefixed(b"balance").to_vec();
+                if key[..prefix_balance.len()].to_vec() != prefix_balance {
+                    panic!("DO NOT ENTER HERE")
+                }
+
+                let api: MockApi = MockApi::new(self.canonical_length);
+                let address_raw = CanonicalAddr::from(&key[prefix_balance.len()..]);
+                let address: HumanAddr = match api.human_address(&address_raw) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        return Err(SystemError::InvalidRequest {
+                            error: format!("Parsing query request: {}", e),
+                            request: key.into(),
+                        })
+                    }
+                };
+
+                match self
+                    .token_querier
+                    .balances
+                    .get(contract_addr)
+                    .and_then(|balances| balances.get(&address))
+                {
+                    Some(v) => Ok(to_binary(&to_binary(v).unwrap())),
+                    None => Err(SystemError::InvalidRequest {
+                        error: "Balance not found".to_string(),
+                        request: key.into(),
+                    }),
                 }
             }
             _ => self.base.handle_query(request),
@@ -317,17 +498,28 @@ impl WasmMockQuerier {
 }
 
 impl WasmMockQuerier {
-    pub fn new<A: Api>(base: MockQuerier<TerraQueryWrapper>, _api: A) -> Self {
+    pub fn new(base: MockQuerier<TerraQueryWrapper>, canonical_length: usize) -> Self {
         WasmMockQuerier {
             base,
+            token_querier: TokenQuerier::default(),
             tax_querier: TaxQuerier::default(),
             epoch_state_querier: EpochStateQuerier::default(),
             oracle_price_querier: OraclePriceQuerier::default(),
             loan_amount_querier: LoanAmountQuerier::default(),
             liquidation_percent_querier: LiquidationPercentQuerier::default(),
+            risk_module_querier: RiskModuleQuerier::default(),
+            dex_twap: HashMap::new(),
+            auctions: HashMap::new(),
+            protocol_mode: ProtocolMode::Normal,
+            registry: (0, HashMap::new()),
+            canonical_length,
         }
     }
 
+    pub fn with_token_balances(&mut self, balances: &[(&HumanAddr, &[(&HumanAddr, &Uint128)])]) {
+        self.token_querier = TokenQuerier::new(balances);
+    }
+
     // configure the tax mock querier
     pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
         self.tax_querier = TaxQuerier::new(rate, caps);
@@ -337,6 +529,11 @@ impl WasmMockQuerier {
         self.epoch_state_querier = EpochStateQuerier::new(epoch_state);
     }
 
+    pub fn with_total_liabilities(&mut self, total_liabilities: Decimal256) {
+        self.epoch_state_querier.total_liabilities = Some(total_liabilities);
+    }
+
+    #[allow(clippy::type_complexity)]
     pub fn with_oracle_price(
         &mut self,
         oracle_price: &[(&(String, String), &(Decimal256, u64, u64))],
@@ -348,7 +545,45 @@ impl WasmMockQuerier {
         self.loan_amount_querier = LoanAmountQuerier::new(loan_amount);
     }
 
+    pub fn with_interest_index(&mut self, interest_index: Decimal256) {
+        self.loan_amount_querier.interest_index = Some(interest_index);
+    }
+
     pub fn with_liquidation_percent(&mut self, liquidation_percent: &[(&HumanAddr, &Decimal256)]) {
         self.liquidation_percent_querier = LiquidationPercentQuerier::new(liquidation_percent);
     }
+
+    pub fn with_risk_module(&mut self, decisions: &[(&HumanAddr, &(bool, bool))]) {
+        self.risk_module_querier = RiskModuleQuerier::new(decisions);
+    }
+
+    pub fn with_dex_twap(&mut self, dex_twap: &[(&(String, String), &Decimal256)]) {
+        self.dex_twap = dex_twap
+            .iter()
+            .map(|(pair, twap)| ((*pair).clone(), **twap))
+            .collect();
+    }
+
+    pub fn with_auctions(&mut self, auctions: &[(&HumanAddr, &LiquidationMechanism)]) {
+        self.auctions = auctions
+            .iter()
+            .map(|(collateral_token, mechanism)| {
+                ((*collateral_token).clone(), (*mechanism).clone())
+            })
+            .collect();
+    }
+
+    pub fn with_protocol_mode(&mut self, mode: ProtocolMode) {
+        self.protocol_mode = mode;
+    }
+
+    pub fn with_registry(&mut self, version: u64, addresses: &[(&str, &HumanAddr)]) {
+        self.registry = (
+            version,
+            addresses
+                .iter()
+                .map(|(name, address)| (name.to_string(), (*address).clone()))
+                .collect(),
+        );
+    }
 }
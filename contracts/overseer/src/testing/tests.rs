@@ -1,6 +1,8 @@
 use crate::contract::{handle, init, query};
 use crate::querier::query_epoch_state;
-use crate::state::{read_epoch_state, store_epoch_state, EpochState};
+use crate::state::{
+    read_epoch_state, store_epoch_record, store_epoch_state, EpochRecord, EpochState,
+};
 use crate::testing::mock_querier::mock_dependencies;
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
@@ -10,13 +12,31 @@ use cosmwasm_std::{
     WasmMsg,
 };
 
-use moneymarket::custody::HandleMsg as CustodyHandleMsg;
+use cw20::Cw20HandleMsg;
+use moneymarket::analytics::{EpochKpis, HandleMsg as AnalyticsHandleMsg};
+use moneymarket::custody::{Cw20HookMsg as CustodyCw20HookMsg, HandleMsg as CustodyHandleMsg};
+use moneymarket::insurance::HandleMsg as InsuranceHandleMsg;
+use moneymarket::liquidation::LiquidationMechanism;
 use moneymarket::market::HandleMsg as MarketHandleMsg;
 use moneymarket::overseer::{
-    AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse, HandleMsg,
-    InitMsg, QueryMsg, WhitelistResponse, WhitelistResponseElem,
+    AllCollateralsResponse, AnalyticsContractResponse, AssetMetadata, AssetMetadataResponse,
+    BorrowLimitResponse, CollateralProtectionResponse, CollateralsResponse, ConfigResponse,
+    EmergencyPriceProposalResponse, EmergencyPriceResponse, EpochPhase, EpochPhaseResponse,
+    EpochRecordResponse, EpochRecordsResponse, FeedLivenessResponse, FeedLivenessResponseElem,
+    HandleMsg, HealthSnapshot, InboxEvent, InboxRecordResponse, InboxResponse, IndexUnitResponse,
+    InitMsg, InsuranceContractsResponse, InterestRebateResponse, InvariantChecksResponse,
+    LiquidatedCollateralResponse, LiquidationCandidateResponse, LiquidationCandidatesResponse,
+    LiquidationOrder, LtvCheckpointResponse, MaxLtvOverrideResponse, PositionHealthResponse,
+    PositionInsuranceResponse, PositionTransferResponse, PriceCheck, PriceCheckResponse, QueryMsg,
+    RegistryResponse, RewardDestination, RewardRouteResponse, RewardShare, RiskModuleResponse,
+    SimulateLiquidationResponse, SnapshotCollateral, SnapshotHealthResponse, SolvencyResponse,
+    SponsorshipResponse, SponsorshipsResponse, WhitelistProposalResponse,
+    WhitelistProposalsResponse, WhitelistResponse, WhitelistResponseElem,
 };
+use moneymarket::protocol_controller::ProtocolMode;
 use moneymarket::querier::deduct_tax;
+use moneymarket::registry::REGISTRY_MAX_AGE;
+use moneymarket::router::{AssetInfo, Cw20HookMsg as RouterCw20HookMsg, SwapOperation};
 
 use std::str::FromStr;
 
@@ -61,6 +81,16 @@ fn proper_initialization() {
             buffer_distribution_factor: Decimal256::percent(20),
             anc_purchase_factor: Decimal256::percent(20),
             price_timeframe: 60u64,
+            guardians: vec![HumanAddr::from("owner")],
+            guardian_threshold: 1u64,
+            max_collateral_types: 10u64,
+            swap_router: None,
+            min_position_collateral_value: Uint256::zero(),
+            liquidation_order: LiquidationOrder::ProRata,
+            max_feed_gap: 0,
+            max_catch_up_epochs: 0,
+            close_factor: Decimal256::zero(),
+            max_emergency_price_duration: 604800u64,
         }
     );
 
@@ -101,21 +131,23 @@ fn update_config() {
     // we can just call .unwrap() to assert this was a success
     let _res = init(&mut deps, env, msg).unwrap();
 
-    // update owner
+    // propose a new owner
     let env = mock_env("owner", &[]);
-    let msg = HandleMsg::UpdateConfig {
-        owner_addr: Some(HumanAddr("owner1".to_string())),
-        oracle_contract: None,
-        liquidation_contract: None,
-        threshold_deposit_rate: None,
-        target_deposit_rate: None,
-        buffer_distribution_factor: None,
-        anc_purchase_factor: None,
-        epoch_period: None,
-        price_timeframe: None,
+    let msg = HandleMsg::ProposeNewOwner {
+        new_owner: HumanAddr::from("owner1"),
+        expiry_height: 12355u64,
     };
+    handle(&mut deps, env, msg).unwrap();
 
-    let res = handle(&mut deps, env, msg).unwrap();
+    // only the proposed owner can accept
+    let env = mock_env("owner", &[]);
+    match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner1", &[]);
+    let res = handle(&mut deps, env, HandleMsg::AcceptOwnership {}).unwrap();
     assert_eq!(0, res.messages.len());
 
     // it worked, let's query the state
@@ -126,7 +158,6 @@ fn update_config() {
     // update left items
     let env = mock_env("owner1", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner_addr: None,
         oracle_contract: Some(HumanAddr("oracle1".to_string())),
         liquidation_contract: Some(HumanAddr("liquidation1".to_string())),
         threshold_deposit_rate: Some(Decimal256::permille(1)),
@@ -135,6 +166,16 @@ fn update_config() {
         anc_purchase_factor: Some(Decimal256::percent(10)),
         epoch_period: Some(100000u64),
         price_timeframe: Some(120u64),
+        guardians: Some(vec![HumanAddr("guardian".to_string())]),
+        guardian_threshold: Some(1u64),
+        max_collateral_types: Some(5u64),
+        swap_router: Some(HumanAddr("router".to_string())),
+        min_position_collateral_value: Some(Uint256::from(1000u64)),
+        liquidation_order: Some(LiquidationOrder::MostLiquidFirst),
+        max_feed_gap: Some(3600u64),
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
     };
 
     let res = handle(&mut deps, env, msg).unwrap();
@@ -158,11 +199,23 @@ fn update_config() {
     assert_eq!(Decimal256::percent(10), config_res.anc_purchase_factor);
     assert_eq!(100000u64, config_res.epoch_period);
     assert_eq!(120u64, config_res.price_timeframe);
+    assert_eq!(vec![HumanAddr::from("guardian")], config_res.guardians);
+    assert_eq!(1u64, config_res.guardian_threshold);
+    assert_eq!(5u64, config_res.max_collateral_types);
+    assert_eq!(Some(HumanAddr::from("router")), config_res.swap_router);
+    assert_eq!(
+        Uint256::from(1000u64),
+        config_res.min_position_collateral_value
+    );
+    assert_eq!(
+        LiquidationOrder::MostLiquidFirst,
+        config_res.liquidation_order
+    );
+    assert_eq!(3600u64, config_res.max_feed_gap);
 
     // Unauthorized err
     let env = mock_env("owner", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner_addr: None,
         oracle_contract: None,
         liquidation_contract: None,
         threshold_deposit_rate: None,
@@ -171,6 +224,16 @@ fn update_config() {
         anc_purchase_factor: None,
         epoch_period: None,
         price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
     };
 
     let res = handle(&mut deps, env, msg);
@@ -178,6 +241,89 @@ fn update_config() {
         Err(StdError::Unauthorized { .. }) => {}
         _ => panic!("Must return unauthorized error"),
     }
+
+    // batched owner operations
+    let msg = HandleMsg::AdminBatch {
+        ops: vec![
+            HandleMsg::UpdateConfig {
+                oracle_contract: None,
+                liquidation_contract: None,
+                threshold_deposit_rate: None,
+                target_deposit_rate: None,
+                buffer_distribution_factor: None,
+                anc_purchase_factor: None,
+                epoch_period: Some(200000u64),
+                price_timeframe: None,
+                guardians: None,
+                guardian_threshold: None,
+                max_collateral_types: None,
+                swap_router: None,
+                min_position_collateral_value: None,
+                liquidation_order: None,
+                max_feed_gap: None,
+                max_catch_up_epochs: None,
+                close_factor: None,
+                max_emergency_price_duration: None,
+            },
+            HandleMsg::Whitelist {
+                name: "bluna".to_string(),
+                symbol: "bluna".to_string(),
+                collateral_token: HumanAddr::from("bluna"),
+                custody_contract: HumanAddr::from("custody"),
+                max_ltv: Decimal256::percent(60),
+            },
+        ],
+    };
+    let res = handle(&mut deps, mock_env("owner", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner1", &[]), msg).unwrap();
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(200000u64, config_res.epoch_period);
+    let whitelist_res: WhitelistResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Whitelist {
+                collateral_token: Some(HumanAddr::from("bluna")),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        HumanAddr::from("custody"),
+        whitelist_res.elems[0].custody_contract
+    );
+
+    let msg = HandleMsg::AdminBatch {
+        ops: vec![HandleMsg::ExecuteEpochOperations {}],
+    };
+    let res = handle(&mut deps, mock_env("owner1", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9002] Only owner operations can be batched")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the invariant checks are turned off outside of a batch,
+    // the batch itself is checked once all its operations ran
+    let msg = HandleMsg::AdminBatch {
+        ops: vec![HandleMsg::UpdateInvariantChecks { enabled: false }],
+    };
+    let res = handle(&mut deps, mock_env("owner1", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9002] Only owner operations can be batched")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 }
 
 #[test]
@@ -268,7 +414,7 @@ fn whitelist() {
     let res = handle(&mut deps, env, msg).unwrap_err();
     match res {
         StdError::GenericErr { msg, .. } => {
-            assert_eq!(msg, "Token is already registered as collateral")
+            assert_eq!(msg, "[E2013] Token is already registered as collateral")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -377,13 +523,57 @@ fn execute_epoch_operations() {
     match res {
         Err(StdError::GenericErr { msg, .. }) => assert_eq!(
             msg,
-            "An epoch has not passed yet; last executed height: 12345"
+            "[E2018] An epoch has not passed yet; last executed height: 12345"
         ),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
     env.block.height += 86400u64;
 
+    // epoch operations are aborted on stale collateral prices
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E2005] Stale collateral prices; valid from {}: batom price unavailable, bluna price unavailable",
+                env.block.time - 60
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::one(), env.block.time, env.block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::one(), env.block.time - 61, env.block.time),
+        ),
+    ]);
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E2005] Stale collateral prices; valid from {}: batom last updated at {}",
+                env.block.time - 60,
+                env.block.time - 61
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // an unexpired emergency price replaces the stale oracle price
+    let update_msg = HandleMsg::SetEmergencyPrice {
+        asset: HumanAddr::from("batom"),
+        price: Decimal256::one(),
+        expires_at: env.block.time + 1,
+    };
+    let _res = handle(&mut deps, env.clone(), update_msg).unwrap();
+
     // If deposit_rate is bigger than threshold_deposit_rate
     deps.querier.with_epoch_state(&[(
         &HumanAddr::from("market"),
@@ -413,12 +603,18 @@ fn execute_epoch_operations() {
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: HumanAddr::from("custody_batom"),
                 send: vec![],
-                msg: to_binary(&CustodyHandleMsg::DistributeRewards {}).unwrap(),
+                msg: to_binary(&CustodyHandleMsg::DistributeRewards {
+                    reward_recipients: None,
+                })
+                .unwrap(),
             }),
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: HumanAddr::from("custody_bluna"),
                 send: vec![],
-                msg: to_binary(&CustodyHandleMsg::DistributeRewards {}).unwrap(),
+                msg: to_binary(&CustodyHandleMsg::DistributeRewards {
+                    reward_recipients: None,
+                })
+                .unwrap(),
             }),
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
@@ -505,12 +701,18 @@ fn execute_epoch_operations() {
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: HumanAddr::from("custody_batom"),
                 send: vec![],
-                msg: to_binary(&CustodyHandleMsg::DistributeRewards {}).unwrap(),
+                msg: to_binary(&CustodyHandleMsg::DistributeRewards {
+                    reward_recipients: None,
+                })
+                .unwrap(),
             }),
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: HumanAddr::from("custody_bluna"),
                 send: vec![],
-                msg: to_binary(&CustodyHandleMsg::DistributeRewards {}).unwrap(),
+                msg: to_binary(&CustodyHandleMsg::DistributeRewards {
+                    reward_recipients: None,
+                })
+                .unwrap(),
             }),
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
@@ -535,6 +737,48 @@ fn execute_epoch_operations() {
             log("anc_purchase_amount", "200000")
         ]
     );
+
+    let res: EpochRecordsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochRecords {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.records.len(), 2);
+    assert_eq!(res.records[0].height, env.block.height - 86400u64);
+    assert_eq!(res.records[0].exchange_rate, Decimal256::percent(120));
+
+    let res: EpochRecordsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::EpochRecords {
+                start_after: Some(env.block.height - 86400u64),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.records,
+        vec![EpochRecordResponse {
+            height: env.block.height,
+            blocks: 86400u64,
+            prev_exchange_rate: Decimal256::percent(120),
+            exchange_rate: Decimal256::percent(125),
+            aterra_supply: Uint256::from(1000000u64),
+            market_balance: Uint256::from(1250000u64),
+            total_liabilities: Decimal256::zero(),
+            total_credit_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::zero(),
+            deposit_rate: Decimal256::from_str("0.000000482253086419").unwrap(),
+        }]
+    );
 }
 
 #[test]
@@ -808,6 +1052,55 @@ fn lock_collateral() {
             }]
         }
     );
+
+    // cap the collateral types per borrower to 2
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: Some(2u64),
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "beth".to_string(),
+        symbol: "beth".to_string(),
+        collateral_token: HumanAddr::from("beth"),
+        custody_contract: HumanAddr::from("custody_beth"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("beth"), Uint256::from(1000000u64))],
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2001] Cannot lock more than 2 collateral types")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // existing collateral types can still be topped up
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
 }
 
 #[test]
@@ -873,7 +1166,7 @@ fn unlock_collateral() {
     let res = handle(&mut deps, env.clone(), msg);
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Unlock amount cannot exceed locked amount")
+            assert_eq!(msg, "[E2002] Unlock amount cannot exceed locked amount")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -912,7 +1205,7 @@ fn unlock_collateral() {
         Err(StdError::GenericErr { msg, .. }) => {
             assert_eq!(
                 msg,
-                "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599999400"
+                "[E2003] Unlock amount too high; Loan liability becomes greater than borrow limit: 12599999400"
             )
         }
         _ => panic!("DO NOT ENTER HERE"),
@@ -926,7 +1219,7 @@ fn unlock_collateral() {
         Err(StdError::GenericErr { msg, .. }) => {
             assert_eq!(
                 msg,
-                "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599998800"
+                "[E2003] Unlock amount too high; Loan liability becomes greater than borrow limit: 12599998800"
             )
         }
         _ => panic!("DO NOT ENTER HERE"),
@@ -954,7 +1247,7 @@ fn unlock_collateral() {
     let res = handle(&mut deps, env.clone(), msg);
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599998800")
+            assert_eq!(msg, "[E2003] Unlock amount too high; Loan liability becomes greater than borrow limit: 12599998800")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -986,6 +1279,18 @@ fn unlock_collateral() {
         ]
     );
 
+    // the position is left at its borrow limit
+    let health: PositionHealthResponse = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        health,
+        PositionHealthResponse {
+            borrower: HumanAddr::from("addr0000"),
+            loan_amount: Uint256::from(12599999400u64),
+            borrow_limit: Uint256::from(12599999400u64),
+            health_factor: Some(Decimal256::one()),
+        }
+    );
+
     //testing for unlocking more collaterals
     deps.querier
         .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(125999900u128))]);
@@ -1116,7 +1421,7 @@ fn liquidate_collateral() {
     let res = handle(&mut deps, env.clone(), msg.clone());
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
+            assert_eq!(msg, "[E2004] Cannot liquidate safely collateralized loan")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -1178,3 +1483,5391 @@ fn liquidate_collateral() {
         }
     );
 }
+
+#[test]
+fn simulate_liquidation() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    for token in ["bluna", "batom"].iter() {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from(format!("custody_{}", token)),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000000u64))]);
+
+    let query_msg = QueryMsg::SimulateLiquidation {
+        borrower: HumanAddr::from("addr0000"),
+        block_height: env.block.height,
+        block_time: env.block.time,
+    };
+    match query(&deps, query_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2004] Cannot liquidate safely collateralized loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
+    let res: SimulateLiquidationResponse = from_binary(&query(&deps, query_msg).unwrap()).unwrap();
+
+    // repay_amount = amount * price * (1 - 0.05) * (1 - 0.01)
+    assert_eq!(
+        res,
+        SimulateLiquidationResponse {
+            borrower: HumanAddr::from("addr0000"),
+            borrow_amount: Uint256::from(12600000001u64),
+            borrow_limit: Uint256::from(12600000000u64),
+            collaterals: vec![
+                LiquidatedCollateralResponse {
+                    collateral_token: HumanAddr::from("batom"),
+                    amount: Uint256::from(100000u64),
+                    price: Decimal256::from_ratio(2000u64, 1u64),
+                    premium_rate: Decimal256::percent(5),
+                    repay_amount: Uint256::from(188100000u64),
+                },
+                LiquidatedCollateralResponse {
+                    collateral_token: HumanAddr::from("bluna"),
+                    amount: Uint256::from(10000u64),
+                    price: Decimal256::from_ratio(1000u64, 1u64),
+                    premium_rate: Decimal256::percent(5),
+                    repay_amount: Uint256::from(9405000u64),
+                },
+            ],
+            repay_amount: Uint256::from(197505000u64),
+            loan_amount_after: Uint256::from(12402495001u64),
+            // 0.6 * (990000 * 1000 + 9900000 * 2000)
+            borrow_limit_after: Uint256::from(12474000000u64),
+            emergency_price_used: false,
+        }
+    );
+
+    // the simulation does not touch the collaterals
+    let res: CollateralsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Collaterals {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.collaterals,
+        vec![
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+        ]
+    );
+}
+
+#[test]
+fn emergency_price_removed_guardian() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let update_guardians = |guardians: Vec<&str>| HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: Some(guardians.into_iter().map(HumanAddr::from).collect()),
+        guardian_threshold: Some(2u64),
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(
+        &mut deps,
+        env.clone(),
+        update_guardians(vec!["guardian", "guardian2"]),
+    )
+    .unwrap();
+
+    let msg = HandleMsg::SetEmergencyPrice {
+        asset: HumanAddr::from("bluna"),
+        price: Decimal256::from_ratio(500u64, 1u64),
+        expires_at: env.block.time + 100,
+    };
+    let res = handle(&mut deps, mock_env("guardian", &[]), msg.clone()).unwrap();
+    assert_eq!(res.log[4], log("approvals", 1));
+
+    // guardian is removed, its approval does not count anymore
+    let _res = handle(
+        &mut deps,
+        env.clone(),
+        update_guardians(vec!["guardian2", "guardian3"]),
+    )
+    .unwrap();
+
+    let res = handle(&mut deps, mock_env("guardian2", &[]), msg.clone()).unwrap();
+    assert_eq!(res.log[4], log("approvals", 1));
+
+    let res = query(
+        &deps,
+        QueryMsg::EmergencyPrice {
+            asset: HumanAddr::from("bluna"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2016] No emergency price for the specified asset")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        &deps,
+        QueryMsg::EmergencyPriceProposal {
+            asset: HumanAddr::from("bluna"),
+        },
+    )
+    .unwrap();
+    let proposal_res: EmergencyPriceProposalResponse = from_binary(&res).unwrap();
+    assert_eq!(proposal_res.approvals, vec![HumanAddr::from("guardian2")]);
+
+    // two current guardians apply the price
+    let res = handle(&mut deps, mock_env("guardian3", &[]), msg).unwrap();
+    assert_eq!(res.log[4], log("approvals", 2));
+
+    let res = query(
+        &deps,
+        QueryMsg::EmergencyPrice {
+            asset: HumanAddr::from("bluna"),
+        },
+    )
+    .unwrap();
+    let emergency_price_res: EmergencyPriceResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        emergency_price_res.price,
+        Decimal256::from_ratio(500u64, 1u64)
+    );
+}
+
+#[test]
+fn set_emergency_price() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // store whitelist elems
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: Some(vec![
+            HumanAddr::from("guardian"),
+            HumanAddr::from("guardian2"),
+        ]),
+        guardian_threshold: Some(3u64),
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2011] Guardian threshold must be between 1 and the number of guardians"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: Some(vec![
+            HumanAddr::from("guardian"),
+            HumanAddr::from("guardian2"),
+        ]),
+        guardian_threshold: Some(2u64),
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: Some(3600u64),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    // oracle price is too old
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time - 100,
+            env.block.time - 100,
+        ),
+    )]);
+
+    let msg = HandleMsg::SetEmergencyPrice {
+        asset: HumanAddr::from("bluna"),
+        price: Decimal256::from_ratio(500u64, 1u64),
+        expires_at: env.block.time + 100,
+    };
+
+    // only guardian can set emergency price
+    let res = handle(&mut deps, mock_env("owner", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // expiry must be in the future
+    let res = handle(
+        &mut deps,
+        mock_env("guardian", &[]),
+        HandleMsg::SetEmergencyPrice {
+            asset: HumanAddr::from("bluna"),
+            price: Decimal256::from_ratio(500u64, 1u64),
+            expires_at: env.block.time,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2014] Emergency price must expire in the future")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // expiry cannot be later than max_emergency_price_duration
+    let res = handle(
+        &mut deps,
+        mock_env("guardian", &[]),
+        HandleMsg::SetEmergencyPrice {
+            asset: HumanAddr::from("bluna"),
+            price: Decimal256::from_ratio(500u64, 1u64),
+            expires_at: env.block.time + 3601,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E2061] Emergency price must expire at or before {}",
+                env.block.time + 3600
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("guardian", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_emergency_price"),
+            log("asset", "bluna"),
+            log("price", "500"),
+            log("expires_at", env.block.time + 100),
+            log("approvals", 1),
+            log("guardian_threshold", 2),
+        ]
+    );
+
+    // a single approval does not apply the price
+    let res = query(
+        &deps,
+        QueryMsg::EmergencyPrice {
+            asset: HumanAddr::from("bluna"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2016] No emergency price for the specified asset")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // approving twice does not count twice
+    let res = handle(&mut deps, mock_env("guardian", &[]), msg.clone()).unwrap();
+    assert_eq!(res.log[4], log("approvals", 1));
+
+    let res = query(
+        &deps,
+        QueryMsg::EmergencyPriceProposal {
+            asset: HumanAddr::from("bluna"),
+        },
+    )
+    .unwrap();
+    let proposal_res: EmergencyPriceProposalResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        proposal_res,
+        EmergencyPriceProposalResponse {
+            asset: HumanAddr::from("bluna"),
+            price: Decimal256::from_ratio(500u64, 1u64),
+            expires_at: env.block.time + 100,
+            approvals: vec![HumanAddr::from("guardian")],
+        }
+    );
+
+    // another price restarts the approvals
+    let res = handle(
+        &mut deps,
+        mock_env("guardian2", &[]),
+        HandleMsg::SetEmergencyPrice {
+            asset: HumanAddr::from("bluna"),
+            price: Decimal256::from_ratio(400u64, 1u64),
+            expires_at: env.block.time + 100,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.log[4], log("approvals", 1));
+
+    let _res = handle(&mut deps, mock_env("guardian", &[]), msg.clone()).unwrap();
+    let res = handle(&mut deps, mock_env("guardian2", &[]), msg).unwrap();
+    assert_eq!(res.log[4], log("approvals", 2));
+
+    // the applied proposal is removed
+    let res = query(
+        &deps,
+        QueryMsg::EmergencyPriceProposal {
+            asset: HumanAddr::from("bluna"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E2017] No emergency price proposal for the specified asset"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        &deps,
+        QueryMsg::EmergencyPrice {
+            asset: HumanAddr::from("bluna"),
+        },
+    )
+    .unwrap();
+    let emergency_price_res: EmergencyPriceResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        emergency_price_res,
+        EmergencyPriceResponse {
+            asset: HumanAddr::from("bluna"),
+            price: Decimal256::from_ratio(500u64, 1u64),
+            expires_at: env.block.time + 100,
+        }
+    );
+
+    // borrow_limit = 500 * 1000000 * 0.6
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: Some(env.block.time),
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrow_limit_res,
+        BorrowLimitResponse {
+            borrower: HumanAddr::from("addr0000"),
+            borrow_limit: Uint256::from(300000000u64),
+            liquidation_borrow_limit: Uint256::from(300000000u64),
+            emergency_price_used: true,
+            index_borrow_limit: None,
+        }
+    );
+
+    // emergency price expired; stale oracle price is rejected again
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: Some(env.block.time + 100),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Price is too old"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn inbox() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // store whitelist elems
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // not registered yet
+    let res = query(
+        &deps,
+        QueryMsg::Inbox {
+            address: HumanAddr::from("addr0000"),
+            start_after: None,
+            limit: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E2021] Inbox not registered"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), HandleMsg::RegisterInbox {}).unwrap();
+    let res = handle(&mut deps, env, HandleMsg::RegisterInbox {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2020] Inbox already registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // every LTV update is written to the inbox, keeping the latest 30 records
+    let env = mock_env("owner", &[]);
+    for i in 0..32u64 {
+        let msg = HandleMsg::UpdateWhitelist {
+            collateral_token: HumanAddr::from("bluna"),
+            custody_contract: None,
+            max_ltv: Some(Decimal256::percent(i)),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let res: InboxResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Inbox {
+                address: HumanAddr::from("addr0000"),
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        InboxResponse {
+            address: HumanAddr::from("addr0000"),
+            records: vec![
+                InboxRecordResponse {
+                    seq: 2,
+                    height: env.block.height,
+                    event: InboxEvent::WhitelistUpdated {
+                        collateral_token: HumanAddr::from("bluna"),
+                        max_ltv: Decimal256::percent(2),
+                    },
+                },
+                InboxRecordResponse {
+                    seq: 3,
+                    height: env.block.height,
+                    event: InboxEvent::WhitelistUpdated {
+                        collateral_token: HumanAddr::from("bluna"),
+                        max_ltv: Decimal256::percent(3),
+                    },
+                },
+            ],
+        }
+    );
+
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), HandleMsg::PruneInbox { up_to: 30 }).unwrap();
+    let res: InboxResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Inbox {
+                address: HumanAddr::from("addr0000"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.records.len(), 1);
+    assert_eq!(res.records[0].seq, 31);
+
+    let _res = handle(&mut deps, env, HandleMsg::UnregisterInbox {}).unwrap();
+    let res = query(
+        &deps,
+        QueryMsg::Inbox {
+            address: HumanAddr::from("addr0000"),
+            start_after: None,
+            limit: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "[E2021] Inbox not registered"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn lock_collateral_for() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // store whitelist elems
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateralFor {
+        borrower: HumanAddr::from("addr0000"),
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+        withdraw_allowed: false,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2043] Cannot lock collateral for the sender itself")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let sponsor_env = mock_env("sponsor", &[]);
+    let res = handle(&mut deps, sponsor_env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LockCollateralFor {
+                locker: HumanAddr::from("sponsor"),
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(1000000u64),
+            })
+            .unwrap(),
+        })]
+    );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "lock_collateral_for"),
+            log("locker", "sponsor"),
+            log("borrower", "addr0000"),
+            log("collaterals", "1000000bluna"),
+            log("withdraw_allowed", "false"),
+        ]
+    );
+
+    // borrower adds its own collaterals
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(500000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let res: SponsorshipsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Sponsorships {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SponsorshipsResponse {
+            borrower: HumanAddr::from("addr0000"),
+            sponsorships: vec![SponsorshipResponse {
+                locker: HumanAddr::from("sponsor"),
+                collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+                withdraw_allowed: false,
+            }],
+        }
+    );
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::zero())]);
+
+    // borrower cannot unlock the sponsored collaterals
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(500001u64))],
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2008] Sponsored collaterals can only be unlocked by their locker"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(500000u64))],
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::UnlockCollateralFor {
+        borrower: HumanAddr::from("addr0000"),
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2044] No collaterals locked for the borrower")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        sponsor_env.clone(),
+        HandleMsg::UnlockCollateralFor {
+            borrower: HumanAddr::from("addr0000"),
+            collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000001u64))],
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2045] Unlock amount cannot exceed sponsored amount")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000000u64))]);
+    let res = handle(&mut deps, sponsor_env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2003] Unlock amount too high; Loan liability becomes greater than borrow limit: 0"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::zero())]);
+    let res = handle(&mut deps, sponsor_env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::UnlockCollateralFor {
+                locker: HumanAddr::from("sponsor"),
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(1000000u64),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let res: SponsorshipsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Sponsorships {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.sponsorships, vec![]);
+
+    // with consent the borrower can unlock the sponsored collaterals,
+    // which also releases the sponsorship
+    let msg = HandleMsg::LockCollateralFor {
+        borrower: HumanAddr::from("addr0000"),
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+        withdraw_allowed: true,
+    };
+    let _res = handle(&mut deps, sponsor_env, msg).unwrap();
+
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(400000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let res: SponsorshipsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Sponsorships {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.sponsorships,
+        vec![SponsorshipResponse {
+            locker: HumanAddr::from("sponsor"),
+            collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(600000u64))],
+            withdraw_allowed: true,
+        }]
+    );
+}
+
+#[test]
+fn swap_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let msg = HandleMsg::SwapCollateral {
+        from_token: HumanAddr::from("bluna"),
+        to_token: HumanAddr::from("batom"),
+        min_receive: Uint256::from(400000u64),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2047] Swap router is not registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let update_msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: Some(HumanAddr::from("router")),
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(&mut deps, env.clone(), update_msg).unwrap();
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::SwapCollateral {
+            from_token: HumanAddr::from("batom"),
+            to_token: HumanAddr::from("bluna"),
+            min_receive: Uint256::zero(),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2050] No unlockable collateral to swap")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("batom"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(100u128),
+        )],
+    )]);
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::UnlockCollateralFor {
+                    locker: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(1000000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::WithdrawCollateral {
+                    amount: Some(Uint256::from(1000000u64)),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("bluna"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Send {
+                    contract: HumanAddr::from("router"),
+                    amount: Uint128::from(1000000u128),
+                    msg: Some(
+                        to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                            operations: vec![
+                                SwapOperation::TerraSwap {
+                                    offer_asset_info: AssetInfo::Token {
+                                        contract_addr: HumanAddr::from("bluna"),
+                                    },
+                                    ask_asset_info: AssetInfo::NativeToken {
+                                        denom: "uusd".to_string(),
+                                    },
+                                },
+                                SwapOperation::TerraSwap {
+                                    offer_asset_info: AssetInfo::NativeToken {
+                                        denom: "uusd".to_string(),
+                                    },
+                                    ask_asset_info: AssetInfo::Token {
+                                        contract_addr: HumanAddr::from("batom"),
+                                    },
+                                },
+                            ],
+                            minimum_receive: Some(Uint128::from(400000u128)),
+                            to: None,
+                        })
+                        .unwrap()
+                    ),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&HandleMsg::FinishSwapCollateral {}).unwrap(),
+            }),
+        ]
+    );
+
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2049] A collateral swap is already in progress")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::FinishSwapCollateral {},
+    );
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env(MOCK_CONTRACT_ADDR, &[]);
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(700000000u64))]);
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("batom"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(300100u128),
+        )],
+    )]);
+    let res = handle(&mut deps, env.clone(), HandleMsg::FinishSwapCollateral {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E2052] Swap received less than the minimum amount: 300000"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the new collateral must cover the loan
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("batom"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(500100u128),
+        )],
+    )]);
+    let res = handle(&mut deps, env.clone(), HandleMsg::FinishSwapCollateral {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2053] Swap amount too low; Loan liability becomes greater than borrow limit: 600000000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(300000000u64))]);
+    let res = handle(&mut deps, env, HandleMsg::FinishSwapCollateral {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("batom"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Send {
+                    contract: HumanAddr::from("custody_batom"),
+                    amount: Uint128::from(500000u128),
+                    msg: Some(to_binary(&CustodyCw20HookMsg::DepositCollateral {}).unwrap()),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LockCollateralFor {
+                    locker: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(500000u64),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Collaterals {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res.collaterals,
+        vec![(HumanAddr::from("batom"), Uint256::from(500000u64))]
+    );
+}
+
+#[test]
+fn interest_rebate() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 100u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::one(), env.block.time, env.block.time),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(200000u64))]);
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let checkpoint_msg = HandleMsg::CheckpointLtv {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), checkpoint_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2039] Interest rebate is not enabled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateInterestRebate {
+        rebate_rate: Decimal256::percent(50),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::UpdateRebateLtv {
+        collateral_token: HumanAddr::from("bluna"),
+        rebate_ltv: Decimal256::percent(70),
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2038] rebate_ltv cannot exceed the max LTV of the collateral: 0.6"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // rebate limit: 1000000 * 1 * 0.3 = 300000
+    let msg = HandleMsg::UpdateRebateLtv {
+        collateral_token: HumanAddr::from("bluna"),
+        rebate_ltv: Decimal256::percent(30),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let res: InterestRebateResponse =
+        from_binary(&query(&deps, QueryMsg::InterestRebate {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        InterestRebateResponse {
+            rebate_rate: Decimal256::percent(50),
+            rebate_ltvs: vec![(HumanAddr::from("bluna"), Decimal256::percent(30))],
+        }
+    );
+
+    let res = handle(&mut deps, mock_env("addr0001", &[]), checkpoint_msg.clone()).unwrap();
+    assert_eq!(res.messages, vec![]);
+
+    let checkpoint_query = QueryMsg::LtvCheckpoint {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res: LtvCheckpointResponse =
+        from_binary(&query(&deps, checkpoint_query.clone()).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        LtvCheckpointResponse {
+            borrower: HumanAddr::from("addr0000"),
+            epoch_height: env.block.height,
+            loan_amount: Uint256::from(200000u64),
+            interest_index: Decimal256::one(),
+        }
+    );
+
+    // the epoch of the checkpoint has ended, no full epoch yet
+    let epoch_record = EpochRecord {
+        blocks: 100u64,
+        prev_exchange_rate: Decimal256::one(),
+        exchange_rate: Decimal256::one(),
+        aterra_supply: Uint256::zero(),
+        market_balance: Uint256::zero(),
+        total_liabilities: Decimal256::zero(),
+        total_credit_liabilities: Decimal256::zero(),
+        total_reserves: Decimal256::zero(),
+        deposit_rate: Decimal256::zero(),
+    };
+    store_epoch_record(&mut deps.storage, env.block.height + 100, &epoch_record).unwrap();
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(220000u64))]);
+    deps.querier.with_interest_index(Decimal256::percent(110));
+
+    let res = handle(&mut deps, mock_env("addr0001", &[]), checkpoint_msg.clone()).unwrap();
+    assert_eq!(res.messages, vec![]);
+    let res: LtvCheckpointResponse =
+        from_binary(&query(&deps, checkpoint_query.clone()).unwrap()).unwrap();
+    assert_eq!(res.epoch_height, env.block.height);
+
+    // a full epoch has passed since the checkpoint
+    // rebate: 200000 * (1.1 - 1) * 0.5 = 10000
+    store_epoch_record(&mut deps.storage, env.block.height + 200, &epoch_record).unwrap();
+    store_epoch_state(
+        &mut deps.storage,
+        &EpochState {
+            deposit_rate: Decimal256::zero(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            prev_interest_buffer: Uint256::zero(),
+            last_executed_height: env.block.height + 200,
+        },
+    )
+    .unwrap();
+
+    let res = handle(&mut deps, mock_env("addr0001", &[]), checkpoint_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market"),
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::RebateInterest {
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(10000u64),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let res: LtvCheckpointResponse =
+        from_binary(&query(&deps, checkpoint_query.clone()).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        LtvCheckpointResponse {
+            borrower: HumanAddr::from("addr0000"),
+            epoch_height: env.block.height + 200,
+            loan_amount: Uint256::from(210000u64),
+            interest_index: Decimal256::percent(110),
+        }
+    );
+
+    // loans above the rebate limit lose the checkpoint
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(300001u64))]);
+    let res = handle(&mut deps, mock_env("addr0001", &[]), checkpoint_msg).unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert!(res.log.contains(&log("eligible", false)));
+
+    let res = query(&deps, checkpoint_query);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2040] No LTV checkpoint exists for the borrower")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn index_unit() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    let msg = HandleMsg::UpdateIndexUnit {
+        index_oracle: HumanAddr::from("index_oracle"),
+        index_asset: "cpi".to_string(),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the index must be priced
+    let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap_err();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("cpi".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_index_unit"),
+            log("index_oracle", "index_oracle"),
+            log("index_asset", "cpi"),
+            log("index_price", "2"),
+        ]
+    );
+
+    let res: IndexUnitResponse =
+        from_binary(&query(&deps, QueryMsg::IndexUnit {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        IndexUnitResponse {
+            index_oracle: Some(HumanAddr::from("index_oracle")),
+            index_asset: Some("cpi".to_string()),
+        }
+    );
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600000000 uusd = 300000000 cpi
+    let borrow_limit_msg = QueryMsg::BorrowLimit {
+        borrower: HumanAddr::from("addr0000"),
+        block_time: Some(env.block.time),
+    };
+    let res: BorrowLimitResponse =
+        from_binary(&query(&deps, borrow_limit_msg.clone()).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        BorrowLimitResponse {
+            borrower: HumanAddr::from("addr0000"),
+            borrow_limit: Uint256::from(600000000u64),
+            liquidation_borrow_limit: Uint256::from(600000000u64),
+            emergency_price_used: false,
+            index_borrow_limit: Some(Uint256::from(300000000u64)),
+        }
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::DisableIndexUnit {},
+    );
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, env, HandleMsg::DisableIndexUnit {}).unwrap();
+    let res: BorrowLimitResponse = from_binary(&query(&deps, borrow_limit_msg).unwrap()).unwrap();
+    assert_eq!(res.index_borrow_limit, None);
+
+    let res: IndexUnitResponse =
+        from_binary(&query(&deps, QueryMsg::IndexUnit {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        IndexUnitResponse {
+            index_oracle: None,
+            index_asset: None,
+        }
+    );
+}
+
+#[test]
+fn transfer_position() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(10u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0002", &[]), msg).unwrap();
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::TransferPosition {
+            to: HumanAddr::from("addr0000"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E2030] Cannot transfer a position to the sender itself"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the receiver must not hold collaterals
+    let _res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::TransferPosition {
+            to: HumanAddr::from("addr0002"),
+        },
+    )
+    .unwrap();
+    let res = handle(
+        &mut deps,
+        mock_env("addr0002", &[]),
+        HandleMsg::AcceptPosition {
+            from: HumanAddr::from("addr0000"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E2033] Cannot accept a position with collaterals locked"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a new offer replaces the previous one
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::TransferPosition {
+            to: HumanAddr::from("addr0001"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "transfer_position"),
+            log("borrower", "addr0000"),
+            log("to", "addr0001"),
+        ]
+    );
+
+    let res: PositionTransferResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::PositionTransfer {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        PositionTransferResponse {
+            borrower: HumanAddr::from("addr0000"),
+            to: HumanAddr::from("addr0001"),
+        }
+    );
+
+    let msg = HandleMsg::AcceptPosition {
+        from: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, mock_env("addr0002", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2032] No position transfer offered to the sender")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::TransferCollateral {
+                    from: HumanAddr::from("addr0000"),
+                    to: HumanAddr::from("addr0001"),
+                    amount: Uint256::from(1000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::TransferLiability {
+                    from: HumanAddr::from("addr0000"),
+                    to: HumanAddr::from("addr0001"),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    let res: CollateralsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Collaterals {
+                borrower: HumanAddr::from("addr0001"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.collaterals,
+        vec![(HumanAddr::from("bluna"), Uint256::from(1000u64))]
+    );
+
+    let res: CollateralsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Collaterals {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.collaterals, vec![]);
+
+    // the offer is consumed
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2032] No position transfer offered to the sender")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::CancelPositionTransfer {},
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2031] No position transfer offered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn collateral_batch() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    for token in ["bluna", "batom"].iter() {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from(format!("custody_{}", token)),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let env = mock_env("addr0000", &[]);
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::LockCollateralBatch {
+            collaterals: vec![(HumanAddr::from("bluna"), Uint256::zero())],
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2007] Collateral batch cannot be empty")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // repeated tokens are summed and zero amounts dropped
+    let msg = HandleMsg::LockCollateralBatch {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(100u64)),
+            (HumanAddr::from("batom"), Uint256::from(50u64)),
+            (HumanAddr::from("bluna"), Uint256::from(20u64)),
+            (HumanAddr::from("batom"), Uint256::zero()),
+        ],
+    };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LockCollateral {
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(120u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LockCollateral {
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(50u64),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    let res: CollateralsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Collaterals {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.collaterals,
+        vec![
+            (HumanAddr::from("batom"), Uint256::from(50u64)),
+            (HumanAddr::from("bluna"), Uint256::from(120u64)),
+        ]
+    );
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(60000u64))]);
+
+    // borrow_limit = (50 + 0) * 1000 * 0.6 = 30000
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::UnlockCollateralBatch {
+            collaterals: vec![
+                (HumanAddr::from("bluna"), Uint256::from(60u64)),
+                (HumanAddr::from("bluna"), Uint256::from(60u64)),
+            ],
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2003] Unlock amount too high; Loan liability becomes greater than borrow limit: 30000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // borrow_limit = (50 + 60) * 1000 * 0.6 = 66000
+    let res = handle(
+        &mut deps,
+        env,
+        HandleMsg::UnlockCollateralBatch {
+            collaterals: vec![
+                (HumanAddr::from("bluna"), Uint256::from(30u64)),
+                (HumanAddr::from("bluna"), Uint256::from(30u64)),
+            ],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::UnlockCollateral {
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(60u64),
+            })
+            .unwrap(),
+        })]
+    );
+}
+
+#[test]
+fn insurance() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    let msg = HandleMsg::AttachInsurance {
+        insurance_contract: HumanAddr::from("insurance"),
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2023] Insurance contract is not registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let register_msg = HandleMsg::RegisterInsurance {
+        insurance_contract: HumanAddr::from("insurance"),
+    };
+    match handle(&mut deps, env.clone(), register_msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), register_msg).unwrap();
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "attach_insurance"),
+            log("borrower", "addr0000"),
+            log("insurance_contract", "insurance"),
+        ]
+    );
+
+    let res: InsuranceContractsResponse =
+        from_binary(&query(&deps, QueryMsg::InsuranceContracts {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        InsuranceContractsResponse {
+            insurance_contracts: vec![HumanAddr::from("insurance")],
+        }
+    );
+
+    // borrow_limit = 1000000 * 1000 * 0.6 = 600000000
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+
+    // the insurance contract is called before the liquidation
+    let liquidator_env = mock_env("liquidator", &[]);
+    let res = handle(
+        &mut deps,
+        liquidator_env.clone(),
+        HandleMsg::LiquidateCollateral {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("insurance"),
+                send: vec![],
+                msg: to_binary(&InsuranceHandleMsg::CurePosition {
+                    borrower: HumanAddr::from("addr0000"),
+                    loan_amount: Uint256::from(600000001u64),
+                    borrow_limit: Uint256::from(600000000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&HandleMsg::FinishInsuredLiquidation {
+                    borrower: HumanAddr::from("addr0000"),
+                    liquidator: HumanAddr::from("liquidator"),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    let finish_msg = HandleMsg::FinishInsuredLiquidation {
+        borrower: HumanAddr::from("addr0000"),
+        liquidator: HumanAddr::from("liquidator"),
+    };
+    match handle(&mut deps, liquidator_env.clone(), finish_msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the insurance repaid part of the loan
+    let contract_env = mock_env(MOCK_CONTRACT_ADDR, &[]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000000u64))]);
+    let res = handle(&mut deps, contract_env.clone(), finish_msg.clone()).unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "finish_insured_liquidation"),
+            log("borrower", "addr0000"),
+            log("cured", true),
+        ]
+    );
+
+    // the position is liquidated when the insurance did not cure it
+    let liquidation_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: HumanAddr::from("custody_bluna"),
+        send: vec![],
+        msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+            liquidator: HumanAddr::from("liquidator"),
+            borrower: HumanAddr::from("addr0000"),
+            amount: Uint256::from(10000u64),
+        })
+        .unwrap(),
+    });
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+    let res = handle(&mut deps, contract_env, finish_msg).unwrap();
+    assert_eq!(res.messages[0], liquidation_msg);
+
+    // deregistered insurance contracts are not called anymore
+    let _res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::DeregisterInsurance {
+            insurance_contract: HumanAddr::from("insurance"),
+        },
+    )
+    .unwrap();
+
+    let res: PositionInsuranceResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::PositionInsurance {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        PositionInsuranceResponse {
+            borrower: HumanAddr::from("addr0000"),
+            insurance_contract: HumanAddr::from("insurance"),
+            registered: false,
+        }
+    );
+
+    let res = handle(
+        &mut deps,
+        liquidator_env,
+        HandleMsg::LiquidateCollateral {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                liquidator: HumanAddr::from("liquidator"),
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(9900u64),
+            })
+            .unwrap(),
+        })
+    );
+
+    let _res = handle(&mut deps, env.clone(), HandleMsg::DetachInsurance {}).unwrap();
+    match handle(&mut deps, env, HandleMsg::DetachInsurance {}) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2024] No insurance attached to the position")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn risk_module() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_liquidation_percent(&[
+        (&HumanAddr::from("liquidation"), &Decimal256::percent(1)),
+        (&HumanAddr::from("risk_module"), &Decimal256::percent(2)),
+    ]);
+    deps.querier
+        .with_risk_module(&[(&HumanAddr::from("risk_module"), &(false, false))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(300000000u64))]);
+
+    let msg = HandleMsg::UpdateRiskModule {
+        risk_module: HumanAddr::from("risk_module"),
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_risk_module"),
+            log("risk_module", "risk_module"),
+        ]
+    );
+
+    let res: RiskModuleResponse =
+        from_binary(&query(&deps, QueryMsg::RiskModule {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        RiskModuleResponse {
+            risk_module: Some(HumanAddr::from("risk_module")),
+        }
+    );
+
+    // the risk module rejects the borrower
+    let borrow_limit_msg = QueryMsg::BorrowLimit {
+        borrower: HumanAddr::from("addr0000"),
+        block_time: None,
+    };
+    let res: BorrowLimitResponse =
+        from_binary(&query(&deps, borrow_limit_msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::zero());
+
+    let unlock_msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(100u64))],
+    };
+    match handle(&mut deps, env.clone(), unlock_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2009] Unlock rejected by the risk module")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the risk module accepts the borrower
+    deps.querier
+        .with_risk_module(&[(&HumanAddr::from("risk_module"), &(true, true))]);
+    let res: BorrowLimitResponse = from_binary(&query(&deps, borrow_limit_msg).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::from(600000000u64));
+
+    let _res = handle(&mut deps, env, unlock_msg).unwrap();
+
+    // liquidation amounts come from the risk module,
+    // borrow_limit = 999900 * 1000 * 0.6 = 599940000
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000000u64))]);
+    let liquidate_msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let liquidator_env = mock_env("liquidator", &[]);
+    let res = handle(&mut deps, liquidator_env.clone(), liquidate_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                liquidator: HumanAddr::from("liquidator"),
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(19998u64),
+            })
+            .unwrap(),
+        })
+    );
+
+    // back to the liquidation contract once disabled,
+    // 979902 collaterals are left after the first liquidation
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::DisableRiskModule {},
+    )
+    .unwrap();
+    assert_eq!(res.log, vec![log("action", "disable_risk_module")]);
+
+    let res: RiskModuleResponse =
+        from_binary(&query(&deps, QueryMsg::RiskModule {}).unwrap()).unwrap();
+    assert_eq!(res, RiskModuleResponse { risk_module: None });
+
+    let res = handle(&mut deps, liquidator_env, liquidate_msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                liquidator: HumanAddr::from("liquidator"),
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(9799u64),
+            })
+            .unwrap(),
+        })
+    );
+}
+
+#[test]
+fn liquidate_bad_debt() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::one())]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // collaterals worth less than the loan are all liquidated
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(1100000000u64))]);
+    let res = handle(
+        &mut deps,
+        mock_env("liquidator", &[]),
+        HandleMsg::LiquidateCollateral {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("liquidator"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(1000000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
+                    borrower: HumanAddr::from("addr0000"),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::WriteOffBadDebt {
+                    borrower: HumanAddr::from("addr0000"),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn min_position_collateral_value() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(10))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::one(), env.block.time, env.block.time),
+    )]);
+
+    let borrow_limit_msg = QueryMsg::BorrowLimit {
+        borrower: HumanAddr::from("addr0000"),
+        block_time: None,
+    };
+    let res: BorrowLimitResponse =
+        from_binary(&query(&deps, borrow_limit_msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::from(600u64));
+
+    // the position is worth 1000uusd
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: Some(Uint256::from(1001u64)),
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    // dust positions cannot borrow
+    let res: BorrowLimitResponse = from_binary(&query(&deps, borrow_limit_msg).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::zero());
+
+    // dust positions are liquidated in full, the rest is bad debt
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(700u64))]);
+    let res = handle(
+        &mut deps,
+        mock_env("liquidator", &[]),
+        HandleMsg::LiquidateCollateral {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("liquidator"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(1000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
+                    borrower: HumanAddr::from("addr0000"),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::WriteOffBadDebt {
+                    borrower: HumanAddr::from("addr0000"),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn epoch_operations_once_per_height() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000000u128),
+        }],
+    );
+
+    let mut env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 0u64,
+        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // a loss decreased the exchange rate over the epoch
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::percent(90)),
+    )]);
+
+    // a zero epoch period does not allow two executions in the init block
+    let msg = HandleMsg::ExecuteEpochOperations {};
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9003] Epoch operations already executed; last executed height: 12345"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.height += 1;
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(res.log[1], log("deposit_rate", "0"));
+
+    let update_msg = HandleMsg::UpdateEpochState {
+        interest_buffer: Uint256::from(8_000_000_000u128),
+        distributed_interest: Uint256::zero(),
+    };
+    let mut contract_env = mock_env(MOCK_CONTRACT_ADDR, &[]);
+    contract_env.block.height = env.block.height;
+    let res = handle(&mut deps, contract_env.clone(), update_msg.clone()).unwrap();
+    assert_eq!(res.log[1], log("deposit_rate", "0"));
+
+    // neither step runs twice in the same block, whatever the order
+    let res = handle(&mut deps, contract_env.clone(), update_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2019] Epoch state already updated; last executed height: 12346"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9003] Epoch operations already executed; last executed height: 12346"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // nor at an older height delivered out of order
+    contract_env.block.height -= 1;
+    let res = handle(&mut deps, contract_env, update_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2019] Epoch state already updated; last executed height: 12346"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.height += 1;
+    let _res = handle(&mut deps, env, msg).unwrap();
+}
+
+#[test]
+fn protocol_mode() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::one(), env.block.time, env.block.time),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::zero())]);
+
+    let msg = HandleMsg::RegisterProtocolController {
+        protocol_controller: HumanAddr::from("controller"),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "register_protocol_controller"),
+            log("protocol_controller", "controller"),
+            log("mode", "normal"),
+        ]
+    );
+
+    let lock_msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), lock_msg.clone()).unwrap();
+
+    // collaterals can only be unlocked
+    deps.querier
+        .with_protocol_mode(ProtocolMode::ConservativeWithdrawOnly);
+    let res = handle(
+        &mut deps,
+        mock_env("controller", &[]),
+        HandleMsg::RefreshProtocolMode {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "refresh_protocol_mode"),
+            log("mode", "conservative_withdraw_only"),
+        ]
+    );
+
+    let res = handle(&mut deps, mock_env("addr0000", &[]), lock_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9008] Deposits are not allowed in conservative_withdraw_only mode"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let unlock_msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(100u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), unlock_msg.clone()).unwrap();
+
+    // nothing moves once paused
+    deps.querier.with_protocol_mode(ProtocolMode::FullPause);
+    let _res = handle(
+        &mut deps,
+        mock_env("controller", &[]),
+        HandleMsg::RefreshProtocolMode {},
+    )
+    .unwrap();
+
+    let res = handle(&mut deps, mock_env("addr0000", &[]), unlock_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E9009] Withdrawals are not allowed in full_pause mode"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn whitelist_proposal() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let metadata = AssetMetadata {
+        oracle_source: "band".to_string(),
+        liquidity_score: 80u8,
+        audit_hash: "ab".repeat(32),
+    };
+    let propose_msg = HandleMsg::ProposeWhitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+        metadata: AssetMetadata {
+            audit_hash: "audit".to_string(),
+            ..metadata.clone()
+        },
+    };
+
+    let mut env = mock_env("proposer", &[]);
+    let res = handle(&mut deps, env.clone(), propose_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2057] audit_hash must be a hex encoded sha256 hash")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let propose_msg = HandleMsg::ProposeWhitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+        metadata: metadata.clone(),
+    };
+    let res = handle(&mut deps, env.clone(), propose_msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "propose_whitelist"),
+            log("proposer", "proposer"),
+            log("name", "bluna"),
+            log("symbol", "bluna"),
+            log("collateral_token", "bluna"),
+            log("custody_contract", "custody_bluna"),
+            log("LTV", "0.6"),
+        ]
+    );
+
+    let res = handle(&mut deps, env.clone(), propose_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2054] Token is already proposed as collateral")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: WhitelistProposalsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::WhitelistProposals {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.proposals,
+        vec![WhitelistProposalResponse {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            collateral_token: HumanAddr::from("bluna"),
+            custody_contract: HumanAddr::from("custody_bluna"),
+            max_ltv: Decimal256::percent(60),
+            metadata: metadata.clone(),
+            proposer: HumanAddr::from("proposer"),
+            proposed_height: env.block.height,
+        }]
+    );
+
+    // proposals are not collaterals yet
+    let res: WhitelistResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Whitelist {
+                collateral_token: None,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.elems, vec![]);
+
+    let confirm_msg = HandleMsg::ConfirmWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+    };
+    let res = handle(&mut deps, env.clone(), confirm_msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let proposed_height = env.block.height;
+    env.message.sender = HumanAddr::from("owner");
+    env.block.height += 100;
+    let res = handle(&mut deps, env.clone(), confirm_msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "confirm_whitelist"),
+            log("proposer", "proposer"),
+            log("name", "bluna"),
+            log("symbol", "bluna"),
+            log("collateral_token", "bluna"),
+            log("custody_contract", "custody_bluna"),
+            log("LTV", "0.6"),
+        ]
+    );
+
+    let res: WhitelistResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Whitelist {
+                collateral_token: Some(HumanAddr::from("bluna")),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.elems,
+        vec![WhitelistResponseElem {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            max_ltv: Decimal256::percent(60),
+            custody_contract: HumanAddr::from("custody_bluna"),
+            collateral_token: HumanAddr::from("bluna"),
+        }]
+    );
+
+    let res: AssetMetadataResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::AssetMetadata {
+                collateral_token: HumanAddr::from("bluna"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        AssetMetadataResponse {
+            collateral_token: HumanAddr::from("bluna"),
+            metadata: metadata.clone(),
+            proposer: HumanAddr::from("proposer"),
+            proposed_height,
+            confirmed_height: env.block.height,
+        }
+    );
+
+    // the proposal is consumed
+    let res = handle(&mut deps, env.clone(), confirm_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2058] Token is not proposed as collateral")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // rejected proposals are dropped
+    let propose_msg = HandleMsg::ProposeWhitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(50),
+        metadata,
+    };
+    let _res = handle(&mut deps, mock_env("proposer", &[]), propose_msg).unwrap();
+
+    let res = handle(
+        &mut deps,
+        env,
+        HandleMsg::RejectWhitelist {
+            collateral_token: HumanAddr::from("batom"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "reject_whitelist"),
+            log("proposer", "proposer"),
+            log("collateral_token", "batom"),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::WhitelistProposal {
+            collateral_token: HumanAddr::from("batom"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2058] Token is not proposed as collateral")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn analytics_contract() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000000u128),
+        }],
+    );
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::one())]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 0u64,
+        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // Unauthorized err
+    let msg = HandleMsg::UpdateAnalyticsContract {
+        analytics_contract: HumanAddr::from("analytics"),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    let res = query(&deps, QueryMsg::AnalyticsContract {}).unwrap();
+    let analytics_res: AnalyticsContractResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        analytics_res.analytics_contract,
+        Some(HumanAddr::from("analytics"))
+    );
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // the loan left once the collaterals are sold is bad debt
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(1100000000u64))]);
+    let _res = handle(
+        &mut deps,
+        mock_env("liquidator", &[]),
+        HandleMsg::LiquidateCollateral {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::one()),
+    )]);
+
+    // the epoch indicators are pushed on the epoch operations
+    env.block.height += 1;
+    let res = handle(&mut deps, env.clone(), HandleMsg::ExecuteEpochOperations {}).unwrap();
+    let record_epoch_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: HumanAddr::from("analytics"),
+        send: vec![],
+        msg: to_binary(&AnalyticsHandleMsg::RecordEpoch {
+            height: env.block.height,
+            kpis: EpochKpis {
+                deposit_rate: Decimal256::zero(),
+                utilization_ratio: Decimal256::zero(),
+                liquidation_volume: Uint256::from(1000000000u64),
+                new_bad_debt: Uint256::from(100000000u64),
+            },
+        })
+        .unwrap(),
+    });
+    assert!(res.messages.contains(&record_epoch_msg));
+
+    // the volumes restart from zero on the next epoch
+    let update_msg = HandleMsg::UpdateEpochState {
+        interest_buffer: Uint256::zero(),
+        distributed_interest: Uint256::zero(),
+    };
+    let mut contract_env = mock_env(MOCK_CONTRACT_ADDR, &[]);
+    contract_env.block.height = env.block.height;
+    let _res = handle(&mut deps, contract_env, update_msg).unwrap();
+
+    env.block.height += 1;
+    let res = handle(&mut deps, env.clone(), HandleMsg::ExecuteEpochOperations {}).unwrap();
+    let record_epoch_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: HumanAddr::from("analytics"),
+        send: vec![],
+        msg: to_binary(&AnalyticsHandleMsg::RecordEpoch {
+            height: env.block.height,
+            kpis: EpochKpis {
+                deposit_rate: Decimal256::zero(),
+                utilization_ratio: Decimal256::zero(),
+                liquidation_volume: Uint256::zero(),
+                new_bad_debt: Uint256::zero(),
+            },
+        })
+        .unwrap(),
+    });
+    assert!(res.messages.contains(&record_epoch_msg));
+
+    // the indicators are no longer pushed once disabled
+    let _res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::DisableAnalyticsContract {},
+    )
+    .unwrap();
+    let res = query(&deps, QueryMsg::AnalyticsContract {}).unwrap();
+    let analytics_res: AnalyticsContractResponse = from_binary(&res).unwrap();
+    assert_eq!(analytics_res.analytics_contract, None);
+
+    let update_msg = HandleMsg::UpdateEpochState {
+        interest_buffer: Uint256::zero(),
+        distributed_interest: Uint256::zero(),
+    };
+    let mut contract_env = mock_env(MOCK_CONTRACT_ADDR, &[]);
+    contract_env.block.height = env.block.height;
+    let _res = handle(&mut deps, contract_env, update_msg).unwrap();
+
+    env.block.height += 1;
+    let res = handle(&mut deps, env, HandleMsg::ExecuteEpochOperations {}).unwrap();
+    assert!(!res.messages.iter().any(|msg| match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) =>
+            contract_addr == &HumanAddr::from("analytics"),
+        _ => false,
+    }));
+}
+
+#[test]
+fn liquidation_order() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // bluna is whitelisted with its risk metadata, batom without
+    let msg = HandleMsg::ProposeWhitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+        metadata: AssetMetadata {
+            oracle_source: "band".to_string(),
+            liquidity_score: 90u8,
+            audit_hash: "ab".repeat(32),
+        },
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    let msg = HandleMsg::ConfirmWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: Some(LiquidationOrder::MostLiquidFirst),
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
+
+    // the pro rata value, 100000 * 2000 + 10000 * 1000 = 210,000,000 uusd,
+    // is seized from bluna only
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(210000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
+                    borrower: HumanAddr::from("addr0000"),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    // once bluna runs out, the rest is seized from batom
+    // 10,000,000 * 2000 * 0.1 + 790,000 * 1000 * 0.1 = 2,079,000,000 uusd
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(10))]);
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[..2].to_vec(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(790000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(644500u64),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Collaterals {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res.collaterals,
+        vec![(HumanAddr::from("batom"), Uint256::from(9355500u64))]
+    );
+}
+
+#[test]
+fn collateral_protection() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    for token in &["bluna", "batom"] {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from(format!("custody_{}", token)),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let env = mock_env("addr0000", &[]);
+    let msg = HandleMsg::UpdateCollateralProtection {
+        protected_collaterals: vec![HumanAddr::from("bust")],
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2046] Token is not registered as collateral")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateCollateralProtection {
+        protected_collaterals: vec![HumanAddr::from("batom"), HumanAddr::from("batom")],
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2037] Collateral protected twice: batom")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateCollateralProtection {
+        protected_collaterals: vec![HumanAddr::from("batom")],
+    };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_collateral_protection"),
+            log("borrower", "addr0000"),
+            log("protected_collaterals", "batom"),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::CollateralProtection {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let protection_res: CollateralProtectionResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        protection_res,
+        CollateralProtectionResponse {
+            borrower: HumanAddr::from("addr0000"),
+            protected_collaterals: vec![HumanAddr::from("batom")],
+        }
+    );
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
+
+    // the pro rata value, 100000 * 2000 + 10000 * 1000 = 210,000,000 uusd,
+    // is seized from the unprotected bluna
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                liquidator: HumanAddr::from("addr0001"),
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(210000u64),
+            })
+            .unwrap(),
+        })
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Collaterals {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res.collaterals,
+        vec![
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+            (HumanAddr::from("bluna"), Uint256::from(790000u64)),
+        ]
+    );
+
+    // an empty list removes the protection
+    let msg = HandleMsg::UpdateCollateralProtection {
+        protected_collaterals: vec![],
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::CollateralProtection {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let protection_res: CollateralProtectionResponse = from_binary(&res).unwrap();
+    assert_eq!(protection_res.protected_collaterals, vec![]);
+}
+
+#[test]
+fn feed_liveness() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    for token in &["bluna", "batom"] {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from(format!("custody_{}", token)),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: Some(3600u64),
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000u64)),
+            (HumanAddr::from("batom"), Uint256::from(1000u64)),
+        ],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    // the batom feeder stopped updating its price
+    let dead_time = env.block.time - 3601;
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(2000u64, 1u64), dead_time, dead_time),
+        ),
+    ]);
+
+    let res = query(
+        &deps,
+        QueryMsg::FeedLiveness {
+            block_time: env.block.time,
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let feed_liveness_res: FeedLivenessResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        feed_liveness_res.feeds,
+        vec![
+            FeedLivenessResponseElem {
+                collateral_token: HumanAddr::from("batom"),
+                last_updated: Some(dead_time),
+                live: false,
+            },
+            FeedLivenessResponseElem {
+                collateral_token: HumanAddr::from("bluna"),
+                last_updated: Some(env.block.time),
+                live: true,
+            },
+        ]
+    );
+
+    // batom gives no borrow power instead of failing on its stale price
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: Some(env.block.time),
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(600000u64));
+
+    // the loan cannot exceed the bluna borrow power
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("batom"), Uint256::from(1u64))],
+    };
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600001u64))]);
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "[E2003] Unlock amount too high; Loan liability becomes greater than borrow limit: 600000"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the switch is disabled with a zero max feed gap,
+    // the stale batom price is rejected again
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: Some(0u64),
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: Some(env.block.time),
+        },
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn reward_route() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000000u128),
+        }],
+    );
+
+    let mut env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    for token in &["bluna", "batom"] {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from(format!("custody_{}", token)),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let route = vec![
+        RewardShare {
+            destination: RewardDestination::Market {},
+            ratio: Decimal256::percent(50),
+        },
+        RewardShare {
+            destination: RewardDestination::Pool {
+                address: HumanAddr::from("bluna_pool"),
+            },
+            ratio: Decimal256::percent(30),
+        },
+        RewardShare {
+            destination: RewardDestination::InterestBuffer {},
+            ratio: Decimal256::percent(20),
+        },
+    ];
+    let msg = HandleMsg::UpdateRewardRoute {
+        collateral_token: HumanAddr::from("bluna"),
+        route: route.clone(),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::UpdateRewardRoute {
+            collateral_token: HumanAddr::from("bluna"),
+            route: route[..2].to_vec(),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2042] Reward share ratios must sum to 1: 0.8")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::UpdateRewardRoute {
+            collateral_token: HumanAddr::from("bluna"),
+            route: vec![
+                RewardShare {
+                    destination: RewardDestination::Market {},
+                    ratio: Decimal256::one(),
+                },
+                RewardShare {
+                    destination: RewardDestination::InterestBuffer {},
+                    ratio: Decimal256::zero(),
+                },
+            ],
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2041] Reward share ratio must be greater than 0")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::RewardRoute {
+            collateral_token: HumanAddr::from("bluna"),
+        },
+    )
+    .unwrap();
+    let route_res: RewardRouteResponse = from_binary(&res).unwrap();
+    assert_eq!(route_res.route, route);
+
+    // the routed custody splits its rewards,
+    // the other one keeps the interest buffer
+    env.block.height += 86400u64;
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::one(), env.block.time, env.block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::one(), env.block.time, env.block.time),
+        ),
+    ]);
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::percent(120)),
+    )]);
+
+    let res = handle(&mut deps, env.clone(), HandleMsg::ExecuteEpochOperations {}).unwrap();
+    assert_eq!(
+        res.messages[1..3],
+        [
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::DistributeRewards {
+                    reward_recipients: None,
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::DistributeRewards {
+                    reward_recipients: Some(vec![
+                        (HumanAddr::from("market"), Decimal256::percent(50)),
+                        (HumanAddr::from("bluna_pool"), Decimal256::percent(30)),
+                        (HumanAddr::from(MOCK_CONTRACT_ADDR), Decimal256::percent(20)),
+                    ]),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    // an empty route goes back to the interest buffer
+    let msg = HandleMsg::UpdateRewardRoute {
+        collateral_token: HumanAddr::from("bluna"),
+        route: vec![],
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::RewardRoute {
+            collateral_token: HumanAddr::from("bluna"),
+        },
+    )
+    .unwrap();
+    let route_res: RewardRouteResponse = from_binary(&res).unwrap();
+    assert_eq!(route_res.route, vec![]);
+}
+
+#[test]
+fn solvency() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    for token in &["bluna", "batom"] {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from(format!("custody_{}", token)),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::percent(50), env.block.time, env.block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_uint256(2u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    deps.querier.with_token_balances(&[
+        (
+            &HumanAddr::from("bluna"),
+            &[(
+                &HumanAddr::from("custody_bluna"),
+                &Uint128::from(400000u128),
+            )],
+        ),
+        (
+            &HumanAddr::from("batom"),
+            &[(
+                &HumanAddr::from("custody_batom"),
+                &Uint128::from(100000u128),
+            )],
+        ),
+    ]);
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::one()),
+    )]);
+    deps.querier
+        .with_total_liabilities(Decimal256::from_uint256(500000u64));
+
+    // the liabilities are only covered up to the collateral value
+    let res = query(
+        &deps,
+        QueryMsg::Solvency {
+            block_height: env.block.height,
+        },
+    )
+    .unwrap();
+    let solvency: SolvencyResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        solvency,
+        SolvencyResponse {
+            market_balance: Uint256::from(1000000u64),
+            total_liabilities: Decimal256::from_uint256(500000u64),
+            total_credit_liabilities: Decimal256::zero(),
+            collateral_value: Uint256::from(400000u64),
+            total_assets: Decimal256::from_uint256(1400000u64),
+            aterra_value: Decimal256::from_uint256(1000000u64),
+            total_reserves: Decimal256::zero(),
+            total_obligations: Decimal256::from_uint256(1000000u64),
+            solvency_ratio: Decimal256::percent(140),
+        }
+    );
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::one(), env.block.time, env.block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_uint256(2u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    let res = query(
+        &deps,
+        QueryMsg::Solvency {
+            block_height: env.block.height,
+        },
+    )
+    .unwrap();
+    let solvency: SolvencyResponse = from_binary(&res).unwrap();
+    assert_eq!(solvency.collateral_value, Uint256::from(600000u64));
+    assert_eq!(solvency.total_assets, Decimal256::from_uint256(1500000u64));
+    assert_eq!(solvency.solvency_ratio, Decimal256::percent(150));
+}
+
+#[test]
+fn price_check() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(300000000u64))]);
+    deps.querier.with_dex_twap(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &Decimal256::from_ratio(980u64, 1u64),
+    )]);
+
+    let msg = HandleMsg::UpdatePriceCheck {
+        collateral_token: HumanAddr::from("bluna"),
+        price_check: Some(PriceCheck {
+            dex_twap: HumanAddr::from("dex_twap"),
+            max_deviation: Decimal256::percent(5),
+        }),
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_price_check"),
+            log("collateral_token", "bluna"),
+            log("enabled", true),
+        ]
+    );
+
+    // deviation = 20 / 980 < 5%
+    let price_check_msg = QueryMsg::PriceCheck {
+        collateral_token: HumanAddr::from("bluna"),
+    };
+    let res: PriceCheckResponse =
+        from_binary(&query(&deps, price_check_msg.clone()).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        PriceCheckResponse {
+            collateral_token: HumanAddr::from("bluna"),
+            price_check: Some(PriceCheck {
+                dex_twap: HumanAddr::from("dex_twap"),
+                max_deviation: Decimal256::percent(5),
+            }),
+            oracle_price: Some(Decimal256::from_ratio(1000u64, 1u64)),
+            dex_twap: Some(Decimal256::from_ratio(980u64, 1u64)),
+            diverged: false,
+        }
+    );
+
+    let borrow_limit_msg = QueryMsg::BorrowLimit {
+        borrower: HumanAddr::from("addr0000"),
+        block_time: None,
+    };
+    let res: BorrowLimitResponse =
+        from_binary(&query(&deps, borrow_limit_msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::from(600000000u64));
+
+    let unlock_msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(100u64))],
+    };
+    let _res = handle(&mut deps, env.clone(), unlock_msg.clone()).unwrap();
+
+    // deviation = 100 / 900 > 5%, borrows and unlocks are blocked
+    deps.querier.with_dex_twap(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &Decimal256::from_ratio(900u64, 1u64),
+    )]);
+    let res: PriceCheckResponse =
+        from_binary(&query(&deps, price_check_msg.clone()).unwrap()).unwrap();
+    assert!(res.diverged);
+
+    let res: BorrowLimitResponse =
+        from_binary(&query(&deps, borrow_limit_msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::zero());
+
+    match handle(&mut deps, env.clone(), unlock_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2035] Oracle price of bluna diverges from the DEX price; unlock is disabled"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // an unavailable twap counts as diverged
+    deps.querier.with_dex_twap(&[]);
+    let res: PriceCheckResponse =
+        from_binary(&query(&deps, price_check_msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.dex_twap, None);
+    assert!(res.diverged);
+
+    let res: BorrowLimitResponse =
+        from_binary(&query(&deps, borrow_limit_msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::zero());
+
+    // removing the check restores the borrow limit
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        HandleMsg::UpdatePriceCheck {
+            collateral_token: HumanAddr::from("bluna"),
+            price_check: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_price_check"),
+            log("collateral_token", "bluna"),
+            log("enabled", false),
+        ]
+    );
+
+    let res: PriceCheckResponse = from_binary(&query(&deps, price_check_msg).unwrap()).unwrap();
+    assert_eq!(res.price_check, None);
+    assert!(!res.diverged);
+
+    // borrow_limit = 999900 * 1000 * 0.6
+    let res: BorrowLimitResponse = from_binary(&query(&deps, borrow_limit_msg).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::from(599940000u64));
+
+    let _res = handle(&mut deps, env, unlock_msg).unwrap();
+}
+
+#[test]
+fn max_ltv_override() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::from_ratio(1000u64, 1u64), u64::MAX, u64::MAX),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(300000000u64))]);
+
+    let msg = HandleMsg::UpdateMaxLtvOverride {
+        max_ltv_override: Some(Decimal256::one()),
+    };
+    match handle(&mut deps, env.clone(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2028] max_ltv_override must be between 0 and 1")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateMaxLtvOverride {
+        max_ltv_override: Some(Decimal256::percent(40)),
+    };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_max_ltv_override"),
+            log("borrower", "addr0000"),
+            log("max_ltv_override", "0.4"),
+        ]
+    );
+
+    let res: MaxLtvOverrideResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::MaxLtvOverride {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        MaxLtvOverrideResponse {
+            borrower: HumanAddr::from("addr0000"),
+            max_ltv_override: Some(Decimal256::percent(40)),
+            raisable_at: Some(env.block.time + 86400u64),
+        }
+    );
+
+    // borrow_limit = 1000000 * 1000 * 0.4
+    let borrow_limit_msg = QueryMsg::BorrowLimit {
+        borrower: HumanAddr::from("addr0000"),
+        block_time: None,
+    };
+    let res: BorrowLimitResponse =
+        from_binary(&query(&deps, borrow_limit_msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::from(400000000u64));
+
+    // 700000 * 1000 * 0.4 < 300000000
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(300000u64))],
+    };
+    match handle(&mut deps, env.clone(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2003] Unlock amount too high; Loan liability becomes greater than borrow limit: 280000000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the override can be lowered at once, not raised
+    let msg = HandleMsg::UpdateMaxLtvOverride {
+        max_ltv_override: Some(Decimal256::percent(50)),
+    };
+    match handle(&mut deps, env.clone(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E2029] Max LTV override cannot be raised or removed before {}",
+                env.block.time + 86400u64
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.time += 3600u64;
+    let msg = HandleMsg::UpdateMaxLtvOverride {
+        max_ltv_override: Some(Decimal256::percent(35)),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let res: BorrowLimitResponse =
+        from_binary(&query(&deps, borrow_limit_msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::from(350000000u64));
+
+    // the cooldown restarts from the last update
+    env.block.time += 86399u64;
+    let msg = HandleMsg::UpdateMaxLtvOverride {
+        max_ltv_override: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap_err();
+
+    env.block.time += 1u64;
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_max_ltv_override"),
+            log("borrower", "addr0000"),
+            log("max_ltv_override", "none"),
+        ]
+    );
+
+    let res: BorrowLimitResponse = from_binary(&query(&deps, borrow_limit_msg).unwrap()).unwrap();
+    assert_eq!(res.borrow_limit, Uint256::from(600000000u64));
+
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(300000u64))],
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+}
+
+#[test]
+fn registry() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_registry(
+        1,
+        &[
+            ("market", &HumanAddr::from("market0001")),
+            ("oracle", &HumanAddr::from("oracle0001")),
+            ("custody:bluna", &HumanAddr::from("custody_bluna0001")),
+        ],
+    );
+
+    let msg = HandleMsg::RegisterRegistry {
+        registry_contract: HumanAddr::from("registry"),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the peers are resolved right away
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "register_registry"),
+            log("registry_contract", "registry"),
+            log("version", 1),
+        ]
+    );
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_res.market_contract, HumanAddr::from("market0001"));
+    assert_eq!(config_res.oracle_contract, HumanAddr::from("oracle0001"));
+    assert_eq!(
+        config_res.liquidation_contract,
+        HumanAddr::from("liquidation")
+    );
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let mut user_env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, user_env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna0001"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LockCollateral {
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(1000000u64),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // the resolved peers are kept until they expire
+    deps.querier
+        .with_registry(2, &[("liquidation", &HumanAddr::from("liquidation0001"))]);
+    let _res = handle(&mut deps, user_env.clone(), msg.clone()).unwrap();
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config_res.liquidation_contract,
+        HumanAddr::from("liquidation")
+    );
+
+    // unregistered names keep their last address
+    user_env.block.height += REGISTRY_MAX_AGE + 1;
+    let _res = handle(&mut deps, user_env.clone(), msg).unwrap();
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_res.market_contract, HumanAddr::from("market0001"));
+    assert_eq!(
+        config_res.liquidation_contract,
+        HumanAddr::from("liquidation0001")
+    );
+
+    let res: RegistryResponse = from_binary(&query(&deps, QueryMsg::Registry {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        RegistryResponse {
+            registry_contract: Some(HumanAddr::from("registry")),
+            version: 2,
+            updated_height: user_env.block.height,
+        }
+    );
+
+    let res = handle(&mut deps, user_env.clone(), HandleMsg::RefreshRegistry {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![log("action", "refresh_registry"), log("version", 2)]
+    );
+
+    let _res = handle(&mut deps, env, HandleMsg::DisableRegistry {}).unwrap();
+    match handle(&mut deps, user_env, HandleMsg::RefreshRegistry {}) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9004] No registry is registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: RegistryResponse = from_binary(&query(&deps, QueryMsg::Registry {}).unwrap()).unwrap();
+    assert_eq!(res.registry_contract, None);
+}
+
+#[test]
+fn epoch_catch_up() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000000u128),
+        }],
+    );
+
+    let mut env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 100u64,
+        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    store_epoch_state(
+        &mut deps.storage,
+        &EpochState {
+            last_executed_height: env.block.height,
+            prev_exchange_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(1000000u64),
+            prev_interest_buffer: Uint256::from(10000000000u128),
+            deposit_rate: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::one()),
+    )]);
+
+    // ten epochs later, the missed ones are combined into a single epoch
+    // missing_deposits = 1,000,000 * 1000 blocks * 0.000001 = 1000
+    env.block.height += 1000u64;
+    let msg = HandleMsg::ExecuteEpochOperations {};
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "epoch_operations"),
+            log("deposit_rate", "0"),
+            log("exchange_rate", "1"),
+            log("aterra_supply", "1000000"),
+            log("distributed_interest", "1000"),
+            log("anc_purchase_amount", "0"),
+            log("missed_epochs", 9),
+            log("distribution_blocks", 1000),
+        ]
+    );
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: Some(2u64),
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_res.max_catch_up_epochs, 2u64);
+
+    // the interest buffer only makes up for two epochs
+    let msg = HandleMsg::ExecuteEpochOperations {};
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "epoch_operations"),
+            log("deposit_rate", "0"),
+            log("exchange_rate", "1"),
+            log("aterra_supply", "1000000"),
+            log("distributed_interest", "200"),
+            log("anc_purchase_amount", "0"),
+            log("missed_epochs", 9),
+            log("distribution_blocks", 200),
+        ]
+    );
+
+    // an execution right on time misses none
+    env.block.height -= 900u64;
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "epoch_operations"),
+            log("deposit_rate", "0"),
+            log("exchange_rate", "1"),
+            log("aterra_supply", "1000000"),
+            log("distributed_interest", "100"),
+            log("anc_purchase_amount", "0"),
+        ]
+    );
+}
+
+#[test]
+fn liquidate_auctioned_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::one())]);
+    deps.querier.with_auctions(&[(
+        &HumanAddr::from("bluna"),
+        &LiquidationMechanism::DutchAuction {
+            start_premium: Decimal256::percent(10),
+            decay_rate: Decimal256::percent(1),
+            floor: Decimal256::percent(95),
+        },
+    )]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // the auction repays the loan as it sells, nothing
+    // is repaid nor written off within the liquidation
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(1100000000u64))]);
+    let res = handle(
+        &mut deps,
+        mock_env("liquidator", &[]),
+        HandleMsg::LiquidateCollateral {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                liquidator: HumanAddr::from("liquidator"),
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(1000000u64),
+            })
+            .unwrap(),
+        })]
+    );
+    assert!(res.log.contains(&log("auctioned_collaterals", 1)));
+}
+
+#[test]
+fn epoch_phase() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000000u128),
+        }],
+    );
+
+    let mut env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::one(), env.block.time, env.block.time),
+    )]);
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::percent(120)),
+    )]);
+
+    let phase_res: EpochPhaseResponse =
+        from_binary(&query(&deps, QueryMsg::EpochPhase {}).unwrap()).unwrap();
+    assert_eq!(
+        phase_res,
+        EpochPhaseResponse {
+            phase: EpochPhase::Idle,
+            height: 0,
+            pending_steps: vec![
+                "validate_prices".to_string(),
+                "distribute_rewards".to_string(),
+                "update_rate".to_string(),
+            ],
+        }
+    );
+
+    // each step is executed and stored alone
+    let msg = HandleMsg::ExecuteEpochStep {};
+    env.block.height += 86400u64;
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert_eq!(
+        res.log,
+        vec![log("action", "epoch_step"), log("step", "validate_prices")]
+    );
+
+    let phase_res: EpochPhaseResponse =
+        from_binary(&query(&deps, QueryMsg::EpochPhase {}).unwrap()).unwrap();
+    assert_eq!(
+        phase_res,
+        EpochPhaseResponse {
+            phase: EpochPhase::PricesValidated,
+            height: env.block.height,
+            pending_steps: vec!["distribute_rewards".to_string(), "update_rate".to_string()],
+        }
+    );
+
+    // prices validated within price_timeframe are
+    // resumed from the blocks that follow
+    env.block.height += 1;
+    env.block.time += 30;
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(res.log[1], log("step", "distribute_rewards"));
+    assert_eq!(res.messages.len(), 2);
+
+    let phase_res: EpochPhaseResponse =
+        from_binary(&query(&deps, QueryMsg::EpochPhase {}).unwrap()).unwrap();
+    assert_eq!(phase_res.phase, EpochPhase::RewardsDistributed);
+    assert_eq!(phase_res.pending_steps, vec!["update_rate".to_string()]);
+
+    // the buffer left by the distribution is carried to the update
+    let update_epoch_state_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+        send: vec![],
+        msg: to_binary(&HandleMsg::UpdateEpochState {
+            interest_buffer: Uint256::from(8_000_000_000u128),
+            distributed_interest: Uint256::zero(),
+        })
+        .unwrap(),
+    });
+
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(res.log[1], log("step", "update_rate"));
+    assert_eq!(res.messages, vec![update_epoch_state_msg.clone()]);
+
+    // the epoch operations resume from the phase reached
+    let res = handle(&mut deps, env.clone(), HandleMsg::ExecuteEpochOperations {}).unwrap();
+    assert_eq!(res.messages, vec![update_epoch_state_msg]);
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "epoch_operations"),
+            log("resumed_phase", "rewards_distributed"),
+        ]
+    );
+
+    let mut contract_env = mock_env(MOCK_CONTRACT_ADDR, &[]);
+    contract_env.block.height = env.block.height;
+    let _res = handle(
+        &mut deps,
+        contract_env,
+        HandleMsg::UpdateEpochState {
+            interest_buffer: Uint256::from(8_000_000_000u128),
+            distributed_interest: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    let phase_res: EpochPhaseResponse =
+        from_binary(&query(&deps, QueryMsg::EpochPhase {}).unwrap()).unwrap();
+    assert_eq!(
+        phase_res,
+        EpochPhaseResponse {
+            phase: EpochPhase::RateUpdated,
+            height: env.block.height,
+            pending_steps: vec![
+                "validate_prices".to_string(),
+                "distribute_rewards".to_string(),
+                "update_rate".to_string(),
+            ],
+        }
+    );
+
+    // a failing step leaves the epoch at the phase reached
+    env.block.height += 86400u64;
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::one(), env.block.time, env.block.time),
+    )]);
+    let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+
+    // prices validated over price_timeframe ago are validated again
+    env.block.time += 61;
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E2005] Stale collateral prices; valid from {}: bluna last updated at {}",
+                env.block.time - 60,
+                env.block.time - 61
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let phase_res: EpochPhaseResponse =
+        from_binary(&query(&deps, QueryMsg::EpochPhase {}).unwrap()).unwrap();
+    assert_eq!(phase_res.phase, EpochPhase::PricesValidated);
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::one(), env.block.time, env.block.time),
+    )]);
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(res.log[1], log("step", "validate_prices"));
+
+    let res = handle(&mut deps, env, HandleMsg::ExecuteEpochOperations {}).unwrap();
+    assert_eq!(res.log[1], log("resumed_phase", "prices_validated"));
+
+    // collector, buffer distribution, rewards and the epoch state update
+    assert_eq!(res.messages.len(), 4);
+}
+
+#[test]
+fn invariant_checks() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::UpdateInvariantChecks { enabled: true };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    let res: InvariantChecksResponse =
+        from_binary(&query(&deps, QueryMsg::InvariantChecks {}).unwrap()).unwrap();
+    assert!(res.enabled);
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: Some(Decimal256::permille(6)),
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E2025] Invariant violated: threshold deposit rate exceeds the target deposit rate"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: Some(Decimal256::permille(6)),
+        target_deposit_rate: Some(Decimal256::permille(6)),
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: None,
+        max_emergency_price_duration: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::UpdateInvariantChecks { enabled: false };
+    let _res = handle(&mut deps, env, msg).unwrap();
+    let res: InvariantChecksResponse =
+        from_binary(&query(&deps, QueryMsg::InvariantChecks {}).unwrap()).unwrap();
+    assert!(!res.enabled);
+}
+
+#[test]
+fn snapshot_health() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(1200000000u64))]);
+
+    let borrow_limit_res: BorrowLimitResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BorrowLimit {
+                borrower: HumanAddr::from("addr0000"),
+                block_time: Some(env.block.time),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    // the snapshot of the position computes the same borrow limit
+    let mut snapshot = HealthSnapshot {
+        loan_amount: Uint256::from(1200000000u64),
+        collaterals: vec![SnapshotCollateral {
+            collateral_token: HumanAddr::from("bluna"),
+            amount: Uint256::from(1000000u64),
+            price: Decimal256::from_ratio(1000u64, 1u64),
+            max_ltv: Decimal256::percent(60),
+            dead_feed: false,
+        }],
+        max_ltv_override: None,
+        min_position_collateral_value: Uint256::zero(),
+    };
+    let snapshot_health = |snapshot: &HealthSnapshot| -> SnapshotHealthResponse {
+        from_binary(
+            &query(
+                &deps,
+                QueryMsg::SnapshotHealth {
+                    snapshot: snapshot.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        snapshot_health(&snapshot),
+        SnapshotHealthResponse {
+            collaterals_value: Uint256::from(1000000000u64),
+            borrow_limit: borrow_limit_res.borrow_limit,
+            liquidation_borrow_limit: Uint256::from(600000000u64),
+            health_factor: Some(Decimal256::percent(50)),
+            liquidatable: true,
+            dust: false,
+        }
+    );
+
+    // the liquidation candidates are found over the same snapshot
+    let res: LiquidationCandidatesResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationCandidates {
+                start_after: None,
+                limit: None,
+                block_height: env.block.height,
+                block_time: env.block.time,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.candidates,
+        vec![LiquidationCandidateResponse {
+            borrower: HumanAddr::from("addr0000"),
+            loan_amount: Uint256::from(1200000000u64),
+            borrow_limit: snapshot_health(&snapshot).liquidation_borrow_limit,
+            shortfall: Uint256::from(600000000u64),
+        }]
+    );
+
+    // the max ltv override lowers the borrow limit but not the liquidation threshold
+    snapshot.loan_amount = Uint256::from(300000000u64);
+    snapshot.max_ltv_override = Some(Decimal256::percent(30));
+    assert_eq!(
+        snapshot_health(&snapshot),
+        SnapshotHealthResponse {
+            collaterals_value: Uint256::from(1000000000u64),
+            borrow_limit: Uint256::from(300000000u64),
+            liquidation_borrow_limit: Uint256::from(600000000u64),
+            health_factor: Some(Decimal256::from_uint256(2u64)),
+            liquidatable: false,
+            dust: false,
+        }
+    );
+
+    // dust positions cannot borrow, dead feeds give no borrow power
+    snapshot.min_position_collateral_value = Uint256::from(1000000001u64);
+    let res = snapshot_health(&snapshot);
+    assert_eq!(res.borrow_limit, Uint256::zero());
+    assert!(res.dust);
+
+    snapshot.collaterals[0].dead_feed = true;
+    snapshot.loan_amount = Uint256::zero();
+    assert_eq!(
+        snapshot_health(&snapshot),
+        SnapshotHealthResponse {
+            collaterals_value: Uint256::from(1000000000u64),
+            borrow_limit: Uint256::zero(),
+            liquidation_borrow_limit: Uint256::zero(),
+            health_factor: None,
+            liquidatable: false,
+            dust: true,
+        }
+    );
+}
+
+#[test]
+fn liquidation_candidates() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    for borrower in ["addr0000", "addr0001", "addr0002"].iter() {
+        let msg = HandleMsg::LockCollateral {
+            collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+        };
+        let _res = handle(&mut deps, mock_env(*borrower, &[]), msg).unwrap();
+    }
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // borrow limit = 1000000 * 1000 * 0.6
+    deps.querier.with_loan_amount(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(600000000u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(600000001u64)),
+        (&HumanAddr::from("addr0002"), &Uint256::from(700000000u64)),
+    ]);
+
+    let res: LiquidationCandidatesResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationCandidates {
+                start_after: None,
+                limit: Some(2),
+                block_height: env.block.height,
+                block_time: env.block.time,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LiquidationCandidatesResponse {
+            candidates: vec![LiquidationCandidateResponse {
+                borrower: HumanAddr::from("addr0001"),
+                loan_amount: Uint256::from(600000001u64),
+                borrow_limit: Uint256::from(600000000u64),
+                shortfall: Uint256::from(1u64),
+            }],
+            last_scanned: Some(HumanAddr::from("addr0001")),
+        }
+    );
+
+    let res: LiquidationCandidatesResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationCandidates {
+                start_after: res.last_scanned,
+                limit: Some(2),
+                block_height: env.block.height,
+                block_time: env.block.time,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LiquidationCandidatesResponse {
+            candidates: vec![LiquidationCandidateResponse {
+                borrower: HumanAddr::from("addr0002"),
+                loan_amount: Uint256::from(700000000u64),
+                borrow_limit: Uint256::from(600000000u64),
+                shortfall: Uint256::from(100000000u64),
+            }],
+            last_scanned: Some(HumanAddr::from("addr0002")),
+        }
+    );
+
+    let res: LiquidationCandidatesResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationCandidates {
+                start_after: res.last_scanned,
+                limit: Some(2),
+                block_height: env.block.height,
+                block_time: env.block.time,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LiquidationCandidatesResponse {
+            candidates: vec![],
+            last_scanned: None,
+        }
+    );
+}
+
+#[test]
+fn liquidation_close_factor() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    for token in ["bluna", "batom"].iter() {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from(format!("custody_{}", token)),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
+
+    let update_close_factor = |close_factor: Decimal256| HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardians: None,
+        guardian_threshold: None,
+        max_collateral_types: None,
+        swap_router: None,
+        min_position_collateral_value: None,
+        liquidation_order: None,
+        max_feed_gap: None,
+        max_catch_up_epochs: None,
+        close_factor: Some(close_factor),
+        max_emergency_price_duration: None,
+    };
+
+    let res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        update_close_factor(Decimal256::percent(101)),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E2012] Close factor cannot be greater than 1")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(
+        &mut deps,
+        mock_env("owner", &[]),
+        update_close_factor(Decimal256::permille(5)),
+    )
+    .unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_res.close_factor, Decimal256::permille(5));
+
+    // the 1% liquidation amounts are capped to 0.5% of the collaterals
+    let res: SimulateLiquidationResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::SimulateLiquidation {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: env.block.height,
+                block_time: env.block.time,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.collaterals,
+        vec![
+            LiquidatedCollateralResponse {
+                collateral_token: HumanAddr::from("batom"),
+                amount: Uint256::from(50000u64),
+                price: Decimal256::from_ratio(2000u64, 1u64),
+                premium_rate: Decimal256::percent(5),
+                repay_amount: Uint256::from(94050000u64),
+            },
+            LiquidatedCollateralResponse {
+                collateral_token: HumanAddr::from("bluna"),
+                amount: Uint256::from(5000u64),
+                price: Decimal256::from_ratio(1000u64, 1u64),
+                premium_rate: Decimal256::percent(5),
+                repay_amount: Uint256::from(4702500u64),
+            },
+        ]
+    );
+    // 0.6 * (995000 * 1000 + 9950000 * 2000)
+    assert_eq!(res.borrow_limit_after, Uint256::from(12537000000u64));
+
+    // the seized collaterals are capped the same way
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+    let res: CollateralsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Collaterals {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.collaterals,
+        vec![
+            (HumanAddr::from("batom"), Uint256::from(9950000u64)),
+            (HumanAddr::from("bluna"), Uint256::from(995000u64)),
+        ]
+    );
+
+    // the position is liquidated again only once the prices update
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "[E2060] The position was liquidated at the prices of {}, waiting for a price update",
+                env.block.time
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time + 1,
+                env.block.time + 1,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    let mut liquidator_env = mock_env("addr0001", &[]);
+    liquidator_env.block.time += 1;
+    let _res = handle(&mut deps, liquidator_env, msg).unwrap();
+    let res: CollateralsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Collaterals {
+                borrower: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.collaterals,
+        vec![
+            (HumanAddr::from("batom"), Uint256::from(9900250u64)),
+            (HumanAddr::from("bluna"), Uint256::from(990025u64)),
+        ]
+    );
+}
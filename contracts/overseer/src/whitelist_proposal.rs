@@ -0,0 +1,252 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+
+use crate::state::{
+    read_asset_metadata, read_config, read_whitelist_elem, read_whitelist_proposal,
+    read_whitelist_proposals, remove_whitelist_proposal, store_asset_metadata,
+    store_whitelist_elem, store_whitelist_proposal, AssetRiskMetadata, Config, WhitelistElem,
+    WhitelistProposal,
+};
+
+use moneymarket::error::ContractError;
+use moneymarket::overseer::{
+    AssetMetadata, AssetMetadataResponse, WhitelistProposalResponse, WhitelistProposalsResponse,
+};
+
+const MAX_LIQUIDITY_SCORE: u8 = 100;
+
+/// Anyone can propose a collateral, the proposer
+/// is recorded along with the metadata
+#[allow(clippy::too_many_arguments)]
+pub fn propose_whitelist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+    symbol: String,
+    collateral_token: HumanAddr,
+    custody_contract: HumanAddr,
+    max_ltv: Decimal256,
+    metadata: AssetMetadata,
+) -> HandleResult {
+    assert_asset_metadata(&metadata)?;
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    if read_whitelist_elem(&deps.storage, &collateral_token_raw).is_ok() {
+        return Err(ContractError::CollateralRegistered {}.into());
+    }
+
+    if read_whitelist_proposal(&deps.storage, &collateral_token_raw)?.is_some() {
+        return Err(ContractError::CollateralProposed {}.into());
+    }
+
+    store_whitelist_proposal(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &WhitelistProposal {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            custody_contract: deps.api.canonical_address(&custody_contract)?,
+            max_ltv,
+            metadata,
+            proposer: deps.api.canonical_address(&env.message.sender)?,
+            proposed_height: env.block.height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "propose_whitelist"),
+            log("proposer", env.message.sender),
+            log("name", name),
+            log("symbol", symbol),
+            log("collateral_token", collateral_token),
+            log("custody_contract", custody_contract),
+            log("LTV", max_ltv),
+        ],
+        data: None,
+    })
+}
+
+pub fn confirm_whitelist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let proposal = load_whitelist_proposal(&deps.storage, &collateral_token_raw)?;
+
+    // the token can be whitelisted directly while proposed
+    if read_whitelist_elem(&deps.storage, &collateral_token_raw).is_ok() {
+        return Err(ContractError::CollateralRegistered {}.into());
+    }
+
+    store_whitelist_elem(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &WhitelistElem {
+            name: proposal.name.clone(),
+            symbol: proposal.symbol.clone(),
+            custody_contract: proposal.custody_contract.clone(),
+            max_ltv: proposal.max_ltv,
+        },
+    )?;
+    store_asset_metadata(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &AssetRiskMetadata {
+            metadata: proposal.metadata,
+            proposer: proposal.proposer.clone(),
+            proposed_height: proposal.proposed_height,
+            confirmed_height: env.block.height,
+        },
+    )?;
+    remove_whitelist_proposal(&mut deps.storage, &collateral_token_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "confirm_whitelist"),
+            log("proposer", deps.api.human_address(&proposal.proposer)?),
+            log("name", proposal.name),
+            log("symbol", proposal.symbol),
+            log("collateral_token", collateral_token),
+            log(
+                "custody_contract",
+                deps.api.human_address(&proposal.custody_contract)?,
+            ),
+            log("LTV", proposal.max_ltv),
+        ],
+        data: None,
+    })
+}
+
+pub fn reject_whitelist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let proposal = load_whitelist_proposal(&deps.storage, &collateral_token_raw)?;
+    remove_whitelist_proposal(&mut deps.storage, &collateral_token_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "reject_whitelist"),
+            log("proposer", deps.api.human_address(&proposal.proposer)?),
+            log("collateral_token", collateral_token),
+        ],
+        data: None,
+    })
+}
+
+fn assert_asset_metadata(metadata: &AssetMetadata) -> StdResult<()> {
+    if metadata.oracle_source.is_empty() {
+        return Err(ContractError::EmptyOracleSource {}.into());
+    }
+
+    if metadata.liquidity_score > MAX_LIQUIDITY_SCORE {
+        return Err(ContractError::LiquidityScoreTooHigh {
+            max: MAX_LIQUIDITY_SCORE,
+        }
+        .into());
+    }
+
+    if metadata.audit_hash.len() != 64
+        || !metadata.audit_hash.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(ContractError::InvalidAuditHash {}.into());
+    }
+
+    Ok(())
+}
+
+fn load_whitelist_proposal<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<WhitelistProposal> {
+    match read_whitelist_proposal(storage, collateral_token)? {
+        Some(v) => Ok(v),
+        None => Err(ContractError::CollateralNotProposed {}.into()),
+    }
+}
+
+fn to_proposal_response<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: &CanonicalAddr,
+    proposal: WhitelistProposal,
+) -> StdResult<WhitelistProposalResponse> {
+    Ok(WhitelistProposalResponse {
+        name: proposal.name,
+        symbol: proposal.symbol,
+        collateral_token: deps.api.human_address(collateral_token)?,
+        custody_contract: deps.api.human_address(&proposal.custody_contract)?,
+        max_ltv: proposal.max_ltv,
+        metadata: proposal.metadata,
+        proposer: deps.api.human_address(&proposal.proposer)?,
+        proposed_height: proposal.proposed_height,
+    })
+}
+
+pub fn query_whitelist_proposal<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<WhitelistProposalResponse> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let proposal = load_whitelist_proposal(&deps.storage, &collateral_token_raw)?;
+
+    to_proposal_response(deps, &collateral_token_raw, proposal)
+}
+
+pub fn query_whitelist_proposals<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<WhitelistProposalsResponse> {
+    let start_after = match start_after {
+        Some(v) => Some(deps.api.canonical_address(&v)?),
+        None => None,
+    };
+
+    let proposals = read_whitelist_proposals(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(k, v)| to_proposal_response(deps, &k, v))
+        .collect::<StdResult<Vec<WhitelistProposalResponse>>>()?;
+
+    Ok(WhitelistProposalsResponse { proposals })
+}
+
+pub fn query_asset_metadata<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<AssetMetadataResponse> {
+    let metadata = match read_asset_metadata(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+    )? {
+        Some(v) => v,
+        None => return Err(ContractError::NotProposalWhitelisted {}.into()),
+    };
+
+    Ok(AssetMetadataResponse {
+        collateral_token,
+        metadata: metadata.metadata,
+        proposer: deps.api.human_address(&metadata.proposer)?,
+        proposed_height: metadata.proposed_height,
+        confirmed_height: metadata.confirmed_height,
+    })
+}
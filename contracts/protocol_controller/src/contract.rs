@@ -0,0 +1,337 @@
+use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    InitResponse, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use moneymarket::protocol_controller::{
+    ConfigResponse, HandleMsg, InitMsg, ProtocolMode, ProtocolModeResponse, QueryMsg,
+    SubscriberHandleMsg,
+};
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: InitMsg,
+) -> StdResult<InitResponse> {
+    store_config(
+        &mut deps.storage,
+        &Config {
+            owner_addr: deps.api.canonical_address(&msg.owner_addr)?,
+            guardian_addr: deps.api.canonical_address(&msg.guardian_addr)?,
+            subscribers: vec![],
+        },
+    )?;
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            mode: ProtocolMode::Normal,
+            updated_height: env.block.height,
+        },
+    )?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> HandleResult {
+    match msg {
+        HandleMsg::UpdateConfig {
+            owner_addr,
+            guardian_addr,
+            subscribers,
+        } => update_config(deps, env, owner_addr, guardian_addr, subscribers),
+        HandleMsg::UpdateProtocolMode { mode } => update_protocol_mode(deps, env, mode),
+    }
+}
+
+pub fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner_addr: Option<HumanAddr>,
+    guardian_addr: Option<HumanAddr>,
+    subscribers: Option<Vec<HumanAddr>>,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if let Some(owner_addr) = owner_addr {
+        config.owner_addr = deps.api.canonical_address(&owner_addr)?;
+    }
+
+    if let Some(guardian_addr) = guardian_addr {
+        config.guardian_addr = deps.api.canonical_address(&guardian_addr)?;
+    }
+
+    if let Some(subscribers) = subscribers {
+        config.subscribers = subscribers
+            .iter()
+            .map(|v| deps.api.canonical_address(v))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+
+    store_config(&mut deps.storage, &config)?;
+    Ok(HandleResponse::default())
+}
+
+/// The owner can set any mode, the guardian can only make it
+/// more restrictive. The subscribers refresh their cached mode
+/// in the same transaction, so they never get out of sync
+pub fn update_protocol_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    mode: ProtocolMode,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    if sender_raw != config.owner_addr && (sender_raw != config.guardian_addr || mode <= state.mode)
+    {
+        return Err(StdError::unauthorized());
+    }
+
+    state.mode = mode;
+    state.updated_height = env.block.height;
+    store_state(&mut deps.storage, &state)?;
+
+    let messages: Vec<CosmosMsg> = config
+        .subscribers
+        .iter()
+        .map(|v| {
+            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.human_address(v)?,
+                send: vec![],
+                msg: to_binary(&SubscriberHandleMsg::RefreshProtocolMode {})?,
+            }))
+        })
+        .collect::<StdResult<Vec<CosmosMsg>>>()?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![log("action", "update_protocol_mode"), log("mode", mode)],
+        data: None,
+    })
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::ProtocolMode {} => to_binary(&query_protocol_mode(deps)?),
+    }
+}
+
+fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    Ok(ConfigResponse {
+        owner_addr: deps.api.human_address(&config.owner_addr)?,
+        guardian_addr: deps.api.human_address(&config.guardian_addr)?,
+        subscribers: config
+            .subscribers
+            .iter()
+            .map(|v| deps.api.human_address(v))
+            .collect::<StdResult<Vec<HumanAddr>>>()?,
+    })
+}
+
+fn query_protocol_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ProtocolModeResponse> {
+    let state: State = read_state(&deps.storage)?;
+    Ok(ProtocolModeResponse {
+        mode: state.mode,
+        updated_height: state.updated_height,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::StdError;
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner_addr: HumanAddr::from("owner0000"),
+            guardian_addr: HumanAddr::from("guardian0000"),
+        };
+
+        let env = mock_env("addr0000", &[]);
+
+        // we can just call .unwrap() to assert this was a success
+        let res = init(&mut deps, env.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let value = query_config(&deps).unwrap();
+        assert_eq!(
+            value,
+            ConfigResponse {
+                owner_addr: HumanAddr::from("owner0000"),
+                guardian_addr: HumanAddr::from("guardian0000"),
+                subscribers: vec![],
+            }
+        );
+
+        let value = query_protocol_mode(&deps).unwrap();
+        assert_eq!(
+            value,
+            ProtocolModeResponse {
+                mode: ProtocolMode::Normal,
+                updated_height: env.block.height,
+            }
+        );
+    }
+
+    #[test]
+    fn update_config() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner_addr: HumanAddr::from("owner0000"),
+            guardian_addr: HumanAddr::from("guardian0000"),
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        // update owner and subscribers
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner_addr: Some(HumanAddr::from("owner0001")),
+            guardian_addr: None,
+            subscribers: Some(vec![HumanAddr::from("market"), HumanAddr::from("overseer")]),
+        };
+
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let value = query_config(&deps).unwrap();
+        assert_eq!(
+            value,
+            ConfigResponse {
+                owner_addr: HumanAddr::from("owner0001"),
+                guardian_addr: HumanAddr::from("guardian0000"),
+                subscribers: vec![HumanAddr::from("market"), HumanAddr::from("overseer")],
+            }
+        );
+
+        // Unauthorized err
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner_addr: None,
+            guardian_addr: Some(HumanAddr::from("guardian0001")),
+            subscribers: None,
+        };
+
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+    }
+
+    #[test]
+    fn update_protocol_mode() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner_addr: HumanAddr::from("owner0000"),
+            guardian_addr: HumanAddr::from("guardian0000"),
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner_addr: None,
+            guardian_addr: None,
+            subscribers: Some(vec![HumanAddr::from("market"), HumanAddr::from("overseer")]),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Unauthorized err
+        let env = mock_env("addr0000", &[]);
+        let msg = HandleMsg::UpdateProtocolMode {
+            mode: ProtocolMode::FullPause,
+        };
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // the guardian restricts the mode, the subscribers refresh
+        let mut env = mock_env("guardian0000", &[]);
+        env.block.height += 100;
+        let msg = HandleMsg::UpdateProtocolMode {
+            mode: ProtocolMode::ConservativeWithdrawOnly,
+        };
+        let res = handle(&mut deps, env.clone(), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: HumanAddr::from("market"),
+                    send: vec![],
+                    msg: to_binary(&SubscriberHandleMsg::RefreshProtocolMode {}).unwrap(),
+                }),
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: HumanAddr::from("overseer"),
+                    send: vec![],
+                    msg: to_binary(&SubscriberHandleMsg::RefreshProtocolMode {}).unwrap(),
+                }),
+            ]
+        );
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "update_protocol_mode"),
+                log("mode", "conservative_withdraw_only"),
+            ]
+        );
+
+        let value = query_protocol_mode(&deps).unwrap();
+        assert_eq!(
+            value,
+            ProtocolModeResponse {
+                mode: ProtocolMode::ConservativeWithdrawOnly,
+                updated_height: env.block.height,
+            }
+        );
+
+        // but cannot relax it
+        let msg = HandleMsg::UpdateProtocolMode {
+            mode: ProtocolMode::Normal,
+        };
+        let res = handle(&mut deps, env.clone(), msg.clone());
+        match res {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // the owner can
+        env.message.sender = HumanAddr::from("owner0000");
+        let _res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            query_protocol_mode(&deps).unwrap().mode,
+            ProtocolMode::Normal
+        );
+    }
+}
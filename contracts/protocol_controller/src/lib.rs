@@ -0,0 +1,5 @@
+pub mod contract;
+pub mod state;
+
+#[cfg(all(target_arch = "wasm32", not(feature = "library")))]
+cosmwasm_std::create_entry_points!(contract);
@@ -0,0 +1,38 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read};
+use moneymarket::protocol_controller::ProtocolMode;
+
+static KEY_CONFIG: &[u8] = b"config";
+static KEY_STATE: &[u8] = b"state";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner_addr: CanonicalAddr,
+    pub guardian_addr: CanonicalAddr,
+    pub subscribers: Vec<CanonicalAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    pub mode: ProtocolMode,
+    pub updated_height: u64,
+}
+
+pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+pub fn store_state<S: Storage>(storage: &mut S, state: &State) -> StdResult<()> {
+    singleton(storage, KEY_STATE).save(state)
+}
+
+pub fn read_state<S: Storage>(storage: &S) -> StdResult<State> {
+    singleton_read(storage, KEY_STATE).load()
+}
@@ -0,0 +1,395 @@
+use crate::state::{
+    read_address, read_addresses, read_config, read_state, remove_address, store_address,
+    store_config, store_state, AddressEntry, Config, State,
+};
+
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    InitResponse, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use moneymarket::registry::{
+    AddressResponse, AddressesResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg,
+    ResolveResponse, SubscriberHandleMsg, VersionResponse,
+};
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: InitMsg,
+) -> StdResult<InitResponse> {
+    store_config(
+        &mut deps.storage,
+        &Config {
+            owner_addr: deps.api.canonical_address(&msg.owner_addr)?,
+            subscribers: vec![],
+        },
+    )?;
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            version: 0,
+            updated_height: env.block.height,
+        },
+    )?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> HandleResult {
+    match msg {
+        HandleMsg::UpdateConfig {
+            owner_addr,
+            subscribers,
+        } => update_config(deps, env, owner_addr, subscribers),
+        HandleMsg::RegisterAddress { name, address } => {
+            register_address(deps, env, name, Some(address))
+        }
+        HandleMsg::RemoveAddress { name } => register_address(deps, env, name, None),
+    }
+}
+
+pub fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner_addr: Option<HumanAddr>,
+    subscribers: Option<Vec<HumanAddr>>,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if let Some(owner_addr) = owner_addr {
+        config.owner_addr = deps.api.canonical_address(&owner_addr)?;
+    }
+
+    if let Some(subscribers) = subscribers {
+        config.subscribers = subscribers
+            .iter()
+            .map(|v| deps.api.canonical_address(v))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+
+    store_config(&mut deps.storage, &config)?;
+    Ok(HandleResponse::default())
+}
+
+/// Registers, replaces or removes (`address` is `None`) the address
+/// of `name` under a new version. The subscribers resolve their
+/// peers again in the same transaction
+pub fn register_address<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+    address: Option<HumanAddr>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if name.is_empty() {
+        return Err(StdError::generic_err("Name cannot be empty"));
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+    state.version += 1;
+    state.updated_height = env.block.height;
+
+    let log = match address {
+        Some(address) => {
+            store_address(
+                &mut deps.storage,
+                &name,
+                &AddressEntry {
+                    address: deps.api.canonical_address(&address)?,
+                    version: state.version,
+                },
+            )?;
+
+            vec![
+                log("action", "register_address"),
+                log("name", &name),
+                log("address", address),
+                log("version", state.version),
+            ]
+        }
+        None => {
+            if read_address(&deps.storage, &name)?.is_none() {
+                return Err(StdError::generic_err(format!(
+                    "No address registered for {}",
+                    name
+                )));
+            }
+
+            remove_address(&mut deps.storage, &name);
+            vec![
+                log("action", "remove_address"),
+                log("name", &name),
+                log("version", state.version),
+            ]
+        }
+    };
+
+    store_state(&mut deps.storage, &state)?;
+
+    let messages: Vec<CosmosMsg> = config
+        .subscribers
+        .iter()
+        .map(|v| {
+            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.human_address(v)?,
+                send: vec![],
+                msg: to_binary(&SubscriberHandleMsg::RefreshRegistry {})?,
+            }))
+        })
+        .collect::<StdResult<Vec<CosmosMsg>>>()?;
+
+    Ok(HandleResponse {
+        messages,
+        log,
+        data: None,
+    })
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Version {} => to_binary(&query_version(deps)?),
+        QueryMsg::Addresses { start_after, limit } => {
+            to_binary(&query_addresses(deps, start_after, limit)?)
+        }
+        QueryMsg::Resolve { names } => to_binary(&query_resolve(deps, names)?),
+    }
+}
+
+fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    Ok(ConfigResponse {
+        owner_addr: deps.api.human_address(&config.owner_addr)?,
+        subscribers: config
+            .subscribers
+            .iter()
+            .map(|v| deps.api.human_address(v))
+            .collect::<StdResult<Vec<HumanAddr>>>()?,
+    })
+}
+
+fn query_version<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<VersionResponse> {
+    let state: State = read_state(&deps.storage)?;
+    Ok(VersionResponse {
+        version: state.version,
+        updated_height: state.updated_height,
+    })
+}
+
+fn query_addresses<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AddressesResponse> {
+    let addresses = read_addresses(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(name, v)| {
+            Ok(AddressResponse {
+                name,
+                address: deps.api.human_address(&v.address)?,
+                version: v.version,
+            })
+        })
+        .collect::<StdResult<Vec<AddressResponse>>>()?;
+
+    Ok(AddressesResponse { addresses })
+}
+
+fn query_resolve<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    names: Vec<String>,
+) -> StdResult<ResolveResponse> {
+    let state: State = read_state(&deps.storage)?;
+    let addresses = names
+        .iter()
+        .map(|name| match read_address(&deps.storage, name)? {
+            Some(v) => Ok(Some(deps.api.human_address(&v.address)?)),
+            None => Ok(None),
+        })
+        .collect::<StdResult<Vec<Option<HumanAddr>>>>()?;
+
+    Ok(ResolveResponse {
+        version: state.version,
+        addresses,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::StdError;
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner_addr: HumanAddr::from("owner0000"),
+        };
+
+        let env = mock_env("addr0000", &[]);
+
+        // we can just call .unwrap() to assert this was a success
+        let res = init(&mut deps, env.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let value = query_config(&deps).unwrap();
+        assert_eq!(
+            value,
+            ConfigResponse {
+                owner_addr: HumanAddr::from("owner0000"),
+                subscribers: vec![],
+            }
+        );
+
+        let value = query_version(&deps).unwrap();
+        assert_eq!(
+            value,
+            VersionResponse {
+                version: 0,
+                updated_height: env.block.height,
+            }
+        );
+    }
+
+    #[test]
+    fn register_address() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner_addr: HumanAddr::from("owner0000"),
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner_addr: None,
+            subscribers: Some(vec![HumanAddr::from("market"), HumanAddr::from("overseer")]),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        // Unauthorized err
+        let env = mock_env("addr0000", &[]);
+        let msg = HandleMsg::RegisterAddress {
+            name: "oracle".to_string(),
+            address: HumanAddr::from("oracle0000"),
+        };
+        let res = handle(&mut deps, env, msg.clone());
+        match res {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // the subscribers resolve their peers again
+        let mut env = mock_env("owner0000", &[]);
+        env.block.height += 100;
+        let res = handle(&mut deps, env.clone(), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: HumanAddr::from("market"),
+                    send: vec![],
+                    msg: to_binary(&SubscriberHandleMsg::RefreshRegistry {}).unwrap(),
+                }),
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: HumanAddr::from("overseer"),
+                    send: vec![],
+                    msg: to_binary(&SubscriberHandleMsg::RefreshRegistry {}).unwrap(),
+                }),
+            ]
+        );
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "register_address"),
+                log("name", "oracle"),
+                log("address", "oracle0000"),
+                log("version", 1),
+            ]
+        );
+
+        let msg = HandleMsg::RegisterAddress {
+            name: "market".to_string(),
+            address: HumanAddr::from("market0000"),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+        let value = query_version(&deps).unwrap();
+        assert_eq!(
+            value,
+            VersionResponse {
+                version: 2,
+                updated_height: env.block.height,
+            }
+        );
+
+        let value = query_addresses(&deps, None, None).unwrap();
+        assert_eq!(
+            value,
+            AddressesResponse {
+                addresses: vec![
+                    AddressResponse {
+                        name: "market".to_string(),
+                        address: HumanAddr::from("market0000"),
+                        version: 2,
+                    },
+                    AddressResponse {
+                        name: "oracle".to_string(),
+                        address: HumanAddr::from("oracle0000"),
+                        version: 1,
+                    },
+                ],
+            }
+        );
+
+        let value = query_addresses(&deps, Some("market".to_string()), None).unwrap();
+        assert_eq!(value.addresses.len(), 1);
+        assert_eq!(value.addresses[0].name, "oracle");
+
+        // remove the oracle
+        let msg = HandleMsg::RemoveAddress {
+            name: "oracle".to_string(),
+        };
+        let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "No address registered for oracle")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        let value = query_resolve(&deps, vec!["oracle".to_string(), "market".to_string()]).unwrap();
+        assert_eq!(
+            value,
+            ResolveResponse {
+                version: 3,
+                addresses: vec![None, Some(HumanAddr::from("market0000"))],
+            }
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, Order, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
+
+static KEY_CONFIG: &[u8] = b"config";
+static KEY_STATE: &[u8] = b"state";
+
+static PREFIX_ADDRESS: &[u8] = b"address";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner_addr: CanonicalAddr,
+    pub subscribers: Vec<CanonicalAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    pub version: u64,
+    pub updated_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressEntry {
+    pub address: CanonicalAddr,
+    pub version: u64,
+}
+
+pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+pub fn store_state<S: Storage>(storage: &mut S, state: &State) -> StdResult<()> {
+    singleton(storage, KEY_STATE).save(state)
+}
+
+pub fn read_state<S: Storage>(storage: &S) -> StdResult<State> {
+    singleton_read(storage, KEY_STATE).load()
+}
+
+pub fn store_address<S: Storage>(
+    storage: &mut S,
+    name: &str,
+    entry: &AddressEntry,
+) -> StdResult<()> {
+    let mut address_bucket: Bucket<S, AddressEntry> = Bucket::new(PREFIX_ADDRESS, storage);
+    address_bucket.save(name.as_bytes(), entry)
+}
+
+pub fn remove_address<S: Storage>(storage: &mut S, name: &str) {
+    let mut address_bucket: Bucket<S, AddressEntry> = Bucket::new(PREFIX_ADDRESS, storage);
+    address_bucket.remove(name.as_bytes())
+}
+
+pub fn read_address<S: Storage>(storage: &S, name: &str) -> StdResult<Option<AddressEntry>> {
+    let address_bucket: ReadonlyBucket<S, AddressEntry> =
+        ReadonlyBucket::new(PREFIX_ADDRESS, storage);
+    address_bucket.may_load(name.as_bytes())
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+pub fn read_addresses<S: Storage>(
+    storage: &S,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, AddressEntry)>> {
+    let address_bucket: ReadonlyBucket<S, AddressEntry> =
+        ReadonlyBucket::new(PREFIX_ADDRESS, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    address_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((String::from_utf8_lossy(&k).to_string(), v))
+        })
+        .collect()
+}
+
+// this will set the first key after the provided key, by appending a 1 byte
+fn calc_range_start(start_after: Option<String>) -> Option<Vec<u8>> {
+    start_after.map(|name| {
+        let mut v = name.into_bytes();
+        v.push(1);
+        v
+    })
+}
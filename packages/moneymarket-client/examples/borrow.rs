@@ -0,0 +1,64 @@
+//! Borrow stable coins against bLuna collateral and repay the loan
+mod common;
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::HumanAddr;
+
+use common::{deploy, stable, STABLE_DENOM};
+
+fn main() {
+    let deployment = deploy(&[("borrower", 100_000_000)], "10");
+    let app = &deployment.app;
+    let market = &deployment.market;
+
+    // provide liquidity to borrow from
+    let depositor = HumanAddr::from("depositor");
+    app.set_balance(&depositor, &[stable(1_000_000_000)]);
+    app.execute_msgs(
+        &depositor,
+        vec![market.deposit_stable(stable(1_000_000_000)).unwrap()],
+    )
+    .unwrap();
+
+    let borrower = HumanAddr::from("borrower");
+    deployment.provide_collateral(&borrower, 100_000_000);
+
+    let borrow_limit = deployment
+        .overseer
+        .borrow_limit(&app.querier(), borrower.clone(), Some(app.block().time))
+        .unwrap();
+    println!("borrow limit: {}", borrow_limit.borrow_limit);
+
+    app.execute_msgs(
+        &borrower,
+        vec![market
+            .borrow_stable(Uint256::from(400_000_000u64), None)
+            .unwrap()],
+    )
+    .unwrap();
+    println!(
+        "borrowed: {}{}",
+        app.balance(&borrower, STABLE_DENOM),
+        STABLE_DENOM
+    );
+
+    // accrue interest for a while, then repay everything
+    app.next_block(10);
+    let loan_amount = market
+        .borrower_info(&app.querier(), borrower.clone(), Some(app.block().height))
+        .unwrap()
+        .loan_amount;
+    println!("loan with interest: {}", loan_amount);
+
+    app.set_balance(&borrower, &[stable(loan_amount.into())]);
+    app.execute_msgs(
+        &borrower,
+        vec![market.repay_stable(stable(loan_amount.into())).unwrap()],
+    )
+    .unwrap();
+
+    let borrower_info = market
+        .borrower_info(&app.querier(), borrower, None)
+        .unwrap();
+    println!("loan after repay: {}", borrower_info.loan_amount);
+}
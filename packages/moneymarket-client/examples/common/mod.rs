@@ -0,0 +1,282 @@
+//! Deploys the moneymarket contracts on a `MockApp` for the examples
+#![allow(dead_code)]
+
+use std::str::FromStr;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Coin, HumanAddr, Uint128};
+use cw20::Cw20CoinHuman;
+use terraswap::token::InitMsg as TokenInitMsg;
+
+use moneymarket::custody::{BAssetInfo, InitMsg as CustodyInitMsg};
+use moneymarket::interest_model::InitMsg as InterestModelInitMsg;
+use moneymarket::liquidation::InitMsg as LiquidationInitMsg;
+use moneymarket::market::{HandleMsg as MarketHandleMsg, InitMsg as MarketInitMsg};
+use moneymarket::oracle::InitMsg as OracleInitMsg;
+use moneymarket::overseer::{HandleMsg as OverseerHandleMsg, InitMsg as OverseerInitMsg};
+use moneymarket_client::contract_wrapper;
+use moneymarket_client::custody::CustodyContract;
+use moneymarket_client::liquidation::LiquidationContract;
+use moneymarket_client::market::MarketContract;
+use moneymarket_client::mock_app::MockApp;
+use moneymarket_client::oracle::OracleContract;
+use moneymarket_client::overseer::OverseerContract;
+
+pub const STABLE_DENOM: &str = "uusd";
+
+pub struct Deployment {
+    pub app: MockApp,
+    pub owner: HumanAddr,
+    pub market: MarketContract,
+    pub overseer: OverseerContract,
+    pub custody: CustodyContract,
+    pub liquidation: LiquidationContract,
+    pub oracle: OracleContract,
+    pub aterra: HumanAddr,
+    pub bluna: HumanAddr,
+}
+
+pub fn stable(amount: u128) -> Coin {
+    Coin {
+        denom: STABLE_DENOM.to_string(),
+        amount: Uint128(amount),
+    }
+}
+
+/// Instantiates and wires all contracts; `bluna_holders` receive bLuna
+/// and the owner feeds the initial bLuna price
+pub fn deploy(bluna_holders: &[(&str, u128)], bluna_price: &str) -> Deployment {
+    let app = MockApp::new();
+    let owner = HumanAddr::from("owner");
+    app.set_balance(&owner, &[stable(1_000_000)]);
+
+    let token_code = app.store_token_code();
+    let market_code = app.store_code(contract_wrapper!(moneymarket_market::contract));
+    let overseer_code = app.store_code(contract_wrapper!(moneymarket_overseer::contract));
+    let custody_code = app.store_code(contract_wrapper!(moneymarket_custody_bluna::contract));
+    let liquidation_code = app.store_code(contract_wrapper!(moneymarket_liquidation::contract));
+    let oracle_code = app.store_code(contract_wrapper!(moneymarket_oracle::contract));
+    let interest_model_code =
+        app.store_code(contract_wrapper!(moneymarket_interest_model::contract));
+
+    let oracle = app
+        .instantiate(
+            oracle_code,
+            &owner,
+            &OracleInitMsg {
+                owner: owner.clone(),
+                base_asset: STABLE_DENOM.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let interest_model = app
+        .instantiate(
+            interest_model_code,
+            &owner,
+            &InterestModelInitMsg {
+                owner: owner.clone(),
+                base_rate: Decimal256::from_str("0.000000002").unwrap(),
+                interest_multiplier: Decimal256::from_str("0.00000002").unwrap(),
+                kink: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let liquidation = app
+        .instantiate(
+            liquidation_code,
+            &owner,
+            &LiquidationInitMsg {
+                owner: owner.clone(),
+                oracle_contract: oracle.clone(),
+                stable_denom: STABLE_DENOM.to_string(),
+                safe_ratio: Decimal256::percent(80),
+                bid_fee: Decimal256::percent(1),
+                max_premium_rate: Decimal256::percent(30),
+                liquidation_threshold: Uint256::from(200u64),
+                price_timeframe: 60,
+            },
+            &[],
+        )
+        .unwrap();
+
+    // the market instantiates aterra and requires the initial deposit
+    let market = app
+        .instantiate(
+            market_code,
+            &owner,
+            &MarketInitMsg {
+                owner_addr: owner.clone(),
+                stable_denom: STABLE_DENOM.to_string(),
+                aterra_code_id: token_code,
+                anc_emission_rate: Decimal256::one(),
+                max_borrow_factor: Decimal256::one(),
+            },
+            &[stable(1_000_000)],
+        )
+        .unwrap();
+    let market = MarketContract(market);
+
+    let overseer = app
+        .instantiate(
+            overseer_code,
+            &owner,
+            &OverseerInitMsg {
+                owner_addr: owner.clone(),
+                oracle_contract: oracle.clone(),
+                market_contract: market.addr(),
+                liquidation_contract: liquidation.clone(),
+                collector_contract: HumanAddr::from("collector"),
+                stable_denom: STABLE_DENOM.to_string(),
+                epoch_period: 86400,
+                threshold_deposit_rate: Decimal256::permille(3),
+                target_deposit_rate: Decimal256::permille(5),
+                buffer_distribution_factor: Decimal256::percent(20),
+                anc_purchase_factor: Decimal256::percent(20),
+                price_timeframe: 60,
+            },
+            &[],
+        )
+        .unwrap();
+
+    app.execute(
+        &owner,
+        &market.addr(),
+        &MarketHandleMsg::RegisterContracts {
+            overseer_contract: overseer.clone(),
+            interest_model,
+            distribution_model: HumanAddr::from("distribution"),
+            collector_contract: HumanAddr::from("collector"),
+            distributor_contract: HumanAddr::from("distributor"),
+        },
+        &[],
+    )
+    .unwrap();
+    let aterra = market.config(&app.querier()).unwrap().aterra_contract;
+
+    let bluna = app
+        .instantiate(
+            token_code,
+            &owner,
+            &TokenInitMsg {
+                name: "bonded luna".to_string(),
+                symbol: "BLUNA".to_string(),
+                decimals: 6,
+                initial_balances: bluna_holders
+                    .iter()
+                    .map(|(address, amount)| Cw20CoinHuman {
+                        address: HumanAddr::from(*address),
+                        amount: Uint128(*amount),
+                    })
+                    .collect(),
+                mint: None,
+                init_hook: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let custody = app
+        .instantiate(
+            custody_code,
+            &owner,
+            &CustodyInitMsg {
+                owner: owner.clone(),
+                collateral_token: bluna.clone(),
+                overseer_contract: overseer.clone(),
+                market_contract: market.addr(),
+                reward_contract: HumanAddr::from("reward"),
+                liquidation_contract: liquidation.clone(),
+                stable_denom: STABLE_DENOM.to_string(),
+                basset_info: BAssetInfo {
+                    name: "bonded luna".to_string(),
+                    symbol: "BLUNA".to_string(),
+                    decimals: 6,
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+    app.execute(
+        &owner,
+        &overseer,
+        &OverseerHandleMsg::Whitelist {
+            name: "bonded luna".to_string(),
+            symbol: "BLUNA".to_string(),
+            collateral_token: bluna.clone(),
+            custody_contract: custody.clone(),
+            max_ltv: Decimal256::percent(50),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let oracle = OracleContract(oracle);
+    app.execute_msgs(
+        &owner,
+        vec![
+            oracle
+                .register_feeder(bluna.to_string(), owner.clone())
+                .unwrap(),
+            oracle
+                .feed_price(vec![(
+                    bluna.to_string(),
+                    Decimal256::from_str(bluna_price).unwrap(),
+                )])
+                .unwrap(),
+        ],
+    )
+    .unwrap();
+
+    Deployment {
+        app,
+        owner,
+        market,
+        overseer: OverseerContract(overseer),
+        custody: CustodyContract(custody),
+        liquidation: LiquidationContract(liquidation),
+        oracle,
+        aterra,
+        bluna,
+    }
+}
+
+impl Deployment {
+    /// Feeds a new bLuna price; prices older than the
+    /// price timeframe are rejected by the contracts
+    pub fn feed_bluna_price(&self, price: &str) {
+        self.app
+            .execute_msgs(
+                &self.owner,
+                vec![self
+                    .oracle
+                    .feed_price(vec![(
+                        self.bluna.to_string(),
+                        Decimal256::from_str(price).unwrap(),
+                    )])
+                    .unwrap()],
+            )
+            .unwrap();
+    }
+
+    /// Deposits bLuna into custody and locks it as collateral
+    pub fn provide_collateral(&self, borrower: &HumanAddr, amount: u128) {
+        self.app
+            .execute_msgs(
+                borrower,
+                vec![
+                    self.custody
+                        .deposit_collateral(&self.bluna, Uint128(amount))
+                        .unwrap(),
+                    self.overseer
+                        .lock_collateral(vec![(self.bluna.clone(), Uint256::from(amount))])
+                        .unwrap(),
+                ],
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+//! Deposit stable coins into the market and redeem them with aterra
+mod common;
+
+use cosmwasm_std::{HumanAddr, Uint128};
+
+use common::{deploy, stable, STABLE_DENOM};
+
+fn main() {
+    let deployment = deploy(&[], "10");
+    let app = &deployment.app;
+    let market = &deployment.market;
+
+    let depositor = HumanAddr::from("depositor");
+    app.set_balance(&depositor, &[stable(1_000_000_000)]);
+
+    app.execute_msgs(
+        &depositor,
+        vec![market.deposit_stable(stable(1_000_000_000)).unwrap()],
+    )
+    .unwrap();
+
+    let aterra_balance = app.token_balance(&deployment.aterra, &depositor);
+    println!("minted aterra: {}", aterra_balance);
+
+    let epoch_state = market.epoch_state(&app.querier(), None, None).unwrap();
+    println!("exchange rate: {}", epoch_state.exchange_rate);
+
+    // redeem half of the deposit
+    app.next_block(100);
+    app.execute_msgs(
+        &depositor,
+        vec![market
+            .redeem_stable(&deployment.aterra, Uint128(aterra_balance.u128() / 2))
+            .unwrap()],
+    )
+    .unwrap();
+
+    println!(
+        "after redeem: {} aterra, {}{}",
+        app.token_balance(&deployment.aterra, &depositor),
+        app.balance(&depositor, STABLE_DENOM),
+        STABLE_DENOM
+    );
+}
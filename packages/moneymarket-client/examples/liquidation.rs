@@ -0,0 +1,89 @@
+//! Liquidate an undercollateralized loan through a liquidator bid
+mod common;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::HumanAddr;
+
+use common::{deploy, stable, STABLE_DENOM};
+
+fn main() {
+    let deployment = deploy(&[("borrower", 100_000_000)], "10");
+    let app = &deployment.app;
+    let market = &deployment.market;
+
+    let depositor = HumanAddr::from("depositor");
+    app.set_balance(&depositor, &[stable(1_000_000_000)]);
+    app.execute_msgs(
+        &depositor,
+        vec![market.deposit_stable(stable(1_000_000_000)).unwrap()],
+    )
+    .unwrap();
+
+    // borrow close to the limit of 500_000_000
+    let borrower = HumanAddr::from("borrower");
+    deployment.provide_collateral(&borrower, 100_000_000);
+    app.execute_msgs(
+        &borrower,
+        vec![market
+            .borrow_stable(Uint256::from(400_000_000u64), None)
+            .unwrap()],
+    )
+    .unwrap();
+
+    // the liquidator places a bid for bLuna
+    let liquidator = HumanAddr::from("liquidator");
+    app.set_balance(&liquidator, &[stable(1_000_000_000)]);
+    app.execute_msgs(
+        &liquidator,
+        vec![deployment
+            .liquidation
+            .submit_bid(
+                deployment.bluna.clone(),
+                Decimal256::percent(5),
+                stable(1_000_000_000),
+            )
+            .unwrap()],
+    )
+    .unwrap();
+
+    // price drop makes the loan undercollateralized
+    app.next_block(1);
+    deployment.feed_bluna_price("7");
+    let borrow_limit = deployment
+        .overseer
+        .borrow_limit(&app.querier(), borrower.clone(), Some(app.block().time))
+        .unwrap();
+    println!(
+        "borrow limit after price drop: {}",
+        borrow_limit.borrow_limit
+    );
+
+    let logs = app
+        .execute_msgs(
+            &liquidator,
+            vec![deployment
+                .overseer
+                .liquidate_collateral(borrower.clone())
+                .unwrap()],
+        )
+        .unwrap();
+    for log in logs.iter().filter(|l| l.key == "action") {
+        println!("executed: {}", log.value);
+    }
+
+    let collaterals = deployment
+        .overseer
+        .collaterals(&app.querier(), borrower.clone())
+        .unwrap();
+    let borrower_info = market
+        .borrower_info(&app.querier(), borrower, None)
+        .unwrap();
+    println!("remaining collaterals: {:?}", collaterals.collaterals);
+    println!("remaining loan: {}", borrower_info.loan_amount);
+    println!(
+        "liquidator received {} bLuna, {}{} left in wallet",
+        app.token_balance(&deployment.bluna, &liquidator),
+        app.balance(&liquidator, STABLE_DENOM),
+        STABLE_DENOM
+    );
+}
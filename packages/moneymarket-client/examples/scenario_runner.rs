@@ -0,0 +1,224 @@
+//! Replay a scripted scenario (deposits, borrows, price moves,
+//! liquidations) from a json file and print the resulting state:
+//!
+//! cargo run -p moneymarket-client --example scenario_runner -- <scenario.json>
+//!
+//! Runs `examples/scenarios/liquidation.json` when no file is given
+mod common;
+
+use std::env;
+use std::fs;
+use std::str::FromStr;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{from_slice, CosmosMsg, HumanAddr, StdResult, Uint128};
+use serde::Deserialize;
+
+use common::{deploy, stable, Deployment, STABLE_DENOM};
+
+const DEFAULT_SCENARIO: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/examples/scenarios/liquidation.json"
+);
+
+#[derive(Deserialize)]
+struct Scenario {
+    /// initial bLuna price in stable denom
+    bluna_price: String,
+    /// (address, amount) receiving bLuna at deployment
+    bluna_holders: Vec<(HumanAddr, Uint128)>,
+    steps: Vec<Step>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Step {
+    SetBalance {
+        address: HumanAddr,
+        amount: Uint128,
+    },
+    Deposit {
+        sender: HumanAddr,
+        amount: Uint128,
+    },
+    Redeem {
+        sender: HumanAddr,
+        amount: Uint128,
+    },
+    ProvideCollateral {
+        borrower: HumanAddr,
+        amount: Uint128,
+    },
+    Borrow {
+        borrower: HumanAddr,
+        amount: Uint256,
+    },
+    Repay {
+        borrower: HumanAddr,
+        amount: Uint128,
+    },
+    SubmitBid {
+        bidder: HumanAddr,
+        premium_rate: Decimal256,
+        amount: Uint128,
+    },
+    Liquidate {
+        liquidator: HumanAddr,
+        borrower: HumanAddr,
+    },
+    FeedPrice {
+        price: String,
+    },
+    NextBlock {
+        blocks: u64,
+    },
+    EpochOperations {},
+    PrintState {
+        addresses: Vec<HumanAddr>,
+    },
+}
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_SCENARIO.to_string());
+    let scenario: Scenario =
+        from_slice(&fs::read(&path).expect("cannot read the scenario file")).unwrap();
+
+    let bluna_holders: Vec<(&str, u128)> = scenario
+        .bluna_holders
+        .iter()
+        .map(|(address, amount)| (address.as_str(), amount.u128()))
+        .collect();
+    let deployment = deploy(&bluna_holders, &scenario.bluna_price);
+
+    for (i, step) in scenario.steps.into_iter().enumerate() {
+        let block = deployment.app.block();
+        print!("#{} height {}: ", i, block.height);
+        match run_step(&deployment, step) {
+            Ok(actions) => println!("ok {}", actions.join(", ")),
+            // failed steps are reverted, the scenario goes on
+            Err(e) => println!("failed {}", e),
+        }
+    }
+}
+
+/// Runs the step and returns the executed contract actions
+fn run_step(deployment: &Deployment, step: Step) -> StdResult<Vec<String>> {
+    let app = &deployment.app;
+    let market = &deployment.market;
+
+    let (sender, msgs): (HumanAddr, Vec<CosmosMsg>) = match step {
+        Step::SetBalance { address, amount } => {
+            app.set_balance(&address, &[stable(amount.u128())]);
+            return Ok(vec!["set_balance".to_string()]);
+        }
+        Step::Deposit { sender, amount } => {
+            let msg = market.deposit_stable(stable(amount.u128()))?;
+            (sender, vec![msg])
+        }
+        Step::Redeem { sender, amount } => {
+            let msg = market.redeem_stable(&deployment.aterra, amount)?;
+            (sender, vec![msg])
+        }
+        Step::ProvideCollateral { borrower, amount } => {
+            deployment.provide_collateral(&borrower, amount.u128());
+            return Ok(vec!["provide_collateral".to_string()]);
+        }
+        Step::Borrow { borrower, amount } => {
+            let msg = market.borrow_stable(amount, None)?;
+            (borrower, vec![msg])
+        }
+        Step::Repay { borrower, amount } => {
+            let msg = market.repay_stable(stable(amount.u128()))?;
+            (borrower, vec![msg])
+        }
+        Step::SubmitBid {
+            bidder,
+            premium_rate,
+            amount,
+        } => {
+            let msg = deployment.liquidation.submit_bid(
+                deployment.bluna.clone(),
+                premium_rate,
+                stable(amount.u128()),
+            )?;
+            (bidder, vec![msg])
+        }
+        Step::Liquidate {
+            liquidator,
+            borrower,
+        } => {
+            let msg = deployment.overseer.liquidate_collateral(borrower)?;
+            (liquidator, vec![msg])
+        }
+        Step::FeedPrice { price } => {
+            Decimal256::from_str(&price)?;
+            deployment.feed_bluna_price(&price);
+            return Ok(vec!["feed_price".to_string()]);
+        }
+        Step::NextBlock { blocks } => {
+            app.next_block(blocks);
+            return Ok(vec!["next_block".to_string()]);
+        }
+        Step::EpochOperations {} => {
+            let msg = deployment.overseer.execute_epoch_operations()?;
+            (deployment.owner.clone(), vec![msg])
+        }
+        Step::PrintState { addresses } => {
+            print_state(deployment, &addresses)?;
+            return Ok(vec!["print_state".to_string()]);
+        }
+    };
+
+    let logs = app.execute_msgs(&sender, msgs)?;
+    Ok(logs
+        .into_iter()
+        .filter(|l| l.key == "action")
+        .map(|l| l.value)
+        .collect())
+}
+
+fn print_state(deployment: &Deployment, addresses: &[HumanAddr]) -> StdResult<()> {
+    let app = &deployment.app;
+    let querier = app.querier();
+    let height = Some(app.block().height);
+
+    let state = deployment.market.state(&querier, height)?;
+    let epoch_state = deployment.market.epoch_state(&querier, height, None)?;
+    println!();
+    println!(
+        "  market: liabilities {}, reserves {}, balance {}{}",
+        state.total_liabilities,
+        state.total_reserves,
+        app.balance(&deployment.market.addr(), STABLE_DENOM),
+        STABLE_DENOM
+    );
+    println!(
+        "  aterra: supply {}, exchange rate {}",
+        epoch_state.aterra_supply, epoch_state.exchange_rate
+    );
+
+    for address in addresses {
+        let loan_amount = deployment
+            .market
+            .borrower_info(&querier, address.clone(), height)?
+            .loan_amount;
+        let collaterals = deployment
+            .overseer
+            .collaterals(&querier, address.clone())?
+            .collaterals;
+        println!(
+            "  {}: {}{}, {} aterra, {} bLuna, loan {}, collaterals {:?}",
+            address,
+            app.balance(address, STABLE_DENOM),
+            STABLE_DENOM,
+            app.token_balance(&deployment.aterra, address),
+            app.token_balance(&deployment.bluna, address),
+            loan_amount,
+            collaterals
+        );
+    }
+
+    Ok(())
+}
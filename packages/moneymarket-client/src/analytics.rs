@@ -0,0 +1,40 @@
+use cosmwasm_std::{HumanAddr, Querier, StdResult};
+
+use crate::query_msg;
+use moneymarket::analytics::{ConfigResponse, EpochKpisResponse, QueryMsg};
+
+/// Analytics contract address with typed queriers
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalyticsContract(pub HumanAddr);
+
+impl AnalyticsContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn epoch_kpis<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_height: Option<u64>,
+        end_height: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<EpochKpisResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::EpochKpis {
+                start_height,
+                end_height,
+                limit,
+            },
+        )
+    }
+}
@@ -0,0 +1,73 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{Coin, CosmosMsg, HumanAddr, Querier, StdResult};
+
+use crate::{execute_msg, query_msg};
+use moneymarket::bid_pool::{
+    BidSlot, ConfigResponse, HandleMsg, PoolResponse, QueryMsg, SharesResponse, StrategyResponse,
+};
+
+/// Bid pool contract address with typed message builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct BidPoolContract(pub HumanAddr);
+
+impl BidPoolContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Execute messages
+    ////////////////////
+
+    pub fn update_strategy(&self, slots: Vec<BidSlot>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::UpdateStrategy { slots }, vec![])
+    }
+
+    pub fn deposit(&self, deposit: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::Deposit {}, vec![deposit])
+    }
+
+    pub fn withdraw(&self, shares: Uint256) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::Withdraw { shares }, vec![])
+    }
+
+    pub fn roll_bids(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RollBids {}, vec![])
+    }
+
+    pub fn liquidate(&self, borrower: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::Liquidate { borrower }, vec![])
+    }
+
+    pub fn sell_collateral(&self, collateral_token: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::SellCollateral { collateral_token },
+            vec![],
+        )
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn strategy<Q: Querier>(&self, querier: &Q) -> StdResult<StrategyResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Strategy {})
+    }
+
+    pub fn pool<Q: Querier>(&self, querier: &Q) -> StdResult<PoolResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Pool {})
+    }
+
+    pub fn shares<Q: Querier>(
+        &self,
+        querier: &Q,
+        depositor: HumanAddr,
+    ) -> StdResult<SharesResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Shares { depositor })
+    }
+}
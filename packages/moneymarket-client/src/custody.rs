@@ -0,0 +1,97 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{CosmosMsg, HumanAddr, Querier, StdResult, Uint128};
+
+use crate::{execute_msg, query_msg, send_token_msg};
+use moneymarket::custody::{
+    BorrowerResponse, BorrowersResponse, ConfigResponse, Cw20HookMsg, HandleMsg, QueryMsg,
+    RewardClaimerResponse, ShareAccountingResponse, ShareConversionResponse,
+};
+
+/// Custody contract address with typed message builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustodyContract(pub HumanAddr);
+
+impl CustodyContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Execute messages
+    ////////////////////
+
+    /// Deposit is triggered by sending the collateral token to the custody
+    pub fn deposit_collateral(
+        &self,
+        collateral_token: &HumanAddr,
+        amount: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        send_token_msg(
+            collateral_token,
+            &self.0,
+            amount,
+            &Cw20HookMsg::DepositCollateral {},
+        )
+    }
+
+    pub fn withdraw_collateral(&self, amount: Option<Uint256>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::WithdrawCollateral { amount }, vec![])
+    }
+
+    pub fn refresh_protocol_mode(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RefreshProtocolMode {}, vec![])
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn borrower<Q: Querier>(
+        &self,
+        querier: &Q,
+        address: HumanAddr,
+    ) -> StdResult<BorrowerResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Borrower { address })
+    }
+
+    pub fn borrowers<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<BorrowersResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::Borrowers { start_after, limit },
+        )
+    }
+
+    pub fn reward_claimer<Q: Querier>(&self, querier: &Q) -> StdResult<RewardClaimerResponse> {
+        query_msg(querier, &self.0, &QueryMsg::RewardClaimer {})
+    }
+
+    pub fn share_accounting<Q: Querier>(&self, querier: &Q) -> StdResult<ShareAccountingResponse> {
+        query_msg(querier, &self.0, &QueryMsg::ShareAccounting {})
+    }
+
+    pub fn shares_to_amount<Q: Querier>(
+        &self,
+        querier: &Q,
+        shares: Uint256,
+    ) -> StdResult<ShareConversionResponse> {
+        query_msg(querier, &self.0, &QueryMsg::SharesToAmount { shares })
+    }
+
+    pub fn amount_to_shares<Q: Querier>(
+        &self,
+        querier: &Q,
+        amount: Uint256,
+    ) -> StdResult<ShareConversionResponse> {
+        query_msg(querier, &self.0, &QueryMsg::AmountToShares { amount })
+    }
+}
@@ -0,0 +1,59 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{HumanAddr, Querier, StdResult};
+
+use crate::query_msg;
+use moneymarket::interest_model::{
+    BorrowRateResponse, ConfigResponse, DepositRateResponse, QueryMsg,
+};
+
+/// Interest model contract address with typed queries
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterestModelContract(pub HumanAddr);
+
+impl InterestModelContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn borrow_rate<Q: Querier>(
+        &self,
+        querier: &Q,
+        market_balance: Uint256,
+        total_liabilities: Decimal256,
+        total_reserves: Decimal256,
+    ) -> StdResult<BorrowRateResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::BorrowRate {
+                market_balance,
+                total_liabilities,
+                total_reserves,
+            },
+        )
+    }
+
+    pub fn deposit_rate<Q: Querier>(
+        &self,
+        querier: &Q,
+        market_balance: Uint256,
+        total_liabilities: Decimal256,
+        total_reserves: Decimal256,
+        reserve_factor: Decimal256,
+    ) -> StdResult<DepositRateResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::DepositRate {
+                market_balance,
+                total_liabilities,
+                total_reserves,
+                reserve_factor,
+            },
+        )
+    }
+}
@@ -0,0 +1,74 @@
+//! Typed message builders and queriers for the moneymarket contracts.
+//!
+//! Each contract is wrapped by a thin address newtype; execute builders
+//! return ready to dispatch `CosmosMsg`s and query helpers decode the
+//! contract responses, so bots and integration tests do not need to
+//! assemble raw json messages.
+
+pub mod analytics;
+pub mod bid_pool;
+pub mod custody;
+pub mod interest_model;
+pub mod liquidation;
+pub mod market;
+pub mod oracle;
+pub mod overseer;
+pub mod protocol_controller;
+pub mod registry;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mock_app;
+
+#[cfg(test)]
+mod testing;
+
+use cosmwasm_std::{
+    to_binary, Coin, CosmosMsg, HumanAddr, Querier, QueryRequest, StdResult, Uint128, WasmMsg,
+    WasmQuery,
+};
+use cw20::Cw20HandleMsg;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Builds a `WasmMsg::Execute` for the given contract message
+pub fn execute_msg<T: Serialize>(
+    contract_addr: &HumanAddr,
+    msg: &T,
+    send: Vec<Coin>,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: contract_addr.clone(),
+        msg: to_binary(msg)?,
+        send,
+    }))
+}
+
+/// Builds a cw20 `Send` of `amount` tokens to `contract` with the given hook message
+pub fn send_token_msg<T: Serialize>(
+    token: &HumanAddr,
+    contract: &HumanAddr,
+    amount: Uint128,
+    hook_msg: &T,
+) -> StdResult<CosmosMsg> {
+    execute_msg(
+        token,
+        &Cw20HandleMsg::Send {
+            contract: contract.clone(),
+            amount,
+            msg: Some(to_binary(hook_msg)?),
+        },
+        vec![],
+    )
+}
+
+/// Runs a smart query against the given contract and decodes the response
+pub fn query_msg<Q: Querier, T: Serialize, R: DeserializeOwned>(
+    querier: &Q,
+    contract_addr: &HumanAddr,
+    msg: &T,
+) -> StdResult<R> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: contract_addr.clone(),
+        msg: to_binary(msg)?,
+    }))
+}
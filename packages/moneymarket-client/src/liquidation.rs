@@ -0,0 +1,232 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Coin, CosmosMsg, HumanAddr, Querier, StdResult};
+
+use crate::{execute_msg, query_msg};
+use moneymarket::liquidation::{
+    AuctionsResponse, BidResponse, BidsResponse, CollateralStreamResponse, ConfigResponse,
+    ContinuationTicketResponse, ContinuationTicketsResponse, HandleMsg, LiquidationAmountResponse,
+    LiquidatorsResponse, MetricsResponse, QueryMsg,
+};
+
+/// Liquidation contract address with typed message builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiquidationContract(pub HumanAddr);
+
+impl LiquidationContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Execute messages
+    ////////////////////
+
+    pub fn submit_bid(
+        &self,
+        collateral_token: HumanAddr,
+        premium_rate: Decimal256,
+        bid: Coin,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::SubmitBid {
+                collateral_token,
+                premium_rate,
+            },
+            vec![bid],
+        )
+    }
+
+    pub fn retract_bid(
+        &self,
+        collateral_token: HumanAddr,
+        amount: Option<Uint256>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::RetractBid {
+                collateral_token,
+                amount,
+            },
+            vec![],
+        )
+    }
+
+    pub fn refresh_protocol_mode(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RefreshProtocolMode {}, vec![])
+    }
+
+    pub fn claim_streamed_collateral(
+        &self,
+        collateral_token: HumanAddr,
+        bidder: Option<HumanAddr>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::ClaimStreamedCollateral {
+                collateral_token,
+                bidder,
+            },
+            vec![],
+        )
+    }
+
+    pub fn buy_auction_collateral(&self, auction_id: u64, payment: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::BuyAuctionCollateral { auction_id },
+            vec![payment],
+        )
+    }
+
+    pub fn resume_liquidation(&self, ticket_id: u64) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::ResumeLiquidation { ticket_id }, vec![])
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn liquidation_amount<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrow_amount: Uint256,
+        borrow_limit: Uint256,
+        collaterals: Vec<(HumanAddr, Uint256)>,
+        collateral_prices: Vec<Decimal256>,
+    ) -> StdResult<LiquidationAmountResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::LiquidationAmount {
+                borrow_amount,
+                borrow_limit,
+                collaterals,
+                collateral_prices,
+            },
+        )
+    }
+
+    pub fn bid<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: HumanAddr,
+        bidder: HumanAddr,
+    ) -> StdResult<BidResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::Bid {
+                collateral_token,
+                bidder,
+            },
+        )
+    }
+
+    pub fn bids_by_user<Q: Querier>(
+        &self,
+        querier: &Q,
+        bidder: HumanAddr,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<BidsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::BidsByUser {
+                bidder,
+                start_after,
+                limit,
+            },
+        )
+    }
+
+    pub fn metrics<Q: Querier>(&self, querier: &Q) -> StdResult<MetricsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Metrics {})
+    }
+
+    pub fn liquidators<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<LiquidatorsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::Liquidators { start_after, limit },
+        )
+    }
+
+    pub fn collateral_stream<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: HumanAddr,
+        bidder: HumanAddr,
+        block_height: Option<u64>,
+    ) -> StdResult<CollateralStreamResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::CollateralStream {
+                collateral_token,
+                bidder,
+                block_height,
+            },
+        )
+    }
+
+    pub fn auctions<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: Option<HumanAddr>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        block_height: Option<u64>,
+    ) -> StdResult<AuctionsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::Auctions {
+                collateral_token,
+                start_after,
+                limit,
+                block_height,
+            },
+        )
+    }
+
+    pub fn continuation_ticket<Q: Querier>(
+        &self,
+        querier: &Q,
+        ticket_id: u64,
+    ) -> StdResult<ContinuationTicketResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::ContinuationTicket { ticket_id },
+        )
+    }
+
+    pub fn continuation_tickets<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: Option<HumanAddr>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ContinuationTicketsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::ContinuationTickets {
+                collateral_token,
+                start_after,
+                limit,
+            },
+        )
+    }
+}
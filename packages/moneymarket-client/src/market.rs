@@ -0,0 +1,906 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Binary, Coin, CosmosMsg, HumanAddr, Querier, StdResult, Uint128};
+
+use crate::{execute_msg, query_msg, send_token_msg};
+use moneymarket::market::{
+    ATerraExchangeRateResponse, AmortizingLoanResponse, AttestationContractResponse,
+    BootstrapModeResponse, BorrowerInfoResponse, BorrowerInfosResponse, ConfigResponse,
+    CreditLineResponse, Cw20HookMsg, Cw20StablesResponse, DebtAuctionParamsResponse,
+    DebtAuctionResponse, DepositorInfoResponse, DepositorsResponse, DonationsResponse,
+    DustForgivenessResponse, EpochStateResponse, FeeExemptRedeemersResponse, FlashLoanResponse,
+    FlashMintResponse, FlowLimitResponse, HandleMsg, IdleDeploymentResponse,
+    InvariantChecksResponse, KeeperBountyResponse, LoanBreakdownResponse, LoanParamsResponse,
+    OperationModeResponse, QueryMsg, QueuedRedemptionsResponse, RateModeResponse,
+    RateSnapshotResponse, RedemptionFeeResponse, RegistryResponse, ShadowInterestModelResponse,
+    SimulateDepositResponse, SimulateRedeemResponse, StableDenomsResponse,
+    StableRateParamsResponse, StateHistoryResponse, StateResponse, StateSnapshotResponse,
+    TaxParamsResponse,
+};
+use moneymarket::ownership::OwnershipProposalResponse;
+
+/// Market contract address with typed message builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketContract(pub HumanAddr);
+
+impl MarketContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Execute messages
+    ////////////////////
+
+    pub fn deposit_stable(&self, deposit: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::DepositStable {
+                recipient: None,
+                amount: None,
+                decimals: None,
+            },
+            vec![deposit],
+        )
+    }
+
+    /// Deposit and have the aterra minted to `recipient`
+    pub fn deposit_stable_for(&self, deposit: Coin, recipient: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::DepositStable {
+                recipient: Some(recipient),
+                amount: None,
+                decimals: None,
+            },
+            vec![deposit],
+        )
+    }
+
+    /// Donate the sent stable to the depositors as yield
+    pub fn donate_to_depositors(&self, donation: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::DonateToDepositors {
+                amount: Uint256::from(donation.amount),
+            },
+            vec![donation],
+        )
+    }
+
+    /// Redeem is triggered by sending aterra to the market
+    pub fn redeem_stable(
+        &self,
+        aterra_contract: &HumanAddr,
+        amount: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        send_token_msg(
+            aterra_contract,
+            &self.0,
+            amount,
+            &Cw20HookMsg::RedeemStable {},
+        )
+    }
+
+    pub fn redeem_stable_or_queue(
+        &self,
+        aterra_contract: &HumanAddr,
+        amount: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        send_token_msg(
+            aterra_contract,
+            &self.0,
+            amount,
+            &Cw20HookMsg::RedeemStableOrQueue {},
+        )
+    }
+
+    pub fn claim_redemption(&self, id: u64) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::ClaimRedemption { id }, vec![])
+    }
+
+    /// The aterra is burned from the allowance given to the market
+    pub fn redeem_stable_exact(&self, stable_amount: Uint256) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::RedeemStableExact { stable_amount },
+            vec![],
+        )
+    }
+
+    /// CW20 deposits are triggered by sending the stable to the market
+    pub fn deposit_cw20_stable(&self, token: &HumanAddr, amount: Uint128) -> StdResult<CosmosMsg> {
+        send_token_msg(token, &self.0, amount, &Cw20HookMsg::DepositStable {})
+    }
+
+    pub fn swap_cw20_stable(&self, token: HumanAddr, payment: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::SwapCw20Stable { token }, vec![payment])
+    }
+
+    pub fn borrow_stable(
+        &self,
+        borrow_amount: Uint256,
+        to: Option<HumanAddr>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::BorrowStable {
+                borrow_amount,
+                to,
+                deposit: None,
+                decimals: None,
+            },
+            vec![],
+        )
+    }
+
+    /// Borrow and deposit the borrowed stable in the same
+    /// message, minting aterra to `to` or the borrower
+    pub fn borrow_stable_into_aterra(
+        &self,
+        borrow_amount: Uint256,
+        to: Option<HumanAddr>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::BorrowStable {
+                borrow_amount,
+                to,
+                deposit: Some(true),
+                decimals: None,
+            },
+            vec![],
+        )
+    }
+
+    pub fn repay_stable(&self, repay: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RepayStable {}, vec![repay])
+    }
+
+    pub fn repay_stable_for(&self, borrower: HumanAddr, repay: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::RepayStableFor { borrower },
+            vec![repay],
+        )
+    }
+
+    pub fn swap_rate_mode(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::SwapRateMode {}, vec![])
+    }
+
+    pub fn claim_rewards(&self, to: Option<HumanAddr>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::ClaimRewards { to }, vec![])
+    }
+
+    pub fn open_loan(
+        &self,
+        amount: Uint256,
+        installments: u32,
+        to: Option<HumanAddr>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::OpenLoan {
+                amount,
+                installments,
+                to,
+            },
+            vec![],
+        )
+    }
+
+    pub fn repay_installment(&self, repay: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RepayInstallment {}, vec![repay])
+    }
+
+    pub fn payoff_loan(&self, repay: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::PayoffLoan {}, vec![repay])
+    }
+
+    pub fn bid_debt_auction(&self, bid: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::BidDebtAuction {}, vec![bid])
+    }
+
+    pub fn poke_position(&self, borrower: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::PokePosition { borrower }, vec![])
+    }
+
+    pub fn rebalance_stable_rate(&self, borrower: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::RebalanceStableRate { borrower },
+            vec![],
+        )
+    }
+
+    pub fn refresh_protocol_mode(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RefreshProtocolMode {}, vec![])
+    }
+
+    pub fn refresh_registry(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RefreshRegistry {}, vec![])
+    }
+
+    pub fn draw_credit(&self, amount: Uint256, to: Option<HumanAddr>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::DrawCredit { amount, to }, vec![])
+    }
+
+    pub fn repay_credit(&self, strategy: Option<HumanAddr>, repay: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RepayCredit { strategy }, vec![repay])
+    }
+
+    /// Collateral is posted by sending aterra to the market
+    pub fn post_credit_collateral(
+        &self,
+        aterra_contract: &HumanAddr,
+        amount: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        send_token_msg(
+            aterra_contract,
+            &self.0,
+            amount,
+            &Cw20HookMsg::PostCreditCollateral {},
+        )
+    }
+
+    pub fn withdraw_credit_collateral(&self, amount: Uint256) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::WithdrawCreditCollateral { amount },
+            vec![],
+        )
+    }
+
+    pub fn flash_mint(&self, amount: Uint256, msg: Binary) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::FlashMint { amount, msg }, vec![])
+    }
+
+    pub fn flash_loan(&self, amount: Uint256, msg: Binary) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::FlashLoan { amount, msg }, vec![])
+    }
+
+    /// Repayment of the flash loan, sent from its callback
+    pub fn repay_flash_loan(&self, repay: Coin) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RepayFlashLoan {}, vec![repay])
+    }
+
+    pub fn deploy_idle_stable(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::DeployIdleStable {}, vec![])
+    }
+
+    pub fn recall_idle_stable(&self, amount: Option<Uint256>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RecallIdleStable { amount }, vec![])
+    }
+
+    pub fn refresh_tax_params(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RefreshTaxParams {}, vec![])
+    }
+
+    pub fn prune_closed_positions(&self, limit: Option<u32>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::PruneClosedPositions { limit }, vec![])
+    }
+
+    pub fn update_reward_indexes(
+        &self,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateRewardIndexes { start_after, limit },
+            vec![],
+        )
+    }
+
+    /// Flash minted aterra is repaid by sending it back to the market
+    pub fn repay_flash_mint(
+        &self,
+        aterra_contract: &HumanAddr,
+        amount: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        send_token_msg(
+            aterra_contract,
+            &self.0,
+            amount,
+            &Cw20HookMsg::RepayFlashMint {},
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_config(
+        &self,
+        max_borrow_factor: Option<Decimal256>,
+        interest_model: Option<HumanAddr>,
+        distribution_model: Option<HumanAddr>,
+        global_borrow_cap: Option<Uint256>,
+        per_account_borrow_cap: Option<Uint256>,
+        min_deposit_amount: Option<Uint256>,
+        min_redeem_amount: Option<Uint256>,
+        flow_window: Option<u64>,
+        max_net_flow_ratio: Option<Decimal256>,
+        guardian: Option<HumanAddr>,
+        reserve_factor: Option<Decimal256>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateConfig {
+                max_borrow_factor,
+                interest_model,
+                distribution_model,
+                global_borrow_cap,
+                per_account_borrow_cap,
+                min_deposit_amount,
+                min_redeem_amount,
+                flow_window,
+                max_net_flow_ratio,
+                guardian,
+                reserve_factor,
+            },
+            vec![],
+        )
+    }
+
+    pub fn propose_new_owner(
+        &self,
+        new_owner: HumanAddr,
+        expiry_height: u64,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::ProposeNewOwner {
+                new_owner,
+                expiry_height,
+            },
+            vec![],
+        )
+    }
+
+    pub fn accept_ownership(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::AcceptOwnership {}, vec![])
+    }
+
+    pub fn withdraw_reserves(&self, amount: Uint256, recipient: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::WithdrawReserves { amount, recipient },
+            vec![],
+        )
+    }
+
+    pub fn update_dust_forgiveness(&self, max_amount: Uint256) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateDustForgiveness { max_amount },
+            vec![],
+        )
+    }
+
+    pub fn update_stable_rate_params(
+        &self,
+        premium: Decimal256,
+        rebalance_threshold: Decimal256,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateStableRateParams {
+                premium,
+                rebalance_threshold,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_redemption_fee(
+        &self,
+        free_ratio: Decimal256,
+        max_fee_rate: Decimal256,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateRedemptionFee {
+                free_ratio,
+                max_fee_rate,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_fee_exempt_redeemer(
+        &self,
+        redeemer: HumanAddr,
+        exempt: bool,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateFeeExemptRedeemer { redeemer, exempt },
+            vec![],
+        )
+    }
+
+    pub fn update_flash_mint_fee(&self, fee_rate: Decimal256) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::UpdateFlashMintFee { fee_rate }, vec![])
+    }
+
+    pub fn update_idle_deployment(
+        &self,
+        venue: HumanAddr,
+        utilization_threshold: Decimal256,
+        max_deploy_ratio: Decimal256,
+        cap: Uint256,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateIdleDeployment {
+                venue,
+                utilization_threshold,
+                max_deploy_ratio,
+                cap,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_invariant_checks(&self, enabled: bool) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateInvariantChecks { enabled },
+            vec![],
+        )
+    }
+
+    pub fn override_flow_limit(&self, enabled: bool) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::OverrideFlowLimit { enabled }, vec![])
+    }
+
+    pub fn enable_time_accrual(&self, seconds_per_block: u64) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::EnableTimeAccrual { seconds_per_block },
+            vec![],
+        )
+    }
+
+    pub fn set_attestation_contract(
+        &self,
+        attestation_contract: Option<HumanAddr>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::SetAttestationContract {
+                attestation_contract,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_bootstrap_mode(
+        &self,
+        end_height: Option<u64>,
+        tvl_threshold: Option<Uint256>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateBootstrapMode {
+                end_height,
+                tvl_threshold,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_shadow_interest_model(
+        &self,
+        interest_model: HumanAddr,
+        trial_blocks: u64,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateShadowInterestModel {
+                interest_model,
+                trial_blocks,
+            },
+            vec![],
+        )
+    }
+
+    pub fn promote_shadow_interest_model(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::PromoteShadowInterestModel {}, vec![])
+    }
+
+    /// Guardian only
+    pub fn update_operation_mode(
+        &self,
+        deposits_enabled: Option<bool>,
+        redemptions_enabled: Option<bool>,
+        borrows_enabled: Option<bool>,
+        repayments_enabled: Option<bool>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateOperationMode {
+                deposits_enabled,
+                redemptions_enabled,
+                borrows_enabled,
+                repayments_enabled,
+            },
+            vec![],
+        )
+    }
+
+    /// Guardian of the config or owner
+    pub fn set_pause(
+        &self,
+        deposit: Option<bool>,
+        redeem: Option<bool>,
+        borrow: Option<bool>,
+        repay: Option<bool>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::SetPause {
+                deposit,
+                redeem,
+                borrow,
+                repay,
+            },
+            vec![],
+        )
+    }
+
+    /// Owner operations applied atomically, e.g. as one multisig proposal
+    pub fn admin_batch(&self, ops: Vec<HandleMsg>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::AdminBatch { ops }, vec![])
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn ownership_proposal<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<OwnershipProposalResponse> {
+        query_msg(querier, &self.0, &QueryMsg::OwnershipProposal {})
+    }
+
+    pub fn state<Q: Querier>(
+        &self,
+        querier: &Q,
+        block_height: Option<u64>,
+    ) -> StdResult<StateResponse> {
+        query_msg(querier, &self.0, &QueryMsg::State { block_height })
+    }
+
+    pub fn epoch_state<Q: Querier>(
+        &self,
+        querier: &Q,
+        block_height: Option<u64>,
+        distributed_interest: Option<Uint256>,
+    ) -> StdResult<EpochStateResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::EpochState {
+                block_height,
+                distributed_interest,
+            },
+        )
+    }
+
+    pub fn borrower_info<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+        block_height: Option<u64>,
+    ) -> StdResult<BorrowerInfoResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::BorrowerInfo {
+                borrower,
+                block_height,
+            },
+        )
+    }
+
+    pub fn borrower_infos<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+        min_liability: Option<Uint256>,
+        only_liquidatable: Option<bool>,
+    ) -> StdResult<BorrowerInfosResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::BorrowerInfos {
+                start_after,
+                limit,
+                min_liability,
+                only_liquidatable,
+            },
+        )
+    }
+
+    pub fn redemption_fee<Q: Querier>(&self, querier: &Q) -> StdResult<RedemptionFeeResponse> {
+        query_msg(querier, &self.0, &QueryMsg::RedemptionFee {})
+    }
+
+    pub fn fee_exempt_redeemers<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<FeeExemptRedeemersResponse> {
+        query_msg(querier, &self.0, &QueryMsg::FeeExemptRedeemers {})
+    }
+
+    pub fn queued_redemptions<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<QueuedRedemptionsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::QueuedRedemptions { start_after, limit },
+        )
+    }
+
+    pub fn depositor_info<Q: Querier>(
+        &self,
+        querier: &Q,
+        depositor: HumanAddr,
+    ) -> StdResult<DepositorInfoResponse> {
+        query_msg(querier, &self.0, &QueryMsg::DepositorInfo { depositor })
+    }
+
+    pub fn depositor<Q: Querier>(
+        &self,
+        querier: &Q,
+        address: HumanAddr,
+    ) -> StdResult<DepositorInfoResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Depositor { address })
+    }
+
+    pub fn depositors<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<DepositorsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::Depositors { start_after, limit },
+        )
+    }
+
+    pub fn credit_line<Q: Querier>(
+        &self,
+        querier: &Q,
+        strategy: HumanAddr,
+        block_height: Option<u64>,
+    ) -> StdResult<CreditLineResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::CreditLine {
+                strategy,
+                block_height,
+            },
+        )
+    }
+
+    pub fn flash_mint_state<Q: Querier>(&self, querier: &Q) -> StdResult<FlashMintResponse> {
+        query_msg(querier, &self.0, &QueryMsg::FlashMint {})
+    }
+
+    pub fn flash_loan_state<Q: Querier>(&self, querier: &Q) -> StdResult<FlashLoanResponse> {
+        query_msg(querier, &self.0, &QueryMsg::FlashLoan {})
+    }
+
+    pub fn idle_deployment<Q: Querier>(&self, querier: &Q) -> StdResult<IdleDeploymentResponse> {
+        query_msg(querier, &self.0, &QueryMsg::IdleDeployment {})
+    }
+
+    pub fn tax_params<Q: Querier>(&self, querier: &Q) -> StdResult<TaxParamsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::TaxParams {})
+    }
+
+    pub fn operation_mode<Q: Querier>(&self, querier: &Q) -> StdResult<OperationModeResponse> {
+        query_msg(querier, &self.0, &QueryMsg::OperationMode {})
+    }
+
+    pub fn registry<Q: Querier>(&self, querier: &Q) -> StdResult<RegistryResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Registry {})
+    }
+
+    pub fn loan_params<Q: Querier>(&self, querier: &Q) -> StdResult<LoanParamsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::LoanParams {})
+    }
+
+    pub fn amortizing_loan<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+        block_height: u64,
+    ) -> StdResult<AmortizingLoanResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::AmortizingLoan {
+                borrower,
+                block_height,
+            },
+        )
+    }
+
+    pub fn debt_auction_params<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<DebtAuctionParamsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::DebtAuctionParams {})
+    }
+
+    pub fn debt_auction<Q: Querier>(
+        &self,
+        querier: &Q,
+        block_height: u64,
+    ) -> StdResult<DebtAuctionResponse> {
+        query_msg(querier, &self.0, &QueryMsg::DebtAuction { block_height })
+    }
+
+    pub fn aterra_exchange_rate<Q: Querier>(
+        &self,
+        querier: &Q,
+        block_height: Option<u64>,
+    ) -> StdResult<ATerraExchangeRateResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::ATerraExchangeRate { block_height },
+        )
+    }
+
+    pub fn keeper_bounty<Q: Querier>(&self, querier: &Q) -> StdResult<KeeperBountyResponse> {
+        query_msg(querier, &self.0, &QueryMsg::KeeperBounty {})
+    }
+
+    pub fn dust_forgiveness<Q: Querier>(&self, querier: &Q) -> StdResult<DustForgivenessResponse> {
+        query_msg(querier, &self.0, &QueryMsg::DustForgiveness {})
+    }
+
+    pub fn donations<Q: Querier>(&self, querier: &Q) -> StdResult<DonationsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Donations {})
+    }
+
+    pub fn stable_rate_params<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<StableRateParamsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::StableRateParams {})
+    }
+
+    pub fn rate_mode<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<RateModeResponse> {
+        query_msg(querier, &self.0, &QueryMsg::RateMode { borrower })
+    }
+
+    pub fn loan_breakdown<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+        block_height: Option<u64>,
+    ) -> StdResult<LoanBreakdownResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::LoanBreakdown {
+                borrower,
+                block_height,
+            },
+        )
+    }
+
+    pub fn rate_snapshot<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<RateSnapshotResponse> {
+        query_msg(querier, &self.0, &QueryMsg::RateSnapshot { borrower })
+    }
+
+    pub fn state_at<Q: Querier>(
+        &self,
+        querier: &Q,
+        height: u64,
+    ) -> StdResult<StateSnapshotResponse> {
+        query_msg(querier, &self.0, &QueryMsg::StateAt { height })
+    }
+
+    pub fn state_history<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<StateHistoryResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::StateHistory { start_after, limit },
+        )
+    }
+
+    pub fn stable_denoms<Q: Querier>(&self, querier: &Q) -> StdResult<StableDenomsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::StableDenoms {})
+    }
+
+    pub fn cw20_stables<Q: Querier>(&self, querier: &Q) -> StdResult<Cw20StablesResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Cw20Stables {})
+    }
+
+    pub fn bootstrap_mode<Q: Querier>(&self, querier: &Q) -> StdResult<BootstrapModeResponse> {
+        query_msg(querier, &self.0, &QueryMsg::BootstrapMode {})
+    }
+
+    pub fn simulate_deposit<Q: Querier>(
+        &self,
+        querier: &Q,
+        amount: Uint256,
+        block_height: Option<u64>,
+    ) -> StdResult<SimulateDepositResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::SimulateDeposit {
+                amount,
+                block_height,
+            },
+        )
+    }
+
+    pub fn simulate_redeem<Q: Querier>(
+        &self,
+        querier: &Q,
+        burn_amount: Uint256,
+        block_height: Option<u64>,
+    ) -> StdResult<SimulateRedeemResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::SimulateRedeem {
+                burn_amount,
+                block_height,
+            },
+        )
+    }
+    pub fn invariant_checks<Q: Querier>(&self, querier: &Q) -> StdResult<InvariantChecksResponse> {
+        query_msg(querier, &self.0, &QueryMsg::InvariantChecks {})
+    }
+
+    pub fn flow_limit<Q: Querier>(&self, querier: &Q) -> StdResult<FlowLimitResponse> {
+        query_msg(querier, &self.0, &QueryMsg::FlowLimit {})
+    }
+
+    pub fn attestation_contract<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<AttestationContractResponse> {
+        query_msg(querier, &self.0, &QueryMsg::AttestationContract {})
+    }
+
+    pub fn shadow_interest_model<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<ShadowInterestModelResponse> {
+        query_msg(querier, &self.0, &QueryMsg::ShadowInterestModel {})
+    }
+}
@@ -0,0 +1,683 @@
+//! Minimal in-memory chain to run the moneymarket contracts together
+//! in examples and integration tests.
+//!
+//! Supports bank transfers, a built-in cw20 token code, the Terra tax
+//! queries and `WasmMsg` dispatch between stored contracts. Each top
+//! level call is atomic: the whole app state is reverted when any of
+//! the dispatched messages fails.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, AllBalanceResponse, Api, BalanceResponse, BankMsg,
+    BankQuery, Binary, BlockInfo, Coin, CosmosMsg, Decimal, Env, Extern, HandleResponse, HumanAddr,
+    InitResponse, LogAttribute, Order, Querier, QuerierResult, QueryRequest, ReadonlyStorage,
+    StdError, StdResult, Storage, SystemError, Uint128, WasmMsg, WasmQuery,
+};
+use cosmwasm_storage::to_length_prefixed;
+use cw20::{
+    BalanceResponse as Cw20BalanceResponse, Cw20HandleMsg, Cw20QueryMsg, Cw20ReceiveMsg,
+    TokenInfoResponse,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use terra_cosmwasm::{
+    TaxCapResponse, TaxRateResponse, TerraMsgWrapper, TerraQuery, TerraQueryWrapper,
+};
+use terraswap::token::InitMsg as TokenInitMsg;
+
+const CANONICAL_LENGTH: usize = 32;
+
+/// Seconds added to the block time for each block
+const BLOCK_TIME: u64 = 5;
+
+pub type MockDeps = Extern<MockStorage, MockApi, AppQuerier>;
+pub type InitFn = fn(&mut MockDeps, Env, Binary) -> StdResult<InitResponse<TerraMsgWrapper>>;
+pub type HandleFn = fn(&mut MockDeps, Env, Binary) -> StdResult<HandleResponse<TerraMsgWrapper>>;
+pub type QueryFn = fn(&MockDeps, Binary) -> StdResult<Binary>;
+
+/// Entry points of a contract stored in the app; use
+/// `contract_wrapper!` to build it from a contract module
+#[derive(Clone, Copy)]
+pub struct ContractWrapper {
+    pub init: InitFn,
+    pub handle: HandleFn,
+    pub query: QueryFn,
+}
+
+/// Builds a `ContractWrapper` from a module exposing `init`, `handle` and `query`
+#[macro_export]
+macro_rules! contract_wrapper {
+    ($($contract:ident)::+) => {
+        $crate::mock_app::ContractWrapper {
+            init: |deps, env, msg| {
+                $crate::mock_app::to_terra_response($($contract)::+::init(
+                    deps,
+                    env,
+                    cosmwasm_std::from_binary(&msg)?,
+                ))
+            },
+            handle: |deps, env, msg| {
+                $crate::mock_app::to_terra_response($($contract)::+::handle(
+                    deps,
+                    env,
+                    cosmwasm_std::from_binary(&msg)?,
+                ))
+            },
+            query: |deps, msg| $($contract)::+::query(deps, cosmwasm_std::from_binary(&msg)?),
+        }
+    };
+}
+
+/// Converts a contract response into the Terra flavoured response used by the app
+pub fn to_terra_response<T: Serialize, U: DeserializeOwned>(res: StdResult<T>) -> StdResult<U> {
+    from_binary(&to_binary(&res?)?)
+}
+
+#[derive(Clone, Copy)]
+enum Code {
+    Wasm(ContractWrapper),
+    Token,
+}
+
+#[derive(Clone)]
+struct ContractData {
+    code: ContractWrapper,
+    storage: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+#[derive(Clone)]
+struct TokenData {
+    name: String,
+    symbol: String,
+    decimals: u8,
+    total_supply: Uint128,
+    minter: Option<HumanAddr>,
+    balances: HashMap<HumanAddr, Uint128>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    codes: Vec<Code>,
+    contracts: HashMap<HumanAddr, ContractData>,
+    tokens: HashMap<HumanAddr, TokenData>,
+    balances: HashMap<HumanAddr, BTreeMap<String, Uint128>>,
+    block: BlockInfo,
+    tax_rate: Decimal,
+    tax_cap: Uint128,
+    contract_count: u64,
+}
+
+/// Querier handed to the contracts; reads the current app state
+#[derive(Clone)]
+pub struct AppQuerier {
+    state: Rc<RefCell<AppState>>,
+}
+
+impl Querier for AppQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+
+        Ok(self.handle_query(request))
+    }
+}
+
+impl AppQuerier {
+    fn handle_query(&self, request: QueryRequest<TerraQueryWrapper>) -> StdResult<Binary> {
+        match request {
+            QueryRequest::Bank(BankQuery::Balance { address, denom }) => {
+                let amount = self.balance(&address, &denom);
+                to_binary(&BalanceResponse {
+                    amount: Coin { denom, amount },
+                })
+            }
+            QueryRequest::Bank(BankQuery::AllBalances { address }) => {
+                let state = self.state.borrow();
+                let amount = state
+                    .balances
+                    .get(&address)
+                    .map(|coins| {
+                        coins
+                            .iter()
+                            .map(|(denom, amount)| Coin {
+                                denom: denom.clone(),
+                                amount: *amount,
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                to_binary(&AllBalanceResponse { amount })
+            }
+            QueryRequest::Custom(TerraQueryWrapper { query_data, .. }) => {
+                let state = self.state.borrow();
+                match query_data {
+                    TerraQuery::TaxRate {} => to_binary(&TaxRateResponse {
+                        rate: state.tax_rate,
+                    }),
+                    TerraQuery::TaxCap { .. } => to_binary(&TaxCapResponse { cap: state.tax_cap }),
+                    _ => Err(StdError::generic_err("Unsupported terra query")),
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                self.query_smart(&contract_addr, msg)
+            }
+            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
+                self.query_raw(&contract_addr, key)
+            }
+            _ => Err(StdError::generic_err("Unsupported query")),
+        }
+    }
+
+    fn balance(&self, address: &HumanAddr, denom: &str) -> Uint128 {
+        self.state
+            .borrow()
+            .balances
+            .get(address)
+            .and_then(|coins| coins.get(denom).copied())
+            .unwrap_or_else(Uint128::zero)
+    }
+
+    fn query_smart(&self, contract_addr: &HumanAddr, msg: Binary) -> StdResult<Binary> {
+        let token = self.state.borrow().tokens.get(contract_addr).cloned();
+        if let Some(token) = token {
+            return match from_binary(&msg)? {
+                Cw20QueryMsg::Balance { address } => to_binary(&Cw20BalanceResponse {
+                    balance: token
+                        .balances
+                        .get(&address)
+                        .copied()
+                        .unwrap_or_else(Uint128::zero),
+                }),
+                Cw20QueryMsg::TokenInfo {} => to_binary(&token_info(&token)),
+                _ => Err(StdError::generic_err("Unsupported token query")),
+            };
+        }
+
+        let (code, deps) = load_contract(&self.state, contract_addr)?;
+        (code.query)(&deps, msg)
+    }
+
+    fn query_raw(&self, contract_addr: &HumanAddr, key: Binary) -> StdResult<Binary> {
+        let state = self.state.borrow();
+        let value: Vec<u8> = if let Some(token) = state.tokens.get(contract_addr) {
+            let balance_prefix = to_length_prefixed(b"balance");
+            if key.as_slice() == to_length_prefixed(b"token_info").as_slice() {
+                to_binary(&token_info(token))?.0
+            } else if key.as_slice().starts_with(&balance_prefix) {
+                let api = MockApi::new(CANONICAL_LENGTH);
+                let holder = api.human_address(&key.as_slice()[balance_prefix.len()..].into())?;
+                let balance =
+                    token.balances.get(&holder).copied().ok_or_else(|| {
+                        StdError::generic_err(format!("No balance for {}", holder))
+                    })?;
+                to_binary(&balance)?.0
+            } else {
+                vec![]
+            }
+        } else {
+            let contract = state.contracts.get(contract_addr).ok_or_else(|| {
+                StdError::generic_err(format!("No such contract {}", contract_addr))
+            })?;
+            contract
+                .storage
+                .get(key.as_slice())
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        to_binary(&Binary(value))
+    }
+}
+
+fn token_info(token: &TokenData) -> TokenInfoResponse {
+    TokenInfoResponse {
+        name: token.name.clone(),
+        symbol: token.symbol.clone(),
+        decimals: token.decimals,
+        total_supply: token.total_supply,
+    }
+}
+
+fn load_contract(
+    state: &Rc<RefCell<AppState>>,
+    contract_addr: &HumanAddr,
+) -> StdResult<(ContractWrapper, MockDeps)> {
+    let app_state = state.borrow();
+    let contract = app_state
+        .contracts
+        .get(contract_addr)
+        .ok_or_else(|| StdError::generic_err(format!("No such contract {}", contract_addr)))?;
+
+    let mut storage = MockStorage::new();
+    for (k, v) in contract.storage.iter() {
+        storage.set(k, v);
+    }
+
+    Ok((
+        contract.code,
+        Extern {
+            storage,
+            api: MockApi::new(CANONICAL_LENGTH),
+            querier: AppQuerier {
+                state: state.clone(),
+            },
+        },
+    ))
+}
+
+/// In-memory chain running the contracts stored through `store_code`
+pub struct MockApp {
+    state: Rc<RefCell<AppState>>,
+}
+
+impl Default for MockApp {
+    fn default() -> Self {
+        MockApp::new()
+    }
+}
+
+impl MockApp {
+    pub fn new() -> Self {
+        MockApp {
+            state: Rc::new(RefCell::new(AppState {
+                codes: vec![],
+                contracts: HashMap::new(),
+                tokens: HashMap::new(),
+                balances: HashMap::new(),
+                block: mock_env("", &[]).block,
+                tax_rate: Decimal::zero(),
+                tax_cap: Uint128::zero(),
+                contract_count: 0,
+            })),
+        }
+    }
+
+    pub fn querier(&self) -> AppQuerier {
+        AppQuerier {
+            state: self.state.clone(),
+        }
+    }
+
+    pub fn block(&self) -> BlockInfo {
+        self.state.borrow().block.clone()
+    }
+
+    /// Advances the chain by the given number of blocks
+    pub fn next_block(&self, blocks: u64) {
+        let mut state = self.state.borrow_mut();
+        state.block.height += blocks;
+        state.block.time += blocks * BLOCK_TIME;
+    }
+
+    pub fn set_tax(&self, rate: Decimal, cap: Uint128) {
+        let mut state = self.state.borrow_mut();
+        state.tax_rate = rate;
+        state.tax_cap = cap;
+    }
+
+    /// Stores a contract code and returns its code id
+    pub fn store_code(&self, code: ContractWrapper) -> u64 {
+        let mut state = self.state.borrow_mut();
+        state.codes.push(Code::Wasm(code));
+        state.codes.len() as u64
+    }
+
+    /// Stores the built-in cw20 token code and returns its code id
+    pub fn store_token_code(&self) -> u64 {
+        let mut state = self.state.borrow_mut();
+        state.codes.push(Code::Token);
+        state.codes.len() as u64
+    }
+
+    pub fn set_balance(&self, address: &HumanAddr, coins: &[Coin]) {
+        let mut state = self.state.borrow_mut();
+        let balances = state.balances.entry(address.clone()).or_default();
+        balances.clear();
+        for coin in coins {
+            balances.insert(coin.denom.clone(), coin.amount);
+        }
+    }
+
+    pub fn balance(&self, address: &HumanAddr, denom: &str) -> Uint128 {
+        self.querier().balance(address, denom)
+    }
+
+    pub fn token_balance(&self, token: &HumanAddr, address: &HumanAddr) -> Uint128 {
+        self.state
+            .borrow()
+            .tokens
+            .get(token)
+            .and_then(|token| token.balances.get(address).copied())
+            .unwrap_or_else(Uint128::zero)
+    }
+
+    pub fn instantiate<T: Serialize>(
+        &self,
+        code_id: u64,
+        sender: &HumanAddr,
+        msg: &T,
+        send: &[Coin],
+    ) -> StdResult<HumanAddr> {
+        self.atomic(|app| {
+            app.instantiate_code(sender, code_id, to_binary(msg)?, send.to_vec(), &mut vec![])
+        })
+    }
+
+    /// Executes a contract message and returns the logs of all dispatched messages
+    pub fn execute<T: Serialize>(
+        &self,
+        sender: &HumanAddr,
+        contract_addr: &HumanAddr,
+        msg: &T,
+        send: &[Coin],
+    ) -> StdResult<Vec<LogAttribute>> {
+        self.atomic(|app| {
+            let mut logs = vec![];
+            app.execute_contract(
+                sender,
+                contract_addr,
+                to_binary(msg)?,
+                send.to_vec(),
+                &mut logs,
+            )?;
+            Ok(logs)
+        })
+    }
+
+    /// Dispatches messages, e.g. built by the contract clients, on behalf of `sender`
+    pub fn execute_msgs(
+        &self,
+        sender: &HumanAddr,
+        msgs: Vec<CosmosMsg>,
+    ) -> StdResult<Vec<LogAttribute>> {
+        self.atomic(|app| {
+            let mut logs = vec![];
+            for msg in msgs {
+                app.dispatch(sender, to_terra_response(Ok(msg))?, &mut logs)?;
+            }
+            Ok(logs)
+        })
+    }
+
+    pub fn query<T: Serialize, R: DeserializeOwned>(
+        &self,
+        contract_addr: &HumanAddr,
+        msg: &T,
+    ) -> StdResult<R> {
+        from_binary(&self.querier().query_smart(contract_addr, to_binary(msg)?)?)
+    }
+
+    fn atomic<R, F: FnOnce(&Self) -> StdResult<R>>(&self, f: F) -> StdResult<R> {
+        let snapshot = self.state.borrow().clone();
+        let res = f(self);
+        if res.is_err() {
+            *self.state.borrow_mut() = snapshot;
+        }
+
+        res
+    }
+
+    fn env(&self, sender: &HumanAddr, contract_addr: &HumanAddr, send: &[Coin]) -> Env {
+        let mut env = mock_env(sender.clone(), send);
+        env.block = self.block();
+        env.contract.address = contract_addr.clone();
+        env
+    }
+
+    fn dispatch(
+        &self,
+        sender: &HumanAddr,
+        msg: CosmosMsg<TerraMsgWrapper>,
+        logs: &mut Vec<LogAttribute>,
+    ) -> StdResult<()> {
+        match msg {
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address,
+                to_address,
+                amount,
+            }) => {
+                if from_address != *sender {
+                    return Err(StdError::unauthorized());
+                }
+
+                self.transfer(&from_address, &to_address, &amount)
+            }
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                send,
+            }) => self.execute_contract(sender, &contract_addr, msg, send, logs),
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                code_id, msg, send, ..
+            }) => self
+                .instantiate_code(sender, code_id, msg, send, logs)
+                .map(|_| ()),
+            _ => Err(StdError::generic_err("Unsupported message")),
+        }
+    }
+
+    fn transfer(&self, from: &HumanAddr, to: &HumanAddr, coins: &[Coin]) -> StdResult<()> {
+        let mut state = self.state.borrow_mut();
+        for coin in coins {
+            let from_balance = state
+                .balances
+                .entry(from.clone())
+                .or_default()
+                .entry(coin.denom.clone())
+                .or_insert_with(Uint128::zero);
+            if from_balance.u128() < coin.amount.u128() {
+                return Err(StdError::generic_err(format!(
+                    "Insufficient {} balance of {}",
+                    coin.denom, from
+                )));
+            }
+            *from_balance = Uint128(from_balance.u128() - coin.amount.u128());
+
+            let to_balance = state
+                .balances
+                .entry(to.clone())
+                .or_default()
+                .entry(coin.denom.clone())
+                .or_insert_with(Uint128::zero);
+            *to_balance = Uint128(to_balance.u128() + coin.amount.u128());
+        }
+
+        Ok(())
+    }
+
+    fn instantiate_code(
+        &self,
+        sender: &HumanAddr,
+        code_id: u64,
+        msg: Binary,
+        send: Vec<Coin>,
+        logs: &mut Vec<LogAttribute>,
+    ) -> StdResult<HumanAddr> {
+        let code = {
+            let mut state = self.state.borrow_mut();
+            let code = *state
+                .codes
+                .get((code_id as usize).wrapping_sub(1))
+                .ok_or_else(|| StdError::generic_err(format!("No such code {}", code_id)))?;
+            state.contract_count += 1;
+            code
+        };
+        let contract_addr = HumanAddr(format!("contract{:04}", self.state.borrow().contract_count));
+        self.transfer(sender, &contract_addr, &send)?;
+
+        match code {
+            Code::Token => {
+                let init: TokenInitMsg = from_binary(&msg)?;
+                let mut token = TokenData {
+                    name: init.name,
+                    symbol: init.symbol,
+                    decimals: init.decimals,
+                    total_supply: Uint128::zero(),
+                    minter: init.mint.map(|m| m.minter),
+                    balances: HashMap::new(),
+                };
+                for balance in init.initial_balances {
+                    token.total_supply += balance.amount;
+                    token.balances.insert(balance.address, balance.amount);
+                }
+                self.state
+                    .borrow_mut()
+                    .tokens
+                    .insert(contract_addr.clone(), token);
+
+                if let Some(init_hook) = init.init_hook {
+                    self.execute_contract(
+                        &contract_addr,
+                        &init_hook.contract_addr,
+                        init_hook.msg,
+                        vec![],
+                        logs,
+                    )?;
+                }
+            }
+            Code::Wasm(code) => {
+                self.state.borrow_mut().contracts.insert(
+                    contract_addr.clone(),
+                    ContractData {
+                        code,
+                        storage: BTreeMap::new(),
+                    },
+                );
+
+                let env = self.env(sender, &contract_addr, &send);
+                let (_, mut deps) = load_contract(&self.state, &contract_addr)?;
+                let res = (code.init)(&mut deps, env, msg)?;
+                self.save_contract(&contract_addr, &deps.storage);
+
+                logs.extend(res.log);
+                for msg in res.messages {
+                    self.dispatch(&contract_addr, msg, logs)?;
+                }
+            }
+        }
+
+        Ok(contract_addr)
+    }
+
+    fn execute_contract(
+        &self,
+        sender: &HumanAddr,
+        contract_addr: &HumanAddr,
+        msg: Binary,
+        send: Vec<Coin>,
+        logs: &mut Vec<LogAttribute>,
+    ) -> StdResult<()> {
+        self.transfer(sender, contract_addr, &send)?;
+        if self.state.borrow().tokens.contains_key(contract_addr) {
+            return self.execute_token(sender, contract_addr, from_binary(&msg)?, logs);
+        }
+
+        let env = self.env(sender, contract_addr, &send);
+        let (code, mut deps) = load_contract(&self.state, contract_addr)?;
+        let res = (code.handle)(&mut deps, env, msg)?;
+        self.save_contract(contract_addr, &deps.storage);
+
+        logs.extend(res.log);
+        for msg in res.messages {
+            self.dispatch(contract_addr, msg, logs)?;
+        }
+
+        Ok(())
+    }
+
+    fn save_contract(&self, contract_addr: &HumanAddr, storage: &MockStorage) {
+        let storage: BTreeMap<Vec<u8>, Vec<u8>> =
+            storage.range(None, None, Order::Ascending).collect();
+        if let Some(contract) = self.state.borrow_mut().contracts.get_mut(contract_addr) {
+            contract.storage = storage;
+        }
+    }
+
+    fn execute_token(
+        &self,
+        sender: &HumanAddr,
+        token_addr: &HumanAddr,
+        msg: Cw20HandleMsg,
+        logs: &mut Vec<LogAttribute>,
+    ) -> StdResult<()> {
+        match msg {
+            Cw20HandleMsg::Transfer { recipient, amount } => {
+                self.move_token(token_addr, sender, &recipient, amount)
+            }
+            Cw20HandleMsg::Send {
+                contract,
+                amount,
+                msg,
+            } => {
+                self.move_token(token_addr, sender, &contract, amount)?;
+                let receive_msg = Cw20ReceiveMsg {
+                    sender: sender.clone(),
+                    amount,
+                    msg,
+                }
+                .into_binary()?;
+                self.execute_contract(token_addr, &contract, receive_msg, vec![], logs)
+            }
+            Cw20HandleMsg::Mint { recipient, amount } => {
+                let mut state = self.state.borrow_mut();
+                let token = state.tokens.get_mut(token_addr).unwrap();
+                if token.minter.as_ref() != Some(sender) {
+                    return Err(StdError::unauthorized());
+                }
+
+                token.total_supply += amount;
+                let balance = token
+                    .balances
+                    .entry(recipient)
+                    .or_insert_with(Uint128::zero);
+                *balance += amount;
+                Ok(())
+            }
+            Cw20HandleMsg::Burn { amount } => {
+                let mut state = self.state.borrow_mut();
+                let token = state.tokens.get_mut(token_addr).unwrap();
+                let balance = token
+                    .balances
+                    .entry(sender.clone())
+                    .or_insert_with(Uint128::zero);
+                *balance = (*balance - amount)?;
+                token.total_supply = (token.total_supply - amount)?;
+                Ok(())
+            }
+            _ => Err(StdError::generic_err("Unsupported token message")),
+        }
+    }
+
+    fn move_token(
+        &self,
+        token_addr: &HumanAddr,
+        from: &HumanAddr,
+        to: &HumanAddr,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        let mut state = self.state.borrow_mut();
+        let token = state.tokens.get_mut(token_addr).unwrap();
+        let from_balance = token
+            .balances
+            .entry(from.clone())
+            .or_insert_with(Uint128::zero);
+        *from_balance = (*from_balance - amount)?;
+        let to_balance = token
+            .balances
+            .entry(to.clone())
+            .or_insert_with(Uint128::zero);
+        *to_balance += amount;
+        Ok(())
+    }
+}
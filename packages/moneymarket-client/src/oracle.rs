@@ -0,0 +1,63 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{CosmosMsg, HumanAddr, Querier, StdResult};
+
+use crate::{execute_msg, query_msg};
+use moneymarket::oracle::{
+    ConfigResponse, FeederResponse, HandleMsg, PriceResponse, PricesResponse, QueryMsg,
+};
+
+/// Oracle contract address with typed message builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct OracleContract(pub HumanAddr);
+
+impl OracleContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Execute messages
+    ////////////////////
+
+    pub fn register_feeder(&self, asset: String, feeder: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::RegisterFeeder { asset, feeder },
+            vec![],
+        )
+    }
+
+    pub fn feed_price(&self, prices: Vec<(String, Decimal256)>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::FeedPrice { prices }, vec![])
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn feeder<Q: Querier>(&self, querier: &Q, asset: String) -> StdResult<FeederResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Feeder { asset })
+    }
+
+    pub fn price<Q: Querier>(
+        &self,
+        querier: &Q,
+        base: String,
+        quote: String,
+    ) -> StdResult<PriceResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Price { base, quote })
+    }
+
+    pub fn prices<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<PricesResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Prices { start_after, limit })
+    }
+}
@@ -0,0 +1,544 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{CosmosMsg, HumanAddr, Querier, StdResult};
+
+use crate::{execute_msg, query_msg};
+use moneymarket::overseer::{
+    AllCollateralsResponse, AnalyticsContractResponse, AssetMetadata, AssetMetadataResponse,
+    BorrowLimitResponse, CollateralProtectionResponse, CollateralsResponse, ConfigResponse,
+    EpochPhaseResponse, EpochRecordsResponse, FeedLivenessResponse, HandleMsg, IndexUnitResponse,
+    InsuranceContractsResponse, InterestRebateResponse, InvariantChecksResponse,
+    LiquidationCandidatesResponse, LtvCheckpointResponse, MaxLtvOverrideResponse,
+    PositionInsuranceResponse, PositionTransferResponse, PriceCheck, PriceCheckResponse, QueryMsg,
+    RegistryResponse, RewardRouteResponse, RewardShare, RiskModuleResponse,
+    SimulateLiquidationResponse, SolvencyResponse, SponsorshipsResponse, WhitelistProposalResponse,
+    WhitelistProposalsResponse, WhitelistResponse,
+};
+
+/// Overseer contract address with typed message builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverseerContract(pub HumanAddr);
+
+impl OverseerContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Execute messages
+    ////////////////////
+
+    pub fn lock_collateral(&self, collaterals: Vec<(HumanAddr, Uint256)>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::LockCollateral { collaterals }, vec![])
+    }
+
+    pub fn unlock_collateral(
+        &self,
+        collaterals: Vec<(HumanAddr, Uint256)>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UnlockCollateral { collaterals },
+            vec![],
+        )
+    }
+
+    pub fn lock_collateral_batch(
+        &self,
+        collaterals: Vec<(HumanAddr, Uint256)>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::LockCollateralBatch { collaterals },
+            vec![],
+        )
+    }
+
+    pub fn unlock_collateral_batch(
+        &self,
+        collaterals: Vec<(HumanAddr, Uint256)>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UnlockCollateralBatch { collaterals },
+            vec![],
+        )
+    }
+
+    pub fn lock_collateral_for(
+        &self,
+        borrower: HumanAddr,
+        collaterals: Vec<(HumanAddr, Uint256)>,
+        withdraw_allowed: bool,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::LockCollateralFor {
+                borrower,
+                collaterals,
+                withdraw_allowed,
+            },
+            vec![],
+        )
+    }
+
+    pub fn unlock_collateral_for(
+        &self,
+        borrower: HumanAddr,
+        collaterals: Vec<(HumanAddr, Uint256)>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UnlockCollateralFor {
+                borrower,
+                collaterals,
+            },
+            vec![],
+        )
+    }
+
+    pub fn transfer_position(&self, to: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::TransferPosition { to }, vec![])
+    }
+
+    pub fn cancel_position_transfer(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::CancelPositionTransfer {}, vec![])
+    }
+
+    pub fn accept_position(&self, from: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::AcceptPosition { from }, vec![])
+    }
+
+    pub fn attach_insurance(&self, insurance_contract: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::AttachInsurance { insurance_contract },
+            vec![],
+        )
+    }
+
+    pub fn detach_insurance(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::DetachInsurance {}, vec![])
+    }
+
+    pub fn update_reward_route(
+        &self,
+        collateral_token: HumanAddr,
+        route: Vec<RewardShare>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateRewardRoute {
+                collateral_token,
+                route,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_price_check(
+        &self,
+        collateral_token: HumanAddr,
+        price_check: Option<PriceCheck>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdatePriceCheck {
+                collateral_token,
+                price_check,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_collateral_protection(
+        &self,
+        protected_collaterals: Vec<HumanAddr>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateCollateralProtection {
+                protected_collaterals,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_max_ltv_override(
+        &self,
+        max_ltv_override: Option<Decimal256>,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateMaxLtvOverride { max_ltv_override },
+            vec![],
+        )
+    }
+
+    pub fn propose_whitelist(
+        &self,
+        name: String,
+        symbol: String,
+        collateral_token: HumanAddr,
+        custody_contract: HumanAddr,
+        max_ltv: Decimal256,
+        metadata: AssetMetadata,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::ProposeWhitelist {
+                name,
+                symbol,
+                collateral_token,
+                custody_contract,
+                max_ltv,
+                metadata,
+            },
+            vec![],
+        )
+    }
+
+    pub fn swap_collateral(
+        &self,
+        from_token: HumanAddr,
+        to_token: HumanAddr,
+        min_receive: Uint256,
+    ) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::SwapCollateral {
+                from_token,
+                to_token,
+                min_receive,
+            },
+            vec![],
+        )
+    }
+
+    pub fn liquidate_collateral(&self, borrower: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::LiquidateCollateral { borrower },
+            vec![],
+        )
+    }
+
+    pub fn checkpoint_ltv(&self, borrower: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::CheckpointLtv { borrower }, vec![])
+    }
+
+    pub fn execute_epoch_operations(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::ExecuteEpochOperations {}, vec![])
+    }
+
+    pub fn update_invariant_checks(&self, enabled: bool) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::UpdateInvariantChecks { enabled },
+            vec![],
+        )
+    }
+
+    pub fn execute_epoch_step(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::ExecuteEpochStep {}, vec![])
+    }
+
+    pub fn refresh_protocol_mode(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RefreshProtocolMode {}, vec![])
+    }
+
+    pub fn refresh_registry(&self) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RefreshRegistry {}, vec![])
+    }
+
+    /// Owner operations applied atomically, e.g. as one multisig proposal
+    pub fn admin_batch(&self, ops: Vec<HandleMsg>) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::AdminBatch { ops }, vec![])
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn whitelist<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: Option<HumanAddr>,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<WhitelistResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::Whitelist {
+                collateral_token,
+                start_after,
+                limit,
+            },
+        )
+    }
+
+    pub fn collaterals<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<CollateralsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Collaterals { borrower })
+    }
+
+    pub fn all_collaterals<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<AllCollateralsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::AllCollaterals { start_after, limit },
+        )
+    }
+
+    pub fn borrow_limit<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+        block_time: Option<u64>,
+    ) -> StdResult<BorrowLimitResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::BorrowLimit {
+                borrower,
+                block_time,
+            },
+        )
+    }
+
+    pub fn sponsorships<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<SponsorshipsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Sponsorships { borrower })
+    }
+
+    pub fn epoch_phase<Q: Querier>(&self, querier: &Q) -> StdResult<EpochPhaseResponse> {
+        query_msg(querier, &self.0, &QueryMsg::EpochPhase {})
+    }
+
+    pub fn invariant_checks<Q: Querier>(&self, querier: &Q) -> StdResult<InvariantChecksResponse> {
+        query_msg(querier, &self.0, &QueryMsg::InvariantChecks {})
+    }
+
+    pub fn epoch_records<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<EpochRecordsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::EpochRecords { start_after, limit },
+        )
+    }
+
+    pub fn index_unit<Q: Querier>(&self, querier: &Q) -> StdResult<IndexUnitResponse> {
+        query_msg(querier, &self.0, &QueryMsg::IndexUnit {})
+    }
+
+    pub fn position_transfer<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<PositionTransferResponse> {
+        query_msg(querier, &self.0, &QueryMsg::PositionTransfer { borrower })
+    }
+
+    pub fn insurance_contracts<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<InsuranceContractsResponse> {
+        query_msg(querier, &self.0, &QueryMsg::InsuranceContracts {})
+    }
+
+    pub fn position_insurance<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<PositionInsuranceResponse> {
+        query_msg(querier, &self.0, &QueryMsg::PositionInsurance { borrower })
+    }
+
+    pub fn feed_liveness<Q: Querier>(
+        &self,
+        querier: &Q,
+        block_time: u64,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<FeedLivenessResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::FeedLiveness {
+                block_time,
+                start_after,
+                limit,
+            },
+        )
+    }
+
+    pub fn collateral_protection<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<CollateralProtectionResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::CollateralProtection { borrower },
+        )
+    }
+
+    pub fn max_ltv_override<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<MaxLtvOverrideResponse> {
+        query_msg(querier, &self.0, &QueryMsg::MaxLtvOverride { borrower })
+    }
+
+    pub fn reward_route<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: HumanAddr,
+    ) -> StdResult<RewardRouteResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::RewardRoute { collateral_token },
+        )
+    }
+
+    pub fn price_check<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: HumanAddr,
+    ) -> StdResult<PriceCheckResponse> {
+        query_msg(querier, &self.0, &QueryMsg::PriceCheck { collateral_token })
+    }
+
+    pub fn risk_module<Q: Querier>(&self, querier: &Q) -> StdResult<RiskModuleResponse> {
+        query_msg(querier, &self.0, &QueryMsg::RiskModule {})
+    }
+
+    pub fn analytics_contract<Q: Querier>(
+        &self,
+        querier: &Q,
+    ) -> StdResult<AnalyticsContractResponse> {
+        query_msg(querier, &self.0, &QueryMsg::AnalyticsContract {})
+    }
+
+    pub fn registry<Q: Querier>(&self, querier: &Q) -> StdResult<RegistryResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Registry {})
+    }
+
+    pub fn whitelist_proposal<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: HumanAddr,
+    ) -> StdResult<WhitelistProposalResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::WhitelistProposal { collateral_token },
+        )
+    }
+
+    pub fn whitelist_proposals<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    ) -> StdResult<WhitelistProposalsResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::WhitelistProposals { start_after, limit },
+        )
+    }
+
+    pub fn asset_metadata<Q: Querier>(
+        &self,
+        querier: &Q,
+        collateral_token: HumanAddr,
+    ) -> StdResult<AssetMetadataResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::AssetMetadata { collateral_token },
+        )
+    }
+
+    pub fn interest_rebate<Q: Querier>(&self, querier: &Q) -> StdResult<InterestRebateResponse> {
+        query_msg(querier, &self.0, &QueryMsg::InterestRebate {})
+    }
+
+    pub fn ltv_checkpoint<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+    ) -> StdResult<LtvCheckpointResponse> {
+        query_msg(querier, &self.0, &QueryMsg::LtvCheckpoint { borrower })
+    }
+
+    pub fn simulate_liquidation<Q: Querier>(
+        &self,
+        querier: &Q,
+        borrower: HumanAddr,
+        block_height: u64,
+        block_time: u64,
+    ) -> StdResult<SimulateLiquidationResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::SimulateLiquidation {
+                borrower,
+                block_height,
+                block_time,
+            },
+        )
+    }
+
+    pub fn liquidation_candidates<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+        block_height: u64,
+        block_time: u64,
+    ) -> StdResult<LiquidationCandidatesResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::LiquidationCandidates {
+                start_after,
+                limit,
+                block_height,
+                block_time,
+            },
+        )
+    }
+
+    pub fn solvency<Q: Querier>(
+        &self,
+        querier: &Q,
+        block_height: u64,
+    ) -> StdResult<SolvencyResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Solvency { block_height })
+    }
+}
@@ -0,0 +1,36 @@
+use cosmwasm_std::{CosmosMsg, HumanAddr, Querier, StdResult};
+
+use crate::{execute_msg, query_msg};
+use moneymarket::protocol_controller::{
+    ConfigResponse, HandleMsg, ProtocolMode, ProtocolModeResponse, QueryMsg,
+};
+
+/// Protocol controller contract address with typed message builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtocolControllerContract(pub HumanAddr);
+
+impl ProtocolControllerContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Execute messages
+    ////////////////////
+
+    pub fn update_protocol_mode(&self, mode: ProtocolMode) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::UpdateProtocolMode { mode }, vec![])
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn protocol_mode<Q: Querier>(&self, querier: &Q) -> StdResult<ProtocolModeResponse> {
+        query_msg(querier, &self.0, &QueryMsg::ProtocolMode {})
+    }
+}
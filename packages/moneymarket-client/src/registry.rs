@@ -0,0 +1,65 @@
+use cosmwasm_std::{CosmosMsg, HumanAddr, Querier, StdResult};
+
+use crate::{execute_msg, query_msg};
+use moneymarket::registry::{
+    AddressesResponse, ConfigResponse, HandleMsg, QueryMsg, ResolveResponse, VersionResponse,
+};
+
+/// Registry contract address with typed message builders
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegistryContract(pub HumanAddr);
+
+impl RegistryContract {
+    pub fn addr(&self) -> HumanAddr {
+        self.0.clone()
+    }
+
+    ////////////////////
+    // Execute messages
+    ////////////////////
+
+    pub fn register_address(&self, name: String, address: HumanAddr) -> StdResult<CosmosMsg> {
+        execute_msg(
+            &self.0,
+            &HandleMsg::RegisterAddress { name, address },
+            vec![],
+        )
+    }
+
+    pub fn remove_address(&self, name: String) -> StdResult<CosmosMsg> {
+        execute_msg(&self.0, &HandleMsg::RemoveAddress { name }, vec![])
+    }
+
+    ////////////////////
+    // Queries
+    ////////////////////
+
+    pub fn config<Q: Querier>(&self, querier: &Q) -> StdResult<ConfigResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Config {})
+    }
+
+    pub fn version<Q: Querier>(&self, querier: &Q) -> StdResult<VersionResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Version {})
+    }
+
+    pub fn addresses<Q: Querier>(
+        &self,
+        querier: &Q,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AddressesResponse> {
+        query_msg(
+            querier,
+            &self.0,
+            &QueryMsg::Addresses { start_after, limit },
+        )
+    }
+
+    pub fn resolve<Q: Querier>(
+        &self,
+        querier: &Q,
+        names: Vec<String>,
+    ) -> StdResult<ResolveResponse> {
+        query_msg(querier, &self.0, &QueryMsg::Resolve { names })
+    }
+}
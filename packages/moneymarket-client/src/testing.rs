@@ -0,0 +1,132 @@
+use crate::contract_wrapper;
+use crate::market::MarketContract;
+use crate::mock_app::MockApp;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{to_binary, Coin, CosmosMsg, HumanAddr, Uint128, WasmMsg};
+use cw20::Cw20HandleMsg;
+use moneymarket::market::{Cw20HookMsg, DepositorInfoResponse, HandleMsg, InitMsg};
+
+fn stable(amount: u128) -> Coin {
+    Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128(amount),
+    }
+}
+
+#[test]
+fn market_messages() {
+    let market = MarketContract(HumanAddr::from("market"));
+
+    assert_eq!(
+        market.deposit_stable(stable(1000000)).unwrap(),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market"),
+            msg: to_binary(&HandleMsg::DepositStable {
+                recipient: None,
+                amount: None,
+                decimals: None,
+            })
+            .unwrap(),
+            send: vec![stable(1000000)],
+        })
+    );
+
+    assert_eq!(
+        market
+            .deposit_stable_for(stable(1000000), HumanAddr::from("addr0000"))
+            .unwrap(),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market"),
+            msg: to_binary(&HandleMsg::DepositStable {
+                recipient: Some(HumanAddr::from("addr0000")),
+                amount: None,
+                decimals: None,
+            })
+            .unwrap(),
+            send: vec![stable(1000000)],
+        })
+    );
+
+    // redemptions are sent through the aterra contract
+    assert_eq!(
+        market
+            .redeem_stable(&HumanAddr::from("aterra"), Uint128(500000))
+            .unwrap(),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("aterra"),
+            msg: to_binary(&Cw20HandleMsg::Send {
+                contract: HumanAddr::from("market"),
+                amount: Uint128(500000),
+                msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+            })
+            .unwrap(),
+            send: vec![],
+        })
+    );
+}
+
+#[test]
+fn market_on_mock_app() {
+    let app = MockApp::new();
+    let owner = HumanAddr::from("owner");
+    app.set_balance(&owner, &[stable(2000000)]);
+
+    let token_code = app.store_token_code();
+    let market_code = app.store_code(contract_wrapper!(moneymarket_market::contract));
+    let market = MarketContract(
+        app.instantiate(
+            market_code,
+            &owner,
+            &InitMsg {
+                owner_addr: owner.clone(),
+                stable_denom: "uusd".to_string(),
+                aterra_code_id: token_code,
+                anc_emission_rate: Decimal256::one(),
+                max_borrow_factor: Decimal256::one(),
+            },
+            &[stable(1000000)],
+        )
+        .unwrap(),
+    );
+
+    let state = market.state(&app.querier(), None).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+    assert_eq!(state.prev_exchange_rate, Decimal256::one());
+
+    let depositor = HumanAddr::from("addr0000");
+    app.set_balance(&depositor, &[stable(1000000), Coin::new(1000000, "ukrw")]);
+    assert_eq!(
+        market.depositor(&app.querier(), depositor.clone()).unwrap(),
+        DepositorInfoResponse {
+            depositor: depositor.clone(),
+            total_deposited: Uint256::zero(),
+            total_redeemed: Uint256::zero(),
+            net_principal: Uint256::zero(),
+        }
+    );
+
+    app.execute_msgs(
+        &depositor,
+        vec![market.deposit_stable(stable(1000000)).unwrap()],
+    )
+    .unwrap();
+    assert_eq!(
+        market.depositor(&app.querier(), depositor.clone()).unwrap(),
+        DepositorInfoResponse {
+            depositor: depositor.clone(),
+            total_deposited: Uint256::from(1000000u64),
+            total_redeemed: Uint256::zero(),
+            net_principal: Uint256::from(1000000u64),
+        }
+    );
+
+    // the failed deposit reverts the transfer of the sent coins
+    let res = app.execute_msgs(
+        &depositor,
+        vec![market.deposit_stable(Coin::new(1000000, "ukrw")).unwrap()],
+    );
+    assert!(res.is_err());
+    assert_eq!(app.balance(&depositor, "ukrw"), Uint128(1000000));
+    assert_eq!(app.balance(&market.addr(), "ukrw"), Uint128::zero());
+}
@@ -0,0 +1,77 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::HumanAddr;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitMsg {
+    pub owner_addr: HumanAddr,
+    pub overseer_contract: HumanAddr,
+    /// Number of epochs kept, the oldest are dropped beyond it
+    pub max_history: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    ////////////////////
+    /// Owner operations
+    ////////////////////
+    UpdateConfig {
+        owner_addr: Option<HumanAddr>,
+        overseer_contract: Option<HumanAddr>,
+        max_history: Option<u64>,
+    },
+
+    ////////////////////
+    /// Overseer operations
+    ////////////////////
+    /// Pushed by the overseer on every epoch operations
+    RecordEpoch { height: u64, kpis: EpochKpis },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    /// Epochs recorded between `start_height` and `end_height`,
+    /// both inclusive, in ascending order
+    EpochKpis {
+        start_height: Option<u64>,
+        end_height: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// Indicators of an epoch, the volumes are
+/// accumulated since the previous epoch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochKpis {
+    /// deposit rate per block
+    pub deposit_rate: Decimal256,
+    pub utilization_ratio: Decimal256,
+    /// value of the collaterals liquidated, in stable denom
+    pub liquidation_volume: Uint256,
+    /// loans left uncovered by the collaterals of the
+    /// positions liquidated in full
+    pub new_bad_debt: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner_addr: HumanAddr,
+    pub overseer_contract: HumanAddr,
+    pub max_history: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochKpisResponseElem {
+    pub height: u64,
+    pub kpis: EpochKpis,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochKpisResponse {
+    pub epochs: Vec<EpochKpisResponseElem>,
+}
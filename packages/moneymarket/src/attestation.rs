@@ -0,0 +1,44 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::HumanAddr;
+
+/// Interface of the attestation contracts the market can ask before
+/// deposits, borrows and redemptions, e.g. to restrict a pool to
+/// verified addresses
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Attest {
+        action: AttestedAction,
+        address: HumanAddr,
+        amount: Uint256,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestedAction {
+    Deposit,
+    Borrow,
+    Redeem,
+}
+
+impl std::fmt::Display for AttestedAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AttestedAction::Deposit => write!(f, "deposit"),
+            AttestedAction::Borrow => write!(f, "borrow"),
+            AttestedAction::Redeem => write!(f, "redeem"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AttestResponse {
+    pub allowed: bool,
+    /// Why the action is denied
+    pub reason: Option<String>,
+}
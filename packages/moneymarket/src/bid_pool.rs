@@ -0,0 +1,112 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::HumanAddr;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitMsg {
+    pub owner: HumanAddr,
+    pub liquidation_contract: HumanAddr,
+    pub overseer_contract: HumanAddr,
+    pub oracle_contract: HumanAddr,
+    pub swap_router: HumanAddr,
+    pub stable_denom: String,
+    /// Max slippage from the oracle price when
+    /// selling liquidated collaterals
+    pub max_slippage: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    ////////////////////
+    /// Owner operations
+    ////////////////////
+    UpdateConfig {
+        owner: Option<HumanAddr>,
+        swap_router: Option<HumanAddr>,
+        max_slippage: Option<Decimal256>,
+    },
+    /// Bid slots the pool spreads its stable over at each roll,
+    /// the stable left by the slot ratios stays idle for withdrawals
+    UpdateStrategy { slots: Vec<BidSlot> },
+
+    ////////////////////
+    /// User operations
+    ////////////////////
+    /// Deposit stable into the pool for pool shares
+    Deposit {},
+    /// Burn pool shares for their value, paid from the idle stable
+    Withdraw { shares: Uint256 },
+
+    /////////////////////////////
+    /// Permissionless operations
+    /////////////////////////////
+    /// Retract the pool bids and place them again following the strategy
+    RollBids {},
+    /// Liquidate `borrower` through the overseer, the pool bids
+    /// buying the liquidated collaterals
+    Liquidate { borrower: HumanAddr },
+    /// Sell the liquidated `collateral_token` held by the
+    /// pool for stable through the swap router
+    SellCollateral { collateral_token: HumanAddr },
+
+    ////////////////////
+    /// Internal operations
+    ////////////////////
+    PlaceBids {},
+}
+
+/// Bid of the pool on a collateral; the liquidation contract
+/// keeps a single bid per bidder and collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidSlot {
+    pub collateral_token: HumanAddr,
+    pub premium_rate: Decimal256,
+    /// Share of the pool stable placed in the bid
+    pub ratio: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Strategy {},
+    Pool {},
+    Shares { depositor: HumanAddr },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: HumanAddr,
+    pub liquidation_contract: HumanAddr,
+    pub overseer_contract: HumanAddr,
+    pub oracle_contract: HumanAddr,
+    pub swap_router: HumanAddr,
+    pub stable_denom: String,
+    pub max_slippage: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyResponse {
+    pub slots: Vec<BidSlot>,
+}
+
+/// Pool value in stable denom, the held
+/// collaterals valued at the oracle price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolResponse {
+    pub total_shares: Uint256,
+    pub idle_amount: Uint256,
+    pub bid_amount: Uint256,
+    pub collateral_value: Uint256,
+    pub total_value: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SharesResponse {
+    pub depositor: HumanAddr,
+    pub shares: Uint256,
+    pub value: Uint256,
+}
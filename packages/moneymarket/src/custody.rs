@@ -1,8 +1,8 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_bignumber::Uint256;
-use cosmwasm_std::HumanAddr;
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Binary, HumanAddr};
 use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -38,9 +38,26 @@ pub enum HandleMsg {
 
     /// Update config
     UpdateConfig {
-        owner: Option<HumanAddr>,
         liquidation_contract: Option<HumanAddr>,
     },
+    /// Propose `new_owner`, who must accept the ownership before
+    /// `expiry_height`
+    ProposeNewOwner {
+        new_owner: HumanAddr,
+        expiry_height: u64,
+    },
+    /// (new owner) Accept the proposed ownership
+    AcceptOwnership {},
+    /// Claim the bAsset rewards through `reward_claimer`,
+    /// or the reward contract of the hub when unset
+    UpdateRewardClaimer {
+        reward_claimer: Option<RewardClaimer>,
+    },
+    /// Follow the operational mode of the protocol controller
+    RegisterProtocolController { protocol_controller: HumanAddr },
+    /// Keep the collateral of rebasing tokens in shares of the
+    /// custody balance instead of fixed amounts
+    UpdateShareAccounting { enabled: bool },
     /// Make specified amount of tokens unspendable
     LockCollateral {
         borrower: HumanAddr,
@@ -51,12 +68,36 @@ pub enum HandleMsg {
         borrower: HumanAddr,
         amount: Uint256,
     },
+    /// Move spendable tokens of the locker to the
+    /// borrower as locked collateral
+    LockCollateralFor {
+        locker: HumanAddr,
+        borrower: HumanAddr,
+        amount: Uint256,
+    },
+    /// Return locked collateral of the borrower
+    /// to the locker as spendable tokens
+    UnlockCollateralFor {
+        locker: HumanAddr,
+        borrower: HumanAddr,
+        amount: Uint256,
+    },
+    /// Move locked collateral of `from` to `to` as locked collateral
+    TransferCollateral {
+        from: HumanAddr,
+        to: HumanAddr,
+        amount: Uint256,
+    },
     /// Claim bAsset rewards and distribute claimed rewards
-    /// to market and overseer contracts
-    DistributeRewards {},
+    /// to `reward_recipients` by ratio, the overseer by default
+    DistributeRewards {
+        reward_recipients: Option<Vec<(HumanAddr, Decimal256)>>,
+    },
 
-    /// (internal) Send withdrawn rewards to market & overseer
-    DistributeHook {},
+    /// (internal) Send withdrawn rewards to the reward recipients
+    DistributeHook {
+        reward_recipients: Option<Vec<(HumanAddr, Decimal256)>>,
+    },
 
     /// (internal) Swap all coins to stable_denom
     SwapToStableDenom {},
@@ -76,6 +117,10 @@ pub enum HandleMsg {
     /// If the amount is not given,
     /// return all spendable collateral
     WithdrawCollateral { amount: Option<Uint256> },
+
+    /// Query the protocol controller again instead of
+    /// waiting for the cached mode to expire
+    RefreshProtocolMode {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -89,6 +134,7 @@ pub enum Cw20HookMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
     Borrower {
         address: HumanAddr,
     },
@@ -96,6 +142,27 @@ pub enum QueryMsg {
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
     },
+    RewardClaimer {},
+    ShareAccounting {},
+    SharesToAmount {
+        shares: Uint256,
+    },
+    AmountToShares {
+        amount: Uint256,
+    },
+}
+
+/// External contract executed with `claim_msg` to claim
+/// the bAsset rewards to the custody
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardClaimer {
+    pub contract_addr: HumanAddr,
+    pub claim_msg: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardClaimerResponse {
+    pub reward_claimer: Option<RewardClaimer>,
 }
 
 // We define a custom struct for each query response
@@ -125,6 +192,21 @@ pub struct BorrowersResponse {
     pub borrowers: Vec<BorrowerResponse>,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShareAccountingResponse {
+    pub enabled: bool,
+    pub total_shares: Uint256,
+    pub collateral_balance: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShareConversionResponse {
+    pub shares: Uint256,
+    pub amount: Uint256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BAssetInfo {
     pub name: String,
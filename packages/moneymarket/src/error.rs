@@ -0,0 +1,1145 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::StdError;
+use std::fmt;
+
+/// Failures other contracts and bots can branch on. The stable code of
+/// each variant prefixes its message, e.g.
+/// `[E1005] Not enough uusd available; borrow demand too high`,
+/// and is recovered from an error message with `parse_error_code`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContractError {
+    // 1xxx: market
+    ZeroDeposit {
+        denom: String,
+    },
+    DepositTooSmall {
+        min_amount: Uint256,
+        denom: String,
+    },
+    ZeroRedeem {},
+    RedeemTooSmall {
+        min_amount: Uint256,
+        denom: String,
+    },
+    NoLiquidity {
+        denom: String,
+    },
+    ZeroRepay {
+        denom: String,
+    },
+    BorrowExceedsLimit {
+        borrow_limit: Uint256,
+    },
+    GlobalBorrowCapExceeded {
+        cap: Uint256,
+    },
+    AccountBorrowCapExceeded {
+        cap: Uint256,
+    },
+    MaxBorrowFactorExceeded {
+        denom: String,
+    },
+    DepositsDisabled {},
+    RedemptionsDisabled {},
+    AttestationDenied {
+        action: String,
+        reason: String,
+    },
+    BorrowsDisabled {},
+    RepaymentsDisabled {},
+    NoBootstrapThreshold {},
+    BootstrapEndPassed {},
+    BootstrapThresholdsReached {},
+    NotBootstrapping {},
+    BootstrapBorrowsDisabled {},
+    LoanTransferToBorrower {},
+    InitialDepositRequired {
+        amount: u128,
+        denom: String,
+    },
+    BlockHeightBeforeInterestUpdate {},
+    BlockHeightBeforeRewardUpdate {},
+    CreditLineDefaulted {},
+    CreditLineCapExceeded {
+        cap: Uint256,
+    },
+    WithdrawExceedsEscrow {},
+    NoCreditLine {},
+    InsufficientCreditCollateral {
+        required_value: Decimal256,
+    },
+    Cw20StableHeld {
+        token: String,
+    },
+    Cw20StableNotRegistered {
+        token: String,
+    },
+    ZeroSwap {
+        denom: String,
+    },
+    Cw20StableHeldExceeded {
+        token: String,
+        held_amount: Uint256,
+    },
+    FloorPriceAboveStartPrice {},
+    DebtAuctionsDisabled {},
+    NoDebtAuction {},
+    ZeroBid {
+        denom: String,
+    },
+    BidTooSmall {},
+    DecimalsWithoutAmount {},
+    DepositAmountMismatch {
+        sent_amount: Uint256,
+        amount: Uint256,
+    },
+    BurnExceedsSupply {
+        supply: Uint256,
+    },
+    ZeroDonation {},
+    DonationAmountMismatch {
+        sent_amount: Uint256,
+        amount: Uint256,
+    },
+    NoDepositsToDonate {},
+    NoExchangeRateOracle {},
+    ZeroFlashLoan {},
+    FlashLoanInProgress {},
+    FlashLoanLiquidity {
+        available: Uint256,
+    },
+    NoFlashLoan {},
+    FlashLoanOverpaid {
+        max_repay: Uint256,
+    },
+    FlashLoanNotRepaid {
+        required: Uint256,
+        repaid: Uint256,
+    },
+    ZeroFlashMint {},
+    FlashMintInProgress {},
+    NoFlashMint {},
+    FlashMintOverpaid {
+        max_repay: Uint256,
+    },
+    FlashMintNotRepaid {
+        required: Uint256,
+        repaid: Uint256,
+    },
+    NetDepositCapExceeded {
+        cap: Uint256,
+        denom: String,
+        until_height: u64,
+    },
+    NetRedemptionCapExceeded {
+        cap: Uint256,
+        denom: String,
+        until_height: u64,
+    },
+    IdleStableDeployed {},
+    NoIdleDeployment {},
+    UtilizationTooHigh {
+        utilization: Decimal256,
+    },
+    NoIdleStable {},
+    NoIdleStableDeployed {},
+    RecallExceedsDeployed {
+        deployed_amount: Uint256,
+    },
+    ReservesExceedCash {
+        reserves: Decimal256,
+        cash: Decimal256,
+    },
+    KeeperBountiesDisabled {},
+    NoLoan {},
+    PositionNotStale {
+        stale_height: u64,
+    },
+    AmortizingLoansDisabled {},
+    ZeroLoan {},
+    InvalidInstallments {
+        max_installments: u32,
+    },
+    AmortizingLoanOpen {},
+    PayoffTooSmall {
+        remaining_principal: Uint256,
+    },
+    NoAmortizingLoan {},
+    FreeRatioTooHigh {},
+    RedemptionQueueEmpty {},
+    NotQueueHead {},
+    RedemptionLiquidity {},
+    ZeroWithdraw {},
+    WithdrawExceedsReserves {
+        reserves: Uint256,
+    },
+    ShadowModelIsLive {},
+    NoShadowModel {},
+    InvalidStableDenom {
+        denom: String,
+    },
+    StableDenomAccepted {
+        denom: String,
+    },
+    StableDenomNotAccepted {
+        denom: String,
+    },
+    NotStableRateLoan {},
+    StableRateWithinThreshold {
+        stable_rate: Decimal256,
+    },
+    StableRateDisabled {},
+    NoStateSnapshot {
+        height: u64,
+    },
+    NoTaxParams {},
+    ColdTrancheInitialized {},
+    NoUnbondingColdDeposits {},
+    ColdDepositsUnbonding {
+        release_height: u64,
+    },
+    ColdTrancheNotInitialized {},
+    DustForgivenessTooHigh {
+        max: Uint256,
+    },
+
+    // 2xxx: overseer
+    TooManyCollateralTypes {
+        max: u64,
+    },
+    UnlockExceedsLockedCollateral {},
+    UnlockExceedsBorrowLimit {
+        borrow_limit: Uint256,
+    },
+    SafelyCollateralized {},
+    StalePrices {
+        valid_from: u64,
+        prices: String,
+    },
+    BatchTooLarge {
+        max: u64,
+    },
+    EmptyBatch {},
+    NotSponsor {},
+    UnlockRejected {},
+    LiquidatedCollateralNotFound {},
+    InvalidGuardianThreshold {},
+    CloseFactorAboveOne {},
+    CollateralRegistered {},
+    EmergencyPriceExpired {},
+    ZeroEmergencyPrice {},
+    NoEmergencyPrice {},
+    NoEmergencyPriceProposal {},
+    EpochNotPassed {
+        last_executed_height: u64,
+    },
+    EpochStateUpdated {
+        last_executed_height: u64,
+    },
+    InboxRegistered {},
+    InboxNotRegistered {},
+    ZeroIndexPrice {},
+    InsuranceNotRegistered {},
+    NoInsurance {},
+    DepositRateInvariant {},
+    BufferFactorInvariant {},
+    EpochHeightInvariant {},
+    InvalidMaxLtvOverride {},
+    MaxLtvOverrideLocked {
+        raisable_at: u64,
+    },
+    PositionTransferToSelf {},
+    NoPositionTransfer {},
+    NoPositionTransferToSender {},
+    AcceptWithCollaterals {},
+    TransferSponsoredPosition {},
+    PriceDivergence {
+        token: String,
+    },
+    TooManyProtectedCollaterals {
+        max: u64,
+    },
+    CollateralProtectedTwice {
+        token: String,
+    },
+    RebateLtvTooHigh {
+        max_ltv: Decimal256,
+    },
+    RebateDisabled {},
+    NoLtvCheckpoint {},
+    ZeroRewardShare {},
+    InvalidRewardShares {
+        total_ratio: Decimal256,
+    },
+    LockForSelf {},
+    NoSponsoredCollaterals {},
+    UnlockExceedsSponsored {},
+    CollateralNotRegistered {},
+    NoSwapRouter {},
+    SwapToSameToken {},
+    SwapInProgress {},
+    NoCollateralToSwap {},
+    NoSwapInProgress {},
+    SwapBelowMinimum {
+        received_amount: Uint256,
+    },
+    SwapExceedsBorrowLimit {
+        borrow_limit: Uint256,
+    },
+    CollateralProposed {},
+    EmptyOracleSource {},
+    LiquidityScoreTooHigh {
+        max: u8,
+    },
+    InvalidAuditHash {},
+    CollateralNotProposed {},
+    NotProposalWhitelisted {},
+    LiquidationAwaitingPriceUpdate {
+        prices_updated_at: u64,
+    },
+    EmergencyPriceTooLong {
+        max_expires_at: u64,
+    },
+
+    // 3xxx: custody
+    WithdrawExceedsSpendable {
+        spendable: Uint256,
+    },
+    LockExceedsSpendable {
+        spendable: Uint256,
+    },
+    UnlockExceedsLocked {
+        locked: Uint256,
+    },
+    TransferExceedsLocked {
+        locked: Uint256,
+    },
+    LiquidationExceedsLocked {
+        locked: Uint256,
+    },
+    SharesOutstanding {
+        total_shares: Uint256,
+    },
+    EmptyShares {},
+
+    // 9xxx: shared by the contracts
+    EmptyAdminBatch {},
+    NotOwnerOperation {},
+    EpochAlreadyExecuted {
+        last_executed_height: u64,
+    },
+    NoRegistry {},
+    MissingHookMsg {
+        msg: String,
+    },
+    RatioAboveOne {
+        name: String,
+    },
+    ZeroParameter {
+        name: String,
+    },
+    DepositsNotAllowed {
+        mode: String,
+    },
+    WithdrawalsNotAllowed {
+        mode: String,
+    },
+    NoProtocolController {},
+    ExpiryHeightPassed {},
+    NoOwnershipProposal {},
+    OwnershipProposalExpired {
+        expiry_height: u64,
+    },
+    CoinAmountOverflow {
+        amount: Uint256,
+    },
+    DecimalsTooLarge {
+        max_decimals: u8,
+    },
+    ScaledAmountOverflow {
+        amount: Uint256,
+        decimals: u8,
+    },
+    AmountBelowSmallestUnit {
+        amount: Uint256,
+    },
+    BorrowsNotAllowed {
+        mode: String,
+    },
+}
+
+impl ContractError {
+    pub fn code(&self) -> u16 {
+        match self {
+            ContractError::ZeroDeposit { .. } => 1001,
+            ContractError::DepositTooSmall { .. } => 1002,
+            ContractError::ZeroRedeem { .. } => 1003,
+            ContractError::RedeemTooSmall { .. } => 1004,
+            ContractError::NoLiquidity { .. } => 1005,
+            ContractError::ZeroRepay { .. } => 1006,
+            ContractError::BorrowExceedsLimit { .. } => 1007,
+            ContractError::GlobalBorrowCapExceeded { .. } => 1008,
+            ContractError::AccountBorrowCapExceeded { .. } => 1009,
+            ContractError::MaxBorrowFactorExceeded { .. } => 1010,
+            ContractError::DepositsDisabled { .. } => 1012,
+            ContractError::RedemptionsDisabled { .. } => 1013,
+            ContractError::AttestationDenied { .. } => 1014,
+            ContractError::BorrowsDisabled { .. } => 1015,
+            ContractError::RepaymentsDisabled { .. } => 1016,
+            ContractError::NoBootstrapThreshold { .. } => 1017,
+            ContractError::BootstrapEndPassed { .. } => 1018,
+            ContractError::BootstrapThresholdsReached { .. } => 1019,
+            ContractError::NotBootstrapping { .. } => 1020,
+            ContractError::BootstrapBorrowsDisabled { .. } => 1021,
+            ContractError::LoanTransferToBorrower { .. } => 1022,
+            ContractError::InitialDepositRequired { .. } => 1023,
+            ContractError::BlockHeightBeforeInterestUpdate { .. } => 1024,
+            ContractError::BlockHeightBeforeRewardUpdate { .. } => 1025,
+            ContractError::CreditLineDefaulted { .. } => 1026,
+            ContractError::CreditLineCapExceeded { .. } => 1027,
+            ContractError::WithdrawExceedsEscrow { .. } => 1028,
+            ContractError::NoCreditLine { .. } => 1029,
+            ContractError::InsufficientCreditCollateral { .. } => 1030,
+            ContractError::Cw20StableHeld { .. } => 1031,
+            ContractError::Cw20StableNotRegistered { .. } => 1032,
+            ContractError::ZeroSwap { .. } => 1033,
+            ContractError::Cw20StableHeldExceeded { .. } => 1034,
+            ContractError::FloorPriceAboveStartPrice { .. } => 1035,
+            ContractError::DebtAuctionsDisabled { .. } => 1036,
+            ContractError::NoDebtAuction { .. } => 1037,
+            ContractError::ZeroBid { .. } => 1038,
+            ContractError::BidTooSmall { .. } => 1039,
+            ContractError::DecimalsWithoutAmount { .. } => 1040,
+            ContractError::DepositAmountMismatch { .. } => 1041,
+            ContractError::BurnExceedsSupply { .. } => 1043,
+            ContractError::ZeroDonation { .. } => 1044,
+            ContractError::DonationAmountMismatch { .. } => 1045,
+            ContractError::NoDepositsToDonate { .. } => 1046,
+            ContractError::NoExchangeRateOracle { .. } => 1047,
+            ContractError::ZeroFlashLoan { .. } => 1048,
+            ContractError::FlashLoanInProgress { .. } => 1049,
+            ContractError::FlashLoanLiquidity { .. } => 1050,
+            ContractError::NoFlashLoan { .. } => 1051,
+            ContractError::FlashLoanOverpaid { .. } => 1052,
+            ContractError::FlashLoanNotRepaid { .. } => 1053,
+            ContractError::ZeroFlashMint { .. } => 1054,
+            ContractError::FlashMintInProgress { .. } => 1055,
+            ContractError::NoFlashMint { .. } => 1056,
+            ContractError::FlashMintOverpaid { .. } => 1057,
+            ContractError::FlashMintNotRepaid { .. } => 1058,
+            ContractError::NetDepositCapExceeded { .. } => 1059,
+            ContractError::NetRedemptionCapExceeded { .. } => 1060,
+            ContractError::IdleStableDeployed { .. } => 1061,
+            ContractError::NoIdleDeployment { .. } => 1062,
+            ContractError::UtilizationTooHigh { .. } => 1063,
+            ContractError::NoIdleStable { .. } => 1064,
+            ContractError::NoIdleStableDeployed { .. } => 1065,
+            ContractError::RecallExceedsDeployed { .. } => 1066,
+            ContractError::ReservesExceedCash { .. } => 1067,
+            ContractError::KeeperBountiesDisabled { .. } => 1069,
+            ContractError::NoLoan { .. } => 1070,
+            ContractError::PositionNotStale { .. } => 1071,
+            ContractError::AmortizingLoansDisabled { .. } => 1072,
+            ContractError::ZeroLoan { .. } => 1073,
+            ContractError::InvalidInstallments { .. } => 1074,
+            ContractError::AmortizingLoanOpen { .. } => 1075,
+            ContractError::PayoffTooSmall { .. } => 1076,
+            ContractError::NoAmortizingLoan { .. } => 1077,
+            ContractError::FreeRatioTooHigh { .. } => 1078,
+            ContractError::RedemptionQueueEmpty { .. } => 1079,
+            ContractError::NotQueueHead { .. } => 1080,
+            ContractError::RedemptionLiquidity { .. } => 1081,
+            ContractError::ZeroWithdraw { .. } => 1082,
+            ContractError::WithdrawExceedsReserves { .. } => 1083,
+            ContractError::ShadowModelIsLive { .. } => 1084,
+            ContractError::NoShadowModel { .. } => 1085,
+            ContractError::InvalidStableDenom { .. } => 1086,
+            ContractError::StableDenomAccepted { .. } => 1087,
+            ContractError::StableDenomNotAccepted { .. } => 1088,
+            ContractError::NotStableRateLoan { .. } => 1089,
+            ContractError::StableRateWithinThreshold { .. } => 1090,
+            ContractError::StableRateDisabled { .. } => 1091,
+            ContractError::NoStateSnapshot { .. } => 1092,
+            ContractError::NoTaxParams { .. } => 1093,
+            ContractError::ColdTrancheInitialized { .. } => 1094,
+            ContractError::NoUnbondingColdDeposits { .. } => 1095,
+            ContractError::ColdDepositsUnbonding { .. } => 1096,
+            ContractError::ColdTrancheNotInitialized { .. } => 1097,
+            ContractError::DustForgivenessTooHigh { .. } => 1098,
+            ContractError::TooManyCollateralTypes { .. } => 2001,
+            ContractError::UnlockExceedsLockedCollateral { .. } => 2002,
+            ContractError::UnlockExceedsBorrowLimit { .. } => 2003,
+            ContractError::SafelyCollateralized { .. } => 2004,
+            ContractError::StalePrices { .. } => 2005,
+            ContractError::BatchTooLarge { .. } => 2006,
+            ContractError::EmptyBatch { .. } => 2007,
+            ContractError::NotSponsor { .. } => 2008,
+            ContractError::UnlockRejected { .. } => 2009,
+            ContractError::LiquidatedCollateralNotFound { .. } => 2010,
+            ContractError::InvalidGuardianThreshold { .. } => 2011,
+            ContractError::CloseFactorAboveOne { .. } => 2012,
+            ContractError::CollateralRegistered { .. } => 2013,
+            ContractError::EmergencyPriceExpired { .. } => 2014,
+            ContractError::ZeroEmergencyPrice { .. } => 2015,
+            ContractError::NoEmergencyPrice { .. } => 2016,
+            ContractError::NoEmergencyPriceProposal { .. } => 2017,
+            ContractError::EpochNotPassed { .. } => 2018,
+            ContractError::EpochStateUpdated { .. } => 2019,
+            ContractError::InboxRegistered { .. } => 2020,
+            ContractError::InboxNotRegistered { .. } => 2021,
+            ContractError::ZeroIndexPrice { .. } => 2022,
+            ContractError::InsuranceNotRegistered { .. } => 2023,
+            ContractError::NoInsurance { .. } => 2024,
+            ContractError::DepositRateInvariant { .. } => 2025,
+            ContractError::BufferFactorInvariant { .. } => 2026,
+            ContractError::EpochHeightInvariant { .. } => 2027,
+            ContractError::InvalidMaxLtvOverride { .. } => 2028,
+            ContractError::MaxLtvOverrideLocked { .. } => 2029,
+            ContractError::PositionTransferToSelf { .. } => 2030,
+            ContractError::NoPositionTransfer { .. } => 2031,
+            ContractError::NoPositionTransferToSender { .. } => 2032,
+            ContractError::AcceptWithCollaterals { .. } => 2033,
+            ContractError::TransferSponsoredPosition { .. } => 2034,
+            ContractError::PriceDivergence { .. } => 2035,
+            ContractError::TooManyProtectedCollaterals { .. } => 2036,
+            ContractError::CollateralProtectedTwice { .. } => 2037,
+            ContractError::RebateLtvTooHigh { .. } => 2038,
+            ContractError::RebateDisabled { .. } => 2039,
+            ContractError::NoLtvCheckpoint { .. } => 2040,
+            ContractError::ZeroRewardShare { .. } => 2041,
+            ContractError::InvalidRewardShares { .. } => 2042,
+            ContractError::LockForSelf { .. } => 2043,
+            ContractError::NoSponsoredCollaterals { .. } => 2044,
+            ContractError::UnlockExceedsSponsored { .. } => 2045,
+            ContractError::CollateralNotRegistered { .. } => 2046,
+            ContractError::NoSwapRouter { .. } => 2047,
+            ContractError::SwapToSameToken { .. } => 2048,
+            ContractError::SwapInProgress { .. } => 2049,
+            ContractError::NoCollateralToSwap { .. } => 2050,
+            ContractError::NoSwapInProgress { .. } => 2051,
+            ContractError::SwapBelowMinimum { .. } => 2052,
+            ContractError::SwapExceedsBorrowLimit { .. } => 2053,
+            ContractError::CollateralProposed { .. } => 2054,
+            ContractError::EmptyOracleSource { .. } => 2055,
+            ContractError::LiquidityScoreTooHigh { .. } => 2056,
+            ContractError::InvalidAuditHash { .. } => 2057,
+            ContractError::CollateralNotProposed { .. } => 2058,
+            ContractError::NotProposalWhitelisted { .. } => 2059,
+            ContractError::LiquidationAwaitingPriceUpdate { .. } => 2060,
+            ContractError::EmergencyPriceTooLong { .. } => 2061,
+            ContractError::WithdrawExceedsSpendable { .. } => 3001,
+            ContractError::LockExceedsSpendable { .. } => 3002,
+            ContractError::UnlockExceedsLocked { .. } => 3003,
+            ContractError::TransferExceedsLocked { .. } => 3004,
+            ContractError::LiquidationExceedsLocked { .. } => 3005,
+            ContractError::SharesOutstanding { .. } => 3006,
+            ContractError::EmptyShares { .. } => 3007,
+            ContractError::EmptyAdminBatch { .. } => 9001,
+            ContractError::NotOwnerOperation { .. } => 9002,
+            ContractError::EpochAlreadyExecuted { .. } => 9003,
+            ContractError::NoRegistry { .. } => 9004,
+            ContractError::MissingHookMsg { .. } => 9005,
+            ContractError::RatioAboveOne { .. } => 9006,
+            ContractError::ZeroParameter { .. } => 9007,
+            ContractError::DepositsNotAllowed { .. } => 9008,
+            ContractError::WithdrawalsNotAllowed { .. } => 9009,
+            ContractError::NoProtocolController { .. } => 9010,
+            ContractError::ExpiryHeightPassed { .. } => 9011,
+            ContractError::NoOwnershipProposal { .. } => 9012,
+            ContractError::OwnershipProposalExpired { .. } => 9013,
+            ContractError::CoinAmountOverflow { .. } => 9014,
+            ContractError::DecimalsTooLarge { .. } => 9015,
+            ContractError::ScaledAmountOverflow { .. } => 9016,
+            ContractError::AmountBelowSmallestUnit { .. } => 9017,
+            ContractError::BorrowsNotAllowed { .. } => 9018,
+        }
+    }
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[E{}] ", self.code())?;
+        match self {
+            ContractError::ZeroDeposit { denom } => {
+                write!(f, "Deposit amount must be greater than 0 {}", denom)
+            }
+            ContractError::DepositTooSmall { min_amount, denom } => {
+                write!(f, "Deposit amount must be at least {}{}", min_amount, denom)
+            }
+            ContractError::ZeroRedeem {} => write!(f, "Redeem amount must be greater than 0"),
+            ContractError::RedeemTooSmall { min_amount, denom } => {
+                write!(f, "Redeem amount must be at least {}{}", min_amount, denom)
+            }
+            ContractError::NoLiquidity { denom } => {
+                write!(f, "Not enough {} available; borrow demand too high", denom)
+            }
+            ContractError::ZeroRepay { denom } => {
+                write!(f, "Repay amount must be greater than 0 {}", denom)
+            }
+            ContractError::BorrowExceedsLimit { borrow_limit } => write!(
+                f,
+                "Borrow amount too high; Loan liability becomes greater than borrow limit: {}",
+                borrow_limit
+            ),
+            ContractError::GlobalBorrowCapExceeded { cap } => {
+                write!(f, "Borrow amount exceeds the global borrow cap: {}", cap)
+            }
+            ContractError::AccountBorrowCapExceeded { cap } => {
+                write!(
+                    f,
+                    "Borrow amount exceeds the per account borrow cap: {}",
+                    cap
+                )
+            }
+            ContractError::MaxBorrowFactorExceeded { denom } => write!(
+                f,
+                "Exceeds {} max borrow factor; borrow demand too high",
+                denom
+            ),
+            ContractError::DepositsDisabled {} => write!(f, "Deposits are disabled"),
+            ContractError::RedemptionsDisabled {} => write!(f, "Redemptions are disabled"),
+            ContractError::AttestationDenied { action, reason } => {
+                write!(f, "The {} is not attested: {}", action, reason)
+            }
+            ContractError::BorrowsDisabled {} => write!(f, "Borrows are disabled"),
+            ContractError::RepaymentsDisabled {} => write!(f, "Repayments are disabled"),
+            ContractError::NoBootstrapThreshold {} => {
+                write!(f, "Either end_height or tvl_threshold must be given")
+            }
+            ContractError::BootstrapEndPassed {} => {
+                write!(f, "end_height must be greater than the current height")
+            }
+            ContractError::BootstrapThresholdsReached {} => {
+                write!(f, "The bootstrap thresholds are already reached")
+            }
+            ContractError::NotBootstrapping {} => write!(f, "The market is not bootstrapping"),
+            ContractError::BootstrapBorrowsDisabled {} => {
+                write!(f, "Borrows are disabled until the market bootstrap ends")
+            }
+            ContractError::LoanTransferToBorrower {} => {
+                write!(f, "Cannot transfer a loan to a borrower with a loan")
+            }
+            ContractError::InitialDepositRequired { amount, denom } => {
+                write!(f, "Must deposit initial funds {:?}{:?}", amount, denom)
+            }
+            ContractError::BlockHeightBeforeInterestUpdate {} => {
+                write!(f, "block_height must bigger than last_interest_updated")
+            }
+            ContractError::BlockHeightBeforeRewardUpdate {} => {
+                write!(f, "block_height must bigger than last_reward_updated")
+            }
+            ContractError::CreditLineDefaulted {} => write!(f, "Credit line is defaulted"),
+            ContractError::CreditLineCapExceeded { cap } => {
+                write!(f, "Draw amount exceeds the credit line cap: {}", cap)
+            }
+            ContractError::WithdrawExceedsEscrow {} => {
+                write!(f, "Withdraw amount cannot exceed the escrowed collateral")
+            }
+            ContractError::NoCreditLine {} => write!(f, "No credit line for the strategy"),
+            ContractError::InsufficientCreditCollateral { required_value } => write!(
+                f,
+                "Insufficient credit line collateral; Required collateral value: {}",
+                required_value
+            ),
+            ContractError::Cw20StableHeld { token } => write!(
+                f,
+                "Cannot change the decimals of {} while the market holds it",
+                token
+            ),
+            ContractError::Cw20StableNotRegistered { token } => {
+                write!(f, "CW20 stable not registered: {}", token)
+            }
+            ContractError::ZeroSwap { denom } => {
+                write!(f, "Swap amount must be greater than 0 {}", denom)
+            }
+            ContractError::Cw20StableHeldExceeded { token, held_amount } => {
+                write!(f, "Not enough {} held: {}", token, held_amount)
+            }
+            ContractError::FloorPriceAboveStartPrice {} => {
+                write!(f, "floor_price must be less than or equal to start_price")
+            }
+            ContractError::DebtAuctionsDisabled {} => write!(f, "Debt auctions are not enabled"),
+            ContractError::NoDebtAuction {} => write!(f, "No debt auction is running"),
+            ContractError::ZeroBid { denom } => {
+                write!(f, "Bid amount must be greater than 0 {}", denom)
+            }
+            ContractError::BidTooSmall {} => write!(f, "Bid amount too small to buy ANC"),
+            ContractError::DecimalsWithoutAmount {} => {
+                write!(f, "Deposit decimals cannot be given without an amount")
+            }
+            ContractError::DepositAmountMismatch {
+                sent_amount,
+                amount,
+            } => write!(
+                f,
+                "Sent amount {} does not match the deposit amount {}",
+                sent_amount, amount
+            ),
+            ContractError::BurnExceedsSupply { supply } => {
+                write!(f, "Burn amount exceeds the aterra supply: {}", supply)
+            }
+            ContractError::ZeroDonation {} => write!(f, "Donation amount must be greater than 0"),
+            ContractError::DonationAmountMismatch {
+                sent_amount,
+                amount,
+            } => write!(
+                f,
+                "Sent amount {} does not match the donation amount {}",
+                sent_amount, amount
+            ),
+            ContractError::NoDepositsToDonate {} => write!(f, "No deposits to donate to"),
+            ContractError::NoExchangeRateOracle {} => {
+                write!(f, "No exchange rate oracle is registered")
+            }
+            ContractError::ZeroFlashLoan {} => {
+                write!(f, "Flash loan amount must be greater than 0")
+            }
+            ContractError::FlashLoanInProgress {} => {
+                write!(f, "A flash loan is already in progress")
+            }
+            ContractError::FlashLoanLiquidity { available } => {
+                write!(f, "Not enough liquidity for the flash loan: {}", available)
+            }
+            ContractError::NoFlashLoan {} => write!(f, "No flash loan in progress"),
+            ContractError::FlashLoanOverpaid { max_repay } => write!(
+                f,
+                "Cannot repay more than the flash loaned amount plus fee: {}",
+                max_repay
+            ),
+            ContractError::FlashLoanNotRepaid { required, repaid } => write!(
+                f,
+                "Flash loaned stable not repaid; required: {}, repaid: {}",
+                required, repaid
+            ),
+            ContractError::ZeroFlashMint {} => {
+                write!(f, "Flash mint amount must be greater than 0")
+            }
+            ContractError::FlashMintInProgress {} => {
+                write!(f, "A flash mint is already in progress")
+            }
+            ContractError::NoFlashMint {} => write!(f, "No flash mint in progress"),
+            ContractError::FlashMintOverpaid { max_repay } => write!(
+                f,
+                "Cannot repay more than the flash minted amount plus fee: {}",
+                max_repay
+            ),
+            ContractError::FlashMintNotRepaid { required, repaid } => write!(
+                f,
+                "Flash minted aterra not repaid; required: {}, repaid: {}",
+                required, repaid
+            ),
+            ContractError::NetDepositCapExceeded {
+                cap,
+                denom,
+                until_height,
+            } => write!(
+                f,
+                "Net deposits would exceed the cap of {}{} until height {}",
+                cap, denom, until_height
+            ),
+            ContractError::NetRedemptionCapExceeded {
+                cap,
+                denom,
+                until_height,
+            } => write!(
+                f,
+                "Net redemptions would exceed the cap of {}{} until height {}",
+                cap, denom, until_height
+            ),
+            ContractError::IdleStableDeployed {} => {
+                write!(f, "Recall the deployed stable before changing the venue")
+            }
+            ContractError::NoIdleDeployment {} => write!(f, "Idle deployment is not registered"),
+            ContractError::UtilizationTooHigh { utilization } => write!(
+                f,
+                "Utilization too high to deploy idle stable: {}",
+                utilization
+            ),
+            ContractError::NoIdleStable {} => write!(f, "No idle stable to deploy"),
+            ContractError::NoIdleStableDeployed {} => write!(f, "No idle stable deployed"),
+            ContractError::RecallExceedsDeployed { deployed_amount } => write!(
+                f,
+                "Recall amount must be between 0 and the deployed amount: {}",
+                deployed_amount
+            ),
+            ContractError::ReservesExceedCash { reserves, cash } => write!(
+                f,
+                "Invariant violated: reserves {} exceed the implied cash {}",
+                reserves, cash
+            ),
+            ContractError::KeeperBountiesDisabled {} => {
+                write!(f, "Keeper bounties are not enabled")
+            }
+            ContractError::NoLoan {} => write!(f, "Position has no loan"),
+            ContractError::PositionNotStale { stale_height } => {
+                write!(f, "Position is not stale until block {}", stale_height)
+            }
+            ContractError::AmortizingLoansDisabled {} => {
+                write!(f, "Amortizing loans are not enabled")
+            }
+            ContractError::ZeroLoan {} => write!(f, "Loan amount must be greater than 0"),
+            ContractError::InvalidInstallments { max_installments } => {
+                write!(f, "installments must be between 1 and {}", max_installments)
+            }
+            ContractError::AmortizingLoanOpen {} => write!(f, "An amortizing loan is already open"),
+            ContractError::PayoffTooSmall {
+                remaining_principal,
+            } => write!(
+                f,
+                "Payoff amount must cover the remaining principal: {}",
+                remaining_principal
+            ),
+            ContractError::NoAmortizingLoan {} => write!(f, "No amortizing loan is open"),
+            ContractError::FreeRatioTooHigh {} => write!(f, "free_ratio must be less than 1"),
+            ContractError::RedemptionQueueEmpty {} => write!(f, "Redemption queue is empty"),
+            ContractError::NotQueueHead {} => write!(
+                f,
+                "Only the redemption at the head of the queue can be claimed"
+            ),
+            ContractError::RedemptionLiquidity {} => {
+                write!(f, "Not enough liquidity to service the redemption")
+            }
+            ContractError::ZeroWithdraw {} => write!(f, "Withdraw amount must be greater than 0"),
+            ContractError::WithdrawExceedsReserves { reserves } => write!(
+                f,
+                "Withdraw amount cannot exceed the reserves: {}",
+                reserves
+            ),
+            ContractError::ShadowModelIsLive {} => {
+                write!(f, "The shadow interest model cannot be the live one")
+            }
+            ContractError::NoShadowModel {} => write!(f, "No shadow interest model is registered"),
+            ContractError::InvalidStableDenom { denom } => {
+                write!(f, "Invalid stable denom: {}", denom)
+            }
+            ContractError::StableDenomAccepted { denom } => {
+                write!(f, "Stable denom already accepted: {}", denom)
+            }
+            ContractError::StableDenomNotAccepted { denom } => {
+                write!(f, "Stable denom not accepted: {}", denom)
+            }
+            ContractError::NotStableRateLoan {} => {
+                write!(f, "Loan is not borrowed at a stable rate")
+            }
+            ContractError::StableRateWithinThreshold { stable_rate } => write!(
+                f,
+                "Stable rate is within the rebalance threshold; current stable rate: {}",
+                stable_rate
+            ),
+            ContractError::StableRateDisabled {} => {
+                write!(f, "Stable rate borrowing is not enabled")
+            }
+            ContractError::NoStateSnapshot { height } => {
+                write!(f, "No state snapshot at or before height {}", height)
+            }
+            ContractError::NoTaxParams {} => write!(f, "No tax parameters cached"),
+            ContractError::ColdTrancheInitialized {} => {
+                write!(f, "Cold tranche already initialized")
+            }
+            ContractError::NoUnbondingColdDeposits {} => write!(f, "No unbonding cold deposits"),
+            ContractError::ColdDepositsUnbonding { release_height } => write!(
+                f,
+                "Cold deposits are unbonding until block {}",
+                release_height
+            ),
+            ContractError::ColdTrancheNotInitialized {} => {
+                write!(f, "Cold tranche is not initialized")
+            }
+            ContractError::DustForgivenessTooHigh { max } => {
+                write!(f, "max_amount cannot exceed {}", max)
+            }
+            ContractError::TooManyCollateralTypes { max } => {
+                write!(f, "Cannot lock more than {} collateral types", max)
+            }
+            ContractError::UnlockExceedsLockedCollateral {} => {
+                write!(f, "Unlock amount cannot exceed locked amount")
+            }
+            ContractError::UnlockExceedsBorrowLimit { borrow_limit } => write!(
+                f,
+                "Unlock amount too high; Loan liability becomes greater than borrow limit: {}",
+                borrow_limit
+            ),
+            ContractError::SafelyCollateralized {} => {
+                write!(f, "Cannot liquidate safely collateralized loan")
+            }
+            ContractError::StalePrices { valid_from, prices } => write!(
+                f,
+                "Stale collateral prices; valid from {}: {}",
+                valid_from, prices
+            ),
+            ContractError::BatchTooLarge { max } => {
+                write!(f, "Collateral batch cannot exceed {} entries", max)
+            }
+            ContractError::EmptyBatch {} => write!(f, "Collateral batch cannot be empty"),
+            ContractError::NotSponsor {} => write!(
+                f,
+                "Sponsored collaterals can only be unlocked by their locker"
+            ),
+            ContractError::UnlockRejected {} => write!(f, "Unlock rejected by the risk module"),
+            ContractError::LiquidatedCollateralNotFound {} => {
+                write!(f, "Liquidated collateral not found")
+            }
+            ContractError::InvalidGuardianThreshold {} => write!(
+                f,
+                "Guardian threshold must be between 1 and the number of guardians"
+            ),
+            ContractError::CloseFactorAboveOne {} => {
+                write!(f, "Close factor cannot be greater than 1")
+            }
+            ContractError::CollateralRegistered {} => {
+                write!(f, "Token is already registered as collateral")
+            }
+            ContractError::EmergencyPriceExpired {} => {
+                write!(f, "Emergency price must expire in the future")
+            }
+            ContractError::ZeroEmergencyPrice {} => write!(f, "Emergency price cannot be zero"),
+            ContractError::NoEmergencyPrice {} => {
+                write!(f, "No emergency price for the specified asset")
+            }
+            ContractError::NoEmergencyPriceProposal {} => {
+                write!(f, "No emergency price proposal for the specified asset")
+            }
+            ContractError::EpochNotPassed {
+                last_executed_height,
+            } => write!(
+                f,
+                "An epoch has not passed yet; last executed height: {}",
+                last_executed_height
+            ),
+            ContractError::EpochStateUpdated {
+                last_executed_height,
+            } => write!(
+                f,
+                "Epoch state already updated; last executed height: {}",
+                last_executed_height
+            ),
+            ContractError::InboxRegistered {} => write!(f, "Inbox already registered"),
+            ContractError::InboxNotRegistered {} => write!(f, "Inbox not registered"),
+            ContractError::ZeroIndexPrice {} => write!(f, "Index price cannot be zero"),
+            ContractError::InsuranceNotRegistered {} => {
+                write!(f, "Insurance contract is not registered")
+            }
+            ContractError::NoInsurance {} => write!(f, "No insurance attached to the position"),
+            ContractError::DepositRateInvariant {} => write!(
+                f,
+                "Invariant violated: threshold deposit rate exceeds the target deposit rate"
+            ),
+            ContractError::BufferFactorInvariant {} => {
+                write!(f, "Invariant violated: interest buffer factors exceed 1")
+            }
+            ContractError::EpochHeightInvariant {} => write!(
+                f,
+                "Invariant violated: epoch executed after the current height"
+            ),
+            ContractError::InvalidMaxLtvOverride {} => {
+                write!(f, "max_ltv_override must be between 0 and 1")
+            }
+            ContractError::MaxLtvOverrideLocked { raisable_at } => write!(
+                f,
+                "Max LTV override cannot be raised or removed before {}",
+                raisable_at
+            ),
+            ContractError::PositionTransferToSelf {} => {
+                write!(f, "Cannot transfer a position to the sender itself")
+            }
+            ContractError::NoPositionTransfer {} => write!(f, "No position transfer offered"),
+            ContractError::NoPositionTransferToSender {} => {
+                write!(f, "No position transfer offered to the sender")
+            }
+            ContractError::AcceptWithCollaterals {} => {
+                write!(f, "Cannot accept a position with collaterals locked")
+            }
+            ContractError::TransferSponsoredPosition {} => {
+                write!(f, "Cannot transfer a position with sponsored collaterals")
+            }
+            ContractError::PriceDivergence { token } => write!(
+                f,
+                "Oracle price of {} diverges from the DEX price; unlock is disabled",
+                token
+            ),
+            ContractError::TooManyProtectedCollaterals { max } => {
+                write!(f, "Cannot protect more than {} collateral types", max)
+            }
+            ContractError::CollateralProtectedTwice { token } => {
+                write!(f, "Collateral protected twice: {}", token)
+            }
+            ContractError::RebateLtvTooHigh { max_ltv } => write!(
+                f,
+                "rebate_ltv cannot exceed the max LTV of the collateral: {}",
+                max_ltv
+            ),
+            ContractError::RebateDisabled {} => write!(f, "Interest rebate is not enabled"),
+            ContractError::NoLtvCheckpoint {} => {
+                write!(f, "No LTV checkpoint exists for the borrower")
+            }
+            ContractError::ZeroRewardShare {} => {
+                write!(f, "Reward share ratio must be greater than 0")
+            }
+            ContractError::InvalidRewardShares { total_ratio } => {
+                write!(f, "Reward share ratios must sum to 1: {}", total_ratio)
+            }
+            ContractError::LockForSelf {} => {
+                write!(f, "Cannot lock collateral for the sender itself")
+            }
+            ContractError::NoSponsoredCollaterals {} => {
+                write!(f, "No collaterals locked for the borrower")
+            }
+            ContractError::UnlockExceedsSponsored {} => {
+                write!(f, "Unlock amount cannot exceed sponsored amount")
+            }
+            ContractError::CollateralNotRegistered {} => {
+                write!(f, "Token is not registered as collateral")
+            }
+            ContractError::NoSwapRouter {} => write!(f, "Swap router is not registered"),
+            ContractError::SwapToSameToken {} => {
+                write!(f, "Cannot swap a collateral to the same token")
+            }
+            ContractError::SwapInProgress {} => {
+                write!(f, "A collateral swap is already in progress")
+            }
+            ContractError::NoCollateralToSwap {} => write!(f, "No unlockable collateral to swap"),
+            ContractError::NoSwapInProgress {} => write!(f, "No collateral swap in progress"),
+            ContractError::SwapBelowMinimum { received_amount } => write!(
+                f,
+                "Swap received less than the minimum amount: {}",
+                received_amount
+            ),
+            ContractError::SwapExceedsBorrowLimit { borrow_limit } => write!(
+                f,
+                "Swap amount too low; Loan liability becomes greater than borrow limit: {}",
+                borrow_limit
+            ),
+            ContractError::CollateralProposed {} => {
+                write!(f, "Token is already proposed as collateral")
+            }
+            ContractError::EmptyOracleSource {} => write!(f, "oracle_source cannot be empty"),
+            ContractError::LiquidityScoreTooHigh { max } => {
+                write!(f, "liquidity_score cannot exceed {}", max)
+            }
+            ContractError::InvalidAuditHash {} => {
+                write!(f, "audit_hash must be a hex encoded sha256 hash")
+            }
+            ContractError::CollateralNotProposed {} => {
+                write!(f, "Token is not proposed as collateral")
+            }
+            ContractError::NotProposalWhitelisted {} => {
+                write!(f, "Token was not whitelisted through a proposal")
+            }
+            ContractError::LiquidationAwaitingPriceUpdate { prices_updated_at } => write!(
+                f,
+                "The position was liquidated at the prices of {}, waiting for a price update",
+                prices_updated_at
+            ),
+            ContractError::EmergencyPriceTooLong { max_expires_at } => write!(
+                f,
+                "Emergency price must expire at or before {}",
+                max_expires_at
+            ),
+            ContractError::WithdrawExceedsSpendable { spendable } => write!(
+                f,
+                "Withdraw amount cannot exceed the user's spendable amount: {}",
+                spendable
+            ),
+            ContractError::LockExceedsSpendable { spendable } => write!(
+                f,
+                "Lock amount cannot exceed the user's spendable amount: {}",
+                spendable
+            ),
+            ContractError::UnlockExceedsLocked { locked } => {
+                write!(f, "Unlock amount cannot exceed locked amount: {}", locked)
+            }
+            ContractError::TransferExceedsLocked { locked } => {
+                write!(f, "Transfer amount cannot exceed locked amount: {}", locked)
+            }
+            ContractError::LiquidationExceedsLocked { locked } => {
+                write!(
+                    f,
+                    "Liquidation amount cannot exceed locked amount: {}",
+                    locked
+                )
+            }
+            ContractError::SharesOutstanding { total_shares } => write!(
+                f,
+                "Cannot disable share accounting while shares are outstanding: {}",
+                total_shares
+            ),
+            ContractError::EmptyShares {} => write!(
+                f,
+                "Cannot deposit while the outstanding shares hold no collateral"
+            ),
+            ContractError::EmptyAdminBatch {} => write!(f, "Admin batch cannot be empty"),
+            ContractError::NotOwnerOperation {} => {
+                write!(f, "Only owner operations can be batched")
+            }
+            ContractError::EpochAlreadyExecuted {
+                last_executed_height,
+            } => write!(
+                f,
+                "Epoch operations already executed; last executed height: {}",
+                last_executed_height
+            ),
+            ContractError::NoRegistry {} => write!(f, "No registry is registered"),
+            ContractError::MissingHookMsg { msg } => write!(
+                f,
+                "Invalid request: \"{}\" message not included in request",
+                msg
+            ),
+            ContractError::RatioAboveOne { name } => {
+                write!(f, "{} must be less than or equal to 1", name)
+            }
+            ContractError::ZeroParameter { name } => write!(f, "{} must be greater than 0", name),
+            ContractError::DepositsNotAllowed { mode } => {
+                write!(f, "Deposits are not allowed in {} mode", mode)
+            }
+            ContractError::WithdrawalsNotAllowed { mode } => {
+                write!(f, "Withdrawals are not allowed in {} mode", mode)
+            }
+            ContractError::NoProtocolController {} => {
+                write!(f, "No protocol controller is registered")
+            }
+            ContractError::ExpiryHeightPassed {} => {
+                write!(f, "expiry_height must be greater than the current height")
+            }
+            ContractError::NoOwnershipProposal {} => {
+                write!(f, "No ownership transfer is proposed")
+            }
+            ContractError::OwnershipProposalExpired { expiry_height } => write!(
+                f,
+                "The ownership proposal expired at height {}",
+                expiry_height
+            ),
+            ContractError::CoinAmountOverflow { amount } => {
+                write!(f, "Amount {} overflows a coin amount", amount)
+            }
+            ContractError::DecimalsTooLarge { max_decimals } => {
+                write!(f, "decimals must be less than or equal to {}", max_decimals)
+            }
+            ContractError::ScaledAmountOverflow { amount, decimals } => write!(
+                f,
+                "Amount {} overflows when scaled by {} decimals",
+                amount, decimals
+            ),
+            ContractError::AmountBelowSmallestUnit { amount } => {
+                write!(f, "Amount {} has dust below the smallest unit", amount)
+            }
+            ContractError::BorrowsNotAllowed { mode } => {
+                write!(f, "Borrows are not allowed in {} mode", mode)
+            }
+        }
+    }
+}
+
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+/// Code of the first `ContractError` found in `message`, which can be
+/// the message of the error or its full display, e.g. from a failed
+/// submessage
+pub fn parse_error_code(message: &str) -> Option<u16> {
+    let start = message.find("[E")? + 2;
+    let end = start + message[start..].find(']')?;
+    message[start..end].parse().ok()
+}
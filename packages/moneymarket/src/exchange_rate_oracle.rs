@@ -0,0 +1,16 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Decimal256;
+
+/// Interface of the oracle contract the market pushes
+/// the aterra exchange rate to at every accrual
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    /// Stable paid per aterra, accrued at `block_height`
+    FeedExchangeRate {
+        exchange_rate: Decimal256,
+        block_height: u64,
+    },
+}
@@ -0,0 +1,22 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::HumanAddr;
+
+/// Interface of the insurance contracts borrowers attach
+/// to their positions, called by the overseer when the
+/// position is liquidated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    /// Cure the position of `borrower`, whose `loan_amount` exceeds
+    /// its `borrow_limit`, for instance by repaying part of the loan
+    /// with the market `RepayStableFor`. The liquidation proceeds
+    /// afterwards if the position is still undercollateralized
+    CurePosition {
+        borrower: HumanAddr,
+        loan_amount: Uint256,
+        borrow_limit: Uint256,
+    },
+}
@@ -9,6 +9,17 @@ pub struct InitMsg {
     pub owner: HumanAddr,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    /// Turns the linear model into a two slope model
+    #[serde(default)]
+    pub kink: Option<Kink>,
+}
+
+/// Above `utilization`, the borrow rate grows with `jump_multiplier`
+/// instead of `interest_multiplier`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Kink {
+    pub utilization: Decimal256,
+    pub jump_multiplier: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -18,6 +29,8 @@ pub enum HandleMsg {
         owner: Option<HumanAddr>,
         base_rate: Option<Decimal256>,
         interest_multiplier: Option<Decimal256>,
+        /// A kink at a utilization of 1 removes it
+        kink: Option<Kink>,
     },
 }
 
@@ -30,6 +43,14 @@ pub enum QueryMsg {
         total_liabilities: Decimal256,
         total_reserves: Decimal256,
     },
+    /// Deposit rate implied by the borrow rate and utilization,
+    /// after `reserve_factor` of the interest goes to the reserves
+    DepositRate {
+        market_balance: Uint256,
+        total_liabilities: Decimal256,
+        total_reserves: Decimal256,
+        reserve_factor: Decimal256,
+    },
 }
 
 // We define a custom struct for each query response
@@ -38,6 +59,7 @@ pub struct ConfigResponse {
     pub owner: HumanAddr,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    pub kink: Option<Kink>,
 }
 
 // We define a custom struct for each query response
@@ -45,3 +67,9 @@ pub struct ConfigResponse {
 pub struct BorrowRateResponse {
     pub rate: Decimal256,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositRateResponse {
+    pub rate: Decimal256,
+}
@@ -1,15 +1,30 @@
+pub mod analytics;
+pub mod attestation;
+pub mod bid_pool;
 pub mod custody;
+pub mod dex_twap;
 pub mod distribution_model;
+pub mod error;
+pub mod exchange_rate_oracle;
+pub mod insurance;
 pub mod interest_model;
 pub mod liquidation;
 pub mod market;
 pub mod oracle;
 pub mod overseer;
+pub mod ownership;
+pub mod protocol_controller;
+pub mod protocol_mode;
 pub mod querier;
+pub mod registry;
+pub mod risk_module;
+pub mod router;
 pub mod tokens;
+pub mod units;
+pub mod venue;
 
-#[cfg(test)]
-mod mock_querier;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock_querier;
 
 #[cfg(test)]
 mod testing;
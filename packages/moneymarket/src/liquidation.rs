@@ -34,7 +34,6 @@ pub struct InitMsg {
 pub enum HandleMsg {
     Receive(Cw20ReceiveMsg),
     UpdateConfig {
-        owner: Option<HumanAddr>,
         oracle_contract: Option<HumanAddr>,
         stable_denom: Option<String>,
         safe_ratio: Option<Decimal256>,
@@ -43,6 +42,14 @@ pub enum HandleMsg {
         liquidation_threshold: Option<Uint256>,
         price_timeframe: Option<u64>,
     },
+    /// Propose `new_owner`, who must accept the ownership before
+    /// `expiry_height`
+    ProposeNewOwner {
+        new_owner: HumanAddr,
+        expiry_height: u64,
+    },
+    /// (new owner) Accept the proposed ownership
+    AcceptOwnership {},
     SubmitBid {
         collateral_token: HumanAddr,
         premium_rate: Decimal256,
@@ -51,6 +58,75 @@ pub enum HandleMsg {
         collateral_token: HumanAddr,
         amount: Option<Uint256>,
     },
+
+    /// Restricts bids and bid executions to the registered
+    /// liquidators; disabled by default
+    UpdateLiquidatorWhitelist {
+        enabled: bool,
+    },
+    RegisterLiquidators {
+        liquidators: Vec<HumanAddr>,
+    },
+    DeregisterLiquidators {
+        liquidators: Vec<HumanAddr>,
+    },
+
+    /// Follow the operational mode of the protocol controller
+    RegisterProtocolController {
+        protocol_controller: HumanAddr,
+    },
+    /// Query the protocol controller again instead of
+    /// waiting for the cached mode to expire
+    RefreshProtocolMode {},
+
+    /// Stream executions of more than `threshold` collateral to the
+    /// bidder over `stream_blocks` blocks; zero blocks pays them at once
+    UpdateCollateralStream {
+        collateral_token: HumanAddr,
+        threshold: Uint256,
+        stream_blocks: u64,
+    },
+    /// Send the released streamed collateral to `bidder`,
+    /// the sender when not given
+    ClaimStreamedCollateral {
+        collateral_token: HumanAddr,
+        bidder: Option<HumanAddr>,
+    },
+
+    /// Select how the liquidated `collateral_token` is sold,
+    /// the running auctions keep their parameters
+    UpdateLiquidationMechanism {
+        collateral_token: HumanAddr,
+        mechanism: LiquidationMechanism,
+    },
+    /// Buy the collateral of an auction at its current price with
+    /// the sent stable; the stable left once all of it is sold is
+    /// returned
+    BuyAuctionCollateral {
+        auction_id: u64,
+    },
+    /// Fill the collateral left by a partially filled liquidation with
+    /// the bid of the sender, at the price locked in the ticket
+    ResumeLiquidation {
+        ticket_id: u64,
+    },
+}
+
+/// How the liquidated collateral is sold
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationMechanism {
+    /// Executed at once against the premium bids
+    Bid {},
+    /// Sold through a descending price auction. The price starts at
+    /// `1 + start_premium` times the oracle price and decreases by
+    /// `decay_rate` of the oracle price per block, down to `floor`
+    /// times the oracle price
+    DutchAuction {
+        start_premium: Decimal256,
+        decay_rate: Decimal256,
+        floor: Decimal256,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -60,6 +136,10 @@ pub enum Cw20HookMsg {
         liquidator: HumanAddr,
         fee_address: Option<HumanAddr>,
         repay_address: Option<HumanAddr>,
+        /// Borrower the collateral was seized from, the auctions
+        /// and the resumed liquidations repay its loan. Without it,
+        /// a bid which cannot fill all the collateral fails
+        borrower: Option<HumanAddr>,
     },
 }
 
@@ -67,6 +147,7 @@ pub enum Cw20HookMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
     LiquidationAmount {
         borrow_amount: Uint256,
         borrow_limit: Uint256,
@@ -87,6 +168,44 @@ pub enum QueryMsg {
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
     },
+    Metrics {},
+    Liquidators {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    StreamConfig {
+        collateral_token: HumanAddr,
+    },
+    /// Streamed collateral of `bidder` released at `block_height`,
+    /// the last claim or execution height when not given
+    CollateralStream {
+        collateral_token: HumanAddr,
+        bidder: HumanAddr,
+        block_height: Option<u64>,
+    },
+    LiquidationMechanism {
+        collateral_token: HumanAddr,
+    },
+    /// Auction with its price ratio at `block_height`,
+    /// the start height when not given
+    Auction {
+        auction_id: u64,
+        block_height: Option<u64>,
+    },
+    Auctions {
+        collateral_token: Option<HumanAddr>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        block_height: Option<u64>,
+    },
+    ContinuationTicket {
+        ticket_id: u64,
+    },
+    ContinuationTickets {
+        collateral_token: Option<HumanAddr>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -122,3 +241,96 @@ pub struct BidResponse {
 pub struct BidsResponse {
     pub bids: Vec<BidResponse>,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralMetricsResponse {
+    pub collateral_token: HumanAddr,
+    pub executions: u64,
+    pub collateral_amount: Uint256,
+    pub collateral_value: Uint256,
+    /// premium rate averaged over the liquidated collateral value
+    pub average_premium_rate: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MetricsResponse {
+    pub total_executions: u64,
+    pub collaterals: Vec<CollateralMetricsResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatorsResponse {
+    pub whitelist_enabled: bool,
+    pub liquidators: Vec<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StreamConfigResponse {
+    pub collateral_token: HumanAddr,
+    pub threshold: Uint256,
+    /// zero when the collateral is paid at once
+    pub stream_blocks: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralStreamResponse {
+    pub collateral_token: HumanAddr,
+    pub bidder: HumanAddr,
+    pub claimable: Uint256,
+    pub locked: Uint256,
+    pub end_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationMechanismResponse {
+    pub collateral_token: HumanAddr,
+    pub mechanism: LiquidationMechanism,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionResponse {
+    pub auction_id: u64,
+    pub collateral_token: HumanAddr,
+    pub borrower: HumanAddr,
+    /// collateral left to sell
+    pub amount: Uint256,
+    pub sold_amount: Uint256,
+    pub raised_amount: Uint256,
+    pub start_height: u64,
+    /// price over the oracle price
+    pub price_ratio: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionsResponse {
+    pub auctions: Vec<AuctionResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContinuationTicketResponse {
+    pub ticket_id: u64,
+    pub collateral_token: HumanAddr,
+    pub borrower: HumanAddr,
+    /// collateral left to fill
+    pub amount: Uint256,
+    pub filled_amount: Uint256,
+    /// oracle price locked at the first execution
+    pub price: Decimal256,
+    pub max_premium_rate: Decimal256,
+    pub created_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContinuationTicketsResponse {
+    pub tickets: Vec<ContinuationTicketResponse>,
+}
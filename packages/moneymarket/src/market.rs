@@ -2,7 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::HumanAddr;
+use cosmwasm_std::{Binary, HumanAddr};
 use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -22,6 +22,7 @@ pub struct InitMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum HandleMsg {
     Receive(Cw20ReceiveMsg),
 
@@ -49,10 +50,296 @@ pub enum HandleMsg {
 
     /// Update config values
     UpdateConfig {
-        owner_addr: Option<HumanAddr>,
         max_borrow_factor: Option<Decimal256>,
         interest_model: Option<HumanAddr>,
         distribution_model: Option<HumanAddr>,
+        /// Limit of the total liabilities, zero removes the cap
+        global_borrow_cap: Option<Uint256>,
+        /// Limit of the loan of each borrower, zero removes the cap
+        per_account_borrow_cap: Option<Uint256>,
+        /// Smallest stable amount accepted by a deposit, zero removes it
+        min_deposit_amount: Option<Uint256>,
+        /// Smallest stable amount a redemption can return, zero removes it
+        min_redeem_amount: Option<Uint256>,
+        /// Length in blocks of the windows the net flow caps apply to,
+        /// zero removes the caps
+        flow_window: Option<u64>,
+        /// Max net deposits and max net redemptions of a window, as a
+        /// ratio of the total deposits at its start; zero removes the caps
+        max_net_flow_ratio: Option<Decimal256>,
+        /// Address allowed to pause the market entry points
+        guardian: Option<HumanAddr>,
+        /// Share of the accrued interest moved to the reserves as it
+        /// accrues, at most 1
+        reserve_factor: Option<Decimal256>,
+    },
+
+    /// Propose `new_owner`, who must accept the ownership before
+    /// `expiry_height`
+    ProposeNewOwner {
+        new_owner: HumanAddr,
+        expiry_height: u64,
+    },
+    /// (new owner) Accept the proposed ownership
+    AcceptOwnership {},
+
+    /// Create the cold tranche token. Cold deposits can only be
+    /// redeemed `redeem_delay` blocks after unbonding, and earn
+    /// `bonus_rate` per block on top of the aterra exchange rate,
+    /// funded from the reserves
+    InitColdTranche {
+        caterra_code_id: u64,
+        redeem_delay: u64,
+        bonus_rate: Decimal256,
+    },
+
+    /// Charge redemptions larger than `free_ratio` of the available
+    /// liquidity a fee growing linearly up to `max_fee_rate` for a
+    /// redemption draining the whole liquidity. The fee is added
+    /// to the reserves
+    UpdateRedemptionFee {
+        free_ratio: Decimal256,
+        max_fee_rate: Decimal256,
+    },
+
+    /// Exempt the redemptions of a protocol contract from the
+    /// redemption fee, or charge them again when `exempt` is false
+    UpdateFeeExemptRedeemer {
+        redeemer: HumanAddr,
+        exempt: bool,
+    },
+
+    /// Grant or update the credit line of a strategy contract.
+    /// The strategy borrows up to `cap` at `interest_rate` per block
+    /// and must escrow `collateral_ratio` of its loan in aterra value;
+    /// a zero ratio makes the line uncollateralized
+    SetCreditLine {
+        strategy: HumanAddr,
+        cap: Uint256,
+        interest_rate: Decimal256,
+        collateral_ratio: Decimal256,
+    },
+
+    /// Set the fee rate charged on flash minted aterra,
+    /// the fee is burned in favor of the depositors
+    UpdateFlashMintFee {
+        fee_rate: Decimal256,
+    },
+
+    /// Set the fee rate charged on flash loaned stable,
+    /// the fee stays in the market in favor of the depositors
+    UpdateFlashLoanFee {
+        fee_rate: Decimal256,
+    },
+
+    /// Mark a strategy as defaulted, seize its escrowed aterra
+    /// to cover the loan and write off the remaining liability
+    DefaultCreditLine {
+        strategy: HumanAddr,
+    },
+
+    /// Register the external venue idle stable is deployed to while
+    /// the utilization is below `utilization_threshold`. At most
+    /// `max_deploy_ratio` of the available liquidity and `cap` in
+    /// total are deployed
+    UpdateIdleDeployment {
+        venue: HumanAddr,
+        utilization_threshold: Decimal256,
+        max_deploy_ratio: Decimal256,
+        cap: Uint256,
+    },
+
+    /// Enable amortizing loans repaid in at most `max_installments`
+    /// equal installments, one every `period_blocks`. Each missed
+    /// installment adds `penalty_rate` of the overdue principal to
+    /// the loan. Open loans keep the parameters they were opened with
+    UpdateLoanParams {
+        period_blocks: u64,
+        max_installments: u32,
+        penalty_rate: Decimal256,
+    },
+
+    /// Enable the debt auction selling ANC for the bad debt the
+    /// reserves could not cover. The price of ANC starts at
+    /// `start_price` and decreases linearly to `floor_price`
+    /// over `price_decay_blocks`
+    UpdateDebtAuctionParams {
+        start_price: Decimal256,
+        floor_price: Decimal256,
+        price_decay_blocks: u64,
+    },
+
+    /// Forgive the accrued interest left by a repayment covering the
+    /// principal when it is at most `max_amount`, zero to never forgive
+    /// it. `max_amount` is at most one unit of the stable denom
+    UpdateDustForgiveness {
+        max_amount: Uint256,
+    },
+
+    /// Send `amount` of the reserves to `recipient`
+    WithdrawReserves {
+        amount: Uint256,
+        recipient: HumanAddr,
+    },
+
+    /// Pay `bounty_amount` out of the reserves for poking a position
+    /// not updated for `stale_blocks`, at most `epoch_cap` per epoch
+    UpdateKeeperBounty {
+        bounty_amount: Uint256,
+        epoch_cap: Uint256,
+        stale_blocks: u64,
+    },
+
+    /// Let borrowers lock the variable borrow rate plus `premium`.
+    /// A locked rate drifting more than `rebalance_threshold`
+    /// from the current stable rate can be rebalanced by anyone
+    UpdateStableRateParams {
+        premium: Decimal256,
+        rebalance_threshold: Decimal256,
+    },
+
+    /// Push the aterra exchange rate to `oracle` at every accrual
+    UpdateExchangeRateOracle {
+        oracle: HumanAddr,
+    },
+
+    /// Stop pushing the exchange rate to the oracle
+    DisableExchangeRateOracle {},
+
+    /// Take the time-weighted average exchange rate
+    /// over the last `twap_window` blocks
+    UpdateExchangeRateTwapWindow {
+        twap_window: u64,
+    },
+
+    /// Follow the operational mode of the protocol controller
+    /// on top of the market operation mode
+    RegisterProtocolController {
+        protocol_controller: HumanAddr,
+    },
+
+    /// Resolve the overseer, the models, the collector and
+    /// the distributor through `registry_contract`
+    RegisterRegistry {
+        registry_contract: HumanAddr,
+    },
+    /// Keep the last resolved addresses in the config
+    DisableRegistry {},
+
+    /// Accept deposits of `stable_denom` on top of the config denom,
+    /// minted as a separate aterra token with its own exchange rate.
+    /// Like at init, the owner must send the initial deposit amount of
+    /// the denom, so it cannot be part of an admin batch. Only the
+    /// config denom is borrowed
+    AddStableDenom {
+        stable_denom: String,
+        aterra_code_id: u64,
+    },
+
+    /// Accept or stop deposits of a CW20 stablecoin pegged to the
+    /// config denom, minting aterra at par like native deposits.
+    /// `decimals` of the token, 6 when it is first accepted
+    UpdateCw20Stable {
+        token: HumanAddr,
+        accept_deposits: bool,
+        decimals: Option<u8>,
+    },
+
+    /// Block borrows until `end_height` or until the market TVL
+    /// reaches `tvl_threshold`, deposits are still accepted.
+    /// The bootstrap ends on its own once either is reached
+    UpdateBootstrapMode {
+        end_height: Option<u64>,
+        tvl_threshold: Option<Uint256>,
+    },
+
+    /// End the bootstrap before its thresholds are reached
+    EndBootstrapMode {},
+
+    /// Re-verify the accounting invariants after every handler,
+    /// aborting the tx on a violation
+    UpdateInvariantChecks {
+        enabled: bool,
+    },
+
+    /// Stop enforcing the net flow caps of the config
+    /// in an emergency, or resume enforcing them
+    OverrideFlowLimit {
+        enabled: bool,
+    },
+
+    /// Accrue interest and rewards with the block time instead of the
+    /// block height, converting the per block rates with `seconds_per_block`
+    EnableTimeAccrual {
+        seconds_per_block: u64,
+    },
+
+    /// Ask `attestation_contract` to allow every deposit, borrow and
+    /// redemption before it is executed, or stop asking when unset
+    SetAttestationContract {
+        attestation_contract: Option<HumanAddr>,
+    },
+
+    /// Quote `interest_model` next to the live model on every accrual
+    /// for `trial_blocks`, logging its borrow rate without affecting
+    /// the accrual
+    UpdateShadowInterestModel {
+        interest_model: HumanAddr,
+        trial_blocks: u64,
+    },
+
+    /// Stop quoting the shadow interest model
+    DisableShadowInterestModel {},
+
+    /// Switch the live interest model to the shadow one,
+    /// accruing the interest at the current rate first
+    PromoteShadowInterestModel {},
+
+    /// Execute owner operations in order as a single operation,
+    /// either all of them are applied or none
+    AdminBatch {
+        ops: Vec<HandleMsg>,
+    },
+
+    /// (internal) Register cold A-token contract address
+    /// Cold A-Token will invoke this after init
+    RegisterColdATerra {},
+
+    /// (internal) Register the A-token contract of an added
+    /// stable denom, the token will invoke this after init
+    RegisterStableDenomATerra {
+        stable_denom: String,
+    },
+
+    /// (internal) Check the flash minted aterra was repaid
+    /// and burn it together with the fee
+    FinishFlashMint {},
+
+    /// (internal) Check the flash loaned stable
+    /// was repaid together with the fee
+    FinishFlashLoan {},
+
+    ////////////////////
+    /// Guardian operations
+    ////////////////////
+    /// Open or close deposits, redemptions, borrows and repayments
+    /// independently, fields left empty keep their current value.
+    /// Borrows also cover credit line draws, flash loans and flash mints
+    UpdateOperationMode {
+        deposits_enabled: Option<bool>,
+        redemptions_enabled: Option<bool>,
+        borrows_enabled: Option<bool>,
+        repayments_enabled: Option<bool>,
+    },
+
+    /// Pause or unpause deposits, redemptions, borrows and repayments
+    /// independently. Sent by the guardian of the config, also allowed
+    /// to the owner; fields left empty keep their current value
+    SetPause {
+        deposit: Option<bool>,
+        redeem: Option<bool>,
+        borrow: Option<bool>,
+        repay: Option<bool>,
     },
 
     ////////////////////
@@ -74,25 +361,219 @@ pub enum HandleMsg {
         distributed_interest: Uint256,
     },
 
+    /// Reduce the loan of `borrower` by `amount` out of the reserves
+    RebateInterest {
+        borrower: HumanAddr,
+        amount: Uint256,
+    },
+
+    /// Move the loan of `from` to `to`, which must have no loan.
+    /// Pending ANC rewards stay with their owner
+    TransferLiability {
+        from: HumanAddr,
+        to: HumanAddr,
+    },
+
+    /// Write off the loan left to `borrower` once all its collaterals
+    /// were liquidated. The reserves cover it first, the rest is
+    /// raised by the debt auction
+    WriteOffBadDebt {
+        borrower: HumanAddr,
+    },
+
     ////////////////////
     /// User operations
     ////////////////////
-    /// Deposit stable asset to get interest
-    DepositStable {},
+    /// Deposit stable asset to get interest, either
+    /// the config denom or an added stable denom.
+    /// The aterra is minted to `recipient` if given.
+    /// With `amount`, expressed with `decimals` (6 by default),
+    /// the sent amount must match it
+    DepositStable {
+        recipient: Option<HumanAddr>,
+        amount: Option<Uint256>,
+        decimals: Option<u8>,
+    },
+
+    /// Redeem exactly `stable_amount` before the redemption fee and
+    /// the tax, burning the aterra worth it rounded up from the
+    /// allowance the sender gave to the market
+    RedeemStableExact {
+        stable_amount: Uint256,
+    },
+
+    /// Redeem the queued redemption `id` once it is at the head
+    /// of the queue and the market has the liquidity for it
+    ClaimRedemption {
+        id: u64,
+    },
+
+    /// Deposit stable asset into the cold tranche
+    DepositStableCold {},
+
+    /// Donate the sent stable to the depositors as yield,
+    /// `amount` must match the sent amount
+    DonateToDepositors {
+        amount: Uint256,
+    },
 
-    /// Borrow stable asset with collaterals in overseer contract
+    /// Buy CW20 stables held by the market at par
+    /// with the config denom
+    SwapCw20Stable {
+        token: HumanAddr,
+    },
+
+    /// Withdraw cold tranche deposits whose unbonding delay has passed
+    WithdrawUnbondedCold {},
+
+    /// Borrow stable asset with collaterals in overseer contract.
+    /// With `deposit`, the borrowed stable is deposited back into
+    /// the market and aterra is minted to `to` instead.
+    /// `borrow_amount` is expressed with `decimals`, 6 by default
     BorrowStable {
         borrow_amount: Uint256,
         to: Option<HumanAddr>,
+        deposit: Option<bool>,
+        decimals: Option<u8>,
     },
 
+    /// Switch the loan of the sender between the variable rate and a
+    /// stable rate locked at the current variable rate plus the premium.
+    /// Later borrows of a stable loan are locked at the rate of their
+    /// origination, averaged with the locked rate by amount
+    SwapRateMode {},
+
     /// Repay stable asset to decrease liability
     RepayStable {},
 
+    /// Repay stable asset to decrease the liability of `borrower`,
+    /// the amount exceeding the loan is returned to the borrower
+    RepayStableFor {
+        borrower: HumanAddr,
+    },
+
     /// Claim distributed ANC rewards
     ClaimRewards {
         to: Option<HumanAddr>,
     },
+
+    /// Mint `amount` aterra to the sender and execute `msg` on it.
+    /// The sender must send back the minted amount plus the fee
+    /// with the `RepayFlashMint` hook within the same transaction
+    FlashMint {
+        amount: Uint256,
+        msg: Binary,
+    },
+
+    /// Send `amount` stable to the sender and execute `msg` on it.
+    /// The sender must send back the loaned amount plus the fee
+    /// with `RepayFlashLoan` within the same transaction
+    FlashLoan {
+        amount: Uint256,
+        msg: Binary,
+    },
+
+    /// Return flash loaned stable and the fee
+    RepayFlashLoan {},
+
+    /// Deploy idle stable to the registered venue
+    /// while the utilization is below the threshold
+    DeployIdleStable {},
+
+    /// Recall deployed stable from the venue, defaults to the whole
+    /// deployment. Anyone can recall once the utilization reached
+    /// the threshold, the owner at any time
+    RecallIdleStable {
+        amount: Option<Uint256>,
+    },
+
+    /// Cache the current tax rate and stable tax cap,
+    /// used instead of querying them on every payout
+    RefreshTaxParams {},
+
+    /// Remove up to `limit` liabilities without loan nor claimable
+    /// rewards that were not updated for a week, continuing from
+    /// where the previous call stopped
+    PruneClosedPositions {
+        limit: Option<u32>,
+    },
+
+    /// Bring the interest and reward indexes of up to `limit`
+    /// liabilities stored after `start_after` to the global ones.
+    /// The `last_borrower` log is the start of the next page, empty
+    /// once every liability was scanned
+    UpdateRewardIndexes {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+
+    /// Borrow `amount` against the collaterals of the sender, like
+    /// `BorrowStable`, with its principal repaid in `installments`.
+    /// A borrower has at most one amortizing loan
+    OpenLoan {
+        amount: Uint256,
+        installments: u32,
+        to: Option<HumanAddr>,
+    },
+
+    /// Repay the sent stable towards the installments of the
+    /// sender's amortizing loan, after charging missed installments
+    RepayInstallment {},
+
+    /// Repay the remaining principal of the sender's amortizing
+    /// loan at once and close its schedule
+    PayoffLoan {},
+
+    /// Charge the missed installments of the amortizing loan of
+    /// `borrower`, callable by anyone
+    ApplyLoanPenalty {
+        borrower: HumanAddr,
+    },
+
+    /// Buy ANC from the debt auction with the sent stable,
+    /// at most the remaining debt is accepted
+    BidDebtAuction {},
+
+    /// Accrue the interest and rewards of the stale position
+    /// of `borrower`, the sender is paid the keeper bounty
+    PokePosition {
+        borrower: HumanAddr,
+    },
+
+    /// Lock the stable rate of `borrower` again at the current stable
+    /// rate once it drifted past the rebalance threshold. Only the
+    /// borrower can lower its own stable rate
+    RebalanceStableRate {
+        borrower: HumanAddr,
+    },
+
+    /// Query the protocol controller again instead of
+    /// waiting for the cached mode to expire
+    RefreshProtocolMode {},
+
+    /// Resolve the peers again instead of waiting
+    /// for the resolved ones to expire
+    RefreshRegistry {},
+
+    ////////////////////
+    /// Strategy operations
+    ////////////////////
+    /// Draw stable asset from the sender's credit line
+    DrawCredit {
+        amount: Uint256,
+        to: Option<HumanAddr>,
+    },
+
+    /// Repay the credit line of the given strategy,
+    /// defaults to the sender's credit line
+    RepayCredit {
+        strategy: Option<HumanAddr>,
+    },
+
+    /// Withdraw escrowed aterra from the sender's credit line
+    WithdrawCreditCollateral {
+        amount: Uint256,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -101,6 +582,26 @@ pub enum Cw20HookMsg {
     /// Return stable coins to a user
     /// according to exchange rate
     RedeemStable {},
+
+    /// Redeem like RedeemStable, or queue the redemption when
+    /// the market lacks the liquidity; deposits and repayments
+    /// service the queue in order
+    RedeemStableOrQueue {},
+
+    /// Burn cold A-tokens and start the
+    /// cold tranche unbonding delay
+    UnbondCold {},
+
+    /// Escrow aterra as collateral of the
+    /// sender's credit line
+    PostCreditCollateral {},
+
+    /// Return flash minted aterra and the fee
+    RepayFlashMint {},
+
+    /// Deposit a CW20 stablecoin accepted by the
+    /// owner to get aterra at par
+    DepositStable {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -113,6 +614,7 @@ pub struct MigrateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
     State {
         block_height: Option<u64>,
     },
@@ -124,10 +626,120 @@ pub enum QueryMsg {
         borrower: HumanAddr,
         block_height: Option<u64>,
     },
+    /// `limit` counts the scanned liabilities, the filters
+    /// may return fewer of them
     BorrowerInfos {
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
+        /// Minimum loan at the last interest accrual
+        min_liability: Option<Uint256>,
+        /// Only loans above the liquidation borrow limit of the overseer
+        only_liquidatable: Option<bool>,
+    },
+    ColdTranche {},
+    ColdUnbonding {
+        address: HumanAddr,
+    },
+    RedemptionFee {},
+    FeeExemptRedeemers {},
+    QueuedRedemptions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    DepositorInfo {
+        depositor: HumanAddr,
+    },
+    /// Lifetime deposits and redemptions of `address`
+    Depositor {
+        address: HumanAddr,
+    },
+    /// Lifetime deposits and redemptions of every depositor, in address order
+    Depositors {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    CreditLine {
+        strategy: HumanAddr,
+        block_height: Option<u64>,
+    },
+    CreditLines {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    FlashMint {},
+    FlashLoan {},
+    IdleDeployment {},
+    TaxParams {},
+    OperationMode {},
+    Registry {},
+    LoanParams {},
+    /// Queries have no access to the block, the
+    /// schedule is evaluated at the given height
+    AmortizingLoan {
+        borrower: HumanAddr,
+        block_height: u64,
+    },
+    DebtAuctionParams {},
+    /// The auction price is evaluated at the given height
+    DebtAuction {
+        block_height: u64,
+    },
+    DustForgiveness {},
+    /// Total stable donated to the depositors
+    Donations {},
+    KeeperBounty {},
+    StableRateParams {},
+    RateMode {
+        borrower: HumanAddr,
+    },
+    /// Exchange rate for the protocols accepting aterra, accrued
+    /// to the given height like the epoch state
+    ATerraExchangeRate {
+        block_height: Option<u64>,
+    },
+    LoanBreakdown {
+        borrower: HumanAddr,
+        block_height: Option<u64>,
+    },
+    /// Rates recorded when the loan of `borrower`
+    /// was last originated or modified
+    RateSnapshot {
+        borrower: HumanAddr,
+    },
+    /// State snapshot of the last epoch executed at or before `height`
+    StateAt {
+        height: u64,
+    },
+    /// State snapshots of the past epochs, oldest first
+    StateHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
     },
+    /// Stable denoms added on top of the config denom
+    StableDenoms {},
+    /// CW20 stablecoins accepted for deposits
+    Cw20Stables {},
+    /// Thresholds of the running bootstrap next to the current TVL
+    BootstrapMode {},
+    /// Outcome of depositing `amount` of the config denom at
+    /// `block_height`, the last accrual height when not given
+    SimulateDeposit {
+        amount: Uint256,
+        block_height: Option<u64>,
+    },
+    /// Outcome of redeeming `burn_amount` aterra at
+    /// `block_height`, the last accrual height when not given
+    SimulateRedeem {
+        burn_amount: Uint256,
+        block_height: Option<u64>,
+    },
+    InvariantChecks {},
+    /// Deposits and redemptions of the current net flow cap window
+    FlowLimit {},
+    AttestationContract {},
+    /// Borrow rates the live and the shadow interest
+    /// models quote for the current market
+    ShadowInterestModel {},
 }
 
 // We define a custom struct for each query response
@@ -142,6 +754,14 @@ pub struct ConfigResponse {
     pub distributor_contract: HumanAddr,
     pub stable_denom: String,
     pub max_borrow_factor: Decimal256,
+    pub global_borrow_cap: Option<Uint256>,
+    pub per_account_borrow_cap: Option<Uint256>,
+    pub min_deposit_amount: Option<Uint256>,
+    pub min_redeem_amount: Option<Uint256>,
+    pub flow_window: Option<u64>,
+    pub max_net_flow_ratio: Option<Decimal256>,
+    pub guardian: Option<HumanAddr>,
+    pub reserve_factor: Decimal256,
 }
 
 // We define a custom struct for each query response
@@ -156,6 +776,13 @@ pub struct StateResponse {
     pub anc_emission_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
+    pub total_credit_liabilities: Decimal256,
+    pub credit_interest_per_block: Decimal256,
+    pub utilization_clamps: u64,
+    /// set once accrual is time based
+    pub seconds_per_block: Option<u64>,
+    pub last_interest_updated_time: Option<u64>,
+    pub forgiven_dust: Uint256,
 }
 
 // We define a custom struct for each query response
@@ -163,6 +790,11 @@ pub struct StateResponse {
 pub struct EpochStateResponse {
     pub exchange_rate: Decimal256,
     pub aterra_supply: Uint256,
+    /// stable balance the exchange rate was computed with
+    pub market_balance: Uint256,
+    pub total_liabilities: Decimal256,
+    pub total_credit_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
 }
 
 // We define a custom struct for each query response
@@ -179,4 +811,396 @@ pub struct BorrowerInfoResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowerInfosResponse {
     pub borrower_infos: Vec<BorrowerInfoResponse>,
+    /// Last liability of the scanned page, the next
+    /// page starts after it; `None` once the scan is done
+    pub last_scanned: Option<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ColdTrancheResponse {
+    pub caterra_contract: HumanAddr,
+    pub redeem_delay: u64,
+    pub bonus_rate: Decimal256,
+    pub aterra_balance: Uint256,
+    pub last_bonus_updated: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StableDenomResponse {
+    pub stable_denom: String,
+    pub aterra_contract: HumanAddr,
+    pub aterra_supply: Uint256,
+    /// stable denom per aterra
+    pub exchange_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StableDenomsResponse {
+    pub stable_denoms: Vec<StableDenomResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateDepositResponse {
+    pub mint_amount: Uint256,
+    /// paid by the depositor on top of the deposit
+    pub tax_amount: Uint256,
+    /// exchange rate after the deposit
+    pub exchange_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateRedeemResponse {
+    /// received by the redeemer after the fee and tax
+    pub redeem_amount: Uint256,
+    /// redemption fee added to the reserves
+    pub fee_amount: Uint256,
+    /// deducted from the redeemed amount
+    pub tax_amount: Uint256,
+    /// exchange rate after the redemption
+    pub exchange_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20StableResponse {
+    pub token: HumanAddr,
+    pub accept_deposits: bool,
+    pub decimals: u8,
+    /// held at par in the market balance, in micro units
+    pub held_amount: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20StablesResponse {
+    pub cw20_stables: Vec<Cw20StableResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ColdUnbondingResponse {
+    pub address: HumanAddr,
+    pub aterra_amount: Uint256,
+    pub release_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionFeeResponse {
+    pub free_ratio: Decimal256,
+    pub max_fee_rate: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeExemptRedeemersResponse {
+    pub redeemers: Vec<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QueuedRedemptionResponse {
+    pub id: u64,
+    pub redeemer: HumanAddr,
+    pub aterra_amount: Uint256,
+    pub queued_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QueuedRedemptionsResponse {
+    pub redemptions: Vec<QueuedRedemptionResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositorInfoResponse {
+    pub depositor: HumanAddr,
+    pub total_deposited: Uint256,
+    pub total_redeemed: Uint256,
+    /// deposits minus redemptions, floored at zero
+    pub net_principal: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositorsResponse {
+    pub depositors: Vec<DepositorInfoResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreditLineResponse {
+    pub strategy: HumanAddr,
+    pub cap: Uint256,
+    pub interest_rate: Decimal256,
+    pub collateral_ratio: Decimal256,
+    pub loan_amount: Uint256,
+    pub collateral_amount: Uint256,
+    pub defaulted: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreditLinesResponse {
+    pub credit_lines: Vec<CreditLineResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashMintResponse {
+    pub fee_rate: Decimal256,
+    /// The flash mint in progress, set only while
+    /// the callback of the minter is executed
+    pub minter: Option<HumanAddr>,
+    pub amount: Uint256,
+    pub fee: Uint256,
+    pub repaid: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoanResponse {
+    pub fee_rate: Decimal256,
+    /// The flash loan in progress, set only while
+    /// the callback of the borrower is executed
+    pub borrower: Option<HumanAddr>,
+    pub amount: Uint256,
+    pub fee: Uint256,
+    pub repaid: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IdleDeploymentResponse {
+    pub venue: Option<HumanAddr>,
+    pub utilization_threshold: Decimal256,
+    pub max_deploy_ratio: Decimal256,
+    pub cap: Uint256,
+    /// stable sent to the venue, excluding its yield
+    pub deployed_amount: Uint256,
+    /// loans over the lendable stable including the deployed amount
+    pub utilization: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TaxParamsResponse {
+    pub rate: Decimal256,
+    pub cap: Uint256,
+    pub updated_height: u64,
+    /// the treasury is queried again after this height
+    pub expires_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegistryResponse {
+    pub registry_contract: Option<HumanAddr>,
+    /// Registry version the peers were resolved at
+    pub version: u64,
+    pub updated_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperationModeResponse {
+    pub deposits_enabled: bool,
+    pub redemptions_enabled: bool,
+    pub borrows_enabled: bool,
+    pub repayments_enabled: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AttestationContractResponse {
+    pub attestation_contract: Option<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LoanParamsResponse {
+    pub period_blocks: u64,
+    pub max_installments: u32,
+    pub penalty_rate: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AmortizingLoanResponse {
+    pub borrower: HumanAddr,
+    pub principal: Uint256,
+    pub installments: u32,
+    pub start_height: u64,
+    pub period_blocks: u64,
+    pub penalty_rate: Decimal256,
+    pub repaid_amount: Uint256,
+    pub penalty_amount: Uint256,
+    /// unset once the last due date has passed
+    pub next_due_height: Option<u64>,
+    /// principal to repay by the next due date, or right
+    /// away once the last one has passed
+    pub amount_due: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DebtAuctionParamsResponse {
+    pub start_price: Decimal256,
+    pub floor_price: Decimal256,
+    pub price_decay_blocks: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DebtAuctionResponse {
+    pub debt_amount: Uint256,
+    pub start_height: u64,
+    pub raised_amount: Uint256,
+    pub sold_amount: Uint256,
+    /// stable paid per ANC, unset until the auction is enabled
+    pub price: Option<Decimal256>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DustForgivenessResponse {
+    pub max_amount: Uint256,
+    /// loan residuals forgiven so far
+    pub forgiven_dust: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DonationsResponse {
+    pub total_donated: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LoanBreakdownResponse {
+    pub borrower: HumanAddr,
+    pub principal: Uint256,
+    /// accrued interest and penalties not yet repaid
+    pub accrued_interest: Uint256,
+    pub principal_repaid: Uint256,
+    pub interest_repaid: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateSnapshotResponse {
+    pub borrower: HumanAddr,
+    /// zero when no snapshot was recorded
+    pub height: u64,
+    /// loan right after the origination or modification
+    pub loan_amount: Uint256,
+    /// interest model rate per block
+    pub borrow_rate: Decimal256,
+    /// global interest index the loan accrues from
+    pub interest_index: Decimal256,
+    /// aterra exchange rate
+    pub exchange_rate: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateSnapshotResponse {
+    /// height of the epoch operations
+    pub height: u64,
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub aterra_supply: Uint256,
+    pub exchange_rate: Decimal256,
+    pub deposit_rate: Decimal256,
+    pub borrow_rate: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateHistoryResponse {
+    pub snapshots: Vec<StateSnapshotResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StableRateParamsResponse {
+    pub premium: Decimal256,
+    pub rebalance_threshold: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateMode {
+    Variable,
+    Stable,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateModeResponse {
+    pub borrower: HumanAddr,
+    pub rate_mode: RateMode,
+    /// locked borrow rate per block of a stable loan
+    pub stable_rate: Option<Decimal256>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct KeeperBountyResponse {
+    pub bounty_amount: Uint256,
+    pub epoch_cap: Uint256,
+    pub stale_blocks: u64,
+    /// paid since the last epoch operations
+    pub paid_amount: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ATerraExchangeRateResponse {
+    pub exchange_rate: Decimal256,
+    /// height of the last interest accrual
+    pub last_updated: u64,
+    /// whether the exchange rate never decreased between accruals
+    pub monotonic: bool,
+    pub oracle: Option<HumanAddr>,
+    /// exchange rate of the accruals weighted by the blocks each
+    /// was in effect over the last `twap_window` blocks, the spot
+    /// exchange rate until an accrual is observed
+    pub twap: Decimal256,
+    pub twap_window: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BootstrapModeResponse {
+    /// borrows are blocked while active
+    pub active: bool,
+    pub end_height: Option<u64>,
+    pub tvl_threshold: Option<Uint256>,
+    /// market balance plus the total liabilities
+    pub tvl: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InvariantChecksResponse {
+    pub enabled: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlowLimitResponse {
+    pub override_enabled: bool,
+    pub window_start_height: Option<u64>,
+    pub base_deposits: Uint256,
+    pub deposited: Uint256,
+    pub redeemed: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShadowInterestModelResponse {
+    pub interest_model: Option<HumanAddr>,
+    pub start_height: u64,
+    /// last height the shadow model is quoted at
+    pub end_height: u64,
+    pub live_borrow_rate: Decimal256,
+    pub shadow_borrow_rate: Decimal256,
 }
@@ -1,4 +1,4 @@
-use cosmwasm_bignumber::Decimal256;
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     from_binary, from_slice, to_binary, Api, Coin, Decimal, Extern, HumanAddr, Querier,
@@ -8,7 +8,9 @@ use std::collections::HashMap;
 
 use crate::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
 
-use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
+use terra_cosmwasm::{
+    SwapResponse, TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute,
+};
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
 /// this uses our CustomQuerier.
@@ -33,6 +35,7 @@ pub struct WasmMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
     tax_querier: TaxQuerier,
     oracle_price_querier: OraclePriceQuerier,
+    swap_querier: SwapQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -66,6 +69,7 @@ pub struct OraclePriceQuerier {
 }
 
 impl OraclePriceQuerier {
+    #[allow(clippy::type_complexity)]
     pub fn new(oracle_price: &[(&(String, String), &(Decimal256, u64, u64))]) -> Self {
         OraclePriceQuerier {
             oracle_price: oracle_price_to_map(oracle_price),
@@ -73,6 +77,7 @@ impl OraclePriceQuerier {
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub(crate) fn oracle_price_to_map(
     oracle_price: &[(&(String, String), &(Decimal256, u64, u64))],
 ) -> HashMap<(String, String), (Decimal256, u64, u64)> {
@@ -84,6 +89,28 @@ pub(crate) fn oracle_price_to_map(
     oracle_price_map
 }
 
+#[derive(Clone, Default)]
+pub struct SwapQuerier {
+    // (offer_denom, ask_denom) => ask amount per offer amount
+    rates: HashMap<(String, String), Decimal256>,
+    // share of the swap output lost to slippage
+    spread: Decimal256,
+}
+
+impl SwapQuerier {
+    pub fn new(rates: &[(&(String, String), &Decimal256)], spread: Decimal256) -> Self {
+        let mut rates_map: HashMap<(String, String), Decimal256> = HashMap::new();
+        for (offer_ask, rate) in rates.iter() {
+            rates_map.insert((*offer_ask).clone(), **rate);
+        }
+
+        SwapQuerier {
+            rates: rates_map,
+            spread,
+        }
+    }
+}
+
 impl Querier for WasmMockQuerier {
     fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
         // MockQuerier doesn't support Custom, so we ignore it completely here
@@ -124,6 +151,39 @@ impl WasmMockQuerier {
                         }
                         _ => panic!("DO NOT ENTER HERE"),
                     }
+                } else if &TerraRoute::Market == route {
+                    match query_data {
+                        TerraQuery::Swap {
+                            offer_coin,
+                            ask_denom,
+                        } => {
+                            let rate = match self
+                                .swap_querier
+                                .rates
+                                .get(&(offer_coin.denom.clone(), ask_denom.clone()))
+                            {
+                                Some(v) => *v,
+                                None => {
+                                    return Err(SystemError::InvalidRequest {
+                                        error: "No swap rate exists".to_string(),
+                                        request: to_binary(request).unwrap_or_default(),
+                                    })
+                                }
+                            };
+
+                            let receive_amount = Uint256::from(offer_coin.amount)
+                                * rate
+                                * (Decimal256::one() - self.swap_querier.spread);
+                            let res = SwapResponse {
+                                receive: Coin {
+                                    denom: ask_denom.to_string(),
+                                    amount: receive_amount.into(),
+                                },
+                            };
+                            Ok(to_binary(&res))
+                        }
+                        _ => panic!("DO NOT ENTER HERE"),
+                    }
                 } else {
                     panic!("DO NOT ENTER HERE")
                 }
@@ -131,7 +191,7 @@ impl WasmMockQuerier {
             QueryRequest::Wasm(WasmQuery::Smart {
                 contract_addr: _,
                 msg,
-            }) => match from_binary(&msg).unwrap() {
+            }) => match from_binary(msg).unwrap() {
                 OracleQueryMsg::Price { base, quote } => {
                     match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
                         Some(v) => Ok(to_binary(&PriceResponse {
@@ -158,6 +218,7 @@ impl WasmMockQuerier {
             base,
             tax_querier: TaxQuerier::default(),
             oracle_price_querier: OraclePriceQuerier::default(),
+            swap_querier: SwapQuerier::default(),
         }
     }
 
@@ -166,10 +227,29 @@ impl WasmMockQuerier {
         self.tax_querier = TaxQuerier::new(rate, caps);
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn with_oracle_price(
         &mut self,
         oracle_price: &[(&(String, String), &(Decimal256, u64, u64))],
     ) {
         self.oracle_price_querier = OraclePriceQuerier::new(oracle_price);
     }
+
+    /// Shifts the update times of all configured oracle prices
+    /// `seconds` into the past, to simulate a stale oracle
+    pub fn age_oracle_prices(&mut self, seconds: u64) {
+        for price in self.oracle_price_querier.oracle_price.values_mut() {
+            price.1 = price.1.saturating_sub(seconds);
+            price.2 = price.2.saturating_sub(seconds);
+        }
+    }
+
+    // configure the market swap mock querier
+    pub fn with_swap_rates(
+        &mut self,
+        rates: &[(&(String, String), &Decimal256)],
+        spread: Decimal256,
+    ) {
+        self.swap_querier = SwapQuerier::new(rates, spread);
+    }
 }
@@ -40,6 +40,7 @@ pub struct InitMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum HandleMsg {
     ////////////////////
     /// Owner operations
@@ -47,7 +48,6 @@ pub enum HandleMsg {
 
     /// Update Configs
     UpdateConfig {
-        owner_addr: Option<HumanAddr>,
         oracle_contract: Option<HumanAddr>,
         liquidation_contract: Option<HumanAddr>,
         threshold_deposit_rate: Option<Decimal256>,
@@ -56,8 +56,27 @@ pub enum HandleMsg {
         anc_purchase_factor: Option<Decimal256>,
         epoch_period: Option<u64>,
         price_timeframe: Option<u64>,
+        guardians: Option<Vec<HumanAddr>>,
+        guardian_threshold: Option<u64>,
+        max_collateral_types: Option<u64>,
+        swap_router: Option<HumanAddr>,
+        min_position_collateral_value: Option<Uint256>,
+        liquidation_order: Option<LiquidationOrder>,
+        max_feed_gap: Option<u64>,
+        max_catch_up_epochs: Option<u64>,
+        close_factor: Option<Decimal256>,
+        max_emergency_price_duration: Option<u64>,
     },
 
+    /// Propose `new_owner`, who must accept the ownership before
+    /// `expiry_height`
+    ProposeNewOwner {
+        new_owner: HumanAddr,
+        expiry_height: u64,
+    },
+    /// (new owner) Accept the proposed ownership
+    AcceptOwnership {},
+
     /// Create new custody contract for the given collateral token
     Whitelist {
         name: String,                // bAsset name
@@ -72,6 +91,79 @@ pub enum HandleMsg {
         custody_contract: Option<HumanAddr>, // bAsset custody contract
         max_ltv: Option<Decimal256>,         // Loan To Value ratio
     },
+    /// Whitelist the proposed collateral and keep its metadata
+    ConfirmWhitelist { collateral_token: HumanAddr },
+    /// Drop the whitelist proposal of the collateral
+    RejectWhitelist { collateral_token: HumanAddr },
+    /// Split the rewards the custody of a collateral distributes at
+    /// each epoch between `route` destinations by ratio, the ratios
+    /// summing to one. An empty route sends them to the interest buffer
+    UpdateRewardRoute {
+        collateral_token: HumanAddr,
+        route: Vec<RewardShare>,
+    },
+    /// Cross-check the oracle price of a collateral against a DEX
+    /// TWAP. While they diverge beyond the max deviation, borrows
+    /// against and unlocks of the collateral are blocked. `None`
+    /// removes the check
+    UpdatePriceCheck {
+        collateral_token: HumanAddr,
+        price_check: Option<PriceCheck>,
+    },
+
+    /// Rebate `rebate_rate` of the interest accrued over a full epoch
+    /// to borrowers whose loan stays under their rebate limit, the sum
+    /// of their collaterals value times the collateral `rebate_ltv`.
+    /// Rebates are funded from the market reserves
+    UpdateInterestRebate { rebate_rate: Decimal256 },
+    /// Set the rebate LTV of a whitelisted collateral,
+    /// zero excludes the collateral from the rebate limit
+    UpdateRebateLtv {
+        collateral_token: HumanAddr,
+        rebate_ltv: Decimal256,
+    },
+
+    /// Denominate borrow limits and liquidations in `index_asset`,
+    /// a unit of account such as a CPI index priced in stable
+    /// denom by `index_oracle`, instead of the stable denom
+    UpdateIndexUnit {
+        index_oracle: HumanAddr,
+        index_asset: String,
+    },
+    /// Go back to stable denominated borrow limits and liquidations
+    DisableIndexUnit {},
+
+    /// Allow borrowers to attach `insurance_contract` to their positions
+    RegisterInsurance { insurance_contract: HumanAddr },
+    /// Stop calling `insurance_contract` on liquidations,
+    /// the positions attached to it are liquidated as uninsured
+    DeregisterInsurance { insurance_contract: HumanAddr },
+
+    /// Delegate the borrow, withdrawal and liquidation decisions
+    /// to `risk_module`, a contract implementing the risk module
+    /// `CanBorrow`, `CanWithdraw` and `LiquidationAmount` queries
+    UpdateRiskModule { risk_module: HumanAddr },
+    /// Go back to the overseer's own borrow, withdrawal and
+    /// liquidation rules
+    DisableRiskModule {},
+    /// Follow the operational mode of the protocol controller
+    RegisterProtocolController { protocol_controller: HumanAddr },
+    /// Push the indicators of every epoch to `analytics_contract`
+    UpdateAnalyticsContract { analytics_contract: HumanAddr },
+    /// Stop pushing the epoch indicators
+    DisableAnalyticsContract {},
+    /// Resolve the market, oracle, liquidation, collector and
+    /// custody contracts through `registry_contract`
+    RegisterRegistry { registry_contract: HumanAddr },
+    /// Keep the last resolved addresses in the config
+    DisableRegistry {},
+    /// Re-verify the config and epoch invariants after
+    /// every handler, aborting the tx on a violation
+    UpdateInvariantChecks { enabled: bool },
+
+    /// Execute owner operations in order as a single operation,
+    /// either all of them are applied or none
+    AdminBatch { ops: Vec<HandleMsg> },
 
     /// Claims all staking rewards from the bAsset contracts
     /// and also do a epoch basis updates
@@ -79,11 +171,39 @@ pub enum HandleMsg {
     /// 2. Invoke [Custody] DistributeRewards
     /// 3. Update epoch state
     ExecuteEpochOperations {},
+    /// Execute the next pending step of the epoch operations alone,
+    /// the steps executed before a failing one are kept
+    ExecuteEpochStep {},
     UpdateEpochState {
         interest_buffer: Uint256,
         distributed_interest: Uint256,
     },
 
+    /// (internal) Lock the collateral received from the swap
+    /// router and re-validate the borrow limit of the borrower
+    FinishSwapCollateral {},
+
+    /// (internal) Liquidate `borrower` on behalf of `liquidator`
+    /// unless its insurance contract has cured the position
+    FinishInsuredLiquidation {
+        borrower: HumanAddr,
+        liquidator: HumanAddr,
+    },
+
+    ////////////////////
+    /// Guardian operations
+    ////////////////////
+
+    /// Approve overriding the oracle price of a whitelisted collateral
+    /// until `expires_at` (block time); used when the oracle is down.
+    /// The override applies once `guardian_threshold` guardians
+    /// approved the same price and expiry
+    SetEmergencyPrice {
+        asset: HumanAddr,
+        price: Decimal256,
+        expires_at: u64,
+    },
+
     ////////////////////
     /// User operations
     ////////////////////
@@ -93,18 +213,110 @@ pub enum HandleMsg {
     UnlockCollateral {
         collaterals: TokensHuman, // <(Collateral Token, Amount)>
     },
+    /// Lock the sender's deposited collaterals to back the borrow limit
+    /// of `borrower`. The borrower can only unlock them when the sender
+    /// sets `withdraw_allowed`
+    LockCollateralFor {
+        borrower: HumanAddr,
+        collaterals: TokensHuman, // <(Collateral Token, Amount)>
+        withdraw_allowed: bool,
+    },
+    /// Return collaterals locked for `borrower` to the sender
+    UnlockCollateralFor {
+        borrower: HumanAddr,
+        collaterals: TokensHuman, // <(Collateral Token, Amount)>
+    },
+
+    /// Replace the sender's unlockable `from_token` collateral with
+    /// `to_token` by swapping it through the swap router; the swap
+    /// fails when less than `min_receive` is received or when the
+    /// new collateral does not cover the loan
+    SwapCollateral {
+        from_token: HumanAddr,
+        to_token: HumanAddr,
+        min_receive: Uint256,
+    },
+
+    /// Lock a batch of collaterals which may repeat tokens,
+    /// the amounts of a repeated token are summed
+    LockCollateralBatch { collaterals: TokensHuman },
+    /// Unlock a batch of collaterals which may repeat tokens,
+    /// the borrow limit is checked once after all the unlocks
+    UnlockCollateralBatch { collaterals: TokensHuman },
+
+    /// Start receiving inbox records for events affecting the sender
+    RegisterInbox {},
+    /// Stop receiving inbox records and drop the stored ones
+    UnregisterInbox {},
+    /// Remove inbox records up to and including `up_to` sequence
+    PruneInbox { up_to: u64 },
+
+    /// Offer the borrow position of the sender, its collaterals
+    /// and loan, to `to`. The position moves once accepted
+    TransferPosition { to: HumanAddr },
+    /// Withdraw the pending position transfer offer of the sender
+    CancelPositionTransfer {},
+    /// Take over the position offered by `from`,
+    /// the sender must have no collaterals and no loan
+    AcceptPosition { from: HumanAddr },
+
+    /// Attach a registered insurance contract to the position of the
+    /// sender, called to cure the position before it is liquidated
+    AttachInsurance { insurance_contract: HumanAddr },
+    /// Detach the insurance contract from the position of the sender
+    DetachInsurance {},
+
+    /// Collaterals of the sender to liquidate last, the first listed
+    /// being seized last; an empty list removes the protection.
+    /// Liquidations still seize the same value from the position
+    UpdateCollateralProtection {
+        protected_collaterals: Vec<HumanAddr>,
+    },
+    /// Cap the LTV of every collateral of the sender's position, for
+    /// its borrows and unlocks only. The override can be lowered at any
+    /// time but only raised or removed (`None`) a cooldown after its
+    /// last update
+    UpdateMaxLtvOverride {
+        max_ltv_override: Option<Decimal256>,
+    },
+
+    /// Propose a collateral to whitelist along with its risk
+    /// metadata, the owner confirms or rejects the proposal
+    ProposeWhitelist {
+        name: String,
+        symbol: String,
+        collateral_token: HumanAddr,
+        custody_contract: HumanAddr,
+        max_ltv: Decimal256,
+        metadata: AssetMetadata,
+    },
 
     /////////////////////////////
     /// Permissionless operations
     /////////////////////////////
     LiquidateCollateral { borrower: HumanAddr },
+    /// Checkpoint the borrower for the current epoch when its loan is
+    /// under the rebate limit, and rebate the interest accrued since a
+    /// checkpoint followed by a full epoch. A borrower above the limit
+    /// loses its checkpoint
+    CheckpointLtv { borrower: HumanAddr },
+    /// Query the protocol controller again instead of
+    /// waiting for the cached mode to expire
+    RefreshProtocolMode {},
+    /// Resolve the peers again instead of waiting
+    /// for the resolved ones to expire
+    RefreshRegistry {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
     EpochState {},
+    /// Phase reached by the epoch operations and the steps left
+    EpochPhase {},
+    InvariantChecks {},
     Whitelist {
         collateral_token: Option<HumanAddr>,
         start_after: Option<HumanAddr>,
@@ -121,6 +333,127 @@ pub enum QueryMsg {
         borrower: HumanAddr,
         block_time: Option<u64>,
     },
+    EmergencyPrice {
+        asset: HumanAddr,
+    },
+    EmergencyPriceProposal {
+        asset: HumanAddr,
+    },
+    Inbox {
+        address: HumanAddr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    Sponsorships {
+        borrower: HumanAddr,
+    },
+    EpochRecords {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    InterestRebate {},
+    LtvCheckpoint {
+        borrower: HumanAddr,
+    },
+    /// Queries have no access to the block, the liquidation
+    /// accrues interest and reads prices at the given one
+    SimulateLiquidation {
+        borrower: HumanAddr,
+        block_height: u64,
+        block_time: u64,
+    },
+    /// Collateral positions whose loan exceeds the borrow limit,
+    /// scanned a page of positions at a time at the given block
+    LiquidationCandidates {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+        block_height: u64,
+        block_time: u64,
+    },
+    IndexUnit {},
+    PositionTransfer {
+        borrower: HumanAddr,
+    },
+    InsuranceContracts {},
+    PositionInsurance {
+        borrower: HumanAddr,
+    },
+    RiskModule {},
+    WhitelistProposal {
+        collateral_token: HumanAddr,
+    },
+    WhitelistProposals {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    /// Metadata of a collateral whitelisted through a proposal
+    AssetMetadata {
+        collateral_token: HumanAddr,
+    },
+    AnalyticsContract {},
+    Registry {},
+    /// Whether the oracle feed of each whitelisted collateral
+    /// was updated within the max feed gap at `block_time`
+    FeedLiveness {
+        block_time: u64,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    CollateralProtection {
+        borrower: HumanAddr,
+    },
+    MaxLtvOverride {
+        borrower: HumanAddr,
+    },
+    RewardRoute {
+        collateral_token: HumanAddr,
+    },
+    PriceCheck {
+        collateral_token: HumanAddr,
+    },
+    /// Assets of the protocol against the aterra redemption
+    /// value and reserves, computed at the given block
+    Solvency {
+        block_height: u64,
+    },
+    /// Health of the position described by `snapshot`, computed
+    /// without reading any state, so off-chain services can check
+    /// the computation against exported state
+    SnapshotHealth {
+        snapshot: HealthSnapshot,
+    },
+}
+
+/// Phases of the epoch operations, reached in order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EpochPhase {
+    Idle,
+    PricesValidated,
+    RewardsDistributed,
+    RateUpdated,
+}
+
+impl std::fmt::Display for EpochPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EpochPhase::Idle => write!(f, "idle"),
+            EpochPhase::PricesValidated => write!(f, "prices_validated"),
+            EpochPhase::RewardsDistributed => write!(f, "rewards_distributed"),
+            EpochPhase::RateUpdated => write!(f, "rate_updated"),
+        }
+    }
+}
+
+/// Risk metadata of a proposed collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetMetadata {
+    /// Where the oracle price of the collateral comes from
+    pub oracle_source: String,
+    /// Liquidity of the collateral markets, from 0 to 100
+    pub liquidity_score: u8,
+    /// Hex encoded sha256 hash of the audit report
+    pub audit_hash: String,
 }
 
 // We define a custom struct for each query response
@@ -138,6 +471,28 @@ pub struct ConfigResponse {
     pub stable_denom: String,
     pub epoch_period: u64,
     pub price_timeframe: u64,
+    pub guardians: Vec<HumanAddr>,
+    pub guardian_threshold: u64,
+    pub max_collateral_types: u64,
+    pub swap_router: Option<HumanAddr>,
+    pub min_position_collateral_value: Uint256,
+    pub liquidation_order: LiquidationOrder,
+    pub max_feed_gap: u64,
+    pub max_catch_up_epochs: u64,
+    pub close_factor: Decimal256,
+    pub max_emergency_price_duration: u64,
+}
+
+/// Order the collaterals of a borrower are seized in
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationOrder {
+    /// The same share of every collateral
+    #[default]
+    ProRata,
+    /// The collaterals with the highest liquidity score first,
+    /// the ones without risk metadata last
+    MostLiquidFirst,
 }
 
 // We define a custom struct for each query response
@@ -156,6 +511,32 @@ pub struct WhitelistResponse {
     pub elems: Vec<WhitelistResponseElem>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistProposalResponse {
+    pub name: String,
+    pub symbol: String,
+    pub collateral_token: HumanAddr,
+    pub custody_contract: HumanAddr,
+    pub max_ltv: Decimal256,
+    pub metadata: AssetMetadata,
+    pub proposer: HumanAddr,
+    pub proposed_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistProposalsResponse {
+    pub proposals: Vec<WhitelistProposalResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetMetadataResponse {
+    pub collateral_token: HumanAddr,
+    pub metadata: AssetMetadata,
+    pub proposer: HumanAddr,
+    pub proposed_height: u64,
+    pub confirmed_height: u64,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct CollateralsResponse {
@@ -173,6 +554,365 @@ pub struct AllCollateralsResponse {
 pub struct BorrowLimitResponse {
     pub borrower: HumanAddr,
     pub borrow_limit: Uint256,
+    /// Borrow limit the liquidations compare the loan to, which
+    /// ignores the max ltv override and the borrowing restrictions
+    pub liquidation_borrow_limit: Uint256,
+    /// True when an emergency price override was
+    /// used instead of the oracle price
+    pub emergency_price_used: bool,
+    /// Borrow limit in index units, set when an index unit is enabled
+    pub index_borrow_limit: Option<Uint256>,
+}
+
+/// Set as the `data` of the borrow, repay and unlock handlers,
+/// so callers can read the resulting position without a query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionHealthResponse {
+    pub borrower: HumanAddr,
+    pub loan_amount: Uint256,
+    pub borrow_limit: Uint256,
+    /// borrow_limit / loan_amount, the position is liquidatable
+    /// below 1. None when there is no loan
+    pub health_factor: Option<Decimal256>,
+}
+
+impl PositionHealthResponse {
+    pub fn new(borrower: HumanAddr, loan_amount: Uint256, borrow_limit: Uint256) -> Self {
+        let health_factor = if loan_amount.is_zero() {
+            None
+        } else {
+            Some(Decimal256::from_uint256(borrow_limit) / Decimal256::from_uint256(loan_amount))
+        };
+
+        PositionHealthResponse {
+            borrower,
+            loan_amount,
+            borrow_limit,
+            health_factor,
+        }
+    }
+}
+
+/// Collateral seized by a simulated liquidation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatedCollateralResponse {
+    pub collateral_token: HumanAddr,
+    pub amount: Uint256,
+    pub price: Decimal256,
+    /// Max premium rate of the liquidation contract, actual
+    /// bids can only apply an equal or smaller premium
+    pub premium_rate: Decimal256,
+    /// Stable repaid to the market after premium, bid fee and tax
+    pub repay_amount: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateLiquidationResponse {
+    pub borrower: HumanAddr,
+    pub borrow_amount: Uint256,
+    pub borrow_limit: Uint256,
+    pub collaterals: Vec<LiquidatedCollateralResponse>,
+    pub repay_amount: Uint256,
+    /// Loan amount left once the repay amount is applied
+    pub loan_amount_after: Uint256,
+    /// Borrow limit of the collaterals left to the borrower
+    pub borrow_limit_after: Uint256,
+    pub emergency_price_used: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationCandidateResponse {
+    pub borrower: HumanAddr,
+    pub loan_amount: Uint256,
+    pub borrow_limit: Uint256,
+    /// loan amount in excess of the borrow limit
+    pub shortfall: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationCandidatesResponse {
+    pub candidates: Vec<LiquidationCandidateResponse>,
+    /// Last position of the scanned page, the next
+    /// page starts after it; `None` once the scan is done
+    pub last_scanned: Option<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SolvencyResponse {
+    /// stable balance of the market, idle deployments included
+    pub market_balance: Uint256,
+    pub total_liabilities: Decimal256,
+    pub total_credit_liabilities: Decimal256,
+    /// oracle value of the collaterals held by the custody contracts
+    pub collateral_value: Uint256,
+    /// market balance, credit liabilities and the liabilities
+    /// covered by the collateral value
+    pub total_assets: Decimal256,
+    /// aterra supply at the exchange rate
+    pub aterra_value: Decimal256,
+    pub total_reserves: Decimal256,
+    pub total_obligations: Decimal256,
+    /// total assets per total obligations, 1 without obligations
+    pub solvency_ratio: Decimal256,
+}
+
+/// Collateral of a `HealthSnapshot`, with the price and the max ltv
+/// the overseer would read at the snapshot block
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SnapshotCollateral {
+    pub collateral_token: HumanAddr,
+    pub amount: Uint256,
+    pub price: Decimal256,
+    pub max_ltv: Decimal256,
+    /// the price of a dead feed counts for liquidations only
+    #[serde(default)]
+    pub dead_feed: bool,
+}
+
+/// Exported state of a position
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HealthSnapshot {
+    pub loan_amount: Uint256,
+    pub collaterals: Vec<SnapshotCollateral>,
+    pub max_ltv_override: Option<Decimal256>,
+    pub min_position_collateral_value: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SnapshotHealthResponse {
+    pub collaterals_value: Uint256,
+    /// zero for dust positions, before the checks of
+    /// the price divergence and of the risk module
+    pub borrow_limit: Uint256,
+    /// borrow limit the liquidations compare the loan to,
+    /// which ignores the max ltv override
+    pub liquidation_borrow_limit: Uint256,
+    /// liquidation_borrow_limit / loan_amount,
+    /// None when there is no loan
+    pub health_factor: Option<Decimal256>,
+    pub liquidatable: bool,
+    /// liquidations seize all the collaterals of dust positions
+    pub dust: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionTransferResponse {
+    pub borrower: HumanAddr,
+    pub to: HumanAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InsuranceContractsResponse {
+    pub insurance_contracts: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeedLivenessResponseElem {
+    pub collateral_token: HumanAddr,
+    /// None when the oracle has no price for the collateral
+    pub last_updated: Option<u64>,
+    pub live: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeedLivenessResponse {
+    pub feeds: Vec<FeedLivenessResponseElem>,
+}
+
+/// Where custody rewards flow at epoch time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardDestination {
+    /// The overseer interest buffer, distributed to depositors
+    /// when the deposit rate is under the threshold
+    InterestBuffer {},
+    /// The market balance, raising the aterra exchange rate
+    Market {},
+    /// An incentive pool of the collateral
+    Pool { address: HumanAddr },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardShare {
+    pub destination: RewardDestination,
+    pub ratio: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardRouteResponse {
+    pub collateral_token: HumanAddr,
+    pub route: Vec<RewardShare>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceCheck {
+    /// Contract answering the DEX `Twap` query
+    pub dex_twap: HumanAddr,
+    /// Max relative deviation of the oracle price from the TWAP
+    pub max_deviation: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceCheckResponse {
+    pub collateral_token: HumanAddr,
+    pub price_check: Option<PriceCheck>,
+    pub oracle_price: Option<Decimal256>,
+    pub dex_twap: Option<Decimal256>,
+    pub diverged: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralProtectionResponse {
+    pub borrower: HumanAddr,
+    pub protected_collaterals: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MaxLtvOverrideResponse {
+    pub borrower: HumanAddr,
+    pub max_ltv_override: Option<Decimal256>,
+    /// Block time from which the override can be raised or removed
+    pub raisable_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionInsuranceResponse {
+    pub borrower: HumanAddr,
+    pub insurance_contract: HumanAddr,
+    /// False once the contract was deregistered,
+    /// the position is then liquidated as uninsured
+    pub registered: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RiskModuleResponse {
+    pub risk_module: Option<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AnalyticsContractResponse {
+    pub analytics_contract: Option<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegistryResponse {
+    pub registry_contract: Option<HumanAddr>,
+    /// Registry version the peers were resolved at
+    pub version: u64,
+    pub updated_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IndexUnitResponse {
+    pub index_oracle: Option<HumanAddr>,
+    pub index_asset: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmergencyPriceResponse {
+    pub asset: HumanAddr,
+    pub price: Decimal256,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmergencyPriceProposalResponse {
+    pub asset: HumanAddr,
+    pub price: Decimal256,
+    pub expires_at: u64,
+    pub approvals: Vec<HumanAddr>,
+}
+
+/// Compact record of an event written to a registered inbox
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InboxEvent {
+    /// Collaterals of the address were liquidated
+    Liquidated {
+        liquidator: HumanAddr,
+        borrow_amount: Uint256,
+        borrow_limit: Uint256,
+    },
+    /// LTV of a collateral held by the address was changed
+    WhitelistUpdated {
+        collateral_token: HumanAddr,
+        max_ltv: Decimal256,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InboxRecordResponse {
+    pub seq: u64,
+    pub height: u64,
+    pub event: InboxEvent,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InboxResponse {
+    pub address: HumanAddr,
+    pub records: Vec<InboxRecordResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SponsorshipResponse {
+    pub locker: HumanAddr,
+    pub collaterals: TokensHuman,
+    pub withdraw_allowed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SponsorshipsResponse {
+    pub borrower: HumanAddr,
+    pub sponsorships: Vec<SponsorshipResponse>,
+}
+
+/// Inputs of the deposit rate of the epoch executed at `height`:
+/// exchange_rate = (market_balance + total_liabilities
+///     + total_credit_liabilities - total_reserves) / aterra_supply
+/// deposit_rate = (exchange_rate / prev_exchange_rate - 1) / blocks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochRecordResponse {
+    pub height: u64,
+    pub blocks: u64,
+    pub prev_exchange_rate: Decimal256,
+    pub exchange_rate: Decimal256,
+    pub aterra_supply: Uint256,
+    pub market_balance: Uint256,
+    pub total_liabilities: Decimal256,
+    pub total_credit_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub deposit_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochRecordsResponse {
+    pub records: Vec<EpochRecordResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochPhaseResponse {
+    pub phase: EpochPhase,
+    /// Height the phase was reached at
+    pub height: u64,
+    /// Steps left to complete the epoch in progress,
+    /// all of them when no epoch is in progress
+    pub pending_steps: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InterestRebateResponse {
+    pub rebate_rate: Decimal256,
+    pub rebate_ltvs: Vec<(HumanAddr, Decimal256)>, // <(Collateral Token, Rebate LTV)>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LtvCheckpointResponse {
+    pub borrower: HumanAddr,
+    /// Start height of the epoch the checkpoint was taken in
+    pub epoch_height: u64,
+    pub loan_amount: Uint256,
+    pub interest_index: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -181,3 +921,8 @@ pub struct MigrateMsg {
     pub target_deposit_rate: Decimal256,
     pub threshold_deposit_rate: Decimal256,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InvariantChecksResponse {
+    pub enabled: bool,
+}
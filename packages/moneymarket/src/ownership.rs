@@ -0,0 +1,134 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+use crate::error::ContractError;
+
+const KEY_OWNERSHIP_PROPOSAL: &[u8] = b"ownership_proposal";
+
+/// Owner transfer waiting for `new_owner` to accept it
+/// before `expiry_height`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposalResponse {
+    pub new_owner: HumanAddr,
+    pub expiry_height: u64,
+}
+
+/// Owner transfer waiting for `new_owner` to accept it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposal {
+    pub new_owner: CanonicalAddr,
+    pub expiry_height: u64,
+}
+
+pub fn store_ownership_proposal<S: Storage>(
+    storage: &mut S,
+    proposal: &OwnershipProposal,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_OWNERSHIP_PROPOSAL).save(proposal)
+}
+
+pub fn remove_ownership_proposal<S: Storage>(storage: &mut S) {
+    Singleton::<S, OwnershipProposal>::new(storage, KEY_OWNERSHIP_PROPOSAL).remove()
+}
+
+pub fn read_ownership_proposal<S: Storage>(storage: &S) -> StdResult<Option<OwnershipProposal>> {
+    ReadonlySingleton::new(storage, KEY_OWNERSHIP_PROPOSAL).may_load()
+}
+
+/// The owner proposes `new_owner`, who must accept the ownership
+/// before `expiry_height`. A new proposal replaces the pending one
+pub fn propose_new_owner<S: Storage, A: Api, Q: Querier, T>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: &CanonicalAddr,
+    new_owner: HumanAddr,
+    expiry_height: u64,
+) -> HandleResult<T>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    if deps.api.canonical_address(&env.message.sender)? != *owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if expiry_height <= env.block.height {
+        return Err(ContractError::ExpiryHeightPassed {}.into());
+    }
+
+    store_ownership_proposal(
+        &mut deps.storage,
+        &OwnershipProposal {
+            new_owner: deps.api.canonical_address(&new_owner)?,
+            expiry_height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "propose_new_owner"),
+            log("new_owner", new_owner),
+            log("expiry_height", expiry_height),
+        ],
+        data: None,
+    })
+}
+
+/// The proposed owner takes over, `store_owner` writes it
+/// to the config of the contract
+pub fn accept_ownership<S: Storage, A: Api, Q: Querier, T, F>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    store_owner: F,
+) -> HandleResult<T>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+    F: FnOnce(&mut S, CanonicalAddr) -> StdResult<()>,
+{
+    let proposal = match read_ownership_proposal(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoOwnershipProposal {}.into()),
+    };
+
+    if deps.api.canonical_address(&env.message.sender)? != proposal.new_owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if env.block.height >= proposal.expiry_height {
+        return Err(ContractError::OwnershipProposalExpired {
+            expiry_height: proposal.expiry_height,
+        }
+        .into());
+    }
+
+    store_owner(&mut deps.storage, proposal.new_owner)?;
+    remove_ownership_proposal(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "accept_ownership"),
+            log("owner", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_ownership_proposal<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<OwnershipProposalResponse> {
+    match read_ownership_proposal(&deps.storage)? {
+        Some(proposal) => Ok(OwnershipProposalResponse {
+            new_owner: deps.api.human_address(&proposal.new_owner)?,
+            expiry_height: proposal.expiry_height,
+        }),
+        None => Err(ContractError::NoOwnershipProposal {}.into()),
+    }
+}
@@ -0,0 +1,112 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdResult};
+
+use crate::error::ContractError;
+
+/// Number of blocks the contracts use their cached protocol mode for,
+/// the controller is queried again once it is older
+pub const PROTOCOL_MODE_MAX_AGE: u64 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitMsg {
+    pub owner_addr: HumanAddr,
+    pub guardian_addr: HumanAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    ////////////////////
+    /// Owner operations
+    ////////////////////
+    UpdateConfig {
+        owner_addr: Option<HumanAddr>,
+        guardian_addr: Option<HumanAddr>,
+        /// Contracts asked to refresh their cached mode on every change
+        subscribers: Option<Vec<HumanAddr>>,
+    },
+
+    ////////////////////
+    /// Owner or guardian operations
+    ////////////////////
+    /// The guardian can only make the mode more restrictive
+    UpdateProtocolMode { mode: ProtocolMode },
+}
+
+/// Sent to the subscribed contracts when the mode changes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriberHandleMsg {
+    RefreshProtocolMode {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    ProtocolMode {},
+}
+
+/// Operational mode shared by the market, the overseer, the custody
+/// contracts and the liquidation contract, ordered from the least
+/// to the most restrictive
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, PartialOrd, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolMode {
+    Normal,
+    /// Positions can only be unwound: stable can be redeemed and
+    /// repaid, collaterals unlocked and withdrawn, loans liquidated
+    ConservativeWithdrawOnly,
+    /// Only the loans can be repaid
+    FullPause,
+}
+
+impl ProtocolMode {
+    /// Deposits, collateral locks and bids
+    pub fn assert_deposits_allowed(&self) -> StdResult<()> {
+        match self {
+            ProtocolMode::Normal => Ok(()),
+            _ => Err(ContractError::DepositsNotAllowed {
+                mode: self.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// Redemptions, withdrawals and liquidations, repayments
+    /// are allowed in every mode
+    pub fn assert_withdrawals_allowed(&self) -> StdResult<()> {
+        match self {
+            ProtocolMode::FullPause => Err(ContractError::WithdrawalsNotAllowed {
+                mode: self.to_string(),
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ProtocolMode::Normal => write!(f, "normal"),
+            ProtocolMode::ConservativeWithdrawOnly => write!(f, "conservative_withdraw_only"),
+            ProtocolMode::FullPause => write!(f, "full_pause"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner_addr: HumanAddr,
+    pub guardian_addr: HumanAddr,
+    pub subscribers: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolModeResponse {
+    pub mode: ProtocolMode,
+    pub updated_height: u64,
+}
@@ -0,0 +1,127 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+use crate::error::ContractError;
+use crate::protocol_controller::{ProtocolMode, PROTOCOL_MODE_MAX_AGE};
+use crate::querier::query_protocol_mode;
+
+const KEY_PROTOCOL_MODE: &[u8] = b"protocol_mode";
+
+/// Mode of the protocol controller, cached at `updated_height`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolModeCache {
+    pub protocol_controller: CanonicalAddr,
+    pub mode: ProtocolMode,
+    pub updated_height: u64,
+}
+
+pub fn store_protocol_mode_cache<S: Storage>(
+    storage: &mut S,
+    data: &ProtocolModeCache,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_PROTOCOL_MODE).save(data)
+}
+
+pub fn read_protocol_mode_cache<S: Storage>(storage: &S) -> StdResult<Option<ProtocolModeCache>> {
+    ReadonlySingleton::new(storage, KEY_PROTOCOL_MODE).may_load()
+}
+
+pub fn register_protocol_controller<S: Storage, A: Api, Q: Querier, T>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: &CanonicalAddr,
+    protocol_controller: HumanAddr,
+) -> HandleResult<T>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    if deps.api.canonical_address(&env.message.sender)? != *owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let mode = query_protocol_mode(deps, &protocol_controller)?.mode;
+    store_protocol_mode_cache(
+        &mut deps.storage,
+        &ProtocolModeCache {
+            protocol_controller: deps.api.canonical_address(&protocol_controller)?,
+            mode,
+            updated_height: env.block.height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_protocol_controller"),
+            log("protocol_controller", protocol_controller),
+            log("mode", mode),
+        ],
+        data: None,
+    })
+}
+
+/// Anyone can refresh the cached mode, the protocol
+/// controller does so whenever the mode changes
+pub fn refresh_protocol_mode<S: Storage, A: Api, Q: Querier, T>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult<T>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    if read_protocol_mode_cache(&deps.storage)?.is_none() {
+        return Err(ContractError::NoProtocolController {}.into());
+    }
+
+    let mode = load_protocol_mode(deps, env.block.height, true)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "refresh_protocol_mode"), log("mode", mode)],
+        data: None,
+    })
+}
+
+impl ProtocolMode {
+    /// Borrows, credit line draws, flash loans and flash mints, which
+    /// open or grow a debt, are only allowed in the Normal mode
+    pub fn assert_borrows_allowed(&self) -> StdResult<()> {
+        match self {
+            ProtocolMode::Normal => Ok(()),
+            _ => Err(ContractError::BorrowsNotAllowed {
+                mode: self.to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+/// Mode of the registered protocol controller, queried again once
+/// the cached one is older than PROTOCOL_MODE_MAX_AGE blocks.
+/// Without a protocol controller the mode is always Normal
+pub fn load_protocol_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block_height: u64,
+    force_refresh: bool,
+) -> StdResult<ProtocolMode> {
+    let mut cache = match read_protocol_mode_cache(&deps.storage)? {
+        Some(v) => v,
+        None => return Ok(ProtocolMode::Normal),
+    };
+
+    if force_refresh || block_height > cache.updated_height + PROTOCOL_MODE_MAX_AGE {
+        cache.mode =
+            query_protocol_mode(deps, &deps.api.human_address(&cache.protocol_controller)?)?.mode;
+        cache.updated_height = block_height;
+        store_protocol_mode_cache(&mut deps.storage, &cache)?;
+    }
+
+    Ok(cache.mode)
+}
@@ -10,7 +10,10 @@ use cosmwasm_storage::to_length_prefixed;
 use cw20::TokenInfoResponse;
 use terra_cosmwasm::TerraQuerier;
 
+use crate::attestation::{AttestResponse, AttestedAction, QueryMsg as AttestationQueryMsg};
 use crate::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
+use crate::protocol_controller::{ProtocolModeResponse, QueryMsg as ProtocolControllerQueryMsg};
+use crate::registry::{QueryMsg as RegistryQueryMsg, ResolveResponse, VersionResponse};
 
 pub fn query_all_balances<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
@@ -50,7 +53,7 @@ pub fn query_token_balance<S: Storage, A: Api, Q: Querier>(
             contract_addr: HumanAddr::from(contract_addr),
             key: Binary::from(concat(
                 &to_length_prefixed(b"balance").to_vec(),
-                (deps.api.canonical_address(&account_addr)?).as_slice(),
+                (deps.api.canonical_address(account_addr)?).as_slice(),
             )),
         }))
         .unwrap_or_else(|_| to_binary(&Uint128::zero()).unwrap());
@@ -80,18 +83,40 @@ pub fn query_tax_rate<S: Storage, A: Api, Q: Querier>(
     Ok(terra_querier.query_tax_rate()?.rate.into())
 }
 
+pub fn query_tax_cap<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    denom: String,
+) -> StdResult<Uint256> {
+    let terra_querier = TerraQuerier::new(&deps.querier);
+    Ok(terra_querier.query_tax_cap(denom)?.cap.into())
+}
+
+/// The treasury only taxes the native Terra stablecoins,
+/// Luna and the IBC denoms are sent tax free
+pub fn is_taxed_denom(denom: &str) -> bool {
+    denom != "uluna" && !denom.starts_with("ibc/")
+}
+
 pub fn compute_tax<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     coin: &Coin,
 ) -> StdResult<Uint256> {
-    let terra_querier = TerraQuerier::new(&deps.querier);
-    let tax_rate = Decimal256::from((terra_querier.query_tax_rate()?).rate);
-    let tax_cap = Uint256::from((terra_querier.query_tax_cap(coin.denom.to_string())?).cap);
+    if !is_taxed_denom(&coin.denom) {
+        return Ok(Uint256::zero());
+    }
+
+    let tax_rate = query_tax_rate(deps)?;
+    let tax_cap = query_tax_cap(deps, coin.denom.to_string())?;
+    Ok(compute_tax_raw(coin, tax_rate, tax_cap))
+}
+
+/// Tax charged on sending `coin` with the given tax parameters
+pub fn compute_tax_raw(coin: &Coin, tax_rate: Decimal256, tax_cap: Uint256) -> Uint256 {
     let amount = Uint256::from(coin.amount);
-    Ok(std::cmp::min(
+    std::cmp::min(
         amount * Decimal256::one() - amount / (Decimal256::one() + tax_rate),
         tax_cap,
-    ))
+    )
 }
 
 pub fn deduct_tax<S: Storage, A: Api, Q: Querier>(
@@ -136,6 +161,54 @@ pub fn query_price<S: Storage, A: Api, Q: Querier>(
     Ok(oracle_price)
 }
 
+pub fn query_protocol_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    protocol_controller: &HumanAddr,
+) -> StdResult<ProtocolModeResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(protocol_controller),
+        msg: to_binary(&ProtocolControllerQueryMsg::ProtocolMode {})?,
+    }))
+}
+
+pub fn query_attestation<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    attestation_contract: &HumanAddr,
+    action: AttestedAction,
+    address: &HumanAddr,
+    amount: Uint256,
+) -> StdResult<AttestResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(attestation_contract),
+        msg: to_binary(&AttestationQueryMsg::Attest {
+            action,
+            address: HumanAddr::from(address),
+            amount,
+        })?,
+    }))
+}
+
+pub fn query_registry_version<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    registry: &HumanAddr,
+) -> StdResult<VersionResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(registry),
+        msg: to_binary(&RegistryQueryMsg::Version {})?,
+    }))
+}
+
+pub fn query_registry_resolve<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    registry: &HumanAddr,
+    names: Vec<String>,
+) -> StdResult<ResolveResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(registry),
+        msg: to_binary(&RegistryQueryMsg::Resolve { names })?,
+    }))
+}
+
 #[inline]
 fn concat(namespace: &[u8], key: &[u8]) -> Vec<u8> {
     let mut k = namespace.to_vec();
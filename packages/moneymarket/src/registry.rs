@@ -0,0 +1,105 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::HumanAddr;
+
+/// Number of blocks the contracts use their resolved peers for,
+/// the registry version is queried again once they are older
+pub const REGISTRY_MAX_AGE: u64 = 10;
+
+/// Names the contracts resolve their peers by
+pub const MARKET: &str = "market";
+pub const OVERSEER: &str = "overseer";
+pub const ORACLE: &str = "oracle";
+pub const LIQUIDATION: &str = "liquidation";
+pub const INTEREST_MODEL: &str = "interest_model";
+pub const DISTRIBUTION_MODEL: &str = "distribution_model";
+pub const COLLECTOR: &str = "collector";
+pub const DISTRIBUTOR: &str = "distributor";
+
+/// Name of the custody contract of `collateral_token`
+pub fn custody_name(collateral_token: &HumanAddr) -> String {
+    format!("custody:{}", collateral_token)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitMsg {
+    pub owner_addr: HumanAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    ////////////////////
+    /// Owner operations
+    ////////////////////
+    UpdateConfig {
+        owner_addr: Option<HumanAddr>,
+        /// Contracts asked to refresh their resolved peers on every change
+        subscribers: Option<Vec<HumanAddr>>,
+    },
+
+    /// Register or replace the address of `name`
+    RegisterAddress {
+        name: String,
+        address: HumanAddr,
+    },
+    RemoveAddress {
+        name: String,
+    },
+}
+
+/// Sent to the subscribed contracts when an address changes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriberHandleMsg {
+    RefreshRegistry {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Version {},
+    Addresses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Addresses of `names` in the same order,
+    /// `None` for the unregistered names
+    Resolve {
+        names: Vec<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner_addr: HumanAddr,
+    pub subscribers: Vec<HumanAddr>,
+}
+
+/// The version is bumped by every registered or removed address
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VersionResponse {
+    pub version: u64,
+    pub updated_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressResponse {
+    pub name: String,
+    pub address: HumanAddr,
+    /// Registry version the address was registered at
+    pub version: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressesResponse {
+    pub addresses: Vec<AddressResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ResolveResponse {
+    pub version: u64,
+    pub addresses: Vec<Option<HumanAddr>>,
+}
@@ -0,0 +1,50 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::HumanAddr;
+
+use crate::tokens::TokensHuman;
+
+/// Interface of the risk module contracts the overseer delegates
+/// its borrow, withdrawal and liquidation decisions to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Whether `borrower` can borrow up to the `borrow_limit`
+    /// the overseer computed for `collaterals`
+    CanBorrow {
+        borrower: HumanAddr,
+        borrow_limit: Uint256,
+        collaterals: TokensHuman,
+        collateral_prices: Vec<Decimal256>,
+    },
+    /// Whether `borrower` can withdraw `unlock_collaterals`, leaving
+    /// `collaterals` worth `borrow_limit` behind `loan_amount`
+    CanWithdraw {
+        borrower: HumanAddr,
+        loan_amount: Uint256,
+        borrow_limit: Uint256,
+        collaterals: TokensHuman,
+        unlock_collaterals: TokensHuman,
+    },
+    /// Collaterals to liquidate from the undercollateralized position
+    /// of `borrower`, answered with a liquidation `LiquidationAmountResponse`
+    LiquidationAmount {
+        borrower: HumanAddr,
+        borrow_amount: Uint256,
+        borrow_limit: Uint256,
+        collaterals: TokensHuman,
+        collateral_prices: Vec<Decimal256>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CanBorrowResponse {
+    pub allowed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CanWithdrawResponse {
+    pub allowed: bool,
+}
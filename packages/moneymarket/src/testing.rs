@@ -1,10 +1,16 @@
+use crate::error::{parse_error_code, ContractError};
 use crate::mock_querier::mock_dependencies;
 use crate::oracle::PriceResponse;
-use crate::querier::{compute_tax, deduct_tax, query_price, query_tax_rate, TimeConstraints};
+use crate::protocol_controller::ProtocolMode;
+use crate::querier::{
+    compute_tax, deduct_tax, is_taxed_denom, query_price, query_tax_rate, TimeConstraints,
+};
 use crate::tokens::{Tokens, TokensHuman, TokensMath, TokensToRaw};
+use crate::units::{from_micro_units, to_micro_units};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{Coin, Decimal, HumanAddr, StdError, Uint128};
+use cosmwasm_std::{Coin, Decimal, HumanAddr, StdError, StdResult, Uint128};
+use terra_cosmwasm::{SwapResponse, TerraQuerier};
 
 #[test]
 fn tax_rate_querier() {
@@ -64,6 +70,33 @@ fn test_deduct_tax() {
     );
 }
 
+#[test]
+fn tax_exempt_denoms() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    assert!(is_taxed_denom("uusd"));
+    assert!(!is_taxed_denom("uluna"));
+    assert!(!is_taxed_denom(
+        "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2"
+    ));
+
+    // no tax, and no tax cap is queried
+    let ibc_denom = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+    assert_eq!(
+        compute_tax(&deps, &Coin::new(50000000u128, ibc_denom)).unwrap(),
+        Uint256::zero()
+    );
+    assert_eq!(
+        deduct_tax(&deps, Coin::new(50000000u128, "uluna")).unwrap(),
+        Coin::new(50000000u128, "uluna")
+    );
+}
+
 #[test]
 fn oracle_price_querier() {
     let mut deps = mock_dependencies(20, &[]);
@@ -115,6 +148,61 @@ fn oracle_price_querier() {
         Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Price is too old"),
         _ => panic!("DO NOT ENTER HERE"),
     }
+
+    // fresh within the timeframe until the prices go stale
+    let time_constraints = TimeConstraints {
+        block_time: 150u64,
+        valid_timeframe: 60u64,
+    };
+    query_price(
+        &deps,
+        &HumanAddr::from("oracle"),
+        "terra123123".to_string(),
+        "uusd".to_string(),
+        Some(time_constraints.clone()),
+    )
+    .unwrap();
+
+    deps.querier.age_oracle_prices(100u64);
+    let res = query_price(
+        &deps,
+        &HumanAddr::from("oracle"),
+        "terra123123".to_string(),
+        "uusd".to_string(),
+        Some(time_constraints),
+    );
+
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Price is too old"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn swap_querier() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    deps.querier.with_swap_rates(
+        &[(
+            &("uluna".to_string(), "uusd".to_string()),
+            &Decimal256::from_ratio(25, 2),
+        )],
+        Decimal256::percent(2),
+    );
+
+    let terra_querier = TerraQuerier::new(&deps.querier);
+    assert_eq!(
+        terra_querier
+            .query_swap(Coin::new(1000000u128, "uluna"), "uusd")
+            .unwrap(),
+        SwapResponse {
+            receive: Coin::new(12250000u128, "uusd"),
+        }
+    );
+
+    terra_querier
+        .query_swap(Coin::new(1000000u128, "uusd"), "uluna")
+        .unwrap_err();
 }
 
 #[test]
@@ -151,9 +239,9 @@ fn tokens_math() {
     let tokens_3_raw: Tokens = tokens_3.to_raw(&deps).unwrap();
     let tokens_4_raw: Tokens = tokens_4.to_raw(&deps).unwrap();
 
-    assert_eq!(tokens_1_raw.clone().sub(tokens_2_raw).is_err(), true);
-    assert_eq!(tokens_1_raw.clone().sub(tokens_3_raw).is_err(), true);
-    assert_eq!(tokens_1_raw.clone().sub(tokens_4_raw).is_err(), true);
+    assert!(tokens_1_raw.clone().sub(tokens_2_raw).is_err());
+    assert!(tokens_1_raw.clone().sub(tokens_3_raw).is_err());
+    assert!(tokens_1_raw.clone().sub(tokens_4_raw).is_err());
 }
 
 #[test]
@@ -251,3 +339,105 @@ fn token_math_invalid_token_2() {
 
     let _ = tokens_1_raw.clone().sub(tokens_2_raw);
 }
+
+#[test]
+fn contract_error_codes() {
+    let err: StdError = ContractError::NoLiquidity {
+        denom: "uusd".to_string(),
+    }
+    .into();
+    assert_eq!(parse_error_code(&err.to_string()), Some(1005u16));
+    match err {
+        StdError::GenericErr { msg, .. } => assert_eq!(
+            msg,
+            "[E1005] Not enough uusd available; borrow demand too high"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    assert_eq!(
+        parse_error_code(
+            "Generic error: Unauthorized; [E3003] Unlock amount cannot exceed locked amount: 1"
+        ),
+        Some(3003u16)
+    );
+    assert_eq!(parse_error_code("Generic error: Unauthorized"), None);
+}
+
+#[test]
+fn units_conversion() {
+    assert_eq!(
+        to_micro_units(Uint256::from(1000000u64), 6).unwrap(),
+        Uint256::from(1000000u64)
+    );
+    assert_eq!(
+        to_micro_units(Uint256::from(1000u64), 3).unwrap(),
+        Uint256::from(1000000u64)
+    );
+    assert_eq!(
+        to_micro_units(Uint256::from(1000000000000000000u64), 18).unwrap(),
+        Uint256::from(1000000u64)
+    );
+    assert_eq!(
+        from_micro_units(Uint256::from(1000000u64), 18).unwrap(),
+        Uint256::from(1000000000000000000u64)
+    );
+    assert_eq!(
+        from_micro_units(Uint256::from(1000000u64), 3).unwrap(),
+        Uint256::from(1000u64)
+    );
+
+    match to_micro_units(Uint256::from(1000000000000000001u64), 18) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9017] Amount 1000000000000000001 has dust below the smallest unit"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    match to_micro_units(Uint256::from(u128::MAX), 0) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "[E9014] Amount 340282366920938463463374607431768211455000000 overflows a coin amount"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    match to_micro_units(Uint256::one(), 19) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9015] decimals must be less than or equal to 18")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn protocol_mode_predicates() {
+    let modes = [
+        ProtocolMode::Normal,
+        ProtocolMode::ConservativeWithdrawOnly,
+        ProtocolMode::FullPause,
+    ];
+    let allowed = |f: fn(&ProtocolMode) -> StdResult<()>| -> Vec<bool> {
+        modes.iter().map(|mode| f(mode).is_ok()).collect()
+    };
+
+    assert_eq!(
+        allowed(ProtocolMode::assert_deposits_allowed),
+        vec![true, false, false]
+    );
+    assert_eq!(
+        allowed(ProtocolMode::assert_borrows_allowed),
+        vec![true, false, false]
+    );
+    assert_eq!(
+        allowed(ProtocolMode::assert_withdrawals_allowed),
+        vec![true, true, false]
+    );
+
+    match ProtocolMode::FullPause.assert_borrows_allowed() {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "[E9018] Borrows are not allowed in full_pause mode")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
@@ -0,0 +1,83 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::StdResult;
+
+use crate::error::ContractError;
+
+/// Decimals of the native stable denoms, every amount
+/// held by the contracts is expressed in micro units
+pub const STABLE_DECIMALS: u8 = 6;
+
+/// Most decimals an amount can be expressed with,
+/// the precision of the bridged 18 decimals tokens
+pub const MAX_DECIMALS: u8 = 18;
+
+/// Converts `amount`, expressed with `decimals` (3 for milli units,
+/// 18 for bridged tokens), to micro units. Amounts with dust below
+/// one micro unit are rejected instead of being truncated
+pub fn to_micro_units(amount: Uint256, decimals: u8) -> StdResult<Uint256> {
+    assert_decimals(decimals)?;
+
+    let micro_amount = if decimals > STABLE_DECIMALS {
+        scale_down(amount, decimals - STABLE_DECIMALS)?
+    } else {
+        scale_up(amount, STABLE_DECIMALS - decimals)?
+    };
+
+    // coin amounts are 128 bits
+    if micro_amount.0.bits() > 128 {
+        return Err(ContractError::CoinAmountOverflow {
+            amount: micro_amount,
+        }
+        .into());
+    }
+
+    Ok(micro_amount)
+}
+
+/// Converts `micro_amount` to an amount expressed with `decimals`
+pub fn from_micro_units(micro_amount: Uint256, decimals: u8) -> StdResult<Uint256> {
+    assert_decimals(decimals)?;
+
+    if decimals > STABLE_DECIMALS {
+        scale_up(micro_amount, decimals - STABLE_DECIMALS)
+    } else {
+        scale_down(micro_amount, STABLE_DECIMALS - decimals)
+    }
+}
+
+pub fn assert_decimals(decimals: u8) -> StdResult<()> {
+    if decimals > MAX_DECIMALS {
+        return Err(ContractError::DecimalsTooLarge {
+            max_decimals: MAX_DECIMALS,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+fn scale_up(amount: Uint256, digits: u8) -> StdResult<Uint256> {
+    let (scaled, overflow) = amount.0.overflowing_mul(pow10(digits).0);
+    if overflow {
+        return Err(ContractError::ScaledAmountOverflow {
+            amount,
+            decimals: digits,
+        }
+        .into());
+    }
+
+    Ok(Uint256::from(scaled))
+}
+
+fn scale_down(amount: Uint256, digits: u8) -> StdResult<Uint256> {
+    let factor = pow10(digits);
+    if !(amount.0 % factor.0).is_zero() {
+        return Err(ContractError::AmountBelowSmallestUnit { amount }.into());
+    }
+
+    Ok(Uint256::from(amount.0 / factor.0))
+}
+
+fn pow10(digits: u8) -> Uint256 {
+    Uint256::from(10u64.pow(digits as u32))
+}
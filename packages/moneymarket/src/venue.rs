@@ -0,0 +1,17 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Uint256;
+
+/// Interface of the external yield venue the market
+/// deploys its idle stable to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    /// Deposit the sent stable coins
+    Deposit {},
+
+    /// Send `amount` of the deposited stable back to the sender,
+    /// defaults to the whole deposit including the accrued yield
+    Withdraw { amount: Option<Uint256> },
+}